@@ -450,6 +450,8 @@ pub async fn custom_evaluate_proposal(
     // Create a temporary proposal object from the content
     let proposal = Proposal {
         description: request.content.clone(),
+        protocol_id: request.protocol_id.clone(),
+        created_at: request.created_at,
     };
 
     // Perform custom evaluation
@@ -8,8 +8,9 @@ use axum::{
     BoxError, Json,
 };
 use futures::future::FutureExt;
+use sha2::{Digest, Sha256};
 use std::panic::AssertUnwindSafe;
-use tracing::{debug, error, info, warn};
+use tracing::{debug, error, warn};
 
 use crate::api::{
     error::{ApiError, ErrorResponse},
@@ -29,22 +30,6 @@ pub trait ApiKeyValidator {
     fn is_valid_api_key(&self, key: &str) -> bool;
 }
 
-/// Logging middleware
-///
-/// This middleware logs incoming requests and outgoing responses
-pub async fn logging_middleware(request: Request, next: Next) -> Result<Response, StatusCode> {
-    let path = request.uri().path().to_string();
-    let method = request.method().clone();
-    info!("Request: {} {}", method, path);
-
-    let response = next.run(request).await;
-
-    let status = response.status();
-    info!("Response: {} {} {}", method, path, status);
-
-    Ok(response)
-}
-
 /// Error handling middleware for catching panics and returning JSON responses
 pub async fn handle_error_middleware(request: Request, next: Next) -> Response {
     // Use AssertUnwindSafe to catch panics and convert them to JSON responses
@@ -130,6 +115,11 @@ pub fn validate_api_key<T: ApiKeyValidator>(
 
 // Error response structure moved to api/error.rs
 
+/// Short fingerprint identifying an API key in logs without revealing it
+pub fn key_fingerprint(key: &str) -> String {
+    hex::encode(&Sha256::digest(key.as_bytes())[..4])
+}
+
 /// API key authentication middleware
 ///
 /// This middleware validates the API key in the request header against the configured API keys.
@@ -152,7 +142,12 @@ where
         .and_then(|value| value.to_str().ok());
 
     match validate_api_key(&state, path, api_key) {
-        Ok(_) => Ok(next.run(request).await),
+        Ok(_) => {
+            if let Some(key) = api_key {
+                telemetry::record_api_key(&key_fingerprint(key));
+            }
+            Ok(next.run(request).await)
+        }
         Err(StatusCode::UNAUTHORIZED) => Err(ApiError::unauthorized("API key is required")),
         Err(StatusCode::FORBIDDEN) => Err(ApiError::forbidden("Invalid API key provided")),
         Err(status_code) => Err(ApiError {
@@ -8,11 +8,9 @@ use axum::{
     routing::{get, post},
     Json, Router,
 };
-use tower_http::{
-    cors::{AllowMethods, AllowOrigin, CorsLayer},
-    trace::{DefaultMakeSpan, DefaultOnResponse, TraceLayer},
-};
-use tracing::{warn, Level};
+use telemetry::{logging_middleware, REQUEST_ID_HEADER};
+use tower_http::cors::{AllowMethods, AllowOrigin, CorsLayer};
+use tracing::warn;
 
 use crate::{
     api::{
@@ -49,14 +47,6 @@ pub fn create_router(
     agent_service: AgentService,
     cache_service: CacheService,
 ) -> Router {
-    let tracing_layer = TraceLayer::new_for_http()
-        .make_span_with(DefaultMakeSpan::new().include_headers(true))
-        .on_response(
-            DefaultOnResponse::new()
-                .include_headers(true)
-                .level(Level::INFO),
-        );
-
     let state = AppState {
         config: config.clone(),
         agent_service,
@@ -84,8 +74,12 @@ pub fn create_router(
                 header::AUTHORIZATION,
                 header::ACCEPT,
                 header::HeaderName::from_static("x-api-key"),
+                header::HeaderName::from_static(REQUEST_ID_HEADER),
+            ])
+            .expose_headers([
+                header::HeaderName::from_static("x-api-key"),
+                header::HeaderName::from_static(REQUEST_ID_HEADER),
             ])
-            .expose_headers([header::HeaderName::from_static("x-api-key")])
     } else {
         // Otherwise, use the exact list of allowed origins
         let mut cors_layer = CorsLayer::new();
@@ -110,8 +104,12 @@ pub fn create_router(
                 header::AUTHORIZATION,
                 header::ACCEPT,
                 header::HeaderName::from_static("x-api-key"),
+                header::HeaderName::from_static(REQUEST_ID_HEADER),
+            ])
+            .expose_headers([
+                header::HeaderName::from_static("x-api-key"),
+                header::HeaderName::from_static(REQUEST_ID_HEADER),
             ])
-            .expose_headers([header::HeaderName::from_static("x-api-key")])
             .allow_credentials(true)
     };
 
@@ -220,6 +218,6 @@ pub fn create_router(
         }))
         .layer(middleware::from_fn(handle_error_middleware))
         .layer(cors)
-        .layer(tracing_layer)
+        .layer(middleware::from_fn(logging_middleware))
         .with_state(state)
 }
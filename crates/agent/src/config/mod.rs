@@ -3,6 +3,7 @@
 use clap::Parser;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
+use telemetry::{LogFormat, OtlpConfig};
 
 /// Application configuration
 #[derive(Debug, Clone, Serialize, Deserialize, Parser)]
@@ -39,6 +40,22 @@ pub struct Config {
     #[arg(env = "WEI_AGENT_API_KEY_AUTH_ENABLED", long, default_value = "true")]
     pub api_key_auth_enabled: bool,
 
+    /// Log output format
+    #[arg(env = "WEI_AGENT_LOG_FORMAT", long, value_enum, default_value = "text")]
+    pub log_format: LogFormat,
+
+    /// OTLP/HTTP collector base URL spans are exported to, export is off if unset
+    #[arg(env = "WEI_AGENT_OTLP_ENDPOINT", long)]
+    pub otlp_endpoint: Option<String>,
+
+    /// Share of new traces exported, from 0 to 1
+    #[arg(env = "WEI_AGENT_OTLP_SAMPLE_RATIO", long, default_value = "1.0")]
+    pub otlp_sample_ratio: f64,
+
+    /// Service name exported spans are tagged with
+    #[arg(env = "WEI_AGENT_SERVICE_NAME", long, default_value = "wei-agent")]
+    pub service_name: String,
+
     /// CORS allowed origins (comma-separated list)
     #[arg(
         env = "CORS_ALLOWED_URLS",
@@ -50,6 +67,14 @@ pub struct Config {
     /// Exa API key for search functionality
     #[arg(env = "WEI_AGENT_EXA_API_KEY", long)]
     pub exa_api_key: Option<String>,
+
+    /// Base URL of the indexer API, treasury context is off if unset
+    #[arg(env = "WEI_AGENT_INDEXER_URL", long)]
+    pub indexer_url: Option<String>,
+
+    /// API key sent to the indexer
+    #[arg(env = "WEI_AGENT_INDEXER_API_KEY", long)]
+    pub indexer_api_key: Option<String>,
 }
 
 impl Config {
@@ -95,4 +120,13 @@ impl Config {
                 .collect()
         }
     }
+
+    /// Get span export configuration, if an OTLP endpoint is set
+    pub fn otlp(&self) -> Option<OtlpConfig> {
+        self.otlp_endpoint.as_ref().map(|endpoint| OtlpConfig {
+            endpoint: endpoint.clone(),
+            service_name: self.service_name.clone(),
+            sample_ratio: self.otlp_sample_ratio,
+        })
+    }
 }
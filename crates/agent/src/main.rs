@@ -26,12 +26,11 @@ async fn main() -> agent::Result<()> {
         std::env::set_var("RUST_LOG", "info");
     }
 
-    tracing_subscriber::fmt()
-        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
-        .with_target(false)
-        .with_thread_ids(true)
-        .with_thread_names(true)
-        .init();
+    // Exported spans are flushed when the guard drops
+    let _tracing =
+        telemetry::init_logging(config.log_format, config.otlp().as_ref()).map_err(|e| {
+            agent::utils::error::Error::Internal(format!("Tracing setup failed: {}", e))
+        })?;
 
     info!("Starting Wei Agent service...");
 
@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use chrono::{DateTime, Utc};
+use governance::ProtocolId;
+
 use crate::models::EvaluationStatus;
 
 /// Custom evaluation criteria request
@@ -10,6 +13,12 @@ pub struct CustomEvaluationRequest {
     pub content: String,
     /// Custom criteria as plain text (e.g., "I want to see if the proposal has clear milestones")
     pub custom_criteria: String,
+    /// Protocol the proposal belongs to, adds its treasury to the evaluation context
+    #[serde(default)]
+    pub protocol_id: Option<ProtocolId>,
+    /// Time the proposal was created, token budgets are valued at the prices of that day
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
 }
 
 /// A custom evaluation criterion
@@ -1,7 +1,8 @@
 //! Data models for the agent service
 //!
 //! This module contains the core data structures used by the agent service
-//! for representing analyses, proposals, and webhook events.
+//! for representing analyses, proposals, and webhook events. Identifiers and
+//! webhook events come from the [`governance`] crate shared with the indexer.
 
 // Module imports and exports
 
@@ -41,6 +42,7 @@ pub use deepresearch::{
     DeepResearchApiResponse, DeepResearchRequest, DeepResearchResponse, DeepResearchResult,
     DiscussionResource,
 };
+pub use governance::id::{ChainId, ProposalId, ProtocolId};
 pub use health::HealthResponse;
 pub use proposal::Proposal;
 pub use webhook::WebhookEvent;
@@ -1,5 +1,7 @@
 use crate::swagger::descriptions;
 use crate::swagger::examples;
+use chrono::{DateTime, Utc};
+use governance::ProtocolId;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
@@ -14,4 +16,22 @@ pub struct Proposal {
         max_length = 10000
     )]
     pub description: String,
+    /// Protocol the proposal belongs to, adds its treasury to the analysis context
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(example = examples::PROPOSAL_PROTOCOL_ID_EXAMPLE)]
+    pub protocol_id: Option<ProtocolId>,
+    /// Time the proposal was created, token budgets are valued at the prices of that day
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(example = examples::PROPOSAL_CREATED_AT_EXAMPLE)]
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl From<&governance::Proposal> for Proposal {
+    fn from(proposal: &governance::Proposal) -> Self {
+        Self {
+            description: proposal.description.clone(),
+            protocol_id: Some(proposal.id.protocol_id().clone()),
+            created_at: Some(proposal.created_at),
+        }
+    }
 }
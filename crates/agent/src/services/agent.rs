@@ -3,9 +3,17 @@
 use std::collections::HashMap;
 use std::future::Future;
 
-use openrouter_rs::{api::chat::ChatCompletionRequest, types::Role, Message, OpenRouterClient};
+use chrono::Utc;
+use futures::future::{join, join_all};
+use governance::Treasury;
+use openrouter_rs::{
+    api::chat::ChatCompletionRequest,
+    error::OpenRouterError,
+    types::{CompletionsResponse, Role},
+    Message, OpenRouterClient,
+};
 use serde_json;
-use tracing::{debug, error, info};
+use tracing::{debug, error, field, info, info_span, warn, Instrument};
 
 use crate::models::analysis::{EvaluationCategory, StructuredAnalysisResponse};
 use crate::models::custom_evaluation::{
@@ -18,6 +26,7 @@ use crate::utils::error::{Error, ResponseError, Result};
 use crate::utils::markdown::extract_json_from_markdown;
 
 use crate::services::cache::{CacheService, CacheableQuery, CachedResponse};
+use crate::services::indexer::{mentioned_tokens, price_context, treasury_context, IndexerClient};
 use crate::{
     db::{
         core::Database,
@@ -34,6 +43,7 @@ pub struct AgentService {
     community_repo: CommunityRepository,
     cache_service: CacheService,
     openrouter: OpenRouterClient,
+    indexer: Option<IndexerClient>,
     config: Config,
 }
 
@@ -49,6 +59,10 @@ impl AgentService {
             cache_service,
             // Unwrap is safe because we are doing it on init only
             openrouter: Self::init_open_router(&config).unwrap(),
+            indexer: config
+                .indexer_url
+                .as_deref()
+                .map(|url| IndexerClient::new(url, config.indexer_api_key.clone())),
             config,
         }
     }
@@ -63,6 +77,116 @@ impl AgentService {
 
         Ok(openrouter)
     }
+
+    /// Send a chat completion in an `openrouter` span recording the model and token usage
+    ///
+    /// The span is exported with the rest of the trace, but the request
+    /// itself carries no `traceparent` as the client cannot add headers.
+    async fn complete(
+        &self,
+        model: &str,
+        request: &ChatCompletionRequest,
+    ) -> std::result::Result<CompletionsResponse, OpenRouterError> {
+        let span = info_span!(
+            "openrouter",
+            model,
+            otel.kind = "client",
+            prompt_tokens = field::Empty,
+            completion_tokens = field::Empty,
+            total_tokens = field::Empty,
+        );
+        let response = self
+            .openrouter
+            .send_chat_completion(request)
+            .instrument(span.clone())
+            .await?;
+        if let Some(usage) = &response.usage {
+            span.record("prompt_tokens", usage.prompt_tokens);
+            span.record("completion_tokens", usage.completion_tokens);
+            span.record("total_tokens", usage.total_tokens);
+        }
+        Ok(response)
+    }
+
+    /// Context messages on the proposal's protocol, such as its treasury size
+    /// and the token prices of the day the proposal was made
+    ///
+    /// Prices cover the tokens the treasury holds and the registry tokens the
+    /// proposal names by symbol or address.
+    ///
+    /// Context that cannot be fetched is left out, so an unavailable indexer
+    /// never fails an evaluation.
+    async fn protocol_context(&self, proposal: &Proposal) -> Vec<Message> {
+        let (Some(indexer), Some(protocol_id)) = (&self.indexer, &proposal.protocol_id) else {
+            return Vec::new();
+        };
+        let chain_id = protocol_id.chain_id.as_eip155();
+        let (treasury, tokens) = join(indexer.treasury(protocol_id), async {
+            match chain_id {
+                Some(chain_id) => indexer.tokens(chain_id).await,
+                None => Ok(Vec::new()),
+            }
+        })
+        .await;
+        let treasury = treasury.unwrap_or_else(|e| {
+            warn!("Failed to fetch the treasury of {}: {}", protocol_id, e);
+            None
+        });
+        let tokens = tokens.unwrap_or_else(|e| {
+            warn!(
+                "Failed to fetch the priced tokens of {}: {}",
+                protocol_id, e
+            );
+            Vec::new()
+        });
+        let mut context: Vec<String> = treasury.iter().filter_map(treasury_context).collect();
+
+        // Budgets are requested in the tokens the treasury holds or the proposal names
+        if let Some(chain_id) = chain_id {
+            let held = treasury
+                .iter()
+                .flat_map(Treasury::totals)
+                .map(|total| total.token.unwrap_or(total.symbol));
+            let named = mentioned_tokens(&proposal.description, &tokens)
+                .into_iter()
+                .map(|token| {
+                    token
+                        .address
+                        .clone()
+                        .unwrap_or_else(|| token.symbol.clone())
+                });
+            let mut priced: Vec<String> = Vec::new();
+            for token in held.chain(named) {
+                if !priced
+                    .iter()
+                    .any(|other| other.eq_ignore_ascii_case(&token))
+                {
+                    priced.push(token);
+                }
+            }
+
+            let at = proposal.created_at.unwrap_or_else(Utc::now);
+            let prices = join_all(
+                priced
+                    .iter()
+                    .map(|token| indexer.value_usd(chain_id, token, 1.0, at)),
+            )
+            .await;
+            let mut values = Vec::new();
+            for (token, price) in priced.iter().zip(prices) {
+                match price {
+                    Ok(value) => values.extend(value),
+                    Err(e) => warn!("Failed to fetch the price of {}: {}", token, e),
+                }
+            }
+            context.extend(price_context(&values, at));
+        }
+
+        context
+            .iter()
+            .map(|context| Message::new(Role::System, context.as_str()))
+            .collect()
+    }
 }
 
 /// Trait for the agent service
@@ -99,18 +223,20 @@ impl AgentService {
         &self,
         proposal: &Proposal,
     ) -> Result<StructuredAnalysisResponse> {
+        let mut messages = vec![Message::new(Role::System, ANALYZE_PROPOSAL_PROMPT)];
+        messages.extend(self.protocol_context(proposal).await);
+        messages.push(Message::new(
+            Role::User,
+            serde_json::to_string(&proposal)?.as_str(),
+        ));
         let request = ChatCompletionRequest::builder()
             .model(self.config.ai_model_name.clone())
-            .messages(vec![
-                Message::new(Role::System, ANALYZE_PROPOSAL_PROMPT),
-                Message::new(Role::User, serde_json::to_string(&proposal)?.as_str()),
-            ])
+            .messages(messages)
             .build()
             .map_err(|e: openrouter_rs::error::OpenRouterError| Error::ChatBuilder(Box::new(e)))?;
 
         let response = self
-            .openrouter
-            .send_chat_completion(&request)
+            .complete(&self.config.ai_model_name, &request)
             .await
             .map_err(Error::from)?;
 
@@ -161,16 +287,18 @@ impl AgentService {
 
         // We no longer need to serialize the proposal as JSON since the content is already in the request
         // Instead, we'll use the proposal's description directly in the user message
+        let mut messages = vec![Message::new(Role::System, custom_prompt.as_str())];
+        messages.extend(self.protocol_context(proposal).await);
+        messages.push(Message::new(Role::User, proposal.description.as_str()));
         let chat_request: ChatCompletionRequest = ChatCompletionRequest::builder()
             .model(self.config.ai_model_name.clone())
-            .messages(vec![
-                Message::new(Role::System, custom_prompt.as_str()),
-                Message::new(Role::User, proposal.description.as_str()),
-            ])
+            .messages(messages)
             .build()
             .map_err(|e| Error::ChatBuilder(Box::new(e)))?;
 
-        let response = self.openrouter.send_chat_completion(&chat_request).await?;
+        let response = self
+            .complete(&self.config.ai_model_name, &chat_request)
+            .await?;
 
         let content = response.choices[0]
             .content()
@@ -249,8 +377,9 @@ impl AgentService {
             topic
         );
 
+        let model = "perplexity/sonar-pro"; // Use Sonar DeepResearch Pro model
         let request = ChatCompletionRequest::builder()
-            .model("perplexity/sonar-pro".to_string()) // Use Sonar DeepResearch Pro model
+            .model(model.to_string())
             .messages(vec![
                 Message::new(Role::System, DEEP_RESEARCH_PROMPT),
                 Message::new(Role::User, &user_prompt),
@@ -259,8 +388,7 @@ impl AgentService {
             .map_err(|e| Error::Internal(e.to_string()))?;
 
         let response = self
-            .openrouter
-            .send_chat_completion(&request)
+            .complete(model, &request)
             .await
             .map_err(|e| Error::Internal(e.to_string()))?;
 
@@ -150,6 +150,11 @@ impl CacheService {
     /// Cache or retrieve a value using a query
     /// If the value exists in cache and is not expired, it's returned
     /// Otherwise, the provided closure is executed and its result is cached
+    #[tracing::instrument(
+        name = "cache",
+        skip_all,
+        fields(endpoint = %query.endpoint, cache_hit = tracing::field::Empty)
+    )]
     pub async fn cache_or_compute<T, F, Fut>(
         &self,
         query: &CacheableQuery,
@@ -166,6 +171,7 @@ impl CacheService {
         // Try to get from cache first
         if let Some(entry) = self.repository.get_entry(&cache_key).await? {
             debug!("Cache hit for query: {}", query.cache_description());
+            tracing::Span::current().record("cache_hit", true);
 
             let cached_data: T = serde_json::from_value(entry.data)?;
             return Ok(CachedResponse {
@@ -184,6 +190,7 @@ impl CacheService {
         );
 
         // Cache miss - compute the value
+        tracing::Span::current().record("cache_hit", false);
         let computed_value = compute_fn().await?;
         let created_at = Utc::now();
 
@@ -306,7 +313,7 @@ impl CacheService {
         }
 
         // Sort by creation time (newest first)
-        cached_queries.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        cached_queries.sort_by_key(|q| std::cmp::Reverse(q.created_at));
 
         Ok(cached_queries)
     }
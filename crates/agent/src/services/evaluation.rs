@@ -0,0 +1,68 @@
+//! Evaluation of the agent against human labels
+//!
+//! The indexer's `/labels/ground-truth` endpoint returns the final human
+//! verdict on every labeled proposal, with the results on the agent's five
+//! evaluation categories that labelers agree on. Comparing them with the
+//! agent's analyses of the same proposals measures how often the agent
+//! reaches the human result.
+
+use governance::{CriterionStatus, LabelCriteria, LabelCriterion};
+
+use crate::models::{analysis::EvaluationCategory, StructuredAnalysisResponse};
+
+/// Results of an analysis on the evaluation categories
+///
+/// Returns `None` if a category has a status other than `pass`, `fail` or `n/a`.
+pub fn analysis_criteria(analysis: &StructuredAnalysisResponse) -> Option<LabelCriteria> {
+    let status = |category: &EvaluationCategory| {
+        category
+            .status
+            .trim()
+            .to_lowercase()
+            .parse::<CriterionStatus>()
+            .ok()
+    };
+
+    Some(LabelCriteria {
+        goals_and_motivation: status(&analysis.goals_and_motivation)?,
+        measurable_outcomes: status(&analysis.measurable_outcomes)?,
+        budget: status(&analysis.budget)?,
+        technical_specifications: status(&analysis.technical_specifications)?,
+        language_quality: status(&analysis.language_quality)?,
+    })
+}
+
+/// Agreement of the agent with human labels on one evaluation category
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CriterionAgreement {
+    /// Evaluation category
+    pub criterion: LabelCriterion,
+    /// Number of proposals compared
+    pub compared: usize,
+    /// Number of proposals where the agent gave the labeled result
+    pub agreed: usize,
+}
+
+impl CriterionAgreement {
+    /// Share of the compared proposals the agent agreed on, `None` without proposals
+    pub fn rate(&self) -> Option<f64> {
+        (self.compared > 0).then(|| self.agreed as f64 / self.compared as f64)
+    }
+}
+
+/// Agreement on every evaluation category, in the order of the agent's response
+///
+/// Every pair holds the labeled results of a proposal and the agent's results.
+pub fn agreement(results: &[(LabelCriteria, LabelCriteria)]) -> Vec<CriterionAgreement> {
+    LabelCriterion::ALL
+        .into_iter()
+        .map(|criterion| CriterionAgreement {
+            criterion,
+            compared: results.len(),
+            agreed: results
+                .iter()
+                .filter(|(labeled, agent)| labeled.get(criterion) == agent.get(criterion))
+                .count(),
+        })
+        .collect()
+}
@@ -3,7 +3,7 @@
 use anyhow::{anyhow, Result};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use tracing::{error, info};
+use tracing::{error, info, info_span, Instrument};
 
 /// Exa API client for searching related content
 #[derive(Clone)]
@@ -114,13 +114,15 @@ impl ExaService {
             r#type: Some("auto".to_string()),
         };
 
-        let response = self
+        let request = self
             .client
             .post(format!("{}/search", self.base_url))
             .header("Authorization", &format!("Bearer {}", self.api_key))
             .header("Content-Type", "application/json")
-            .json(&search_request)
+            .json(&search_request);
+        let response = telemetry::propagate(request)
             .send()
+            .instrument(info_span!("exa"))
             .await
             .map_err(|e| anyhow!("Failed to send request to Exa API: {}", e))?;
 
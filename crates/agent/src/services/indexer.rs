@@ -0,0 +1,213 @@
+//! Client of the indexer API for proposal context
+
+use std::{collections::HashSet, time::Duration};
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use governance::{
+    GroundTruth, PricedToken, Proposal, ProposalId, ProtocolId, TokenValue, Treasury,
+};
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde::de::DeserializeOwned;
+use tracing::{info_span, Instrument};
+
+/// Header carrying the indexer API key
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Time the indexer has to answer a request, so context never holds up an evaluation for long
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Indexer API client
+#[derive(Clone)]
+pub struct IndexerClient {
+    client: Client,
+    base_url: String,
+    api_key: Option<String>,
+}
+
+impl IndexerClient {
+    /// Create a client of the indexer at `base_url`
+    pub fn new(base_url: &str, api_key: Option<String>) -> Self {
+        Self {
+            client: Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .expect("Failed to build indexer HTTP client"),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+        }
+    }
+
+    /// Treasury of a protocol, `None` if the indexer does not track the protocol
+    pub async fn treasury(&self, protocol_id: &ProtocolId) -> Result<Option<Treasury>> {
+        let url = format!("{}/protocols/{}/treasury", self.base_url, protocol_id);
+        self.get(self.client.get(url), "treasury").await
+    }
+
+    /// Indexed proposal, `None` if the indexer does not know it
+    pub async fn proposal(&self, proposal_id: &ProposalId) -> Result<Option<Proposal>> {
+        let url = format!("{}/proposals/{}", self.base_url, proposal_id);
+        self.get(self.client.get(url), "proposal").await
+    }
+
+    /// Final human verdicts of all labeled proposals, for evaluating the agent
+    pub async fn ground_truth(&self) -> Result<Vec<GroundTruth>> {
+        let url = format!("{}/labels/ground-truth", self.base_url);
+        Ok(self
+            .get(self.client.get(url), "ground truth")
+            .await?
+            .unwrap_or_default())
+    }
+
+    /// Registry tokens of a chain whose prices the indexer tracks
+    pub async fn tokens(&self, chain_id: u64) -> Result<Vec<PricedToken>> {
+        let request = self
+            .client
+            .get(format!("{}/prices/tokens", self.base_url))
+            .query(&[("chain_id", chain_id)]);
+        Ok(self.get(request, "tokens").await?.unwrap_or_default())
+    }
+
+    /// USD value of an amount of a token at the price of the day of `at`
+    ///
+    /// The token is given by its symbol or contract address. Returns `None`
+    /// if the indexer does not know the token or has no recent price of it.
+    pub async fn value_usd(
+        &self,
+        chain_id: u64,
+        token: &str,
+        amount: f64,
+        at: DateTime<Utc>,
+    ) -> Result<Option<TokenValue>> {
+        let request = self
+            .client
+            .get(format!("{}/prices/value", self.base_url))
+            .query(&[
+                ("chain_id", chain_id.to_string()),
+                ("token", token.to_string()),
+                ("amount", amount.to_string()),
+                ("at", at.to_rfc3339_opts(SecondsFormat::Secs, true)),
+            ]);
+        self.get(request, "price").await
+    }
+
+    /// Send a GET request, mapping a 404 to `None`
+    async fn get<T: DeserializeOwned>(
+        &self,
+        mut request: RequestBuilder,
+        resource: &str,
+    ) -> Result<Option<T>> {
+        if let Some(api_key) = &self.api_key {
+            request = request.header(API_KEY_HEADER, api_key);
+        }
+        let response = telemetry::propagate(request)
+            .send()
+            .instrument(info_span!("indexer"))
+            .await
+            .map_err(|e| anyhow!("Failed to send request to the indexer: {}", e))?;
+
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => {
+                response.json().await.map(Some).map_err(|e| {
+                    anyhow!("Failed to parse the indexer's {} response: {}", resource, e)
+                })
+            }
+            status => Err(anyhow!(
+                "Indexer {} request failed with status {}",
+                resource,
+                status
+            )),
+        }
+    }
+}
+
+/// Describe a treasury's latest holdings for a prompt, `None` before its first snapshot
+pub fn treasury_context(treasury: &Treasury) -> Option<String> {
+    let updated_at = treasury.updated_at?;
+    let holdings: Vec<String> = treasury
+        .totals()
+        .iter()
+        .map(|total| match total.value_usd {
+            Some(value) => format!(
+                "{} {} (${})",
+                format_amount(total.balance),
+                total.symbol,
+                format_amount(value)
+            ),
+            None => format!("{} {}", format_amount(total.balance), total.symbol),
+        })
+        .collect();
+    let worth = treasury
+        .value_usd()
+        .map(|value| format!(", worth ${} in priced tokens", format_amount(value)))
+        .unwrap_or_default();
+    Some(format!(
+        "Treasury of {} across {} address(es) as of {}: {}{}. \
+         Use it to judge whether any requested budget is proportional to what the DAO holds.",
+        treasury.protocol_id,
+        treasury.addresses.len(),
+        updated_at.format("%Y-%m-%d"),
+        if holdings.is_empty() {
+            "no tracked holdings".to_string()
+        } else {
+            holdings.join(", ")
+        },
+        worth
+    ))
+}
+
+/// Describe token prices for a prompt, `None` without prices
+///
+/// Lets the model convert budgets requested in tokens to USD at the prices
+/// of the day the proposal was made.
+pub fn price_context(values: &[TokenValue], at: DateTime<Utc>) -> Option<String> {
+    if values.is_empty() {
+        return None;
+    }
+    let prices: Vec<String> = values
+        .iter()
+        .map(|value| format!("1 {} = ${}", value.symbol, format_amount(value.price_usd)))
+        .collect();
+    Some(format!(
+        "Token prices on {}: {}. \
+         Use them to convert budgets requested in tokens to USD.",
+        at.format("%Y-%m-%d"),
+        prices.join(", ")
+    ))
+}
+
+/// Tokens a text mentions by symbol or contract address, in registry order
+///
+/// Symbols match whole words and are case-sensitive, so `ARB` matches in
+/// "10,000 ARB" and "$ARB" but not in "arbitrary" or "ARBITRUM".
+pub fn mentioned_tokens<'a>(text: &str, tokens: &'a [PricedToken]) -> Vec<&'a PricedToken> {
+    let words: HashSet<&str> = text
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect();
+    tokens
+        .iter()
+        .filter(|token| {
+            words.contains(token.symbol.as_str())
+                || token.address.as_deref().is_some_and(|address| {
+                    words.iter().any(|word| word.eq_ignore_ascii_case(address))
+                })
+        })
+        .collect()
+}
+
+/// Amount rounded to two decimals with thousands separators
+fn format_amount(amount: f64) -> String {
+    let rounded = format!("{:.2}", amount.abs());
+    let (whole, fraction) = rounded.split_once('.').unwrap_or((&rounded, "00"));
+    let mut grouped = String::new();
+    for (i, digit) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    let sign = if amount < 0.0 { "-" } else { "" };
+    format!("{sign}{grouped}.{fraction}")
+}
@@ -7,8 +7,12 @@
 pub mod agent;
 /// Cache service for all API endpoints
 pub mod cache;
+/// Evaluation of the agent against human labels
+pub mod evaluation;
 /// Exa search service for finding related proposals
 pub mod exa;
+/// Indexer API client for proposal context
+pub mod indexer;
 /// Webhook service for receiving events
 pub mod webhook;
 
@@ -25,10 +25,9 @@ impl WebhookService {
     }
 
     /// Verify webhook signature
-    #[allow(dead_code, unused_variables)] // TODO: Remove after development phase
+    #[allow(dead_code)] // TODO: Remove after development phase
     pub fn verify_signature(&self, payload: &[u8], signature: &str, secret: &str) -> bool {
-        // TODO: Implement signature verification
-        todo!("Implement verify_signature")
+        governance::webhook::verify_signature(signature, payload, secret)
     }
 }
 
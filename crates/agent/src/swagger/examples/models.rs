@@ -6,6 +6,18 @@ pub fn PROPOSAL_DESCRIPTION_EXAMPLE() -> &'static str {
     "This proposal aims to increase the reward rate for stakers from 5% to 7% to incentivize more participation in the protocol. The change will be implemented over a 30-day period with quarterly reviews to assess impact on protocol sustainability."
 }
 
+/// Example of a proposal's protocol ID
+#[allow(non_snake_case)]
+pub fn PROPOSAL_PROTOCOL_ID_EXAMPLE() -> &'static str {
+    "eip155:1:uniswap"
+}
+
+/// Example of a proposal's creation time
+#[allow(non_snake_case)]
+pub fn PROPOSAL_CREATED_AT_EXAMPLE() -> &'static str {
+    "2026-09-30T12:00:00Z"
+}
+
 /// Example of proposal metadata title
 #[allow(non_snake_case)]
 pub fn PROPOSAL_METADATA_TITLE_EXAMPLE() -> &'static str {
@@ -0,0 +1,42 @@
+use agent::services::agent::AgentServiceTrait;
+use agent::services::evaluation::{agreement, analysis_criteria};
+
+// Import the fixtures as proper modules
+mod fixtures;
+use fixtures::create_agent::create_agent_service;
+use fixtures::ground_truth::labeled_proposals;
+
+/// Number of labeled proposals analyzed
+const PROPOSALS: usize = 10;
+
+#[tokio::test]
+async fn test_e2e_ground_truth_agreement() {
+    let labeled = labeled_proposals(PROPOSALS).await;
+    assert!(
+        !labeled.is_empty(),
+        "The indexer has no proposals with agreed labels"
+    );
+
+    let agent_service = create_agent_service().await.unwrap();
+    let mut results = Vec::new();
+    for (proposal, labeled_criteria) in &labeled {
+        let analysis = agent_service.analyze_proposal(proposal).await.unwrap();
+        let criteria = analysis_criteria(&analysis.data)
+            .expect("The analysis should only use pass, fail and n/a statuses");
+        results.push((*labeled_criteria, criteria));
+    }
+
+    println!(
+        "Agreement with human labels on {} proposals:",
+        results.len()
+    );
+    for category in agreement(&results) {
+        println!(
+            "{}: {}/{} ({:.0}%)",
+            category.criterion.as_str(),
+            category.agreed,
+            category.compared,
+            category.rate().unwrap_or_default() * 100.0
+        );
+    }
+}
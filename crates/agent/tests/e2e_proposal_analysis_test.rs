@@ -11,6 +11,8 @@ async fn test_e2e_proposal_analysis() {
     let proposals_data = get_proposals();
     let proposal = Proposal {
         description: proposals_data[0].to_string(),
+        protocol_id: None,
+        created_at: None,
     };
 
     let agent_service = create_agent_service().await.unwrap();
@@ -31,6 +33,8 @@ async fn test_e2e_multiple_proposals() {
     for (i, proposal_text) in proposals_data.iter().enumerate().take(max_proposals) {
         let proposal = Proposal {
             description: proposal_text.to_string(),
+            protocol_id: None,
+            created_at: None,
         };
         let analysis = agent_service.analyze_proposal(&proposal).await.unwrap();
         validate_analysis(&analysis.data);
@@ -52,6 +56,8 @@ async fn test_e2e_all_proposals() {
     for (i, proposal_text) in proposals_data.iter().enumerate() {
         let proposal = Proposal {
             description: proposal_text.to_string(),
+            protocol_id: None,
+            created_at: None,
         };
 
         println!("Running analysis on proposal {}...", i + 1);
@@ -138,6 +138,8 @@ pub async fn query_agent(agent_service: &AgentService, prompt: &str) -> Result<S
     // Create a simple proposal with the prompt as the description
     let proposal = agent::models::Proposal {
         description: prompt.to_string(),
+        protocol_id: None,
+        created_at: None,
     };
 
     // Use the analyze_proposal method from the AgentServiceTrait
@@ -0,0 +1,41 @@
+use std::env;
+
+use agent::models::Proposal;
+use agent::services::indexer::IndexerClient;
+use dotenv::dotenv;
+use governance::LabelCriteria;
+
+/// Loads labeled proposals from the indexer for evaluating the agent
+///
+/// Returns up to `limit` proposals whose labelers agree on the results of
+/// every evaluation category, with those results. It requires the following
+/// environment variables to be set:
+/// - WEI_AGENT_INDEXER_URL: base URL of an indexer with labeled proposals
+/// - WEI_AGENT_INDEXER_API_KEY: API key of the indexer, if it requires one
+#[allow(dead_code)]
+pub async fn labeled_proposals(limit: usize) -> Vec<(Proposal, LabelCriteria)> {
+    dotenv().ok();
+
+    let base_url = env::var("WEI_AGENT_INDEXER_URL")
+        .ok()
+        .filter(|url| !url.trim().is_empty())
+        .expect(
+            "WEI_AGENT_INDEXER_URL environment variable must be set and non-empty for e2e tests.\n\n\
+             Point it to an indexer with labeled proposals, see the labeling section of the README",
+        );
+    let indexer = IndexerClient::new(&base_url, env::var("WEI_AGENT_INDEXER_API_KEY").ok());
+
+    let mut labeled = Vec::new();
+    for truth in indexer.ground_truth().await.unwrap() {
+        if labeled.len() == limit {
+            break;
+        }
+        let Some(criteria) = truth.criteria else {
+            continue;
+        };
+        if let Some(proposal) = indexer.proposal(&truth.proposal_id).await.unwrap() {
+            labeled.push((Proposal::from(&proposal), criteria));
+        }
+    }
+    labeled
+}
@@ -1,3 +1,4 @@
 // Export the fixtures modules
 pub mod create_agent;
+pub mod ground_truth;
 pub mod proposals;
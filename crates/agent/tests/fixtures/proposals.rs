@@ -1,5 +1,6 @@
 // Fixed proposal strings with proper Rust syntax
 
+#[allow(dead_code)]
 pub fn get_proposals() -> Vec<&'static str> {
     // Proposal 1 - Arbitrum Hackathon Continuation Program
     let proposal_1: &'static str = r#"Abstract
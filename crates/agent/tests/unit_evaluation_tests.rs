@@ -0,0 +1,177 @@
+//! Unit tests for evaluating the agent against human labels
+
+use agent::{
+    models::{analysis::EvaluationCategory, Proposal, StructuredAnalysisResponse},
+    services::{
+        evaluation::{agreement, analysis_criteria},
+        indexer::IndexerClient,
+    },
+};
+use axum::{extract::Path, http::StatusCode, routing::get, Json, Router};
+use governance::{CriterionStatus, LabelCriteria, LabelCriterion, LabelValue};
+use serde_json::json;
+
+const PROPOSAL_ID: &str = "eip155:1:uniswap:snapshot:0x01";
+
+fn category(status: &str) -> EvaluationCategory {
+    EvaluationCategory {
+        status: status.to_string(),
+        ..Default::default()
+    }
+}
+
+fn criteria(statuses: [CriterionStatus; 5]) -> LabelCriteria {
+    let mut criteria = LabelCriteria::default();
+    for (criterion, status) in LabelCriterion::ALL.into_iter().zip(statuses) {
+        criteria.set(criterion, status);
+    }
+    criteria
+}
+
+#[test]
+fn test_analysis_criteria() {
+    let analysis = StructuredAnalysisResponse {
+        summary: "Fund the grants program".to_string(),
+        goals_and_motivation: category("pass"),
+        measurable_outcomes: category(" Fail "),
+        budget: category("N/A"),
+        technical_specifications: category("pass"),
+        language_quality: category("fail"),
+    };
+    use CriterionStatus::*;
+    assert_eq!(
+        analysis_criteria(&analysis),
+        Some(criteria([Pass, Fail, NotApplicable, Pass, Fail]))
+    );
+
+    let unknown = StructuredAnalysisResponse {
+        budget: category("partial"),
+        ..analysis
+    };
+    assert_eq!(analysis_criteria(&unknown), None);
+}
+
+#[test]
+fn test_agreement() {
+    use CriterionStatus::*;
+    let labeled = criteria([Pass, Fail, NotApplicable, Pass, Pass]);
+    let results = [
+        (labeled, criteria([Pass, Pass, NotApplicable, Pass, Pass])),
+        (labeled, criteria([Pass, Fail, Fail, Fail, Pass])),
+    ];
+
+    let rates: Vec<_> = agreement(&results)
+        .iter()
+        .map(|category| (category.criterion, category.agreed, category.rate()))
+        .collect();
+    assert_eq!(
+        rates,
+        vec![
+            (LabelCriterion::GoalsAndMotivation, 2, Some(1.0)),
+            (LabelCriterion::MeasurableOutcomes, 1, Some(0.5)),
+            (LabelCriterion::Budget, 1, Some(0.5)),
+            (LabelCriterion::TechnicalSpecifications, 1, Some(0.5)),
+            (LabelCriterion::LanguageQuality, 2, Some(1.0)),
+        ]
+    );
+    assert!(agreement(&[])
+        .iter()
+        .all(|category| category.rate().is_none()));
+}
+
+#[tokio::test]
+async fn test_ground_truth_from_indexer() {
+    let app = Router::new()
+        .route(
+            "/labels/ground-truth",
+            get(|| async {
+                Json(json!([
+                    {
+                        "proposal_id": PROPOSAL_ID,
+                        "label": "good",
+                        "criteria": {
+                            "goals_and_motivation": "pass",
+                            "measurable_outcomes": "fail",
+                            "budget": "n/a",
+                            "technical_specifications": "pass",
+                            "language_quality": "pass"
+                        },
+                        "labels": 2,
+                        "resolved_by": null
+                    },
+                    {
+                        "proposal_id": "eip155:1:uniswap:snapshot:0x02",
+                        "label": "bad",
+                        "criteria": null,
+                        "labels": 1,
+                        "resolved_by": "alice"
+                    }
+                ]))
+            }),
+        )
+        .route(
+            "/proposals/:id",
+            get(|Path(id): Path<String>| async move {
+                if id != PROPOSAL_ID {
+                    return Err(StatusCode::NOT_FOUND);
+                }
+                Ok(Json(json!({
+                    "id": PROPOSAL_ID,
+                    "title": "Fund the grants program",
+                    "description": "Allocate 1M UNI to grants",
+                    "status": "Active",
+                    "protocol_id": "eip155:1:uniswap",
+                    "choices": ["For", "Against"],
+                    "author": "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+                    "comments": [],
+                    "created_at": "2026-10-01T12:00:00Z",
+                    "updated_at": "2026-10-01T12:00:00Z",
+                    "voting_start": null,
+                    "voting_end": null,
+                    "snapshot_block": null,
+                    "quorum": null,
+                    "voting_type": "single-choice",
+                    "scores": [],
+                    "scores_total": null,
+                    "discussion_url": null,
+                    "ipfs_hash": null,
+                    "source_url": null
+                })))
+            }),
+        );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    let client = IndexerClient::new(&format!("http://{address}"), None);
+    let truths = client.ground_truth().await.unwrap();
+    assert_eq!(truths.len(), 2);
+    assert_eq!(truths[0].label, LabelValue::Good);
+    assert_eq!(
+        truths[0].criteria.map(|criteria| criteria.budget),
+        Some(CriterionStatus::NotApplicable)
+    );
+    assert_eq!(truths[1].criteria, None);
+    assert_eq!(truths[1].resolved_by.as_deref(), Some("alice"));
+
+    let proposal = client.proposal(&truths[0].proposal_id).await.unwrap();
+    let proposal = Proposal::from(&proposal.unwrap());
+    assert_eq!(proposal.description, "Allocate 1M UNI to grants");
+    assert_eq!(
+        proposal.protocol_id.map(|id| id.to_string()).as_deref(),
+        Some("eip155:1:uniswap")
+    );
+    assert!(client
+        .proposal(&truths[1].proposal_id)
+        .await
+        .unwrap()
+        .is_none());
+
+    // An indexer without labels has no ground truth
+    let empty = Router::new();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, empty).await.unwrap() });
+    let client = IndexerClient::new(&format!("http://{address}"), None);
+    assert!(client.ground_truth().await.unwrap().is_empty());
+}
@@ -92,6 +92,8 @@ async fn test_expected_format() {
     // Create a proposal from the test proposal
     let proposal = Proposal {
         description: proposal_text.to_string(),
+        protocol_id: None,
+        created_at: None,
     };
 
     // Create a response with the expected format
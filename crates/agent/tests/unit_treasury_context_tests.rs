@@ -0,0 +1,238 @@
+//! Unit tests for the treasury and token price context of proposal evaluations
+
+use std::collections::HashMap;
+
+use agent::services::indexer::{
+    mentioned_tokens, price_context, treasury_context, IndexerClient, API_KEY_HEADER,
+};
+use axum::{
+    extract::{Path, Query},
+    http::HeaderMap,
+    http::StatusCode,
+    routing::get,
+    Json, Router,
+};
+use chrono::{NaiveDate, TimeZone, Utc};
+use governance::{PricedToken, ProtocolId, TokenValue, Treasury, TreasuryBalance};
+
+fn balance(address: &str, token: Option<&str>, symbol: &str, balance: f64) -> TreasuryBalance {
+    TreasuryBalance {
+        address: address.to_string(),
+        token: token.map(str::to_string),
+        symbol: symbol.to_string(),
+        decimals: 18,
+        raw_balance: "0".to_string(),
+        balance,
+        value_usd: None,
+        block_number: 20_000_000,
+        recorded_at: Utc.with_ymd_and_hms(2026, 10, 1, 12, 0, 0).unwrap(),
+    }
+}
+
+fn treasury() -> Treasury {
+    let uni = Some("0x1f9840a85d5aF5bf1D1762F925BDADdC4201F984");
+    let balances = vec![
+        balance("0xa", None, "ETH", 10.0),
+        balance("0xa", uni, "UNI", 1_000_000.5),
+        balance("0xb", None, "ETH", 2.5),
+        balance("0xb", uni, "UNI", 234_567.25),
+    ];
+    Treasury {
+        protocol_id: "eip155:1:uniswap".parse().unwrap(),
+        addresses: vec!["0xa".to_string(), "0xb".to_string()],
+        updated_at: Some(balances[0].recorded_at),
+        balances,
+        history: Vec::new(),
+    }
+}
+
+#[test]
+fn test_treasury_context() {
+    let treasury = treasury();
+    let totals = treasury.totals();
+    assert_eq!(totals.len(), 2);
+    assert_eq!(
+        (totals[0].symbol.as_str(), totals[0].balance),
+        ("ETH", 12.5)
+    );
+    assert_eq!(
+        (totals[1].symbol.as_str(), totals[1].balance),
+        ("UNI", 1_234_567.75)
+    );
+
+    let context = treasury_context(&treasury).unwrap();
+    assert!(context.starts_with(
+        "Treasury of eip155:1:uniswap across 2 address(es) as of 2026-10-01: \
+         12.50 ETH, 1,234,567.75 UNI."
+    ));
+
+    let empty = Treasury {
+        updated_at: None,
+        balances: Vec::new(),
+        ..treasury
+    };
+    assert_eq!(treasury_context(&empty), None);
+}
+
+#[test]
+fn test_treasury_context_in_usd() {
+    let mut treasury = treasury();
+    for balance in &mut treasury.balances {
+        if balance.symbol == "ETH" {
+            balance.value_usd = Some(balance.balance * 2_000.0);
+        }
+    }
+    assert_eq!(treasury.totals()[0].value_usd, Some(25_000.0));
+    assert_eq!(treasury.totals()[1].value_usd, None);
+    assert_eq!(treasury.value_usd(), Some(25_000.0));
+
+    let context = treasury_context(&treasury).unwrap();
+    assert!(context.starts_with(
+        "Treasury of eip155:1:uniswap across 2 address(es) as of 2026-10-01: \
+         12.50 ETH ($25,000.00), 1,234,567.75 UNI, worth $25,000.00 in priced tokens."
+    ));
+}
+
+fn token_value(symbol: &str, amount: f64, price_usd: f64) -> TokenValue {
+    TokenValue {
+        chain_id: 1,
+        token: None,
+        symbol: symbol.to_string(),
+        amount,
+        price_usd,
+        price_date: NaiveDate::from_ymd_opt(2026, 9, 30).unwrap(),
+        value_usd: amount * price_usd,
+    }
+}
+
+#[test]
+fn test_price_context() {
+    let at = Utc.with_ymd_and_hms(2026, 9, 30, 18, 0, 0).unwrap();
+    let values = [
+        token_value("ETH", 1.0, 2_512.346),
+        token_value("UNI", 1.0, 7.5),
+    ];
+    assert_eq!(
+        price_context(&values, at).unwrap(),
+        "Token prices on 2026-09-30: 1 ETH = $2,512.35, 1 UNI = $7.50. \
+         Use them to convert budgets requested in tokens to USD."
+    );
+    assert_eq!(price_context(&[], at), None);
+}
+
+fn priced_token(address: Option<&str>, symbol: &str) -> PricedToken {
+    PricedToken {
+        chain_id: 1,
+        address: address.map(str::to_string),
+        symbol: symbol.to_string(),
+        decimals: 18,
+    }
+}
+
+#[test]
+fn test_mentioned_tokens() {
+    let usdc = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+    let tokens = [
+        priced_token(None, "ETH"),
+        priced_token(Some("0x1f9840a85d5aF5bf1D1762F925BDADdC4201F984"), "UNI"),
+        priced_token(Some(usdc), "USDC"),
+    ];
+    let symbols = |text: &str| -> Vec<String> {
+        mentioned_tokens(text, &tokens)
+            .into_iter()
+            .map(|token| token.symbol.clone())
+            .collect()
+    };
+
+    assert_eq!(
+        symbols("We request 250,000 USDC and 10 ETH for the program."),
+        vec!["ETH", "USDC"]
+    );
+    assert_eq!(
+        symbols("Budget: 1M $UNI (UNI/USD at proposal time)"),
+        vec!["UNI"]
+    );
+    assert_eq!(
+        symbols(&format!("Paid from {} on Ethereum", usdc.to_lowercase())),
+        vec!["USDC"]
+    );
+    // Symbols are case-sensitive whole words
+    assert!(symbols("A unified ETHEREUM community with eth staking").is_empty());
+}
+
+#[tokio::test]
+async fn test_indexer_client_values_tokens() {
+    let app = Router::new()
+        .route(
+            "/prices/value",
+            get(|Query(query): Query<HashMap<String, String>>| async move {
+                if query["chain_id"] != "1" || query["token"] != "UNI" {
+                    return Err(StatusCode::NOT_FOUND);
+                }
+                assert_eq!(query["at"], "2026-09-30T18:00:00Z");
+                let amount: f64 = query["amount"].parse().unwrap();
+                Ok(Json(token_value("UNI", amount, 7.5)))
+            }),
+        )
+        .route(
+            "/prices/tokens",
+            get(|Query(query): Query<HashMap<String, String>>| async move {
+                let tokens = match query["chain_id"].as_str() {
+                    "1" => vec![priced_token(None, "ETH"), priced_token(None, "UNI")],
+                    _ => Vec::new(),
+                };
+                Json(tokens)
+            }),
+        );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    let client = IndexerClient::new(&format!("http://{address}"), None);
+    let at = Utc.with_ymd_and_hms(2026, 9, 30, 18, 0, 0).unwrap();
+    let value = client.value_usd(1, "UNI", 200_000.0, at).await.unwrap();
+    assert_eq!(value.map(|value| value.value_usd), Some(1_500_000.0));
+    assert_eq!(client.value_usd(1, "ARB", 1.0, at).await.unwrap(), None);
+
+    let symbols: Vec<String> = client
+        .tokens(1)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|token| token.symbol)
+        .collect();
+    assert_eq!(symbols, vec!["ETH", "UNI"]);
+    assert!(client.tokens(42161).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_indexer_client_fetches_treasury() {
+    let app = Router::new().route(
+        "/protocols/:id/treasury",
+        get(|Path(id): Path<String>, headers: HeaderMap| async move {
+            if headers
+                .get(API_KEY_HEADER)
+                .is_none_or(|key| key != "secret")
+            {
+                return Err(StatusCode::UNAUTHORIZED);
+            }
+            if id != "eip155:1:uniswap" {
+                return Err(StatusCode::NOT_FOUND);
+            }
+            Ok(Json(treasury()))
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    let client = IndexerClient::new(&format!("http://{address}/"), Some("secret".to_string()));
+    let uniswap: ProtocolId = "eip155:1:uniswap".parse().unwrap();
+    assert_eq!(client.treasury(&uniswap).await.unwrap(), Some(treasury()));
+
+    let unknown: ProtocolId = "eip155:1:unknown".parse().unwrap();
+    assert_eq!(client.treasury(&unknown).await.unwrap(), None);
+
+    let anonymous = IndexerClient::new(&format!("http://{address}"), None);
+    assert!(anonymous.treasury(&uniswap).await.is_err());
+}
@@ -0,0 +1,44 @@
+//! Governance participants
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::id::{AccountId, ChainId, IdError, ProtocolId};
+
+/// Represents an actor/entity in the governance system
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Actor {
+    /// Ethereum address of the actor
+    pub address: String,
+    /// ENS name if available
+    pub ens: Option<String>,
+    /// Organization name
+    pub name: Option<String>,
+    /// Description of the entity
+    pub description: Option<String>,
+    /// Voting power of the entity
+    pub voting_power: Option<String>,
+    /// Protocol/network identifier
+    pub protocol_id: Option<ProtocolId>,
+    /// Creation timestamp
+    pub created_at: DateTime<Utc>,
+    /// Last update timestamp
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Actor {
+    /// CAIP-10 account ID of the actor
+    ///
+    /// Uses the chain of the actor's protocol, or Ethereum mainnet for actors
+    /// that are not tied to a protocol.
+    pub fn account_id(&self) -> Result<AccountId, IdError> {
+        let chain_id = self
+            .protocol_id
+            .as_ref()
+            .map(|protocol_id| protocol_id.chain_id.clone())
+            .unwrap_or_else(|| ChainId::eip155(1));
+
+        AccountId::new(chain_id, &self.address)
+    }
+}
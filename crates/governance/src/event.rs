@@ -0,0 +1,222 @@
+//! Events announcing changes to indexed proposals
+//!
+//! Events are sent to clients of the indexer's event streams and webhooks
+//! in an [`EventEnvelope`], which carries the position of the event in the
+//! indexer's event log and the version of the event schema. Receivers should
+//! reject envelopes of a newer version than [`EVENT_VERSION`] instead of
+//! misreading them.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use utoipa::ToSchema;
+
+use crate::{
+    id::{ProposalId, ProtocolId},
+    proposal::{ProposalField, ProposalStatus},
+    status::StatusChange,
+};
+
+/// Version of the event schema sent by this build
+///
+/// Adding event types or optional fields keeps the version; renaming or
+/// removing fields, or changing their meaning, requires a new one.
+pub const EVENT_VERSION: u32 = 1;
+
+/// Names of the event types, as used in the `type` field
+pub const EVENT_TYPES: [&str; 6] = [
+    "proposal_created",
+    "proposal_updated",
+    "votes_updated",
+    "voting_started",
+    "voting_ended",
+    "status_changed",
+];
+
+/// Event emitted when indexed data changes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum IndexerEvent {
+    /// A proposal was indexed for the first time
+    ProposalCreated {
+        /// Canonical proposal ID
+        proposal_id: ProposalId,
+        /// Revision number of the initial content
+        revision: i32,
+        /// Time the event occurred
+        timestamp: DateTime<Utc>,
+    },
+    /// The content of a known proposal changed
+    ProposalUpdated {
+        /// Canonical proposal ID
+        proposal_id: ProposalId,
+        /// Revision number of the new content
+        revision: i32,
+        /// Fields that differ from the previous revision
+        changed_fields: Vec<ProposalField>,
+        /// Time the event occurred
+        timestamp: DateTime<Utc>,
+    },
+    /// Votes were cast on a proposal
+    VotesUpdated {
+        /// Canonical proposal ID
+        proposal_id: ProposalId,
+        /// Voting power cast for each choice
+        scores: Vec<f64>,
+        /// Total voting power cast
+        scores_total: Option<f64>,
+        /// Time the event occurred
+        timestamp: DateTime<Utc>,
+    },
+    /// Voting on a proposal opened
+    VotingStarted {
+        /// Canonical proposal ID
+        proposal_id: ProposalId,
+        /// Time the event occurred
+        timestamp: DateTime<Utc>,
+    },
+    /// Voting on a proposal closed
+    VotingEnded {
+        /// Canonical proposal ID
+        proposal_id: ProposalId,
+        /// Result of the vote
+        outcome: ProposalStatus,
+        /// Time the event occurred
+        timestamp: DateTime<Utc>,
+    },
+    /// A proposal's status changed outside of its voting window
+    StatusChanged {
+        /// Canonical proposal ID
+        proposal_id: ProposalId,
+        /// Previous status
+        from: ProposalStatus,
+        /// New status
+        to: ProposalStatus,
+        /// Time the event occurred
+        timestamp: DateTime<Utc>,
+    },
+}
+
+impl IndexerEvent {
+    /// Proposal the event is about
+    pub fn proposal_id(&self) -> &ProposalId {
+        match self {
+            Self::ProposalCreated { proposal_id, .. }
+            | Self::ProposalUpdated { proposal_id, .. }
+            | Self::VotesUpdated { proposal_id, .. }
+            | Self::VotingStarted { proposal_id, .. }
+            | Self::VotingEnded { proposal_id, .. }
+            | Self::StatusChanged { proposal_id, .. } => proposal_id,
+        }
+    }
+
+    /// Name of the event type, one of [`EVENT_TYPES`]
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            Self::ProposalCreated { .. } => "proposal_created",
+            Self::ProposalUpdated { .. } => "proposal_updated",
+            Self::VotesUpdated { .. } => "votes_updated",
+            Self::VotingStarted { .. } => "voting_started",
+            Self::VotingEnded { .. } => "voting_ended",
+            Self::StatusChanged { .. } => "status_changed",
+        }
+    }
+
+    /// Protocol the event is about
+    pub fn protocol_id(&self) -> &ProtocolId {
+        self.proposal_id().protocol_id()
+    }
+
+    /// Event announcing a status change
+    ///
+    /// Returns `None` for the initial status of a new proposal, which is
+    /// announced by [`IndexerEvent::ProposalCreated`].
+    pub fn for_status_change(change: &StatusChange) -> Option<Self> {
+        let from = change.from?;
+        let proposal_id = change.proposal_id.clone();
+        let timestamp = change.changed_at;
+
+        Some(match (from, change.to) {
+            (_, ProposalStatus::Active) => Self::VotingStarted {
+                proposal_id,
+                timestamp,
+            },
+            (
+                ProposalStatus::Pending | ProposalStatus::Active,
+                outcome @ (ProposalStatus::Accepted | ProposalStatus::Rejected),
+            ) => Self::VotingEnded {
+                proposal_id,
+                outcome,
+                timestamp,
+            },
+            (from, to) => Self::StatusChanged {
+                proposal_id,
+                from,
+                to,
+                timestamp,
+            },
+        })
+    }
+}
+
+/// Event as sent to event stream clients and webhooks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct EventEnvelope {
+    /// Version of the event schema, see [`EVENT_VERSION`]
+    ///
+    /// Envelopes sent before versioning have no version and are version 1.
+    #[serde(default = "first_version")]
+    pub version: u32,
+    /// Position in the event log, increasing with every event
+    pub id: i64,
+    /// The event
+    #[serde(flatten)]
+    pub event: IndexerEvent,
+}
+
+fn first_version() -> u32 {
+    1
+}
+
+/// Error returned when an event envelope cannot be read
+#[derive(Error, Debug)]
+pub enum EnvelopeError {
+    /// The envelope was sent by a newer indexer
+    #[error("Unsupported event version {0}, expected at most {EVENT_VERSION}")]
+    UnsupportedVersion(u32),
+    /// The envelope is not valid JSON or not a known event
+    #[error("Invalid event: {0}")]
+    Invalid(#[from] serde_json::Error),
+}
+
+impl EventEnvelope {
+    /// Envelope of a logged event, at the current version
+    pub fn new(id: i64, event: IndexerEvent) -> Self {
+        Self {
+            version: EVENT_VERSION,
+            id,
+            event,
+        }
+    }
+
+    /// Read an envelope from JSON, rejecting versions newer than [`EVENT_VERSION`]
+    pub fn from_json(json: &str) -> Result<Self, EnvelopeError> {
+        #[derive(Deserialize)]
+        struct Version {
+            #[serde(default = "first_version")]
+            version: u32,
+        }
+
+        let Version { version } = serde_json::from_str(json)?;
+        if version > EVENT_VERSION {
+            return Err(EnvelopeError::UnsupportedVersion(version));
+        }
+        Ok(serde_json::from_str(json)?)
+    }
+}
+
+impl From<EventEnvelope> for IndexerEvent {
+    fn from(envelope: EventEnvelope) -> Self {
+        envelope.event
+    }
+}
@@ -0,0 +1,395 @@
+//! Canonical identifiers
+//!
+//! Chains are identified by [CAIP-2] chain IDs (`eip155:1`) and accounts by
+//! [CAIP-10] account IDs (`eip155:1:0xab16…`). Protocols and proposals extend
+//! the chain ID with colon separated segments:
+//!
+//! - protocol: `<namespace>:<reference>:<protocol>` (e.g. `eip155:42161:arbitrum`)
+//! - proposal: `<namespace>:<reference>:<protocol>:<source>:<native id>`
+//!   (e.g. `eip155:42161:arbitrum:snapshot:0x1f3a…`)
+//!
+//! Every segment except the trailing native ID has a restricted charset
+//! without colons, so the native ID is kept verbatim and parsing is lossless
+//! even when it contains colons itself.
+//!
+//! [CAIP-2]: https://github.com/ChainAgnostic/CAIPs/blob/main/CAIPs/caip-2.md
+//! [CAIP-10]: https://github.com/ChainAgnostic/CAIPs/blob/main/CAIPs/caip-10.md
+
+use std::{fmt, str::FromStr};
+
+use thiserror::Error;
+use utoipa::openapi::{schema::SchemaType, ObjectBuilder, RefOr, Schema, Type};
+
+/// CAIP-2 namespace of EVM chains
+pub const EIP155_NAMESPACE: &str = "eip155";
+
+/// Maximum length of a proposal's native ID
+pub const MAX_NATIVE_ID_LENGTH: usize = 256;
+
+/// Error returned when an identifier is malformed
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum IdError {
+    /// Identifier does not have the expected number of segments
+    #[error("Invalid {kind} '{value}': expected {expected}")]
+    Format {
+        /// Kind of identifier being parsed
+        kind: &'static str,
+        /// Offending value
+        value: String,
+        /// Description of the expected format
+        expected: &'static str,
+    },
+
+    /// A segment of the identifier is invalid
+    #[error("Invalid {segment} '{value}' in identifier")]
+    Segment {
+        /// Name of the offending segment
+        segment: &'static str,
+        /// Offending value
+        value: String,
+    },
+}
+
+fn segment_error(segment: &'static str, value: &str) -> IdError {
+    IdError::Segment {
+        segment,
+        value: value.to_string(),
+    }
+}
+
+/// Check a CAIP-2 namespace (`[-a-z0-9]{3,8}`)
+fn is_valid_namespace(value: &str) -> bool {
+    (3..=8).contains(&value.len())
+        && value
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+/// Check a CAIP-2 reference (`[-_a-zA-Z0-9]{1,32}`)
+fn is_valid_reference(value: &str) -> bool {
+    (1..=32).contains(&value.len())
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Check a CAIP-10 account address (`[-.%a-zA-Z0-9]{1,128}`)
+fn is_valid_address(value: &str) -> bool {
+    (1..=128).contains(&value.len())
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '%'))
+}
+
+/// Check a protocol slug (`[a-z0-9][-_.a-z0-9]{0,63}`)
+pub fn is_valid_protocol_slug(value: &str) -> bool {
+    (1..=64).contains(&value.len())
+        && value
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && value
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '-' | '_' | '.'))
+}
+
+/// Check a data source name (`[a-z0-9][-a-z0-9]{0,31}`)
+fn is_valid_source(value: &str) -> bool {
+    (1..=32).contains(&value.len())
+        && value
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && value
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+/// Check a proposal's native ID: printable, without whitespace, bounded length
+fn is_valid_native_id(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= MAX_NATIVE_ID_LENGTH
+        && !value.chars().any(|c| c.is_whitespace() || c.is_control())
+}
+
+/// Implement `Serialize`/`Deserialize` through `Display`/`FromStr`, and
+/// describe the type as a string in OpenAPI schemas
+macro_rules! string_serde {
+    ($ty:ty, $example:literal) => {
+        impl serde::Serialize for $ty {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $ty {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let value = <String as serde::Deserialize>::deserialize(deserializer)?;
+                value.parse().map_err(serde::de::Error::custom)
+            }
+        }
+
+        impl utoipa::PartialSchema for $ty {
+            fn schema() -> RefOr<Schema> {
+                ObjectBuilder::new()
+                    .schema_type(SchemaType::new(Type::String))
+                    .examples([$example])
+                    .into()
+            }
+        }
+
+        impl utoipa::ToSchema for $ty {}
+    };
+}
+
+/// CAIP-2 chain ID
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ChainId {
+    namespace: String,
+    reference: String,
+}
+
+impl ChainId {
+    /// Create a chain ID from its namespace and reference
+    pub fn new(namespace: &str, reference: &str) -> Result<Self, IdError> {
+        if !is_valid_namespace(namespace) {
+            return Err(segment_error("chain namespace", namespace));
+        }
+        if !is_valid_reference(reference) {
+            return Err(segment_error("chain reference", reference));
+        }
+
+        Ok(Self {
+            namespace: namespace.to_string(),
+            reference: reference.to_string(),
+        })
+    }
+
+    /// Chain ID of an EVM chain
+    pub fn eip155(chain_id: u64) -> Self {
+        Self {
+            namespace: EIP155_NAMESPACE.to_string(),
+            reference: chain_id.to_string(),
+        }
+    }
+
+    /// CAIP-2 namespace (e.g. `eip155`)
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    /// CAIP-2 reference (e.g. `1`)
+    pub fn reference(&self) -> &str {
+        &self.reference
+    }
+
+    /// Numeric EVM chain ID, if this is an `eip155` chain
+    pub fn as_eip155(&self) -> Option<u64> {
+        if self.namespace == EIP155_NAMESPACE {
+            self.reference.parse().ok()
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for ChainId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.namespace, self.reference)
+    }
+}
+
+impl FromStr for ChainId {
+    type Err = IdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split(':').collect::<Vec<_>>()[..] {
+            [namespace, reference] => Self::new(namespace, reference),
+            _ => Err(IdError::Format {
+                kind: "chain ID",
+                value: s.to_string(),
+                expected: "<namespace>:<reference>",
+            }),
+        }
+    }
+}
+
+string_serde!(ChainId, "eip155:1");
+
+/// CAIP-10 account ID
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AccountId {
+    chain_id: ChainId,
+    address: String,
+}
+
+impl AccountId {
+    /// Create an account ID on a chain
+    pub fn new(chain_id: ChainId, address: &str) -> Result<Self, IdError> {
+        if !is_valid_address(address) {
+            return Err(segment_error("account address", address));
+        }
+
+        Ok(Self {
+            chain_id,
+            address: address.to_string(),
+        })
+    }
+
+    /// Chain the account lives on
+    pub fn chain_id(&self) -> &ChainId {
+        &self.chain_id
+    }
+
+    /// Account address
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+}
+
+impl fmt::Display for AccountId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.chain_id, self.address)
+    }
+}
+
+impl FromStr for AccountId {
+    type Err = IdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split(':').collect::<Vec<_>>()[..] {
+            [namespace, reference, address] => {
+                Self::new(ChainId::new(namespace, reference)?, address)
+            }
+            _ => Err(IdError::Format {
+                kind: "account ID",
+                value: s.to_string(),
+                expected: "<namespace>:<reference>:<address>",
+            }),
+        }
+    }
+}
+
+string_serde!(
+    AccountId,
+    "eip155:1:0xab16a96d359ec26a11e2c2b3d8f8b8942d5bfcdb"
+);
+
+/// Canonical identifier of a protocol: its CAIP-2 chain and registry slug
+///
+/// Formatted as `<namespace>:<reference>:<protocol>`, e.g. `eip155:42161:arbitrum`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ProtocolId {
+    /// CAIP-2 chain ID
+    pub chain_id: ChainId,
+    /// Protocol slug
+    pub protocol: String,
+}
+
+impl ProtocolId {
+    /// Creates a new protocol ID
+    pub fn new(chain_id: ChainId, protocol: &str) -> Result<Self, IdError> {
+        if !is_valid_protocol_slug(protocol) {
+            return Err(segment_error("protocol", protocol));
+        }
+
+        Ok(Self {
+            chain_id,
+            protocol: protocol.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for ProtocolId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.chain_id, self.protocol)
+    }
+}
+
+impl FromStr for ProtocolId {
+    type Err = IdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split(':').collect::<Vec<_>>()[..] {
+            [namespace, reference, protocol] => {
+                Self::new(ChainId::new(namespace, reference)?, protocol)
+            }
+            _ => Err(IdError::Format {
+                kind: "protocol ID",
+                value: s.to_string(),
+                expected: "<namespace>:<reference>:<protocol>",
+            }),
+        }
+    }
+}
+
+string_serde!(ProtocolId, "eip155:42161:arbitrum");
+
+/// Canonical proposal ID
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ProposalId {
+    protocol_id: ProtocolId,
+    source: String,
+    native_id: String,
+}
+
+impl ProposalId {
+    /// Create a proposal ID from the protocol, the data source name and the
+    /// proposal's ID within that source
+    pub fn new(protocol_id: ProtocolId, source: &str, native_id: &str) -> Result<Self, IdError> {
+        if !is_valid_source(source) {
+            return Err(segment_error("source", source));
+        }
+        if !is_valid_native_id(native_id) {
+            return Err(segment_error("native proposal ID", native_id));
+        }
+
+        Ok(Self {
+            protocol_id,
+            source: source.to_string(),
+            native_id: native_id.to_string(),
+        })
+    }
+
+    /// Protocol the proposal belongs to
+    pub fn protocol_id(&self) -> &ProtocolId {
+        &self.protocol_id
+    }
+
+    /// Data source the proposal was indexed from (e.g. `snapshot`)
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Proposal ID within its data source
+    pub fn native_id(&self) -> &str {
+        &self.native_id
+    }
+}
+
+impl fmt::Display for ProposalId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.protocol_id, self.source, self.native_id)
+    }
+}
+
+impl FromStr for ProposalId {
+    type Err = IdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.splitn(5, ':').collect::<Vec<_>>()[..] {
+            [namespace, reference, protocol, source, native_id] => Self::new(
+                ProtocolId::new(ChainId::new(namespace, reference)?, protocol)?,
+                source,
+                native_id,
+            ),
+            _ => Err(IdError::Format {
+                kind: "proposal ID",
+                value: s.to_string(),
+                expected: "<namespace>:<reference>:<protocol>:<source>:<native id>",
+            }),
+        }
+    }
+}
+
+string_serde!(ProposalId, "eip155:42161:arbitrum:tally:42");
@@ -0,0 +1,174 @@
+//! Human labels of proposal quality
+//!
+//! Labels mirror the agent's five evaluation categories, so the indexer's
+//! ground truth can be compared with the agent's analyses.
+
+use serde::{Deserialize, Serialize};
+
+use crate::id::ProposalId;
+
+/// Quality verdict given to a proposal by a human labeler
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LabelValue {
+    /// A proposal the agent should learn from as a good example
+    Good,
+    /// A proposal the agent should learn from as a bad example
+    Bad,
+}
+
+impl LabelValue {
+    /// Database representation of the label
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Good => "good",
+            Self::Bad => "bad",
+        }
+    }
+}
+
+impl std::str::FromStr for LabelValue {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "good" => Ok(Self::Good),
+            "bad" => Ok(Self::Bad),
+            other => Err(format!("Unknown label: {other}")),
+        }
+    }
+}
+
+/// Evaluation category of the agent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LabelCriterion {
+    /// Goals and motivation are clear
+    GoalsAndMotivation,
+    /// Outcomes are measurable
+    MeasurableOutcomes,
+    /// The budget is justified
+    Budget,
+    /// Technical specifications are sufficient
+    TechnicalSpecifications,
+    /// The language is clear and professional
+    LanguageQuality,
+}
+
+impl LabelCriterion {
+    /// All criteria, in the order of the agent's response
+    pub const ALL: [Self; 5] = [
+        Self::GoalsAndMotivation,
+        Self::MeasurableOutcomes,
+        Self::Budget,
+        Self::TechnicalSpecifications,
+        Self::LanguageQuality,
+    ];
+
+    /// Name of the criterion, also its database column
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::GoalsAndMotivation => "goals_and_motivation",
+            Self::MeasurableOutcomes => "measurable_outcomes",
+            Self::Budget => "budget",
+            Self::TechnicalSpecifications => "technical_specifications",
+            Self::LanguageQuality => "language_quality",
+        }
+    }
+}
+
+/// Result of a proposal on a criterion, as reported by the agent
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub enum CriterionStatus {
+    /// The proposal meets the criterion
+    #[serde(rename = "pass")]
+    Pass,
+    /// The proposal does not meet the criterion
+    #[serde(rename = "fail")]
+    Fail,
+    /// The criterion does not apply to the proposal
+    #[default]
+    #[serde(rename = "n/a")]
+    NotApplicable,
+}
+
+impl CriterionStatus {
+    /// Database representation of the status
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pass => "pass",
+            Self::Fail => "fail",
+            Self::NotApplicable => "n/a",
+        }
+    }
+}
+
+impl std::str::FromStr for CriterionStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pass" => Ok(Self::Pass),
+            "fail" => Ok(Self::Fail),
+            "n/a" => Ok(Self::NotApplicable),
+            other => Err(format!("Unknown criterion status: {other}")),
+        }
+    }
+}
+
+/// Results of a proposal on the agent's five evaluation categories
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct LabelCriteria {
+    /// Goals and motivation
+    pub goals_and_motivation: CriterionStatus,
+    /// Measurable outcomes
+    pub measurable_outcomes: CriterionStatus,
+    /// Budget
+    pub budget: CriterionStatus,
+    /// Technical specifications
+    pub technical_specifications: CriterionStatus,
+    /// Language quality
+    pub language_quality: CriterionStatus,
+}
+
+impl LabelCriteria {
+    /// Status of a criterion
+    pub fn get(&self, criterion: LabelCriterion) -> CriterionStatus {
+        match criterion {
+            LabelCriterion::GoalsAndMotivation => self.goals_and_motivation,
+            LabelCriterion::MeasurableOutcomes => self.measurable_outcomes,
+            LabelCriterion::Budget => self.budget,
+            LabelCriterion::TechnicalSpecifications => self.technical_specifications,
+            LabelCriterion::LanguageQuality => self.language_quality,
+        }
+    }
+
+    /// Set the status of a criterion
+    pub fn set(&mut self, criterion: LabelCriterion, status: CriterionStatus) {
+        let field = match criterion {
+            LabelCriterion::GoalsAndMotivation => &mut self.goals_and_motivation,
+            LabelCriterion::MeasurableOutcomes => &mut self.measurable_outcomes,
+            LabelCriterion::Budget => &mut self.budget,
+            LabelCriterion::TechnicalSpecifications => &mut self.technical_specifications,
+            LabelCriterion::LanguageQuality => &mut self.language_quality,
+        };
+        *field = status;
+    }
+}
+
+/// Final verdict on a labeled proposal
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GroundTruth {
+    /// Canonical ID of the proposal
+    pub proposal_id: ProposalId,
+    /// Resolved verdict, or else the majority verdict of the labels
+    pub label: LabelValue,
+    /// Resolved results, or else the results all labelers agree on
+    pub criteria: Option<LabelCriteria>,
+    /// Number of labels of the proposal
+    pub labels: usize,
+    /// Person who resolved a conflict on the proposal
+    pub resolved_by: Option<String>,
+}
@@ -0,0 +1,31 @@
+//! Wei Governance - types shared by the agent and indexer
+//!
+//! This crate holds the canonical identifiers, proposals, actors, votes,
+//! events, treasuries, token values and human labels of the governance domain, and the webhook delivery
+//! contract between the indexer and its subscribers. Both services use these types,
+//! so a change to the contract fails to compile on the side that was not
+//! updated instead of failing at runtime.
+#![deny(missing_docs)]
+
+pub mod actor;
+pub mod event;
+pub mod id;
+pub mod label;
+pub mod price;
+pub mod proposal;
+pub mod status;
+pub mod treasury;
+pub mod vote;
+pub mod webhook;
+
+// Re-export commonly used types
+pub use actor::Actor;
+pub use event::{EnvelopeError, EventEnvelope, IndexerEvent, EVENT_TYPES, EVENT_VERSION};
+pub use id::{AccountId, ChainId, IdError, ProposalId, ProtocolId};
+pub use label::{CriterionStatus, GroundTruth, LabelCriteria, LabelCriterion, LabelValue};
+pub use price::{PricedToken, TokenValue};
+pub use proposal::{Proposal, ProposalField, ProposalStatus};
+pub use status::{StatusChange, StatusTrigger};
+pub use treasury::{Treasury, TreasuryBalance, TreasuryTotal};
+pub use vote::{Vote, VoteChoice, VotingType};
+pub use webhook::{PingEvent, WebhookPayload};
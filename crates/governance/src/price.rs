@@ -0,0 +1,37 @@
+//! USD values of token amounts
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Token whose USD prices are tracked
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct PricedToken {
+    /// EVM chain ID the token lives on
+    pub chain_id: u64,
+    /// Token contract address, `None` for the chain's native currency
+    pub address: Option<String>,
+    /// Ticker symbol, e.g. `ARB`
+    pub symbol: String,
+    /// Decimals of the token's base unit
+    pub decimals: u8,
+}
+
+/// Amount of a token valued in USD at the daily price of a given day
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct TokenValue {
+    /// EVM chain ID the token lives on
+    pub chain_id: u64,
+    /// Token contract address, `None` for the chain's native currency
+    pub token: Option<String>,
+    /// Ticker symbol of the token
+    pub symbol: String,
+    /// Amount in whole tokens
+    pub amount: f64,
+    /// USD price of one token
+    pub price_usd: f64,
+    /// Day of the price, the latest known day on or before the valuation time
+    pub price_date: NaiveDate,
+    /// USD value of the amount
+    pub value_usd: f64,
+}
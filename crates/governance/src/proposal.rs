@@ -0,0 +1,233 @@
+//! Proposals and their status
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{
+    id::{ProposalId, ProtocolId},
+    vote::VotingType,
+};
+
+/// How long after voting closed the final scores may still revise an outcome
+pub const OUTCOME_REVISION_WINDOW: Duration = Duration::hours(24);
+
+/// Represents a DAO/Governance proposal
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Proposal {
+    /// Canonical identifier combining protocol, source and proposal ID
+    pub id: ProposalId,
+    /// Title of the proposal
+    pub title: String,
+    /// Description of the proposal
+    pub description: String,
+    /// Current status of the proposal
+    pub status: ProposalStatus,
+    /// Protocol/network identifier
+    pub protocol_id: ProtocolId,
+    /// Available choices for voting
+    pub choices: Vec<String>,
+    /// Author of the proposal
+    pub author: String,
+    /// Comments and discussions
+    pub comments: Vec<String>,
+    /// Creation timestamp
+    pub created_at: DateTime<Utc>,
+    /// Last update timestamp
+    pub updated_at: DateTime<Utc>,
+    /// Time voting opens
+    pub voting_start: Option<DateTime<Utc>>,
+    /// Time voting closes
+    pub voting_end: Option<DateTime<Utc>>,
+    /// Block at which voting power is measured
+    pub snapshot_block: Option<u64>,
+    /// Voting power required for the result to be valid
+    pub quorum: Option<f64>,
+    /// Voting system used to count votes
+    pub voting_type: VotingType,
+    /// Voting power cast for each choice, in the order of `choices`
+    pub scores: Vec<f64>,
+    /// Total voting power cast
+    pub scores_total: Option<f64>,
+    /// Link to the forum discussion
+    pub discussion_url: Option<String>,
+    /// IPFS hash of the proposal content
+    pub ipfs_hash: Option<String>,
+    /// Link to the proposal on its source
+    pub source_url: Option<String>,
+    /// Earliest proposal of the near-duplicate cluster this proposal belongs to
+    #[serde(default)]
+    pub is_duplicate_of: Option<ProposalId>,
+    /// Likelihood that the proposal is spam, from 0 to 1, once assessed
+    #[serde(default)]
+    pub spam_score: Option<f64>,
+}
+
+impl Proposal {
+    /// Result of the vote according to its scores and quorum
+    ///
+    /// Returns `None` if no votes were counted. Governor (`basic`) votes pass
+    /// when `For` beats `Against`, with `For` and `Abstain` counting towards
+    /// quorum; other voting types pass when the first choice has the most
+    /// voting power.
+    pub fn outcome(&self) -> Option<ProposalStatus> {
+        let max = self.scores.iter().copied().fold(f64::MIN, f64::max);
+        if self.scores.is_empty() || max <= 0.0 {
+            return None;
+        }
+
+        let score = |i: usize| self.scores.get(i).copied().unwrap_or_default();
+        let (passed, participation) = match self.voting_type {
+            VotingType::Basic => (score(0) > score(1), score(0) + score(2)),
+            _ => (
+                score(0) >= max,
+                self.scores_total
+                    .unwrap_or_else(|| self.scores.iter().sum()),
+            ),
+        };
+        let quorum_reached = self.quorum.is_none_or(|quorum| participation >= quorum);
+
+        Some(if passed && quorum_reached {
+            ProposalStatus::Accepted
+        } else {
+            ProposalStatus::Rejected
+        })
+    }
+
+    /// Status of the proposal at `now` derived from its voting window
+    ///
+    /// Final statuses reported by the source are kept. Pending and active
+    /// proposals move on as their voting window opens and closes, so a stale
+    /// stored status does not outlive the vote.
+    pub fn status_at(&self, now: DateTime<Utc>) -> ProposalStatus {
+        if !matches!(
+            self.status,
+            ProposalStatus::Pending | ProposalStatus::Active
+        ) {
+            return self.status;
+        }
+
+        match (self.voting_start, self.voting_end) {
+            (Some(start), _) if now < start => ProposalStatus::Pending,
+            (_, Some(end)) if now >= end => self.outcome().unwrap_or(ProposalStatus::Rejected),
+            (Some(_), _) => ProposalStatus::Active,
+            (None, _) => self.status,
+        }
+    }
+
+    /// Whether the proposal may move from its status to `next` at `now`
+    ///
+    /// Besides the forward transitions of [`ProposalStatus::can_transition_to`],
+    /// an accepted or rejected outcome may be revised by the final scores
+    /// until [`OUTCOME_REVISION_WINDOW`] after voting closed. After that the
+    /// outcome is settled.
+    pub fn can_transition_to(&self, next: ProposalStatus, now: DateTime<Utc>) -> bool {
+        use ProposalStatus::*;
+
+        if self.status.can_transition_to(next) {
+            return true;
+        }
+        matches!(
+            (self.status, next),
+            (Accepted, Rejected) | (Rejected, Accepted)
+        ) && self
+            .voting_end
+            .is_some_and(|end| now < end + OUTCOME_REVISION_WINDOW)
+    }
+
+    /// Next time the voting window changes the proposal's status
+    ///
+    /// Returns `None` once voting closed or if the window is unknown.
+    pub fn next_transition_at(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        if !matches!(
+            self.status,
+            ProposalStatus::Pending | ProposalStatus::Active
+        ) {
+            return None;
+        }
+
+        [self.voting_start, self.voting_end]
+            .into_iter()
+            .flatten()
+            .find(|time| *time > now)
+    }
+}
+
+/// Status of a proposal
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub enum ProposalStatus {
+    /// Proposal is currently active and open for voting
+    Active,
+    /// Proposal was accepted by voters
+    Accepted,
+    /// Proposal was rejected by voters
+    Rejected,
+    /// Proposal is pending and not yet active
+    #[default]
+    Pending,
+    /// Proposal was cancelled
+    Cancelled,
+    /// Proposal was executed on-chain
+    Executed,
+}
+
+impl ProposalStatus {
+    /// Whether a proposal may move from this status to `next`
+    ///
+    /// Statuses only move forward: a proposal never returns to voting once
+    /// it closed, an accepted proposal can only be cancelled or executed,
+    /// and cancelled, executed and rejected proposals stay so. Revising an
+    /// outcome depends on when voting closed, see
+    /// [`Proposal::can_transition_to`].
+    pub fn can_transition_to(&self, next: ProposalStatus) -> bool {
+        use ProposalStatus::*;
+
+        match (self, next) {
+            (from, to) if *from == to => true,
+            (Pending, _) => true,
+            (Active, Accepted | Rejected | Cancelled | Executed) => true,
+            (Accepted, Cancelled | Executed) => true,
+            _ => false,
+        }
+    }
+
+    /// Database representation of the status
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Active => "active",
+            Self::Accepted => "accepted",
+            Self::Rejected => "rejected",
+            Self::Pending => "pending",
+            Self::Cancelled => "cancelled",
+            Self::Executed => "executed",
+        }
+    }
+}
+
+impl std::str::FromStr for ProposalStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "active" => Ok(Self::Active),
+            "accepted" => Ok(Self::Accepted),
+            "rejected" => Ok(Self::Rejected),
+            "pending" => Ok(Self::Pending),
+            "cancelled" => Ok(Self::Cancelled),
+            "executed" => Ok(Self::Executed),
+            other => Err(format!("Unknown proposal status: {other}")),
+        }
+    }
+}
+
+/// Proposal content field tracked by revisions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ProposalField {
+    /// Proposal title
+    Title,
+    /// Proposal body
+    Description,
+    /// Voting choices
+    Choices,
+}
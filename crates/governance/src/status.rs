@@ -0,0 +1,54 @@
+//! Proposal status history
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{id::ProposalId, proposal::ProposalStatus};
+
+/// What caused a proposal status change
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StatusTrigger {
+    /// A data source reported the new status
+    Source,
+    /// The voting window opened or closed
+    Timer,
+}
+
+impl StatusTrigger {
+    /// Database representation of the trigger
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Source => "source",
+            Self::Timer => "timer",
+        }
+    }
+}
+
+impl std::str::FromStr for StatusTrigger {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "source" => Ok(Self::Source),
+            "timer" => Ok(Self::Timer),
+            other => Err(format!("Unknown status trigger: {other}")),
+        }
+    }
+}
+
+/// Entry of a proposal's status history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct StatusChange {
+    /// Canonical proposal ID
+    pub proposal_id: ProposalId,
+    /// Previous status, `None` when the proposal was first indexed
+    pub from: Option<ProposalStatus>,
+    /// New status
+    pub to: ProposalStatus,
+    /// What caused the change
+    pub triggered_by: StatusTrigger,
+    /// Time of the change
+    pub changed_at: DateTime<Utc>,
+}
@@ -0,0 +1,93 @@
+//! Treasury holdings of a protocol
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::id::ProtocolId;
+
+/// Balance of one token held at one treasury address
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct TreasuryBalance {
+    /// Treasury address holding the balance
+    pub address: String,
+    /// Token contract address, `None` for the chain's native currency
+    pub token: Option<String>,
+    /// Ticker symbol of the token
+    pub symbol: String,
+    /// Decimals of the token's base unit
+    pub decimals: u8,
+    /// Balance in base units, as a decimal string since it may exceed 64 bits
+    pub raw_balance: String,
+    /// Balance in whole tokens
+    pub balance: f64,
+    /// USD value of the balance, `None` if the token has no known price
+    #[serde(default)]
+    pub value_usd: Option<f64>,
+    /// Block the balance was read at
+    pub block_number: u64,
+    /// Time of the snapshot
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// Holdings of a token summed over all treasury addresses
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct TreasuryTotal {
+    /// Token contract address, `None` for the chain's native currency
+    pub token: Option<String>,
+    /// Ticker symbol of the token
+    pub symbol: String,
+    /// Balance in whole tokens
+    pub balance: f64,
+    /// USD value of the balance, `None` if the token has no known price
+    #[serde(default)]
+    pub value_usd: Option<f64>,
+    /// Time of the snapshot
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// Treasury of a protocol, with its latest balances and their history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Treasury {
+    /// Canonical protocol ID
+    pub protocol_id: ProtocolId,
+    /// Tracked treasury addresses
+    pub addresses: Vec<String>,
+    /// Time of the latest snapshot, `None` until the first one is taken
+    pub updated_at: Option<DateTime<Utc>>,
+    /// Balances of the latest snapshot
+    pub balances: Vec<TreasuryBalance>,
+    /// Totals per token of every snapshot in the requested period, oldest first
+    pub history: Vec<TreasuryTotal>,
+}
+
+impl Treasury {
+    /// Totals per token of the latest snapshot, in order of first appearance
+    pub fn totals(&self) -> Vec<TreasuryTotal> {
+        let mut totals: Vec<TreasuryTotal> = Vec::new();
+        for balance in &self.balances {
+            match totals.iter_mut().find(|total| total.token == balance.token) {
+                Some(total) => {
+                    total.balance += balance.balance;
+                    total.value_usd = total.value_usd.zip(balance.value_usd).map(|(a, b)| a + b);
+                }
+                None => totals.push(TreasuryTotal {
+                    token: balance.token.clone(),
+                    symbol: balance.symbol.clone(),
+                    balance: balance.balance,
+                    value_usd: balance.value_usd,
+                    recorded_at: balance.recorded_at,
+                }),
+            }
+        }
+        totals
+    }
+
+    /// USD value of the latest snapshot's tokens with a known price, `None` if none has one
+    pub fn value_usd(&self) -> Option<f64> {
+        self.balances
+            .iter()
+            .filter_map(|balance| balance.value_usd)
+            .reduce(|a, b| a + b)
+    }
+}
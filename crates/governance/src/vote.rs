@@ -0,0 +1,87 @@
+//! Votes and voting systems
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::id::{AccountId, ProposalId};
+
+/// Voting system of a proposal
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum VotingType {
+    /// One choice per voter
+    #[default]
+    SingleChoice,
+    /// Voters approve any number of choices
+    Approval,
+    /// Voting power is spread quadratically across choices
+    Quadratic,
+    /// Voters rank the choices
+    RankedChoice,
+    /// Voters split their voting power across choices
+    Weighted,
+    /// For, Against and Abstain, as used by Governor contracts
+    Basic,
+}
+
+impl VotingType {
+    /// Database representation of the voting type
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::SingleChoice => "single-choice",
+            Self::Approval => "approval",
+            Self::Quadratic => "quadratic",
+            Self::RankedChoice => "ranked-choice",
+            Self::Weighted => "weighted",
+            Self::Basic => "basic",
+        }
+    }
+}
+
+impl std::str::FromStr for VotingType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "single-choice" => Ok(Self::SingleChoice),
+            "approval" => Ok(Self::Approval),
+            "quadratic" => Ok(Self::Quadratic),
+            "ranked-choice" => Ok(Self::RankedChoice),
+            "weighted" => Ok(Self::Weighted),
+            "basic" => Ok(Self::Basic),
+            other => Err(format!("Unknown voting type: {other}")),
+        }
+    }
+}
+
+/// Vote cast on a proposal
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Vote {
+    /// Proposal voted on
+    pub proposal_id: ProposalId,
+    /// Account that cast the vote
+    pub voter: AccountId,
+    /// Choice of the voter
+    pub choice: VoteChoice,
+    /// Voting power of the voter
+    pub voting_power: f64,
+    /// Reason given by the voter
+    pub reason: Option<String>,
+    /// Time the vote was cast
+    pub cast_at: DateTime<Utc>,
+}
+
+/// Choice of a vote, as indices into the proposal's choices starting at 0
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(untagged)]
+pub enum VoteChoice {
+    /// One choice, for single-choice and basic votes
+    Single(usize),
+    /// Choices approved, or ranked from most to least preferred
+    Multiple(Vec<usize>),
+    /// Share of voting power given to each choice, for weighted and quadratic votes
+    Weighted(BTreeMap<usize, f64>),
+}
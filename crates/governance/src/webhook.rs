@@ -0,0 +1,98 @@
+//! Webhook delivery contract
+//!
+//! The indexer posts each event to subscribed webhooks as the JSON of its
+//! [`EventEnvelope`], or a [`PingEvent`] when a webhook is tested. The
+//! [`SIGNATURE_HEADER`] lists the hex HMAC-SHA256 of the body as
+//! `sha256=<signature>`, followed after a comma by the signature with the
+//! previous secret while a rotated secret is in its grace period.
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use utoipa::ToSchema;
+
+use crate::event::{EnvelopeError, EventEnvelope};
+
+/// Header carrying the signatures of a delivery
+pub const SIGNATURE_HEADER: &str = "x-wei-webhook-signature";
+
+/// Header carrying the event type of a delivery
+pub const EVENT_HEADER: &str = "x-wei-event";
+
+/// Header carrying the ID of the webhook a delivery is for
+pub const WEBHOOK_ID_HEADER: &str = "x-wei-webhook-id";
+
+/// Event type of test deliveries
+pub const PING_EVENT: &str = "ping";
+
+/// Prefix of each signature in the signature header
+const SIGNATURE_PREFIX: &str = "sha256=";
+
+/// Test delivery sent when a webhook is pinged
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename = "ping")]
+pub struct PingEvent {
+    /// Webhook being tested
+    pub webhook_id: String,
+    /// Time of the test
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Body of a webhook delivery
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum WebhookPayload {
+    /// A logged event
+    Event(EventEnvelope),
+    /// A test delivery
+    Ping(PingEvent),
+}
+
+impl WebhookPayload {
+    /// Read a delivery body, rejecting event versions this build does not know
+    pub fn from_json(json: &str) -> Result<Self, EnvelopeError> {
+        #[derive(Deserialize)]
+        struct Type {
+            #[serde(rename = "type")]
+            event_type: String,
+        }
+
+        let Type { event_type } = serde_json::from_str(json)?;
+        if event_type == PING_EVENT {
+            Ok(Self::Ping(serde_json::from_str(json)?))
+        } else {
+            Ok(Self::Event(EventEnvelope::from_json(json)?))
+        }
+    }
+}
+
+fn mac(secret: &str, body: &[u8]) -> Hmac<Sha256> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    mac
+}
+
+/// Value of the signature header of a body signed with `secrets`
+pub fn signature_header(secrets: &[&str], body: &str) -> String {
+    secrets
+        .iter()
+        .map(|secret| {
+            let signature = hex::encode(mac(secret, body.as_bytes()).finalize().into_bytes());
+            format!("{SIGNATURE_PREFIX}{signature}")
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Whether any signature of a signature header signs `body` with `secret`
+///
+/// Signatures are compared in constant time.
+pub fn verify_signature(header: &str, body: &[u8], secret: &str) -> bool {
+    header
+        .split(',')
+        .filter_map(|signature| signature.trim().strip_prefix(SIGNATURE_PREFIX))
+        .filter_map(|signature| hex::decode(signature).ok())
+        .any(|signature| mac(secret, body).verify_slice(&signature).is_ok())
+}
@@ -0,0 +1,88 @@
+//! Unit tests for event envelopes and the webhook contract
+
+use chrono::Utc;
+use governance::{
+    webhook::{signature_header, verify_signature, PING_EVENT},
+    ChainId, EnvelopeError, EventEnvelope, IndexerEvent, PingEvent, ProposalId, ProtocolId,
+    WebhookPayload, EVENT_VERSION,
+};
+use serde_json::json;
+use utoipa::PartialSchema;
+
+fn event() -> IndexerEvent {
+    let protocol_id = ProtocolId::new(ChainId::eip155(1), "uniswap").unwrap();
+    IndexerEvent::VotingStarted {
+        proposal_id: ProposalId::new(protocol_id, "tally", "42").unwrap(),
+        timestamp: Utc::now(),
+    }
+}
+
+#[test]
+fn test_envelope_versions() {
+    let envelope = EventEnvelope::new(5, event());
+    let body = serde_json::to_string(&envelope).unwrap();
+    assert_eq!(EventEnvelope::from_json(&body).unwrap(), envelope);
+
+    // Envelopes sent before versioning are version 1
+    let mut unversioned = serde_json::to_value(&envelope).unwrap();
+    unversioned.as_object_mut().unwrap().remove("version");
+    let parsed = EventEnvelope::from_json(&unversioned.to_string()).unwrap();
+    assert_eq!(parsed.version, 1);
+
+    let mut newer = serde_json::to_value(&envelope).unwrap();
+    newer["version"] = json!(EVENT_VERSION + 1);
+    assert!(matches!(
+        EventEnvelope::from_json(&newer.to_string()),
+        Err(EnvelopeError::UnsupportedVersion(v)) if v == EVENT_VERSION + 1
+    ));
+
+    let unknown = json!({"version": 1, "id": 6, "type": "proposal_deleted"});
+    assert!(matches!(
+        EventEnvelope::from_json(&unknown.to_string()),
+        Err(EnvelopeError::Invalid(_))
+    ));
+}
+
+#[test]
+fn test_payloads() {
+    let ping = WebhookPayload::Ping(PingEvent {
+        webhook_id: "wh_1".to_string(),
+        timestamp: Utc::now(),
+    });
+    let body = serde_json::to_string(&ping).unwrap();
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&body).unwrap()["type"],
+        PING_EVENT
+    );
+    assert_eq!(WebhookPayload::from_json(&body).unwrap(), ping);
+
+    let envelope = EventEnvelope::new(7, event());
+    let body = serde_json::to_string(&WebhookPayload::Event(envelope.clone())).unwrap();
+    assert_eq!(body, serde_json::to_string(&envelope).unwrap());
+    assert_eq!(
+        WebhookPayload::from_json(&body).unwrap(),
+        WebhookPayload::Event(envelope)
+    );
+}
+
+#[test]
+fn test_signatures() {
+    let body = r#"{"id":1}"#;
+    let header = signature_header(&["new-secret", "old-secret"], body);
+    assert!(verify_signature(&header, body.as_bytes(), "new-secret"));
+    assert!(verify_signature(&header, body.as_bytes(), "old-secret"));
+    assert!(!verify_signature(&header, body.as_bytes(), "other-secret"));
+    assert!(!verify_signature(&header, br#"{"id":2}"#, "new-secret"));
+    assert!(!verify_signature(
+        "sha256=zz",
+        body.as_bytes(),
+        "new-secret"
+    ));
+}
+
+#[test]
+fn test_schemas() {
+    let schema = serde_json::to_value(ProposalId::schema()).unwrap();
+    assert_eq!(schema["type"], "string");
+    assert!(serde_json::to_value(EventEnvelope::schema()).is_ok());
+}
@@ -0,0 +1,12 @@
+-- Track registry metadata for each protocol
+
+ALTER TABLE protocols ADD COLUMN IF NOT EXISTS slug VARCHAR(64) UNIQUE;
+ALTER TABLE protocols ADD COLUMN IF NOT EXISTS snapshot_spaces JSONB NOT NULL DEFAULT '[]';
+ALTER TABLE protocols ADD COLUMN IF NOT EXISTS tally_organization VARCHAR(255);
+ALTER TABLE protocols ADD COLUMN IF NOT EXISTS governor_addresses JSONB NOT NULL DEFAULT '[]';
+ALTER TABLE protocols ADD COLUMN IF NOT EXISTS timelock_addresses JSONB NOT NULL DEFAULT '[]';
+ALTER TABLE protocols ADD COLUMN IF NOT EXISTS forum_urls JSONB NOT NULL DEFAULT '[]';
+ALTER TABLE protocols ADD COLUMN IF NOT EXISTS polling_interval_secs BIGINT NOT NULL DEFAULT 300;
+ALTER TABLE protocols ADD COLUMN IF NOT EXISTS enabled BOOLEAN NOT NULL DEFAULT TRUE;
+
+CREATE INDEX IF NOT EXISTS idx_protocols_enabled ON protocols(enabled);
@@ -0,0 +1,70 @@
+-- Migrate proposal IDs to canonical CAIP-2 based identifiers
+--
+-- Legacy IDs were built as `<chain_id>:<name>:<protocol>:<proposal id>`, which
+-- cannot be split reliably when a name or proposal ID contains a colon. The
+-- prefix is rebuilt from the referenced protocol row instead, so the native
+-- proposal ID is recovered verbatim. New IDs have the form
+-- `eip155:<chain_id>:<protocol>:<source>:<native id>`.
+
+ALTER TABLE proposals ALTER COLUMN id TYPE TEXT;
+ALTER TABLE proposals ADD COLUMN IF NOT EXISTS source VARCHAR(32);
+ALTER TABLE proposals ADD COLUMN IF NOT EXISTS native_id TEXT;
+
+-- Give legacy protocols a slug so they can be part of canonical IDs
+UPDATE protocols
+SET slug = left(
+    coalesce(
+        nullif(trim(both '-._' from regexp_replace(lower(protocol), '[^a-z0-9._-]+', '-', 'g')), ''),
+        'legacy'
+    ),
+    50
+) || '-' || id
+WHERE slug IS NULL;
+
+UPDATE proposals p
+SET native_id = substr(p.id, length(pr.chain_id::text || ':' || pr.name || ':' || pr.protocol || ':') + 1)
+FROM protocols pr
+WHERE p.protocol_id = pr.id
+  AND p.native_id IS NULL
+  AND starts_with(p.id, pr.chain_id::text || ':' || pr.name || ':' || pr.protocol || ':');
+
+-- IDs that do not start with their protocol's legacy prefix are kept whole
+-- as the native ID, under the `legacy` source below
+UPDATE proposals p
+SET native_id = p.id
+FROM protocols pr
+WHERE p.protocol_id = pr.id
+  AND p.native_id IS NULL
+  AND p.id NOT LIKE 'eip155:%';
+
+-- Native IDs cannot be empty or contain whitespace or control characters
+UPDATE proposals
+SET native_id = coalesce(nullif(regexp_replace(native_id, '[[:space:][:cntrl:]]+', '_', 'g'), ''), 'empty')
+WHERE native_id IS NOT NULL
+  AND native_id !~ '^[^[:space:][:cntrl:]]+$';
+
+-- Snapshot IDs are 32-byte hashes and Governor IDs are integers; anything else
+-- is kept under a `legacy` source
+UPDATE proposals
+SET source = CASE
+    WHEN native_id ~ '^0x[0-9a-fA-F]{64}$' THEN 'snapshot'
+    WHEN native_id ~ '^[0-9]+$' THEN 'tally'
+    ELSE 'legacy'
+END
+WHERE native_id IS NOT NULL AND source IS NULL;
+
+UPDATE proposals p
+SET id = 'eip155:' || pr.chain_id || ':' || pr.slug || ':' || p.source || ':' || p.native_id,
+    updated_at = NOW()
+FROM protocols pr
+WHERE p.protocol_id = pr.id
+  AND p.native_id IS NOT NULL
+  AND p.id NOT LIKE 'eip155:%';
+
+-- Proposals without a protocol cannot get a canonical ID, so they are moved
+-- to `legacy_proposals` for manual review instead of failing every read
+CREATE TABLE IF NOT EXISTS legacy_proposals (LIKE proposals INCLUDING DEFAULTS);
+INSERT INTO legacy_proposals SELECT * FROM proposals WHERE protocol_id IS NULL;
+DELETE FROM proposals WHERE protocol_id IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_proposals_source_native_id ON proposals(source, native_id);
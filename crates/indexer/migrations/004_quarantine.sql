@@ -0,0 +1,30 @@
+-- Quarantine for ingested records that failed validation or normalization
+--
+-- The raw payload is kept verbatim so that a record can be fixed and
+-- re-ingested. `errors` holds the structured validation errors
+-- (`[{field, rule, message}]`) and `rule` the first failing rule for filtering.
+
+CREATE TABLE IF NOT EXISTS quarantined_records (
+    id BIGSERIAL PRIMARY KEY,
+    source VARCHAR(32) NOT NULL,
+    protocol_id VARCHAR(128) NOT NULL,
+    native_id TEXT,
+    payload JSONB NOT NULL,
+    errors JSONB NOT NULL,
+    rule VARCHAR(64) NOT NULL,
+    status VARCHAR(16) NOT NULL DEFAULT 'pending',
+    attempts INTEGER NOT NULL DEFAULT 1,
+    proposal_id TEXT,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    resolved_at TIMESTAMP WITH TIME ZONE
+);
+
+-- A record crawled again while still quarantined updates the existing row.
+-- Records without a native ID share one pending row per source and protocol,
+-- since NULLs would never conflict.
+CREATE UNIQUE INDEX IF NOT EXISTS idx_quarantined_records_pending
+    ON quarantined_records(source, protocol_id, COALESCE(native_id, ''))
+    WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_quarantined_records_status ON quarantined_records(status);
+CREATE INDEX IF NOT EXISTS idx_quarantined_records_rule ON quarantined_records(rule);
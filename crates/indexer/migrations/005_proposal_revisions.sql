@@ -0,0 +1,21 @@
+-- Revision history of proposal content
+--
+-- A revision is recorded whenever the title, description or choices of a
+-- proposal change. `content_hash` is the SHA-256 of the revision content,
+-- `edited_at` the source's last update time and `created_at` the time the
+-- indexer observed the revision.
+
+CREATE TABLE IF NOT EXISTS proposal_revisions (
+    id BIGSERIAL PRIMARY KEY,
+    proposal_id TEXT NOT NULL REFERENCES proposals(id) ON DELETE CASCADE,
+    revision INTEGER NOT NULL,
+    title TEXT NOT NULL,
+    description TEXT NOT NULL,
+    choices JSONB NOT NULL,
+    content_hash CHAR(64) NOT NULL,
+    edited_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    UNIQUE(proposal_id, revision)
+);
+
+CREATE INDEX IF NOT EXISTS idx_proposal_revisions_content_hash ON proposal_revisions(content_hash);
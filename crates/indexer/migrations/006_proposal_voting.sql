@@ -0,0 +1,14 @@
+-- Voting window, results and source links of proposals
+
+ALTER TABLE proposals ADD COLUMN IF NOT EXISTS voting_start TIMESTAMP WITH TIME ZONE;
+ALTER TABLE proposals ADD COLUMN IF NOT EXISTS voting_end TIMESTAMP WITH TIME ZONE;
+ALTER TABLE proposals ADD COLUMN IF NOT EXISTS snapshot_block BIGINT;
+ALTER TABLE proposals ADD COLUMN IF NOT EXISTS quorum DOUBLE PRECISION;
+ALTER TABLE proposals ADD COLUMN IF NOT EXISTS voting_type VARCHAR(32) NOT NULL DEFAULT 'single-choice';
+ALTER TABLE proposals ADD COLUMN IF NOT EXISTS scores JSONB;
+ALTER TABLE proposals ADD COLUMN IF NOT EXISTS scores_total DOUBLE PRECISION;
+ALTER TABLE proposals ADD COLUMN IF NOT EXISTS discussion_url TEXT;
+ALTER TABLE proposals ADD COLUMN IF NOT EXISTS ipfs_hash VARCHAR(255);
+ALTER TABLE proposals ADD COLUMN IF NOT EXISTS source_url TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_proposals_voting_end ON proposals(voting_end);
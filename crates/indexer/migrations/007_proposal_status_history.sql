@@ -0,0 +1,17 @@
+-- Status history of proposals
+--
+-- A row is recorded whenever the status of a proposal changes, either
+-- because a data source reported it (`source`) or because its voting
+-- window opened or closed (`timer`).
+
+CREATE TABLE IF NOT EXISTS proposal_status_history (
+    id BIGSERIAL PRIMARY KEY,
+    proposal_id TEXT NOT NULL REFERENCES proposals(id) ON DELETE CASCADE,
+    from_status VARCHAR(50),
+    to_status VARCHAR(50) NOT NULL,
+    triggered_by VARCHAR(32) NOT NULL,
+    changed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_proposal_status_history_proposal_id
+    ON proposal_status_history(proposal_id, changed_at);
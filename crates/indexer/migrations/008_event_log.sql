@@ -0,0 +1,16 @@
+-- Persistent log of indexer events
+--
+-- Every event published by the indexer is appended here. The `id` orders
+-- the log and lets stream clients resume after the last event they saw.
+
+CREATE TABLE IF NOT EXISTS events (
+    id BIGSERIAL PRIMARY KEY,
+    event_type VARCHAR(50) NOT NULL,
+    protocol_id TEXT NOT NULL,
+    proposal_id TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_events_protocol_id ON events(protocol_id, id);
+CREATE INDEX IF NOT EXISTS idx_events_event_type ON events(event_type, id);
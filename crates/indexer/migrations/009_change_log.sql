@@ -0,0 +1,16 @@
+-- Change log of indexed entities
+--
+-- Every write to a proposal or protocol appends an entry with a global,
+-- increasing sequence number. Deletions are recorded as tombstones with
+-- `operation = 'delete'` and no data.
+
+CREATE TABLE IF NOT EXISTS change_log (
+    seq BIGSERIAL PRIMARY KEY,
+    entity_type VARCHAR(32) NOT NULL,
+    entity_id TEXT NOT NULL,
+    operation VARCHAR(16) NOT NULL CHECK (operation IN ('upsert', 'delete')),
+    data JSONB,
+    changed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_change_log_entity ON change_log(entity_type, seq);
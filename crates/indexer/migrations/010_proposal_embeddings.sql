@@ -0,0 +1,10 @@
+-- Proposal embeddings for semantic similarity search
+--
+-- Proposal text is split into chunks and every chunk is embedded separately.
+-- `content_hash` is the content hash of the proposal revision that was
+-- embedded, so stale embeddings can be found and refreshed.
+--
+-- The `proposal_embeddings` table requires the pgvector extension, so it is
+-- not created here: the indexer creates it at startup whenever pgvector is
+-- available (see `EmbeddingRepository::ensure_table`). Installing pgvector
+-- later enables similarity search on the next start.
@@ -0,0 +1,30 @@
+-- Near-duplicate and spam detection
+--
+-- `is_duplicate_of` points at the earliest proposal of a cluster of
+-- near-duplicates and `spam_score` is the spam likelihood from 0 to 1.
+-- Fingerprints are cut into band keys; proposals sharing a key are
+-- candidate near-duplicates.
+
+ALTER TABLE proposals
+    ADD COLUMN IF NOT EXISTS is_duplicate_of TEXT REFERENCES proposals(id) ON DELETE SET NULL,
+    ADD COLUMN IF NOT EXISTS spam_score DOUBLE PRECISION;
+
+CREATE INDEX IF NOT EXISTS idx_proposals_is_duplicate_of ON proposals(is_duplicate_of);
+
+CREATE TABLE IF NOT EXISTS proposal_fingerprints (
+    proposal_id TEXT PRIMARY KEY REFERENCES proposals(id) ON DELETE CASCADE,
+    simhash BIGINT,
+    minhash BIGINT[],
+    space_verified BOOLEAN,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS proposal_fingerprint_bands (
+    proposal_id TEXT NOT NULL REFERENCES proposals(id) ON DELETE CASCADE,
+    band SMALLINT NOT NULL,
+    key BIGINT NOT NULL,
+    PRIMARY KEY (proposal_id, band)
+);
+
+CREATE INDEX IF NOT EXISTS idx_proposal_fingerprint_bands_key
+    ON proposal_fingerprint_bands(band, key);
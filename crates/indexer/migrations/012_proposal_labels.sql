@@ -0,0 +1,14 @@
+-- Human labels of proposal quality
+--
+-- Every labeler gives at most one verdict per proposal. Labels are exported
+-- with training datasets.
+
+CREATE TABLE IF NOT EXISTS proposal_labels (
+    id BIGSERIAL PRIMARY KEY,
+    proposal_id TEXT NOT NULL REFERENCES proposals(id) ON DELETE CASCADE,
+    labeler VARCHAR(255) NOT NULL,
+    label VARCHAR(16) NOT NULL CHECK (label IN ('good', 'bad')),
+    rationale TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    UNIQUE (proposal_id, labeler)
+);
@@ -0,0 +1,36 @@
+-- Per-criterion labels and conflict resolutions
+--
+-- Labelers judge proposals on the five evaluation categories of the agent.
+-- Labels given before criteria existed count as not applicable. When
+-- labelers disagree, a resolution records the final verdict.
+
+ALTER TABLE proposal_labels
+    ADD COLUMN IF NOT EXISTS goals_and_motivation VARCHAR(8) NOT NULL DEFAULT 'n/a'
+        CHECK (goals_and_motivation IN ('pass', 'fail', 'n/a')),
+    ADD COLUMN IF NOT EXISTS measurable_outcomes VARCHAR(8) NOT NULL DEFAULT 'n/a'
+        CHECK (measurable_outcomes IN ('pass', 'fail', 'n/a')),
+    ADD COLUMN IF NOT EXISTS budget VARCHAR(8) NOT NULL DEFAULT 'n/a'
+        CHECK (budget IN ('pass', 'fail', 'n/a')),
+    ADD COLUMN IF NOT EXISTS technical_specifications VARCHAR(8) NOT NULL DEFAULT 'n/a'
+        CHECK (technical_specifications IN ('pass', 'fail', 'n/a')),
+    ADD COLUMN IF NOT EXISTS language_quality VARCHAR(8) NOT NULL DEFAULT 'n/a'
+        CHECK (language_quality IN ('pass', 'fail', 'n/a'));
+
+CREATE INDEX IF NOT EXISTS idx_proposal_labels_labeler ON proposal_labels(labeler);
+
+CREATE TABLE IF NOT EXISTS proposal_label_resolutions (
+    proposal_id TEXT PRIMARY KEY REFERENCES proposals(id) ON DELETE CASCADE,
+    label VARCHAR(16) NOT NULL CHECK (label IN ('good', 'bad')),
+    goals_and_motivation VARCHAR(8) NOT NULL
+        CHECK (goals_and_motivation IN ('pass', 'fail', 'n/a')),
+    measurable_outcomes VARCHAR(8) NOT NULL
+        CHECK (measurable_outcomes IN ('pass', 'fail', 'n/a')),
+    budget VARCHAR(8) NOT NULL CHECK (budget IN ('pass', 'fail', 'n/a')),
+    technical_specifications VARCHAR(8) NOT NULL
+        CHECK (technical_specifications IN ('pass', 'fail', 'n/a')),
+    language_quality VARCHAR(8) NOT NULL
+        CHECK (language_quality IN ('pass', 'fail', 'n/a')),
+    resolved_by VARCHAR(255) NOT NULL,
+    rationale TEXT,
+    resolved_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
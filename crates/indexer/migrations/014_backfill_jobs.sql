@@ -0,0 +1,26 @@
+-- Resumable backfill jobs
+--
+-- A job crawls one Snapshot space or Tally organization from a start date.
+-- The cursor of the next page and the progress counters are updated in the
+-- same transaction that holds the job row lock, so concurrent runs of a job
+-- take turns page by page and an interrupted run resumes where it stopped.
+
+CREATE TABLE IF NOT EXISTS backfill_jobs (
+    id BIGSERIAL PRIMARY KEY,
+    source VARCHAR(32) NOT NULL,
+    target VARCHAR(255) NOT NULL,
+    protocol_id VARCHAR(128) NOT NULL,
+    since TIMESTAMP WITH TIME ZONE NOT NULL,
+    cursor TEXT,
+    status VARCHAR(16) NOT NULL DEFAULT 'running'
+        CHECK (status IN ('running', 'completed', 'failed')),
+    pages BIGINT NOT NULL DEFAULT 0,
+    fetched BIGINT NOT NULL DEFAULT 0,
+    stored BIGINT NOT NULL DEFAULT 0,
+    quarantined BIGINT NOT NULL DEFAULT 0,
+    error TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMP WITH TIME ZONE,
+    UNIQUE (source, target, since)
+);
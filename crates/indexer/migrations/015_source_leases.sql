@@ -0,0 +1,15 @@
+-- Leases of data sources between indexer replicas
+--
+-- A replica crawls a data source only while it holds the source's lease.
+-- Leases are renewed well before they expire; the lease of a replica that
+-- stops renewing expires and is taken over by another replica.
+
+CREATE TABLE IF NOT EXISTS source_leases (
+    key VARCHAR(300) PRIMARY KEY,
+    holder VARCHAR(255) NOT NULL,
+    acquired_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    renewed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_source_leases_holder ON source_leases(holder);
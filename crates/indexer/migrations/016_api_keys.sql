@@ -0,0 +1,19 @@
+-- API keys of the indexer API
+--
+-- Keys are shown once when created; only the SHA-256 hash of their secret
+-- part is stored. The public prefix identifies a key without revealing it.
+-- Keys used for HMAC request signing also store their signing secret, which
+-- the server needs to verify signatures.
+
+CREATE TABLE IF NOT EXISTS api_keys (
+    id BIGSERIAL PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    prefix VARCHAR(16) NOT NULL UNIQUE,
+    key_hash CHAR(64) NOT NULL,
+    scopes TEXT[] NOT NULL,
+    rate_limit INTEGER CHECK (rate_limit IS NULL OR rate_limit > 0),
+    signing_secret TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    last_used_at TIMESTAMP WITH TIME ZONE,
+    revoked_at TIMESTAMP WITH TIME ZONE
+);
@@ -0,0 +1,38 @@
+-- Webhook subscriptions with filters, secret rotation and delivery log
+--
+-- Hooks only receive events of the listed types, protocols and proposal
+-- statuses; empty lists match everything. After a secret rotation the
+-- previous secret keeps signing deliveries until its grace period ends.
+-- Hooks failing `consecutive_failures` deliveries in a row are disabled.
+
+ALTER TABLE webhook_registrations
+    ADD COLUMN IF NOT EXISTS protocols TEXT[] NOT NULL DEFAULT '{}',
+    ADD COLUMN IF NOT EXISTS statuses TEXT[] NOT NULL DEFAULT '{}',
+    ADD COLUMN IF NOT EXISTS previous_secret VARCHAR(255),
+    ADD COLUMN IF NOT EXISTS previous_secret_expires_at TIMESTAMP WITH TIME ZONE,
+    ADD COLUMN IF NOT EXISTS enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    ADD COLUMN IF NOT EXISTS disabled_reason TEXT,
+    ADD COLUMN IF NOT EXISTS consecutive_failures INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS last_delivery_at TIMESTAMP WITH TIME ZONE;
+
+UPDATE webhook_registrations SET created_at = NOW() WHERE created_at IS NULL;
+UPDATE webhook_registrations SET updated_at = NOW() WHERE updated_at IS NULL;
+ALTER TABLE webhook_registrations
+    ALTER COLUMN created_at SET NOT NULL,
+    ALTER COLUMN updated_at SET NOT NULL;
+
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+    id BIGSERIAL PRIMARY KEY,
+    webhook_id VARCHAR(255) NOT NULL REFERENCES webhook_registrations(id) ON DELETE CASCADE,
+    event_id BIGINT,
+    event_type VARCHAR(50) NOT NULL,
+    attempts INTEGER NOT NULL,
+    status_code INTEGER,
+    error TEXT,
+    success BOOLEAN NOT NULL,
+    duration_ms BIGINT NOT NULL,
+    delivered_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_webhook_id
+    ON webhook_deliveries(webhook_id, id DESC);
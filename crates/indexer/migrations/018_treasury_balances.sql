@@ -0,0 +1,26 @@
+-- Treasury balances of the tracked protocols
+--
+-- The registry lists the treasury addresses of a protocol and the ERC-20
+-- tokens to track in them. Every snapshot records the balance of each
+-- address and token, read at the same block, under one `recorded_at`.
+-- `token` is NULL for the chain's native currency. Raw balances are kept
+-- exactly; `balance` is scaled by the token decimals for aggregation.
+
+ALTER TABLE protocols ADD COLUMN IF NOT EXISTS treasury_addresses JSONB NOT NULL DEFAULT '[]';
+ALTER TABLE protocols ADD COLUMN IF NOT EXISTS treasury_tokens JSONB NOT NULL DEFAULT '[]';
+
+CREATE TABLE IF NOT EXISTS treasury_balances (
+    id BIGSERIAL PRIMARY KEY,
+    protocol VARCHAR(64) NOT NULL REFERENCES protocols(slug) ON DELETE CASCADE,
+    address VARCHAR(42) NOT NULL,
+    token VARCHAR(42),
+    symbol VARCHAR(32) NOT NULL,
+    decimals SMALLINT NOT NULL,
+    raw_balance NUMERIC(78, 0) NOT NULL,
+    balance DOUBLE PRECISION NOT NULL,
+    block_number BIGINT NOT NULL,
+    recorded_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_treasury_balances_protocol
+    ON treasury_balances(protocol, recorded_at DESC);
@@ -0,0 +1,15 @@
+-- Daily USD prices of tracked tokens
+--
+-- Prices are kept per provider asset (`price_id` in the registry), so a
+-- token and its bridged copies on other chains share one series. A day's
+-- price is imported from CSV or fetched from the configured provider, and a
+-- later import or fetch of the same day replaces it.
+
+CREATE TABLE IF NOT EXISTS token_prices (
+    asset VARCHAR(128) NOT NULL,
+    day DATE NOT NULL,
+    price_usd DOUBLE PRECISION NOT NULL,
+    source VARCHAR(32) NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (asset, day)
+);
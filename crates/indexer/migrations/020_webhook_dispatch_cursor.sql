@@ -0,0 +1,11 @@
+-- Position of the webhook dispatcher in the event log
+--
+-- The single row holds the ID of the last event whose deliveries all
+-- finished, so a dispatcher taking over after a restart or failover resumes
+-- right after it instead of skipping the events logged in between.
+
+CREATE TABLE IF NOT EXISTS webhook_dispatch_cursor (
+    id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    last_event_id BIGINT NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
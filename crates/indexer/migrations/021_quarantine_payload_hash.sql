@@ -0,0 +1,16 @@
+-- Keep quarantined records without a native ID apart
+--
+-- Records without a native ID used to share one pending row per source and
+-- protocol, so quarantining one overwrote the payload of another. They are
+-- now told apart by the hash of the payload they were first quarantined
+-- with, which fixes do not change, so a record crawled again still updates
+-- its own row.
+
+ALTER TABLE quarantined_records ADD COLUMN IF NOT EXISTS payload_hash TEXT;
+UPDATE quarantined_records SET payload_hash = md5(payload::text) WHERE payload_hash IS NULL;
+ALTER TABLE quarantined_records ALTER COLUMN payload_hash SET NOT NULL;
+
+DROP INDEX IF EXISTS idx_quarantined_records_pending;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_quarantined_records_pending
+    ON quarantined_records(source, protocol_id, COALESCE(native_id, payload_hash))
+    WHERE status = 'pending';
@@ -0,0 +1,15 @@
+-- ERC-20 transfers executed by proposals
+--
+-- Decoded from the `transfer(address,uint256)` calls among the executable
+-- calls of onchain proposals. Amounts are kept exactly in the token's base
+-- unit; they are scaled and valued in USD when read, using the registry
+-- token and the price of the day the proposal was created.
+
+CREATE TABLE IF NOT EXISTS proposal_transfers (
+    proposal_id TEXT NOT NULL REFERENCES proposals(id) ON DELETE CASCADE,
+    call_index INTEGER NOT NULL,
+    token VARCHAR(42) NOT NULL,
+    recipient VARCHAR(42) NOT NULL,
+    raw_amount NUMERIC(78, 0) NOT NULL,
+    PRIMARY KEY (proposal_id, call_index)
+);
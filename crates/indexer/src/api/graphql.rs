@@ -0,0 +1,607 @@
+//! Snapshot-compatible GraphQL API
+//!
+//! Implements the subset of the Snapshot hub schema used by the UI and common
+//! Snapshot tooling (`proposals`, `proposal`, `spaces` and `space`) on top of
+//! the indexer database, so that clients can point at the indexer instead of
+//! `hub.snapshot.org`. Every protocol of the registry is exposed as a space,
+//! and its proposals from all sources are listed under it. Fields that
+//! Snapshot does not have, such as the lifecycle `status` or the `source` of a
+//! proposal, are added alongside the Snapshot ones.
+
+use std::sync::Arc;
+
+use async_graphql::{
+    http::GraphiQLSource, Context, EmptyMutation, EmptySubscription, Enum, InputObject, Object,
+    Schema,
+};
+use axum::{extract::State, response::Html, Json};
+use chrono::{DateTime, TimeZone, Utc};
+use tokio::sync::watch;
+use tracing::error;
+
+use crate::{
+    config::{ProtocolEntry, ProtocolRegistry},
+    db::{
+        repositories::{
+            proposal::{ProposalOrder, ProposalQuery},
+            ProposalRepository,
+        },
+        Database,
+    },
+    models::{proposal::ProposalStatus, Proposal},
+};
+
+/// Maximum number of items returned by a list query
+pub const MAX_FIRST: i32 = 1000;
+
+/// Maximum number of items skipped by a list query
+pub const MAX_SKIP: i32 = 5000;
+
+/// GraphQL schema served at `/graphql`
+pub type GraphqlSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+/// Build the GraphQL schema
+///
+/// Spaces are read from the registry channel, so reloads are picked up
+/// without rebuilding the schema.
+pub fn build_schema(
+    db: Database,
+    registry: watch::Receiver<Arc<ProtocolRegistry>>,
+) -> GraphqlSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .data(db)
+        .data(registry)
+        .limit_depth(8)
+        .finish()
+}
+
+/// Execute a GraphQL request
+pub async fn graphql_handler(
+    State(schema): State<GraphqlSchema>,
+    Json(request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    Json(schema.execute(request).await)
+}
+
+/// Serve the GraphiQL IDE
+pub async fn graphiql() -> Html<String> {
+    Html(GraphiQLSource::build().endpoint("/graphql").finish())
+}
+
+/// Sort direction of a list query
+#[derive(Enum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[graphql(rename_items = "lowercase")]
+pub enum OrderDirection {
+    /// Ascending order
+    Asc,
+    /// Descending order
+    #[default]
+    Desc,
+}
+
+/// Filter of the `proposals` query
+#[derive(InputObject, Debug, Clone, Default)]
+#[graphql(name = "ProposalWhere", rename_fields = "snake_case")]
+pub struct ProposalWhere {
+    /// Snapshot proposal ID or canonical ID
+    pub id: Option<String>,
+    /// Any of these Snapshot proposal IDs or canonical IDs
+    pub id_in: Option<Vec<String>>,
+    /// Space ID
+    pub space: Option<String>,
+    /// Any of these space IDs
+    pub space_in: Option<Vec<String>>,
+    /// Author address
+    pub author: Option<String>,
+    /// Any of these author addresses
+    pub author_in: Option<Vec<String>>,
+    /// Snapshot state: `pending`, `active` or `closed`
+    pub state: Option<String>,
+    /// Text contained in the title
+    pub title_contains: Option<String>,
+    /// Created after this Unix timestamp
+    pub created_gt: Option<i64>,
+    /// Created at or after this Unix timestamp
+    pub created_gte: Option<i64>,
+    /// Created before this Unix timestamp
+    pub created_lt: Option<i64>,
+    /// Created at or before this Unix timestamp
+    pub created_lte: Option<i64>,
+    /// Whether the proposal is a near-duplicate of another proposal
+    pub is_duplicate: Option<bool>,
+    /// Spam score at most this value, counting unassessed proposals as 0
+    pub spam_score_lte: Option<f64>,
+}
+
+/// Filter of the `spaces` query
+#[derive(InputObject, Debug, Clone, Default)]
+#[graphql(name = "SpaceWhere", rename_fields = "snake_case")]
+pub struct SpaceWhere {
+    /// Space ID
+    pub id: Option<String>,
+    /// Any of these space IDs
+    pub id_in: Option<Vec<String>>,
+    /// Whether the space is verified
+    pub verified: Option<bool>,
+}
+
+/// Root of the GraphQL queries
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// List proposals, newest first by default
+    async fn proposals(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = 20)] first: i32,
+        #[graphql(default = 0)] skip: i32,
+        r#where: Option<ProposalWhere>,
+        #[graphql(name = "orderBy", default_with = "String::from(\"created\")")] order_by: String,
+        #[graphql(name = "orderDirection", default)] order_direction: OrderDirection,
+    ) -> async_graphql::Result<Vec<ProposalNode>> {
+        let registry = ctx
+            .data::<watch::Receiver<Arc<ProtocolRegistry>>>()?
+            .borrow()
+            .clone();
+        let (offset, limit) = page(first, skip)?;
+        let Some(mut query) = proposal_query(&r#where.unwrap_or_default(), &registry)? else {
+            return Ok(Vec::new());
+        };
+        query.order = parse_order(&order_by)?;
+        query.ascending = order_direction == OrderDirection::Asc;
+        query.offset = offset;
+        query.limit = limit;
+
+        let proposals = ProposalRepository::new(ctx.data::<Database>()?.clone())
+            .list(&query)
+            .await
+            .map_err(internal_error)?;
+
+        Ok(proposals
+            .into_iter()
+            .map(|proposal| ProposalNode::new(proposal, &registry))
+            .collect())
+    }
+
+    /// Get a proposal by its Snapshot proposal ID or canonical ID
+    async fn proposal(
+        &self,
+        ctx: &Context<'_>,
+        id: String,
+    ) -> async_graphql::Result<Option<ProposalNode>> {
+        let registry = ctx
+            .data::<watch::Receiver<Arc<ProtocolRegistry>>>()?
+            .borrow()
+            .clone();
+        let query = ProposalQuery {
+            ids: Some(vec![id]),
+            limit: 1,
+            ..Default::default()
+        };
+
+        let proposals = ProposalRepository::new(ctx.data::<Database>()?.clone())
+            .list(&query)
+            .await
+            .map_err(internal_error)?;
+
+        Ok(proposals
+            .into_iter()
+            .next()
+            .map(|proposal| ProposalNode::new(proposal, &registry)))
+    }
+
+    /// List the spaces of the tracked protocols, in registry order
+    ///
+    /// `orderBy` and `orderDirection` are accepted for compatibility and ignored.
+    async fn spaces(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = 20)] first: i32,
+        #[graphql(default = 0)] skip: i32,
+        r#where: Option<SpaceWhere>,
+        #[graphql(name = "orderBy", default_with = "String::from(\"created\")")] _order_by: String,
+        #[graphql(name = "orderDirection", default)] _order_direction: OrderDirection,
+    ) -> async_graphql::Result<Vec<SpaceNode>> {
+        let registry = ctx
+            .data::<watch::Receiver<Arc<ProtocolRegistry>>>()?
+            .borrow()
+            .clone();
+        let (offset, limit) = page(first, skip)?;
+        let filter = r#where.unwrap_or_default();
+
+        Ok(registry
+            .enabled()
+            .filter(|entry| space_matches(entry, &filter))
+            .skip(offset as usize)
+            .take(limit as usize)
+            .map(SpaceNode::new)
+            .collect())
+    }
+
+    /// Get a space by its ID
+    async fn space(
+        &self,
+        ctx: &Context<'_>,
+        id: String,
+    ) -> async_graphql::Result<Option<SpaceNode>> {
+        let registry = ctx
+            .data::<watch::Receiver<Arc<ProtocolRegistry>>>()?
+            .borrow()
+            .clone();
+
+        Ok(find_space(&registry, &id).map(SpaceNode::new))
+    }
+}
+
+/// A tracked protocol, exposed as a Snapshot space
+#[derive(Debug, Clone)]
+pub struct SpaceNode {
+    entry: ProtocolEntry,
+}
+
+impl SpaceNode {
+    fn new(entry: &ProtocolEntry) -> Self {
+        Self {
+            entry: entry.clone(),
+        }
+    }
+}
+
+#[Object(name = "Space", rename_fields = "snake_case")]
+impl SpaceNode {
+    /// First Snapshot space of the protocol, or its registry ID if it has none
+    async fn id(&self) -> &str {
+        space_id(&self.entry)
+    }
+
+    /// Protocol name
+    async fn name(&self) -> &str {
+        &self.entry.name
+    }
+
+    /// Description of the space, not tracked by the indexer
+    async fn about(&self) -> Option<&str> {
+        None
+    }
+
+    /// Chain ID of the protocol
+    async fn network(&self) -> String {
+        self.entry.chain_id.to_string()
+    }
+
+    /// Avatar of the space, not tracked by the indexer
+    async fn avatar(&self) -> Option<&str> {
+        None
+    }
+
+    /// Always `true`, since every protocol of the registry is curated
+    async fn verified(&self) -> bool {
+        true
+    }
+
+    /// Custom domain of the space, not tracked by the indexer
+    async fn domain(&self) -> Option<&str> {
+        None
+    }
+
+    /// Number of members, not tracked by the indexer
+    async fn members(&self) -> i32 {
+        0
+    }
+
+    /// Canonical protocol ID
+    async fn protocol(&self) -> String {
+        self.entry.protocol_id().to_string()
+    }
+
+    /// All Snapshot spaces of the protocol
+    async fn snapshot_spaces(&self) -> &[String] {
+        &self.entry.snapshot_spaces
+    }
+
+    /// Tally organization slug
+    async fn tally_organization(&self) -> Option<&str> {
+        self.entry.tally_organization.as_deref()
+    }
+
+    /// Governance forum URLs
+    async fn forums(&self) -> &[String] {
+        &self.entry.forum_urls
+    }
+}
+
+/// An indexed proposal, exposed as a Snapshot proposal
+#[derive(Debug, Clone)]
+pub struct ProposalNode {
+    proposal: Proposal,
+    space: Option<SpaceNode>,
+}
+
+impl ProposalNode {
+    fn new(proposal: Proposal, registry: &ProtocolRegistry) -> Self {
+        let space = registry
+            .protocols
+            .iter()
+            .find(|entry| entry.protocol_id() == proposal.protocol_id)
+            .map(SpaceNode::new);
+
+        Self { proposal, space }
+    }
+}
+
+#[Object(name = "Proposal", rename_fields = "snake_case")]
+impl ProposalNode {
+    /// Snapshot proposal ID for Snapshot proposals, canonical ID otherwise
+    async fn id(&self) -> String {
+        match self.proposal.id.source() {
+            "snapshot" => self.proposal.id.native_id().to_string(),
+            _ => self.proposal.id.to_string(),
+        }
+    }
+
+    /// IPFS hash of the proposal content
+    async fn ipfs(&self) -> Option<&str> {
+        self.proposal.ipfs_hash.as_deref()
+    }
+
+    /// Author address
+    async fn author(&self) -> &str {
+        &self.proposal.author
+    }
+
+    /// Creation time, as a Unix timestamp
+    async fn created(&self) -> i64 {
+        self.proposal.created_at.timestamp()
+    }
+
+    /// Last update time, as a Unix timestamp
+    async fn updated(&self) -> i64 {
+        self.proposal.updated_at.timestamp()
+    }
+
+    /// Space of the proposal's protocol
+    async fn space(&self) -> Option<&SpaceNode> {
+        self.space.as_ref()
+    }
+
+    /// Voting type
+    async fn r#type(&self) -> &str {
+        self.proposal.voting_type.as_str()
+    }
+
+    /// Title
+    async fn title(&self) -> &str {
+        &self.proposal.title
+    }
+
+    /// Description
+    async fn body(&self) -> &str {
+        &self.proposal.description
+    }
+
+    /// Voting choices
+    async fn choices(&self) -> &[String] {
+        &self.proposal.choices
+    }
+
+    /// Start of the voting window, as a Unix timestamp
+    async fn start(&self) -> Option<i64> {
+        self.proposal.voting_start.map(|start| start.timestamp())
+    }
+
+    /// End of the voting window, as a Unix timestamp
+    async fn end(&self) -> Option<i64> {
+        self.proposal.voting_end.map(|end| end.timestamp())
+    }
+
+    /// Block number voting power is measured at
+    async fn snapshot(&self) -> Option<String> {
+        self.proposal.snapshot_block.map(|block| block.to_string())
+    }
+
+    /// Snapshot state: `pending`, `active` or `closed`
+    async fn state(&self) -> &str {
+        snapshot_state(self.proposal.status)
+    }
+
+    /// Link to the proposal on its source
+    async fn link(&self) -> Option<&str> {
+        self.proposal.source_url.as_deref()
+    }
+
+    /// Link to the forum discussion
+    async fn discussion(&self) -> Option<&str> {
+        self.proposal.discussion_url.as_deref()
+    }
+
+    /// Votes per choice
+    async fn scores(&self) -> &[f64] {
+        &self.proposal.scores
+    }
+
+    /// Total votes
+    async fn scores_total(&self) -> Option<f64> {
+        self.proposal.scores_total
+    }
+
+    /// Quorum required for the proposal to pass
+    async fn quorum(&self) -> f64 {
+        self.proposal.quorum.unwrap_or_default()
+    }
+
+    /// Canonical proposal ID
+    async fn canonical_id(&self) -> String {
+        self.proposal.id.to_string()
+    }
+
+    /// Source the proposal was indexed from, e.g. `snapshot` or `tally`
+    async fn source(&self) -> &str {
+        self.proposal.id.source()
+    }
+
+    /// Lifecycle status, e.g. `accepted` or `executed`
+    async fn status(&self) -> &str {
+        self.proposal.status.as_str()
+    }
+
+    /// Canonical ID of the earliest proposal this one is a near-duplicate of
+    async fn is_duplicate_of(&self) -> Option<String> {
+        self.proposal
+            .is_duplicate_of
+            .as_ref()
+            .map(ToString::to_string)
+    }
+
+    /// Likelihood that the proposal is spam, from 0 to 1, once assessed
+    async fn spam_score(&self) -> Option<f64> {
+        self.proposal.spam_score
+    }
+}
+
+/// Snapshot state of a proposal status
+pub fn snapshot_state(status: ProposalStatus) -> &'static str {
+    match status {
+        ProposalStatus::Pending => "pending",
+        ProposalStatus::Active => "active",
+        _ => "closed",
+    }
+}
+
+/// Statuses covered by a Snapshot state
+fn statuses_of_state(state: &str) -> async_graphql::Result<Vec<ProposalStatus>> {
+    match state {
+        "pending" => Ok(vec![ProposalStatus::Pending]),
+        "active" => Ok(vec![ProposalStatus::Active]),
+        "closed" => Ok(vec![
+            ProposalStatus::Accepted,
+            ProposalStatus::Rejected,
+            ProposalStatus::Cancelled,
+            ProposalStatus::Executed,
+        ]),
+        other => Err(format!("Invalid state: {other}").into()),
+    }
+}
+
+/// Space ID of a protocol
+fn space_id(entry: &ProtocolEntry) -> &str {
+    entry
+        .snapshot_spaces
+        .first()
+        .map_or(entry.id.as_str(), String::as_str)
+}
+
+/// Whether a space ID refers to a protocol
+///
+/// Any of the protocol's Snapshot spaces and its registry ID are accepted.
+fn is_space_of(entry: &ProtocolEntry, id: &str) -> bool {
+    entry.id == id || entry.snapshot_spaces.iter().any(|space| space == id)
+}
+
+fn find_space<'a>(registry: &'a ProtocolRegistry, id: &str) -> Option<&'a ProtocolEntry> {
+    registry.enabled().find(|entry| is_space_of(entry, id))
+}
+
+fn space_matches(entry: &ProtocolEntry, filter: &SpaceWhere) -> bool {
+    filter.id.as_deref().is_none_or(|id| is_space_of(entry, id))
+        && filter
+            .id_in
+            .as_ref()
+            .is_none_or(|ids| ids.iter().any(|id| is_space_of(entry, id)))
+        && filter.verified != Some(false)
+}
+
+/// Validate `first` and `skip`, returning the offset and limit
+fn page(first: i32, skip: i32) -> async_graphql::Result<(i64, i64)> {
+    if !(0..=MAX_FIRST).contains(&first) {
+        return Err(format!("`first` must be between 0 and {MAX_FIRST}").into());
+    }
+    if !(0..=MAX_SKIP).contains(&skip) {
+        return Err(format!("`skip` must be between 0 and {MAX_SKIP}").into());
+    }
+
+    Ok((skip.into(), first.into()))
+}
+
+fn parse_order(order_by: &str) -> async_graphql::Result<ProposalOrder> {
+    match order_by {
+        "created" => Ok(ProposalOrder::Created),
+        "start" => Ok(ProposalOrder::Start),
+        "end" => Ok(ProposalOrder::End),
+        other => Err(format!("Invalid orderBy: {other}").into()),
+    }
+}
+
+fn timestamp(seconds: i64) -> async_graphql::Result<DateTime<Utc>> {
+    Utc.timestamp_opt(seconds, 0)
+        .single()
+        .ok_or_else(|| format!("Invalid timestamp: {seconds}").into())
+}
+
+/// Translate a Snapshot proposal filter into a repository query
+///
+/// Returns `None` if the filter names only spaces that are not tracked, in
+/// which case no proposal matches.
+fn proposal_query(
+    filter: &ProposalWhere,
+    registry: &ProtocolRegistry,
+) -> async_graphql::Result<Option<ProposalQuery>> {
+    let mut query = ProposalQuery {
+        ids: merge(filter.id.as_ref(), filter.id_in.as_ref()),
+        authors: merge(filter.author.as_ref(), filter.author_in.as_ref()),
+        statuses: filter.state.as_deref().map(statuses_of_state).transpose()?,
+        title_contains: filter.title_contains.clone(),
+        duplicates: filter.is_duplicate,
+        max_spam_score: filter.spam_score_lte,
+        ..Default::default()
+    };
+
+    if let Some(spaces) = merge(filter.space.as_ref(), filter.space_in.as_ref()) {
+        let protocols: Vec<_> = registry
+            .protocols
+            .iter()
+            .filter(|entry| spaces.iter().any(|space| is_space_of(entry, space)))
+            .map(ProtocolEntry::protocol_id)
+            .collect();
+        if protocols.is_empty() {
+            return Ok(None);
+        }
+        query.protocols = Some(protocols);
+    }
+
+    let from = [
+        filter.created_gt.map(|t| t.saturating_add(1)),
+        filter.created_gte,
+    ];
+    if let Some(from) = from.into_iter().flatten().max() {
+        query.created_from = Some(timestamp(from)?);
+    }
+    let until = [
+        filter.created_lt,
+        filter.created_lte.map(|t| t.saturating_add(1)),
+    ];
+    if let Some(until) = until.into_iter().flatten().min() {
+        query.created_until = Some(timestamp(until)?);
+    }
+
+    Ok(Some(query))
+}
+
+/// Combine a single-value filter with its `_in` variant
+fn merge(value: Option<&String>, values: Option<&Vec<String>>) -> Option<Vec<String>> {
+    match (value, values) {
+        (None, None) => None,
+        (value, values) => Some(
+            value
+                .into_iter()
+                .chain(values.into_iter().flatten())
+                .cloned()
+                .collect(),
+        ),
+    }
+}
+
+fn internal_error(e: sqlx::Error) -> async_graphql::Error {
+    error!("Database error: {}", e);
+    async_graphql::Error::new("Internal error")
+}
@@ -1,104 +1,1133 @@
 //! API handlers for the indexer service
 
+use std::time::Duration;
+
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
+use tracing::error;
 
-use crate::{db::Database, models::Proposal};
+use crate::{
+    api::routes::AppState,
+    config::parse_since,
+    db::{
+        repositories::{
+            proposal::ProposalQuery, quarantine::QuarantineFilter, ApiKeyRepository,
+            ChangeRepository, LabelRepository, ProposalRepository, QuarantineRepository,
+            TransferRepository, TreasuryRepository, WebhookRepository,
+        },
+        Database,
+    },
+    models::{
+        label::{GroundTruth, LabelCriteria, LabelResolution},
+        revision::{FieldDiff, RevisionDiff},
+        ApiKey, ApiScope, Change, DatasetManifest, EntityType, LabelValue, NewWebhook, PricedToken,
+        Proposal, ProposalLabel, ProposalRevision, ProposalTransfer, ProtocolId, QuarantinedRecord,
+        SourceLease, StatusChange, TokenValue, Treasury, Webhook, WebhookDelivery, WebhookUpdate,
+    },
+    services::{
+        auth::{CreatedApiKey, KeySettings},
+        dataset::{DatasetError, ExportOptions},
+        embeddings::SimilarProposal,
+        http::{CircuitState, HostMetrics},
+        labeling::{self, AgreementReport, LabelConflict, LABELS_PER_PROPOSAL},
+        metrics::Gauges,
+        prices::PriceError,
+        webhook::{CreatedWebhook, WebhookError},
+        Authenticator, DatasetExporter, HttpClient, IngestPipeline, LeaderElection, PriceService,
+        RegistryService, SimilaritySearch, WebhookService,
+    },
+    utils::{
+        diff::diff_revisions,
+        id::{ChainId, ProposalId},
+    },
+};
 
 /// Health check endpoint
 pub async fn health() -> StatusCode {
     StatusCode::OK
 }
 
-/// Get proposal by ID
-#[allow(unused_variables)] // TODO: Remove after development phase
+/// Readiness check of the database and the data sources
+///
+/// Only an unreachable database makes the replica unready. Unavailable
+/// data sources degrade it, since every replica shares them.
+pub async fn ready(State(state): State<AppState>) -> (StatusCode, Json<Readiness>) {
+    let database = match sqlx::query("SELECT 1").execute(&state.db).await {
+        Ok(_) => true,
+        Err(e) => {
+            error!("Readiness check of the database failed: {}", e);
+            false
+        }
+    };
+    let sources: Vec<SourceReadiness> = state
+        .http
+        .metrics()
+        .into_iter()
+        .map(|host| SourceReadiness {
+            available: host.circuit != CircuitState::Open,
+            host: host.host,
+        })
+        .collect();
+
+    let (code, status) = if !database {
+        (StatusCode::SERVICE_UNAVAILABLE, "unavailable")
+    } else if sources.iter().any(|source| !source.available) {
+        (StatusCode::OK, "degraded")
+    } else {
+        (StatusCode::OK, "ready")
+    };
+    (
+        code,
+        Json(Readiness {
+            status,
+            database,
+            sources,
+        }),
+    )
+}
+
+/// Prometheus metrics of the indexer
+pub async fn metrics(State(state): State<AppState>) -> Result<Response, StatusCode> {
+    let now = Utc::now();
+    let sync_lag = ProposalRepository::new(state.db.clone())
+        .newest_by_source()
+        .await
+        .map_err(internal_error)?
+        .into_iter()
+        .map(|(id, created_at)| {
+            let lag = (now - created_at).num_milliseconds().max(0) as f64 / 1000.0;
+            ((id.source().to_string(), id.protocol_id().to_string()), lag)
+        })
+        .collect();
+    let gauges = Gauges {
+        sync_lag,
+        pool_size: state.db.size(),
+        pool_idle: state.db.num_idle(),
+        pool_max: state.db.options().get_max_connections(),
+        hosts: state.http.metrics(),
+    };
+
+    Ok((
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.metrics.render(&gauges),
+    )
+        .into_response())
+}
+
+/// Get rate limit, retry and circuit breaker metrics of the data source hosts
+pub async fn get_source_health(State(http): State<HttpClient>) -> Json<Vec<HostMetrics>> {
+    Json(http.metrics())
+}
+
+/// Get the data source leases of all replicas
+pub async fn get_source_leases(
+    State(leader): State<LeaderElection>,
+) -> Result<Json<SourceLeases>, StatusCode> {
+    Ok(Json(SourceLeases {
+        replica: leader.replica().to_string(),
+        leases: leader.leases().await.map_err(internal_error)?,
+    }))
+}
+
+/// Get the treasury of a protocol with the totals of its snapshots since `since`
+///
+/// The protocol is either its registry ID (`uniswap`) or its protocol ID
+/// (`eip155:1:uniswap`). The history covers the last 30 days by default.
+pub async fn get_protocol_treasury(
+    Path(id): Path<String>,
+    Query(params): Query<TreasuryParams>,
+    State(registry): State<RegistryService>,
+    State(db): State<Database>,
+) -> Result<Json<Treasury>, StatusCode> {
+    let since = match &params.since {
+        Some(since) => parse_since(since).map_err(|_| StatusCode::BAD_REQUEST)?,
+        None => Utc::now() - chrono::Duration::days(DEFAULT_TREASURY_HISTORY_DAYS),
+    };
+    let protocols = registry.current();
+    let entry = match id.parse::<ProtocolId>() {
+        Ok(protocol_id) => protocols
+            .protocols
+            .iter()
+            .find(|entry| entry.protocol_id() == protocol_id),
+        Err(_) => protocols.get(&id),
+    }
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    let repository = TreasuryRepository::new(db.clone());
+    let balances = repository.latest(&entry.id).await.map_err(internal_error)?;
+    let history = repository
+        .history(&entry.id, since)
+        .await
+        .map_err(internal_error)?;
+
+    let mut treasury = Treasury {
+        protocol_id: entry.protocol_id(),
+        addresses: entry.treasuries.clone(),
+        updated_at: balances.first().map(|balance| balance.recorded_at),
+        balances,
+        history,
+    };
+    PriceService::new(db, registry)
+        .value_treasury(entry.chain_id, &mut treasury)
+        .await
+        .map_err(internal_error)?;
+    Ok(Json(treasury))
+}
+
+/// Value an amount of a registry token in USD at the price of the day of `at`
+///
+/// The token is given by its symbol or contract address on the chain.
+pub async fn get_token_value(
+    Query(params): Query<TokenValueParams>,
+    State(registry): State<RegistryService>,
+    State(db): State<Database>,
+) -> Result<Json<TokenValue>, StatusCode> {
+    if !params.amount.is_finite() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let at = match &params.at {
+        Some(at) => parse_since(at).map_err(|_| StatusCode::BAD_REQUEST)?,
+        None => Utc::now(),
+    };
+
+    PriceService::new(db, registry)
+        .value_usd(params.chain_id, &params.token, params.amount, at)
+        .await
+        .map(Json)
+        .map_err(|e| match e {
+            PriceError::UnknownToken { .. } | PriceError::NoPrice { .. } => StatusCode::NOT_FOUND,
+            PriceError::Database(e) => internal_error(e),
+        })
+}
+
+/// List the registry tokens of a chain whose prices are tracked
+pub async fn get_price_tokens(
+    Query(params): Query<PriceTokenParams>,
+    State(registry): State<RegistryService>,
+) -> Json<Vec<PricedToken>> {
+    let registry = registry.current();
+    Json(
+        registry
+            .tokens
+            .iter()
+            .filter(|token| token.chain_id == params.chain_id)
+            .map(PricedToken::from)
+            .collect(),
+    )
+}
+
+/// Get proposal by its canonical ID
 pub async fn get_proposal_by_id(
     Path(id): Path<String>,
-    State(_db): State<Database>,
+    State(db): State<Database>,
 ) -> Result<Json<Proposal>, StatusCode> {
-    // TODO: Implement proposal retrieval by ID
-    todo!("Implement get_proposal_by_id")
+    let id: ProposalId = id.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    ProposalRepository::new(db)
+        .find_by_id(&id)
+        .await
+        .map_err(internal_error)?
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
 }
 
 /// Get proposals by network
-#[allow(unused_variables)] // TODO: Remove after development phase
+///
+/// The network is either a CAIP-2 chain ID (`eip155:1`) or a protocol ID
+/// (`eip155:1:uniswap`).
 pub async fn get_proposals_by_network(
     Path(network): Path<String>,
-    State(_db): State<Database>,
+    Query(params): Query<ListingParams>,
+    State(db): State<Database>,
 ) -> Result<Json<Vec<Proposal>>, StatusCode> {
-    // TODO: Implement proposal retrieval by network
-    todo!("Implement get_proposals_by_network")
+    let mut query = ProposalQuery {
+        duplicates: params.duplicates,
+        max_spam_score: params.max_spam_score,
+        limit: i64::MAX,
+        ..Default::default()
+    };
+    if let Ok(chain_id) = network.parse::<ChainId>() {
+        query.chains = Some(vec![chain_id]);
+    } else if let Ok(protocol_id) = network.parse::<ProtocolId>() {
+        query.protocols = Some(vec![protocol_id]);
+    } else {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    ProposalRepository::new(db)
+        .list(&query)
+        .await
+        .map(Json)
+        .map_err(internal_error)
+}
+
+/// Get the revision history of a proposal, each with its diff to the previous revision
+pub async fn get_proposal_revisions(
+    Path(id): Path<String>,
+    State(db): State<Database>,
+) -> Result<Json<Vec<RevisionEntry>>, StatusCode> {
+    let id: ProposalId = id.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+    let revisions = ProposalRepository::new(db)
+        .revisions(&id)
+        .await
+        .map_err(internal_error)?;
+    if revisions.is_empty() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let entries = revisions
+        .iter()
+        .enumerate()
+        .map(|(i, revision)| RevisionEntry {
+            changes: i
+                .checked_sub(1)
+                .map(|previous| diff_revisions(&revisions[previous], revision).fields),
+            revision: revision.clone(),
+        })
+        .collect();
+
+    Ok(Json(entries))
+}
+
+/// Get the status history of a proposal, oldest first
+pub async fn get_proposal_status_history(
+    Path(id): Path<String>,
+    State(db): State<Database>,
+) -> Result<Json<Vec<StatusChange>>, StatusCode> {
+    let id: ProposalId = id.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+    let history = ProposalRepository::new(db)
+        .status_history(&id)
+        .await
+        .map_err(internal_error)?;
+    if history.is_empty() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(Json(history))
+}
+
+/// Get the ERC-20 transfers a proposal executes, in call order
+///
+/// Each transfer is valued in USD at the price of the day the proposal was
+/// created, if its token is in the registry.
+pub async fn get_proposal_transfers(
+    Path(id): Path<String>,
+    State(registry): State<RegistryService>,
+    State(db): State<Database>,
+) -> Result<Json<Vec<ProposalTransfer>>, StatusCode> {
+    let id: ProposalId = id.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+    let proposal = ProposalRepository::new(db.clone())
+        .find_by_id(&id)
+        .await
+        .map_err(internal_error)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let mut transfers = TransferRepository::new(db.clone())
+        .for_proposal(&id)
+        .await
+        .map_err(internal_error)?;
+
+    if let Some(chain_id) = proposal.protocol_id.chain_id.as_eip155() {
+        PriceService::new(db, registry)
+            .value_transfers(chain_id, &mut transfers, proposal.created_at)
+            .await
+            .map_err(internal_error)?;
+    }
+    Ok(Json(transfers))
+}
+
+/// Get the line-level diff between two revisions of a proposal
+///
+/// Defaults to the latest revision and the one before it.
+pub async fn get_proposal_revision_diff(
+    Path(id): Path<String>,
+    Query(params): Query<DiffParams>,
+    State(db): State<Database>,
+) -> Result<Json<RevisionDiff>, StatusCode> {
+    let id: ProposalId = id.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+    let repository = ProposalRepository::new(db);
+
+    let to = match params.to {
+        Some(to) => repository.revision(&id, to).await,
+        None => repository
+            .revisions(&id)
+            .await
+            .map(|revisions| revisions.into_iter().last()),
+    }
+    .map_err(internal_error)?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    let from = params.from.unwrap_or(to.revision - 1);
+    if from >= to.revision {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let from = repository
+        .revision(&id, from)
+        .await
+        .map_err(internal_error)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(diff_revisions(&from, &to)))
+}
+
+/// Get the proposals most similar to a proposal
+pub async fn get_similar_proposals(
+    Path(id): Path<String>,
+    Query(params): Query<SimilarParams>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<SimilarProposal>>, StatusCode> {
+    let id: ProposalId = id.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+    let limit = similar_limit(&params, &state.similarity)?;
+    let proposal = ProposalRepository::new(state.db)
+        .find_by_id(&id)
+        .await
+        .map_err(internal_error)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    state
+        .similarity
+        .similar_to_proposal(&proposal, limit)
+        .await
+        .map(Json)
+        .map_err(similarity_error)
+}
+
+/// Get the proposals most similar to a text
+pub async fn search_similar_proposals(
+    Query(params): Query<SimilarParams>,
+    State(similarity): State<SimilaritySearch>,
+) -> Result<Json<Vec<SimilarProposal>>, StatusCode> {
+    let limit = similar_limit(&params, &similarity)?;
+    let text = params
+        .text
+        .as_deref()
+        .filter(|text| !text.trim().is_empty())
+        .ok_or(StatusCode::BAD_REQUEST)?;
+
+    similarity
+        .similar_to_text(text, limit)
+        .await
+        .map(Json)
+        .map_err(similarity_error)
+}
+
+/// Validate the limit of a similarity query
+fn similar_limit(params: &SimilarParams, similarity: &SimilaritySearch) -> Result<i64, StatusCode> {
+    if !similarity.is_enabled() {
+        return Err(StatusCode::SERVICE_UNAVAILABLE);
+    }
+    let limit = params.limit.unwrap_or(DEFAULT_SIMILAR_LIMIT);
+    if !(1..=MAX_SIMILAR_LIMIT).contains(&limit) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    Ok(limit)
 }
 
-/// Search proposals by description/title
-#[allow(unused_variables)] // TODO: Remove after development phase
+fn similarity_error(e: anyhow::Error) -> StatusCode {
+    error!("Similarity search failed: {}", e);
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+/// Get entity changes after a sequence number, in sequence order
+///
+/// Consumers resume by passing the returned `next_since` as `since`.
+pub async fn get_changes(
+    Query(params): Query<ChangeParams>,
+    State(db): State<Database>,
+) -> Result<Json<ChangeFeed>, StatusCode> {
+    let entity_type = params
+        .entity_type
+        .as_deref()
+        .map(str::parse::<EntityType>)
+        .transpose()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    let since = params.since.unwrap_or(0);
+    let limit = params.limit.unwrap_or(DEFAULT_CHANGE_LIMIT);
+    if since < 0 || !(1..=MAX_CHANGE_LIMIT).contains(&limit) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let changes = ChangeRepository::new(db)
+        .since(since, entity_type, limit)
+        .await
+        .map_err(internal_error)?;
+    let next_since = changes.last().map_or(since, |change| change.seq);
+
+    Ok(Json(ChangeFeed {
+        changes,
+        next_since,
+    }))
+}
+
+/// Search proposals by description/title, newest first
 pub async fn search_proposals(
-    Query(_params): Query<SearchParams>,
-    State(_db): State<Database>,
+    Query(params): Query<SearchParams>,
+    State(db): State<Database>,
 ) -> Result<Json<Vec<Proposal>>, StatusCode> {
-    // TODO: Implement proposal search
-    todo!("Implement search_proposals")
+    let text = |text: &Option<String>| text.clone().filter(|text| !text.trim().is_empty());
+    let (title, description) = (text(&params.title), text(&params.description));
+    let limit = params.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    if (title.is_none() && description.is_none()) || !(1..=MAX_SEARCH_LIMIT).contains(&limit) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    ProposalRepository::new(db)
+        .search(title.as_deref(), description.as_deref(), limit)
+        .await
+        .map(Json)
+        .map_err(internal_error)
 }
 
 /// Get account by address
-#[allow(unused_variables)] // TODO: Remove after development phase
-pub async fn get_account_by_address(
-    Query(_params): Query<AccountParams>,
-    State(_db): State<Database>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    // TODO: Implement account retrieval by address
-    todo!("Implement get_account_by_address")
+///
+/// Accounts are not indexed yet, so this always responds `501`.
+pub async fn get_account_by_address() -> StatusCode {
+    StatusCode::NOT_IMPLEMENTED
 }
 
-/// Register webhook
-#[allow(unused_variables)] // TODO: Remove after development phase
+/// Register a webhook, returning its secret
 pub async fn register_webhook(
-    State(_db): State<Database>,
-    Json(_payload): Json<WebhookRegistration>,
-) -> Result<Json<WebhookResponse>, StatusCode> {
-    // TODO: Implement webhook registration
-    todo!("Implement register_webhook")
+    State(webhooks): State<WebhookService>,
+    Json(webhook): Json<NewWebhook>,
+) -> Result<(StatusCode, Json<CreatedWebhook>), StatusCode> {
+    webhooks
+        .create(&webhook)
+        .await
+        .map(|created| (StatusCode::CREATED, Json(created)))
+        .map_err(webhook_error)
 }
 
+/// List webhooks, without their secrets
+pub async fn list_webhooks(State(db): State<Database>) -> Result<Json<Vec<Webhook>>, StatusCode> {
+    WebhookRepository::new(db)
+        .list()
+        .await
+        .map(Json)
+        .map_err(internal_error)
+}
+
+/// Get a webhook
+pub async fn get_webhook(
+    Path(id): Path<String>,
+    State(db): State<Database>,
+) -> Result<Json<Webhook>, StatusCode> {
+    WebhookRepository::new(db)
+        .find_by_id(&id)
+        .await
+        .map_err(internal_error)?
+        .map(|stored| Json(stored.webhook))
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Change the URL, filters or state of a webhook
+pub async fn update_webhook(
+    Path(id): Path<String>,
+    State(webhooks): State<WebhookService>,
+    Json(update): Json<WebhookUpdate>,
+) -> Result<Json<Webhook>, StatusCode> {
+    webhooks
+        .update(&id, &update)
+        .await
+        .map(Json)
+        .map_err(webhook_error)
+}
+
+/// Delete a webhook and its delivery log
+pub async fn delete_webhook(
+    Path(id): Path<String>,
+    State(db): State<Database>,
+) -> Result<StatusCode, StatusCode> {
+    match WebhookRepository::new(db).delete(&id).await {
+        Ok(true) => Ok(StatusCode::NO_CONTENT),
+        Ok(false) => Err(StatusCode::NOT_FOUND),
+        Err(e) => Err(internal_error(e)),
+    }
+}
+
+/// Replace the secret of a webhook, returning the new one
+pub async fn rotate_webhook_secret(
+    Path(id): Path<String>,
+    State(webhooks): State<WebhookService>,
+    body: Option<Json<SecretRotation>>,
+) -> Result<Json<CreatedWebhook>, StatusCode> {
+    let grace_period = body
+        .and_then(|Json(rotation)| rotation.grace_period_secs)
+        .map(Duration::from_secs);
+    webhooks
+        .rotate_secret(&id, grace_period)
+        .await
+        .map(Json)
+        .map_err(webhook_error)
+}
+
+/// Send a test event to a webhook
+pub async fn ping_webhook(
+    Path(id): Path<String>,
+    State(webhooks): State<WebhookService>,
+) -> Result<Json<WebhookDelivery>, StatusCode> {
+    webhooks.ping(&id).await.map(Json).map_err(webhook_error)
+}
+
+/// Latest deliveries to a webhook, newest first
+pub async fn get_webhook_deliveries(
+    Path(id): Path<String>,
+    Query(params): Query<DeliveryParams>,
+    State(db): State<Database>,
+) -> Result<Json<Vec<WebhookDelivery>>, StatusCode> {
+    let repository = WebhookRepository::new(db);
+    if repository
+        .find_by_id(&id)
+        .await
+        .map_err(internal_error)?
+        .is_none()
+    {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    repository
+        .deliveries(&id, params.limit.unwrap_or(50).clamp(1, 500))
+        .await
+        .map(Json)
+        .map_err(internal_error)
+}
+
+fn webhook_error(e: WebhookError) -> StatusCode {
+    match e {
+        WebhookError::InvalidUrl(_) | WebhookError::UnknownEvent(_) => {
+            StatusCode::UNPROCESSABLE_ENTITY
+        }
+        WebhookError::NotFound(_) => StatusCode::NOT_FOUND,
+        WebhookError::Database(e) => internal_error(e),
+        WebhookError::Serialization(e) => {
+            error!("Failed to serialize webhook delivery: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+/// List quarantined records
+pub async fn list_quarantined(
+    Query(filter): Query<QuarantineFilter>,
+    State(db): State<Database>,
+) -> Result<Json<Vec<QuarantinedRecord>>, StatusCode> {
+    QuarantineRepository::new(db)
+        .list(&filter)
+        .await
+        .map(Json)
+        .map_err(internal_error)
+}
+
+/// Get a quarantined record with its raw payload and failed rules
+pub async fn get_quarantined(
+    Path(id): Path<i64>,
+    State(db): State<Database>,
+) -> Result<Json<QuarantinedRecord>, StatusCode> {
+    QuarantineRepository::new(db)
+        .find_by_id(id)
+        .await
+        .map_err(internal_error)?
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Replace the payload of a pending quarantined record
+pub async fn fix_quarantined(
+    Path(id): Path<i64>,
+    State(db): State<Database>,
+    Json(fix): Json<QuarantineFix>,
+) -> Result<Json<QuarantinedRecord>, StatusCode> {
+    QuarantineRepository::new(db)
+        .update_payload(id, &fix.payload)
+        .await
+        .map_err(internal_error)?
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Run a pending quarantined record through the ingestion pipeline again
+///
+/// Responds with the stored proposal ID, or `422` with the validation errors
+/// if the record still fails.
+pub async fn reingest_quarantined(
+    Path(id): Path<i64>,
+    State(state): State<AppState>,
+) -> Result<Json<ReingestResponse>, Response> {
+    let registry = state.registry.current();
+    let outcome = IngestPipeline::new(state.db, state.events, state.lifecycle)
+        .reingest(id, &registry)
+        .await
+        .map_err(|e| internal_error(e).into_response())?;
+
+    match outcome {
+        Some(Ok(proposal_id)) => Ok(Json(ReingestResponse { proposal_id })),
+        Some(Err(errors)) => Err((StatusCode::UNPROCESSABLE_ENTITY, Json(errors)).into_response()),
+        None => Err(StatusCode::NOT_FOUND.into_response()),
+    }
+}
+
+/// Export a new version of the training dataset
+pub async fn export_dataset(
+    State(datasets): State<DatasetExporter>,
+    Json(options): Json<ExportOptions>,
+) -> Result<(StatusCode, Json<DatasetManifest>), StatusCode> {
+    datasets
+        .export(&options)
+        .await
+        .map(|manifest| (StatusCode::CREATED, Json(manifest)))
+        .map_err(dataset_error)
+}
+
+/// Get the manifest of an exported dataset version
+pub async fn get_dataset(
+    Path(version): Path<String>,
+    State(datasets): State<DatasetExporter>,
+) -> Result<Json<DatasetManifest>, StatusCode> {
+    datasets
+        .manifest(&version)
+        .await
+        .map_err(dataset_error)?
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+fn dataset_error(e: DatasetError) -> StatusCode {
+    match e {
+        DatasetError::InvalidVersion(_) | DatasetError::InvalidRatios(_) => StatusCode::BAD_REQUEST,
+        DatasetError::AlreadyExists(_) => StatusCode::CONFLICT,
+        e => {
+            error!("Dataset export failed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+/// Get the next proposal for a labeler, `204 No Content` once nothing is left
+pub async fn get_next_unlabeled(
+    Query(params): Query<NextLabelParams>,
+    State(db): State<Database>,
+) -> Result<Response, StatusCode> {
+    let labeler = valid_name(&params.labeler)?;
+    let protocol_id = params
+        .protocol
+        .as_deref()
+        .map(str::parse::<ProtocolId>)
+        .transpose()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let next = LabelRepository::new(db.clone())
+        .next_unlabeled(labeler, protocol_id.as_ref(), LABELS_PER_PROPOSAL)
+        .await
+        .map_err(internal_error)?;
+    let Some(id) = next else {
+        return Ok(StatusCode::NO_CONTENT.into_response());
+    };
+
+    ProposalRepository::new(db)
+        .find_by_id(&id)
+        .await
+        .map_err(internal_error)?
+        .map(|proposal| Json(proposal).into_response())
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Get the labels and conflict resolution of a proposal
+pub async fn get_proposal_labels(
+    Path(id): Path<String>,
+    State(db): State<Database>,
+) -> Result<Json<ProposalLabels>, StatusCode> {
+    let id: ProposalId = id.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+    let repository = LabelRepository::new(db);
+
+    Ok(Json(ProposalLabels {
+        labels: repository.for_proposal(&id).await.map_err(internal_error)?,
+        resolution: repository.resolution(&id).await.map_err(internal_error)?,
+    }))
+}
+
+/// Label a proposal, replacing an earlier label of the same labeler
+pub async fn submit_label(
+    Path(id): Path<String>,
+    State(db): State<Database>,
+    Json(submission): Json<LabelSubmission>,
+) -> Result<(StatusCode, Json<ProposalLabel>), StatusCode> {
+    let id: ProposalId = id.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+    let labeler = valid_name(&submission.labeler)?;
+    ensure_proposal(&db, &id).await?;
+
+    LabelRepository::new(db)
+        .save(
+            &id,
+            labeler,
+            submission.label,
+            &submission.criteria,
+            submission.rationale.as_deref(),
+        )
+        .await
+        .map(|label| (StatusCode::CREATED, Json(label)))
+        .map_err(internal_error)
+}
+
+/// Record the final verdict on a proposal, replacing an earlier resolution
+pub async fn resolve_labels(
+    Path(id): Path<String>,
+    State(db): State<Database>,
+    Json(request): Json<ResolutionRequest>,
+) -> Result<Json<LabelResolution>, StatusCode> {
+    let id: ProposalId = id.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+    let resolved_by = valid_name(&request.resolved_by)?;
+    ensure_proposal(&db, &id).await?;
+
+    LabelRepository::new(db)
+        .resolve(
+            &id,
+            request.label,
+            &request.criteria,
+            resolved_by,
+            request.rationale.as_deref(),
+        )
+        .await
+        .map(Json)
+        .map_err(internal_error)
+}
+
+/// Get the inter-annotator agreement of all labelers
+pub async fn get_label_agreement(
+    State(db): State<Database>,
+) -> Result<Json<AgreementReport>, StatusCode> {
+    let labels = LabelRepository::new(db)
+        .all()
+        .await
+        .map_err(internal_error)?;
+
+    Ok(Json(labeling::agreement_report(&labels)))
+}
+
+/// Get the unresolved proposals whose labelers disagree
+pub async fn get_label_conflicts(
+    State(db): State<Database>,
+) -> Result<Json<Vec<LabelConflict>>, StatusCode> {
+    let repository = LabelRepository::new(db);
+    let labels = repository.all().await.map_err(internal_error)?;
+    let resolutions = repository.resolutions().await.map_err(internal_error)?;
+
+    Ok(Json(labeling::conflicts(&labels, &resolutions)))
+}
+
+/// Get the final verdicts of all labeled proposals, for agent evaluation
+pub async fn get_ground_truth(
+    State(db): State<Database>,
+) -> Result<Json<Vec<GroundTruth>>, StatusCode> {
+    let repository = LabelRepository::new(db);
+    let labels = repository.all().await.map_err(internal_error)?;
+    let resolutions = repository.resolutions().await.map_err(internal_error)?;
+
+    Ok(Json(labeling::ground_truth(&labels, &resolutions)))
+}
+
+/// Validate the name of a labeler
+fn valid_name(name: &str) -> Result<&str, StatusCode> {
+    let name = name.trim();
+    if name.is_empty() || name.len() > MAX_LABELER_LENGTH {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    Ok(name)
+}
+
+/// Fail with `404 Not Found` if a proposal is not indexed
+async fn ensure_proposal(db: &Database, id: &ProposalId) -> Result<(), StatusCode> {
+    ProposalRepository::new(db.clone())
+        .find_by_id(id)
+        .await
+        .map_err(internal_error)?
+        .map(|_| ())
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Create an API key, returning its secrets once
+pub async fn create_api_key(
+    State(auth): State<Authenticator>,
+    Json(request): Json<ApiKeyRequest>,
+) -> Result<(StatusCode, Json<CreatedApiKey>), StatusCode> {
+    if request.name.trim().is_empty()
+        || request.scopes.is_empty()
+        || request.rate_limit.is_some_and(|limit| limit < 1)
+    {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    auth.create_key(&KeySettings {
+        name: request.name,
+        scopes: request.scopes,
+        rate_limit: request.rate_limit,
+        signing: request.signing,
+    })
+    .await
+    .map(|key| (StatusCode::CREATED, Json(key)))
+    .map_err(internal_error)
+}
+
+/// List API keys, without their secrets
+pub async fn list_api_keys(State(db): State<Database>) -> Result<Json<Vec<ApiKey>>, StatusCode> {
+    ApiKeyRepository::new(db)
+        .list()
+        .await
+        .map(Json)
+        .map_err(internal_error)
+}
+
+/// Revoke an API key
+pub async fn revoke_api_key(
+    Path(id): Path<i64>,
+    State(db): State<Database>,
+) -> Result<Json<ApiKey>, StatusCode> {
+    ApiKeyRepository::new(db)
+        .revoke(id)
+        .await
+        .map_err(internal_error)?
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+fn internal_error(e: sqlx::Error) -> StatusCode {
+    error!("Database error: {}", e);
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+/// Maximum length of a labeler name
+const MAX_LABELER_LENGTH: usize = 255;
+
+/// Default number of proposals returned by similarity queries
+const DEFAULT_SIMILAR_LIMIT: i64 = 10;
+
+/// Maximum number of proposals returned by similarity queries
+const MAX_SIMILAR_LIMIT: i64 = 50;
+
+/// Proposal listing query parameters
+#[derive(Deserialize)]
+pub struct ListingParams {
+    /// Only near-duplicates if `true`, only originals if `false`
+    pub duplicates: Option<bool>,
+    /// Only proposals with at most this spam score, counting unassessed ones as 0
+    pub max_spam_score: Option<f64>,
+}
+
+/// Similarity query parameters
+#[derive(Deserialize)]
+pub struct SimilarParams {
+    /// Text to find similar proposals for, only used by `/proposals/similar`
+    pub text: Option<String>,
+    /// Maximum number of proposals, defaults to 10 and at most 50
+    pub limit: Option<i64>,
+}
+
+/// Default number of changes returned by the change feed
+const DEFAULT_CHANGE_LIMIT: i64 = 100;
+
+/// Maximum number of changes returned by the change feed
+const MAX_CHANGE_LIMIT: i64 = 1000;
+
+/// Change feed query parameters
+#[derive(Deserialize)]
+pub struct ChangeParams {
+    /// Return changes with a greater sequence number, defaults to 0
+    pub since: Option<i64>,
+    /// Maximum number of changes, defaults to 100 and at most 1000
+    pub limit: Option<i64>,
+    /// Only return changes of this entity type
+    pub entity_type: Option<String>,
+}
+
+/// Page of the change feed
+#[derive(Serialize)]
+pub struct ChangeFeed {
+    /// Changes in sequence order
+    pub changes: Vec<Change>,
+    /// Sequence number to resume from
+    pub next_since: i64,
+}
+
+/// Default period of a treasury's history, in days
+const DEFAULT_TREASURY_HISTORY_DAYS: i64 = 30;
+
+/// Treasury query parameters
+#[derive(Deserialize)]
+pub struct TreasuryParams {
+    /// Start of the history, as `YYYY-MM-DD` or an RFC 3339 time
+    pub since: Option<String>,
+}
+
+/// Priced token query parameters
+#[derive(Deserialize)]
+pub struct PriceTokenParams {
+    /// EVM chain ID of the tokens
+    pub chain_id: u64,
+}
+
+/// Token value query parameters
+#[derive(Deserialize)]
+pub struct TokenValueParams {
+    /// EVM chain ID of the token
+    pub chain_id: u64,
+    /// Symbol or contract address of the token
+    pub token: String,
+    /// Amount in whole tokens
+    pub amount: f64,
+    /// Time to value the amount at, as `YYYY-MM-DD` or an RFC 3339 time, defaults to now
+    pub at: Option<String>,
+}
+
+/// Default number of proposals returned by text searches
+const DEFAULT_SEARCH_LIMIT: i64 = 20;
+
+/// Maximum number of proposals returned by text searches
+const MAX_SEARCH_LIMIT: i64 = 100;
+
 /// Search parameters for proposal queries
 #[derive(Deserialize)]
-#[allow(dead_code)] // TODO: Remove after development phase
 pub struct SearchParams {
     /// Description text to search for
     pub description: Option<String>,
     /// Title text to search for
     pub title: Option<String>,
+    /// Maximum number of proposals, defaults to 20 and at most 100
+    pub limit: Option<i64>,
+}
+
+/// A proposal revision with its changes since the previous revision
+#[derive(Serialize)]
+pub struct RevisionEntry {
+    /// Revision content
+    #[serde(flatten)]
+    pub revision: ProposalRevision,
+    /// Per-field diffs to the previous revision, absent for the first one
+    pub changes: Option<Vec<FieldDiff>>,
+}
+
+/// Revisions to compare
+#[derive(Deserialize)]
+pub struct DiffParams {
+    /// Older revision, defaults to the one before `to`
+    pub from: Option<i32>,
+    /// Newer revision, defaults to the latest
+    pub to: Option<i32>,
+}
+
+/// Fixed payload for a quarantined record
+#[derive(Deserialize)]
+pub struct QuarantineFix {
+    /// Corrected raw payload
+    pub payload: serde_json::Value,
+}
+
+/// Response of a successful re-ingestion
+#[derive(Serialize)]
+pub struct ReingestResponse {
+    /// Canonical ID of the stored proposal
+    pub proposal_id: ProposalId,
+}
+
+/// Next proposal to label query parameters
+#[derive(Deserialize)]
+pub struct NextLabelParams {
+    /// Name of the labeler
+    pub labeler: String,
+    /// Only proposals of this protocol
+    pub protocol: Option<String>,
 }
 
-/// Account lookup parameters
+/// Label submitted for a proposal
 #[derive(Deserialize)]
-#[allow(dead_code)] // TODO: Remove after development phase
-pub struct AccountParams {
-    /// Ethereum address to look up
-    pub address: Option<String>,
-    /// ENS name to look up
-    pub ens: Option<String>,
+pub struct LabelSubmission {
+    /// Name of the labeler
+    pub labeler: String,
+    /// Verdict of the labeler
+    pub label: LabelValue,
+    /// Results on the agent's five evaluation categories
+    pub criteria: LabelCriteria,
+    /// Reasons for the verdict
+    pub rationale: Option<String>,
 }
 
-/// Webhook registration request
+/// Final verdict on a proposal whose labelers disagree
 #[derive(Deserialize)]
-#[allow(dead_code)] // TODO: Remove after development phase
-pub struct WebhookRegistration {
-    /// URL to send webhook notifications to
-    pub url: String,
-    /// List of event types to subscribe to
-    pub events: Vec<String>,
+pub struct ResolutionRequest {
+    /// Name of the person resolving the conflict
+    pub resolved_by: String,
+    /// Final verdict
+    pub label: LabelValue,
+    /// Final results on the agent's five evaluation categories
+    pub criteria: LabelCriteria,
+    /// Reasons for the final verdict
+    pub rationale: Option<String>,
+}
+
+/// Labels of a proposal
+#[derive(Serialize)]
+pub struct ProposalLabels {
+    /// Labels, oldest first
+    pub labels: Vec<ProposalLabel>,
+    /// Final verdict, if a conflict was resolved
+    pub resolution: Option<LabelResolution>,
 }
 
-/// Webhook registration response
+/// Data source leases as seen by a replica
 #[derive(Serialize)]
-pub struct WebhookResponse {
-    /// ID of the registered webhook
-    pub id: String,
-    /// Status of the registration
-    pub status: String,
+pub struct SourceLeases {
+    /// ID of the replica that answered
+    pub replica: String,
+    /// Unexpired leases of all replicas
+    pub leases: Vec<SourceLease>,
+}
+
+/// API key to create
+#[derive(Deserialize)]
+pub struct ApiKeyRequest {
+    /// Name of the client using the key
+    pub name: String,
+    /// Permissions of the key
+    pub scopes: Vec<ApiScope>,
+    /// Requests per minute allowed, unlimited if unset
+    pub rate_limit: Option<i32>,
+    /// Whether to create a secret to sign requests with
+    #[serde(default)]
+    pub signing: bool,
+}
+
+/// Secret rotation request
+#[derive(Deserialize)]
+pub struct SecretRotation {
+    /// Seconds the previous secret keeps signing, the configured period if unset
+    pub grace_period_secs: Option<u64>,
+}
+
+/// Delivery log query parameters
+#[derive(Deserialize)]
+pub struct DeliveryParams {
+    /// Number of deliveries to return, 50 by default and at most 500
+    pub limit: Option<i64>,
+}
+
+/// Readiness of the indexer
+#[derive(Debug, Serialize)]
+pub struct Readiness {
+    /// `ready`, `degraded` when data sources are unavailable, or `unavailable`
+    pub status: &'static str,
+    /// Whether the database is reachable
+    pub database: bool,
+    /// Availability of the data source hosts
+    pub sources: Vec<SourceReadiness>,
+}
+
+/// Availability of a data source host
+#[derive(Debug, Serialize)]
+pub struct SourceReadiness {
+    /// Host name
+    pub host: String,
+    /// Whether its circuit breaker lets requests through
+    pub available: bool,
 }
@@ -1,19 +1,121 @@
 //! Middleware for the indexer API
 
-use axum::{extract::Request, http::StatusCode, middleware::Next, response::Response};
-
-/// Logging middleware
-#[allow(dead_code)] // TODO: Remove after development phase
-pub async fn logging_middleware(request: Request, next: Next) -> Result<Response, StatusCode> {
-    // TODO: Implement request logging
-    let response = next.run(request).await;
-    Ok(response)
-}
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tracing::error;
+
+use crate::services::{
+    auth::{
+        required_scope, string_to_sign, AuthError, Credentials, API_KEY_HEADER, KEY_ID_HEADER,
+        SIGNATURE_HEADER, TIMESTAMP_HEADER,
+    },
+    Authenticator,
+};
+
+/// Largest request body accepted with a signature
+const MAX_SIGNED_BODY_BYTES: usize = 2 * 1024 * 1024;
 
 /// Authentication middleware
-#[allow(dead_code)] // TODO: Remove after development phase
-pub async fn auth_middleware(request: Request, next: Next) -> Result<Response, StatusCode> {
-    // TODO: Implement authentication
-    let response = next.run(request).await;
-    Ok(response)
+///
+/// Checks the API key or signature of a request against the scope of its
+/// route and stores the authenticated [`ApiKey`](crate::models::ApiKey), if
+/// any, in the request extensions. The key prefix is recorded in the request
+/// logs.
+pub async fn auth_middleware(
+    State(auth): State<Authenticator>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(scope) = required_scope(request.method(), request.uri().path()) else {
+        return next.run(request).await;
+    };
+
+    let header = |headers: &HeaderMap, name: &str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+    let headers = request.headers();
+    let key = header(headers, API_KEY_HEADER).or_else(|| {
+        header(headers, header::AUTHORIZATION.as_str())
+            .and_then(|value| value.strip_prefix("Bearer ").map(str::to_string))
+    });
+    let signature = header(headers, SIGNATURE_HEADER);
+    let key_id = header(headers, KEY_ID_HEADER);
+    let timestamp = header(headers, TIMESTAMP_HEADER);
+
+    let (request, result) = match (signature, key_id, timestamp) {
+        (Some(signature), Some(key_id), Some(timestamp)) => {
+            let Ok(timestamp) = timestamp.parse::<i64>() else {
+                return auth_error(AuthError::InvalidCredentials);
+            };
+            // The signature covers the body, which is put back afterwards
+            let (parts, body) = request.into_parts();
+            let Ok(body) = to_bytes(body, MAX_SIGNED_BODY_BYTES).await else {
+                return StatusCode::PAYLOAD_TOO_LARGE.into_response();
+            };
+            let path = parts
+                .uri
+                .path_and_query()
+                .map_or(parts.uri.path(), |path| path.as_str());
+            let signed = string_to_sign(timestamp, parts.method.as_str(), path, &body);
+            let credentials = Credentials::Signed {
+                key_id: &key_id,
+                timestamp,
+                signature: &signature,
+                signed: &signed,
+            };
+            let result = auth.authenticate(credentials, scope).await;
+            (Request::from_parts(parts, Body::from(body)), result)
+        }
+        (None, None, None) => {
+            let credentials = key
+                .as_deref()
+                .map_or(Credentials::Anonymous, Credentials::Key);
+            let result = auth.authenticate(credentials, scope).await;
+            (request, result)
+        }
+        _ => return auth_error(AuthError::InvalidCredentials),
+    };
+
+    match result {
+        Ok(key) => {
+            let mut request = request;
+            if let Some(key) = key {
+                telemetry::record_api_key(&key.prefix);
+                request.extensions_mut().insert(key);
+            }
+            next.run(request).await
+        }
+        Err(e) => auth_error(e),
+    }
+}
+
+fn auth_error(e: AuthError) -> Response {
+    let status = match &e {
+        AuthError::MissingCredentials
+        | AuthError::InvalidCredentials
+        | AuthError::ExpiredSignature => StatusCode::UNAUTHORIZED,
+        AuthError::Forbidden(_) => StatusCode::FORBIDDEN,
+        AuthError::RateLimited(retry_in) => {
+            let retry_after = retry_in.as_secs_f64().ceil().max(1.0) as u64;
+            return (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, retry_after.to_string())],
+                e.to_string(),
+            )
+                .into_response();
+        }
+        AuthError::Database(e) => {
+            error!("Database error: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    (status, e.to_string()).into_response()
 }
@@ -3,12 +3,16 @@
 //! This module provides the HTTP API for the indexer service, including
 //! handlers, routes, and middleware for processing requests.
 
+/// Snapshot-compatible GraphQL API
+pub mod graphql;
 /// Request handlers for API endpoints
 pub mod handlers;
 /// Middleware for request processing
 pub mod middleware;
 /// Route definitions and router creation
 pub mod routes;
+/// Server-Sent Events and WebSocket event streams
+pub mod stream;
 // TODO: Remove unused import after development phase
 #[allow(unused_imports)]
-pub use routes::create_router;
+pub use routes::{create_router, AppState};
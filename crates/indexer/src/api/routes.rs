@@ -1,24 +1,227 @@
 //! API routes for the indexer service
 
 use axum::{
-    routing::{get, post},
+    extract::FromRef,
+    http::{header, Method},
+    middleware,
+    routing::{delete, get, post, put},
     Router,
 };
+use telemetry::{logging_middleware, REQUEST_ID_HEADER};
+use tower_http::cors::{Any, CorsLayer};
 
-use crate::{api::handlers, db::Database};
+use crate::{
+    api::{graphql, handlers, middleware::auth_middleware, stream},
+    db::Database,
+    services::{
+        auth::API_KEY_HEADER, Authenticator, DatasetExporter, EventBus, EventLog, HttpClient,
+        LeaderElection, LifecycleScheduler, Metrics, RegistryService, SimilaritySearch,
+        WebhookService,
+    },
+};
+
+/// Application state
+#[derive(Clone)]
+pub struct AppState {
+    /// Database connection pool
+    pub db: Database,
+    /// Currently active protocol registry
+    pub registry: RegistryService,
+    /// Bus receiving indexer events
+    pub events: EventBus,
+    /// Persistent log of published events
+    pub event_log: EventLog,
+    /// Scheduler of proposal status transitions
+    pub lifecycle: LifecycleScheduler,
+    /// Similarity search over proposal embeddings
+    pub similarity: SimilaritySearch,
+    /// Exporter of training datasets
+    pub datasets: DatasetExporter,
+    /// HTTP client shared by the data sources
+    pub http: HttpClient,
+    /// Election of the replica crawling each data source
+    pub leader: LeaderElection,
+    /// Authenticator of API requests
+    pub auth: Authenticator,
+    /// Webhook management and delivery
+    pub webhooks: WebhookService,
+    /// Metrics recorded by the crawler and the webhook dispatcher
+    pub metrics: Metrics,
+}
+
+impl FromRef<AppState> for Database {
+    fn from_ref(state: &AppState) -> Self {
+        state.db.clone()
+    }
+}
+
+impl FromRef<AppState> for RegistryService {
+    fn from_ref(state: &AppState) -> Self {
+        state.registry.clone()
+    }
+}
+
+impl FromRef<AppState> for EventLog {
+    fn from_ref(state: &AppState) -> Self {
+        state.event_log.clone()
+    }
+}
+
+impl FromRef<AppState> for SimilaritySearch {
+    fn from_ref(state: &AppState) -> Self {
+        state.similarity.clone()
+    }
+}
+
+impl FromRef<AppState> for HttpClient {
+    fn from_ref(state: &AppState) -> Self {
+        state.http.clone()
+    }
+}
+
+impl FromRef<AppState> for LeaderElection {
+    fn from_ref(state: &AppState) -> Self {
+        state.leader.clone()
+    }
+}
+
+impl FromRef<AppState> for Authenticator {
+    fn from_ref(state: &AppState) -> Self {
+        state.auth.clone()
+    }
+}
+
+impl FromRef<AppState> for WebhookService {
+    fn from_ref(state: &AppState) -> Self {
+        state.webhooks.clone()
+    }
+}
+
+impl FromRef<AppState> for DatasetExporter {
+    fn from_ref(state: &AppState) -> Self {
+        state.datasets.clone()
+    }
+}
 
 /// Create the API router
-#[allow(dead_code)] // TODO: Remove after development phase
-pub fn create_router(db: Database) -> Router {
+pub fn create_router(state: AppState) -> Router {
+    let schema = graphql::build_schema(state.db.clone(), state.registry.subscribe());
+    // Like the Snapshot hub, the GraphQL API is public and read-only
+    let graphql_cors = CorsLayer::new()
+        .allow_origin(Any)
+        .allow_methods([Method::GET, Method::POST])
+        .allow_headers([
+            header::CONTENT_TYPE,
+            header::AUTHORIZATION,
+            header::HeaderName::from_static(API_KEY_HEADER),
+            header::HeaderName::from_static(REQUEST_ID_HEADER),
+        ])
+        .expose_headers([header::HeaderName::from_static(REQUEST_ID_HEADER)]);
+
     Router::new()
         .route("/health", get(handlers::health))
+        .route("/ready", get(handlers::ready))
+        .route("/metrics", get(handlers::metrics))
+        .route("/sources/health", get(handlers::get_source_health))
+        .route("/sources/leases", get(handlers::get_source_leases))
+        .route(
+            "/protocols/:id/treasury",
+            get(handlers::get_protocol_treasury),
+        )
+        .route("/prices/tokens", get(handlers::get_price_tokens))
+        .route("/prices/value", get(handlers::get_token_value))
         .route("/proposals/:id", get(handlers::get_proposal_by_id))
+        .route(
+            "/proposals/:id/revisions",
+            get(handlers::get_proposal_revisions),
+        )
+        .route(
+            "/proposals/:id/revisions/diff",
+            get(handlers::get_proposal_revision_diff),
+        )
+        .route(
+            "/proposals/:id/similar",
+            get(handlers::get_similar_proposals),
+        )
+        .route(
+            "/proposals/similar",
+            get(handlers::search_similar_proposals),
+        )
+        .route(
+            "/proposals/:id/labels",
+            get(handlers::get_proposal_labels).post(handlers::submit_label),
+        )
+        .route(
+            "/proposals/:id/labels/resolution",
+            put(handlers::resolve_labels),
+        )
+        .route(
+            "/proposals/:id/status-history",
+            get(handlers::get_proposal_status_history),
+        )
+        .route(
+            "/proposals/:id/transfers",
+            get(handlers::get_proposal_transfers),
+        )
         .route(
             "/proposals/network/:network",
             get(handlers::get_proposals_by_network),
         )
         .route("/proposals/search", get(handlers::search_proposals))
         .route("/accounts", get(handlers::get_account_by_address))
-        .route("/hooks", post(handlers::register_webhook))
-        .with_state(db)
+        .route(
+            "/hooks",
+            get(handlers::list_webhooks).post(handlers::register_webhook),
+        )
+        .route(
+            "/hooks/:id",
+            get(handlers::get_webhook)
+                .patch(handlers::update_webhook)
+                .delete(handlers::delete_webhook),
+        )
+        .route(
+            "/hooks/:id/rotate-secret",
+            post(handlers::rotate_webhook_secret),
+        )
+        .route("/hooks/:id/ping", post(handlers::ping_webhook))
+        .route(
+            "/hooks/:id/deliveries",
+            get(handlers::get_webhook_deliveries),
+        )
+        .route("/changes", get(handlers::get_changes))
+        .route("/labels/next", get(handlers::get_next_unlabeled))
+        .route("/labels/agreement", get(handlers::get_label_agreement))
+        .route("/labels/conflicts", get(handlers::get_label_conflicts))
+        .route("/labels/ground-truth", get(handlers::get_ground_truth))
+        .route("/datasets", post(handlers::export_dataset))
+        .route("/datasets/:version", get(handlers::get_dataset))
+        .route(
+            "/graphql",
+            get(graphql::graphiql)
+                .post(graphql::graphql_handler)
+                .with_state(schema)
+                .layer(graphql_cors),
+        )
+        .route("/events/stream", get(stream::sse_stream))
+        .route("/events/ws", get(stream::ws_stream))
+        .route("/quarantine", get(handlers::list_quarantined))
+        .route(
+            "/quarantine/:id",
+            get(handlers::get_quarantined).put(handlers::fix_quarantined),
+        )
+        .route(
+            "/quarantine/:id/reingest",
+            post(handlers::reingest_quarantined),
+        )
+        .route(
+            "/api-keys",
+            get(handlers::list_api_keys).post(handlers::create_api_key),
+        )
+        .route("/api-keys/:id", delete(handlers::revoke_api_key))
+        .route_layer(middleware::from_fn_with_state(
+            state.auth.clone(),
+            auth_middleware,
+        ))
+        .layer(middleware::from_fn(logging_middleware))
+        .with_state(state)
 }
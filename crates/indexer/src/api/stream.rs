@@ -0,0 +1,164 @@
+//! Real-time event streams
+//!
+//! Events are pushed over Server-Sent Events (`/events/stream`) or a
+//! WebSocket (`/events/ws`). Both accept the same filters and resume after
+//! a given event ID: for SSE it is taken from the `Last-Event-ID` header
+//! sent by reconnecting clients, or the `last_event_id` query parameter.
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    http::{HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+};
+use futures::{stream, Stream};
+use serde::Deserialize;
+use tokio::sync::mpsc;
+use tracing::{debug, error};
+
+use crate::{
+    models::{event::EVENT_TYPES, EventEnvelope, EventFilter},
+    services::EventLog,
+};
+
+/// Events buffered per client before the feed waits for it
+const CLIENT_BUFFER: usize = 64;
+
+/// Query parameters of the event streams
+#[derive(Debug, Default, Deserialize)]
+pub struct StreamParams {
+    /// Comma-separated protocol IDs to include
+    pub protocols: Option<String>,
+    /// Comma-separated event types to include
+    pub types: Option<String>,
+    /// Resume after this event
+    pub last_event_id: Option<i64>,
+}
+
+impl StreamParams {
+    /// Event filter described by the parameters
+    pub fn filter(&self) -> Result<EventFilter, String> {
+        let protocols = split(self.protocols.as_deref())
+            .map(|id| {
+                id.parse()
+                    .map_err(|e| format!("invalid protocol '{id}': {e}"))
+            })
+            .collect::<Result<_, _>>()?;
+        let types = split(self.types.as_deref())
+            .map(|event_type| {
+                if EVENT_TYPES.contains(&event_type) {
+                    Ok(event_type.to_string())
+                } else {
+                    Err(format!("unknown event type '{event_type}'"))
+                }
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(EventFilter { protocols, types })
+    }
+}
+
+fn split(list: Option<&str>) -> impl Iterator<Item = &str> {
+    list.unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+}
+
+/// Start feeding logged events to a new client
+fn subscribe(
+    log: EventLog,
+    filter: EventFilter,
+    last_event_id: Option<i64>,
+) -> mpsc::Receiver<EventEnvelope> {
+    let (sender, receiver) = mpsc::channel(CLIENT_BUFFER);
+    tokio::spawn(async move {
+        if let Err(e) = log.feed(filter, last_event_id, sender).await {
+            error!("Event stream failed: {}", e);
+        }
+    });
+    receiver
+}
+
+/// Stream events as Server-Sent Events
+pub async fn sse_stream(
+    State(log): State<EventLog>,
+    Query(params): Query<StreamParams>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<Event, axum::Error>>>, (StatusCode, String)> {
+    let filter = params.filter().map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let last_event_id = headers
+        .get("last-event-id")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+        .or(params.last_event_id);
+
+    let events = stream::unfold(
+        subscribe(log, filter, last_event_id),
+        |mut receiver| async move {
+            let stored = receiver.recv().await?;
+            let event = Event::default()
+                .id(stored.id.to_string())
+                .event(stored.event.event_type())
+                .json_data(&stored);
+            Some((event, receiver))
+        },
+    );
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+/// Stream events over a WebSocket
+///
+/// Each message is a JSON event including its `id`.
+pub async fn ws_stream(
+    ws: WebSocketUpgrade,
+    State(log): State<EventLog>,
+    Query(params): Query<StreamParams>,
+) -> Response {
+    match params.filter() {
+        Ok(filter) => ws
+            .on_upgrade(move |socket| forward(socket, log, filter, params.last_event_id))
+            .into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e).into_response(),
+    }
+}
+
+async fn forward(
+    mut socket: WebSocket,
+    log: EventLog,
+    filter: EventFilter,
+    last_event_id: Option<i64>,
+) {
+    let mut events = subscribe(log, filter, last_event_id);
+    loop {
+        tokio::select! {
+            stored = events.recv() => {
+                let Some(stored) = stored else {
+                    break;
+                };
+                let text = match serde_json::to_string(&stored) {
+                    Ok(text) => text,
+                    Err(e) => {
+                        error!("Failed to serialize event {}: {}", stored.id, e);
+                        continue;
+                    }
+                };
+                if socket.send(Message::Text(text)).await.is_err() {
+                    break;
+                }
+            }
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                // Pings are answered by axum, other messages are ignored
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+    debug!("WebSocket event stream closed");
+}
@@ -1,7 +1,21 @@
 //! Configuration management for the indexer service
 
-use clap::Parser;
+use std::path::PathBuf;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use serde::{Deserialize, Serialize};
+use telemetry::{LogFormat, OtlpConfig};
+
+/// Declarative protocol registry
+pub mod registry;
+
+pub use registry::{ProtocolEntry, ProtocolRegistry, RegistryError, TokenEntry, TreasuryToken};
+
+use crate::models::{
+    dataset::{DatasetFormat, SplitRatios},
+    ApiScope, BackfillSource,
+};
 
 /// Application configuration
 #[derive(Debug, Clone, Serialize, Deserialize, Parser)]
@@ -38,6 +52,10 @@ pub struct Config {
     #[arg(env = "WEI_INDEXER_SNAPSHOT_API_KEY", long)]
     pub snapshot_api_key: Option<String>,
 
+    /// Requests per minute sent to the Snapshot API
+    #[arg(env = "WEI_INDEXER_SNAPSHOT_RATE_LIMIT", long, default_value = "100")]
+    pub snapshot_rate_limit: u32,
+
     /// Tally API base URL
     #[arg(
         env = "WEI_INDEXER_TALLY_BASE_URL",
@@ -50,6 +68,38 @@ pub struct Config {
     #[arg(env = "WEI_INDEXER_TALLY_API_KEY", long)]
     pub tally_api_key: Option<String>,
 
+    /// Requests per minute sent to the Tally API
+    #[arg(env = "WEI_INDEXER_TALLY_RATE_LIMIT", long, default_value = "60")]
+    pub tally_rate_limit: u32,
+
+    /// Retries of a data source request after a 429, 5xx, network error or timeout
+    #[arg(env = "WEI_INDEXER_HTTP_MAX_RETRIES", long, default_value = "3")]
+    pub http_max_retries: u32,
+
+    /// Consecutive failed requests after which a data source is marked unavailable
+    #[arg(
+        env = "WEI_INDEXER_CIRCUIT_BREAKER_THRESHOLD",
+        long,
+        default_value = "5"
+    )]
+    pub circuit_breaker_threshold: u32,
+
+    /// Seconds an unavailable data source is left alone before it is tried again
+    #[arg(
+        env = "WEI_INDEXER_CIRCUIT_BREAKER_COOLDOWN",
+        long,
+        default_value = "60"
+    )]
+    pub circuit_breaker_cooldown: u64,
+
+    /// Seconds a data source request may take, including reading the response
+    #[arg(env = "WEI_INDEXER_HTTP_TIMEOUT", long, default_value = "30")]
+    pub http_timeout: u64,
+
+    /// Seconds to wait for a connection to a data source
+    #[arg(env = "WEI_INDEXER_HTTP_CONNECT_TIMEOUT", long, default_value = "10")]
+    pub http_connect_timeout: u64,
+
     /// Webhook secret for authentication
     #[arg(env = "WEI_INDEXER_WEBHOOK_SECRET", long)]
     pub webhook_secret: String,
@@ -57,6 +107,303 @@ pub struct Config {
     /// Maximum webhook retry attempts
     #[arg(env = "WEI_INDEXER_MAX_RETRIES", long, default_value = "3")]
     pub max_retries: u32,
+
+    /// Failed deliveries in a row after which a webhook is disabled
+    #[arg(env = "WEI_INDEXER_WEBHOOK_FAILURE_LIMIT", long, default_value = "10")]
+    pub webhook_failure_limit: u32,
+
+    /// Seconds a rotated webhook secret keeps signing deliveries
+    #[arg(
+        env = "WEI_INDEXER_WEBHOOK_SECRET_GRACE_PERIOD",
+        long,
+        default_value = "86400"
+    )]
+    pub webhook_secret_grace_period: u64,
+
+    /// Path to the protocol registry file (TOML or YAML)
+    #[arg(
+        env = "WEI_INDEXER_REGISTRY_PATH",
+        long,
+        default_value = "config/protocols.toml"
+    )]
+    pub registry_path: PathBuf,
+
+    /// Interval between registry file change checks, in seconds
+    #[arg(
+        env = "WEI_INDEXER_REGISTRY_RELOAD_INTERVAL",
+        long,
+        default_value = "30"
+    )]
+    pub registry_reload_interval: u64,
+
+    /// Embedding provider for similarity search, disabled if unset
+    #[arg(env = "WEI_INDEXER_EMBEDDING_PROVIDER", long, value_enum)]
+    pub embedding_provider: Option<EmbeddingProviderKind>,
+
+    /// Base URL of the OpenAI-compatible embedding API
+    #[arg(
+        env = "WEI_INDEXER_EMBEDDING_BASE_URL",
+        long,
+        default_value = "https://api.openai.com/v1"
+    )]
+    pub embedding_base_url: String,
+
+    /// Embedding API key (optional)
+    #[arg(env = "WEI_INDEXER_EMBEDDING_API_KEY", long)]
+    pub embedding_api_key: Option<String>,
+
+    /// Embedding model
+    #[arg(
+        env = "WEI_INDEXER_EMBEDDING_MODEL",
+        long,
+        default_value = "text-embedding-3-small"
+    )]
+    pub embedding_model: String,
+
+    /// JSON-RPC node per chain, as comma-separated `<chain ID>=<URL>` pairs
+    #[arg(
+        env = "WEI_INDEXER_RPC_URLS",
+        long,
+        value_delimiter = ',',
+        value_parser = parse_rpc_endpoint
+    )]
+    pub rpc_urls: Vec<RpcEndpoint>,
+
+    /// Seconds between treasury balance snapshots of a protocol
+    #[arg(env = "WEI_INDEXER_TREASURY_INTERVAL", long, default_value = "3600")]
+    pub treasury_interval: u64,
+
+    /// Provider of daily token prices, price syncing is disabled if unset
+    #[arg(env = "WEI_INDEXER_PRICE_PROVIDER", long, value_enum)]
+    pub price_provider: Option<PriceProviderKind>,
+
+    /// Base URL of the CoinGecko API
+    #[arg(
+        env = "WEI_INDEXER_PRICE_BASE_URL",
+        long,
+        default_value = "https://api.coingecko.com/api/v3"
+    )]
+    pub price_base_url: String,
+
+    /// CoinGecko API key (optional)
+    #[arg(env = "WEI_INDEXER_PRICE_API_KEY", long)]
+    pub price_api_key: Option<String>,
+
+    /// CSV file of daily prices read by the `csv` price provider
+    #[arg(
+        env = "WEI_INDEXER_PRICE_CSV_PATH",
+        long,
+        required_if_eq("price_provider", "csv")
+    )]
+    pub price_csv_path: Option<PathBuf>,
+
+    /// Seconds between price syncs
+    #[arg(env = "WEI_INDEXER_PRICE_SYNC_INTERVAL", long, default_value = "21600")]
+    pub price_sync_interval: u64,
+
+    /// Days of history fetched for a token without any prices
+    #[arg(env = "WEI_INDEXER_PRICE_HISTORY_DAYS", long, default_value = "365")]
+    pub price_history_days: u32,
+
+    /// ID of this replica in leader election, defaults to the host name and a random suffix
+    #[arg(env = "WEI_INDEXER_REPLICA_ID", long)]
+    pub replica_id: Option<String>,
+
+    /// Seconds a data source lease stays valid without renewal
+    #[arg(env = "WEI_INDEXER_LEASE_TTL", long, default_value = "60")]
+    pub lease_ttl: u64,
+
+    /// Whether API requests need an API key
+    #[arg(env = "WEI_INDEXER_API_KEY_AUTH_ENABLED", long, default_value = "true")]
+    pub api_key_auth_enabled: bool,
+
+    /// Whether read endpoints can be used without an API key
+    #[arg(env = "WEI_INDEXER_ANONYMOUS_READ", long, default_value = "true")]
+    pub anonymous_read: bool,
+
+    /// Directory exported training datasets are written to
+    #[arg(env = "WEI_INDEXER_DATASET_DIR", long, default_value = "datasets")]
+    pub dataset_dir: PathBuf,
+
+    /// Log output format
+    #[arg(
+        env = "WEI_INDEXER_LOG_FORMAT",
+        long,
+        value_enum,
+        default_value = "text"
+    )]
+    pub log_format: LogFormat,
+
+    /// OTLP/HTTP collector base URL spans are exported to, export is off if unset
+    #[arg(env = "WEI_INDEXER_OTLP_ENDPOINT", long)]
+    pub otlp_endpoint: Option<String>,
+
+    /// Share of new traces exported, from 0 to 1
+    #[arg(env = "WEI_INDEXER_OTLP_SAMPLE_RATIO", long, default_value = "1.0")]
+    pub otlp_sample_ratio: f64,
+
+    /// Service name exported spans are tagged with
+    #[arg(env = "WEI_INDEXER_SERVICE_NAME", long, default_value = "wei-indexer")]
+    pub service_name: String,
+
+    /// Command to run, `serve` if omitted
+    #[command(subcommand)]
+    #[serde(skip)]
+    pub command: Option<Command>,
+}
+
+/// Command of the indexer binary
+#[derive(Debug, Clone, Subcommand)]
+pub enum Command {
+    /// Run the API server and background services
+    Serve,
+    /// Export a versioned training dataset and exit
+    Export(ExportArgs),
+    /// Crawl the history of a Snapshot space or Tally organization, resuming earlier runs
+    Backfill(BackfillArgs),
+    /// Fetch proposals from their data sources again and re-run the pipeline
+    Reindex(ReindexArgs),
+    /// Check stored proposals for inconsistencies, failing if any are found
+    Verify,
+    /// Manage API keys
+    #[command(subcommand)]
+    ApiKey(ApiKeyCommand),
+    /// Import or sync daily token prices
+    #[command(subcommand)]
+    Prices(PricesCommand),
+}
+
+/// Subcommand of the `prices` command
+#[derive(Debug, Clone, Subcommand)]
+pub enum PricesCommand {
+    /// Import daily prices from a `date,asset,price_usd` CSV file
+    Import {
+        /// Path to the CSV file
+        #[arg(long)]
+        file: PathBuf,
+    },
+    /// Fetch the prices missing since the last sync from the configured provider
+    Sync,
+}
+
+/// Subcommand of the `api-key` command
+#[derive(Debug, Clone, Subcommand)]
+pub enum ApiKeyCommand {
+    /// Create a key and print it, it cannot be shown again
+    Create(CreateApiKeyArgs),
+    /// List keys
+    List,
+    /// Revoke a key
+    Revoke {
+        /// ID of the key
+        #[arg(long)]
+        id: i64,
+    },
+}
+
+/// Arguments of the `api-key create` command
+#[derive(Debug, Clone, Args)]
+pub struct CreateApiKeyArgs {
+    /// Name of the client using the key
+    #[arg(long)]
+    pub name: String,
+
+    /// Scope granted to the key, repeatable
+    #[arg(long = "scope", value_enum, required = true)]
+    pub scopes: Vec<ApiScope>,
+
+    /// Requests per minute allowed, unlimited if omitted
+    #[arg(long, value_parser = clap::value_parser!(i32).range(1..))]
+    pub rate_limit: Option<i32>,
+
+    /// Also create a secret to sign requests with
+    #[arg(long)]
+    pub signing: bool,
+}
+
+/// Arguments of the `backfill` command
+#[derive(Debug, Clone, Args)]
+pub struct BackfillArgs {
+    /// Data source to crawl
+    #[arg(long, value_enum)]
+    pub source: BackfillSource,
+
+    /// Snapshot space to crawl
+    #[arg(
+        long,
+        required_if_eq("source", "snapshot"),
+        conflicts_with = "organization"
+    )]
+    pub space: Option<String>,
+
+    /// Tally organization slug to crawl
+    #[arg(long, required_if_eq("source", "tally"))]
+    pub organization: Option<String>,
+
+    /// Only crawl proposals created at or after this date or RFC 3339 time
+    #[arg(long, value_parser = parse_since, default_value = "1970-01-01")]
+    pub since: DateTime<Utc>,
+
+    /// Start over instead of resuming an earlier run
+    #[arg(long)]
+    pub restart: bool,
+}
+
+/// Arguments of the `reindex` command
+#[derive(Debug, Clone, Args)]
+pub struct ReindexArgs {
+    /// Canonical ID of a proposal to reindex, repeatable
+    #[arg(long = "proposal", required = true)]
+    pub proposals: Vec<String>,
+}
+
+/// Parse a `YYYY-MM-DD` date as midnight UTC, or an RFC 3339 time
+pub fn parse_since(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_time(chrono::NaiveTime::MIN).and_utc());
+    }
+    DateTime::parse_from_rfc3339(value)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(|_| format!("Invalid date: {value}, expected YYYY-MM-DD or RFC 3339"))
+}
+
+/// Parse a `<chain ID>=<URL>` JSON-RPC endpoint
+pub fn parse_rpc_endpoint(value: &str) -> Result<RpcEndpoint, String> {
+    let (chain_id, url) = value
+        .split_once('=')
+        .ok_or_else(|| format!("Invalid RPC endpoint: {value}, expected <chain ID>=<URL>"))?;
+    let chain_id = chain_id
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid chain ID in RPC endpoint: {value}"))?;
+    let url = url.trim();
+    match url::Url::parse(url) {
+        Ok(parsed) if parsed.scheme() == "http" || parsed.scheme() == "https" => Ok(RpcEndpoint {
+            chain_id,
+            url: url.to_string(),
+        }),
+        _ => Err(format!("Invalid URL in RPC endpoint: {value}")),
+    }
+}
+
+/// Arguments of the `export` command
+#[derive(Debug, Clone, Args)]
+pub struct ExportArgs {
+    /// Version of the dataset, defaults to the UTC time of the export
+    #[arg(long = "dataset-version")]
+    pub version: Option<String>,
+
+    /// Format to write, repeatable; all formats if omitted
+    #[arg(long = "format", value_enum)]
+    pub formats: Vec<DatasetFormat>,
+
+    /// Relative sizes of the train, validation and test splits
+    #[arg(long, default_value = "5:10:5")]
+    pub splits: SplitRatios,
+
+    /// Seed of the split assignment
+    #[arg(long, default_value = "wei")]
+    pub seed: String,
 }
 
 #[allow(dead_code)] // TODO: Remove after development phase
@@ -83,10 +430,19 @@ impl Config {
             snapshot: SnapshotConfig {
                 base_url: self.snapshot_base_url.clone(),
                 api_key: self.snapshot_api_key.clone(),
+                requests_per_minute: self.snapshot_rate_limit,
             },
             tally: TallyConfig {
                 base_url: self.tally_base_url.clone(),
                 api_key: self.tally_api_key.clone(),
+                requests_per_minute: self.tally_rate_limit,
+            },
+            http: HttpConfig {
+                max_retries: self.http_max_retries,
+                failure_threshold: self.circuit_breaker_threshold,
+                cooldown_secs: self.circuit_breaker_cooldown,
+                timeout_secs: self.http_timeout,
+                connect_timeout_secs: self.http_connect_timeout,
             },
         }
     }
@@ -96,8 +452,65 @@ impl Config {
         WebhookConfig {
             secret: self.webhook_secret.clone(),
             max_retries: self.max_retries,
+            failure_limit: self.webhook_failure_limit,
+            secret_grace_period_secs: self.webhook_secret_grace_period,
         }
     }
+
+    /// Get protocol registry configuration
+    pub fn registry(&self) -> RegistryConfig {
+        RegistryConfig {
+            path: self.registry_path.clone(),
+            reload_interval_secs: self.registry_reload_interval,
+        }
+    }
+
+    /// Get API authentication configuration
+    pub fn auth(&self) -> AuthConfig {
+        AuthConfig {
+            enabled: self.api_key_auth_enabled,
+            anonymous_read: self.anonymous_read,
+        }
+    }
+
+    /// Get embedding configuration
+    pub fn embedding(&self) -> EmbeddingConfig {
+        EmbeddingConfig {
+            provider: self.embedding_provider,
+            base_url: self.embedding_base_url.clone(),
+            api_key: self.embedding_api_key.clone(),
+            model: self.embedding_model.clone(),
+        }
+    }
+
+    /// Get treasury tracking configuration
+    pub fn treasury(&self) -> TreasuryConfig {
+        TreasuryConfig {
+            rpc_urls: self.rpc_urls.clone(),
+            interval_secs: self.treasury_interval,
+        }
+    }
+
+    /// Get token price configuration
+    pub fn prices(&self) -> PriceConfig {
+        PriceConfig {
+            provider: self.price_provider,
+            base_url: self.price_base_url.clone(),
+            api_key: self.price_api_key.clone(),
+            csv_path: self.price_csv_path.clone(),
+            sync_interval_secs: self.price_sync_interval,
+            history_days: self.price_history_days,
+        }
+    }
+
+    /// Get span export configuration, if an OTLP endpoint is set
+    pub fn otlp(&self) -> Option<OtlpConfig> {
+        self.otlp_endpoint.as_ref().map(|endpoint| OtlpConfig {
+            endpoint: endpoint.clone(),
+            service_name: self.service_name.clone(),
+            sample_ratio: self.otlp_sample_ratio,
+        })
+    }
 }
 
 /// Server configuration
@@ -125,6 +538,8 @@ pub struct DataSourceConfig {
     pub snapshot: SnapshotConfig,
     /// Tally API configuration
     pub tally: TallyConfig,
+    /// Retry and circuit breaker settings shared by all data sources
+    pub http: HttpConfig,
 }
 
 /// Snapshot API configuration
@@ -134,6 +549,8 @@ pub struct SnapshotConfig {
     pub base_url: String,
     /// API key (optional)
     pub api_key: Option<String>,
+    /// Requests per minute sent to the API
+    pub requests_per_minute: u32,
 }
 
 /// Tally API configuration
@@ -143,6 +560,23 @@ pub struct TallyConfig {
     pub base_url: String,
     /// API key (optional)
     pub api_key: Option<String>,
+    /// Requests per minute sent to the API
+    pub requests_per_minute: u32,
+}
+
+/// Data source HTTP client configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpConfig {
+    /// Retries of a request after a 429, 5xx, network error or timeout
+    pub max_retries: u32,
+    /// Consecutive failed requests that open a host's circuit breaker
+    pub failure_threshold: u32,
+    /// Seconds an open circuit breaker rejects requests
+    pub cooldown_secs: u64,
+    /// Seconds a request may take, including reading the response
+    pub timeout_secs: u64,
+    /// Seconds to wait for a connection
+    pub connect_timeout_secs: u64,
 }
 
 /// Webhook configuration
@@ -152,4 +586,96 @@ pub struct WebhookConfig {
     pub secret: String,
     /// Maximum retry attempts
     pub max_retries: u32,
+    /// Failed deliveries in a row after which a webhook is disabled
+    pub failure_limit: u32,
+    /// Seconds a rotated secret keeps signing deliveries
+    pub secret_grace_period_secs: u64,
+}
+
+/// API authentication configuration
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct AuthConfig {
+    /// Whether API requests need an API key
+    pub enabled: bool,
+    /// Whether read endpoints can be used without an API key
+    pub anonymous_read: bool,
+}
+
+/// Protocol registry configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistryConfig {
+    /// Path to the registry file
+    pub path: PathBuf,
+    /// Interval between file change checks, in seconds
+    pub reload_interval_secs: u64,
+}
+
+/// JSON-RPC node of a chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RpcEndpoint {
+    /// EVM chain ID
+    pub chain_id: u64,
+    /// URL of the node
+    pub url: String,
+}
+
+/// Treasury tracking configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreasuryConfig {
+    /// JSON-RPC node per chain, treasuries on other chains are not tracked
+    pub rpc_urls: Vec<RpcEndpoint>,
+    /// Seconds between balance snapshots of a protocol
+    pub interval_secs: u64,
+}
+
+/// Kind of embedding provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum EmbeddingProviderKind {
+    /// OpenAI-compatible HTTP API
+    Openai,
+    /// Deterministic local embeddings, for tests and development
+    Mock,
+}
+
+/// Embedding configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingConfig {
+    /// Provider to use, similarity search is disabled if unset
+    pub provider: Option<EmbeddingProviderKind>,
+    /// Base URL of the OpenAI-compatible API
+    pub base_url: String,
+    /// API key (optional)
+    pub api_key: Option<String>,
+    /// Embedding model
+    pub model: String,
+}
+
+/// Kind of token price provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum PriceProviderKind {
+    /// CoinGecko HTTP API
+    Coingecko,
+    /// CSV file of daily prices
+    Csv,
+    /// Deterministic local prices, for tests and development
+    Mock,
+}
+
+/// Token price configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceConfig {
+    /// Provider to sync prices from, syncing is disabled if unset
+    pub provider: Option<PriceProviderKind>,
+    /// Base URL of the CoinGecko API
+    pub base_url: String,
+    /// CoinGecko API key (optional)
+    pub api_key: Option<String>,
+    /// CSV file read by the CSV provider
+    pub csv_path: Option<PathBuf>,
+    /// Seconds between price syncs
+    pub sync_interval_secs: u64,
+    /// Days of history fetched for a token without any prices
+    pub history_days: u32,
 }
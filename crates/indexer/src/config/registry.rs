@@ -0,0 +1,483 @@
+//! Declarative registry of the protocols tracked by the indexer
+//!
+//! The registry is loaded from a TOML or YAML file listing every DAO the
+//! indexer should crawl, together with the governance sources it uses, and
+//! the tokens whose prices are tracked.
+
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use url::Url;
+
+use crate::{
+    models::{PricedToken, ProtocolId},
+    utils::{
+        id::{is_valid_protocol_slug, ChainId},
+        validation::normalize_address,
+    },
+};
+
+/// Default polling interval for a protocol, in seconds
+pub const DEFAULT_POLLING_INTERVAL_SECS: u64 = 300;
+
+/// Minimum accepted polling interval, in seconds
+pub const MIN_POLLING_INTERVAL_SECS: u64 = 10;
+
+/// Maximum decimals of a token
+///
+/// Balances are 256-bit integers, which have at most 78 digits.
+pub const MAX_TOKEN_DECIMALS: u8 = 77;
+
+/// Errors raised while loading or validating the registry
+#[derive(Error, Debug)]
+pub enum RegistryError {
+    /// Registry file could not be read
+    #[error("Failed to read registry file {path}: {source}")]
+    Io {
+        /// Path of the registry file
+        path: PathBuf,
+        /// Underlying I/O error
+        source: std::io::Error,
+    },
+
+    /// Registry file extension is not supported
+    #[error("Unsupported registry format for {0}, expected .toml, .yaml or .yml")]
+    UnsupportedFormat(PathBuf),
+
+    /// Registry contents could not be parsed
+    #[error("Failed to parse registry: {0}")]
+    Parse(String),
+
+    /// Registry contents are invalid
+    #[error("Invalid registry entry '{protocol}': {message}")]
+    Invalid {
+        /// Registry ID of the offending protocol
+        protocol: String,
+        /// Description of the problem
+        message: String,
+    },
+}
+
+/// Serialization format of a registry file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegistryFormat {
+    /// TOML document
+    Toml,
+    /// YAML document
+    Yaml,
+}
+
+impl RegistryFormat {
+    /// Detect the format from a file extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Some(Self::Toml),
+            Some("yaml") | Some("yml") => Some(Self::Yaml),
+            _ => None,
+        }
+    }
+}
+
+/// Registry-wide defaults applied to every protocol entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegistryDefaults {
+    /// Polling interval used when an entry does not set its own, in seconds
+    #[serde(default = "default_polling_interval")]
+    pub polling_interval_secs: u64,
+}
+
+impl Default for RegistryDefaults {
+    fn default() -> Self {
+        Self {
+            polling_interval_secs: DEFAULT_POLLING_INTERVAL_SECS,
+        }
+    }
+}
+
+fn default_polling_interval() -> u64 {
+    DEFAULT_POLLING_INTERVAL_SECS
+}
+
+/// A single tracked protocol
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProtocolEntry {
+    /// Stable registry identifier (lowercase slug, e.g. `arbitrum`)
+    pub id: String,
+    /// Human readable protocol name
+    pub name: String,
+    /// EVM chain ID the protocol governs
+    pub chain_id: u64,
+    /// Snapshot space IDs (e.g. `arbitrumfoundation.eth`)
+    #[serde(default)]
+    pub snapshot_spaces: Vec<String>,
+    /// Tally organization slug
+    #[serde(default)]
+    pub tally_organization: Option<String>,
+    /// On-chain Governor contract addresses
+    #[serde(default)]
+    pub governors: Vec<String>,
+    /// Timelock contract addresses
+    #[serde(default)]
+    pub timelocks: Vec<String>,
+    /// Governance forum URLs
+    #[serde(default)]
+    pub forum_urls: Vec<String>,
+    /// Addresses holding the DAO's treasury
+    #[serde(default)]
+    pub treasuries: Vec<String>,
+    /// ERC-20 tokens tracked in the treasury, besides the native currency
+    #[serde(default)]
+    pub treasury_tokens: Vec<TreasuryToken>,
+    /// Polling interval override, in seconds
+    #[serde(default)]
+    pub polling_interval_secs: Option<u64>,
+    /// Whether the protocol should be indexed
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+/// ERC-20 token whose treasury balances are tracked
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TreasuryToken {
+    /// Token contract address
+    pub address: String,
+    /// Ticker symbol, e.g. `ARB`
+    pub symbol: String,
+    /// Decimals of the token's base unit
+    pub decimals: u8,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl ProtocolEntry {
+    /// Protocol ID used throughout the indexer for this entry
+    pub fn protocol_id(&self) -> ProtocolId {
+        ProtocolId {
+            chain_id: ChainId::eip155(self.chain_id),
+            protocol: self.id.clone(),
+        }
+    }
+}
+
+/// Token whose USD price is tracked
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TokenEntry {
+    /// EVM chain ID the token lives on
+    pub chain_id: u64,
+    /// Token contract address, `None` for the chain's native currency
+    #[serde(default)]
+    pub address: Option<String>,
+    /// Ticker symbol, e.g. `ARB`
+    pub symbol: String,
+    /// Decimals of the token's base unit
+    pub decimals: u8,
+    /// ID of the asset at the price provider, e.g. the CoinGecko coin ID
+    ///
+    /// Bridged copies of a token share the ID of the original.
+    pub price_id: String,
+}
+
+impl From<&TokenEntry> for PricedToken {
+    fn from(token: &TokenEntry) -> Self {
+        Self {
+            chain_id: token.chain_id,
+            address: token.address.clone(),
+            symbol: token.symbol.clone(),
+            decimals: token.decimals,
+        }
+    }
+}
+
+/// The full set of tracked protocols
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProtocolRegistry {
+    /// Defaults applied to every entry
+    #[serde(default)]
+    pub defaults: RegistryDefaults,
+    /// Tracked protocols
+    #[serde(default)]
+    pub protocols: Vec<ProtocolEntry>,
+    /// Tokens with tracked prices
+    #[serde(default)]
+    pub tokens: Vec<TokenEntry>,
+}
+
+impl ProtocolRegistry {
+    /// Load and validate a registry file
+    pub fn load(path: &Path) -> Result<Self, RegistryError> {
+        let format = RegistryFormat::from_path(path)
+            .ok_or_else(|| RegistryError::UnsupportedFormat(path.to_path_buf()))?;
+        let contents = std::fs::read_to_string(path).map_err(|source| RegistryError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        Self::parse(&contents, format)
+    }
+
+    /// Parse and validate registry contents
+    pub fn parse(contents: &str, format: RegistryFormat) -> Result<Self, RegistryError> {
+        let registry: Self = match format {
+            RegistryFormat::Toml => {
+                toml::from_str(contents).map_err(|e| RegistryError::Parse(e.to_string()))?
+            }
+            RegistryFormat::Yaml => {
+                serde_yaml::from_str(contents).map_err(|e| RegistryError::Parse(e.to_string()))?
+            }
+        };
+
+        registry.validate()?;
+        Ok(registry)
+    }
+
+    /// Validate all entries of the registry
+    pub fn validate(&self) -> Result<(), RegistryError> {
+        if self.defaults.polling_interval_secs < MIN_POLLING_INTERVAL_SECS {
+            return Err(RegistryError::Invalid {
+                protocol: "defaults".to_string(),
+                message: format!(
+                    "polling interval must be at least {MIN_POLLING_INTERVAL_SECS} seconds"
+                ),
+            });
+        }
+
+        let mut ids = HashSet::new();
+        let mut spaces = HashSet::new();
+
+        for entry in &self.protocols {
+            validate_entry(entry)?;
+
+            if !ids.insert(entry.id.as_str()) {
+                return Err(invalid(entry, "duplicate protocol ID"));
+            }
+
+            for space in &entry.snapshot_spaces {
+                if !spaces.insert(space.as_str()) {
+                    return Err(invalid(
+                        entry,
+                        format!("Snapshot space '{space}' is already tracked by another entry"),
+                    ));
+                }
+            }
+        }
+
+        let mut symbols = HashSet::new();
+        // A chain has a single native currency, listed without an address
+        let mut addresses = HashSet::new();
+        for token in &self.tokens {
+            validate_token(token)?;
+
+            if !symbols.insert((token.chain_id, token.symbol.to_lowercase())) {
+                return Err(invalid_token(
+                    token,
+                    "symbol is already listed for the chain",
+                ));
+            }
+            let address = token.address.as_deref().map(str::to_lowercase);
+            if !addresses.insert((token.chain_id, address)) {
+                return Err(invalid_token(
+                    token,
+                    "address or native currency is already listed for the chain",
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Find an entry by its registry ID
+    pub fn get(&self, id: &str) -> Option<&ProtocolEntry> {
+        self.protocols.iter().find(|entry| entry.id == id)
+    }
+
+    /// Find the entry tracking a Snapshot space
+    pub fn find_by_snapshot_space(&self, space: &str) -> Option<&ProtocolEntry> {
+        self.protocols
+            .iter()
+            .find(|entry| entry.snapshot_spaces.iter().any(|s| s == space))
+    }
+
+    /// Find the entry tracking a Tally organization
+    pub fn find_by_tally_organization(&self, organization: &str) -> Option<&ProtocolEntry> {
+        self.protocols
+            .iter()
+            .find(|entry| entry.tally_organization.as_deref() == Some(organization))
+    }
+
+    /// Iterate over enabled entries
+    pub fn enabled(&self) -> impl Iterator<Item = &ProtocolEntry> {
+        self.protocols.iter().filter(|entry| entry.enabled)
+    }
+
+    /// Find a token of a chain by its symbol or contract address
+    ///
+    /// Symbols and addresses are matched case-insensitively.
+    pub fn resolve_token(&self, chain_id: u64, token: &str) -> Option<&TokenEntry> {
+        self.tokens.iter().find(|entry| {
+            entry.chain_id == chain_id
+                && (entry.symbol.eq_ignore_ascii_case(token)
+                    || entry
+                        .address
+                        .as_deref()
+                        .is_some_and(|address| address.eq_ignore_ascii_case(token)))
+        })
+    }
+
+    /// Native currency of a chain, if listed
+    pub fn native_token(&self, chain_id: u64) -> Option<&TokenEntry> {
+        self.tokens
+            .iter()
+            .find(|entry| entry.chain_id == chain_id && entry.address.is_none())
+    }
+
+    /// Effective polling interval for an entry, in seconds
+    pub fn polling_interval_secs(&self, entry: &ProtocolEntry) -> u64 {
+        entry
+            .polling_interval_secs
+            .unwrap_or(self.defaults.polling_interval_secs)
+    }
+}
+
+fn invalid(entry: &ProtocolEntry, message: impl Into<String>) -> RegistryError {
+    RegistryError::Invalid {
+        protocol: entry.id.clone(),
+        message: message.into(),
+    }
+}
+
+fn invalid_token(token: &TokenEntry, message: impl Into<String>) -> RegistryError {
+    RegistryError::Invalid {
+        protocol: format!("token {} on chain {}", token.symbol, token.chain_id),
+        message: message.into(),
+    }
+}
+
+fn validate_token(token: &TokenEntry) -> Result<(), RegistryError> {
+    if token.chain_id == 0 {
+        return Err(invalid_token(token, "chain ID must be greater than zero"));
+    }
+    if token.symbol.trim().is_empty() || token.symbol.chars().any(char::is_whitespace) {
+        return Err(invalid_token(
+            token,
+            "symbol cannot be empty or contain spaces",
+        ));
+    }
+    if token.price_id.trim().is_empty() {
+        return Err(invalid_token(token, "price ID cannot be empty"));
+    }
+    if token.decimals > MAX_TOKEN_DECIMALS {
+        return Err(invalid_token(
+            token,
+            format!("cannot have more than {MAX_TOKEN_DECIMALS} decimals"),
+        ));
+    }
+    if let Some(address) = &token.address {
+        if let Err(e) = normalize_address("address", address) {
+            return Err(invalid_token(
+                token,
+                format!("invalid token address: {}", e.message),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn validate_entry(entry: &ProtocolEntry) -> Result<(), RegistryError> {
+    if !is_valid_protocol_slug(&entry.id) {
+        return Err(invalid(
+            entry,
+            "ID must be a lowercase slug of letters, digits, '-', '_' or '.'",
+        ));
+    }
+
+    if entry.name.trim().is_empty() {
+        return Err(invalid(entry, "name cannot be empty"));
+    }
+
+    if entry.chain_id == 0 {
+        return Err(invalid(entry, "chain ID must be greater than zero"));
+    }
+
+    if entry.snapshot_spaces.is_empty()
+        && entry.tally_organization.is_none()
+        && entry.governors.is_empty()
+    {
+        return Err(invalid(
+            entry,
+            "at least one Snapshot space, Tally organization or Governor is required",
+        ));
+    }
+
+    for space in &entry.snapshot_spaces {
+        if space.trim().is_empty() || space.chars().any(char::is_whitespace) {
+            return Err(invalid(entry, format!("invalid Snapshot space '{space}'")));
+        }
+    }
+
+    if let Some(organization) = &entry.tally_organization {
+        if organization.trim().is_empty() {
+            return Err(invalid(entry, "Tally organization cannot be empty"));
+        }
+    }
+
+    for address in entry
+        .governors
+        .iter()
+        .chain(&entry.timelocks)
+        .chain(&entry.treasuries)
+        .chain(entry.treasury_tokens.iter().map(|token| &token.address))
+    {
+        if let Err(e) = normalize_address("address", address) {
+            return Err(invalid(
+                entry,
+                format!("invalid contract address: {}", e.message),
+            ));
+        }
+    }
+
+    if !entry.treasury_tokens.is_empty() && entry.treasuries.is_empty() {
+        return Err(invalid(entry, "treasury tokens require a treasury address"));
+    }
+
+    for token in &entry.treasury_tokens {
+        if token.symbol.trim().is_empty() {
+            return Err(invalid(
+                entry,
+                format!("treasury token {} needs a symbol", token.address),
+            ));
+        }
+        if token.decimals > MAX_TOKEN_DECIMALS {
+            return Err(invalid(
+                entry,
+                format!(
+                    "treasury token {} cannot have more than {MAX_TOKEN_DECIMALS} decimals",
+                    token.symbol
+                ),
+            ));
+        }
+    }
+
+    for forum in &entry.forum_urls {
+        match Url::parse(forum) {
+            Ok(url) if url.scheme() == "https" || url.scheme() == "http" => {}
+            _ => return Err(invalid(entry, format!("invalid forum URL '{forum}'"))),
+        }
+    }
+
+    if let Some(interval) = entry.polling_interval_secs {
+        if interval < MIN_POLLING_INTERVAL_SECS {
+            return Err(invalid(
+                entry,
+                format!("polling interval must be at least {MIN_POLLING_INTERVAL_SECS} seconds"),
+            ));
+        }
+    }
+
+    Ok(())
+}
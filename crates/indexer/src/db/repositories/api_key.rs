@@ -0,0 +1,168 @@
+//! API key repository for database operations
+
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, PgPool};
+
+use crate::models::{ApiKey, ApiScope};
+
+/// Columns selected when loading API keys
+const KEY_COLUMNS: &str = "id, name, prefix, key_hash, scopes, rate_limit, signing_secret, \
+                           created_at, last_used_at, revoked_at";
+
+/// Database row of the `api_keys` table
+#[derive(FromRow)]
+struct KeyRow {
+    id: i64,
+    name: String,
+    prefix: String,
+    key_hash: String,
+    scopes: Vec<String>,
+    rate_limit: Option<i32>,
+    signing_secret: Option<String>,
+    created_at: DateTime<Utc>,
+    last_used_at: Option<DateTime<Utc>>,
+    revoked_at: Option<DateTime<Utc>>,
+}
+
+/// API key with the secrets needed to verify requests
+#[derive(Debug, Clone)]
+pub struct StoredApiKey {
+    /// The key
+    pub key: ApiKey,
+    /// SHA-256 hash of the key's secret, hex encoded
+    pub key_hash: String,
+    /// Secret of HMAC request signatures
+    pub signing_secret: Option<String>,
+}
+
+impl TryFrom<KeyRow> for StoredApiKey {
+    type Error = sqlx::Error;
+
+    fn try_from(row: KeyRow) -> Result<Self, Self::Error> {
+        let decode = |e: String| {
+            sqlx::Error::Decode(Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                e,
+            )))
+        };
+
+        Ok(StoredApiKey {
+            key: ApiKey {
+                id: row.id,
+                name: row.name,
+                prefix: row.prefix,
+                scopes: row
+                    .scopes
+                    .iter()
+                    .map(|scope| scope.parse::<ApiScope>())
+                    .collect::<Result<_, _>>()
+                    .map_err(decode)?,
+                rate_limit: row.rate_limit,
+                signing: row.signing_secret.is_some(),
+                created_at: row.created_at,
+                last_used_at: row.last_used_at,
+                revoked_at: row.revoked_at,
+            },
+            key_hash: row.key_hash,
+            signing_secret: row.signing_secret,
+        })
+    }
+}
+
+/// New API key to store
+#[derive(Debug, Clone)]
+pub struct NewApiKey<'a> {
+    /// Name of the client
+    pub name: &'a str,
+    /// Public part of the key
+    pub prefix: &'a str,
+    /// SHA-256 hash of the key's secret, hex encoded
+    pub key_hash: &'a str,
+    /// Permissions of the key
+    pub scopes: &'a [ApiScope],
+    /// Requests per minute allowed, unlimited if unset
+    pub rate_limit: Option<i32>,
+    /// Secret of HMAC request signatures
+    pub signing_secret: Option<&'a str>,
+}
+
+/// Repository for API keys
+pub struct ApiKeyRepository {
+    pool: PgPool,
+}
+
+impl ApiKeyRepository {
+    /// Create a new API key repository
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Store a new key
+    pub async fn create(&self, key: &NewApiKey<'_>) -> Result<ApiKey, sqlx::Error> {
+        let query = format!(
+            "INSERT INTO api_keys (name, prefix, key_hash, scopes, rate_limit, signing_secret) \
+             VALUES ($1, $2, $3, $4, $5, $6) RETURNING {KEY_COLUMNS}"
+        );
+        let scopes: Vec<&str> = key.scopes.iter().map(ApiScope::as_str).collect();
+        let row: KeyRow = sqlx::query_as(&query)
+            .bind(key.name)
+            .bind(key.prefix)
+            .bind(key.key_hash)
+            .bind(&scopes)
+            .bind(key.rate_limit)
+            .bind(key.signing_secret)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(StoredApiKey::try_from(row)?.key)
+    }
+
+    /// Key with the given prefix, revoked or not
+    pub async fn find_by_prefix(&self, prefix: &str) -> Result<Option<StoredApiKey>, sqlx::Error> {
+        let query = format!("SELECT {KEY_COLUMNS} FROM api_keys WHERE prefix = $1");
+        let row: Option<KeyRow> = sqlx::query_as(&query)
+            .bind(prefix)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.map(StoredApiKey::try_from).transpose()
+    }
+
+    /// All keys, oldest first
+    pub async fn list(&self) -> Result<Vec<ApiKey>, sqlx::Error> {
+        let query = format!("SELECT {KEY_COLUMNS} FROM api_keys ORDER BY id");
+        let rows: Vec<KeyRow> = sqlx::query_as(&query).fetch_all(&self.pool).await?;
+
+        rows.into_iter()
+            .map(|row| StoredApiKey::try_from(row).map(|stored| stored.key))
+            .collect()
+    }
+
+    /// Revoke a key, returning it unless it does not exist
+    pub async fn revoke(&self, id: i64) -> Result<Option<ApiKey>, sqlx::Error> {
+        let query = format!(
+            "UPDATE api_keys SET revoked_at = COALESCE(revoked_at, NOW()) \
+             WHERE id = $1 RETURNING {KEY_COLUMNS}"
+        );
+        let row: Option<KeyRow> = sqlx::query_as(&query)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.map(|row| StoredApiKey::try_from(row).map(|stored| stored.key))
+            .transpose()
+    }
+
+    /// Record that a key was used, at most once a minute
+    pub async fn touch(&self, id: i64) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE api_keys SET last_used_at = NOW() WHERE id = $1 \
+             AND (last_used_at IS NULL OR last_used_at < NOW() - INTERVAL '1 minute')",
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,197 @@
+//! Backfill job repository for database operations
+
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, PgConnection, PgPool};
+
+use crate::models::{BackfillJob, BackfillSource};
+
+/// Columns selected when loading backfill jobs
+const JOB_COLUMNS: &str = "id, source, target, protocol_id, since, cursor, status, pages, \
+                           fetched, stored, quarantined, error, created_at, updated_at, \
+                           completed_at";
+
+/// Database row of the `backfill_jobs` table
+#[derive(FromRow)]
+struct JobRow {
+    id: i64,
+    source: String,
+    target: String,
+    protocol_id: String,
+    since: DateTime<Utc>,
+    cursor: Option<String>,
+    status: String,
+    pages: i64,
+    fetched: i64,
+    stored: i64,
+    quarantined: i64,
+    error: Option<String>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    completed_at: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<JobRow> for BackfillJob {
+    type Error = sqlx::Error;
+
+    fn try_from(row: JobRow) -> Result<Self, Self::Error> {
+        let decode = |e: String| {
+            sqlx::Error::Decode(Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                e,
+            )))
+        };
+
+        Ok(BackfillJob {
+            id: row.id,
+            source: row.source.parse().map_err(decode)?,
+            target: row.target,
+            protocol_id: row.protocol_id,
+            since: row.since,
+            cursor: row.cursor,
+            status: row.status.parse().map_err(decode)?,
+            pages: row.pages,
+            fetched: row.fetched,
+            stored: row.stored,
+            quarantined: row.quarantined,
+            error: row.error,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+            completed_at: row.completed_at,
+        })
+    }
+}
+
+/// Records counted on a crawled page
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PageProgress {
+    /// Number of records fetched
+    pub fetched: i64,
+    /// Number of records stored as proposals
+    pub stored: i64,
+    /// Number of records sent to quarantine
+    pub quarantined: i64,
+}
+
+/// Repository for backfill jobs
+pub struct BackfillRepository {
+    pool: PgPool,
+}
+
+impl BackfillRepository {
+    /// Create a new backfill repository
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Create a job or pick up an existing one for the same target and start
+    ///
+    /// An existing job keeps its cursor and counters, so it resumes where
+    /// it stopped; a completed job stays completed. With `restart`, the job
+    /// starts over from its first page.
+    pub async fn start(
+        &self,
+        source: BackfillSource,
+        target: &str,
+        protocol_id: &str,
+        since: DateTime<Utc>,
+        restart: bool,
+    ) -> Result<BackfillJob, sqlx::Error> {
+        let query = format!(
+            "INSERT INTO backfill_jobs (source, target, protocol_id, since) \
+             VALUES ($1, $2, $3, $4) \
+             ON CONFLICT (source, target, since) DO UPDATE SET \
+             protocol_id = EXCLUDED.protocol_id, \
+             status = CASE WHEN $5 OR backfill_jobs.status <> 'completed' THEN 'running' \
+                      ELSE backfill_jobs.status END, \
+             cursor = CASE WHEN $5 THEN NULL ELSE backfill_jobs.cursor END, \
+             pages = CASE WHEN $5 THEN 0 ELSE backfill_jobs.pages END, \
+             fetched = CASE WHEN $5 THEN 0 ELSE backfill_jobs.fetched END, \
+             stored = CASE WHEN $5 THEN 0 ELSE backfill_jobs.stored END, \
+             quarantined = CASE WHEN $5 THEN 0 ELSE backfill_jobs.quarantined END, \
+             completed_at = CASE WHEN $5 THEN NULL ELSE backfill_jobs.completed_at END, \
+             error = NULL, \
+             updated_at = NOW() \
+             RETURNING {JOB_COLUMNS}"
+        );
+        let row: JobRow = sqlx::query_as(&query)
+            .bind(source.as_str())
+            .bind(target)
+            .bind(protocol_id)
+            .bind(since)
+            .bind(restart)
+            .fetch_one(&self.pool)
+            .await?;
+
+        row.try_into()
+    }
+
+    /// Lock a job for the rest of the caller's transaction and return its state
+    ///
+    /// Another run of the same job waits here until the page being crawled
+    /// is committed, and then continues from the page after it.
+    pub async fn lock(&self, conn: &mut PgConnection, id: i64) -> Result<BackfillJob, sqlx::Error> {
+        let query = format!("SELECT {JOB_COLUMNS} FROM backfill_jobs WHERE id = $1 FOR UPDATE");
+        let row: JobRow = sqlx::query_as(&query).bind(id).fetch_one(conn).await?;
+
+        row.try_into()
+    }
+
+    /// Record a crawled page as part of the caller's transaction
+    ///
+    /// Without a `next` cursor the job is completed.
+    pub async fn advance(
+        &self,
+        conn: &mut PgConnection,
+        id: i64,
+        next: Option<&str>,
+        progress: PageProgress,
+    ) -> Result<BackfillJob, sqlx::Error> {
+        let query = format!(
+            "UPDATE backfill_jobs SET \
+             cursor = $2, \
+             status = CASE WHEN $2::TEXT IS NULL THEN 'completed' ELSE 'running' END, \
+             completed_at = CASE WHEN $2::TEXT IS NULL THEN NOW() END, \
+             pages = pages + 1, \
+             fetched = fetched + $3, \
+             stored = stored + $4, \
+             quarantined = quarantined + $5, \
+             updated_at = NOW() \
+             WHERE id = $1 \
+             RETURNING {JOB_COLUMNS}"
+        );
+        let row: JobRow = sqlx::query_as(&query)
+            .bind(id)
+            .bind(next)
+            .bind(progress.fetched)
+            .bind(progress.stored)
+            .bind(progress.quarantined)
+            .fetch_one(conn)
+            .await?;
+
+        row.try_into()
+    }
+
+    /// Mark a job as failed, keeping its cursor so it can be resumed
+    pub async fn fail(&self, id: i64, error: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE backfill_jobs SET status = 'failed', error = $2, updated_at = NOW() \
+             WHERE id = $1",
+        )
+        .bind(id)
+        .bind(error)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Jobs that are not completed, oldest first
+    pub async fn unfinished(&self) -> Result<Vec<BackfillJob>, sqlx::Error> {
+        let query = format!(
+            "SELECT {JOB_COLUMNS} FROM backfill_jobs \
+             WHERE status <> 'completed' ORDER BY created_at, id"
+        );
+        let rows: Vec<JobRow> = sqlx::query_as(&query).fetch_all(&self.pool).await?;
+
+        rows.into_iter().map(BackfillJob::try_from).collect()
+    }
+}
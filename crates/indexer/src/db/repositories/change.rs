@@ -0,0 +1,114 @@
+//! Change log repository for database operations
+//!
+//! Every write to a tracked entity appends an entry to the change log in the
+//! same transaction. Appends are serialized with a transaction-level advisory
+//! lock, so entries become visible in sequence order and a consumer reading
+//! past its last sequence number never misses a change committed later. The
+//! sequence may have gaps left by rolled back transactions.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::{types::Json, FromRow, PgConnection, PgPool};
+
+use crate::models::change::{Change, ChangeOperation, EntityType};
+
+/// Key of the advisory lock serializing change log appends
+const CHANGE_LOG_LOCK: i64 = 0x5745_495f_4348_4e47;
+
+/// Database row of the `change_log` table
+#[derive(FromRow)]
+struct ChangeRow {
+    seq: i64,
+    entity_type: String,
+    entity_id: String,
+    operation: String,
+    data: Option<Json<Value>>,
+    changed_at: DateTime<Utc>,
+}
+
+impl TryFrom<ChangeRow> for Change {
+    type Error = sqlx::Error;
+
+    fn try_from(row: ChangeRow) -> Result<Self, Self::Error> {
+        let decode = |e: String| {
+            sqlx::Error::Decode(Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                e,
+            )))
+        };
+
+        Ok(Change {
+            seq: row.seq,
+            entity_type: row.entity_type.parse().map_err(decode)?,
+            entity_id: row.entity_id,
+            operation: row.operation.parse().map_err(decode)?,
+            data: row.data.map(|data| data.0),
+            changed_at: row.changed_at,
+        })
+    }
+}
+
+/// Append a change to the log as part of the caller's transaction
+///
+/// `data` is the entity after the change, or `None` to record a deletion.
+/// Returns the sequence number of the change.
+pub(crate) async fn record_change<T: Serialize + Sync>(
+    conn: &mut PgConnection,
+    entity_type: EntityType,
+    entity_id: &str,
+    data: Option<&T>,
+) -> Result<i64, sqlx::Error> {
+    sqlx::query("SELECT pg_advisory_xact_lock($1)")
+        .bind(CHANGE_LOG_LOCK)
+        .execute(&mut *conn)
+        .await?;
+
+    let operation = match data {
+        Some(_) => ChangeOperation::Upsert,
+        None => ChangeOperation::Delete,
+    };
+    sqlx::query_scalar(
+        "INSERT INTO change_log (entity_type, entity_id, operation, data) \
+         VALUES ($1, $2, $3, $4) RETURNING seq",
+    )
+    .bind(entity_type.as_str())
+    .bind(entity_id)
+    .bind(operation.as_str())
+    .bind(data.map(Json))
+    .fetch_one(conn)
+    .await
+}
+
+/// Repository for reading the change log
+pub struct ChangeRepository {
+    pool: PgPool,
+}
+
+impl ChangeRepository {
+    /// Create a new change log repository
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Changes with a sequence number above `since`, in order
+    pub async fn since(
+        &self,
+        since: i64,
+        entity_type: Option<EntityType>,
+        limit: i64,
+    ) -> Result<Vec<Change>, sqlx::Error> {
+        let rows: Vec<ChangeRow> = sqlx::query_as(
+            "SELECT seq, entity_type, entity_id, operation, data, changed_at FROM change_log \
+             WHERE seq > $1 AND ($2::text IS NULL OR entity_type = $2) \
+             ORDER BY seq LIMIT $3",
+        )
+        .bind(since)
+        .bind(entity_type.map(|t| t.as_str()))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(Change::try_from).collect()
+    }
+}
@@ -0,0 +1,277 @@
+//! Proposal embedding repository for database operations
+//!
+//! Vectors are sent to pgvector in its text format (`[1,2,3]`) and cast in
+//! SQL, so no client-side vector type is needed.
+
+use sqlx::{Executor, PgPool};
+
+use crate::utils::id::ProposalId;
+
+/// Number of nearest chunks considered per query vector and requested match
+///
+/// Several chunks of the same proposal can be among the nearest ones, so more
+/// chunks than proposals are fetched before grouping them by proposal.
+const CANDIDATES_PER_MATCH: i64 = 4;
+
+/// An embedded chunk of proposal text
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmbeddedChunk {
+    /// Text of the chunk
+    pub content: String,
+    /// Embedding of the text
+    pub embedding: Vec<f32>,
+}
+
+/// A proposal similar to a query, with its cosine similarity
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimilarityMatch {
+    /// Canonical ID of the matching proposal
+    pub proposal_id: ProposalId,
+    /// Cosine similarity of the closest chunk, 1 for identical directions
+    pub similarity: f64,
+}
+
+/// Key of the advisory lock serializing replicas creating the table
+const SCHEMA_LOCK: i64 = 0x5745_495f_454d_4244;
+
+/// Embeddings table and index, created when pgvector is available
+const SCHEMA: &str = r#"
+CREATE EXTENSION IF NOT EXISTS vector;
+
+CREATE TABLE IF NOT EXISTS proposal_embeddings (
+    proposal_id TEXT NOT NULL REFERENCES proposals(id) ON DELETE CASCADE,
+    chunk INTEGER NOT NULL,
+    model VARCHAR(128) NOT NULL,
+    content_hash CHAR(64) NOT NULL,
+    content TEXT NOT NULL,
+    embedding vector(1536) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (proposal_id, chunk)
+);
+
+CREATE INDEX IF NOT EXISTS idx_proposal_embeddings_hnsw
+    ON proposal_embeddings USING hnsw (embedding vector_cosine_ops);
+"#;
+
+/// Format a vector as a pgvector literal
+fn vector_literal(vector: &[f32]) -> String {
+    let values: Vec<String> = vector.iter().map(f32::to_string).collect();
+    format!("[{}]", values.join(","))
+}
+
+/// Repository for proposal embeddings
+pub struct EmbeddingRepository {
+    pool: PgPool,
+}
+
+impl EmbeddingRepository {
+    /// Create a new embedding repository
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Create the embeddings table if pgvector is available
+    ///
+    /// Safe to run on every start. Returns whether the table exists, which
+    /// is `false` on servers without pgvector.
+    pub async fn ensure_table(&self) -> Result<bool, sqlx::Error> {
+        let available: bool = sqlx::query_scalar(
+            "SELECT EXISTS (SELECT 1 FROM pg_available_extensions WHERE name = 'vector')",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        if !available {
+            return Ok(false);
+        }
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("SELECT pg_advisory_xact_lock($1)")
+            .bind(SCHEMA_LOCK)
+            .execute(&mut *tx)
+            .await?;
+        tx.execute(SCHEMA).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Content hash of the stored embeddings of a proposal for a model
+    pub async fn content_hash(
+        &self,
+        proposal_id: &ProposalId,
+        model: &str,
+    ) -> Result<Option<String>, sqlx::Error> {
+        sqlx::query_scalar(
+            "SELECT content_hash FROM proposal_embeddings \
+             WHERE proposal_id = $1 AND model = $2 LIMIT 1",
+        )
+        .bind(proposal_id.to_string())
+        .bind(model)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Replace the embeddings of a proposal
+    pub async fn replace(
+        &self,
+        proposal_id: &ProposalId,
+        model: &str,
+        content_hash: &str,
+        chunks: &[EmbeddedChunk],
+    ) -> Result<(), sqlx::Error> {
+        let proposal_id = proposal_id.to_string();
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM proposal_embeddings WHERE proposal_id = $1")
+            .bind(&proposal_id)
+            .execute(&mut *tx)
+            .await?;
+        for (index, chunk) in chunks.iter().enumerate() {
+            sqlx::query(
+                "INSERT INTO proposal_embeddings \
+                 (proposal_id, chunk, model, content_hash, content, embedding) \
+                 VALUES ($1, $2, $3, $4, $5, $6::vector)",
+            )
+            .bind(&proposal_id)
+            .bind(index as i32)
+            .bind(model)
+            .bind(content_hash)
+            .bind(&chunk.content)
+            .bind(vector_literal(&chunk.embedding))
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await
+    }
+
+    /// Proposals after `after` without up-to-date embeddings for a model, by ID
+    ///
+    /// Embeddings are stale when they were computed for another model or for
+    /// an older revision of the proposal.
+    pub async fn find_stale(
+        &self,
+        model: &str,
+        after: Option<&ProposalId>,
+        limit: i64,
+    ) -> Result<Vec<ProposalId>, sqlx::Error> {
+        let ids: Vec<String> = sqlx::query_scalar(
+            r#"
+            SELECT p.id
+            FROM proposals p
+            LEFT JOIN LATERAL (
+                SELECT content_hash FROM proposal_revisions r
+                WHERE r.proposal_id = p.id
+                ORDER BY r.revision DESC
+                LIMIT 1
+            ) latest ON TRUE
+            WHERE ($2::TEXT IS NULL OR p.id > $2)
+              AND NOT EXISTS (
+                  SELECT 1 FROM proposal_embeddings e
+                  WHERE e.proposal_id = p.id
+                    AND e.model = $1
+                    AND (latest.content_hash IS NULL OR e.content_hash = latest.content_hash)
+              )
+            ORDER BY p.id
+            LIMIT $3
+            "#,
+        )
+        .bind(model)
+        .bind(after.map(ProposalId::to_string))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        ids.into_iter()
+            .map(|id| id.parse().map_err(|e| sqlx::Error::Decode(Box::new(e))))
+            .collect()
+    }
+
+    /// Proposals closest to the stored embeddings of a proposal, excluding itself
+    pub async fn similar_to_proposal(
+        &self,
+        proposal_id: &ProposalId,
+        model: &str,
+        limit: i64,
+    ) -> Result<Vec<SimilarityMatch>, sqlx::Error> {
+        let rows: Vec<(String, f64)> = sqlx::query_as(
+            r#"
+            WITH query AS (
+                SELECT embedding FROM proposal_embeddings
+                WHERE proposal_id = $1 AND model = $2
+            )
+            SELECT nearest.proposal_id, MIN(nearest.distance)::FLOAT8 AS distance
+            FROM query
+            CROSS JOIN LATERAL (
+                SELECT e.proposal_id, e.embedding <=> query.embedding AS distance
+                FROM proposal_embeddings e
+                WHERE e.model = $2 AND e.proposal_id <> $1
+                ORDER BY e.embedding <=> query.embedding
+                LIMIT $3
+            ) nearest
+            GROUP BY nearest.proposal_id
+            ORDER BY distance, nearest.proposal_id
+            LIMIT $4
+            "#,
+        )
+        .bind(proposal_id.to_string())
+        .bind(model)
+        .bind(limit * CANDIDATES_PER_MATCH)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        into_matches(rows)
+    }
+
+    /// Proposals closest to any of the given vectors
+    pub async fn similar_to_vectors(
+        &self,
+        vectors: &[Vec<f32>],
+        model: &str,
+        exclude: Option<&ProposalId>,
+        limit: i64,
+    ) -> Result<Vec<SimilarityMatch>, sqlx::Error> {
+        let vectors: Vec<String> = vectors.iter().map(|v| vector_literal(v)).collect();
+        let rows: Vec<(String, f64)> = sqlx::query_as(
+            r#"
+            WITH query AS (
+                SELECT literal::vector AS embedding FROM unnest($1::TEXT[]) AS literal
+            )
+            SELECT nearest.proposal_id, MIN(nearest.distance)::FLOAT8 AS distance
+            FROM query
+            CROSS JOIN LATERAL (
+                SELECT e.proposal_id, e.embedding <=> query.embedding AS distance
+                FROM proposal_embeddings e
+                WHERE e.model = $2 AND ($3::TEXT IS NULL OR e.proposal_id <> $3)
+                ORDER BY e.embedding <=> query.embedding
+                LIMIT $4
+            ) nearest
+            GROUP BY nearest.proposal_id
+            ORDER BY distance, nearest.proposal_id
+            LIMIT $5
+            "#,
+        )
+        .bind(vectors)
+        .bind(model)
+        .bind(exclude.map(ProposalId::to_string))
+        .bind(limit * CANDIDATES_PER_MATCH)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        into_matches(rows)
+    }
+}
+
+fn into_matches(rows: Vec<(String, f64)>) -> Result<Vec<SimilarityMatch>, sqlx::Error> {
+    rows.into_iter()
+        .map(|(proposal_id, distance)| {
+            Ok(SimilarityMatch {
+                proposal_id: proposal_id
+                    .parse()
+                    .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+                similarity: 1.0 - distance,
+            })
+        })
+        .collect()
+}
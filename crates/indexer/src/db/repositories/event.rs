@@ -0,0 +1,85 @@
+//! Event log repository for database operations
+//!
+//! Events are appended in the same transaction as the write they describe,
+//! so an event is logged if and only if its change is committed. Appends
+//! are serialized with a transaction-level advisory lock: events become
+//! visible in ID order, and a reader that sees an event can be sure every
+//! earlier ID is either visible too or was rolled back.
+
+use sqlx::{types::Json, PgConnection, PgPool};
+
+use crate::models::{EventEnvelope, EventFilter, IndexerEvent};
+
+/// Key of the advisory lock serializing event log appends
+const EVENT_LOG_LOCK: i64 = 0x5745_495f_4556_4e54;
+
+/// Append an event to the log as part of the caller's transaction
+pub(crate) async fn record_event(
+    conn: &mut PgConnection,
+    event: &IndexerEvent,
+) -> Result<EventEnvelope, sqlx::Error> {
+    sqlx::query("SELECT pg_advisory_xact_lock($1)")
+        .bind(EVENT_LOG_LOCK)
+        .execute(&mut *conn)
+        .await?;
+
+    let id: i64 = sqlx::query_scalar(
+        "INSERT INTO events (event_type, protocol_id, proposal_id, payload) \
+         VALUES ($1, $2, $3, $4) RETURNING id",
+    )
+    .bind(event.event_type())
+    .bind(event.protocol_id().to_string())
+    .bind(event.proposal_id().to_string())
+    .bind(Json(event))
+    .fetch_one(conn)
+    .await?;
+
+    Ok(EventEnvelope::new(id, event.clone()))
+}
+
+/// Repository for the persistent event log
+pub struct EventRepository {
+    pool: PgPool,
+}
+
+impl EventRepository {
+    /// Create a new event repository
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// ID of the latest logged event, 0 if the log is empty
+    pub async fn latest_id(&self) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar("SELECT COALESCE(MAX(id), 0) FROM events")
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    /// Events logged after `after_id` matching `filter`, oldest first
+    pub async fn after(
+        &self,
+        after_id: i64,
+        filter: &EventFilter,
+        limit: i64,
+    ) -> Result<Vec<EventEnvelope>, sqlx::Error> {
+        let protocols: Vec<String> = filter.protocols.iter().map(ToString::to_string).collect();
+        let rows: Vec<(i64, Json<IndexerEvent>)> = sqlx::query_as(
+            "SELECT id, payload FROM events \
+             WHERE id > $1 \
+               AND (cardinality($2::text[]) = 0 OR protocol_id = ANY($2)) \
+               AND (cardinality($3::text[]) = 0 OR event_type = ANY($3)) \
+             ORDER BY id LIMIT $4",
+        )
+        .bind(after_id)
+        .bind(&protocols)
+        .bind(&filter.types)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(id, event)| EventEnvelope::new(id, event.0))
+            .collect())
+    }
+}
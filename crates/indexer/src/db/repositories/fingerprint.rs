@@ -0,0 +1,228 @@
+//! Proposal fingerprint repository for database operations
+
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, PgPool};
+
+use crate::utils::{fingerprint::Fingerprint, id::ProposalId};
+
+/// Maximum number of candidate near-duplicates loaded for a proposal
+const MAX_CANDIDATES: i64 = 200;
+
+/// Stored fingerprint of a proposal
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredFingerprint {
+    /// Fingerprint of the proposal text, `None` if the text is too short
+    pub fingerprint: Option<Fingerprint>,
+    /// Whether the Snapshot space is verified, `None` if unknown or not on Snapshot
+    pub space_verified: Option<bool>,
+}
+
+/// A proposal sharing a band key with another one
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateCandidate {
+    /// Canonical ID of the proposal
+    pub proposal_id: ProposalId,
+    /// Creation time of the proposal
+    pub created_at: DateTime<Utc>,
+    /// Proposal the candidate is a near-duplicate of
+    pub is_duplicate_of: Option<ProposalId>,
+    /// Fingerprint of the proposal text
+    pub fingerprint: Fingerprint,
+}
+
+/// Database row of the `proposal_fingerprints` table
+#[derive(FromRow)]
+struct FingerprintRow {
+    simhash: Option<i64>,
+    minhash: Option<Vec<i64>>,
+    space_verified: Option<bool>,
+}
+
+/// Database row of a candidate near-duplicate
+#[derive(FromRow)]
+struct CandidateRow {
+    id: String,
+    created_at: DateTime<Utc>,
+    is_duplicate_of: Option<String>,
+    simhash: i64,
+    minhash: Vec<i64>,
+}
+
+fn decode_id(id: String) -> Result<ProposalId, sqlx::Error> {
+    id.parse().map_err(|e| sqlx::Error::Decode(Box::new(e)))
+}
+
+fn into_fingerprint(simhash: i64, minhash: Vec<i64>) -> Fingerprint {
+    Fingerprint {
+        simhash: simhash as u64,
+        minhash: minhash.into_iter().map(|value| value as u64).collect(),
+    }
+}
+
+impl From<FingerprintRow> for StoredFingerprint {
+    fn from(row: FingerprintRow) -> Self {
+        Self {
+            fingerprint: row
+                .simhash
+                .zip(row.minhash)
+                .map(|(simhash, minhash)| into_fingerprint(simhash, minhash)),
+            space_verified: row.space_verified,
+        }
+    }
+}
+
+impl TryFrom<CandidateRow> for DuplicateCandidate {
+    type Error = sqlx::Error;
+
+    fn try_from(row: CandidateRow) -> Result<Self, Self::Error> {
+        Ok(Self {
+            proposal_id: decode_id(row.id)?,
+            created_at: row.created_at,
+            is_duplicate_of: row.is_duplicate_of.map(decode_id).transpose()?,
+            fingerprint: into_fingerprint(row.simhash, row.minhash),
+        })
+    }
+}
+
+/// Repository for proposal fingerprints
+pub struct FingerprintRepository {
+    pool: PgPool,
+}
+
+impl FingerprintRepository {
+    /// Create a new fingerprint repository
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Replace the fingerprint and band keys of a proposal
+    pub async fn save(
+        &self,
+        proposal_id: &ProposalId,
+        stored: &StoredFingerprint,
+    ) -> Result<(), sqlx::Error> {
+        let proposal_id = proposal_id.to_string();
+        let fingerprint = stored.fingerprint.as_ref();
+        let (bands, keys): (Vec<i16>, Vec<i64>) = fingerprint
+            .map(Fingerprint::band_keys)
+            .unwrap_or_default()
+            .into_iter()
+            .unzip();
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO proposal_fingerprints (proposal_id, simhash, minhash, space_verified)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (proposal_id)
+            DO UPDATE SET
+                simhash = EXCLUDED.simhash,
+                minhash = EXCLUDED.minhash,
+                space_verified = EXCLUDED.space_verified,
+                updated_at = NOW()
+            "#,
+        )
+        .bind(&proposal_id)
+        .bind(fingerprint.map(|f| f.simhash as i64))
+        .bind(fingerprint.map(|f| f.minhash.iter().map(|v| *v as i64).collect::<Vec<_>>()))
+        .bind(stored.space_verified)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("DELETE FROM proposal_fingerprint_bands WHERE proposal_id = $1")
+            .bind(&proposal_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "INSERT INTO proposal_fingerprint_bands (proposal_id, band, key) \
+             SELECT $1, band, key FROM unnest($2::SMALLINT[], $3::BIGINT[]) AS k(band, key)",
+        )
+        .bind(&proposal_id)
+        .bind(bands)
+        .bind(keys)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await
+    }
+
+    /// Find the stored fingerprint of a proposal
+    pub async fn find(
+        &self,
+        proposal_id: &ProposalId,
+    ) -> Result<Option<StoredFingerprint>, sqlx::Error> {
+        let row: Option<FingerprintRow> = sqlx::query_as(
+            "SELECT simhash, minhash, space_verified FROM proposal_fingerprints \
+             WHERE proposal_id = $1",
+        )
+        .bind(proposal_id.to_string())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(StoredFingerprint::from))
+    }
+
+    /// Other proposals sharing at least one band key with a fingerprint, oldest first
+    pub async fn candidates(
+        &self,
+        proposal_id: &ProposalId,
+        fingerprint: &Fingerprint,
+    ) -> Result<Vec<DuplicateCandidate>, sqlx::Error> {
+        let (bands, keys): (Vec<i16>, Vec<i64>) = fingerprint.band_keys().into_iter().unzip();
+        let rows: Vec<CandidateRow> = sqlx::query_as(
+            r#"
+            SELECT p.id, p.created_at, p.is_duplicate_of, f.simhash, f.minhash
+            FROM proposals p
+            JOIN proposal_fingerprints f ON f.proposal_id = p.id
+            WHERE p.id <> $1
+              AND p.created_at IS NOT NULL
+              AND f.simhash IS NOT NULL
+              AND EXISTS (
+                  SELECT 1
+                  FROM proposal_fingerprint_bands b
+                  JOIN unnest($2::SMALLINT[], $3::BIGINT[]) AS k(band, key)
+                    ON b.band = k.band AND b.key = k.key
+                  WHERE b.proposal_id = p.id
+              )
+            ORDER BY p.created_at, p.id
+            LIMIT $4
+            "#,
+        )
+        .bind(proposal_id.to_string())
+        .bind(bands)
+        .bind(keys)
+        .bind(MAX_CANDIDATES)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(DuplicateCandidate::try_from).collect()
+    }
+
+    /// Proposals after `after` without a fingerprint of their latest revision, by ID
+    pub async fn find_unassessed(
+        &self,
+        after: Option<&ProposalId>,
+        limit: i64,
+    ) -> Result<Vec<ProposalId>, sqlx::Error> {
+        let ids: Vec<String> = sqlx::query_scalar(
+            "SELECT p.id FROM proposals p \
+             WHERE ($1::TEXT IS NULL OR p.id > $1) \
+               AND NOT EXISTS ( \
+                   SELECT 1 FROM proposal_fingerprints f \
+                   WHERE f.proposal_id = p.id \
+                     AND f.updated_at >= COALESCE( \
+                         (SELECT MAX(r.created_at) FROM proposal_revisions r \
+                          WHERE r.proposal_id = p.id), \
+                         '-infinity' \
+                     ) \
+               ) \
+             ORDER BY p.id LIMIT $2",
+        )
+        .bind(after.map(ProposalId::to_string))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        ids.into_iter().map(decode_id).collect()
+    }
+}
@@ -0,0 +1,307 @@
+//! Proposal label repository for database operations
+
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, PgPool};
+
+use crate::{
+    models::{
+        label::{LabelCriteria, LabelResolution},
+        LabelValue, ProposalLabel, ProtocolId,
+    },
+    utils::id::ProposalId,
+};
+
+const LABEL_COLUMNS: &str = "proposal_id, labeler, label, goals_and_motivation, \
+                             measurable_outcomes, budget, technical_specifications, \
+                             language_quality, rationale, created_at";
+
+const RESOLUTION_COLUMNS: &str = "proposal_id, label, goals_and_motivation, \
+                                  measurable_outcomes, budget, technical_specifications, \
+                                  language_quality, resolved_by, rationale, resolved_at";
+
+/// Per-criterion columns shared by labels and resolutions
+#[derive(FromRow)]
+struct CriteriaColumns {
+    goals_and_motivation: String,
+    measurable_outcomes: String,
+    budget: String,
+    technical_specifications: String,
+    language_quality: String,
+}
+
+impl TryFrom<CriteriaColumns> for LabelCriteria {
+    type Error = sqlx::Error;
+
+    fn try_from(row: CriteriaColumns) -> Result<Self, Self::Error> {
+        let decode = |status: String| {
+            status.parse().map_err(|e: String| {
+                sqlx::Error::Decode(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    e,
+                )))
+            })
+        };
+
+        Ok(LabelCriteria {
+            goals_and_motivation: decode(row.goals_and_motivation)?,
+            measurable_outcomes: decode(row.measurable_outcomes)?,
+            budget: decode(row.budget)?,
+            technical_specifications: decode(row.technical_specifications)?,
+            language_quality: decode(row.language_quality)?,
+        })
+    }
+}
+
+/// Database row of the `proposal_labels` table
+#[derive(FromRow)]
+struct LabelRow {
+    proposal_id: String,
+    labeler: String,
+    label: String,
+    #[sqlx(flatten)]
+    criteria: CriteriaColumns,
+    rationale: Option<String>,
+    created_at: DateTime<Utc>,
+}
+
+impl TryFrom<LabelRow> for ProposalLabel {
+    type Error = sqlx::Error;
+
+    fn try_from(row: LabelRow) -> Result<Self, Self::Error> {
+        let decode = |e: String| {
+            sqlx::Error::Decode(Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                e,
+            )))
+        };
+
+        Ok(ProposalLabel {
+            proposal_id: row
+                .proposal_id
+                .parse()
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            labeler: row.labeler,
+            label: row.label.parse().map_err(decode)?,
+            criteria: row.criteria.try_into()?,
+            rationale: row.rationale,
+            created_at: row.created_at,
+        })
+    }
+}
+
+/// Database row of the `proposal_label_resolutions` table
+#[derive(FromRow)]
+struct ResolutionRow {
+    proposal_id: String,
+    label: String,
+    #[sqlx(flatten)]
+    criteria: CriteriaColumns,
+    resolved_by: String,
+    rationale: Option<String>,
+    resolved_at: DateTime<Utc>,
+}
+
+impl TryFrom<ResolutionRow> for LabelResolution {
+    type Error = sqlx::Error;
+
+    fn try_from(row: ResolutionRow) -> Result<Self, Self::Error> {
+        let decode = |e: String| {
+            sqlx::Error::Decode(Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                e,
+            )))
+        };
+
+        Ok(LabelResolution {
+            proposal_id: row
+                .proposal_id
+                .parse()
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            label: row.label.parse().map_err(decode)?,
+            criteria: row.criteria.try_into()?,
+            resolved_by: row.resolved_by,
+            rationale: row.rationale,
+            resolved_at: row.resolved_at,
+        })
+    }
+}
+
+/// Repository for human proposal labels
+pub struct LabelRepository {
+    pool: PgPool,
+}
+
+impl LabelRepository {
+    /// Create a new label repository
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// All labels, by proposal and oldest first
+    pub async fn all(&self) -> Result<Vec<ProposalLabel>, sqlx::Error> {
+        let query = format!(
+            "SELECT {LABEL_COLUMNS} FROM proposal_labels ORDER BY proposal_id, created_at, id"
+        );
+        let rows: Vec<LabelRow> = sqlx::query_as(&query).fetch_all(&self.pool).await?;
+
+        rows.into_iter().map(ProposalLabel::try_from).collect()
+    }
+
+    /// Labels of a proposal, oldest first
+    pub async fn for_proposal(&self, id: &ProposalId) -> Result<Vec<ProposalLabel>, sqlx::Error> {
+        let query = format!(
+            "SELECT {LABEL_COLUMNS} FROM proposal_labels \
+             WHERE proposal_id = $1 ORDER BY created_at, id"
+        );
+        let rows: Vec<LabelRow> = sqlx::query_as(&query)
+            .bind(id.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.into_iter().map(ProposalLabel::try_from).collect()
+    }
+
+    /// Save a label, replacing an earlier label of the same labeler
+    pub async fn save(
+        &self,
+        id: &ProposalId,
+        labeler: &str,
+        label: LabelValue,
+        criteria: &LabelCriteria,
+        rationale: Option<&str>,
+    ) -> Result<ProposalLabel, sqlx::Error> {
+        let query = format!(
+            "INSERT INTO proposal_labels \
+             (proposal_id, labeler, label, goals_and_motivation, measurable_outcomes, budget, \
+              technical_specifications, language_quality, rationale) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) \
+             ON CONFLICT (proposal_id, labeler) DO UPDATE SET \
+             label = EXCLUDED.label, \
+             goals_and_motivation = EXCLUDED.goals_and_motivation, \
+             measurable_outcomes = EXCLUDED.measurable_outcomes, \
+             budget = EXCLUDED.budget, \
+             technical_specifications = EXCLUDED.technical_specifications, \
+             language_quality = EXCLUDED.language_quality, \
+             rationale = EXCLUDED.rationale, \
+             created_at = NOW() \
+             RETURNING {LABEL_COLUMNS}"
+        );
+        let row: LabelRow = sqlx::query_as(&query)
+            .bind(id.to_string())
+            .bind(labeler)
+            .bind(label.as_str())
+            .bind(criteria.goals_and_motivation.as_str())
+            .bind(criteria.measurable_outcomes.as_str())
+            .bind(criteria.budget.as_str())
+            .bind(criteria.technical_specifications.as_str())
+            .bind(criteria.language_quality.as_str())
+            .bind(rationale)
+            .fetch_one(&self.pool)
+            .await?;
+
+        row.try_into()
+    }
+
+    /// Next proposal for a labeler, `None` once nothing is left to label
+    ///
+    /// Skips near-duplicates, resolved proposals, proposals the labeler has
+    /// labeled and proposals with `target` labels. Proposals labeled by
+    /// others come first so that labelers overlap for agreement reporting.
+    pub async fn next_unlabeled(
+        &self,
+        labeler: &str,
+        protocol_id: Option<&ProtocolId>,
+        target: i64,
+    ) -> Result<Option<ProposalId>, sqlx::Error> {
+        let id: Option<String> = sqlx::query_scalar(
+            "SELECT p.id FROM proposals p \
+             LEFT JOIN (SELECT proposal_id, COUNT(*) AS labels FROM proposal_labels \
+                        GROUP BY proposal_id) l ON l.proposal_id = p.id \
+             WHERE p.is_duplicate_of IS NULL \
+               AND COALESCE(l.labels, 0) < $2 \
+               AND ($3::TEXT IS NULL OR starts_with(p.id, $3)) \
+               AND NOT EXISTS (SELECT 1 FROM proposal_labels pl \
+                               WHERE pl.proposal_id = p.id AND pl.labeler = $1) \
+               AND NOT EXISTS (SELECT 1 FROM proposal_label_resolutions r \
+                               WHERE r.proposal_id = p.id) \
+             ORDER BY COALESCE(l.labels, 0) DESC, p.created_at DESC, p.id \
+             LIMIT 1",
+        )
+        .bind(labeler)
+        .bind(target)
+        .bind(protocol_id.map(|protocol_id| format!("{protocol_id}:")))
+        .fetch_optional(&self.pool)
+        .await?;
+
+        id.map(|id| id.parse().map_err(|e| sqlx::Error::Decode(Box::new(e))))
+            .transpose()
+    }
+
+    /// All conflict resolutions, by proposal
+    pub async fn resolutions(&self) -> Result<Vec<LabelResolution>, sqlx::Error> {
+        let query = format!(
+            "SELECT {RESOLUTION_COLUMNS} FROM proposal_label_resolutions ORDER BY proposal_id"
+        );
+        let rows: Vec<ResolutionRow> = sqlx::query_as(&query).fetch_all(&self.pool).await?;
+
+        rows.into_iter().map(LabelResolution::try_from).collect()
+    }
+
+    /// Conflict resolution of a proposal
+    pub async fn resolution(
+        &self,
+        id: &ProposalId,
+    ) -> Result<Option<LabelResolution>, sqlx::Error> {
+        let query = format!(
+            "SELECT {RESOLUTION_COLUMNS} FROM proposal_label_resolutions WHERE proposal_id = $1"
+        );
+        let row: Option<ResolutionRow> = sqlx::query_as(&query)
+            .bind(id.to_string())
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.map(LabelResolution::try_from).transpose()
+    }
+
+    /// Save the final verdict on a proposal, replacing an earlier one
+    pub async fn resolve(
+        &self,
+        id: &ProposalId,
+        label: LabelValue,
+        criteria: &LabelCriteria,
+        resolved_by: &str,
+        rationale: Option<&str>,
+    ) -> Result<LabelResolution, sqlx::Error> {
+        let query = format!(
+            "INSERT INTO proposal_label_resolutions \
+             (proposal_id, label, goals_and_motivation, measurable_outcomes, budget, \
+              technical_specifications, language_quality, resolved_by, rationale) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) \
+             ON CONFLICT (proposal_id) DO UPDATE SET \
+             label = EXCLUDED.label, \
+             goals_and_motivation = EXCLUDED.goals_and_motivation, \
+             measurable_outcomes = EXCLUDED.measurable_outcomes, \
+             budget = EXCLUDED.budget, \
+             technical_specifications = EXCLUDED.technical_specifications, \
+             language_quality = EXCLUDED.language_quality, \
+             resolved_by = EXCLUDED.resolved_by, \
+             rationale = EXCLUDED.rationale, \
+             resolved_at = NOW() \
+             RETURNING {RESOLUTION_COLUMNS}"
+        );
+        let row: ResolutionRow = sqlx::query_as(&query)
+            .bind(id.to_string())
+            .bind(label.as_str())
+            .bind(criteria.goals_and_motivation.as_str())
+            .bind(criteria.measurable_outcomes.as_str())
+            .bind(criteria.budget.as_str())
+            .bind(criteria.technical_specifications.as_str())
+            .bind(criteria.language_quality.as_str())
+            .bind(resolved_by)
+            .bind(rationale)
+            .fetch_one(&self.pool)
+            .await?;
+
+        row.try_into()
+    }
+}
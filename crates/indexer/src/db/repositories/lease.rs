@@ -0,0 +1,109 @@
+//! Data source lease repository for database operations
+
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, PgPool};
+
+use crate::models::SourceLease;
+
+/// Columns selected when loading leases
+const LEASE_COLUMNS: &str = "key, holder, acquired_at, renewed_at, expires_at";
+
+/// Database row of the `source_leases` table
+#[derive(FromRow)]
+struct LeaseRow {
+    key: String,
+    holder: String,
+    acquired_at: DateTime<Utc>,
+    renewed_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+}
+
+impl From<LeaseRow> for SourceLease {
+    fn from(row: LeaseRow) -> Self {
+        SourceLease {
+            key: row.key,
+            holder: row.holder,
+            acquired_at: row.acquired_at,
+            renewed_at: row.renewed_at,
+            expires_at: row.expires_at,
+        }
+    }
+}
+
+/// Repository for data source leases
+pub struct LeaseRepository {
+    pool: PgPool,
+}
+
+impl LeaseRepository {
+    /// Create a new lease repository
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Take or renew a lease for `ttl_secs` seconds
+    ///
+    /// Succeeds when the lease is free, expired or already held by `holder`,
+    /// and returns `None` while another holder's lease is valid. The check
+    /// and the update are a single statement, so two replicas never both
+    /// get the same lease.
+    pub async fn acquire(
+        &self,
+        key: &str,
+        holder: &str,
+        ttl_secs: i64,
+    ) -> Result<Option<SourceLease>, sqlx::Error> {
+        let query = format!(
+            "INSERT INTO source_leases (key, holder, expires_at) \
+             VALUES ($1, $2, NOW() + make_interval(secs => $3)) \
+             ON CONFLICT (key) DO UPDATE SET \
+             holder = EXCLUDED.holder, \
+             acquired_at = CASE WHEN source_leases.holder = EXCLUDED.holder \
+                           THEN source_leases.acquired_at ELSE NOW() END, \
+             renewed_at = NOW(), \
+             expires_at = EXCLUDED.expires_at \
+             WHERE source_leases.holder = EXCLUDED.holder \
+                OR source_leases.expires_at < NOW() \
+             RETURNING {LEASE_COLUMNS}"
+        );
+        let row: Option<LeaseRow> = sqlx::query_as(&query)
+            .bind(key)
+            .bind(holder)
+            .bind(ttl_secs as f64)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(SourceLease::from))
+    }
+
+    /// Give up a lease, returning whether `holder` held it
+    pub async fn release(&self, key: &str, holder: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM source_leases WHERE key = $1 AND holder = $2")
+            .bind(key)
+            .bind(holder)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Give up all leases of a holder, returning how many it held
+    pub async fn release_all(&self, holder: &str) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM source_leases WHERE holder = $1")
+            .bind(holder)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// All unexpired leases, by key
+    pub async fn active(&self) -> Result<Vec<SourceLease>, sqlx::Error> {
+        let query = format!(
+            "SELECT {LEASE_COLUMNS} FROM source_leases WHERE expires_at >= NOW() ORDER BY key"
+        );
+        let rows: Vec<LeaseRow> = sqlx::query_as(&query).fetch_all(&self.pool).await?;
+
+        Ok(rows.into_iter().map(SourceLease::from).collect())
+    }
+}
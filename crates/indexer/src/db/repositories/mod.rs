@@ -5,18 +5,53 @@
 
 /// Actor data repository
 pub mod actor;
+/// API key repository
+pub mod api_key;
+/// Backfill job repository
+pub mod backfill;
+/// Change log repository
+pub mod change;
+/// Proposal embedding repository
+pub mod embedding;
+/// Event log repository
+pub mod event;
+/// Proposal fingerprint repository
+pub mod fingerprint;
+/// Proposal label repository
+pub mod label;
+/// Data source lease repository
+pub mod lease;
+/// Token price repository
+pub mod price;
 /// Proposal data repository
 pub mod proposal;
 /// Protocol data repository
 pub mod protocol;
+/// Quarantined record repository
+pub mod quarantine;
+/// Decoded proposal transfer repository
+pub mod transfer;
+/// Treasury balance repository
+pub mod treasury;
 /// Webhook data repository
 pub mod webhook;
 // TODO: Remove unused imports after development phase
 #[allow(unused_imports)]
 pub use actor::ActorRepository;
+pub use api_key::ApiKeyRepository;
+pub use backfill::BackfillRepository;
+pub use change::ChangeRepository;
+pub use embedding::EmbeddingRepository;
+pub use event::EventRepository;
+pub use fingerprint::FingerprintRepository;
+pub use label::LabelRepository;
+pub use lease::LeaseRepository;
+pub use price::PriceRepository;
 #[allow(unused_imports)]
 pub use proposal::ProposalRepository;
 #[allow(unused_imports)]
 pub use protocol::ProtocolRepository;
-#[allow(unused_imports)]
+pub use quarantine::QuarantineRepository;
+pub use transfer::TransferRepository;
+pub use treasury::TreasuryRepository;
 pub use webhook::WebhookRepository;
@@ -0,0 +1,93 @@
+//! Token price repository for database operations
+
+use chrono::NaiveDate;
+use sqlx::{FromRow, PgPool};
+
+use crate::models::DailyPrice;
+
+/// Database row of the `token_prices` table
+#[derive(FromRow)]
+struct PriceRow {
+    asset: String,
+    day: NaiveDate,
+    price_usd: f64,
+    source: String,
+}
+
+impl From<PriceRow> for DailyPrice {
+    fn from(row: PriceRow) -> Self {
+        DailyPrice {
+            asset: row.asset,
+            day: row.day,
+            price_usd: row.price_usd,
+            source: row.source,
+        }
+    }
+}
+
+/// Repository for daily token prices
+pub struct PriceRepository {
+    pool: PgPool,
+}
+
+impl PriceRepository {
+    /// Create a new price repository
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Store daily prices, replacing earlier prices of the same asset and day
+    ///
+    /// Returns the number of prices stored.
+    pub async fn upsert(&self, prices: &[DailyPrice]) -> Result<u64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let mut stored = 0;
+        for price in prices {
+            stored += sqlx::query(
+                r#"
+                INSERT INTO token_prices (asset, day, price_usd, source)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (asset, day) DO UPDATE SET
+                    price_usd = EXCLUDED.price_usd,
+                    source = EXCLUDED.source,
+                    updated_at = NOW()
+                "#,
+            )
+            .bind(&price.asset)
+            .bind(price.day)
+            .bind(price.price_usd)
+            .bind(&price.source)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+        tx.commit().await?;
+        Ok(stored)
+    }
+
+    /// Latest price of an asset on or before `day`
+    pub async fn at(&self, asset: &str, day: NaiveDate) -> Result<Option<DailyPrice>, sqlx::Error> {
+        let row: Option<PriceRow> = sqlx::query_as(
+            r#"
+            SELECT asset, day, price_usd, source
+            FROM token_prices
+            WHERE asset = $1 AND day <= $2
+            ORDER BY day DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(asset)
+        .bind(day)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.map(DailyPrice::from))
+    }
+
+    /// Latest day an asset has a price for
+    pub async fn latest_day(&self, asset: &str) -> Result<Option<NaiveDate>, sqlx::Error> {
+        sqlx::query_scalar("SELECT MAX(day) FROM token_prices WHERE asset = $1")
+            .bind(asset)
+            .fetch_one(&self.pool)
+            .await
+    }
+}
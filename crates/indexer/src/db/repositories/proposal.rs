@@ -1,7 +1,349 @@
 //! Proposal repository for database operations
 
-use crate::models::Proposal;
-use sqlx::PgPool;
+use chrono::{DateTime, Utc};
+use sqlx::{types::Json, FromRow, PgConnection, PgPool};
+
+use super::{change::record_change, event::record_event};
+use crate::{
+    models::{
+        change::EntityType,
+        proposal::ProposalStatus,
+        revision::{changed_fields, content_hash, ProposalField},
+        EventEnvelope, IndexerEvent, Proposal, ProposalRevision, ProtocolId, StatusChange,
+        StatusTrigger,
+    },
+    utils::id::{ChainId, ProposalId},
+};
+
+/// Column proposal listings are sorted by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProposalOrder {
+    /// Creation time
+    #[default]
+    Created,
+    /// Start of the voting window
+    Start,
+    /// End of the voting window
+    End,
+}
+
+impl ProposalOrder {
+    fn column(&self) -> &'static str {
+        match self {
+            Self::Created => "p.created_at",
+            Self::Start => "p.voting_start",
+            Self::End => "p.voting_end",
+        }
+    }
+}
+
+/// Filter, order and page of a proposal listing
+///
+/// Unset filters match every proposal.
+#[derive(Debug, Clone, Default)]
+pub struct ProposalQuery {
+    /// Only proposals with one of these canonical IDs or Snapshot proposal IDs
+    pub ids: Option<Vec<String>>,
+    /// Only proposals of one of these protocols
+    pub protocols: Option<Vec<ProtocolId>>,
+    /// Only proposals of protocols on one of these chains
+    pub chains: Option<Vec<ChainId>>,
+    /// Only proposals by one of these authors, compared case-insensitively
+    pub authors: Option<Vec<String>>,
+    /// Only proposals with one of these stored statuses
+    pub statuses: Option<Vec<ProposalStatus>>,
+    /// Only proposals whose title contains this text, compared case-insensitively
+    pub title_contains: Option<String>,
+    /// Only proposals created at or after this time
+    pub created_from: Option<DateTime<Utc>>,
+    /// Only proposals created before this time
+    pub created_until: Option<DateTime<Utc>>,
+    /// Only near-duplicates of another proposal if `true`, only originals if `false`
+    pub duplicates: Option<bool>,
+    /// Only proposals with at most this spam score, counting unassessed ones as 0
+    pub max_spam_score: Option<f64>,
+    /// Sort column
+    pub order: ProposalOrder,
+    /// Whether to sort in ascending order
+    pub ascending: bool,
+    /// Number of proposals to skip
+    pub offset: i64,
+    /// Maximum number of proposals to return
+    pub limit: i64,
+}
+
+/// Result of saving a proposal
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SaveOutcome {
+    /// The proposal was not known before
+    Created {
+        /// Revision number of the initial content
+        revision: i32,
+    },
+    /// The content of a known proposal changed
+    Updated {
+        /// Revision number of the new content
+        revision: i32,
+        /// Fields that changed
+        changed_fields: Vec<ProposalField>,
+    },
+    /// The content is identical to the stored version
+    Unchanged,
+}
+
+/// Result of saving a proposal
+#[derive(Debug, Clone, PartialEq)]
+pub struct SavedProposal {
+    /// Whether the content changed
+    pub content: SaveOutcome,
+    /// Status stored, which is the previous one if the new one was not a valid transition
+    pub status: ProposalStatus,
+    /// Status change recorded in the history, if any
+    pub status_change: Option<StatusChange>,
+    /// Whether the scores of a known proposal changed
+    pub votes_changed: bool,
+    /// Events logged with the change, to be published once it is committed
+    pub events: Vec<EventEnvelope>,
+}
+
+/// Result of moving a proposal to a new status
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatusTransition {
+    /// Status change recorded in the history
+    pub change: StatusChange,
+    /// Event logged with the change, if the change emits one
+    pub event: Option<EventEnvelope>,
+}
+
+/// Advisory lock class serializing writers of the same proposal
+const PROPOSAL_LOCK_CLASS: i32 = 0x5745_4950;
+
+/// Columns selected when loading proposals
+const PROPOSAL_COLUMNS: &str = "p.id, p.title, p.description, p.status, p.choices, p.author, \
+                                p.comments, p.created_at, p.updated_at, p.voting_start, \
+                                p.voting_end, p.snapshot_block, p.quorum, p.voting_type, \
+                                p.scores, p.scores_total, p.discussion_url, p.ipfs_hash, \
+                                p.source_url, p.is_duplicate_of, p.spam_score";
+
+/// Database row of the `proposals` table
+#[derive(FromRow)]
+struct ProposalRow {
+    id: String,
+    title: String,
+    description: String,
+    status: String,
+    choices: Option<Json<Vec<String>>>,
+    author: String,
+    comments: Option<Json<Vec<String>>>,
+    created_at: Option<DateTime<Utc>>,
+    updated_at: Option<DateTime<Utc>>,
+    voting_start: Option<DateTime<Utc>>,
+    voting_end: Option<DateTime<Utc>>,
+    snapshot_block: Option<i64>,
+    quorum: Option<f64>,
+    voting_type: String,
+    scores: Option<Json<Vec<f64>>>,
+    scores_total: Option<f64>,
+    discussion_url: Option<String>,
+    ipfs_hash: Option<String>,
+    source_url: Option<String>,
+    is_duplicate_of: Option<String>,
+    spam_score: Option<f64>,
+}
+
+fn decode_error(message: String) -> sqlx::Error {
+    sqlx::Error::Decode(Box::new(std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        message,
+    )))
+}
+
+impl TryFrom<ProposalRow> for Proposal {
+    type Error = sqlx::Error;
+
+    fn try_from(row: ProposalRow) -> Result<Self, Self::Error> {
+        let id: ProposalId = row
+            .id
+            .parse()
+            .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+        let status = row.status.parse().map_err(decode_error)?;
+        let voting_type = row.voting_type.parse().map_err(decode_error)?;
+        let created_at = row.created_at.unwrap_or_else(Utc::now);
+
+        Ok(Proposal {
+            protocol_id: id.protocol_id().clone(),
+            id,
+            title: row.title,
+            description: row.description,
+            status,
+            choices: row.choices.map(|c| c.0).unwrap_or_default(),
+            author: row.author,
+            comments: row.comments.map(|c| c.0).unwrap_or_default(),
+            created_at,
+            updated_at: row.updated_at.unwrap_or(created_at),
+            voting_start: row.voting_start,
+            voting_end: row.voting_end,
+            snapshot_block: row.snapshot_block.map(|block| block as u64),
+            quorum: row.quorum,
+            voting_type,
+            scores: row.scores.map(|s| s.0).unwrap_or_default(),
+            scores_total: row.scores_total,
+            discussion_url: row.discussion_url,
+            ipfs_hash: row.ipfs_hash,
+            source_url: row.source_url,
+            is_duplicate_of: row
+                .is_duplicate_of
+                .map(|id| id.parse())
+                .transpose()
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            spam_score: row.spam_score,
+        })
+    }
+}
+
+/// Convert a row into a proposal with its current status
+///
+/// The stored status is only as fresh as the last crawl or lifecycle timer.
+fn into_current(row: ProposalRow) -> Result<Proposal, sqlx::Error> {
+    let mut proposal = Proposal::try_from(row)?;
+    proposal.status = proposal.status_at(Utc::now());
+    Ok(proposal)
+}
+
+/// Stored proposal with the latest entries of its histories
+#[derive(Debug, Clone)]
+pub struct StoredProposal {
+    /// Proposal with its stored status
+    pub proposal: Proposal,
+    /// Content hash of the latest revision
+    pub revision_hash: Option<String>,
+    /// Status the latest status history entry moved to
+    pub history_status: Option<ProposalStatus>,
+}
+
+/// Database row of a proposal with its latest revision and status change
+#[derive(FromRow)]
+struct StoredProposalRow {
+    #[sqlx(flatten)]
+    proposal: ProposalRow,
+    revision_hash: Option<String>,
+    history_status: Option<String>,
+}
+
+/// Columns selected when loading revisions
+const REVISION_COLUMNS: &str =
+    "proposal_id, revision, title, description, choices, content_hash, edited_at, created_at";
+
+/// Database row of the `proposal_revisions` table
+#[derive(FromRow)]
+struct RevisionRow {
+    proposal_id: String,
+    revision: i32,
+    title: String,
+    description: String,
+    choices: Json<Vec<String>>,
+    content_hash: String,
+    edited_at: Option<DateTime<Utc>>,
+    created_at: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<RevisionRow> for ProposalRevision {
+    type Error = sqlx::Error;
+
+    fn try_from(row: RevisionRow) -> Result<Self, Self::Error> {
+        Ok(ProposalRevision {
+            proposal_id: row
+                .proposal_id
+                .parse()
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            revision: row.revision,
+            title: row.title,
+            description: row.description,
+            choices: row.choices.0,
+            content_hash: row.content_hash,
+            edited_at: row.edited_at,
+            created_at: row.created_at.unwrap_or_else(Utc::now),
+        })
+    }
+}
+
+/// Database row of the `proposal_status_history` table
+#[derive(FromRow)]
+struct StatusChangeRow {
+    proposal_id: String,
+    from_status: Option<String>,
+    to_status: String,
+    triggered_by: String,
+    changed_at: DateTime<Utc>,
+}
+
+impl TryFrom<StatusChangeRow> for StatusChange {
+    type Error = sqlx::Error;
+
+    fn try_from(row: StatusChangeRow) -> Result<Self, Self::Error> {
+        Ok(StatusChange {
+            proposal_id: row
+                .proposal_id
+                .parse()
+                .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+            from: row
+                .from_status
+                .map(|status| status.parse())
+                .transpose()
+                .map_err(decode_error)?,
+            to: row.to_status.parse().map_err(decode_error)?,
+            triggered_by: row.triggered_by.parse().map_err(decode_error)?,
+            changed_at: row.changed_at,
+        })
+    }
+}
+
+/// Record a status change in the history
+async fn insert_status_change(
+    conn: &mut PgConnection,
+    proposal_id: &ProposalId,
+    from: Option<ProposalStatus>,
+    to: ProposalStatus,
+    triggered_by: StatusTrigger,
+) -> Result<StatusChange, sqlx::Error> {
+    let changed_at: DateTime<Utc> = sqlx::query_scalar(
+        "INSERT INTO proposal_status_history (proposal_id, from_status, to_status, triggered_by) \
+         VALUES ($1, $2, $3, $4) RETURNING changed_at",
+    )
+    .bind(proposal_id.to_string())
+    .bind(from.map(|status| status.as_str()))
+    .bind(to.as_str())
+    .bind(triggered_by.as_str())
+    .fetch_one(conn)
+    .await?;
+
+    Ok(StatusChange {
+        proposal_id: proposal_id.clone(),
+        from,
+        to,
+        triggered_by,
+        changed_at,
+    })
+}
+
+fn into_proposals(rows: Vec<ProposalRow>) -> Result<Vec<Proposal>, sqlx::Error> {
+    rows.into_iter().map(into_current).collect()
+}
+
+/// Record the stored state of a proposal in the change log
+async fn record_proposal_change(
+    conn: &mut PgConnection,
+    proposal: &Proposal,
+) -> Result<(), sqlx::Error> {
+    record_change(
+        conn,
+        EntityType::Proposal,
+        &proposal.id.to_string(),
+        Some(proposal),
+    )
+    .await?;
+    Ok(())
+}
 
 /// Repository for proposal operations
 #[allow(dead_code)] // TODO: Remove after development phase
@@ -17,31 +359,537 @@ impl ProposalRepository {
     }
 
     /// Find proposal by ID
-    #[allow(dead_code, unused_variables)] // TODO: Remove after development phase
-    pub async fn find_by_id(&self, id: &str) -> Result<Option<Proposal>, sqlx::Error> {
-        // TODO: Implement find by ID
-        todo!("Implement find_by_id")
+    pub async fn find_by_id(&self, id: &ProposalId) -> Result<Option<Proposal>, sqlx::Error> {
+        let query = format!("SELECT {PROPOSAL_COLUMNS} FROM proposals p WHERE p.id = $1");
+        let row: Option<ProposalRow> = sqlx::query_as(&query)
+            .bind(id.to_string())
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.map(into_current).transpose()
     }
 
-    /// Find proposals by network
-    #[allow(dead_code, unused_variables)] // TODO: Remove after development phase
-    pub async fn find_by_network(&self, network: &str) -> Result<Vec<Proposal>, sqlx::Error> {
-        // TODO: Implement find by network
-        todo!("Implement find_by_network")
+    /// List proposals matching a query
+    pub async fn list(&self, query: &ProposalQuery) -> Result<Vec<Proposal>, sqlx::Error> {
+        let direction = if query.ascending { "ASC" } else { "DESC" };
+        let order = query.order.column();
+        let sql = format!(
+            "SELECT {PROPOSAL_COLUMNS} FROM proposals p \
+             WHERE ($1::TEXT[] IS NULL OR p.id = ANY($1) \
+                    OR (p.source = 'snapshot' AND p.native_id = ANY($1))) \
+               AND ($2::TEXT[] IS NULL \
+                    OR EXISTS (SELECT 1 FROM unnest($2::TEXT[]) AS prefix \
+                               WHERE starts_with(p.id, prefix))) \
+               AND ($3::TEXT[] IS NULL OR lower(p.author) = ANY($3)) \
+               AND ($4::TEXT[] IS NULL OR p.status = ANY($4)) \
+               AND ($5::TEXT IS NULL OR strpos(lower(p.title), lower($5)) > 0) \
+               AND ($6::TIMESTAMPTZ IS NULL OR p.created_at >= $6) \
+               AND ($7::TIMESTAMPTZ IS NULL OR p.created_at < $7) \
+               AND ($10::BOOL IS NULL OR (p.is_duplicate_of IS NOT NULL) = $10) \
+               AND ($11::FLOAT8 IS NULL OR COALESCE(p.spam_score, 0) <= $11) \
+               AND ($12::TEXT[] IS NULL \
+                    OR EXISTS (SELECT 1 FROM unnest($12::TEXT[]) AS prefix \
+                               WHERE starts_with(p.id, prefix))) \
+             ORDER BY {order} {direction} NULLS LAST, p.id {direction} \
+             LIMIT $8 OFFSET $9"
+        );
+        // Canonical IDs start with the chain and protocol ID, so a prefix match is exact
+        let protocols: Option<Vec<String>> = query
+            .protocols
+            .as_ref()
+            .map(|protocols| protocols.iter().map(|p| format!("{p}:")).collect());
+        let chains: Option<Vec<String>> = query
+            .chains
+            .as_ref()
+            .map(|chains| chains.iter().map(|c| format!("{c}:")).collect());
+        let authors: Option<Vec<String>> = query
+            .authors
+            .as_ref()
+            .map(|authors| authors.iter().map(|a| a.to_lowercase()).collect());
+        let statuses: Option<Vec<&str>> = query
+            .statuses
+            .as_ref()
+            .map(|statuses| statuses.iter().map(ProposalStatus::as_str).collect());
+
+        let rows: Vec<ProposalRow> = sqlx::query_as(&sql)
+            .bind(&query.ids)
+            .bind(protocols)
+            .bind(authors)
+            .bind(statuses)
+            .bind(&query.title_contains)
+            .bind(query.created_from)
+            .bind(query.created_until)
+            .bind(query.limit)
+            .bind(query.offset)
+            .bind(query.duplicates)
+            .bind(query.max_spam_score)
+            .bind(chains)
+            .fetch_all(&self.pool)
+            .await?;
+
+        into_proposals(rows)
     }
 
-    /// Search proposals by description/title
-    #[allow(dead_code, unused_variables)] // TODO: Remove after development phase
-    pub async fn search(&self, query: &str) -> Result<Vec<Proposal>, sqlx::Error> {
-        // TODO: Implement search
-        todo!("Implement search")
+    /// Proposals after `after` with their latest revision and status change, by ID
+    ///
+    /// Statuses are returned as stored, without applying the voting window.
+    pub async fn find_stored(
+        &self,
+        after: Option<&ProposalId>,
+        limit: i64,
+    ) -> Result<Vec<StoredProposal>, sqlx::Error> {
+        let query = format!(
+            "SELECT {PROPOSAL_COLUMNS}, \
+             (SELECT r.content_hash FROM proposal_revisions r WHERE r.proposal_id = p.id \
+              ORDER BY r.revision DESC LIMIT 1) AS revision_hash, \
+             (SELECT h.to_status FROM proposal_status_history h WHERE h.proposal_id = p.id \
+              ORDER BY h.changed_at DESC, h.id DESC LIMIT 1) AS history_status \
+             FROM proposals p WHERE ($1::TEXT IS NULL OR p.id > $1) ORDER BY p.id LIMIT $2"
+        );
+        let rows: Vec<StoredProposalRow> = sqlx::query_as(&query)
+            .bind(after.map(ProposalId::to_string))
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(StoredProposal {
+                    proposal: Proposal::try_from(row.proposal)?,
+                    revision_hash: row.revision_hash,
+                    history_status: row
+                        .history_status
+                        .map(|status| status.parse())
+                        .transpose()
+                        .map_err(decode_error)?,
+                })
+            })
+            .collect()
     }
 
-    /// Save proposal
-    #[allow(dead_code, unused_variables)] // TODO: Remove after development phase
-    pub async fn save(&self, proposal: &Proposal) -> Result<(), sqlx::Error> {
-        // TODO: Implement save
-        todo!("Implement save")
+    /// Search proposals by description/title, newest first
+    ///
+    /// Texts match case-insensitively anywhere in their field; a proposal
+    /// has to match every given text.
+    pub async fn search(
+        &self,
+        title: Option<&str>,
+        description: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<Proposal>, sqlx::Error> {
+        let query = format!(
+            "SELECT {PROPOSAL_COLUMNS} FROM proposals p \
+             WHERE ($1::TEXT IS NULL OR strpos(lower(p.title), lower($1)) > 0) \
+               AND ($2::TEXT IS NULL OR strpos(lower(p.description), lower($2)) > 0) \
+             ORDER BY p.created_at DESC, p.id DESC \
+             LIMIT $3"
+        );
+        let rows: Vec<ProposalRow> = sqlx::query_as(&query)
+            .bind(title)
+            .bind(description)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+        into_proposals(rows)
+    }
+
+    /// Save proposal, replacing any previous version with the same ID
+    ///
+    /// A new revision is recorded when the title, description or choices
+    /// differ from the latest revision. The status is only replaced if the
+    /// change is a valid transition, and recorded in the status history.
+    pub async fn save(&self, proposal: &Proposal) -> Result<SavedProposal, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let id = proposal.id.to_string();
+
+        // A new proposal has no row to lock yet, so concurrent writers such as
+        // a backfill and the live server are serialized on its ID as well
+        sqlx::query("SELECT pg_advisory_xact_lock($1, hashtext($2))")
+            .bind(PROPOSAL_LOCK_CLASS)
+            .bind(&id)
+            .execute(&mut *tx)
+            .await?;
+
+        let query =
+            format!("SELECT {PROPOSAL_COLUMNS} FROM proposals p WHERE p.id = $1 FOR UPDATE");
+        let previous: Option<Proposal> = sqlx::query_as::<_, ProposalRow>(&query)
+            .bind(&id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .map(Proposal::try_from)
+            .transpose()?;
+
+        let previous_status = previous.as_ref().map(|previous| previous.status);
+        let status = match &previous {
+            Some(previous) if !previous.can_transition_to(proposal.status, Utc::now()) => {
+                previous.status
+            }
+            _ => proposal.status,
+        };
+        // Duplicate and spam assessments are kept until the proposal is assessed again
+        let stored = Proposal {
+            status,
+            is_duplicate_of: previous.as_ref().and_then(|p| p.is_duplicate_of.clone()),
+            spam_score: previous.as_ref().and_then(|p| p.spam_score),
+            ..proposal.clone()
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO proposals (
+                id, title, description, status, protocol_id, choices, author, comments,
+                source, native_id, created_at, updated_at, voting_start, voting_end,
+                snapshot_block, quorum, voting_type, scores, scores_total, discussion_url,
+                ipfs_hash, source_url
+            )
+            VALUES (
+                $1, $2, $3, $4, (SELECT id FROM protocols WHERE slug = $5), $6, $7, $8,
+                $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22
+            )
+            ON CONFLICT (id)
+            DO UPDATE SET
+                title = EXCLUDED.title,
+                description = EXCLUDED.description,
+                status = EXCLUDED.status,
+                protocol_id = EXCLUDED.protocol_id,
+                choices = EXCLUDED.choices,
+                author = EXCLUDED.author,
+                comments = EXCLUDED.comments,
+                updated_at = EXCLUDED.updated_at,
+                voting_start = EXCLUDED.voting_start,
+                voting_end = EXCLUDED.voting_end,
+                snapshot_block = EXCLUDED.snapshot_block,
+                quorum = EXCLUDED.quorum,
+                voting_type = EXCLUDED.voting_type,
+                scores = EXCLUDED.scores,
+                scores_total = EXCLUDED.scores_total,
+                discussion_url = EXCLUDED.discussion_url,
+                ipfs_hash = EXCLUDED.ipfs_hash,
+                source_url = EXCLUDED.source_url
+            "#,
+        )
+        .bind(&id)
+        .bind(&proposal.title)
+        .bind(&proposal.description)
+        .bind(status.as_str())
+        .bind(&proposal.protocol_id.protocol)
+        .bind(Json(&proposal.choices))
+        .bind(&proposal.author)
+        .bind(Json(&proposal.comments))
+        .bind(proposal.id.source())
+        .bind(proposal.id.native_id())
+        .bind(proposal.created_at)
+        .bind(proposal.updated_at)
+        .bind(proposal.voting_start)
+        .bind(proposal.voting_end)
+        .bind(proposal.snapshot_block.map(|block| block as i64))
+        .bind(proposal.quorum)
+        .bind(proposal.voting_type.as_str())
+        .bind(Json(&proposal.scores))
+        .bind(proposal.scores_total)
+        .bind(&proposal.discussion_url)
+        .bind(&proposal.ipfs_hash)
+        .bind(&proposal.source_url)
+        .execute(&mut *tx)
+        .await?;
+
+        let hash = content_hash(proposal);
+        let latest: Option<(i32, String)> = sqlx::query_as(
+            "SELECT revision, content_hash FROM proposal_revisions \
+             WHERE proposal_id = $1 ORDER BY revision DESC LIMIT 1",
+        )
+        .bind(&id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let revision = match &latest {
+            Some((revision, latest_hash)) if *latest_hash == hash => *revision,
+            _ => {
+                let revision = latest.as_ref().map_or(1, |(revision, _)| revision + 1);
+                sqlx::query(
+                    r#"
+                    INSERT INTO proposal_revisions (
+                        proposal_id, revision, title, description, choices, content_hash, edited_at
+                    )
+                    VALUES ($1, $2, $3, $4, $5, $6, $7)
+                    "#,
+                )
+                .bind(&id)
+                .bind(revision)
+                .bind(&proposal.title)
+                .bind(&proposal.description)
+                .bind(Json(&proposal.choices))
+                .bind(&hash)
+                .bind(proposal.updated_at)
+                .execute(&mut *tx)
+                .await?;
+                revision
+            }
+        };
+
+        let status_change = if previous_status != Some(status) {
+            Some(
+                insert_status_change(
+                    &mut tx,
+                    &proposal.id,
+                    previous_status,
+                    status,
+                    StatusTrigger::Source,
+                )
+                .await?,
+            )
+        } else {
+            None
+        };
+
+        let unchanged = previous.as_ref().is_some_and(|previous| {
+            serde_json::to_value(previous).ok() == serde_json::to_value(&stored).ok()
+        });
+        if !unchanged {
+            record_proposal_change(&mut tx, &stored).await?;
+        }
+
+        let votes_changed = previous.as_ref().is_some_and(|previous| {
+            previous.scores != proposal.scores || previous.scores_total != proposal.scores_total
+        });
+        let content = match previous {
+            None => SaveOutcome::Created { revision },
+            Some(previous) => {
+                let changed_fields = changed_fields(
+                    (&previous.title, &previous.description, &previous.choices),
+                    (&proposal.title, &proposal.description, &proposal.choices),
+                );
+                if changed_fields.is_empty() {
+                    SaveOutcome::Unchanged
+                } else {
+                    SaveOutcome::Updated {
+                        revision,
+                        changed_fields,
+                    }
+                }
+            }
+        };
+
+        let timestamp = Utc::now();
+        let mut events = Vec::new();
+        match &content {
+            SaveOutcome::Created { revision } => events.push(IndexerEvent::ProposalCreated {
+                proposal_id: proposal.id.clone(),
+                revision: *revision,
+                timestamp,
+            }),
+            SaveOutcome::Updated {
+                revision,
+                changed_fields,
+            } => events.push(IndexerEvent::ProposalUpdated {
+                proposal_id: proposal.id.clone(),
+                revision: *revision,
+                changed_fields: changed_fields.clone(),
+                timestamp,
+            }),
+            SaveOutcome::Unchanged => {}
+        }
+        if votes_changed {
+            events.push(IndexerEvent::VotesUpdated {
+                proposal_id: proposal.id.clone(),
+                scores: proposal.scores.clone(),
+                scores_total: proposal.scores_total,
+                timestamp,
+            });
+        }
+        events.extend(
+            status_change
+                .as_ref()
+                .and_then(IndexerEvent::for_status_change),
+        );
+
+        let mut logged = Vec::with_capacity(events.len());
+        for event in &events {
+            logged.push(record_event(&mut tx, event).await?);
+        }
+
+        tx.commit().await?;
+
+        Ok(SavedProposal {
+            content,
+            status,
+            status_change,
+            votes_changed,
+            events: logged,
+        })
+    }
+
+    /// Move a proposal to a new status, recording it in the status history
+    /// and the event log
+    ///
+    /// Returns `None` if the proposal does not exist, already has the
+    /// status, or cannot move to it.
+    pub async fn transition(
+        &self,
+        id: &ProposalId,
+        to: ProposalStatus,
+        triggered_by: StatusTrigger,
+    ) -> Result<Option<StatusTransition>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let query =
+            format!("SELECT {PROPOSAL_COLUMNS} FROM proposals p WHERE p.id = $1 FOR UPDATE");
+        let current: Option<Proposal> = sqlx::query_as::<_, ProposalRow>(&query)
+            .bind(id.to_string())
+            .fetch_optional(&mut *tx)
+            .await?
+            .map(Proposal::try_from)
+            .transpose()?;
+        let Some(current) = current else {
+            return Ok(None);
+        };
+        let from = current.status;
+        if from == to || !current.can_transition_to(to, Utc::now()) {
+            return Ok(None);
+        }
+
+        let query = format!(
+            "UPDATE proposals p SET status = $2 WHERE p.id = $1 RETURNING {PROPOSAL_COLUMNS}"
+        );
+        let updated: ProposalRow = sqlx::query_as(&query)
+            .bind(id.to_string())
+            .bind(to.as_str())
+            .fetch_one(&mut *tx)
+            .await?;
+        let change = insert_status_change(&mut tx, id, Some(from), to, triggered_by).await?;
+        record_proposal_change(&mut tx, &Proposal::try_from(updated)?).await?;
+        let event = match IndexerEvent::for_status_change(&change) {
+            Some(event) => Some(record_event(&mut tx, &event).await?),
+            None => None,
+        };
+
+        tx.commit().await?;
+        Ok(Some(StatusTransition { change, event }))
+    }
+
+    /// Whether an author created any proposal before a time, compared case-insensitively
+    pub async fn has_earlier_by_author(
+        &self,
+        author: &str,
+        before: DateTime<Utc>,
+    ) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar(
+            "SELECT EXISTS (SELECT 1 FROM proposals \
+             WHERE lower(author) = lower($1) AND created_at < $2)",
+        )
+        .bind(author)
+        .bind(before)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Store the duplicate and spam assessment of a proposal
+    ///
+    /// Returns whether the assessment changed, in which case the proposal is
+    /// recorded in the change log.
+    pub async fn set_quality(
+        &self,
+        id: &ProposalId,
+        is_duplicate_of: Option<&ProposalId>,
+        spam_score: f64,
+    ) -> Result<bool, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let query = format!(
+            "UPDATE proposals p SET is_duplicate_of = $2, spam_score = $3 \
+             WHERE p.id = $1 AND (p.is_duplicate_of, p.spam_score) IS DISTINCT FROM ($2, $3) \
+             RETURNING {PROPOSAL_COLUMNS}"
+        );
+        let updated: Option<ProposalRow> = sqlx::query_as(&query)
+            .bind(id.to_string())
+            .bind(is_duplicate_of.map(ProposalId::to_string))
+            .bind(spam_score)
+            .fetch_optional(&mut *tx)
+            .await?;
+        let Some(updated) = updated else {
+            return Ok(false);
+        };
+        record_proposal_change(&mut tx, &Proposal::try_from(updated)?).await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Status history of a proposal, oldest first
+    pub async fn status_history(&self, id: &ProposalId) -> Result<Vec<StatusChange>, sqlx::Error> {
+        let rows: Vec<StatusChangeRow> = sqlx::query_as(
+            "SELECT proposal_id, from_status, to_status, triggered_by, changed_at \
+             FROM proposal_status_history WHERE proposal_id = $1 ORDER BY changed_at, id",
+        )
+        .bind(id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(StatusChange::try_from).collect()
+    }
+
+    /// IDs of the proposals whose stored status is pending or active
+    pub async fn find_open(&self) -> Result<Vec<ProposalId>, sqlx::Error> {
+        let ids: Vec<String> =
+            sqlx::query_scalar("SELECT id FROM proposals WHERE status IN ('pending', 'active')")
+                .fetch_all(&self.pool)
+                .await?;
+
+        ids.into_iter()
+            .map(|id| id.parse().map_err(|e| sqlx::Error::Decode(Box::new(e))))
+            .collect()
+    }
+
+    /// Newest proposal of each data source and protocol, with its creation time
+    pub async fn newest_by_source(&self) -> Result<Vec<(ProposalId, DateTime<Utc>)>, sqlx::Error> {
+        let rows: Vec<(String, DateTime<Utc>)> = sqlx::query_as(
+            "SELECT DISTINCT ON (source, protocol_id) id, created_at FROM proposals \
+             WHERE created_at IS NOT NULL \
+             ORDER BY source, protocol_id, created_at DESC",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|(id, created_at)| {
+                let id = id.parse().map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+                Ok((id, created_at))
+            })
+            .collect()
+    }
+
+    /// All revisions of a proposal, oldest first
+    pub async fn revisions(&self, id: &ProposalId) -> Result<Vec<ProposalRevision>, sqlx::Error> {
+        let query = format!(
+            "SELECT {REVISION_COLUMNS} FROM proposal_revisions \
+             WHERE proposal_id = $1 ORDER BY revision"
+        );
+        let rows: Vec<RevisionRow> = sqlx::query_as(&query)
+            .bind(id.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.into_iter().map(ProposalRevision::try_from).collect()
+    }
+
+    /// A single revision of a proposal
+    pub async fn revision(
+        &self,
+        id: &ProposalId,
+        revision: i32,
+    ) -> Result<Option<ProposalRevision>, sqlx::Error> {
+        let query = format!(
+            "SELECT {REVISION_COLUMNS} FROM proposal_revisions \
+             WHERE proposal_id = $1 AND revision = $2"
+        );
+        let row: Option<RevisionRow> = sqlx::query_as(&query)
+            .bind(id.to_string())
+            .bind(revision)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.map(ProposalRevision::try_from).transpose()
     }
 
     /// Update proposal
@@ -51,10 +899,23 @@ impl ProposalRepository {
         todo!("Implement update")
     }
 
-    /// Delete proposal
-    #[allow(dead_code, unused_variables)] // TODO: Remove after development phase
-    pub async fn delete(&self, id: &str) -> Result<(), sqlx::Error> {
-        // TODO: Implement delete
-        todo!("Implement delete")
+    /// Delete a proposal, recording a tombstone in the change log
+    ///
+    /// Returns whether the proposal existed.
+    pub async fn delete(&self, id: &ProposalId) -> Result<bool, sqlx::Error> {
+        let id = id.to_string();
+        let mut tx = self.pool.begin().await?;
+
+        let deleted: Option<String> =
+            sqlx::query_scalar("DELETE FROM proposals WHERE id = $1 RETURNING id")
+                .bind(&id)
+                .fetch_optional(&mut *tx)
+                .await?;
+        if deleted.is_some() {
+            record_change::<Proposal>(&mut tx, EntityType::Proposal, &id, None).await?;
+        }
+
+        tx.commit().await?;
+        Ok(deleted.is_some())
     }
 }
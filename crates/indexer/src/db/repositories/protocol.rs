@@ -1,7 +1,78 @@
 //! Protocol repository for database operations
 
-use crate::models::ProtocolId;
-use sqlx::PgPool;
+use super::change::record_change;
+use crate::{
+    config::{ProtocolEntry, ProtocolRegistry, TreasuryToken},
+    models::{EntityType, ProtocolId},
+    utils::id::ChainId,
+};
+use sqlx::{types::Json, FromRow, PgConnection, PgPool};
+
+/// Registry columns of the `protocols` table
+const REGISTRY_COLUMNS: &str = "slug, chain_id, name, snapshot_spaces, tally_organization, \
+     governor_addresses, timelock_addresses, forum_urls, treasury_addresses, treasury_tokens, \
+     polling_interval_secs, enabled";
+
+/// Outcome of synchronizing the registry into the database
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RegistrySyncReport {
+    /// Number of protocols inserted or updated
+    pub upserted: usize,
+    /// Number of protocols disabled because they left the registry
+    pub disabled: u64,
+}
+
+/// Registry columns of a row of the `protocols` table
+#[derive(FromRow)]
+struct RegistryRow {
+    slug: String,
+    chain_id: i64,
+    name: String,
+    snapshot_spaces: Json<Vec<String>>,
+    tally_organization: Option<String>,
+    governor_addresses: Json<Vec<String>>,
+    timelock_addresses: Json<Vec<String>>,
+    forum_urls: Json<Vec<String>>,
+    treasury_addresses: Json<Vec<String>>,
+    treasury_tokens: Json<Vec<TreasuryToken>>,
+    polling_interval_secs: i64,
+    enabled: bool,
+}
+
+impl From<RegistryRow> for ProtocolEntry {
+    fn from(row: RegistryRow) -> Self {
+        ProtocolEntry {
+            id: row.slug,
+            name: row.name,
+            chain_id: row.chain_id as u64,
+            snapshot_spaces: row.snapshot_spaces.0,
+            tally_organization: row.tally_organization,
+            governors: row.governor_addresses.0,
+            timelocks: row.timelock_addresses.0,
+            forum_urls: row.forum_urls.0,
+            treasuries: row.treasury_addresses.0,
+            treasury_tokens: row.treasury_tokens.0,
+            polling_interval_secs: Some(row.polling_interval_secs as u64),
+            enabled: row.enabled,
+        }
+    }
+}
+
+/// Record the stored registry entry of a protocol in the change log
+async fn record_protocol_change(
+    conn: &mut PgConnection,
+    row: RegistryRow,
+) -> Result<(), sqlx::Error> {
+    let entry = ProtocolEntry::from(row);
+    record_change(
+        conn,
+        EntityType::Protocol,
+        &entry.protocol_id().to_string(),
+        Some(&entry),
+    )
+    .await?;
+    Ok(())
+}
 
 /// Repository for protocol operations
 #[allow(dead_code)] // TODO: Remove after development phase
@@ -17,11 +88,22 @@ impl ProtocolRepository {
         Self { pool }
     }
 
-    /// Find protocol by ID
-    #[allow(dead_code)] // TODO: Remove after development phase
+    /// Find protocol by its registry ID
     pub async fn find_by_id(&self, id: &str) -> Result<Option<ProtocolId>, sqlx::Error> {
-        // TODO: Implement find by ID
-        todo!("Implement find_by_id")
+        let row: Option<(i64, String)> = sqlx::query_as(
+            r#"
+            SELECT chain_id, slug
+            FROM protocols
+            WHERE slug = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|(chain_id, slug)| ProtocolId::new(ChainId::eip155(chain_id as u64), &slug))
+            .transpose()
+            .map_err(|e| sqlx::Error::Decode(Box::new(e)))
     }
 
     /// Save protocol
@@ -37,4 +119,109 @@ impl ProtocolRepository {
         // TODO: Implement update
         todo!("Implement update")
     }
+
+    /// Synchronize the registry into the `protocols` table
+    ///
+    /// Entries are upserted by registry ID. Protocols that were previously
+    /// synced but are no longer listed are disabled rather than deleted, so
+    /// that proposals referencing them stay intact. Only entries that actually
+    /// changed are written and recorded in the change log.
+    pub async fn sync_registry(
+        &self,
+        registry: &ProtocolRegistry,
+    ) -> Result<RegistrySyncReport, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        for entry in &registry.protocols {
+            upsert_entry(&mut tx, entry, registry.polling_interval_secs(entry)).await?;
+        }
+
+        let slugs: Vec<String> = registry.protocols.iter().map(|e| e.id.clone()).collect();
+        let query = format!(
+            "UPDATE protocols SET enabled = FALSE, updated_at = NOW() \
+             WHERE slug IS NOT NULL AND enabled AND NOT (slug = ANY($1)) \
+             RETURNING {REGISTRY_COLUMNS}"
+        );
+        let disabled: Vec<RegistryRow> = sqlx::query_as(&query)
+            .bind(&slugs)
+            .fetch_all(&mut *tx)
+            .await?;
+        let disabled_count = disabled.len() as u64;
+        for row in disabled {
+            record_protocol_change(&mut tx, row).await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(RegistrySyncReport {
+            upserted: registry.protocols.len(),
+            disabled: disabled_count,
+        })
+    }
+}
+
+async fn upsert_entry(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    entry: &ProtocolEntry,
+    polling_interval_secs: u64,
+) -> Result<(), sqlx::Error> {
+    let query = format!(
+        r#"
+        INSERT INTO protocols (
+            slug, chain_id, name, protocol, snapshot_spaces, tally_organization,
+            governor_addresses, timelock_addresses, forum_urls, treasury_addresses,
+            treasury_tokens, polling_interval_secs, enabled
+        )
+        VALUES ($1, $2, $3, $1, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+        ON CONFLICT (slug)
+        DO UPDATE SET
+            chain_id = EXCLUDED.chain_id,
+            name = EXCLUDED.name,
+            snapshot_spaces = EXCLUDED.snapshot_spaces,
+            tally_organization = EXCLUDED.tally_organization,
+            governor_addresses = EXCLUDED.governor_addresses,
+            timelock_addresses = EXCLUDED.timelock_addresses,
+            forum_urls = EXCLUDED.forum_urls,
+            treasury_addresses = EXCLUDED.treasury_addresses,
+            treasury_tokens = EXCLUDED.treasury_tokens,
+            polling_interval_secs = EXCLUDED.polling_interval_secs,
+            enabled = EXCLUDED.enabled,
+            updated_at = NOW()
+        WHERE (
+            protocols.slug, protocols.chain_id, protocols.name, protocols.snapshot_spaces,
+            protocols.tally_organization, protocols.governor_addresses,
+            protocols.timelock_addresses, protocols.forum_urls,
+            protocols.treasury_addresses, protocols.treasury_tokens,
+            protocols.polling_interval_secs, protocols.enabled
+        ) IS DISTINCT FROM (
+            EXCLUDED.slug, EXCLUDED.chain_id, EXCLUDED.name, EXCLUDED.snapshot_spaces,
+            EXCLUDED.tally_organization, EXCLUDED.governor_addresses,
+            EXCLUDED.timelock_addresses, EXCLUDED.forum_urls,
+            EXCLUDED.treasury_addresses, EXCLUDED.treasury_tokens,
+            EXCLUDED.polling_interval_secs, EXCLUDED.enabled
+        )
+        RETURNING {REGISTRY_COLUMNS}
+        "#
+    );
+    let changed: Option<RegistryRow> = sqlx::query_as(&query)
+        .bind(&entry.id)
+        .bind(entry.chain_id as i64)
+        .bind(&entry.name)
+        .bind(sqlx::types::Json(&entry.snapshot_spaces))
+        .bind(&entry.tally_organization)
+        .bind(sqlx::types::Json(&entry.governors))
+        .bind(sqlx::types::Json(&entry.timelocks))
+        .bind(sqlx::types::Json(&entry.forum_urls))
+        .bind(sqlx::types::Json(&entry.treasuries))
+        .bind(sqlx::types::Json(&entry.treasury_tokens))
+        .bind(polling_interval_secs as i64)
+        .bind(entry.enabled)
+        .fetch_optional(&mut **tx)
+        .await?;
+
+    if let Some(row) = changed {
+        record_protocol_change(tx, row).await?;
+    }
+
+    Ok(())
 }
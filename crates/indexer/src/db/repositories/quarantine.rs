@@ -0,0 +1,271 @@
+//! Quarantine repository for database operations
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use sqlx::{types::Json, FromRow, PgPool};
+
+use crate::{
+    models::{QuarantineStatus, QuarantinedRecord, RawRecord},
+    utils::{
+        id::ProposalId,
+        validation::{ValidationError, ValidationErrors},
+    },
+};
+
+/// Columns selected when loading quarantined records
+const QUARANTINE_COLUMNS: &str = "id, source, protocol_id, native_id, payload, errors, status, \
+                                  attempts, proposal_id, created_at, updated_at, resolved_at";
+
+/// Filters for listing quarantined records
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct QuarantineFilter {
+    /// Only records from this source
+    pub source: Option<String>,
+    /// Only records whose first failure is this rule
+    pub rule: Option<String>,
+    /// Only records with this status (defaults to all)
+    pub status: Option<QuarantineStatus>,
+    /// Maximum number of records to return
+    pub limit: Option<i64>,
+}
+
+/// Default and maximum page size of quarantine listings
+const MAX_LIST_LIMIT: i64 = 500;
+
+/// Database row of the `quarantined_records` table
+#[derive(FromRow)]
+struct QuarantineRow {
+    id: i64,
+    source: String,
+    protocol_id: String,
+    native_id: Option<String>,
+    payload: Json<serde_json::Value>,
+    errors: Json<Vec<ValidationError>>,
+    status: String,
+    attempts: i32,
+    proposal_id: Option<String>,
+    created_at: Option<DateTime<Utc>>,
+    updated_at: Option<DateTime<Utc>>,
+    resolved_at: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<QuarantineRow> for QuarantinedRecord {
+    type Error = sqlx::Error;
+
+    fn try_from(row: QuarantineRow) -> Result<Self, Self::Error> {
+        let status = row.status.parse().map_err(|e: String| {
+            sqlx::Error::Decode(Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                e,
+            )))
+        })?;
+        let created_at = row.created_at.unwrap_or_else(Utc::now);
+
+        Ok(QuarantinedRecord {
+            id: row.id,
+            source: row.source,
+            protocol_id: row.protocol_id,
+            native_id: row.native_id,
+            payload: row.payload.0,
+            errors: row.errors.0,
+            status,
+            attempts: row.attempts,
+            proposal_id: row.proposal_id,
+            created_at,
+            updated_at: row.updated_at.unwrap_or(created_at),
+            resolved_at: row.resolved_at,
+        })
+    }
+}
+
+/// Repository for quarantined records
+pub struct QuarantineRepository {
+    pool: PgPool,
+}
+
+impl QuarantineRepository {
+    /// Create a new quarantine repository
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Number of records waiting for a fix
+    pub async fn count_pending(&self) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar("SELECT COUNT(*) FROM quarantined_records WHERE status = 'pending'")
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    /// Quarantine a record that failed the pipeline
+    ///
+    /// A record that is already pending in quarantine (same source, protocol
+    /// and native ID) is updated instead of duplicated; records without a
+    /// native ID are only merged with a pending entry first quarantined with
+    /// the same payload. Returns the entry ID.
+    pub async fn quarantine(
+        &self,
+        record: &RawRecord,
+        errors: &ValidationErrors,
+    ) -> Result<i64, sqlx::Error> {
+        let rule = errors
+            .errors
+            .first()
+            .map(|e| e.rule.as_str())
+            .unwrap_or_default();
+
+        sqlx::query_scalar(
+            r#"
+            INSERT INTO quarantined_records
+                (source, protocol_id, native_id, payload, payload_hash, errors, rule)
+            VALUES ($1, $2, $3, $4, md5($4::JSONB::TEXT), $5, $6)
+            ON CONFLICT (source, protocol_id, COALESCE(native_id, payload_hash))
+                WHERE status = 'pending'
+            DO UPDATE SET
+                payload = EXCLUDED.payload,
+                errors = EXCLUDED.errors,
+                rule = EXCLUDED.rule,
+                attempts = quarantined_records.attempts + 1,
+                updated_at = NOW()
+            RETURNING id
+            "#,
+        )
+        .bind(&record.source)
+        .bind(record.protocol_id.to_string())
+        .bind(&record.native_id)
+        .bind(Json(&record.payload))
+        .bind(Json(&errors.errors))
+        .bind(rule)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Find a quarantined record by ID
+    pub async fn find_by_id(&self, id: i64) -> Result<Option<QuarantinedRecord>, sqlx::Error> {
+        let query = format!("SELECT {QUARANTINE_COLUMNS} FROM quarantined_records WHERE id = $1");
+        let row: Option<QuarantineRow> = sqlx::query_as(&query)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.map(QuarantinedRecord::try_from).transpose()
+    }
+
+    /// List quarantined records, newest first
+    pub async fn list(
+        &self,
+        filter: &QuarantineFilter,
+    ) -> Result<Vec<QuarantinedRecord>, sqlx::Error> {
+        let query = format!(
+            "SELECT {QUARANTINE_COLUMNS} FROM quarantined_records \
+             WHERE ($1::TEXT IS NULL OR source = $1) \
+               AND ($2::TEXT IS NULL OR rule = $2) \
+               AND ($3::TEXT IS NULL OR status = $3) \
+             ORDER BY updated_at DESC, id DESC \
+             LIMIT $4"
+        );
+        let rows: Vec<QuarantineRow> = sqlx::query_as(&query)
+            .bind(&filter.source)
+            .bind(&filter.rule)
+            .bind(filter.status.map(|s| s.as_str()))
+            .bind(
+                filter
+                    .limit
+                    .unwrap_or(MAX_LIST_LIMIT)
+                    .clamp(1, MAX_LIST_LIMIT),
+            )
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.into_iter().map(QuarantinedRecord::try_from).collect()
+    }
+
+    /// Replace the payload of a pending record with a fixed version
+    pub async fn update_payload(
+        &self,
+        id: i64,
+        payload: &serde_json::Value,
+    ) -> Result<Option<QuarantinedRecord>, sqlx::Error> {
+        let query = format!(
+            "UPDATE quarantined_records SET payload = $2, updated_at = NOW() \
+             WHERE id = $1 AND status = 'pending' RETURNING {QUARANTINE_COLUMNS}"
+        );
+        let row: Option<QuarantineRow> = sqlx::query_as(&query)
+            .bind(id)
+            .bind(Json(payload))
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.map(QuarantinedRecord::try_from).transpose()
+    }
+
+    /// Record a failed re-ingestion attempt
+    pub async fn record_failure(
+        &self,
+        id: i64,
+        errors: &ValidationErrors,
+    ) -> Result<(), sqlx::Error> {
+        let rule = errors
+            .errors
+            .first()
+            .map(|e| e.rule.as_str())
+            .unwrap_or_default();
+
+        sqlx::query(
+            r#"
+            UPDATE quarantined_records
+            SET errors = $2, rule = $3, attempts = attempts + 1, updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(Json(&errors.errors))
+        .bind(rule)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Mark a record as re-ingested into the given proposal
+    ///
+    /// The errors of the last failed attempt are kept for auditing.
+    pub async fn resolve(&self, id: i64, proposal_id: &ProposalId) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE quarantined_records
+            SET status = 'resolved', proposal_id = $2, attempts = attempts + 1,
+                updated_at = NOW(), resolved_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(proposal_id.to_string())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Resolve pending entries of a record that was later ingested successfully
+    pub async fn resolve_matching(
+        &self,
+        record: &RawRecord,
+        proposal_id: &ProposalId,
+    ) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE quarantined_records
+            SET status = 'resolved', proposal_id = $4, updated_at = NOW(), resolved_at = NOW()
+            WHERE status = 'pending' AND source = $1 AND protocol_id = $2 AND native_id = $3
+            "#,
+        )
+        .bind(&record.source)
+        .bind(record.protocol_id.to_string())
+        .bind(&record.native_id)
+        .bind(proposal_id.to_string())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,89 @@
+//! Decoded proposal transfer repository for database operations
+
+use sqlx::{FromRow, PgPool};
+
+use crate::{models::ProposalTransfer, utils::id::ProposalId};
+
+/// Database row of the `proposal_transfers` table
+#[derive(FromRow)]
+struct TransferRow {
+    call_index: i32,
+    token: String,
+    recipient: String,
+    raw_amount: String,
+}
+
+impl From<TransferRow> for ProposalTransfer {
+    fn from(row: TransferRow) -> Self {
+        ProposalTransfer {
+            call_index: row.call_index as u32,
+            token: row.token,
+            recipient: row.recipient,
+            raw_amount: row.raw_amount,
+            value: None,
+        }
+    }
+}
+
+/// Repository for the transfers decoded from proposal calls
+pub struct TransferRepository {
+    pool: PgPool,
+}
+
+impl TransferRepository {
+    /// Create a new transfer repository
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Replace the transfers of a proposal
+    ///
+    /// The transfers are written in one transaction, so readers never see
+    /// them partially replaced.
+    pub async fn replace(
+        &self,
+        proposal_id: &ProposalId,
+        transfers: &[ProposalTransfer],
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM proposal_transfers WHERE proposal_id = $1")
+            .bind(proposal_id.to_string())
+            .execute(&mut *tx)
+            .await?;
+        for transfer in transfers {
+            sqlx::query(
+                r#"
+                INSERT INTO proposal_transfers (proposal_id, call_index, token, recipient, raw_amount)
+                VALUES ($1, $2, $3, $4, $5::NUMERIC)
+                "#,
+            )
+            .bind(proposal_id.to_string())
+            .bind(transfer.call_index as i32)
+            .bind(&transfer.token)
+            .bind(&transfer.recipient)
+            .bind(&transfer.raw_amount)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await
+    }
+
+    /// Transfers of a proposal, in call order, without values
+    pub async fn for_proposal(
+        &self,
+        proposal_id: &ProposalId,
+    ) -> Result<Vec<ProposalTransfer>, sqlx::Error> {
+        let rows: Vec<TransferRow> = sqlx::query_as(
+            r#"
+            SELECT call_index, token, recipient, raw_amount::TEXT AS raw_amount
+            FROM proposal_transfers
+            WHERE proposal_id = $1
+            ORDER BY call_index
+            "#,
+        )
+        .bind(proposal_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(ProposalTransfer::from).collect())
+    }
+}
@@ -0,0 +1,145 @@
+//! Treasury balance repository for database operations
+
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, PgPool};
+
+use crate::models::{TreasuryBalance, TreasuryTotal};
+
+/// Columns selected when loading balances
+const BALANCE_COLUMNS: &str =
+    "address, token, symbol, decimals, raw_balance::TEXT AS raw_balance, \
+     balance, block_number, recorded_at";
+
+/// Database row of the `treasury_balances` table
+#[derive(FromRow)]
+struct BalanceRow {
+    address: String,
+    token: Option<String>,
+    symbol: String,
+    decimals: i16,
+    raw_balance: String,
+    balance: f64,
+    block_number: i64,
+    recorded_at: DateTime<Utc>,
+}
+
+impl From<BalanceRow> for TreasuryBalance {
+    fn from(row: BalanceRow) -> Self {
+        TreasuryBalance {
+            address: row.address,
+            token: row.token,
+            symbol: row.symbol,
+            decimals: row.decimals as u8,
+            raw_balance: row.raw_balance,
+            balance: row.balance,
+            value_usd: None,
+            block_number: row.block_number as u64,
+            recorded_at: row.recorded_at,
+        }
+    }
+}
+
+/// Per-token total of a snapshot
+#[derive(FromRow)]
+struct TotalRow {
+    token: Option<String>,
+    symbol: String,
+    balance: f64,
+    recorded_at: DateTime<Utc>,
+}
+
+impl From<TotalRow> for TreasuryTotal {
+    fn from(row: TotalRow) -> Self {
+        TreasuryTotal {
+            token: row.token,
+            symbol: row.symbol,
+            balance: row.balance,
+            value_usd: None,
+            recorded_at: row.recorded_at,
+        }
+    }
+}
+
+/// Repository for treasury balance snapshots
+pub struct TreasuryRepository {
+    pool: PgPool,
+}
+
+impl TreasuryRepository {
+    /// Create a new treasury repository
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Store the balances of a snapshot of a protocol's treasury
+    ///
+    /// The balances are written in one transaction, so a snapshot is never
+    /// partially visible.
+    pub async fn record(
+        &self,
+        protocol: &str,
+        balances: &[TreasuryBalance],
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        for balance in balances {
+            sqlx::query(
+                r#"
+                INSERT INTO treasury_balances (
+                    protocol, address, token, symbol, decimals, raw_balance, balance,
+                    block_number, recorded_at
+                )
+                VALUES ($1, $2, $3, $4, $5, $6::NUMERIC, $7, $8, $9)
+                "#,
+            )
+            .bind(protocol)
+            .bind(&balance.address)
+            .bind(&balance.token)
+            .bind(&balance.symbol)
+            .bind(balance.decimals as i16)
+            .bind(&balance.raw_balance)
+            .bind(balance.balance)
+            .bind(balance.block_number as i64)
+            .bind(balance.recorded_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await
+    }
+
+    /// Balances of the latest snapshot of a protocol's treasury
+    pub async fn latest(&self, protocol: &str) -> Result<Vec<TreasuryBalance>, sqlx::Error> {
+        let query = format!(
+            "SELECT {BALANCE_COLUMNS} FROM treasury_balances \
+             WHERE protocol = $1 AND recorded_at = \
+             (SELECT MAX(recorded_at) FROM treasury_balances WHERE protocol = $1) \
+             ORDER BY address, token NULLS FIRST"
+        );
+        let rows: Vec<BalanceRow> = sqlx::query_as(&query)
+            .bind(protocol)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.into_iter().map(TreasuryBalance::from).collect())
+    }
+
+    /// Totals per token of the snapshots taken since `since`, oldest first
+    pub async fn history(
+        &self,
+        protocol: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<TreasuryTotal>, sqlx::Error> {
+        let rows: Vec<TotalRow> = sqlx::query_as(
+            r#"
+            SELECT token, symbol, SUM(balance) AS balance, recorded_at
+            FROM treasury_balances
+            WHERE protocol = $1 AND recorded_at >= $2
+            GROUP BY recorded_at, token, symbol
+            ORDER BY recorded_at, token NULLS FIRST
+            "#,
+        )
+        .bind(protocol)
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(TreasuryTotal::from).collect())
+    }
+}
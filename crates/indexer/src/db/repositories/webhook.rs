@@ -1,40 +1,401 @@
 //! Webhook repository for database operations
 
-use crate::services::webhook::WebhookRegistration;
-use sqlx::PgPool;
+use chrono::{DateTime, Utc};
+use sqlx::{types::Json, FromRow, PgPool};
+
+use crate::models::{
+    proposal::ProposalStatus, NewWebhook, ProtocolId, Webhook, WebhookDelivery, WebhookUpdate,
+};
+
+/// Columns selected when loading webhooks
+const WEBHOOK_COLUMNS: &str = "id, url, events, protocols, statuses, secret, previous_secret, \
+                               previous_secret_expires_at, enabled, disabled_reason, \
+                               consecutive_failures, last_delivery_at, created_at, updated_at";
+
+/// Columns selected when loading deliveries
+const DELIVERY_COLUMNS: &str = "id, webhook_id, event_id, event_type, attempts, status_code, \
+                                error, success, duration_ms, delivered_at";
+
+/// Database row of the `webhook_registrations` table
+#[derive(FromRow)]
+struct WebhookRow {
+    id: String,
+    url: String,
+    events: Json<Vec<String>>,
+    protocols: Vec<String>,
+    statuses: Vec<String>,
+    secret: String,
+    previous_secret: Option<String>,
+    previous_secret_expires_at: Option<DateTime<Utc>>,
+    enabled: bool,
+    disabled_reason: Option<String>,
+    consecutive_failures: i32,
+    last_delivery_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+/// Webhook with the secrets signing its deliveries
+#[derive(Debug, Clone)]
+pub struct StoredWebhook {
+    /// The webhook
+    pub webhook: Webhook,
+    /// Current signing secret
+    pub secret: String,
+    /// Secret replaced by the last rotation, signing until its grace period ends
+    pub previous_secret: Option<String>,
+}
+
+impl StoredWebhook {
+    /// Secrets that sign deliveries at `now`, the current one first
+    pub fn signing_secrets(&self, now: DateTime<Utc>) -> Vec<&str> {
+        let previous = self.previous_secret.as_deref().filter(|_| {
+            self.webhook
+                .previous_secret_expires_at
+                .is_some_and(|expires_at| expires_at > now)
+        });
+        std::iter::once(self.secret.as_str())
+            .chain(previous)
+            .collect()
+    }
+}
+
+impl TryFrom<WebhookRow> for StoredWebhook {
+    type Error = sqlx::Error;
+
+    fn try_from(row: WebhookRow) -> Result<Self, Self::Error> {
+        let decode = |e: String| {
+            sqlx::Error::Decode(Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                e,
+            )))
+        };
+
+        Ok(StoredWebhook {
+            webhook: Webhook {
+                id: row.id,
+                url: row.url,
+                events: row.events.0,
+                protocols: row
+                    .protocols
+                    .iter()
+                    .map(|protocol| protocol.parse::<ProtocolId>())
+                    .collect::<Result<_, _>>()
+                    .map_err(|e| decode(e.to_string()))?,
+                statuses: row
+                    .statuses
+                    .iter()
+                    .map(|status| status.parse::<ProposalStatus>())
+                    .collect::<Result<_, _>>()
+                    .map_err(decode)?,
+                enabled: row.enabled,
+                disabled_reason: row.disabled_reason,
+                consecutive_failures: row.consecutive_failures,
+                previous_secret_expires_at: row.previous_secret_expires_at,
+                last_delivery_at: row.last_delivery_at,
+                created_at: row.created_at,
+                updated_at: row.updated_at,
+            },
+            secret: row.secret,
+            previous_secret: row.previous_secret,
+        })
+    }
+}
+
+/// Database row of the `webhook_deliveries` table
+#[derive(FromRow)]
+struct DeliveryRow {
+    id: i64,
+    webhook_id: String,
+    event_id: Option<i64>,
+    event_type: String,
+    attempts: i32,
+    status_code: Option<i32>,
+    error: Option<String>,
+    success: bool,
+    duration_ms: i64,
+    delivered_at: DateTime<Utc>,
+}
+
+impl From<DeliveryRow> for WebhookDelivery {
+    fn from(row: DeliveryRow) -> Self {
+        WebhookDelivery {
+            id: row.id,
+            webhook_id: row.webhook_id,
+            event_id: row.event_id,
+            event_type: row.event_type,
+            attempts: row.attempts,
+            status_code: row.status_code,
+            error: row.error,
+            success: row.success,
+            duration_ms: row.duration_ms,
+            delivered_at: row.delivered_at,
+        }
+    }
+}
+
+/// Outcome of a delivery to log
+#[derive(Debug, Clone)]
+pub struct NewDelivery<'a> {
+    /// Webhook the event was posted to
+    pub webhook_id: &'a str,
+    /// Position of the event in the event log, absent for pings
+    pub event_id: Option<i64>,
+    /// Type of the event
+    pub event_type: &'a str,
+    /// Requests sent, including retries
+    pub attempts: i32,
+    /// Status of the last response
+    pub status_code: Option<i32>,
+    /// Error of the last attempt
+    pub error: Option<String>,
+    /// Whether the webhook accepted the event
+    pub success: bool,
+    /// Time spent delivering, in milliseconds
+    pub duration_ms: i64,
+}
+
+fn protocol_strings(protocols: &[ProtocolId]) -> Vec<String> {
+    protocols.iter().map(ProtocolId::to_string).collect()
+}
+
+fn status_strings(statuses: &[ProposalStatus]) -> Vec<&'static str> {
+    statuses.iter().map(ProposalStatus::as_str).collect()
+}
 
 /// Repository for webhook operations
-#[allow(dead_code)] // TODO: Remove after development phase
 pub struct WebhookRepository {
     pool: PgPool,
 }
 
-#[allow(unused_variables)] // TODO: Remove after development phase
 impl WebhookRepository {
     /// Create a new webhook repository
-    #[allow(dead_code)] // TODO: Remove after development phase
     pub fn new(pool: PgPool) -> Self {
         Self { pool }
     }
 
+    /// Store a new webhook signing with `secret`
+    pub async fn create(
+        &self,
+        id: &str,
+        webhook: &NewWebhook,
+        secret: &str,
+    ) -> Result<Webhook, sqlx::Error> {
+        let query = format!(
+            "INSERT INTO webhook_registrations (id, url, events, protocols, statuses, secret) \
+             VALUES ($1, $2, $3, $4, $5, $6) RETURNING {WEBHOOK_COLUMNS}"
+        );
+        let row: WebhookRow = sqlx::query_as(&query)
+            .bind(id)
+            .bind(&webhook.url)
+            .bind(Json(&webhook.events))
+            .bind(protocol_strings(&webhook.protocols))
+            .bind(status_strings(&webhook.statuses))
+            .bind(secret)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(StoredWebhook::try_from(row)?.webhook)
+    }
+
     /// Find webhook by ID
-    #[allow(dead_code)] // TODO: Remove after development phase
-    pub async fn find_by_id(&self, id: &str) -> Result<Option<WebhookRegistration>, sqlx::Error> {
-        // TODO: Implement find by ID
-        todo!("Implement find_by_id")
+    pub async fn find_by_id(&self, id: &str) -> Result<Option<StoredWebhook>, sqlx::Error> {
+        let query = format!("SELECT {WEBHOOK_COLUMNS} FROM webhook_registrations WHERE id = $1");
+        let row: Option<WebhookRow> = sqlx::query_as(&query)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.map(StoredWebhook::try_from).transpose()
+    }
+
+    /// All webhooks, oldest first
+    pub async fn list(&self) -> Result<Vec<Webhook>, sqlx::Error> {
+        let query =
+            format!("SELECT {WEBHOOK_COLUMNS} FROM webhook_registrations ORDER BY created_at, id");
+        let rows: Vec<WebhookRow> = sqlx::query_as(&query).fetch_all(&self.pool).await?;
+
+        rows.into_iter()
+            .map(|row| StoredWebhook::try_from(row).map(|stored| stored.webhook))
+            .collect()
+    }
+
+    /// Enabled webhooks with their secrets
+    pub async fn enabled(&self) -> Result<Vec<StoredWebhook>, sqlx::Error> {
+        let query = format!(
+            "SELECT {WEBHOOK_COLUMNS} FROM webhook_registrations WHERE enabled \
+             ORDER BY created_at, id"
+        );
+        let rows: Vec<WebhookRow> = sqlx::query_as(&query).fetch_all(&self.pool).await?;
+
+        rows.into_iter().map(StoredWebhook::try_from).collect()
     }
 
-    /// Save webhook registration
-    #[allow(dead_code)] // TODO: Remove after development phase
-    pub async fn save(&self, registration: &WebhookRegistration) -> Result<(), sqlx::Error> {
-        // TODO: Implement save
-        todo!("Implement save")
+    /// Apply changes to a webhook, returning it unless it does not exist
+    ///
+    /// Enabling a webhook clears its failure count and the reason it was
+    /// disabled.
+    pub async fn update(
+        &self,
+        id: &str,
+        update: &WebhookUpdate,
+    ) -> Result<Option<Webhook>, sqlx::Error> {
+        let query = format!(
+            "UPDATE webhook_registrations SET \
+             url = COALESCE($2, url), \
+             events = COALESCE($3, events), \
+             protocols = COALESCE($4, protocols), \
+             statuses = COALESCE($5, statuses), \
+             enabled = COALESCE($6, enabled), \
+             consecutive_failures = CASE WHEN $6 THEN 0 ELSE consecutive_failures END, \
+             disabled_reason = CASE WHEN $6 IS NULL THEN disabled_reason END, \
+             updated_at = NOW() \
+             WHERE id = $1 RETURNING {WEBHOOK_COLUMNS}"
+        );
+        let row: Option<WebhookRow> = sqlx::query_as(&query)
+            .bind(id)
+            .bind(&update.url)
+            .bind(update.events.as_ref().map(Json))
+            .bind(update.protocols.as_deref().map(protocol_strings))
+            .bind(update.statuses.as_deref().map(status_strings))
+            .bind(update.enabled)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.map(|row| StoredWebhook::try_from(row).map(|stored| stored.webhook))
+            .transpose()
     }
 
-    /// Delete webhook registration
-    #[allow(dead_code)] // TODO: Remove after development phase
-    pub async fn delete(&self, id: &str) -> Result<(), sqlx::Error> {
-        // TODO: Implement delete
-        todo!("Implement delete")
+    /// Replace the secret of a webhook, keeping the old one for `grace_secs`
+    pub async fn rotate_secret(
+        &self,
+        id: &str,
+        secret: &str,
+        grace_secs: i64,
+    ) -> Result<Option<Webhook>, sqlx::Error> {
+        let query = format!(
+            "UPDATE webhook_registrations SET \
+             previous_secret = secret, \
+             previous_secret_expires_at = NOW() + make_interval(secs => $3), \
+             secret = $2, \
+             updated_at = NOW() \
+             WHERE id = $1 RETURNING {WEBHOOK_COLUMNS}"
+        );
+        let row: Option<WebhookRow> = sqlx::query_as(&query)
+            .bind(id)
+            .bind(secret)
+            .bind(grace_secs as f64)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.map(|row| StoredWebhook::try_from(row).map(|stored| stored.webhook))
+            .transpose()
+    }
+
+    /// Delete webhook registration and its delivery log
+    pub async fn delete(&self, id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM webhook_registrations WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Log a delivery
+    pub async fn record_delivery(
+        &self,
+        delivery: &NewDelivery<'_>,
+    ) -> Result<WebhookDelivery, sqlx::Error> {
+        let query = format!(
+            "INSERT INTO webhook_deliveries \
+             (webhook_id, event_id, event_type, attempts, status_code, error, success, duration_ms) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING {DELIVERY_COLUMNS}"
+        );
+        let row: DeliveryRow = sqlx::query_as(&query)
+            .bind(delivery.webhook_id)
+            .bind(delivery.event_id)
+            .bind(delivery.event_type)
+            .bind(delivery.attempts)
+            .bind(delivery.status_code)
+            .bind(&delivery.error)
+            .bind(delivery.success)
+            .bind(delivery.duration_ms)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(row.into())
+    }
+
+    /// Count a delivery outcome, disabling the webhook after `failure_limit` failures in a row
+    ///
+    /// Returns the updated webhook unless it was deleted meanwhile.
+    pub async fn record_outcome(
+        &self,
+        id: &str,
+        success: bool,
+        failure_limit: i32,
+    ) -> Result<Option<Webhook>, sqlx::Error> {
+        let query = format!(
+            "UPDATE webhook_registrations SET \
+             consecutive_failures = CASE WHEN $2 THEN 0 ELSE consecutive_failures + 1 END, \
+             last_delivery_at = NOW(), \
+             enabled = enabled AND ($2 OR consecutive_failures + 1 < $3), \
+             disabled_reason = CASE \
+                 WHEN enabled AND NOT $2 AND consecutive_failures + 1 >= $3 \
+                 THEN 'Disabled after ' || (consecutive_failures + 1) || ' failed deliveries in a row' \
+                 ELSE disabled_reason END \
+             WHERE id = $1 RETURNING {WEBHOOK_COLUMNS}"
+        );
+        let row: Option<WebhookRow> = sqlx::query_as(&query)
+            .bind(id)
+            .bind(success)
+            .bind(failure_limit)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.map(|row| StoredWebhook::try_from(row).map(|stored| stored.webhook))
+            .transpose()
+    }
+
+    /// Latest deliveries to a webhook, newest first
+    pub async fn deliveries(
+        &self,
+        webhook_id: &str,
+        limit: i64,
+    ) -> Result<Vec<WebhookDelivery>, sqlx::Error> {
+        let query = format!(
+            "SELECT {DELIVERY_COLUMNS} FROM webhook_deliveries WHERE webhook_id = $1 \
+             ORDER BY id DESC LIMIT $2"
+        );
+        let rows: Vec<DeliveryRow> = sqlx::query_as(&query)
+            .bind(webhook_id)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    /// ID of the last event the dispatcher finished delivering, if it ever started
+    pub async fn dispatch_cursor(&self) -> Result<Option<i64>, sqlx::Error> {
+        sqlx::query_scalar("SELECT last_event_id FROM webhook_dispatch_cursor")
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    /// Move the dispatcher past an event, never backwards
+    pub async fn save_dispatch_cursor(&self, last_event_id: i64) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO webhook_dispatch_cursor (last_event_id) VALUES ($1) \
+             ON CONFLICT (id) DO UPDATE SET \
+             last_event_id = GREATEST(webhook_dispatch_cursor.last_event_id, EXCLUDED.last_event_id), \
+             updated_at = NOW()",
+        )
+        .bind(last_event_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
     }
 }
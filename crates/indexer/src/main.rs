@@ -1,14 +1,31 @@
-use clap::Parser;
-use tracing::info;
+use std::{collections::BTreeSet, time::Duration};
 
-use indexer::config::Config;
+use anyhow::anyhow;
+use chrono::Utc;
+use clap::Parser;
+use tracing::{info, warn};
 
-mod api;
-mod config;
-mod db;
-mod models;
-mod services;
-mod utils;
+use indexer::{
+    api::{create_router, AppState},
+    config::{ApiKeyCommand, Command, Config, PricesCommand},
+    db::{
+        self,
+        repositories::{ApiKeyRepository, PriceRepository},
+    },
+    models::BackfillSource,
+    services::{
+        auth::KeySettings,
+        backfill::BackfillRequest,
+        dataset::ExportOptions,
+        embeddings::provider_from_config,
+        prices::{price_provider_from_config, CsvPriceProvider},
+        Authenticator, Backfiller, Crawler, DatasetExporter, EventBus, EventLog, HttpClient,
+        IngestOutcome, IngestPipeline, LeaderElection, LifecycleEngine, Metrics, PriceService,
+        PriceSync, QualityAssessor, RegistryService, RpcClient, SimilaritySearch, TreasuryTracker,
+        Verifier, WebhookService,
+    },
+    utils::id::ProposalId,
+};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -20,20 +37,276 @@ async fn main() -> anyhow::Result<()> {
         std::env::set_var("RUST_LOG", "info");
     }
 
-    // Initialize tracing
-    tracing_subscriber::fmt()
-        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
-        .with_target(false)
-        .with_thread_ids(true)
-        .with_thread_names(true)
-        .init();
+    // Initialize tracing, exported spans are flushed when the guard drops
+    let _tracing = telemetry::init_logging(config.log_format, config.otlp().as_ref())?;
 
     info!("Starting Wei Indexer service...");
 
-    // TODO: Initialize database connection
-    // TODO: Initialize services
-    // TODO: Start API server
-    // TODO: Start background indexing tasks
+    let db = db::init_database(&config.database_url).await?;
+    info!("Database initialized successfully with migrations");
+
+    let datasets = DatasetExporter::new(db.clone(), config.dataset_dir.clone());
+    if let Some(Command::Export(args)) = &config.command {
+        let manifest = datasets
+            .export(&ExportOptions {
+                version: args.version.clone(),
+                formats: args.formats.clone(),
+                ratios: args.splits,
+                seed: args.seed.clone(),
+            })
+            .await?;
+        info!(
+            "Wrote dataset {} to {}",
+            manifest.version,
+            datasets.version_dir(&manifest.version).display()
+        );
+        return Ok(());
+    }
+
+    let auth = Authenticator::new(db.clone(), config.auth());
+    if let Some(Command::ApiKey(command)) = &config.command {
+        let repository = ApiKeyRepository::new(db.clone());
+        match command {
+            ApiKeyCommand::Create(args) => {
+                let created = auth
+                    .create_key(&KeySettings {
+                        name: args.name.clone(),
+                        scopes: args.scopes.clone(),
+                        rate_limit: args.rate_limit,
+                        signing: args.signing,
+                    })
+                    .await?;
+                info!(
+                    "Created API key {} ({})",
+                    created.key.id, created.key.prefix
+                );
+                // Secrets go to stdout only, they are not stored in plain text
+                println!("API key: {}", created.secret);
+                if let Some(signing_secret) = &created.signing_secret {
+                    println!("Signing secret: {signing_secret}");
+                }
+            }
+            ApiKeyCommand::List => {
+                for key in repository.list().await? {
+                    let scopes: Vec<&str> = key.scopes.iter().map(|s| s.as_str()).collect();
+                    println!(
+                        "{}\t{}\t{}\t{}\t{}",
+                        key.id,
+                        key.prefix,
+                        key.name,
+                        scopes.join(","),
+                        if key.revoked_at.is_some() {
+                            "revoked"
+                        } else {
+                            "active"
+                        }
+                    );
+                }
+            }
+            ApiKeyCommand::Revoke { id } => {
+                let key = repository
+                    .revoke(*id)
+                    .await?
+                    .ok_or_else(|| anyhow!("API key {} not found", id))?;
+                info!("Revoked API key {} ({})", key.id, key.prefix);
+            }
+        }
+        return Ok(());
+    }
+
+    let registry_config = config.registry();
+    let registry = RegistryService::init(&registry_config, db.clone()).await?;
+    info!(
+        "Loaded {} protocols from {}",
+        registry.current().protocols.len(),
+        registry_config.path.display()
+    );
+
+    match &config.command {
+        Some(Command::Backfill(args)) => {
+            let target = match args.source {
+                BackfillSource::Snapshot => args.space.clone(),
+                BackfillSource::Tally => args.organization.clone(),
+            }
+            .ok_or_else(|| anyhow!("No {} target given", args.source.as_str()))?;
+            let backfiller = Backfiller::new(db.clone(), registry.current(), config.data_sources());
+            let job = backfiller
+                .backfill(&BackfillRequest {
+                    source: args.source,
+                    target,
+                    since: args.since,
+                    restart: args.restart,
+                })
+                .await?;
+            info!(
+                "Backfill {} completed: {} records fetched, {} stored, {} quarantined",
+                job.id, job.fetched, job.stored, job.quarantined
+            );
+            for host in backfiller.http().metrics() {
+                info!(
+                    "{}: {} requests, {} throttled, {} rate limited, {} retries",
+                    host.host, host.requests, host.throttled, host.rate_limited, host.retries
+                );
+            }
+            return Ok(());
+        }
+        Some(Command::Reindex(args)) => {
+            let backfiller = Backfiller::new(db.clone(), registry.current(), config.data_sources());
+            for (i, id) in args.proposals.iter().enumerate() {
+                let id: ProposalId = id.parse()?;
+                match backfiller.reindex(&id).await? {
+                    IngestOutcome::Stored(_) => info!(
+                        "Reindexed proposal {} ({}/{})",
+                        id,
+                        i + 1,
+                        args.proposals.len()
+                    ),
+                    IngestOutcome::Quarantined { id: entry, errors } => {
+                        warn!("Proposal {} quarantined (entry {}): {}", id, entry, errors)
+                    }
+                    IngestOutcome::Removed(_) => info!(
+                        "Removed proposal {} unpublished by {} ({}/{})",
+                        id,
+                        id.source(),
+                        i + 1,
+                        args.proposals.len()
+                    ),
+                }
+            }
+            return Ok(());
+        }
+        Some(Command::Verify) => {
+            let report = Verifier::new(db.clone())
+                .verify(&registry.current())
+                .await?;
+            for issue in &report.issues {
+                warn!("{}: {}", issue.proposal_id, issue.message);
+            }
+            for job in &report.unfinished_backfills {
+                warn!(
+                    "Backfill {} of {} {} is {}",
+                    job.id,
+                    job.source.as_str(),
+                    job.target,
+                    job.status.as_str()
+                );
+            }
+            info!(
+                "Verified {} proposals, {} records pending in quarantine",
+                report.proposals, report.pending_quarantine
+            );
+            if !report.issues.is_empty() {
+                return Err(anyhow!("Found {} inconsistencies", report.issues.len()));
+            }
+            return Ok(());
+        }
+        Some(Command::Prices(PricesCommand::Import { file })) => {
+            let prices = CsvPriceProvider::read(file).await?;
+            let stored = PriceRepository::new(db.clone()).upsert(&prices).await?;
+            // Prices of unlisted assets are kept, but no token is valued with them
+            let current = registry.current();
+            let unlisted: BTreeSet<&str> = prices
+                .iter()
+                .map(|price| price.asset.as_str())
+                .filter(|asset| !current.tokens.iter().any(|token| token.price_id == *asset))
+                .collect();
+            for asset in unlisted {
+                warn!("No registry token has the price ID {}", asset);
+            }
+            info!("Imported {} prices from {}", stored, file.display());
+            return Ok(());
+        }
+        Some(Command::Prices(PricesCommand::Sync)) => {
+            let prices = config.prices();
+            let provider =
+                price_provider_from_config(&prices, HttpClient::new(&config.data_sources()))
+                    .ok_or_else(|| anyhow!("No price provider is configured"))?;
+            let stored = PriceService::new(db.clone(), registry.clone())
+                .sync(&*provider, Utc::now().date_naive(), prices.history_days)
+                .await?;
+            info!("Stored {} prices from {}", stored, provider.name());
+            return Ok(());
+        }
+        Some(Command::Serve | Command::Export(_) | Command::ApiKey(_)) | None => {}
+    }
+
+    registry.spawn_watcher(Duration::from_secs(registry_config.reload_interval_secs));
+
+    let server = config.server();
+    let listener = tokio::net::TcpListener::bind((server.host.as_str(), server.port)).await?;
+    let events = EventBus::default();
+    let event_log = EventLog::new(db.clone(), events.clone());
+    let metrics = Metrics::default();
+    let leader = LeaderElection::new(
+        db.clone(),
+        config
+            .replica_id
+            .clone()
+            .unwrap_or_else(LeaderElection::default_replica_id),
+        Duration::from_secs(config.lease_ttl),
+    );
+    let webhooks = WebhookService::spawn(
+        db.clone(),
+        &event_log,
+        leader.clone(),
+        config.webhook(),
+        metrics.clone(),
+    );
+    let lifecycle = LifecycleEngine::spawn(db.clone(), events.clone()).await?;
+    let similarity = SimilaritySearch::spawn(
+        db.clone(),
+        &events,
+        provider_from_config(&config.embedding()),
+    )
+    .await?;
+    QualityAssessor::spawn_backfill(db.clone());
+
+    let http = HttpClient::new(&config.data_sources());
+    Crawler::spawn(
+        registry.clone(),
+        config.data_sources(),
+        http.clone(),
+        leader.clone(),
+        IngestPipeline::new(db.clone(), events.clone(), lifecycle.clone())
+            .with_metrics(metrics.clone()),
+    );
+    let treasury = config.treasury();
+    TreasuryTracker::spawn(
+        registry.clone(),
+        RpcClient::new(http.clone(), &treasury.rpc_urls),
+        leader.clone(),
+        db.clone(),
+        Duration::from_secs(treasury.interval_secs),
+    );
+    let prices = config.prices();
+    if let Some(provider) = price_provider_from_config(&prices, http.clone()) {
+        PriceSync::spawn(
+            PriceService::new(db.clone(), registry.clone()),
+            provider,
+            leader.clone(),
+            &prices,
+        );
+    }
+
+    let router = create_router(AppState {
+        db,
+        registry,
+        events,
+        event_log,
+        lifecycle,
+        similarity,
+        datasets,
+        http,
+        leader: leader.clone(),
+        auth,
+        webhooks,
+        metrics,
+    });
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, router).await {
+            tracing::error!("API server error: {}", e);
+        }
+    });
 
     info!(
         "Wei Indexer service started successfully on port {}",
@@ -46,5 +319,9 @@ async fn main() -> anyhow::Result<()> {
         .expect("Failed to listen for ctrl+c");
 
     info!("Shutting down Wei Indexer service...");
+    match leader.release_all().await {
+        Ok(released) => info!("Released {} data source leases", released),
+        Err(e) => warn!("Failed to release data source leases: {}", e),
+    }
     Ok(())
 }
@@ -0,0 +1,78 @@
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// Permission granted to an API key
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, ValueEnum,
+)]
+pub enum ApiScope {
+    /// Read proposals, labels, datasets and events
+    #[serde(rename = "read")]
+    #[value(name = "read")]
+    Read,
+    /// Register and manage webhooks
+    #[serde(rename = "webhooks:write")]
+    #[value(name = "webhooks:write")]
+    WebhooksWrite,
+    /// Everything, including writes and API key management
+    #[serde(rename = "admin")]
+    #[value(name = "admin")]
+    Admin,
+}
+
+impl ApiScope {
+    /// Get the scope as a string
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiScope::Read => "read",
+            ApiScope::WebhooksWrite => "webhooks:write",
+            ApiScope::Admin => "admin",
+        }
+    }
+}
+
+impl std::str::FromStr for ApiScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "read" => Ok(Self::Read),
+            "webhooks:write" => Ok(Self::WebhooksWrite),
+            "admin" => Ok(Self::Admin),
+            other => Err(format!("Unknown API scope: {other}")),
+        }
+    }
+}
+
+/// Key of an API client, without its secret
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiKey {
+    /// Key ID
+    pub id: i64,
+    /// Name of the client the key was issued to
+    pub name: String,
+    /// Public part of the key, identifying it in logs and signed requests
+    pub prefix: String,
+    /// Permissions of the key
+    pub scopes: Vec<ApiScope>,
+    /// Requests per minute allowed, unlimited if unset
+    pub rate_limit: Option<i32>,
+    /// Whether the key can sign requests with HMAC
+    pub signing: bool,
+    /// Time the key was created
+    pub created_at: DateTime<Utc>,
+    /// Time the key was last used, to the minute
+    pub last_used_at: Option<DateTime<Utc>>,
+    /// Time the key was revoked
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl ApiKey {
+    /// Whether the key grants a scope; `admin` grants every scope
+    pub fn allows(&self, scope: ApiScope) -> bool {
+        self.scopes
+            .iter()
+            .any(|granted| *granted == scope || *granted == ApiScope::Admin)
+    }
+}
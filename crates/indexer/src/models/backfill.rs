@@ -0,0 +1,106 @@
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// Data source a backfill crawls
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum BackfillSource {
+    /// A Snapshot space
+    Snapshot,
+    /// A Tally organization
+    Tally,
+}
+
+impl BackfillSource {
+    /// Name of the data source
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Snapshot => "snapshot",
+            Self::Tally => "tally",
+        }
+    }
+}
+
+impl std::str::FromStr for BackfillSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "snapshot" => Ok(Self::Snapshot),
+            "tally" => Ok(Self::Tally),
+            other => Err(format!("Unknown backfill source: {other}")),
+        }
+    }
+}
+
+/// Status of a backfill job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackfillStatus {
+    /// Pages are left to crawl
+    Running,
+    /// The last page was crawled
+    Completed,
+    /// The last run stopped on an error and can be resumed
+    Failed,
+}
+
+impl BackfillStatus {
+    /// Database representation of the status
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Running => "running",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+impl std::str::FromStr for BackfillStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "running" => Ok(Self::Running),
+            "completed" => Ok(Self::Completed),
+            "failed" => Ok(Self::Failed),
+            other => Err(format!("Unknown backfill status: {other}")),
+        }
+    }
+}
+
+/// Resumable crawl of a Snapshot space or Tally organization
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackfillJob {
+    /// Job ID
+    pub id: i64,
+    /// Data source crawled
+    pub source: BackfillSource,
+    /// Snapshot space or Tally organization slug
+    pub target: String,
+    /// Canonical ID of the protocol the target belongs to
+    pub protocol_id: String,
+    /// Only proposals created at or after this time are crawled
+    pub since: DateTime<Utc>,
+    /// Cursor of the next page, `None` before the first page
+    pub cursor: Option<String>,
+    /// Job status
+    pub status: BackfillStatus,
+    /// Number of pages crawled
+    pub pages: i64,
+    /// Number of records fetched
+    pub fetched: i64,
+    /// Number of records stored as proposals
+    pub stored: i64,
+    /// Number of records sent to quarantine
+    pub quarantined: i64,
+    /// Error that stopped the last run
+    pub error: Option<String>,
+    /// Time the job was created
+    pub created_at: DateTime<Utc>,
+    /// Time of the last progress
+    pub updated_at: DateTime<Utc>,
+    /// Time the last page was crawled
+    pub completed_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,84 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Kind of entity recorded in the change log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntityType {
+    /// A governance proposal
+    Proposal,
+    /// A protocol of the registry
+    Protocol,
+}
+
+impl EntityType {
+    /// Database representation of the entity type
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Proposal => "proposal",
+            Self::Protocol => "protocol",
+        }
+    }
+}
+
+impl std::str::FromStr for EntityType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "proposal" => Ok(Self::Proposal),
+            "protocol" => Ok(Self::Protocol),
+            other => Err(format!("Unknown entity type: {other}")),
+        }
+    }
+}
+
+/// Kind of change made to an entity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeOperation {
+    /// The entity was created or updated
+    Upsert,
+    /// The entity was deleted
+    Delete,
+}
+
+impl ChangeOperation {
+    /// Database representation of the operation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Upsert => "upsert",
+            Self::Delete => "delete",
+        }
+    }
+}
+
+impl std::str::FromStr for ChangeOperation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "upsert" => Ok(Self::Upsert),
+            "delete" => Ok(Self::Delete),
+            other => Err(format!("Unknown change operation: {other}")),
+        }
+    }
+}
+
+/// Entry of the change log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Change {
+    /// Global sequence number, increasing with every change
+    pub seq: i64,
+    /// Kind of entity changed
+    pub entity_type: EntityType,
+    /// Canonical ID of the entity
+    pub entity_id: String,
+    /// Kind of change
+    pub operation: ChangeOperation,
+    /// Entity after the change, `None` for deletions
+    pub data: Option<Value>,
+    /// Time of the change
+    pub changed_at: DateTime<Utc>,
+}
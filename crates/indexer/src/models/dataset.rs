@@ -0,0 +1,288 @@
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    label::{LabelCriteria, LabelValue, ProposalLabel},
+    proposal::{ProposalStatus, VotingType},
+};
+
+/// Version of the dataset record schema, bumped on incompatible changes
+pub const DATASET_SCHEMA_VERSION: u32 = 1;
+
+/// Part of a dataset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DatasetSplit {
+    /// Examples the agent is tuned on
+    Train,
+    /// Examples checked after every merge to `dev`
+    Validation,
+    /// Unseen examples only checked on releases
+    Test,
+}
+
+impl DatasetSplit {
+    /// All splits, in file order
+    pub const ALL: [Self; 3] = [Self::Train, Self::Validation, Self::Test];
+
+    /// Name of the split, also used as file stem
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Train => "train",
+            Self::Validation => "validation",
+            Self::Test => "test",
+        }
+    }
+}
+
+/// File format of an exported dataset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum DatasetFormat {
+    /// One JSON record per line
+    Jsonl,
+    /// Apache Parquet, with labels as a JSON string column
+    Parquet,
+}
+
+impl DatasetFormat {
+    /// File extension of the format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Jsonl => "jsonl",
+            Self::Parquet => "parquet",
+        }
+    }
+}
+
+/// Outcome of a closed vote
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    /// The proposal was accepted or executed
+    Passed,
+    /// The proposal was rejected
+    Failed,
+}
+
+impl Outcome {
+    /// Outcome of a proposal with a status, `None` while voting or if cancelled
+    pub fn of(status: ProposalStatus) -> Option<Self> {
+        match status {
+            ProposalStatus::Accepted | ProposalStatus::Executed => Some(Self::Passed),
+            ProposalStatus::Rejected => Some(Self::Failed),
+            ProposalStatus::Active | ProposalStatus::Pending | ProposalStatus::Cancelled => None,
+        }
+    }
+
+    /// Name of the outcome
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Passed => "passed",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+/// Relative sizes of the train, validation and test splits
+///
+/// Written as `train:validation:test`. The default `5:10:5` matches the
+/// evaluation process of the agent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SplitRatios {
+    /// Weight of the train split
+    pub train: u32,
+    /// Weight of the validation split
+    pub validation: u32,
+    /// Weight of the test split
+    pub test: u32,
+}
+
+impl SplitRatios {
+    /// Sum of the weights, `None` if it overflows
+    pub fn total(&self) -> Option<u32> {
+        self.train
+            .checked_add(self.validation)?
+            .checked_add(self.test)
+    }
+
+    /// Check that the weights add up to a positive `u32`
+    pub fn validate(&self) -> Result<(), String> {
+        match self.total() {
+            Some(total) if total > 0 => Ok(()),
+            _ => Err(format!(
+                "Invalid split ratios: {self}, the weights must add up to between 1 and {}",
+                u32::MAX
+            )),
+        }
+    }
+}
+
+impl Default for SplitRatios {
+    fn default() -> Self {
+        Self {
+            train: 5,
+            validation: 10,
+            test: 5,
+        }
+    }
+}
+
+impl fmt::Display for SplitRatios {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.train, self.validation, self.test)
+    }
+}
+
+impl FromStr for SplitRatios {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let weights: Vec<u32> = s
+            .split(':')
+            .map(|weight| weight.trim().parse())
+            .collect::<Result<_, _>>()
+            .map_err(|_| format!("Invalid split ratios: {s}"))?;
+
+        let [train, validation, test] = weights[..] else {
+            return Err(format!(
+                "Invalid split ratios: {s}, expected train:validation:test"
+            ));
+        };
+        let ratios = Self {
+            train,
+            validation,
+            test,
+        };
+        ratios.validate()?;
+        Ok(ratios)
+    }
+}
+
+/// Track record of a proposal's author before the proposal was created
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthorMetrics {
+    /// Proposals created earlier by the author, in any protocol
+    pub prior_proposals: u32,
+    /// Earlier proposals that passed
+    pub prior_passed: u32,
+    /// Earlier proposals that failed
+    pub prior_failed: u32,
+}
+
+/// Label of a dataset record
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordLabel {
+    /// Name of the labeler
+    pub labeler: String,
+    /// Verdict of the labeler
+    pub label: LabelValue,
+    /// Results on the evaluation categories
+    pub criteria: LabelCriteria,
+    /// Reasons given for the verdict
+    pub rationale: Option<String>,
+}
+
+impl From<&ProposalLabel> for RecordLabel {
+    fn from(label: &ProposalLabel) -> Self {
+        Self {
+            labeler: label.labeler.clone(),
+            label: label.label,
+            criteria: label.criteria,
+            rationale: label.rationale.clone(),
+        }
+    }
+}
+
+/// Proposal with its outcome, votes, author metrics and labels
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DatasetRecord {
+    /// Canonical proposal ID
+    pub id: String,
+    /// Protocol of the proposal
+    pub protocol_id: String,
+    /// Source the proposal was indexed from
+    pub source: String,
+    /// Title of the proposal
+    pub title: String,
+    /// Description of the proposal
+    pub description: String,
+    /// Choices of the vote
+    pub choices: Vec<String>,
+    /// Author of the proposal
+    pub author: String,
+    /// Creation time
+    pub created_at: DateTime<Utc>,
+    /// Time voting opened
+    pub voting_start: Option<DateTime<Utc>>,
+    /// Time voting closed
+    pub voting_end: Option<DateTime<Utc>>,
+    /// Voting system
+    pub voting_type: VotingType,
+    /// Final status, e.g. `accepted` or `executed`
+    pub status: String,
+    /// Outcome of the vote
+    pub outcome: Outcome,
+    /// Voting power cast for each choice
+    pub scores: Vec<f64>,
+    /// Total voting power cast
+    pub scores_total: Option<f64>,
+    /// Voting power required for the result to be valid
+    pub quorum: Option<f64>,
+    /// Earliest proposal this one is a near-duplicate of
+    pub is_duplicate_of: Option<String>,
+    /// Spam likelihood from 0 to 1
+    pub spam_score: Option<f64>,
+    /// Track record of the author
+    pub author_metrics: AuthorMetrics,
+    /// Human labels, oldest first
+    pub labels: Vec<RecordLabel>,
+    /// Resolved verdict, or else the majority verdict of the labels, unset
+    /// without labels or on a tie
+    pub label: Option<LabelValue>,
+    /// Resolved evaluation results, or else the results all labels agree on
+    pub criteria: Option<LabelCriteria>,
+    /// Split the record belongs to
+    pub split: DatasetSplit,
+}
+
+/// Exported dataset file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DatasetFile {
+    /// Path relative to the dataset directory
+    pub path: String,
+    /// File format
+    pub format: DatasetFormat,
+    /// Split stored in the file
+    pub split: DatasetSplit,
+    /// Number of records
+    pub rows: usize,
+    /// Hex SHA-256 checksum of the file
+    pub sha256: String,
+}
+
+/// Manifest of an exported dataset, stored as `manifest.json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DatasetManifest {
+    /// Version of the dataset, also its directory name
+    pub version: String,
+    /// Version of the record schema
+    pub schema_version: u32,
+    /// Time of the export
+    pub created_at: DateTime<Utc>,
+    /// Seed of the split assignment
+    pub seed: String,
+    /// Relative split sizes
+    pub ratios: SplitRatios,
+    /// Number of records per split
+    pub splits: BTreeMap<DatasetSplit, usize>,
+    /// Number of records per split of every `<protocol>/<outcome>` stratum
+    pub strata: BTreeMap<String, BTreeMap<DatasetSplit, usize>>,
+    /// Number of records with a resolved or majority label
+    pub labeled: usize,
+    /// Exported files
+    pub files: Vec<DatasetFile>,
+}
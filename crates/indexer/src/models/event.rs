@@ -0,0 +1,24 @@
+pub use governance::event::{
+    EnvelopeError, EventEnvelope, IndexerEvent, EVENT_TYPES, EVENT_VERSION,
+};
+
+use super::ProtocolId;
+
+/// Selection of events by protocol and type
+///
+/// Empty lists match everything.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EventFilter {
+    /// Protocols to include
+    pub protocols: Vec<ProtocolId>,
+    /// Event types to include
+    pub types: Vec<String>,
+}
+
+impl EventFilter {
+    /// Whether the filter selects `event`
+    pub fn matches(&self, event: &IndexerEvent) -> bool {
+        (self.protocols.is_empty() || self.protocols.contains(event.protocol_id()))
+            && (self.types.is_empty() || self.types.iter().any(|t| t == event.event_type()))
+    }
+}
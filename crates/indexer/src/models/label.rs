@@ -0,0 +1,42 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+pub use governance::label::{
+    CriterionStatus, GroundTruth, LabelCriteria, LabelCriterion, LabelValue,
+};
+
+use crate::utils::id::ProposalId;
+
+/// Human label of a proposal
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProposalLabel {
+    /// Canonical ID of the labeled proposal
+    pub proposal_id: ProposalId,
+    /// Name of the labeler
+    pub labeler: String,
+    /// Verdict of the labeler
+    pub label: LabelValue,
+    /// Results on the evaluation categories
+    pub criteria: LabelCriteria,
+    /// Reasons given for the verdict
+    pub rationale: Option<String>,
+    /// Time the label was given or last revised
+    pub created_at: DateTime<Utc>,
+}
+
+/// Final verdict on a proposal whose labelers disagree
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LabelResolution {
+    /// Canonical ID of the proposal
+    pub proposal_id: ProposalId,
+    /// Final verdict
+    pub label: LabelValue,
+    /// Final results on the evaluation categories
+    pub criteria: LabelCriteria,
+    /// Name of the person resolving the conflict
+    pub resolved_by: String,
+    /// Reasons for the final verdict
+    pub rationale: Option<String>,
+    /// Time of the resolution
+    pub resolved_at: DateTime<Utc>,
+}
@@ -0,0 +1,17 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Lease giving one indexer replica the right to crawl a data source
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceLease {
+    /// Leased data source, as `<source>:<space or organization>`
+    pub key: String,
+    /// ID of the replica holding the lease
+    pub holder: String,
+    /// Time the holder took the lease
+    pub acquired_at: DateTime<Utc>,
+    /// Time the holder last renewed the lease
+    pub renewed_at: DateTime<Utc>,
+    /// Time the lease expires unless renewed
+    pub expires_at: DateTime<Utc>,
+}
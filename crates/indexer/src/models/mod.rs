@@ -1,15 +1,62 @@
 //! Data models for the indexer service
 //!
 //! This module contains the core data structures used by the indexer service
-//! for representing proposals, actors, and protocols.
+//! for representing proposals, actors, and protocols. Types that are part of
+//! the contract with the agent and webhook receivers are defined in the
+//! [`governance`] crate and re-exported here.
 
 /// Actor/entity data model  
 pub mod actor;
+/// API key model
+pub mod api_key;
+/// Backfill job model
+pub mod backfill;
+/// Change log model
+pub mod change;
+/// Training dataset model
+pub mod dataset;
+/// Indexer event model
+pub mod event;
+/// Human proposal label model
+pub mod label;
+/// Data source lease model
+pub mod lease;
+/// Token price model
+pub mod price;
 /// Proposal data model
 pub mod proposal;
 /// Protocol/network data model
 pub mod protocol;
+/// Quarantined record data model
+pub mod quarantine;
+/// Raw data source record model
+pub mod record;
+/// Proposal revision model
+pub mod revision;
+/// Proposal status history model
+pub mod status;
+/// Decoded proposal transfer model
+pub mod transfer;
+/// Treasury balance model
+pub mod treasury;
+/// Webhook subscription model
+pub mod webhook;
 
 pub use actor::Actor;
+pub use api_key::{ApiKey, ApiScope};
+pub use backfill::{BackfillJob, BackfillSource, BackfillStatus};
+pub use change::{Change, ChangeOperation, EntityType};
+pub use dataset::{DatasetManifest, DatasetRecord};
+pub use event::{EventEnvelope, EventFilter, IndexerEvent};
+pub use label::{LabelValue, ProposalLabel};
+pub use lease::SourceLease;
+pub use price::{DailyPrice, PricedToken, TokenValue};
 pub use proposal::Proposal;
 pub use protocol::ProtocolId;
+pub use quarantine::{QuarantineStatus, QuarantinedRecord};
+pub use record::RawRecord;
+pub use revision::ProposalRevision;
+pub use status::{StatusChange, StatusTrigger};
+pub use transfer::ProposalTransfer;
+pub use treasury::{Treasury, TreasuryBalance, TreasuryTotal};
+pub use webhook::{NewWebhook, Webhook, WebhookDelivery, WebhookUpdate};
@@ -0,0 +1,17 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+pub use governance::price::{PricedToken, TokenValue};
+
+/// USD price of an asset on one day
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyPrice {
+    /// ID of the asset at the price provider, the `price_id` of registry tokens
+    pub asset: String,
+    /// Day of the price, in UTC
+    pub day: NaiveDate,
+    /// USD price of one token, the last one of the day
+    pub price_usd: f64,
+    /// Name of the provider the price came from
+    pub source: String,
+}
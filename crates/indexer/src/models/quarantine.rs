@@ -0,0 +1,66 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::validation::ValidationError;
+
+/// A raw record that failed the ingestion pipeline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuarantinedRecord {
+    /// Quarantine entry ID
+    pub id: i64,
+    /// Data source the record came from (e.g. `snapshot`)
+    pub source: String,
+    /// Canonical ID of the protocol the record was crawled for
+    pub protocol_id: String,
+    /// ID of the record within its source, if it could be extracted
+    pub native_id: Option<String>,
+    /// Raw payload as returned by the source
+    pub payload: serde_json::Value,
+    /// Validation failures of the last ingestion attempt
+    pub errors: Vec<ValidationError>,
+    /// Review status
+    pub status: QuarantineStatus,
+    /// Number of ingestion attempts
+    pub attempts: i32,
+    /// Canonical ID of the stored proposal once resolved
+    pub proposal_id: Option<String>,
+    /// Time the record was first quarantined
+    pub created_at: DateTime<Utc>,
+    /// Time of the last attempt or fix
+    pub updated_at: DateTime<Utc>,
+    /// Time the record was successfully re-ingested
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+/// Review status of a quarantined record
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuarantineStatus {
+    /// Waiting for a fix
+    #[default]
+    Pending,
+    /// Re-ingested successfully
+    Resolved,
+}
+
+impl QuarantineStatus {
+    /// Database representation of the status
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Resolved => "resolved",
+        }
+    }
+}
+
+impl std::str::FromStr for QuarantineStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(Self::Pending),
+            "resolved" => Ok(Self::Resolved),
+            other => Err(format!("Unknown quarantine status: {other}")),
+        }
+    }
+}
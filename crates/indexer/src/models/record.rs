@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+use super::ProtocolId;
+
+/// A record as returned by a data source, before normalization
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RawRecord {
+    /// Data source the record came from (e.g. `snapshot`)
+    pub source: String,
+    /// Protocol the record was crawled for
+    pub protocol_id: ProtocolId,
+    /// ID of the record within its source, if present in the payload
+    pub native_id: Option<String>,
+    /// Payload as returned by the source
+    pub payload: serde_json::Value,
+}
+
+impl RawRecord {
+    /// Create a raw record, extracting the native ID from the payload
+    ///
+    /// Tally proposals are identified by their on-chain ID (`onchainId`),
+    /// other sources by `id`.
+    pub fn new(source: &str, protocol_id: ProtocolId, payload: serde_json::Value) -> Self {
+        let id_field = if source == "tally" { "onchainId" } else { "id" };
+        let native_id = match payload.get(id_field) {
+            Some(serde_json::Value::String(id)) => Some(id.clone()),
+            Some(serde_json::Value::Number(id)) => Some(id.to_string()),
+            _ => None,
+        };
+
+        Self {
+            source: source.to_string(),
+            protocol_id,
+            native_id,
+            payload,
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+pub use governance::proposal::ProposalField;
+
+use super::Proposal;
+use crate::utils::id::ProposalId;
+
+/// A distinct version of a proposal's content
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProposalRevision {
+    /// Proposal the revision belongs to
+    pub proposal_id: ProposalId,
+    /// Revision number, starting at 1
+    pub revision: i32,
+    /// Title at this revision
+    pub title: String,
+    /// Description at this revision
+    pub description: String,
+    /// Choices at this revision
+    pub choices: Vec<String>,
+    /// Hex encoded SHA-256 of the content, see [`content_hash`]
+    pub content_hash: String,
+    /// Last update time reported by the source
+    pub edited_at: Option<DateTime<Utc>>,
+    /// Time the indexer recorded the revision
+    pub created_at: DateTime<Utc>,
+}
+
+impl ProposalRevision {
+    /// Fields whose content differs from another revision
+    pub fn changed_fields(&self, other: &Self) -> Vec<ProposalField> {
+        changed_fields(
+            (&self.title, &self.description, &self.choices),
+            (&other.title, &other.description, &other.choices),
+        )
+    }
+}
+
+type Content<'a> = (&'a String, &'a String, &'a Vec<String>);
+
+pub(crate) fn changed_fields(a: Content<'_>, b: Content<'_>) -> Vec<ProposalField> {
+    let mut changed = Vec::new();
+    if a.0 != b.0 {
+        changed.push(ProposalField::Title);
+    }
+    if a.1 != b.1 {
+        changed.push(ProposalField::Description);
+    }
+    if a.2 != b.2 {
+        changed.push(ProposalField::Choices);
+    }
+    changed
+}
+
+/// SHA-256 of a proposal's revisioned content (title, description and choices)
+///
+/// Fields are length-prefixed so that moving text between fields changes
+/// the hash.
+pub fn content_hash(proposal: &Proposal) -> String {
+    let mut hasher = Sha256::new();
+    let fields = [&proposal.title, &proposal.description]
+        .into_iter()
+        .chain(proposal.choices.iter());
+
+    hasher.update((proposal.choices.len() as u64).to_be_bytes());
+    for field in fields {
+        hasher.update((field.len() as u64).to_be_bytes());
+        hasher.update(field.as_bytes());
+    }
+
+    hex::encode(hasher.finalize())
+}
+
+/// Line-level diff of one field between two revisions
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldDiff {
+    /// Field being compared
+    pub field: ProposalField,
+    /// Unified diff of the field
+    pub unified: String,
+    /// Number of inserted lines
+    pub insertions: usize,
+    /// Number of deleted lines
+    pub deletions: usize,
+}
+
+/// Differences between two revisions of a proposal
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevisionDiff {
+    /// Proposal both revisions belong to
+    pub proposal_id: ProposalId,
+    /// Older revision number
+    pub from: i32,
+    /// Newer revision number
+    pub to: i32,
+    /// Per-field diffs, only for fields that changed
+    pub fields: Vec<FieldDiff>,
+}
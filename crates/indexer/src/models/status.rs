@@ -0,0 +1 @@
+pub use governance::status::{StatusChange, StatusTrigger};
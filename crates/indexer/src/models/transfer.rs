@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+
+use super::TokenValue;
+
+/// ERC-20 transfer executed by a proposal, decoded from its call data
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProposalTransfer {
+    /// Position of the call among the proposal's executable calls
+    pub call_index: u32,
+    /// Contract address of the token, in lowercase
+    pub token: String,
+    /// Recipient address, in lowercase
+    pub recipient: String,
+    /// Amount in the token's base unit, as a decimal string
+    pub raw_amount: String,
+    /// Value of the amount when the proposal was created
+    ///
+    /// Unset for tokens missing from the registry or without a recent price.
+    pub value: Option<TokenValue>,
+}
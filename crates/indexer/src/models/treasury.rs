@@ -0,0 +1 @@
+pub use governance::treasury::{Treasury, TreasuryBalance, TreasuryTotal};
@@ -0,0 +1,102 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{event::IndexerEvent, proposal::ProposalStatus, ProtocolId};
+
+pub use governance::webhook::PING_EVENT;
+
+/// Webhook subscription, without its secrets
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Webhook {
+    /// Webhook ID
+    pub id: String,
+    /// URL events are posted to
+    pub url: String,
+    /// Event types delivered, all if empty
+    pub events: Vec<String>,
+    /// Protocols whose events are delivered, all if empty
+    pub protocols: Vec<ProtocolId>,
+    /// Proposal statuses whose events are delivered, all if empty
+    pub statuses: Vec<ProposalStatus>,
+    /// Whether events are delivered
+    pub enabled: bool,
+    /// Why the webhook was disabled automatically
+    pub disabled_reason: Option<String>,
+    /// Failed deliveries since the last successful one
+    pub consecutive_failures: i32,
+    /// Time the previous secret stops signing deliveries, after a rotation
+    pub previous_secret_expires_at: Option<DateTime<Utc>>,
+    /// Time of the last delivery
+    pub last_delivery_at: Option<DateTime<Utc>>,
+    /// Time the webhook was created
+    pub created_at: DateTime<Utc>,
+    /// Time the webhook was last changed
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Webhook {
+    /// Whether the webhook subscribes to `event` of a proposal with `status`
+    pub fn matches(&self, event: &IndexerEvent, status: Option<ProposalStatus>) -> bool {
+        (self.events.is_empty() || self.events.iter().any(|t| t == event.event_type()))
+            && (self.protocols.is_empty() || self.protocols.contains(event.protocol_id()))
+            && (self.statuses.is_empty() || status.is_some_and(|s| self.statuses.contains(&s)))
+    }
+}
+
+/// Logged delivery of an event to a webhook
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    /// Delivery ID
+    pub id: i64,
+    /// Webhook the event was posted to
+    pub webhook_id: String,
+    /// Position of the event in the event log, absent for pings
+    pub event_id: Option<i64>,
+    /// Type of the event
+    pub event_type: String,
+    /// Requests sent, including retries
+    pub attempts: i32,
+    /// Status of the last response
+    pub status_code: Option<i32>,
+    /// Error of the last attempt
+    pub error: Option<String>,
+    /// Whether the webhook accepted the event
+    pub success: bool,
+    /// Time spent delivering, including retries, in milliseconds
+    pub duration_ms: i64,
+    /// Time the delivery finished
+    pub delivered_at: DateTime<Utc>,
+}
+
+/// Webhook to create
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct NewWebhook {
+    /// URL events are posted to
+    pub url: String,
+    /// Event types to deliver, all if empty
+    #[serde(default)]
+    pub events: Vec<String>,
+    /// Protocols whose events are delivered, all if empty
+    #[serde(default)]
+    pub protocols: Vec<ProtocolId>,
+    /// Proposal statuses whose events are delivered, all if empty
+    #[serde(default)]
+    pub statuses: Vec<ProposalStatus>,
+    /// Secret signing deliveries, generated if unset
+    pub secret: Option<String>,
+}
+
+/// Changes to a webhook, unset fields are kept
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct WebhookUpdate {
+    /// URL events are posted to
+    pub url: Option<String>,
+    /// Event types to deliver, all if empty
+    pub events: Option<Vec<String>>,
+    /// Protocols whose events are delivered, all if empty
+    pub protocols: Option<Vec<ProtocolId>>,
+    /// Proposal statuses whose events are delivered, all if empty
+    pub statuses: Option<Vec<ProposalStatus>>,
+    /// Whether events are delivered; enabling resets the failure count
+    pub enabled: Option<bool>,
+}
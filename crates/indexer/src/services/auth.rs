@@ -0,0 +1,327 @@
+//! API key authentication, scopes and request signing
+//!
+//! Clients authenticate with an API key of the form `wei_<prefix>_<secret>`,
+//! sent in the `x-api-key` header or as a bearer token. Only the SHA-256 hash
+//! of the secret is stored, so keys are shown once when created. Services
+//! such as the agent can instead sign requests with HMAC-SHA256 using a
+//! key's signing secret, which keeps the secret off the wire:
+//!
+//! - `x-wei-key-id`: the key prefix
+//! - `x-wei-timestamp`: Unix time of the request, at most five minutes off
+//! - `x-wei-signature`: hex HMAC of [`string_to_sign`](crate::services::auth::string_to_sign)
+//!
+//! Every route requires an [`ApiScope`](crate::models::ApiScope), `admin`
+//! granting all of them. Read endpoints can be opened to anonymous clients by
+//! configuration, and keys can be limited to a number of requests per minute
+//! on each replica.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::http::Method;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use tracing::warn;
+
+use crate::{
+    config::AuthConfig,
+    db::{
+        repositories::{api_key::NewApiKey, ApiKeyRepository},
+        Database,
+    },
+    models::{ApiKey, ApiScope},
+    services::http::TokenBucket,
+};
+
+/// Header carrying an API key
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Header carrying the prefix of the key that signed a request
+pub const KEY_ID_HEADER: &str = "x-wei-key-id";
+
+/// Header carrying the Unix time a request was signed at
+pub const TIMESTAMP_HEADER: &str = "x-wei-timestamp";
+
+/// Header carrying the HMAC signature of a request
+pub const SIGNATURE_HEADER: &str = "x-wei-signature";
+
+/// Largest difference between a signature's timestamp and the server clock
+pub const MAX_SIGNATURE_AGE_SECS: u64 = 300;
+
+/// Errors of request authentication
+#[derive(Debug, Error)]
+pub enum AuthError {
+    /// No API key or signature was sent to a protected endpoint
+    #[error("API key is required")]
+    MissingCredentials,
+    /// The API key or signature is unknown, revoked or wrong
+    #[error("Invalid API key or signature")]
+    InvalidCredentials,
+    /// The signature timestamp is too far from the server clock
+    #[error("Request signature has expired")]
+    ExpiredSignature,
+    /// The key lacks the scope of the endpoint
+    #[error("API key lacks the {} scope", .0.as_str())]
+    Forbidden(ApiScope),
+    /// The key exceeded its rate limit
+    #[error("Rate limit exceeded, retry in {0:?}")]
+    RateLimited(Duration),
+    /// Keys could not be loaded
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+}
+
+/// Credentials sent with a request
+#[derive(Debug, Clone, Copy)]
+pub enum Credentials<'a> {
+    /// No credentials
+    Anonymous,
+    /// An API key
+    Key(&'a str),
+    /// An HMAC signature of the request
+    Signed {
+        /// Prefix of the signing key
+        key_id: &'a str,
+        /// Unix time the request was signed at
+        timestamp: i64,
+        /// Hex-encoded signature
+        signature: &'a str,
+        /// String the signature covers, from [`string_to_sign`]
+        signed: &'a str,
+    },
+}
+
+/// API key created for a client, with its secrets
+#[derive(Debug, Clone, Serialize)]
+pub struct CreatedApiKey {
+    /// The stored key
+    #[serde(flatten)]
+    pub key: ApiKey,
+    /// Full API key, shown only once
+    pub secret: String,
+    /// Secret of HMAC request signatures, shown only once
+    pub signing_secret: Option<String>,
+}
+
+/// Settings of a new API key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeySettings {
+    /// Name of the client
+    pub name: String,
+    /// Permissions of the key
+    pub scopes: Vec<ApiScope>,
+    /// Requests per minute allowed, unlimited if unset
+    pub rate_limit: Option<i32>,
+    /// Whether the key gets a secret to sign requests with
+    pub signing: bool,
+}
+
+/// Scope a request needs, `None` for public endpoints
+///
+/// The health, readiness and metrics endpoints and CORS preflight requests
+/// are public. Webhook routes
+/// need `webhooks:write` and key management `admin`. Other reads, including
+/// GraphQL queries, need `read` and other writes `admin`.
+pub fn required_scope(method: &Method, path: &str) -> Option<ApiScope> {
+    let under = |prefix: &str| {
+        path.strip_prefix(prefix)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    };
+
+    if matches!(path, "/health" | "/ready" | "/metrics") || *method == Method::OPTIONS {
+        None
+    } else if under("/api-keys") {
+        Some(ApiScope::Admin)
+    } else if under("/hooks") {
+        Some(ApiScope::WebhooksWrite)
+    } else if matches!(*method, Method::GET | Method::HEAD) || path == "/graphql" {
+        Some(ApiScope::Read)
+    } else {
+        Some(ApiScope::Admin)
+    }
+}
+
+/// Random hex string of `bytes` bytes
+pub(crate) fn random_hex(bytes: usize) -> String {
+    let mut buffer = vec![0u8; bytes];
+    rand::thread_rng().fill_bytes(&mut buffer);
+    hex::encode(buffer)
+}
+
+/// Generate a key, returning its prefix, secret part and full form
+pub fn generate_key() -> (String, String, String) {
+    let prefix = random_hex(4);
+    let secret = random_hex(24);
+    let key = format!("wei_{prefix}_{secret}");
+    (prefix, secret, key)
+}
+
+/// Split a full key into its prefix and secret part
+pub fn split_key(key: &str) -> Option<(&str, &str)> {
+    let (prefix, secret) = key.strip_prefix("wei_")?.split_once('_')?;
+    (!prefix.is_empty() && !secret.is_empty()).then_some((prefix, secret))
+}
+
+/// SHA-256 hash of a key's secret part, hex encoded
+pub fn hash_secret(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
+
+/// String covered by a request signature
+///
+/// The timestamp, method, path with query and SHA-256 hash of the body, one
+/// per line.
+pub fn string_to_sign(timestamp: i64, method: &str, path_and_query: &str, body: &[u8]) -> String {
+    format!(
+        "{timestamp}\n{method}\n{path_and_query}\n{}",
+        hex::encode(Sha256::digest(body))
+    )
+}
+
+/// Hex-encoded HMAC-SHA256 signature of a string
+pub fn sign(signing_secret: &str, signed: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(signing_secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(signed.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Whether a hex signature is the HMAC of a string, in constant time
+pub fn verify_signature(signing_secret: &str, signed: &str, signature: &str) -> bool {
+    let Ok(signature) = hex::decode(signature) else {
+        return false;
+    };
+    let mut mac = Hmac::<Sha256>::new_from_slice(signing_secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(signed.as_bytes());
+    mac.verify_slice(&signature).is_ok()
+}
+
+/// Compare two byte strings in constant time
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Authenticator of API requests
+#[derive(Clone)]
+pub struct Authenticator {
+    db: Database,
+    config: AuthConfig,
+    limits: Arc<Mutex<HashMap<i64, TokenBucket>>>,
+}
+
+impl Authenticator {
+    /// Create an authenticator checking keys stored in `db`
+    pub fn new(db: Database, config: AuthConfig) -> Self {
+        Self {
+            db,
+            config,
+            limits: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Create and store a key, returning it with its secrets
+    pub async fn create_key(&self, settings: &KeySettings) -> Result<CreatedApiKey, sqlx::Error> {
+        let (prefix, secret, full) = generate_key();
+        let signing_secret = settings.signing.then(|| random_hex(32));
+        let key = ApiKeyRepository::new(self.db.clone())
+            .create(&NewApiKey {
+                name: &settings.name,
+                prefix: &prefix,
+                key_hash: &hash_secret(&secret),
+                scopes: &settings.scopes,
+                rate_limit: settings.rate_limit,
+                signing_secret: signing_secret.as_deref(),
+            })
+            .await?;
+
+        Ok(CreatedApiKey {
+            key,
+            secret: full,
+            signing_secret,
+        })
+    }
+
+    /// Check the credentials of a request needing `scope`
+    ///
+    /// Returns the authenticated key, or `None` when authentication is
+    /// disabled or the request is an allowed anonymous read.
+    pub async fn authenticate(
+        &self,
+        credentials: Credentials<'_>,
+        scope: ApiScope,
+    ) -> Result<Option<ApiKey>, AuthError> {
+        if !self.config.enabled {
+            return Ok(None);
+        }
+
+        let repository = ApiKeyRepository::new(self.db.clone());
+        let key = match credentials {
+            Credentials::Anonymous if scope == ApiScope::Read && self.config.anonymous_read => {
+                return Ok(None);
+            }
+            Credentials::Anonymous => return Err(AuthError::MissingCredentials),
+            Credentials::Key(full) => {
+                let (prefix, secret) = split_key(full).ok_or(AuthError::InvalidCredentials)?;
+                let stored = repository
+                    .find_by_prefix(prefix)
+                    .await?
+                    .ok_or(AuthError::InvalidCredentials)?;
+                if !constant_time_eq(hash_secret(secret).as_bytes(), stored.key_hash.as_bytes()) {
+                    return Err(AuthError::InvalidCredentials);
+                }
+                stored.key
+            }
+            Credentials::Signed {
+                key_id,
+                timestamp,
+                signature,
+                signed,
+            } => {
+                if Utc::now().timestamp().abs_diff(timestamp) > MAX_SIGNATURE_AGE_SECS {
+                    return Err(AuthError::ExpiredSignature);
+                }
+                let stored = repository
+                    .find_by_prefix(key_id)
+                    .await?
+                    .ok_or(AuthError::InvalidCredentials)?;
+                let secret = stored
+                    .signing_secret
+                    .as_deref()
+                    .ok_or(AuthError::InvalidCredentials)?;
+                if !verify_signature(secret, signed, signature) {
+                    return Err(AuthError::InvalidCredentials);
+                }
+                stored.key
+            }
+        };
+
+        if key.revoked_at.is_some() {
+            return Err(AuthError::InvalidCredentials);
+        }
+        if !key.allows(scope) {
+            return Err(AuthError::Forbidden(scope));
+        }
+        if let Some(limit) = key.rate_limit {
+            let mut limits = self.limits.lock().unwrap_or_else(|e| e.into_inner());
+            let now = Instant::now();
+            limits
+                .entry(key.id)
+                .or_insert_with(|| TokenBucket::new(limit.max(1) as u32, now))
+                .try_acquire(now)
+                .map_err(AuthError::RateLimited)?;
+        }
+
+        if let Err(e) = repository.touch(key.id).await {
+            warn!("Failed to record use of API key {}: {}", key.prefix, e);
+        }
+        Ok(Some(key))
+    }
+}
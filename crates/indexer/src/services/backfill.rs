@@ -0,0 +1,258 @@
+//! Backfill and reindex of proposals from their data sources
+//!
+//! A backfill crawls a Snapshot space or Tally organization page by page,
+//! oldest proposals first, and runs every record through the ingestion
+//! pipeline. Progress is kept in a
+//! [`BackfillJob`](crate::models::BackfillJob): each page is crawled while
+//! holding the job's row lock and its cursor is advanced in the same
+//! transaction, so an interrupted backfill resumes at the page it stopped
+//! on and concurrent runs of the same job never crawl a page twice.
+//! Proposals themselves are locked while they are saved, which lets
+//! backfills run next to the live server.
+
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context};
+use chrono::{DateTime, Utc};
+use tracing::{error, info};
+
+use crate::{
+    config::{DataSourceConfig, ProtocolEntry, ProtocolRegistry},
+    db::{
+        repositories::{backfill::PageProgress, BackfillRepository},
+        Database,
+    },
+    models::{BackfillJob, BackfillSource, BackfillStatus},
+    services::{
+        data_sources::{DataSource, SnapshotDataSource, TallyDataSource},
+        http::HttpClient,
+        EventBus, IngestOutcome, IngestPipeline, LifecycleScheduler,
+    },
+    utils::id::ProposalId,
+};
+
+/// Target and start of a backfill
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackfillRequest {
+    /// Data source to crawl
+    pub source: BackfillSource,
+    /// Snapshot space or Tally organization slug
+    pub target: String,
+    /// Only proposals created at or after this time are crawled
+    pub since: DateTime<Utc>,
+    /// Start over instead of resuming an existing job
+    pub restart: bool,
+}
+
+/// Runner of backfills and reindexes
+#[derive(Clone)]
+pub struct Backfiller {
+    db: Database,
+    registry: Arc<ProtocolRegistry>,
+    sources: DataSourceConfig,
+    http: HttpClient,
+    pipeline: IngestPipeline,
+}
+
+impl Backfiller {
+    /// Create a backfiller for the protocols of `registry`
+    ///
+    /// Stored proposals are recorded in the change feed; no live events are
+    /// published and voting windows are left to the server's lifecycle engine.
+    pub fn new(db: Database, registry: Arc<ProtocolRegistry>, sources: DataSourceConfig) -> Self {
+        Self {
+            pipeline: IngestPipeline::new(
+                db.clone(),
+                EventBus::default(),
+                LifecycleScheduler::default(),
+            ),
+            db,
+            registry,
+            http: HttpClient::new(&sources),
+            sources,
+        }
+    }
+
+    /// Client the data sources send their requests through
+    pub fn http(&self) -> &HttpClient {
+        &self.http
+    }
+
+    fn snapshot(&self, entry: &ProtocolEntry, space: &str) -> Box<dyn DataSource + Send + Sync> {
+        Box::new(SnapshotDataSource::new(
+            self.http.clone(),
+            self.sources.snapshot.base_url.clone(),
+            self.sources.snapshot.api_key.clone(),
+            entry.protocol_id(),
+            space.to_string(),
+        ))
+    }
+
+    fn tally(
+        &self,
+        entry: &ProtocolEntry,
+        organization: &str,
+    ) -> Box<dyn DataSource + Send + Sync> {
+        Box::new(TallyDataSource::new(
+            self.http.clone(),
+            self.sources.tally.base_url.clone(),
+            self.sources.tally.api_key.clone(),
+            entry.protocol_id(),
+            organization.to_string(),
+        ))
+    }
+
+    /// Data source crawling a target of the registry
+    fn data_source(
+        &self,
+        source: BackfillSource,
+        target: &str,
+    ) -> anyhow::Result<Box<dyn DataSource + Send + Sync>> {
+        match source {
+            BackfillSource::Snapshot => self
+                .registry
+                .find_by_snapshot_space(target)
+                .map(|entry| self.snapshot(entry, target))
+                .ok_or_else(|| {
+                    anyhow!("Snapshot space '{target}' is not in the protocol registry")
+                }),
+            BackfillSource::Tally => self
+                .registry
+                .find_by_tally_organization(target)
+                .map(|entry| self.tally(entry, target))
+                .ok_or_else(|| {
+                    anyhow!("Tally organization '{target}' is not in the protocol registry")
+                }),
+        }
+    }
+
+    /// Crawl a target until its last page, resuming an earlier job
+    ///
+    /// A failing page marks the job as failed and stops the backfill; running
+    /// it again retries that page.
+    pub async fn backfill(&self, request: &BackfillRequest) -> anyhow::Result<BackfillJob> {
+        let source = self.data_source(request.source, &request.target)?;
+        let jobs = BackfillRepository::new(self.db.clone());
+        let mut job = jobs
+            .start(
+                request.source,
+                &request.target,
+                &source.protocol_id().to_string(),
+                request.since,
+                request.restart,
+            )
+            .await?;
+        if job.status == BackfillStatus::Completed {
+            info!(
+                "Backfill {} of {} {} is already completed",
+                job.id,
+                job.source.as_str(),
+                job.target
+            );
+            return Ok(job);
+        }
+        info!(
+            "Backfilling {} {} since {} (job {}, {} pages done)",
+            job.source.as_str(),
+            job.target,
+            job.since,
+            job.id,
+            job.pages
+        );
+
+        while job.status != BackfillStatus::Completed {
+            job = match self.crawl_page(source.as_ref(), &jobs, job.id).await {
+                Ok(job) => job,
+                Err(e) => {
+                    let message = format!("{e:#}");
+                    error!("Backfill {} failed: {}", job.id, message);
+                    jobs.fail(job.id, &message).await?;
+                    return Err(e);
+                }
+            };
+            info!(
+                "Backfill {} of {} {}: {} pages, {} records fetched, {} stored, {} quarantined",
+                job.id,
+                job.source.as_str(),
+                job.target,
+                job.pages,
+                job.fetched,
+                job.stored,
+                job.quarantined
+            );
+        }
+
+        Ok(job)
+    }
+
+    /// Crawl the next page of a job while holding its lock
+    async fn crawl_page(
+        &self,
+        source: &(dyn DataSource + Send + Sync),
+        jobs: &BackfillRepository,
+        id: i64,
+    ) -> anyhow::Result<BackfillJob> {
+        let mut tx = self.db.begin().await?;
+        let job = jobs.lock(&mut tx, id).await?;
+        // Another run may have crawled the last page while this one waited
+        if job.status == BackfillStatus::Completed {
+            return Ok(job);
+        }
+
+        let page = source.fetch_page(job.since, job.cursor.as_deref()).await?;
+        let mut progress = PageProgress {
+            fetched: page.records.len() as i64,
+            ..Default::default()
+        };
+        for record in &page.records {
+            match self.pipeline.ingest(record, &self.registry).await? {
+                IngestOutcome::Stored(_) => progress.stored += 1,
+                IngestOutcome::Quarantined { .. } => progress.quarantined += 1,
+                // Hidden proposals only count as fetched
+                IngestOutcome::Removed(_) => {}
+            }
+        }
+
+        let job = jobs
+            .advance(&mut tx, id, page.next.as_deref(), progress)
+            .await?;
+        tx.commit().await?;
+        Ok(job)
+    }
+
+    /// Fetch a proposal from its data source again and run it through the pipeline
+    ///
+    /// A proposal the source no longer has is removed.
+    pub async fn reindex(&self, id: &ProposalId) -> anyhow::Result<IngestOutcome> {
+        let protocol = &id.protocol_id().protocol;
+        let entry = self
+            .registry
+            .get(protocol)
+            .ok_or_else(|| anyhow!("Protocol '{protocol}' is not in the protocol registry"))?;
+
+        let source = match id.source() {
+            "snapshot" => entry
+                .snapshot_spaces
+                .first()
+                .map(|space| self.snapshot(entry, space)),
+            "tally" => entry
+                .tally_organization
+                .as_deref()
+                .map(|organization| self.tally(entry, organization)),
+            other => return Err(anyhow!("Unknown data source '{other}'")),
+        }
+        .ok_or_else(|| anyhow!("Protocol '{protocol}' has no {} source", id.source()))?;
+
+        let record = source
+            .fetch_proposal(id.native_id())
+            .await
+            .with_context(|| format!("Failed to fetch proposal {id}"))?;
+        let Some(record) = record else {
+            // The source deleted the proposal
+            self.pipeline.remove(id).await?;
+            return Ok(IngestOutcome::Removed(id.clone()));
+        };
+
+        Ok(self.pipeline.ingest(&record, &self.registry).await?)
+    }
+}
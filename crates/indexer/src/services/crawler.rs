@@ -0,0 +1,136 @@
+//! Background crawling of the registry's data sources
+//!
+//! The crawler wakes up at the lease renewal interval of the
+//! [`LeaderElection`](crate::services::leader::LeaderElection). Each round it
+//! takes or renews the lease of every enabled data source, then crawls the
+//! leased sources whose protocol polling interval has passed, renewing the
+//! lease of a source while it is crawled. Sources leased by another replica
+//! are left alone; a lease taken over from a failed replica is crawled right
+//! away, and a crawl whose lease is lost is canceled.
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info};
+
+use crate::{
+    config::DataSourceConfig,
+    services::{
+        data_sources::{data_sources_for, DataSource},
+        http::HttpClient,
+        indexer::index_source,
+        leader::{HeldLeases, LeaderElection},
+        pipeline::IngestPipeline,
+        registry::RegistryService,
+    },
+};
+
+/// Times data sources are due to be crawled again
+#[derive(Debug, Clone, Default)]
+pub struct CrawlSchedule {
+    due: HashMap<String, Instant>,
+}
+
+impl CrawlSchedule {
+    /// Whether a source is due, which it is until first crawled
+    pub fn is_due(&self, key: &str, now: Instant) -> bool {
+        self.due.get(key).is_none_or(|due| *due <= now)
+    }
+
+    /// Record a crawl of a source, due again after `interval`
+    pub fn crawled(&mut self, key: &str, now: Instant, interval: Duration) {
+        self.due.insert(key.to_string(), now + interval);
+    }
+
+    /// Forget a source, so it is due as soon as it is leased again
+    pub fn forget(&mut self, key: &str) {
+        self.due.remove(key);
+    }
+}
+
+/// Background crawler of the data sources leased by this replica
+pub struct Crawler {
+    registry: RegistryService,
+    sources: DataSourceConfig,
+    http: HttpClient,
+    pipeline: IngestPipeline,
+    leases: HeldLeases,
+}
+
+impl Crawler {
+    /// Start crawling in the background
+    ///
+    /// Fetched records go through `pipeline`, which also records the crawl
+    /// metrics.
+    pub fn spawn(
+        registry: RegistryService,
+        sources: DataSourceConfig,
+        http: HttpClient,
+        leader: LeaderElection,
+        pipeline: IngestPipeline,
+    ) -> JoinHandle<()> {
+        let crawler = Self {
+            pipeline,
+            registry,
+            sources,
+            http,
+            leases: HeldLeases::new(leader, "crawls"),
+        };
+        tokio::spawn(crawler.run())
+    }
+
+    /// Run crawl rounds forever
+    async fn run(mut self) {
+        let leader = self.leases.leader();
+        info!("Crawling data sources as replica {}", leader.replica());
+        let mut interval = tokio::time::interval(leader.renew_interval());
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            self.round().await;
+        }
+    }
+
+    /// Renew leases, then crawl the due sources this replica holds
+    async fn round(&mut self) {
+        let registry = self.registry.current();
+        let sources: Vec<(Duration, Box<dyn DataSource + Send + Sync>)> = registry
+            .enabled()
+            .flat_map(|entry| {
+                let interval = Duration::from_secs(registry.polling_interval_secs(entry));
+                data_sources_for(entry, &self.sources, &self.http)
+                    .into_iter()
+                    .map(move |source| (interval, source))
+            })
+            .collect();
+
+        // Sources removed from the registry are handed back
+        let keys: Vec<String> = sources.iter().map(|(_, s)| s.lease_key()).collect();
+        self.leases.renew(&keys).await;
+
+        for ((interval, source), key) in sources.into_iter().zip(keys) {
+            if !self.leases.is_due(&key) {
+                continue;
+            }
+            if !source.is_available().await {
+                debug!("Skipping unavailable source {}", key);
+                continue;
+            }
+
+            let crawl = index_source(&self.pipeline, source.as_ref(), &registry);
+            match self.leases.run(&key, interval, crawl).await {
+                Some(Ok(report)) => debug!(
+                    "Crawled {}: {} proposals stored, {} records quarantined",
+                    key,
+                    report.stored.len(),
+                    report.quarantined
+                ),
+                Some(Err(e)) => error!("Failed to crawl {}: {:#}", key, e),
+                None => {}
+            }
+        }
+    }
+}
@@ -1,8 +1,223 @@
 //! Data source abstractions using Strategy pattern
 
+use anyhow::{anyhow, Context};
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
 
-use crate::models::{Actor, Proposal, ProtocolId};
+use crate::{
+    config::{DataSourceConfig, ProtocolEntry},
+    models::{Actor, ProtocolId, RawRecord},
+    services::http::HttpClient,
+};
+
+/// Number of proposals requested per crawl
+const PAGE_SIZE: u32 = 100;
+
+/// Snapshot proposals with the fields read by the pipeline's normalizer
+const SNAPSHOT_PROPOSALS_QUERY: &str = r#"
+query Proposals($space: String!, $first: Int!) {
+  proposals(
+    first: $first,
+    where: { space: $space },
+    orderBy: "created",
+    orderDirection: desc
+  ) {
+    id title body choices start end snapshot state author created updated
+    type scores scores_total quorum discussion ipfs link flagged
+    space { id verified }
+  }
+}
+"#;
+
+/// Snapshot proposals of a space created at or after a time, oldest first
+const SNAPSHOT_PROPOSALS_SINCE_QUERY: &str = r#"
+query ProposalsSince($space: String!, $first: Int!, $skip: Int!, $since: Int!) {
+  proposals(
+    first: $first,
+    skip: $skip,
+    where: { space: $space, created_gte: $since },
+    orderBy: "created",
+    orderDirection: asc
+  ) {
+    id title body choices start end snapshot state author created updated
+    type scores scores_total quorum discussion ipfs link flagged
+    space { id verified }
+  }
+}
+"#;
+
+/// A single Snapshot proposal
+const SNAPSHOT_PROPOSAL_QUERY: &str = r#"
+query Proposal($id: String!) {
+  proposal(id: $id) {
+    id title body choices start end snapshot state author created updated
+    type scores scores_total quorum discussion ipfs link flagged
+    space { id verified }
+  }
+}
+"#;
+
+/// Tally organization ID for a slug
+const TALLY_ORGANIZATION_QUERY: &str = r#"
+query Organization($slug: String!) {
+  organization(input: { slug: $slug }) { id }
+}
+"#;
+
+/// Tally proposals with the fields read by the pipeline's normalizer
+const TALLY_PROPOSALS_QUERY: &str = r#"
+query Proposals($organizationId: IntID!, $limit: Int!) {
+  proposals(input: {
+    filters: { organizationId: $organizationId },
+    sort: { sortBy: id, isDescending: true },
+    page: { limit: $limit }
+  }) {
+    nodes {
+      ... on Proposal {
+        id onchainId status createdAt quorum
+        metadata { title description discourseURL ipfsHash }
+        proposer { address }
+        start { ... on Block { number timestamp } ... on BlocklessTimestamp { timestamp } }
+        end { ... on Block { number timestamp } ... on BlocklessTimestamp { timestamp } }
+        voteStats { type votesCount }
+        executableCalls { target calldata }
+        organization { slug }
+      }
+    }
+  }
+}
+"#;
+
+/// Tally proposals after a page cursor, oldest first
+const TALLY_PROPOSALS_PAGE_QUERY: &str = r#"
+query ProposalsPage($organizationId: IntID!, $limit: Int!, $afterCursor: String) {
+  proposals(input: {
+    filters: { organizationId: $organizationId },
+    sort: { sortBy: id, isDescending: false },
+    page: { limit: $limit, afterCursor: $afterCursor }
+  }) {
+    nodes {
+      ... on Proposal {
+        id onchainId status createdAt quorum
+        metadata { title description discourseURL ipfsHash }
+        proposer { address }
+        start { ... on Block { number timestamp } ... on BlocklessTimestamp { timestamp } }
+        end { ... on Block { number timestamp } ... on BlocklessTimestamp { timestamp } }
+        voteStats { type votesCount }
+        executableCalls { target calldata }
+        organization { slug }
+      }
+    }
+    pageInfo { lastCursor }
+  }
+}
+"#;
+
+/// Page of raw records returned by a paginated crawl
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RecordPage {
+    /// Records of the page, oldest first
+    pub records: Vec<RawRecord>,
+    /// Cursor of the next page, `None` after the last page
+    pub next: Option<String>,
+}
+
+/// Position in a Snapshot crawl ordered by creation time
+///
+/// Snapshot only filters by creation time, so proposals created in the same
+/// second as the last one seen are skipped by count. Written as
+/// `<created>:<skip>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnapshotCursor {
+    /// Unix time of the last proposal seen
+    pub created: i64,
+    /// Number of proposals seen that were created at `created`
+    pub skip: u32,
+}
+
+impl SnapshotCursor {
+    /// Cursor of the page following one with proposals created at `created`
+    ///
+    /// Returns `None` for a page shorter than `page_size`, the last one.
+    pub fn next(&self, created: &[i64], page_size: usize) -> Option<Self> {
+        if created.len() < page_size {
+            return None;
+        }
+        let last = *created.last()?;
+        let same_second = created.iter().filter(|&&c| c == last).count() as u32;
+
+        Some(Self {
+            created: last,
+            skip: if last == self.created {
+                self.skip + same_second
+            } else {
+                same_second
+            },
+        })
+    }
+}
+
+impl std::fmt::Display for SnapshotCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.created, self.skip)
+    }
+}
+
+impl std::str::FromStr for SnapshotCursor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split_once(':')
+            .and_then(|(created, skip)| {
+                Some(Self {
+                    created: created.parse().ok()?,
+                    skip: skip.parse().ok()?,
+                })
+            })
+            .ok_or_else(|| format!("Invalid Snapshot cursor: {s}"))
+    }
+}
+
+/// Run a GraphQL query and return its `data` object
+async fn graphql(
+    http: &HttpClient,
+    url: &str,
+    api_key: Option<(&str, &str)>,
+    query: &str,
+    variables: Value,
+) -> anyhow::Result<Value> {
+    let mut request = http
+        .post(url)
+        .json(&json!({ "query": query, "variables": variables }));
+    if let Some((header, key)) = api_key {
+        request = request.header(header, key);
+    }
+
+    let mut response: Value = http
+        .send(request)
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+        .with_context(|| format!("Invalid GraphQL response from {url}"))?;
+
+    if let Some(errors) = response.get("errors").filter(|e| !e.is_null()) {
+        return Err(anyhow!("GraphQL query to {url} failed: {errors}"));
+    }
+    Ok(response["data"].take())
+}
+
+/// Wrap the objects of a JSON array as raw records
+fn raw_records(source: &str, protocol_id: &ProtocolId, items: Value) -> Vec<RawRecord> {
+    match items {
+        Value::Array(items) => items
+            .into_iter()
+            .map(|payload| RawRecord::new(source, protocol_id.clone(), payload))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
 
 /// Trait for data sources (Strategy pattern)
 #[async_trait]
@@ -15,37 +230,78 @@ pub trait DataSource {
     #[allow(dead_code)] // TODO: Remove after development phase
     fn protocol_id(&self) -> ProtocolId;
 
-    /// Fetch proposals from this data source
+    /// Get the Snapshot space or Tally organization this source crawls
+    fn target(&self) -> &str;
+
+    /// Key of the lease replicas take before crawling this source
+    fn lease_key(&self) -> String {
+        format!("{}:{}", self.name(), self.target())
+    }
+
+    /// Fetch raw proposal records from this data source
+    ///
+    /// Records are normalized and validated by the ingestion pipeline, so
+    /// sources return payloads as received.
     #[allow(dead_code)] // TODO: Remove after development phase
-    async fn fetch_proposals(&self) -> anyhow::Result<Vec<Proposal>>;
+    async fn fetch_proposals(&self) -> anyhow::Result<Vec<RawRecord>>;
+
+    /// Fetch a page of raw proposal records created at or after `since`, oldest first
+    ///
+    /// `cursor` is the `next` cursor of the previous page, `None` for the
+    /// first page.
+    async fn fetch_page(
+        &self,
+        since: DateTime<Utc>,
+        cursor: Option<&str>,
+    ) -> anyhow::Result<RecordPage>;
+
+    /// Fetch the raw record of a single proposal by its ID within the source
+    async fn fetch_proposal(&self, native_id: &str) -> anyhow::Result<Option<RawRecord>>;
 
     /// Fetch actors from this data source
     #[allow(dead_code)] // TODO: Remove after development phase
     async fn fetch_actors(&self) -> anyhow::Result<Vec<Actor>>;
 
     /// Check if the data source is available
-    #[allow(dead_code)] // TODO: Remove after development phase
+    ///
+    /// A source is unavailable while the circuit breaker of its API host is
+    /// open after repeated failed requests.
     async fn is_available(&self) -> bool;
 }
 
 /// Snapshot data source implementation
 #[allow(dead_code)] // TODO: Remove after development phase
 pub struct SnapshotDataSource {
+    http: HttpClient,
     base_url: String,
     api_key: Option<String>,
     protocol_id: ProtocolId,
+    space: String,
 }
 
 impl SnapshotDataSource {
-    /// Create a new Snapshot data source
+    /// Create a new Snapshot data source for a space
     #[allow(dead_code)] // TODO: Remove after development phase
-    pub fn new(base_url: String, api_key: Option<String>, protocol_id: ProtocolId) -> Self {
+    pub fn new(
+        http: HttpClient,
+        base_url: String,
+        api_key: Option<String>,
+        protocol_id: ProtocolId,
+        space: String,
+    ) -> Self {
         Self {
+            http,
             base_url,
             api_key,
             protocol_id,
+            space,
         }
     }
+
+    /// Snapshot space ID this source crawls
+    pub fn space(&self) -> &str {
+        &self.space
+    }
 }
 
 #[async_trait]
@@ -60,10 +316,82 @@ impl DataSource for SnapshotDataSource {
         self.protocol_id.clone()
     }
 
+    fn target(&self) -> &str {
+        &self.space
+    }
+
     #[allow(dead_code)] // TODO: Remove after development phase
-    async fn fetch_proposals(&self) -> anyhow::Result<Vec<Proposal>> {
-        // TODO: Implement Snapshot API integration
-        todo!("Implement Snapshot fetch_proposals")
+    async fn fetch_proposals(&self) -> anyhow::Result<Vec<RawRecord>> {
+        let mut data = graphql(
+            &self.http,
+            &format!("{}/graphql", self.base_url),
+            self.api_key.as_deref().map(|key| ("x-api-key", key)),
+            SNAPSHOT_PROPOSALS_QUERY,
+            json!({ "space": self.space, "first": PAGE_SIZE }),
+        )
+        .await?;
+
+        Ok(raw_records(
+            self.name(),
+            &self.protocol_id,
+            data["proposals"].take(),
+        ))
+    }
+
+    async fn fetch_page(
+        &self,
+        since: DateTime<Utc>,
+        cursor: Option<&str>,
+    ) -> anyhow::Result<RecordPage> {
+        let cursor = match cursor {
+            Some(cursor) => cursor.parse::<SnapshotCursor>().map_err(|e| anyhow!(e))?,
+            None => SnapshotCursor {
+                created: since.timestamp(),
+                skip: 0,
+            },
+        };
+
+        let mut data = graphql(
+            &self.http,
+            &format!("{}/graphql", self.base_url),
+            self.api_key.as_deref().map(|key| ("x-api-key", key)),
+            SNAPSHOT_PROPOSALS_SINCE_QUERY,
+            json!({
+                "space": self.space,
+                "first": PAGE_SIZE,
+                "skip": cursor.skip,
+                "since": cursor.created,
+            }),
+        )
+        .await?;
+
+        let records = raw_records(self.name(), &self.protocol_id, data["proposals"].take());
+        let created: Vec<i64> = records
+            .iter()
+            .map(|record| record.payload["created"].as_i64().unwrap_or(cursor.created))
+            .collect();
+
+        Ok(RecordPage {
+            next: cursor
+                .next(&created, PAGE_SIZE as usize)
+                .map(|next| next.to_string()),
+            records,
+        })
+    }
+
+    async fn fetch_proposal(&self, native_id: &str) -> anyhow::Result<Option<RawRecord>> {
+        let mut data = graphql(
+            &self.http,
+            &format!("{}/graphql", self.base_url),
+            self.api_key.as_deref().map(|key| ("x-api-key", key)),
+            SNAPSHOT_PROPOSAL_QUERY,
+            json!({ "id": native_id }),
+        )
+        .await?;
+
+        let payload = data["proposal"].take();
+        Ok((!payload.is_null())
+            .then(|| RawRecord::new(self.name(), self.protocol_id.clone(), payload)))
     }
 
     #[allow(dead_code)] // TODO: Remove after development phase
@@ -72,30 +400,73 @@ impl DataSource for SnapshotDataSource {
         todo!("Implement Snapshot fetch_actors")
     }
 
-    #[allow(dead_code)] // TODO: Remove after development phase
     async fn is_available(&self) -> bool {
-        // TODO: Implement availability check
-        true
+        self.http.is_available(&self.base_url)
     }
 }
 
 /// Tally data source implementation
 #[allow(dead_code)] // TODO: Remove after development phase
 pub struct TallyDataSource {
+    http: HttpClient,
     base_url: String,
     api_key: Option<String>,
     protocol_id: ProtocolId,
+    organization: String,
 }
 
 impl TallyDataSource {
-    /// Create a new Tally data source
+    /// Create a new Tally data source for an organization
     #[allow(dead_code)] // TODO: Remove after development phase
-    pub fn new(base_url: String, api_key: Option<String>, protocol_id: ProtocolId) -> Self {
+    pub fn new(
+        http: HttpClient,
+        base_url: String,
+        api_key: Option<String>,
+        protocol_id: ProtocolId,
+        organization: String,
+    ) -> Self {
         Self {
+            http,
             base_url,
             api_key,
             protocol_id,
+            organization,
+        }
+    }
+
+    /// Tally organization slug this source crawls
+    pub fn organization(&self) -> &str {
+        &self.organization
+    }
+
+    /// Query the Tally API
+    async fn query(&self, query: &str, variables: Value) -> anyhow::Result<Value> {
+        graphql(
+            &self.http,
+            &format!("{}/query", self.base_url),
+            self.api_key.as_deref().map(|key| ("Api-Key", key)),
+            query,
+            variables,
+        )
+        .await
+    }
+
+    /// Tally ID of the organization
+    async fn organization_id(&self) -> anyhow::Result<Value> {
+        let organization = self
+            .query(
+                TALLY_ORGANIZATION_QUERY,
+                json!({ "slug": self.organization }),
+            )
+            .await?;
+        let organization_id = organization["organization"]["id"].clone();
+        if organization_id.is_null() {
+            return Err(anyhow!(
+                "Unknown Tally organization '{}'",
+                self.organization
+            ));
         }
+        Ok(organization_id)
     }
 }
 
@@ -109,9 +480,90 @@ impl DataSource for TallyDataSource {
         self.protocol_id.clone()
     }
 
-    async fn fetch_proposals(&self) -> anyhow::Result<Vec<Proposal>> {
-        // TODO: Implement Tally API integration
-        todo!("Implement Tally fetch_proposals")
+    fn target(&self) -> &str {
+        &self.organization
+    }
+
+    async fn fetch_proposals(&self) -> anyhow::Result<Vec<RawRecord>> {
+        let organization_id = self.organization_id().await?;
+        let mut data = self
+            .query(
+                TALLY_PROPOSALS_QUERY,
+                json!({ "organizationId": organization_id, "limit": PAGE_SIZE }),
+            )
+            .await?;
+
+        Ok(raw_records(
+            self.name(),
+            &self.protocol_id,
+            data["proposals"]["nodes"].take(),
+        ))
+    }
+
+    /// Tally cannot filter by creation time, so older proposals are dropped
+    /// from every page instead
+    async fn fetch_page(
+        &self,
+        since: DateTime<Utc>,
+        cursor: Option<&str>,
+    ) -> anyhow::Result<RecordPage> {
+        let organization_id = self.organization_id().await?;
+        let mut data = self
+            .query(
+                TALLY_PROPOSALS_PAGE_QUERY,
+                json!({
+                    "organizationId": organization_id,
+                    "limit": PAGE_SIZE,
+                    "afterCursor": cursor,
+                }),
+            )
+            .await?;
+
+        let records = raw_records(
+            self.name(),
+            &self.protocol_id,
+            data["proposals"]["nodes"].take(),
+        );
+        let full = records.len() >= PAGE_SIZE as usize;
+        let next = data["proposals"]["pageInfo"]["lastCursor"]
+            .as_str()
+            .filter(|_| full)
+            .map(str::to_string);
+
+        Ok(RecordPage {
+            records: records
+                .into_iter()
+                .filter(|record| {
+                    record.payload["createdAt"]
+                        .as_str()
+                        .and_then(|created| DateTime::parse_from_rfc3339(created).ok())
+                        .is_none_or(|created| created >= since)
+                })
+                .collect(),
+            next,
+        })
+    }
+
+    /// Tally proposals are identified by on-chain ID, which the API cannot
+    /// look up without the Governor, so the organization's pages are searched
+    async fn fetch_proposal(&self, native_id: &str) -> anyhow::Result<Option<RawRecord>> {
+        let mut cursor = None;
+        loop {
+            let page = self
+                .fetch_page(DateTime::UNIX_EPOCH, cursor.as_deref())
+                .await?;
+            if let Some(record) = page
+                .records
+                .into_iter()
+                .find(|record| record.native_id.as_deref() == Some(native_id))
+            {
+                return Ok(Some(record));
+            }
+            match page.next {
+                Some(next) => cursor = Some(next),
+                None => return Ok(None),
+            }
+        }
     }
 
     async fn fetch_actors(&self) -> anyhow::Result<Vec<Actor>> {
@@ -120,7 +572,42 @@ impl DataSource for TallyDataSource {
     }
 
     async fn is_available(&self) -> bool {
-        // TODO: Implement availability check
-        true
+        self.http.is_available(&self.base_url)
+    }
+}
+
+/// Build the data sources for a registry entry
+///
+/// One Snapshot source is created per space and one Tally source for the
+/// organization, if configured. All sources send their requests through
+/// `http`, so they share its rate limits.
+pub fn data_sources_for(
+    entry: &ProtocolEntry,
+    config: &DataSourceConfig,
+    http: &HttpClient,
+) -> Vec<Box<dyn DataSource + Send + Sync>> {
+    let protocol_id = entry.protocol_id();
+    let mut sources: Vec<Box<dyn DataSource + Send + Sync>> = Vec::new();
+
+    for space in &entry.snapshot_spaces {
+        sources.push(Box::new(SnapshotDataSource::new(
+            http.clone(),
+            config.snapshot.base_url.clone(),
+            config.snapshot.api_key.clone(),
+            protocol_id.clone(),
+            space.clone(),
+        )));
     }
+
+    if let Some(organization) = &entry.tally_organization {
+        sources.push(Box::new(TallyDataSource::new(
+            http.clone(),
+            config.tally.base_url.clone(),
+            config.tally.api_key.clone(),
+            protocol_id,
+            organization.clone(),
+        )));
+    }
+
+    sources
 }
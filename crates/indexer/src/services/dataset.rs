@@ -0,0 +1,589 @@
+//! Training dataset export
+//!
+//! Proposals with a closed vote are exported together with their outcome,
+//! scores, author track record, human labels and resolved verdicts. Records are split into
+//! train, validation and test sets per `<protocol>/<outcome>` stratum, by the
+//! SHA-256 of a seed and the proposal ID, so the same seed and data always
+//! give the same splits. Every export is written to its own version
+//! directory with a `manifest.json` listing the files and their checksums;
+//! versions are never overwritten.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use arrow_array::{
+    builder::{Float64Builder, ListBuilder, StringBuilder},
+    ArrayRef, Float64Array, RecordBatch, StringArray, TimestampMillisecondArray, UInt32Array,
+};
+use arrow_schema::{ArrowError, DataType, Field, Schema, TimeUnit};
+use chrono::Utc;
+use parquet::{arrow::ArrowWriter, basic::Compression, file::properties::WriterProperties};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use tracing::info;
+
+use crate::{
+    db::{
+        repositories::{proposal::ProposalQuery, LabelRepository, ProposalRepository},
+        Database,
+    },
+    models::{
+        dataset::{
+            AuthorMetrics, DatasetFile, DatasetFormat, DatasetSplit, Outcome, RecordLabel,
+            SplitRatios, DATASET_SCHEMA_VERSION,
+        },
+        label::{LabelCriteria, LabelResolution},
+        DatasetManifest, DatasetRecord, LabelValue, Proposal, ProposalLabel,
+    },
+    utils::id::ProposalId,
+};
+
+/// Seed of the split assignment when none is given
+pub const DEFAULT_SEED: &str = "wei";
+
+/// Name of the manifest file in a dataset directory
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// Maximum length of a dataset version
+const MAX_VERSION_LENGTH: usize = 64;
+
+/// Dataset export error
+#[derive(Error, Debug)]
+pub enum DatasetError {
+    /// Loading the proposals failed
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+
+    /// Reading or writing a file failed
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// Encoding a record or the manifest failed
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+
+    /// Building the Parquet columns failed
+    #[error("Arrow error: {0}")]
+    Arrow(#[from] ArrowError),
+
+    /// Writing a Parquet file failed
+    #[error("Parquet error: {0}")]
+    Parquet(#[from] parquet::errors::ParquetError),
+
+    /// The version cannot be used as a directory name
+    #[error("Invalid dataset version: {0}")]
+    InvalidVersion(String),
+
+    /// The split ratios cannot be used
+    #[error("{0}")]
+    InvalidRatios(String),
+
+    /// A dataset with the version was already exported
+    #[error("Dataset version already exists: {0}")]
+    AlreadyExists(String),
+}
+
+/// Options of a dataset export
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ExportOptions {
+    /// Version of the dataset, defaults to the UTC time of the export
+    pub version: Option<String>,
+    /// Formats to write, all formats if empty
+    pub formats: Vec<DatasetFormat>,
+    /// Relative split sizes
+    pub ratios: SplitRatios,
+    /// Seed of the split assignment
+    pub seed: String,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            version: None,
+            formats: Vec::new(),
+            ratios: SplitRatios::default(),
+            seed: DEFAULT_SEED.to_string(),
+        }
+    }
+}
+
+/// Whether a version can be used as a dataset directory name
+pub fn is_valid_version(version: &str) -> bool {
+    !version.is_empty()
+        && version.len() <= MAX_VERSION_LENGTH
+        && !version.starts_with('.')
+        && version
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+}
+
+/// Track record of every proposal's author before the proposal was created
+///
+/// Authors are compared case-insensitively across protocols. Proposals
+/// created at the same time do not count towards each other.
+pub fn author_metrics(proposals: &[Proposal]) -> HashMap<ProposalId, AuthorMetrics> {
+    let mut by_author: HashMap<String, Vec<&Proposal>> = HashMap::new();
+    for proposal in proposals {
+        by_author
+            .entry(proposal.author.to_lowercase())
+            .or_default()
+            .push(proposal);
+    }
+
+    let mut metrics = HashMap::new();
+    for mut authored in by_author.into_values() {
+        authored.sort_by_key(|proposal| proposal.created_at);
+
+        let mut prior = AuthorMetrics::default();
+        for group in authored.chunk_by(|a, b| a.created_at == b.created_at) {
+            for proposal in group {
+                metrics.insert(proposal.id.clone(), prior);
+            }
+            for proposal in group {
+                prior.prior_proposals += 1;
+                match Outcome::of(proposal.status) {
+                    Some(Outcome::Passed) => prior.prior_passed += 1,
+                    Some(Outcome::Failed) => prior.prior_failed += 1,
+                    None => {}
+                }
+            }
+        }
+    }
+    metrics
+}
+
+/// Verdict given by most labelers, `None` without labels or on a tie
+pub fn majority_label(labels: &[RecordLabel]) -> Option<LabelValue> {
+    let good = labels
+        .iter()
+        .filter(|label| label.label == LabelValue::Good)
+        .count();
+    let bad = labels.len() - good;
+
+    match good.cmp(&bad) {
+        std::cmp::Ordering::Greater => Some(LabelValue::Good),
+        std::cmp::Ordering::Less => Some(LabelValue::Bad),
+        std::cmp::Ordering::Equal => None,
+    }
+}
+
+/// Evaluation results shared by all labels, `None` without labels or if they differ
+pub fn agreed_criteria(labels: &[RecordLabel]) -> Option<LabelCriteria> {
+    let first = labels.first()?;
+    labels
+        .iter()
+        .all(|label| label.criteria == first.criteria)
+        .then_some(first.criteria)
+}
+
+/// Stratum of a record, `<protocol>/<outcome>`
+pub fn stratum(record: &DatasetRecord) -> String {
+    format!("{}/{}", record.protocol_id, record.outcome.as_str())
+}
+
+/// Build the dataset records of the proposals with a closed vote, by ID
+pub fn build_records(
+    proposals: &[Proposal],
+    labels: &[ProposalLabel],
+    resolutions: &[LabelResolution],
+    ratios: &SplitRatios,
+    seed: &str,
+) -> Vec<DatasetRecord> {
+    let metrics = author_metrics(proposals);
+    let resolutions: HashMap<&ProposalId, &LabelResolution> = resolutions
+        .iter()
+        .map(|resolution| (&resolution.proposal_id, resolution))
+        .collect();
+    let mut labels_by_proposal: HashMap<&ProposalId, Vec<RecordLabel>> = HashMap::new();
+    for label in labels {
+        labels_by_proposal
+            .entry(&label.proposal_id)
+            .or_default()
+            .push(RecordLabel::from(label));
+    }
+
+    let mut records: Vec<DatasetRecord> = proposals
+        .iter()
+        .filter_map(|proposal| {
+            let outcome = Outcome::of(proposal.status)?;
+            let labels = labels_by_proposal.remove(&proposal.id).unwrap_or_default();
+            let resolution = resolutions.get(&proposal.id);
+            Some(DatasetRecord {
+                id: proposal.id.to_string(),
+                protocol_id: proposal.protocol_id.to_string(),
+                source: proposal.id.source().to_string(),
+                title: proposal.title.clone(),
+                description: proposal.description.clone(),
+                choices: proposal.choices.clone(),
+                author: proposal.author.clone(),
+                created_at: proposal.created_at,
+                voting_start: proposal.voting_start,
+                voting_end: proposal.voting_end,
+                voting_type: proposal.voting_type,
+                status: proposal.status.as_str().to_string(),
+                outcome,
+                scores: proposal.scores.clone(),
+                scores_total: proposal.scores_total,
+                quorum: proposal.quorum,
+                is_duplicate_of: proposal.is_duplicate_of.as_ref().map(ToString::to_string),
+                spam_score: proposal.spam_score,
+                author_metrics: metrics.get(&proposal.id).copied().unwrap_or_default(),
+                label: resolution
+                    .map(|r| r.label)
+                    .or_else(|| majority_label(&labels)),
+                criteria: resolution
+                    .map(|r| r.criteria)
+                    .or_else(|| agreed_criteria(&labels)),
+                labels,
+                split: DatasetSplit::Train,
+            })
+        })
+        .collect();
+
+    records.sort_by(|a, b| a.id.cmp(&b.id));
+    assign_splits(&mut records, ratios, seed);
+    records
+}
+
+/// Assign every record to a split, stratified by protocol and outcome
+///
+/// Within a stratum, records are ordered by the SHA-256 of the seed and
+/// their ID and cut according to the ratios, rounding each cut to the
+/// nearest record.
+pub fn assign_splits(records: &mut [DatasetRecord], ratios: &SplitRatios, seed: &str) {
+    let mut strata: BTreeMap<String, Vec<(String, usize)>> = BTreeMap::new();
+    for (index, record) in records.iter().enumerate() {
+        let digest = hex::encode(Sha256::digest(format!("{seed}:{}", record.id)));
+        strata
+            .entry(stratum(record))
+            .or_default()
+            .push((digest, index));
+    }
+
+    // Summed as u64 so unvalidated weights cannot overflow
+    let weight = |weight: u32| u64::from(weight);
+    let total = (weight(ratios.train) + weight(ratios.validation) + weight(ratios.test)).max(1);
+    let cut = |len: usize, weight: u64| ((len as u64 * weight + total / 2) / total) as usize;
+    for mut members in strata.into_values() {
+        members.sort();
+        let len = members.len();
+        let train_end = cut(len, weight(ratios.train));
+        let validation_end = cut(len, weight(ratios.train) + weight(ratios.validation));
+
+        for (position, (_, index)) in members.into_iter().enumerate() {
+            records[index].split = if position < train_end {
+                DatasetSplit::Train
+            } else if position < validation_end {
+                DatasetSplit::Validation
+            } else {
+                DatasetSplit::Test
+            };
+        }
+    }
+}
+
+/// Exports versioned training datasets to a directory
+#[derive(Clone)]
+pub struct DatasetExporter {
+    db: Database,
+    dir: PathBuf,
+}
+
+impl DatasetExporter {
+    /// Create an exporter writing dataset versions below `dir`
+    pub fn new(db: Database, dir: PathBuf) -> Self {
+        Self { db, dir }
+    }
+
+    /// Directory of a dataset version
+    pub fn version_dir(&self, version: &str) -> PathBuf {
+        self.dir.join(version)
+    }
+
+    /// Export a new dataset version
+    ///
+    /// Files are written to a temporary directory that is renamed once the
+    /// manifest is complete, so a version directory is never partial.
+    pub async fn export(&self, options: &ExportOptions) -> Result<DatasetManifest, DatasetError> {
+        let created_at = Utc::now();
+        let version = options
+            .version
+            .clone()
+            .unwrap_or_else(|| created_at.format("%Y%m%dT%H%M%SZ").to_string());
+        if !is_valid_version(&version) {
+            return Err(DatasetError::InvalidVersion(version));
+        }
+        options
+            .ratios
+            .validate()
+            .map_err(DatasetError::InvalidRatios)?;
+        let target = self.version_dir(&version);
+        if target.exists() {
+            return Err(DatasetError::AlreadyExists(version));
+        }
+
+        let proposals = ProposalRepository::new(self.db.clone())
+            .list(&ProposalQuery {
+                ascending: true,
+                limit: i64::MAX,
+                ..Default::default()
+            })
+            .await?;
+        let repository = LabelRepository::new(self.db.clone());
+        let labels = repository.all().await?;
+        let resolutions = repository.resolutions().await?;
+        let records = build_records(
+            &proposals,
+            &labels,
+            &resolutions,
+            &options.ratios,
+            &options.seed,
+        );
+
+        let formats = if options.formats.is_empty() {
+            vec![DatasetFormat::Jsonl, DatasetFormat::Parquet]
+        } else {
+            options.formats.clone()
+        };
+        let mut manifest = DatasetManifest {
+            version: version.clone(),
+            schema_version: DATASET_SCHEMA_VERSION,
+            created_at,
+            seed: options.seed.clone(),
+            ratios: options.ratios,
+            splits: DatasetSplit::ALL.iter().map(|split| (*split, 0)).collect(),
+            strata: BTreeMap::new(),
+            labeled: records.iter().filter(|r| r.label.is_some()).count(),
+            files: Vec::new(),
+        };
+        for record in &records {
+            *manifest.splits.entry(record.split).or_default() += 1;
+            *manifest
+                .strata
+                .entry(stratum(record))
+                .or_default()
+                .entry(record.split)
+                .or_default() += 1;
+        }
+
+        let staging = self.dir.join(format!(".{version}.tmp"));
+        let manifest = tokio::task::spawn_blocking(move || {
+            write_dataset(&staging, &target, &records, &formats, manifest)
+        })
+        .await
+        .map_err(std::io::Error::other)??;
+
+        info!(
+            "Exported dataset {} with {} records",
+            manifest.version,
+            manifest.splits.values().sum::<usize>()
+        );
+        Ok(manifest)
+    }
+
+    /// Manifest of an exported dataset version
+    pub async fn manifest(&self, version: &str) -> Result<Option<DatasetManifest>, DatasetError> {
+        if !is_valid_version(version) {
+            return Err(DatasetError::InvalidVersion(version.to_string()));
+        }
+
+        match tokio::fs::read(self.version_dir(version).join(MANIFEST_FILE)).await {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Write the files and manifest of a dataset and move them to `target`
+fn write_dataset(
+    staging: &Path,
+    target: &Path,
+    records: &[DatasetRecord],
+    formats: &[DatasetFormat],
+    mut manifest: DatasetManifest,
+) -> Result<DatasetManifest, DatasetError> {
+    if staging.exists() {
+        fs::remove_dir_all(staging)?;
+    }
+    fs::create_dir_all(staging)?;
+
+    for format in formats {
+        for split in DatasetSplit::ALL {
+            let rows: Vec<&DatasetRecord> = records.iter().filter(|r| r.split == split).collect();
+            let path = format!("{}.{}", split.as_str(), format.extension());
+            let file = staging.join(&path);
+            match format {
+                DatasetFormat::Jsonl => write_jsonl(&file, &rows)?,
+                DatasetFormat::Parquet => write_parquet(&file, &rows)?,
+            }
+
+            manifest.files.push(DatasetFile {
+                sha256: hex::encode(Sha256::digest(fs::read(&file)?)),
+                path,
+                format: *format,
+                split,
+                rows: rows.len(),
+            });
+        }
+    }
+
+    fs::write(
+        staging.join(MANIFEST_FILE),
+        serde_json::to_vec_pretty(&manifest)?,
+    )?;
+    fs::rename(staging, target)?;
+    Ok(manifest)
+}
+
+fn write_jsonl(path: &Path, records: &[&DatasetRecord]) -> Result<(), DatasetError> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    for record in records {
+        serde_json::to_writer(&mut writer, record)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Schema of the Parquet files
+///
+/// Author metrics are flattened into columns, labels and evaluation results
+/// are stored as JSON strings, as in the JSONL records.
+pub fn parquet_schema() -> Schema {
+    let timestamp = DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into()));
+    let list = |item: DataType| DataType::List(Arc::new(Field::new("item", item, true)));
+
+    Schema::new(vec![
+        Field::new("id", DataType::Utf8, false),
+        Field::new("protocol_id", DataType::Utf8, false),
+        Field::new("source", DataType::Utf8, false),
+        Field::new("title", DataType::Utf8, false),
+        Field::new("description", DataType::Utf8, false),
+        Field::new("choices", list(DataType::Utf8), false),
+        Field::new("author", DataType::Utf8, false),
+        Field::new("created_at", timestamp.clone(), false),
+        Field::new("voting_start", timestamp.clone(), true),
+        Field::new("voting_end", timestamp, true),
+        Field::new("voting_type", DataType::Utf8, false),
+        Field::new("status", DataType::Utf8, false),
+        Field::new("outcome", DataType::Utf8, false),
+        Field::new("scores", list(DataType::Float64), false),
+        Field::new("scores_total", DataType::Float64, true),
+        Field::new("quorum", DataType::Float64, true),
+        Field::new("is_duplicate_of", DataType::Utf8, true),
+        Field::new("spam_score", DataType::Float64, true),
+        Field::new("author_prior_proposals", DataType::UInt32, false),
+        Field::new("author_prior_passed", DataType::UInt32, false),
+        Field::new("author_prior_failed", DataType::UInt32, false),
+        Field::new("labels", DataType::Utf8, false),
+        Field::new("label", DataType::Utf8, true),
+        Field::new("criteria", DataType::Utf8, true),
+        Field::new("split", DataType::Utf8, false),
+    ])
+}
+
+fn write_parquet(path: &Path, records: &[&DatasetRecord]) -> Result<(), DatasetError> {
+    let strings = |value: fn(&DatasetRecord) -> &str| -> ArrayRef {
+        Arc::new(StringArray::from_iter_values(
+            records.iter().map(|r| value(r)),
+        ))
+    };
+    let optional_strings = |value: fn(&DatasetRecord) -> Option<&str>| -> ArrayRef {
+        Arc::new(StringArray::from(
+            records.iter().map(|r| value(r)).collect::<Vec<_>>(),
+        ))
+    };
+    let optional_floats = |value: fn(&DatasetRecord) -> Option<f64>| -> ArrayRef {
+        Arc::new(Float64Array::from(
+            records.iter().map(|r| value(r)).collect::<Vec<_>>(),
+        ))
+    };
+    let counts = |value: fn(&AuthorMetrics) -> u32| -> ArrayRef {
+        Arc::new(UInt32Array::from_iter_values(
+            records.iter().map(|r| value(&r.author_metrics)),
+        ))
+    };
+    let timestamps = |values: Vec<Option<i64>>| -> ArrayRef {
+        Arc::new(TimestampMillisecondArray::from(values).with_timezone("UTC"))
+    };
+
+    let mut choices = ListBuilder::new(StringBuilder::new());
+    let mut scores = ListBuilder::new(Float64Builder::new());
+    for record in records {
+        for choice in &record.choices {
+            choices.values().append_value(choice);
+        }
+        choices.append(true);
+        scores.values().append_slice(&record.scores);
+        scores.append(true);
+    }
+    let labels = records
+        .iter()
+        .map(|r| serde_json::to_string(&r.labels))
+        .collect::<Result<Vec<_>, _>>()?;
+    let criteria = records
+        .iter()
+        .map(|r| r.criteria.as_ref().map(serde_json::to_string).transpose())
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let columns: Vec<ArrayRef> = vec![
+        strings(|r| &r.id),
+        strings(|r| &r.protocol_id),
+        strings(|r| &r.source),
+        strings(|r| &r.title),
+        strings(|r| &r.description),
+        Arc::new(choices.finish()),
+        strings(|r| &r.author),
+        timestamps(
+            records
+                .iter()
+                .map(|r| Some(r.created_at.timestamp_millis()))
+                .collect(),
+        ),
+        timestamps(
+            records
+                .iter()
+                .map(|r| r.voting_start.map(|t| t.timestamp_millis()))
+                .collect(),
+        ),
+        timestamps(
+            records
+                .iter()
+                .map(|r| r.voting_end.map(|t| t.timestamp_millis()))
+                .collect(),
+        ),
+        strings(|r| r.voting_type.as_str()),
+        strings(|r| &r.status),
+        strings(|r| r.outcome.as_str()),
+        Arc::new(scores.finish()),
+        optional_floats(|r| r.scores_total),
+        optional_floats(|r| r.quorum),
+        optional_strings(|r| r.is_duplicate_of.as_deref()),
+        optional_floats(|r| r.spam_score),
+        counts(|m| m.prior_proposals),
+        counts(|m| m.prior_passed),
+        counts(|m| m.prior_failed),
+        Arc::new(StringArray::from(labels)),
+        optional_strings(|r| r.label.as_ref().map(LabelValue::as_str)),
+        Arc::new(StringArray::from(criteria)),
+        strings(|r| r.split.as_str()),
+    ];
+
+    let schema = Arc::new(parquet_schema());
+    let batch = RecordBatch::try_new(schema.clone(), columns)?;
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let mut writer = ArrowWriter::try_new(File::create(path)?, schema, Some(properties))?;
+    writer.write(&batch)?;
+    writer.close()?;
+    Ok(())
+}
@@ -0,0 +1,510 @@
+//! Proposal embeddings and semantic similarity search
+//!
+//! Proposal text is split into chunks that are embedded by a pluggable
+//! [`EmbeddingProvider`](crate::services::embeddings::EmbeddingProvider) and
+//! stored with pgvector. The
+//! [`EmbeddingIndexer`](crate::services::embeddings::EmbeddingIndexer) embeds
+//! proposals as they are created or edited and backfills the ones whose
+//! embeddings are missing or stale; the
+//! [`SimilaritySearch`](crate::services::embeddings::SimilaritySearch) handle
+//! serves nearest-neighbour queries.
+
+use std::sync::Arc;
+
+use anyhow::{anyhow, ensure};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, error, info, warn};
+
+use crate::{
+    config::{EmbeddingConfig, EmbeddingProviderKind},
+    db::{
+        repositories::{
+            embedding::{EmbeddedChunk, SimilarityMatch},
+            EmbeddingRepository, ProposalRepository,
+        },
+        Database,
+    },
+    models::{revision::content_hash, EventEnvelope, IndexerEvent, Proposal},
+    services::EventBus,
+    utils::id::ProposalId,
+};
+
+/// Dimension of stored embeddings, fixed by the `proposal_embeddings` table
+pub const EMBEDDING_DIMENSIONS: usize = 1536;
+
+/// Longest chunk of proposal text embedded at once, in characters
+pub const MAX_CHUNK_CHARS: usize = 4000;
+
+/// Most chunks embedded per proposal
+///
+/// Text beyond the limit is not embedded, which bounds the cost of very long
+/// proposals.
+pub const MAX_CHUNKS: usize = 32;
+
+/// Number of texts sent to the provider per request
+const EMBED_BATCH_SIZE: usize = 64;
+
+/// Number of stale proposals loaded per backfill query
+const BACKFILL_BATCH_SIZE: i64 = 100;
+
+/// Source of text embeddings
+#[async_trait]
+pub trait EmbeddingProvider: Send + Sync {
+    /// Name of the embedding model, stored with every embedding
+    fn model(&self) -> &str;
+
+    /// Embed texts, returning one vector of [`EMBEDDING_DIMENSIONS`] per text
+    async fn embed(&self, texts: &[String]) -> anyhow::Result<Vec<Vec<f32>>>;
+}
+
+/// Create the provider selected by the configuration, if any
+pub fn provider_from_config(config: &EmbeddingConfig) -> Option<Arc<dyn EmbeddingProvider>> {
+    match config.provider? {
+        EmbeddingProviderKind::Openai => Some(Arc::new(OpenAiEmbeddingProvider::new(
+            &config.base_url,
+            config.api_key.clone(),
+            &config.model,
+        ))),
+        EmbeddingProviderKind::Mock => Some(Arc::new(MockEmbeddingProvider)),
+    }
+}
+
+/// Provider for OpenAI-compatible `/embeddings` endpoints
+pub struct OpenAiEmbeddingProvider {
+    client: Client,
+    base_url: String,
+    api_key: Option<String>,
+    model: String,
+}
+
+#[derive(Deserialize)]
+struct OpenAiEmbeddingResponse {
+    data: Vec<OpenAiEmbedding>,
+}
+
+#[derive(Deserialize)]
+struct OpenAiEmbedding {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+impl OpenAiEmbeddingProvider {
+    /// Create a provider for the API at `base_url`, e.g. `https://api.openai.com/v1`
+    pub fn new(base_url: &str, api_key: Option<String>, model: &str) -> Self {
+        Self {
+            client: Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+            model: model.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for OpenAiEmbeddingProvider {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn embed(&self, texts: &[String]) -> anyhow::Result<Vec<Vec<f32>>> {
+        let url = format!("{}/embeddings", self.base_url);
+        let mut request = self.client.post(&url).json(&json!({
+            "model": self.model,
+            "input": texts,
+            "dimensions": EMBEDDING_DIMENSIONS,
+        }));
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let mut response: OpenAiEmbeddingResponse =
+            request.send().await?.error_for_status()?.json().await?;
+        response.data.sort_by_key(|embedding| embedding.index);
+
+        ensure!(
+            response.data.len() == texts.len(),
+            "{url} returned {} embeddings for {} texts",
+            response.data.len(),
+            texts.len()
+        );
+        response
+            .data
+            .into_iter()
+            .map(|embedding| {
+                ensure!(
+                    embedding.embedding.len() == EMBEDDING_DIMENSIONS,
+                    "{url} returned an embedding of dimension {}, expected {EMBEDDING_DIMENSIONS}",
+                    embedding.embedding.len()
+                );
+                Ok(embedding.embedding)
+            })
+            .collect()
+    }
+}
+
+/// Deterministic provider for tests and local development
+///
+/// Every word is hashed to a signed dimension, so texts sharing words get
+/// similar vectors without calling a model.
+pub struct MockEmbeddingProvider;
+
+impl MockEmbeddingProvider {
+    /// Name of the mock model
+    pub const MODEL: &'static str = "mock";
+
+    /// Embed a single text
+    pub fn embed_text(text: &str) -> Vec<f32> {
+        let mut vector = vec![0.0f32; EMBEDDING_DIMENSIONS];
+        let words = text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty());
+        for word in words {
+            let digest = Sha256::digest(word.to_lowercase().as_bytes());
+            let bucket = u64::from_be_bytes(digest[..8].try_into().expect("8 bytes"));
+            let sign = if digest[8] & 1 == 0 { 1.0 } else { -1.0 };
+            vector[(bucket % EMBEDDING_DIMENSIONS as u64) as usize] += sign;
+        }
+
+        let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            vector.iter_mut().for_each(|x| *x /= norm);
+        }
+        vector
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for MockEmbeddingProvider {
+    fn model(&self) -> &str {
+        Self::MODEL
+    }
+
+    async fn embed(&self, texts: &[String]) -> anyhow::Result<Vec<Vec<f32>>> {
+        Ok(texts.iter().map(|text| Self::embed_text(text)).collect())
+    }
+}
+
+/// Split text into chunks of at most `max_chars` characters
+///
+/// Paragraphs are packed into chunks whole where possible; longer paragraphs
+/// are split between words, and words longer than a chunk are split anywhere.
+/// Blank text yields no chunks.
+pub fn chunk_text(text: &str, max_chars: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+
+    let paragraphs = text
+        .split("\n\n")
+        .map(str::trim)
+        .filter(|paragraph| !paragraph.is_empty());
+    for paragraph in paragraphs {
+        let length = paragraph.chars().count();
+        if !current.is_empty() && current.chars().count() + 2 + length <= max_chars {
+            current.push_str("\n\n");
+            current.push_str(paragraph);
+            continue;
+        }
+
+        if !current.is_empty() {
+            chunks.push(std::mem::take(&mut current));
+        }
+        if length <= max_chars {
+            current.push_str(paragraph);
+            continue;
+        }
+
+        for mut word in paragraph.split_whitespace() {
+            while word.chars().count() > max_chars {
+                if !current.is_empty() {
+                    chunks.push(std::mem::take(&mut current));
+                }
+                let (split, _) = word.char_indices().nth(max_chars).expect("longer word");
+                chunks.push(word[..split].to_string());
+                word = &word[split..];
+            }
+
+            if !current.is_empty() && current.chars().count() + 1 + word.chars().count() > max_chars
+            {
+                chunks.push(std::mem::take(&mut current));
+            }
+            if !current.is_empty() {
+                current.push(' ');
+            }
+            current.push_str(word);
+        }
+    }
+
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// Chunks of a proposal to embed: its title and description
+pub fn proposal_chunks(proposal: &Proposal) -> Vec<String> {
+    let text = format!("{}\n\n{}", proposal.title, proposal.description);
+    let mut chunks = chunk_text(&text, MAX_CHUNK_CHARS);
+    chunks.truncate(MAX_CHUNKS);
+    chunks
+}
+
+/// Embed texts in batches
+async fn embed_all(
+    provider: &dyn EmbeddingProvider,
+    texts: &[String],
+) -> anyhow::Result<Vec<Vec<f32>>> {
+    let mut vectors = Vec::with_capacity(texts.len());
+    for batch in texts.chunks(EMBED_BATCH_SIZE) {
+        vectors.extend(provider.embed(batch).await?);
+    }
+    ensure!(
+        vectors.iter().all(|v| v.len() == EMBEDDING_DIMENSIONS),
+        "Provider returned embeddings of the wrong dimension"
+    );
+    Ok(vectors)
+}
+
+/// Background worker keeping proposal embeddings up to date
+#[derive(Clone)]
+pub struct EmbeddingIndexer {
+    db: Database,
+    provider: Arc<dyn EmbeddingProvider>,
+}
+
+impl EmbeddingIndexer {
+    /// Create an embedding indexer
+    pub fn new(db: Database, provider: Arc<dyn EmbeddingProvider>) -> Self {
+        Self { db, provider }
+    }
+
+    /// Embed a proposal unless its current content is already embedded
+    ///
+    /// Returns whether new embeddings were stored.
+    pub async fn embed_proposal(&self, proposal_id: &ProposalId) -> anyhow::Result<bool> {
+        let Some(proposal) = ProposalRepository::new(self.db.clone())
+            .find_by_id(proposal_id)
+            .await?
+        else {
+            return Ok(false);
+        };
+
+        let repository = EmbeddingRepository::new(self.db.clone());
+        let hash = content_hash(&proposal);
+        let stored = repository
+            .content_hash(proposal_id, self.provider.model())
+            .await?;
+        if stored.as_deref() == Some(hash.as_str()) {
+            return Ok(false);
+        }
+
+        let texts = proposal_chunks(&proposal);
+        let vectors = embed_all(self.provider.as_ref(), &texts).await?;
+        let chunks: Vec<EmbeddedChunk> = texts
+            .into_iter()
+            .zip(vectors)
+            .map(|(content, embedding)| EmbeddedChunk { content, embedding })
+            .collect();
+        repository
+            .replace(proposal_id, self.provider.model(), &hash, &chunks)
+            .await?;
+
+        debug!(
+            "Embedded {} chunks of proposal {}",
+            chunks.len(),
+            proposal_id
+        );
+        Ok(true)
+    }
+
+    /// Embed every proposal whose embeddings are missing or stale
+    ///
+    /// Proposals that fail are logged and skipped. Returns the number of
+    /// proposals embedded.
+    pub async fn backfill(&self) -> Result<usize, sqlx::Error> {
+        let repository = EmbeddingRepository::new(self.db.clone());
+        let mut cursor = None;
+        let mut embedded = 0;
+
+        loop {
+            let stale = repository
+                .find_stale(self.provider.model(), cursor.as_ref(), BACKFILL_BATCH_SIZE)
+                .await?;
+            let Some(last) = stale.last().cloned() else {
+                break;
+            };
+
+            for proposal_id in &stale {
+                match self.embed_proposal(proposal_id).await {
+                    Ok(true) => embedded += 1,
+                    Ok(false) => {}
+                    Err(e) => error!("Failed to embed proposal {}: {}", proposal_id, e),
+                }
+            }
+            cursor = Some(last);
+        }
+
+        Ok(embedded)
+    }
+
+    /// Backfill, then embed proposals as they are created or edited
+    async fn run(self, bus: EventBus) {
+        // Subscribe before the backfill so no edit falls in between
+        let mut events = bus.subscribe();
+        match self.backfill().await {
+            Ok(count) => info!("Backfilled embeddings of {} proposals", count),
+            Err(e) => error!("Failed to backfill proposal embeddings: {}", e),
+        }
+
+        loop {
+            match events.recv().await {
+                Ok(EventEnvelope {
+                    event:
+                        IndexerEvent::ProposalCreated { proposal_id, .. }
+                        | IndexerEvent::ProposalUpdated { proposal_id, .. },
+                    ..
+                }) => {
+                    if let Err(e) = self.embed_proposal(&proposal_id).await {
+                        error!("Failed to embed proposal {}: {}", proposal_id, e);
+                    }
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(missed)) => {
+                    warn!("Embedding indexer missed {} events, backfilling", missed);
+                    if let Err(e) = self.backfill().await {
+                        error!("Failed to backfill proposal embeddings: {}", e);
+                    }
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+        debug!("Embedding indexer stopped");
+    }
+}
+
+/// A proposal similar to a query
+#[derive(Debug, Clone, Serialize)]
+pub struct SimilarProposal {
+    /// The matching proposal
+    #[serde(flatten)]
+    pub proposal: Proposal,
+    /// Cosine similarity of its closest chunk, 1 for identical directions
+    pub similarity: f64,
+}
+
+/// Handle serving similarity queries over proposal embeddings
+///
+/// The default handle is disabled and serves no queries.
+#[derive(Clone, Default)]
+pub struct SimilaritySearch {
+    inner: Option<(Database, Arc<dyn EmbeddingProvider>)>,
+}
+
+impl SimilaritySearch {
+    /// Start the embedding indexer and return the search handle
+    ///
+    /// Search stays disabled without a provider or when pgvector is not
+    /// installed.
+    pub async fn spawn(
+        db: Database,
+        bus: &EventBus,
+        provider: Option<Arc<dyn EmbeddingProvider>>,
+    ) -> Result<Self, sqlx::Error> {
+        let Some(provider) = provider else {
+            info!("No embedding provider configured, similarity search is disabled");
+            return Ok(Self::default());
+        };
+        if !EmbeddingRepository::new(db.clone()).ensure_table().await? {
+            warn!("pgvector is not installed, similarity search is disabled");
+            return Ok(Self::default());
+        }
+
+        info!("Embedding proposals with model {}", provider.model());
+        let indexer = EmbeddingIndexer::new(db.clone(), provider.clone());
+        tokio::spawn(indexer.run(bus.clone()));
+
+        Ok(Self {
+            inner: Some((db, provider)),
+        })
+    }
+
+    /// Whether similarity queries are served
+    pub fn is_enabled(&self) -> bool {
+        self.inner.is_some()
+    }
+
+    /// Proposals most similar to a stored proposal
+    ///
+    /// A proposal that is not embedded yet is embedded on the fly.
+    pub async fn similar_to_proposal(
+        &self,
+        proposal: &Proposal,
+        limit: i64,
+    ) -> anyhow::Result<Vec<SimilarProposal>> {
+        let (db, provider) = self.enabled()?;
+        let repository = EmbeddingRepository::new(db.clone());
+
+        let mut matches = repository
+            .similar_to_proposal(&proposal.id, provider.model(), limit)
+            .await?;
+        if matches.is_empty()
+            && repository
+                .content_hash(&proposal.id, provider.model())
+                .await?
+                .is_none()
+        {
+            let vectors = embed_all(provider.as_ref(), &proposal_chunks(proposal)).await?;
+            matches = repository
+                .similar_to_vectors(&vectors, provider.model(), Some(&proposal.id), limit)
+                .await?;
+        }
+
+        self.load(matches).await
+    }
+
+    /// Proposals most similar to a text
+    pub async fn similar_to_text(
+        &self,
+        text: &str,
+        limit: i64,
+    ) -> anyhow::Result<Vec<SimilarProposal>> {
+        let (db, provider) = self.enabled()?;
+        let mut texts = chunk_text(text, MAX_CHUNK_CHARS);
+        texts.truncate(MAX_CHUNKS);
+
+        let vectors = embed_all(provider.as_ref(), &texts).await?;
+        let matches = EmbeddingRepository::new(db.clone())
+            .similar_to_vectors(&vectors, provider.model(), None, limit)
+            .await?;
+
+        self.load(matches).await
+    }
+
+    fn enabled(&self) -> anyhow::Result<&(Database, Arc<dyn EmbeddingProvider>)> {
+        self.inner
+            .as_ref()
+            .ok_or_else(|| anyhow!("Similarity search is disabled"))
+    }
+
+    /// Load the matching proposals, keeping the order of the matches
+    async fn load(&self, matches: Vec<SimilarityMatch>) -> anyhow::Result<Vec<SimilarProposal>> {
+        let (db, _) = self.enabled()?;
+        let repository = ProposalRepository::new(db.clone());
+
+        let mut similar = Vec::with_capacity(matches.len());
+        for matched in matches {
+            // Proposals deleted since the query are skipped
+            if let Some(proposal) = repository.find_by_id(&matched.proposal_id).await? {
+                similar.push(SimilarProposal {
+                    proposal,
+                    similarity: matched.similarity,
+                });
+            }
+        }
+        Ok(similar)
+    }
+}
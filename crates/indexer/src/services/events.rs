@@ -0,0 +1,148 @@
+//! In-process event bus and persistent event log
+//!
+//! Every [`IndexerEvent`](crate::models::event::IndexerEvent) is appended
+//! to the database in the same transaction as the change it describes.
+//! Once committed, services publish the logged events on the bus so
+//! in-process consumers receive them right away. The
+//! [`EventLog`](crate::services::events::EventLog) feeds logged events to
+//! stream clients and webhook delivery, which can resume from the last
+//! event they received. Events logged by other replicas are only published
+//! on their own bus, so an idle feed reads the database again every
+//! [`LIVE_POLL_INTERVAL`](crate::services::events::LIVE_POLL_INTERVAL).
+
+use std::time::Duration;
+
+use tokio::sync::{
+    broadcast::{self, error::RecvError},
+    mpsc,
+};
+use tracing::debug;
+
+use crate::{
+    db::{repositories::EventRepository, Database},
+    models::{EventEnvelope, EventFilter},
+};
+
+/// Number of events buffered for slow subscribers
+const DEFAULT_CAPACITY: usize = 1024;
+
+/// Broadcast channel of logged indexer events
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<EventEnvelope>,
+}
+
+impl EventBus {
+    /// Create an event bus buffering up to `capacity` events per subscriber
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            sender: broadcast::channel(capacity).0,
+        }
+    }
+
+    /// Publish a logged event to all current subscribers
+    pub fn publish(&self, event: EventEnvelope) {
+        // Sending only fails when nobody is subscribed
+        if self.sender.send(event).is_err() {
+            debug!("No subscribers for indexer event");
+        }
+    }
+
+    /// Subscribe to events published from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<EventEnvelope> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+/// Number of logged events loaded per query when replaying
+const REPLAY_BATCH_SIZE: i64 = 500;
+
+/// Time a feed waits for a live event before reading the database again
+pub const LIVE_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Persistent log of indexer events with live subscriptions
+#[derive(Clone)]
+pub struct EventLog {
+    db: Database,
+    bus: EventBus,
+}
+
+impl EventLog {
+    /// Create a log feeding events from the database and, live, from `bus`
+    pub fn new(db: Database, bus: EventBus) -> Self {
+        Self { db, bus }
+    }
+
+    /// Send logged events matching `filter` to `sender` until it is closed
+    ///
+    /// Events after `last_event_id` are replayed from the database first;
+    /// without it only new events are sent. A subscriber falling behind the
+    /// live broadcast, or receiving an event out of order, catches up from
+    /// the database, so no event is skipped. Without live events the
+    /// database is read every [`LIVE_POLL_INTERVAL`] for events logged by
+    /// other replicas.
+    pub async fn feed(
+        &self,
+        filter: EventFilter,
+        last_event_id: Option<i64>,
+        sender: mpsc::Sender<EventEnvelope>,
+    ) -> Result<(), sqlx::Error> {
+        let repository = EventRepository::new(self.db.clone());
+        // Subscribe before reading the log so nothing falls in between
+        let mut live = self.bus.subscribe();
+        let mut cursor = match last_event_id {
+            Some(id) => id,
+            None => repository.latest_id().await?,
+        };
+
+        loop {
+            // Replay from the database until it is exhausted
+            loop {
+                let batch = repository.after(cursor, &filter, REPLAY_BATCH_SIZE).await?;
+                let done = (batch.len() as i64) < REPLAY_BATCH_SIZE;
+                for stored in batch {
+                    cursor = stored.id;
+                    if sender.send(stored).await.is_err() {
+                        return Ok(());
+                    }
+                }
+                if done {
+                    break;
+                }
+            }
+
+            // Forward live events until one is missing, the subscription
+            // lags behind or none arrives for a while. Events are published
+            // once their transaction commits, so they may arrive out of
+            // order, and events logged by other replicas never arrive, so
+            // they only show up as a gap or after the poll interval.
+            loop {
+                let Ok(received) = tokio::time::timeout(LIVE_POLL_INTERVAL, live.recv()).await
+                else {
+                    break;
+                };
+                match received {
+                    Ok(stored) if stored.id <= cursor => {}
+                    Ok(stored) if stored.id > cursor + 1 => break,
+                    Ok(stored) => {
+                        cursor = stored.id;
+                        if sender.is_closed() {
+                            return Ok(());
+                        }
+                        if filter.matches(&stored.event) && sender.send(stored).await.is_err() {
+                            return Ok(());
+                        }
+                    }
+                    Err(RecvError::Lagged(_)) => break,
+                    Err(RecvError::Closed) => return Ok(()),
+                }
+            }
+        }
+    }
+}
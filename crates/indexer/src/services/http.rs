@@ -0,0 +1,535 @@
+//! Rate-limited HTTP client shared by the data sources
+//!
+//! Every request to a host first takes a token from the host's
+//! [`TokenBucket`](crate::services::http::TokenBucket), so crawls stay within
+//! the API's published rate limit. A `429` or `5xx` response, a network
+//! error or a timeout is retried with jittered exponential backoff, waiting at least as
+//! long as the `Retry-After` header asks. Requests that still fail count
+//! towards the host's
+//! [`CircuitBreaker`](crate::services::http::CircuitBreaker); once it opens,
+//! requests are rejected without being sent and the data sources of the host
+//! report themselves unavailable until the cooldown has passed.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use reqwest::{header::RETRY_AFTER, Client, RequestBuilder, Response, StatusCode};
+use serde::Serialize;
+use thiserror::Error;
+use tracing::warn;
+
+use crate::config::{DataSourceConfig, HttpConfig};
+
+/// Delay before the first retry
+const BASE_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Longest delay between retries computed by the backoff
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Errors of requests sent through the [`HttpClient`]
+#[derive(Debug, Error)]
+pub enum HttpError {
+    /// The host's circuit breaker is open
+    #[error("{host} is unavailable after repeated failures, retrying in {retry_in:?}")]
+    CircuitOpen {
+        /// Host of the request
+        host: String,
+        /// Time until the breaker lets a request through
+        retry_in: Duration,
+    },
+    /// The request could not be built or sent
+    #[error(transparent)]
+    Request(#[from] reqwest::Error),
+}
+
+/// Token bucket allowing a number of requests per minute
+///
+/// The bucket starts full, so up to a minute's worth of requests can be sent
+/// at once before they are spread out.
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    per_second: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    /// Create a full bucket refilled with `requests_per_minute` tokens a minute
+    pub fn new(requests_per_minute: u32, now: Instant) -> Self {
+        let capacity = f64::from(requests_per_minute.max(1));
+        Self {
+            capacity,
+            tokens: capacity,
+            per_second: capacity / 60.0,
+            updated: now,
+        }
+    }
+
+    /// Take a token, or return how long to wait until one is available
+    pub fn try_acquire(&mut self, now: Instant) -> Result<(), Duration> {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_second).min(self.capacity);
+        self.updated = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (1.0 - self.tokens) / self.per_second,
+            ))
+        }
+    }
+}
+
+/// State of a circuit breaker
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Requests are sent
+    Closed,
+    /// Requests are rejected until the cooldown has passed
+    Open,
+    /// The cooldown has passed and the next request decides whether to close
+    HalfOpen,
+}
+
+impl CircuitState {
+    /// Get the state as a string
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CircuitState::Closed => "closed",
+            CircuitState::Open => "open",
+            CircuitState::HalfOpen => "half_open",
+        }
+    }
+}
+
+/// Circuit breaker opened by consecutive failed requests
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    failures: u32,
+    opened_at: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    /// Create a closed breaker opening after `threshold` consecutive failures
+    pub fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            threshold: threshold.max(1),
+            cooldown,
+            failures: 0,
+            opened_at: None,
+        }
+    }
+
+    /// Current state of the breaker
+    pub fn state(&self, now: Instant) -> CircuitState {
+        match self.opened_at {
+            None => CircuitState::Closed,
+            Some(opened_at) if now.saturating_duration_since(opened_at) < self.cooldown => {
+                CircuitState::Open
+            }
+            Some(_) => CircuitState::HalfOpen,
+        }
+    }
+
+    /// Time until an open breaker lets a request through, `None` unless open
+    pub fn retry_in(&self, now: Instant) -> Option<Duration> {
+        let opened_at = self.opened_at?;
+        self.cooldown
+            .checked_sub(now.saturating_duration_since(opened_at))
+            .filter(|remaining| !remaining.is_zero())
+    }
+
+    /// Record a successful request, closing the breaker
+    pub fn record_success(&mut self) {
+        self.failures = 0;
+        self.opened_at = None;
+    }
+
+    /// Record a failed request, returning whether the breaker opened
+    ///
+    /// A failure while half-open opens the breaker for another cooldown.
+    pub fn record_failure(&mut self, now: Instant) -> bool {
+        self.failures = self.failures.saturating_add(1);
+        if self.failures < self.threshold {
+            return false;
+        }
+        let opened = self.state(now) != CircuitState::Open;
+        if opened {
+            self.opened_at = Some(now);
+        }
+        opened
+    }
+}
+
+/// Retry schedule of failed requests
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for every further retry
+    pub base_delay: Duration,
+    /// Longest delay computed by the backoff
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: BASE_RETRY_DELAY,
+            max_delay: MAX_RETRY_DELAY,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before retry number `retry`, starting at 0
+    ///
+    /// Half of the exponential backoff is fixed and the other half scaled by
+    /// `jitter` in `[0, 1]`, so that clients throttled together do not retry
+    /// together. A `Retry-After` delay requested by the server is waited at
+    /// least, up to `max_delay`.
+    pub fn delay(&self, retry: u32, retry_after: Option<Duration>, jitter: f64) -> Duration {
+        let backoff = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_delay);
+        let delay = backoff / 2 + backoff.mul_f64(jitter.clamp(0.0, 1.0) / 2.0);
+        retry_after.map_or(delay, |retry_after| {
+            retry_after.min(self.max_delay).max(delay)
+        })
+    }
+}
+
+/// Parse a `Retry-After` header given in seconds or as an HTTP date
+pub fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let time = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (time.with_timezone(&Utc) - now)
+            .to_std()
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+/// Whether a response status is worth retrying
+pub fn is_retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Counters of the requests sent to a host
+#[derive(Debug, Default)]
+struct HostCounters {
+    requests: AtomicU64,
+    throttled: AtomicU64,
+    rate_limited: AtomicU64,
+    retries: AtomicU64,
+    failures: AtomicU64,
+    rejected: AtomicU64,
+    circuit_opened: AtomicU64,
+}
+
+/// Rate limit, breaker and counters of a host
+#[derive(Debug)]
+struct Host {
+    bucket: Mutex<Option<TokenBucket>>,
+    breaker: Mutex<CircuitBreaker>,
+    counters: HostCounters,
+}
+
+/// Request metrics and breaker state of a host
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HostMetrics {
+    /// Host name
+    pub host: String,
+    /// Configured requests per minute, unset for hosts without a limit
+    pub requests_per_minute: Option<u32>,
+    /// State of the host's circuit breaker
+    pub circuit: CircuitState,
+    /// Requests sent, including retries
+    pub requests: u64,
+    /// Requests delayed by the local rate limit
+    pub throttled: u64,
+    /// `429 Too Many Requests` responses received
+    pub rate_limited: u64,
+    /// Retries after a 429, 5xx, network error or timeout
+    pub retries: u64,
+    /// Requests that failed after all retries
+    pub failures: u64,
+    /// Requests rejected by the open circuit breaker
+    pub rejected: u64,
+    /// Number of times the circuit breaker opened
+    pub circuit_opened: u64,
+}
+
+/// HTTP client with per-host rate limits, retries and circuit breakers
+///
+/// Cloning is cheap and clones share their limits, so a single client
+/// should serve all data sources of the process.
+#[derive(Clone)]
+pub struct HttpClient {
+    client: Client,
+    retry: RetryPolicy,
+    failure_threshold: u32,
+    cooldown: Duration,
+    limits: Arc<HashMap<String, u32>>,
+    hosts: Arc<Mutex<BTreeMap<String, Arc<Host>>>>,
+}
+
+impl HttpClient {
+    /// Create a client for the configured data sources
+    pub fn new(config: &DataSourceConfig) -> Self {
+        let limits = [
+            (
+                &config.snapshot.base_url,
+                config.snapshot.requests_per_minute,
+            ),
+            (&config.tally.base_url, config.tally.requests_per_minute),
+        ]
+        .into_iter()
+        .filter_map(|(base_url, limit)| Some((host_of(base_url)?, limit)))
+        .collect();
+
+        Self::with_limits(limits, &config.http)
+    }
+
+    /// Create a client with requests per minute per host
+    ///
+    /// Hosts without a limit are only subject to retries and circuit breaking.
+    pub fn with_limits(limits: HashMap<String, u32>, config: &HttpConfig) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .connect_timeout(Duration::from_secs(config.connect_timeout_secs))
+            .build()
+            .expect("Failed to build data source HTTP client");
+        let client = Self {
+            client,
+            retry: RetryPolicy {
+                max_retries: config.max_retries,
+                ..RetryPolicy::default()
+            },
+            failure_threshold: config.failure_threshold,
+            cooldown: Duration::from_secs(config.cooldown_secs),
+            limits: Arc::new(limits),
+            hosts: Arc::new(Mutex::new(BTreeMap::new())),
+        };
+        // Hosts with a limit are reported before the first request
+        for host in client.limits.keys() {
+            client.host(host);
+        }
+        client
+    }
+
+    /// Start building a GET request
+    pub fn get(&self, url: &str) -> RequestBuilder {
+        self.client.get(url)
+    }
+
+    /// Start building a POST request
+    pub fn post(&self, url: &str) -> RequestBuilder {
+        self.client.post(url)
+    }
+
+    /// Rate limit, breaker and counters of a host, created on first use
+    fn host(&self, host: &str) -> Arc<Host> {
+        let mut hosts = self.hosts.lock().unwrap_or_else(|e| e.into_inner());
+        hosts
+            .entry(host.to_string())
+            .or_insert_with(|| {
+                Arc::new(Host {
+                    bucket: Mutex::new(
+                        self.limits
+                            .get(host)
+                            .map(|&limit| TokenBucket::new(limit, Instant::now())),
+                    ),
+                    breaker: Mutex::new(CircuitBreaker::new(self.failure_threshold, self.cooldown)),
+                    counters: HostCounters::default(),
+                })
+            })
+            .clone()
+    }
+
+    /// Whether requests to the host of `url` are let through
+    pub fn is_available(&self, url: &str) -> bool {
+        let Some(host) = host_of(url) else {
+            return true;
+        };
+        let host = self.host(&host);
+        let breaker = host.breaker.lock().unwrap_or_else(|e| e.into_inner());
+        breaker.state(Instant::now()) != CircuitState::Open
+    }
+
+    /// Send a request, retrying throttled and failed attempts
+    ///
+    /// Returns the last response, which may still be an error status once
+    /// retries are exhausted or for statuses that are not retried.
+    ///
+    /// Requests made while handling an API request carry its request ID.
+    #[tracing::instrument(name = "http", skip_all, fields(host = tracing::field::Empty))]
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response, HttpError> {
+        let request = telemetry::propagate(request).build()?;
+        let name = request.url().host_str().unwrap_or_default().to_string();
+        tracing::Span::current().record("host", name.as_str());
+        let host = self.host(&name);
+
+        if let Some(retry_in) = host
+            .breaker
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retry_in(Instant::now())
+        {
+            host.counters.rejected.fetch_add(1, Ordering::Relaxed);
+            return Err(HttpError::CircuitOpen {
+                host: name,
+                retry_in,
+            });
+        }
+
+        let mut retry = 0;
+        loop {
+            self.acquire(&host).await;
+            host.counters.requests.fetch_add(1, Ordering::Relaxed);
+
+            let Some(attempt) = request.try_clone() else {
+                // Streaming bodies cannot be replayed, so they are sent once
+                return Ok(self.client.execute(request).await?);
+            };
+            let (result, retry_after) = match self.client.execute(attempt).await {
+                Ok(response) if is_retryable(response.status()) => {
+                    if response.status() == StatusCode::TOO_MANY_REQUESTS {
+                        host.counters.rate_limited.fetch_add(1, Ordering::Relaxed);
+                    }
+                    let retry_after = response
+                        .headers()
+                        .get(RETRY_AFTER)
+                        .and_then(|value| value.to_str().ok())
+                        .and_then(|value| parse_retry_after(value, Utc::now()));
+                    (Ok(response), retry_after)
+                }
+                Ok(response) => {
+                    host.breaker
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .record_success();
+                    return Ok(response);
+                }
+                Err(e) => (Err(e), None),
+            };
+
+            if retry >= self.retry.max_retries {
+                self.record_failure(&name, &host);
+                return result.map_err(HttpError::from);
+            }
+
+            let delay = self
+                .retry
+                .delay(retry, retry_after, rand::thread_rng().gen());
+            match &result {
+                Ok(response) => warn!(
+                    "{} answered {}, retrying in {:?}",
+                    name,
+                    response.status(),
+                    delay
+                ),
+                Err(e) => warn!("Request to {} failed: {}, retrying in {:?}", name, e, delay),
+            }
+            host.counters.retries.fetch_add(1, Ordering::Relaxed);
+            retry += 1;
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Wait until the host's rate limit allows another request
+    async fn acquire(&self, host: &Host) {
+        let mut throttled = false;
+        loop {
+            let wait = match host
+                .bucket
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .as_mut()
+            {
+                Some(bucket) => bucket.try_acquire(Instant::now()),
+                None => Ok(()),
+            };
+            match wait {
+                Ok(()) => return,
+                Err(wait) => {
+                    if !throttled {
+                        host.counters.throttled.fetch_add(1, Ordering::Relaxed);
+                        throttled = true;
+                    }
+                    tokio::time::sleep(wait).await;
+                }
+            }
+        }
+    }
+
+    /// Count a request that failed after all retries
+    fn record_failure(&self, name: &str, host: &Host) {
+        host.counters.failures.fetch_add(1, Ordering::Relaxed);
+        let opened = host
+            .breaker
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .record_failure(Instant::now());
+        if opened {
+            host.counters.circuit_opened.fetch_add(1, Ordering::Relaxed);
+            warn!(
+                "{} is unavailable after {} failed requests, pausing requests for {:?}",
+                name, self.failure_threshold, self.cooldown
+            );
+        }
+    }
+
+    /// Metrics of every rate-limited host and every host requests were sent to,
+    /// by host name
+    pub fn metrics(&self) -> Vec<HostMetrics> {
+        let hosts = self.hosts.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        hosts
+            .iter()
+            .map(|(name, host)| {
+                let counters = &host.counters;
+                HostMetrics {
+                    host: name.clone(),
+                    requests_per_minute: self.limits.get(name).copied(),
+                    circuit: host
+                        .breaker
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .state(now),
+                    requests: counters.requests.load(Ordering::Relaxed),
+                    throttled: counters.throttled.load(Ordering::Relaxed),
+                    rate_limited: counters.rate_limited.load(Ordering::Relaxed),
+                    retries: counters.retries.load(Ordering::Relaxed),
+                    failures: counters.failures.load(Ordering::Relaxed),
+                    rejected: counters.rejected.load(Ordering::Relaxed),
+                    circuit_opened: counters.circuit_opened.load(Ordering::Relaxed),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Host name of a URL
+fn host_of(url: &str) -> Option<String> {
+    url::Url::parse(url).ok()?.host_str().map(str::to_string)
+}
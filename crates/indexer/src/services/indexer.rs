@@ -1,16 +1,43 @@
 //! Main indexer service
 
+use std::time::Instant;
+
+use tracing::{error, warn};
+
 use crate::{
+    config::{DataSourceConfig, ProtocolRegistry},
     db::Database,
-    models::{Actor, Proposal},
-    services::data_sources::DataSource,
+    models::Actor,
+    services::{
+        data_sources::{data_sources_for, DataSource},
+        events::EventBus,
+        http::HttpClient,
+        lifecycle::LifecycleScheduler,
+        metrics::Metrics,
+        pipeline::{IngestOutcome, IngestPipeline},
+    },
+    utils::id::ProposalId,
 };
 
+/// Outcome of an indexing run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IndexingReport {
+    /// IDs of the proposals stored
+    pub stored: Vec<ProposalId>,
+    /// Number of records sent to quarantine
+    pub quarantined: usize,
+    /// IDs of the proposals removed because their source hides them
+    pub removed: Vec<ProposalId>,
+}
+
 /// Main indexer service
 #[allow(dead_code)] // TODO: Remove after development phase
 pub struct IndexerService {
     db: Database,
     data_sources: Vec<Box<dyn DataSource + Send + Sync>>,
+    events: EventBus,
+    lifecycle: LifecycleScheduler,
+    metrics: Metrics,
 }
 
 impl IndexerService {
@@ -20,9 +47,45 @@ impl IndexerService {
         Self {
             db,
             data_sources: Vec::new(),
+            events: EventBus::default(),
+            lifecycle: LifecycleScheduler::default(),
+            metrics: Metrics::default(),
         }
     }
 
+    /// Publish proposal events to the given bus
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// Hand the voting window of stored proposals to the given lifecycle engine
+    pub fn with_lifecycle(mut self, lifecycle: LifecycleScheduler) -> Self {
+        self.lifecycle = lifecycle;
+        self
+    }
+
+    /// Record fetches and ingested records in the given metrics
+    pub fn with_metrics(mut self, metrics: Metrics) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Create an indexer service crawling every enabled protocol of the registry
+    pub fn from_registry(
+        db: Database,
+        registry: &ProtocolRegistry,
+        config: &DataSourceConfig,
+        http: &HttpClient,
+    ) -> Self {
+        let mut service = Self::new(db);
+        service.data_sources = registry
+            .enabled()
+            .flat_map(|entry| data_sources_for(entry, config, http))
+            .collect();
+        service
+    }
+
     /// Add a data source
     #[allow(dead_code, unused_variables)] // TODO: Remove after development phase
     pub fn add_data_source(&mut self, source: Box<dyn DataSource + Send + Sync>) {
@@ -37,10 +100,44 @@ impl IndexerService {
     }
 
     /// Index proposals from all data sources
-    #[allow(dead_code, unused_variables)] // TODO: Remove after development phase
-    pub async fn index_proposals(&self) -> anyhow::Result<Vec<Proposal>> {
-        // TODO: Implement proposal indexing
-        todo!("Implement index_proposals")
+    ///
+    /// Every record goes through the ingestion pipeline; invalid records are
+    /// quarantined. A failing source is logged and does not stop the others;
+    /// unavailable sources are skipped until their API recovers.
+    pub async fn index_proposals(
+        &self,
+        registry: &ProtocolRegistry,
+    ) -> anyhow::Result<IndexingReport> {
+        let pipeline =
+            IngestPipeline::new(self.db.clone(), self.events.clone(), self.lifecycle.clone())
+                .with_metrics(self.metrics.clone());
+        let mut report = IndexingReport::default();
+
+        for source in &self.data_sources {
+            if !source.is_available().await {
+                warn!(
+                    "Skipping unavailable source {} for {}",
+                    source.name(),
+                    source.protocol_id()
+                );
+                continue;
+            }
+
+            match index_source(&pipeline, source.as_ref(), registry).await {
+                Ok(indexed) => {
+                    report.stored.extend(indexed.stored);
+                    report.quarantined += indexed.quarantined;
+                }
+                Err(e) => error!(
+                    "Failed to index proposals from {} for {}: {:#}",
+                    source.name(),
+                    source.protocol_id(),
+                    e
+                ),
+            }
+        }
+
+        Ok(report)
     }
 
     /// Index actors from all data sources
@@ -50,3 +147,35 @@ impl IndexerService {
         todo!("Implement index_actors")
     }
 }
+
+/// Fetch the latest proposals of a data source and run them through the pipeline
+///
+/// The fetch and the ingested records are recorded in the pipeline's metrics.
+pub async fn index_source(
+    pipeline: &IngestPipeline,
+    source: &(dyn DataSource + Send + Sync),
+    registry: &ProtocolRegistry,
+) -> anyhow::Result<IndexingReport> {
+    let protocol = source.protocol_id().to_string();
+    let started = Instant::now();
+    let fetched = source.fetch_proposals().await;
+    pipeline
+        .metrics()
+        .record_fetch(source.name(), &protocol, started.elapsed(), fetched.is_ok());
+
+    let mut report = IndexingReport::default();
+    for record in &fetched? {
+        match pipeline.ingest(record, registry).await? {
+            IngestOutcome::Stored(id) => report.stored.push(id),
+            IngestOutcome::Quarantined { .. } => report.quarantined += 1,
+            IngestOutcome::Removed(id) => report.removed.push(id),
+        }
+    }
+    pipeline.metrics().record_ingested(
+        source.name(),
+        &protocol,
+        report.stored.len(),
+        report.quarantined,
+    );
+    Ok(report)
+}
@@ -0,0 +1,280 @@
+//! Human labeling of proposal quality
+//!
+//! Labelers give every proposal a good/bad verdict and a pass/fail result on
+//! each of the agent's five evaluation categories. Proposals are handed out
+//! so that labelers overlap, which makes their agreement measurable with
+//! Cohen's kappa. Disagreements are listed as conflicts until someone records
+//! a final verdict. Resolved and majority verdicts are the ground truth for
+//! dataset export and agent evaluation.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    hash::Hash,
+};
+
+use serde::Serialize;
+
+use crate::{
+    models::{
+        dataset::RecordLabel,
+        label::{GroundTruth, LabelCriterion, LabelResolution},
+        ProposalLabel,
+    },
+    services::dataset::{agreed_criteria, majority_label},
+    utils::id::ProposalId,
+};
+
+/// Number of labels a proposal receives before it is no longer handed out
+pub const LABELS_PER_PROPOSAL: i64 = 2;
+
+/// Agreement between labelers on one question
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct AgreementStats {
+    /// Number of proposals labeled by both labelers
+    pub items: usize,
+    /// Share of proposals with the same answer
+    pub observed: f64,
+    /// Cohen's kappa, unset when chance agreement is certain
+    pub kappa: Option<f64>,
+}
+
+impl AgreementStats {
+    /// Agreement over pairs of answers, `None` without pairs
+    pub fn of<T: Eq + Hash + Copy>(pairs: &[(T, T)]) -> Option<Self> {
+        if pairs.is_empty() {
+            return None;
+        }
+
+        let agreed = pairs.iter().filter(|(a, b)| a == b).count();
+        Some(Self {
+            items: pairs.len(),
+            observed: agreed as f64 / pairs.len() as f64,
+            kappa: cohens_kappa(pairs),
+        })
+    }
+}
+
+/// Agreement on the verdict and on each criterion
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Agreement {
+    /// Agreement on the good/bad verdict
+    pub label: AgreementStats,
+    /// Agreement on each evaluation category
+    pub criteria: BTreeMap<LabelCriterion, AgreementStats>,
+}
+
+impl Agreement {
+    /// Agreement over pairs of labels of the same proposals, `None` without pairs
+    fn of(pairs: &[(&ProposalLabel, &ProposalLabel)]) -> Option<Self> {
+        let verdicts: Vec<_> = pairs.iter().map(|(a, b)| (a.label, b.label)).collect();
+        let label = AgreementStats::of(&verdicts)?;
+
+        let criteria = LabelCriterion::ALL
+            .into_iter()
+            .filter_map(|criterion| {
+                let statuses: Vec<_> = pairs
+                    .iter()
+                    .map(|(a, b)| (a.criteria.get(criterion), b.criteria.get(criterion)))
+                    .collect();
+                AgreementStats::of(&statuses).map(|stats| (criterion, stats))
+            })
+            .collect();
+
+        Some(Self { label, criteria })
+    }
+}
+
+/// Agreement between two labelers
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PairAgreement {
+    /// Names of the labelers, in alphabetical order
+    pub labelers: [String; 2],
+    /// Agreement on the proposals both labeled
+    #[serde(flatten)]
+    pub agreement: Agreement,
+}
+
+/// Inter-annotator agreement report
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AgreementReport {
+    /// Number of labelers
+    pub labelers: usize,
+    /// Number of labeled proposals
+    pub proposals: usize,
+    /// Number of labels
+    pub labels: usize,
+    /// Agreement over every pair of labels of the same proposal
+    pub overall: Option<Agreement>,
+    /// Agreement of every pair of labelers with shared proposals
+    pub pairs: Vec<PairAgreement>,
+}
+
+/// Proposal whose labelers disagree and that is not resolved yet
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LabelConflict {
+    /// Canonical ID of the proposal
+    pub proposal_id: ProposalId,
+    /// Whether the labelers disagree on the verdict
+    pub label: bool,
+    /// Evaluation categories the labelers disagree on
+    pub criteria: Vec<LabelCriterion>,
+    /// Labels of the proposal, oldest first
+    pub labels: Vec<ProposalLabel>,
+}
+
+/// Cohen's kappa of two raters, from their answers on the same items
+///
+/// `None` without items or when both raters always give the same single
+/// answer, where agreement by chance is certain and kappa is undefined.
+pub fn cohens_kappa<T: Eq + Hash + Copy>(pairs: &[(T, T)]) -> Option<f64> {
+    if pairs.is_empty() {
+        return None;
+    }
+
+    let n = pairs.len() as f64;
+    let mut first: HashMap<T, f64> = HashMap::new();
+    let mut second: HashMap<T, f64> = HashMap::new();
+    for (a, b) in pairs {
+        *first.entry(*a).or_default() += 1.0;
+        *second.entry(*b).or_default() += 1.0;
+    }
+
+    let observed = pairs.iter().filter(|(a, b)| a == b).count() as f64 / n;
+    let expected = first
+        .iter()
+        .map(|(answer, count)| count * second.get(answer).copied().unwrap_or(0.0))
+        .fold(0.0, |sum, product| sum + product)
+        / (n * n);
+
+    (expected < 1.0).then(|| (observed - expected) / (1.0 - expected))
+}
+
+/// Group labels by proposal, keeping their order
+fn by_proposal(labels: &[ProposalLabel]) -> Vec<(&ProposalId, Vec<&ProposalLabel>)> {
+    let mut groups: Vec<(&ProposalId, Vec<&ProposalLabel>)> = Vec::new();
+    let mut index: HashMap<&ProposalId, usize> = HashMap::new();
+    for label in labels {
+        let i = *index.entry(&label.proposal_id).or_insert_with(|| {
+            groups.push((&label.proposal_id, Vec::new()));
+            groups.len() - 1
+        });
+        groups[i].1.push(label);
+    }
+    groups
+}
+
+/// Agreement between all labelers, overall and per labeler pair
+pub fn agreement_report(labels: &[ProposalLabel]) -> AgreementReport {
+    let groups = by_proposal(labels);
+    let mut overall = Vec::new();
+    let mut pairs: BTreeMap<[&str; 2], Vec<(&ProposalLabel, &ProposalLabel)>> = BTreeMap::new();
+
+    for (_, group) in &groups {
+        for (i, a) in group.iter().enumerate() {
+            for b in &group[i + 1..] {
+                let (a, b) = if a.labeler <= b.labeler {
+                    (*a, *b)
+                } else {
+                    (*b, *a)
+                };
+                overall.push((a, b));
+                pairs
+                    .entry([a.labeler.as_str(), b.labeler.as_str()])
+                    .or_default()
+                    .push((a, b));
+            }
+        }
+    }
+
+    AgreementReport {
+        labelers: labels
+            .iter()
+            .map(|label| label.labeler.as_str())
+            .collect::<HashSet<_>>()
+            .len(),
+        proposals: groups.len(),
+        labels: labels.len(),
+        overall: Agreement::of(&overall),
+        pairs: pairs
+            .into_iter()
+            .filter_map(|([a, b], pairs)| {
+                Agreement::of(&pairs).map(|agreement| PairAgreement {
+                    labelers: [a.to_string(), b.to_string()],
+                    agreement,
+                })
+            })
+            .collect(),
+    }
+}
+
+/// Proposals whose labels disagree on the verdict or a criterion, without resolution
+pub fn conflicts(labels: &[ProposalLabel], resolutions: &[LabelResolution]) -> Vec<LabelConflict> {
+    let resolved: HashSet<&ProposalId> = resolutions.iter().map(|r| &r.proposal_id).collect();
+
+    by_proposal(labels)
+        .into_iter()
+        .filter(|(id, _)| !resolved.contains(id))
+        .filter_map(|(id, group)| {
+            let first = group.first()?;
+            let label = group.iter().any(|l| l.label != first.label);
+            let criteria: Vec<_> = LabelCriterion::ALL
+                .into_iter()
+                .filter(|&c| {
+                    group
+                        .iter()
+                        .any(|l| l.criteria.get(c) != first.criteria.get(c))
+                })
+                .collect();
+
+            (label || !criteria.is_empty()).then(|| LabelConflict {
+                proposal_id: id.clone(),
+                label,
+                criteria,
+                labels: group.into_iter().cloned().collect(),
+            })
+        })
+        .collect()
+}
+
+/// Final verdicts of all proposals with a resolution or a majority verdict
+pub fn ground_truth(labels: &[ProposalLabel], resolutions: &[LabelResolution]) -> Vec<GroundTruth> {
+    let mut resolutions: HashMap<&ProposalId, &LabelResolution> =
+        resolutions.iter().map(|r| (&r.proposal_id, r)).collect();
+    let groups = by_proposal(labels);
+
+    let mut truths: Vec<GroundTruth> = groups
+        .iter()
+        .filter_map(|(id, group)| {
+            let count = group.len();
+            if let Some(resolution) = resolutions.remove(id) {
+                return Some(GroundTruth {
+                    proposal_id: (*id).clone(),
+                    label: resolution.label,
+                    criteria: Some(resolution.criteria),
+                    labels: count,
+                    resolved_by: Some(resolution.resolved_by.clone()),
+                });
+            }
+
+            let records: Vec<RecordLabel> = group.iter().map(|l| RecordLabel::from(*l)).collect();
+            majority_label(&records).map(|label| GroundTruth {
+                proposal_id: (*id).clone(),
+                label,
+                criteria: agreed_criteria(&records),
+                labels: count,
+                resolved_by: None,
+            })
+        })
+        .collect();
+
+    // Proposals resolved without labels are settled as well
+    truths.extend(resolutions.into_values().map(|resolution| GroundTruth {
+        proposal_id: resolution.proposal_id.clone(),
+        label: resolution.label,
+        criteria: Some(resolution.criteria),
+        labels: 0,
+        resolved_by: Some(resolution.resolved_by.clone()),
+    }));
+    truths.sort_by_key(|truth| truth.proposal_id.to_string());
+    truths
+}
@@ -0,0 +1,234 @@
+//! Leader election between indexer replicas
+//!
+//! Every data source has a lease row in the database. A replica crawls a
+//! source only while it holds the source's lease and renews it on every
+//! round of the [`Crawler`](crate::services::crawler::Crawler), well before
+//! it expires. When a replica dies its leases expire and the other replicas
+//! take them over on their next round, so each source is crawled by exactly
+//! one replica at a time. The read API does not need a lease and is served
+//! by every replica.
+//!
+//! Background tasks keep their leases in
+//! [`HeldLeases`](crate::services::leader::HeldLeases), which also renews the
+//! lease of a source while its work runs, so long crawls keep their lease and
+//! are canceled once it is lost.
+
+use std::{
+    collections::HashSet,
+    future::Future,
+    time::{Duration, Instant},
+};
+
+use tokio::task::JoinSet;
+use tracing::{error, info, warn};
+
+use crate::{
+    db::{repositories::LeaseRepository, Database},
+    models::SourceLease,
+    services::crawler::CrawlSchedule,
+};
+
+/// Lease-based election of the replica crawling each data source
+#[derive(Clone)]
+pub struct LeaderElection {
+    db: Database,
+    replica: String,
+    ttl: Duration,
+}
+
+impl LeaderElection {
+    /// Create an election for replica `replica` with leases valid for `ttl`
+    pub fn new(db: Database, replica: String, ttl: Duration) -> Self {
+        Self { db, replica, ttl }
+    }
+
+    /// Replica ID from the `HOSTNAME` environment variable and a random suffix
+    ///
+    /// The suffix keeps restarted replicas on the same host apart, so a new
+    /// process does not inherit the leases of a crashed one before they expire.
+    pub fn default_replica_id() -> String {
+        let host = std::env::var("HOSTNAME").unwrap_or_else(|_| "indexer".to_string());
+        let suffix = uuid::Uuid::new_v4().simple().to_string();
+        format!("{host}-{}", &suffix[..8])
+    }
+
+    /// ID of this replica
+    pub fn replica(&self) -> &str {
+        &self.replica
+    }
+
+    /// Interval at which held leases are renewed, a third of their lifetime
+    pub fn renew_interval(&self) -> Duration {
+        self.ttl / 3
+    }
+
+    /// Take or renew the lease of a data source, returning whether this replica holds it
+    pub async fn acquire(&self, key: &str) -> Result<bool, sqlx::Error> {
+        let lease = LeaseRepository::new(self.db.clone())
+            .acquire(key, &self.replica, self.ttl.as_secs().max(1) as i64)
+            .await?;
+        Ok(lease.is_some())
+    }
+
+    /// Keep renewing a held lease every renewal interval, returning once it is lost
+    ///
+    /// A failed renewal is retried on the next interval, unless the lease may
+    /// have expired by then.
+    pub async fn hold(&self, key: &str) {
+        let mut renewed = Instant::now();
+        loop {
+            tokio::time::sleep(self.renew_interval()).await;
+            match self.acquire(key).await {
+                Ok(true) => renewed = Instant::now(),
+                Ok(false) => return,
+                Err(e) => {
+                    error!("Failed to renew lease of {}: {}", key, e);
+                    if renewed.elapsed() + self.renew_interval() >= self.ttl {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Give up the lease of a data source
+    pub async fn release(&self, key: &str) -> Result<bool, sqlx::Error> {
+        LeaseRepository::new(self.db.clone())
+            .release(key, &self.replica)
+            .await
+    }
+
+    /// Give up all leases of this replica, so others take over right away
+    pub async fn release_all(&self) -> Result<u64, sqlx::Error> {
+        LeaseRepository::new(self.db.clone())
+            .release_all(&self.replica)
+            .await
+    }
+
+    /// Unexpired leases of all replicas
+    pub async fn leases(&self) -> Result<Vec<SourceLease>, sqlx::Error> {
+        LeaseRepository::new(self.db.clone()).active().await
+    }
+}
+
+/// Leases held by a background task and the schedule of their work
+///
+/// Each round, [`renew`](Self::renew) takes or renews the leases of the keys
+/// the task currently works on, and [`run`](Self::run) does the due work of a
+/// held key.
+pub struct HeldLeases {
+    leader: LeaderElection,
+    activity: &'static str,
+    held: HashSet<String>,
+    schedule: CrawlSchedule,
+}
+
+impl HeldLeases {
+    /// No leases yet of a task doing `activity` on its keys, such as `crawls`
+    pub fn new(leader: LeaderElection, activity: &'static str) -> Self {
+        Self {
+            leader,
+            activity,
+            held: HashSet::new(),
+            schedule: CrawlSchedule::default(),
+        }
+    }
+
+    /// Election the leases are taken in
+    pub fn leader(&self) -> &LeaderElection {
+        &self.leader
+    }
+
+    /// Take or renew the leases of `keys`, handing back held leases of other keys
+    pub async fn renew(&mut self, keys: &[String]) {
+        let current: HashSet<&str> = keys.iter().map(String::as_str).collect();
+        let removed: Vec<String> = self
+            .held
+            .iter()
+            .filter(|key| !current.contains(key.as_str()))
+            .cloned()
+            .collect();
+        for key in removed {
+            if let Err(e) = self.leader.release(&key).await {
+                error!("Failed to release lease of {}: {}", key, e);
+            }
+            self.held.remove(&key);
+            self.schedule.forget(&key);
+        }
+
+        for key in keys {
+            match self.leader.acquire(key).await {
+                Ok(true) => {
+                    if self.held.insert(key.clone()) {
+                        info!(
+                            "Replica {} now {} {}",
+                            self.leader.replica(),
+                            self.activity,
+                            key
+                        );
+                    }
+                }
+                Ok(false) => {
+                    if self.held.remove(key) {
+                        warn!("Lease of {} was taken over by another replica", key);
+                    }
+                    self.schedule.forget(key);
+                }
+                Err(e) => error!("Failed to renew lease of {}: {}", key, e),
+            }
+        }
+    }
+
+    /// Whether this replica holds the lease of a key and its work is due
+    pub fn is_due(&self, key: &str) -> bool {
+        self.held.contains(key) && self.schedule.is_due(key, Instant::now())
+    }
+
+    /// Do the work of a held key, due again after `interval` once done
+    ///
+    /// The lease is renewed before the work starts and then in a background
+    /// task while it runs. Returns `None` without finishing the work if the
+    /// lease is lost on the way.
+    pub async fn run<F: Future>(
+        &mut self,
+        key: &str,
+        interval: Duration,
+        work: F,
+    ) -> Option<F::Output> {
+        let now = Instant::now();
+        match self.leader.acquire(key).await {
+            Ok(true) => {}
+            Ok(false) => {
+                self.lost(key);
+                return None;
+            }
+            Err(e) => {
+                error!("Failed to renew lease of {}: {}", key, e);
+                return None;
+            }
+        }
+
+        // Dropping the set aborts the renewals once the work is done
+        let mut renewals = JoinSet::new();
+        let (leader, held_key) = (self.leader.clone(), key.to_string());
+        renewals.spawn(async move { leader.hold(&held_key).await });
+        tokio::select! {
+            output = work => {
+                self.schedule.crawled(key, now, interval);
+                Some(output)
+            }
+            _ = renewals.join_next() => {
+                self.lost(key);
+                None
+            }
+        }
+    }
+
+    /// Forget a key whose lease was lost
+    fn lost(&mut self, key: &str) {
+        if self.held.remove(key) {
+            warn!("Lost the lease of {}, stopped its work", key);
+        }
+        self.schedule.forget(key);
+    }
+}
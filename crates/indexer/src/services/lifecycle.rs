@@ -0,0 +1,179 @@
+//! Proposal lifecycle engine
+//!
+//! The status of a pending or active proposal changes when its voting window
+//! opens or closes. The engine keeps one timer per open proposal in a
+//! [`DelayQueue`](tokio_util::time::DelayQueue) timer wheel and applies the
+//! transition, with its `VotingStarted` or `VotingEnded` event, as soon as it
+//! is due instead of waiting for the next crawl.
+
+use std::{collections::HashMap, time::Duration};
+
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use tokio::sync::mpsc;
+use tokio_util::time::{delay_queue, DelayQueue};
+use tracing::{debug, error, info};
+
+use crate::{
+    db::{repositories::ProposalRepository, Database},
+    models::{Proposal, StatusTrigger},
+    services::EventBus,
+    utils::id::ProposalId,
+};
+
+/// Longest delay armed at once
+///
+/// Timers further out fire early and are re-armed, which keeps them within
+/// the range of the timer wheel.
+const MAX_DELAY: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Request to replace the timer of a proposal
+struct Schedule {
+    proposal_id: ProposalId,
+    at: Option<DateTime<Utc>>,
+}
+
+/// Handle scheduling lifecycle timers
+#[derive(Clone)]
+pub struct LifecycleScheduler {
+    sender: mpsc::UnboundedSender<Schedule>,
+}
+
+impl LifecycleScheduler {
+    /// Schedule the next status transition of a proposal
+    ///
+    /// Replaces any previous timer of the proposal and cancels it if the
+    /// proposal has no upcoming transition.
+    pub fn schedule(&self, proposal: &Proposal) {
+        let schedule = Schedule {
+            proposal_id: proposal.id.clone(),
+            at: proposal.next_transition_at(Utc::now()),
+        };
+        // Sending only fails when no engine is running
+        if self.sender.send(schedule).is_err() {
+            debug!("No lifecycle engine for proposal {}", proposal.id);
+        }
+    }
+}
+
+impl Default for LifecycleScheduler {
+    /// Scheduler without an engine, ignoring every request
+    fn default() -> Self {
+        Self {
+            sender: mpsc::unbounded_channel().0,
+        }
+    }
+}
+
+/// Engine applying status transitions when voting opens or closes
+pub struct LifecycleEngine {
+    db: Database,
+    events: EventBus,
+    receiver: mpsc::UnboundedReceiver<Schedule>,
+    timers: DelayQueue<(ProposalId, DateTime<Utc>)>,
+    keys: HashMap<ProposalId, delay_queue::Key>,
+}
+
+impl LifecycleEngine {
+    /// Create an engine and the scheduler feeding it
+    pub fn new(db: Database, events: EventBus) -> (Self, LifecycleScheduler) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let engine = Self {
+            db,
+            events,
+            receiver,
+            timers: DelayQueue::new(),
+            keys: HashMap::new(),
+        };
+        (engine, LifecycleScheduler { sender })
+    }
+
+    /// Start an engine in the background
+    ///
+    /// Proposals left open in the database are checked right away, so
+    /// transitions missed while the indexer was down are applied.
+    pub async fn spawn(db: Database, events: EventBus) -> Result<LifecycleScheduler, sqlx::Error> {
+        let (mut engine, scheduler) = Self::new(db, events);
+
+        let open = ProposalRepository::new(engine.db.clone())
+            .find_open()
+            .await?;
+        info!("Tracking lifecycle of {} open proposals", open.len());
+        let now = Utc::now();
+        for proposal_id in open {
+            engine.set_timer(proposal_id, Some(now));
+        }
+
+        tokio::spawn(engine.run());
+        Ok(scheduler)
+    }
+
+    /// Handle schedule requests and timers until every scheduler is dropped
+    pub async fn run(mut self) {
+        loop {
+            tokio::select! {
+                schedule = self.receiver.recv() => match schedule {
+                    Some(schedule) => self.set_timer(schedule.proposal_id, schedule.at),
+                    None => break,
+                },
+                Some(expired) = self.timers.next(), if !self.timers.is_empty() => {
+                    let (proposal_id, at) = expired.into_inner();
+                    self.keys.remove(&proposal_id);
+                    if let Err(e) = self.fire(&proposal_id, at).await {
+                        error!("Failed to update status of proposal {}: {}", proposal_id, e);
+                    }
+                }
+            }
+        }
+        debug!("Lifecycle engine stopped");
+    }
+
+    fn set_timer(&mut self, proposal_id: ProposalId, at: Option<DateTime<Utc>>) {
+        if let Some(key) = self.keys.remove(&proposal_id) {
+            self.timers.remove(&key);
+        }
+        let Some(at) = at else {
+            return;
+        };
+
+        let delay = (at - Utc::now())
+            .to_std()
+            .unwrap_or_default()
+            .min(MAX_DELAY);
+        let fire_at = Utc::now() + delay;
+        let key = self.timers.insert((proposal_id.clone(), fire_at), delay);
+        self.keys.insert(proposal_id, key);
+    }
+
+    /// Apply the status due at `at` and arm the next timer
+    async fn fire(
+        &mut self,
+        proposal_id: &ProposalId,
+        at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        let repository = ProposalRepository::new(self.db.clone());
+        let Some(mut proposal) = repository.find_by_id(proposal_id).await? else {
+            return Ok(());
+        };
+
+        // Timers may fire a little before their deadline
+        let now = Utc::now().max(at);
+        proposal.status = proposal.status_at(now);
+
+        if let Some(transition) = repository
+            .transition(proposal_id, proposal.status, StatusTrigger::Timer)
+            .await?
+        {
+            debug!(
+                "Proposal {} moved from {:?} to {:?}",
+                proposal_id, transition.change.from, transition.change.to
+            );
+            if let Some(event) = transition.event {
+                self.events.publish(event);
+            }
+        }
+
+        self.set_timer(proposal_id.clone(), proposal.next_transition_at(now));
+        Ok(())
+    }
+}
@@ -0,0 +1,341 @@
+//! Prometheus metrics of the indexer
+//!
+//! Counters and histograms are recorded in process by the crawler and the
+//! webhook dispatcher. Gauges such as the sync lag and the database pool
+//! usage are read when `/metrics` is scraped and passed in as
+//! [`Gauges`](crate::services::metrics::Gauges).
+//! Metrics are rendered in the Prometheus text exposition format.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::services::http::{CircuitState, HostMetrics};
+
+/// Upper bounds of the fetch latency buckets, in seconds
+pub const LATENCY_BUCKETS: [f64; 10] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0];
+
+/// Distribution of observed values over [`LATENCY_BUCKETS`]
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Histogram {
+    /// Observations per bucket, not cumulative
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    /// Number of observations
+    count: u64,
+    /// Sum of the observed values
+    sum: f64,
+}
+
+impl Histogram {
+    /// Record an observation
+    fn observe(&mut self, value: f64) {
+        if let Some(bucket) = LATENCY_BUCKETS.iter().position(|bound| value <= *bound) {
+            self.buckets[bucket] += 1;
+        }
+        self.count += 1;
+        self.sum += value;
+    }
+}
+
+/// Labels of the per-source metrics: data source name and protocol ID
+type SourceKey = (String, String);
+
+/// Counter of the data source HTTP client, by name and help text
+type HostCounter = (&'static str, &'static str, fn(&HostMetrics) -> u64);
+
+#[derive(Default)]
+struct Registry {
+    fetches: BTreeMap<(SourceKey, &'static str), u64>,
+    fetch_duration: BTreeMap<SourceKey, Histogram>,
+    ingested: BTreeMap<(SourceKey, &'static str), u64>,
+    deliveries: BTreeMap<&'static str, u64>,
+    webhooks_disabled: u64,
+}
+
+/// Values read when metrics are scraped
+#[derive(Debug, Clone, Default)]
+pub struct Gauges {
+    /// Seconds since the newest proposal of each source and protocol was created
+    pub sync_lag: Vec<(SourceKey, f64)>,
+    /// Open database connections
+    pub pool_size: u32,
+    /// Idle database connections
+    pub pool_idle: usize,
+    /// Largest number of database connections
+    pub pool_max: u32,
+    /// Counters of the data source HTTP client
+    pub hosts: Vec<HostMetrics>,
+}
+
+/// In-process metrics registry
+///
+/// Cloning is cheap and clones share their values.
+#[derive(Clone, Default)]
+pub struct Metrics {
+    registry: Arc<Mutex<Registry>>,
+}
+
+fn outcome(success: bool) -> &'static str {
+    if success {
+        "success"
+    } else {
+        "error"
+    }
+}
+
+impl Metrics {
+    fn registry(&self) -> std::sync::MutexGuard<'_, Registry> {
+        self.registry.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Record a fetch from a data source and how long it took
+    pub fn record_fetch(&self, source: &str, protocol: &str, elapsed: Duration, success: bool) {
+        let key = (source.to_string(), protocol.to_string());
+        let mut registry = self.registry();
+        *registry
+            .fetches
+            .entry((key.clone(), outcome(success)))
+            .or_default() += 1;
+        registry
+            .fetch_duration
+            .entry(key)
+            .or_default()
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Record the records of a fetch that were stored or quarantined
+    pub fn record_ingested(&self, source: &str, protocol: &str, stored: usize, quarantined: usize) {
+        let key = (source.to_string(), protocol.to_string());
+        let mut registry = self.registry();
+        *registry
+            .ingested
+            .entry((key.clone(), "stored"))
+            .or_default() += stored as u64;
+        *registry.ingested.entry((key, "quarantined")).or_default() += quarantined as u64;
+    }
+
+    /// Record a webhook delivery, after retries
+    pub fn record_delivery(&self, success: bool) {
+        *self
+            .registry()
+            .deliveries
+            .entry(outcome(success))
+            .or_default() += 1;
+    }
+
+    /// Record that a webhook was disabled after failing repeatedly
+    pub fn record_webhook_disabled(&self) {
+        self.registry().webhooks_disabled += 1;
+    }
+
+    /// Render all metrics in the Prometheus text format
+    pub fn render(&self, gauges: &Gauges) -> String {
+        let mut out = Exposition::default();
+        let registry = self.registry();
+
+        out.family(
+            "wei_indexer_source_fetches_total",
+            "Fetches from data sources by outcome",
+            "counter",
+        );
+        for (((source, protocol), outcome), count) in &registry.fetches {
+            out.sample(
+                "wei_indexer_source_fetches_total",
+                &[
+                    ("source", source),
+                    ("protocol", protocol),
+                    ("outcome", outcome),
+                ],
+                *count as f64,
+            );
+        }
+
+        out.family(
+            "wei_indexer_source_fetch_duration_seconds",
+            "Duration of fetches from data sources",
+            "histogram",
+        );
+        for ((source, protocol), histogram) in &registry.fetch_duration {
+            let labels = [("source", source.as_str()), ("protocol", protocol.as_str())];
+            let mut cumulative = 0;
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets) {
+                cumulative += count;
+                let bound = bound.to_string();
+                out.sample(
+                    "wei_indexer_source_fetch_duration_seconds_bucket",
+                    &[labels[0], labels[1], ("le", &bound)],
+                    cumulative as f64,
+                );
+            }
+            out.sample(
+                "wei_indexer_source_fetch_duration_seconds_bucket",
+                &[labels[0], labels[1], ("le", "+Inf")],
+                histogram.count as f64,
+            );
+            out.sample(
+                "wei_indexer_source_fetch_duration_seconds_sum",
+                &labels,
+                histogram.sum,
+            );
+            out.sample(
+                "wei_indexer_source_fetch_duration_seconds_count",
+                &labels,
+                histogram.count as f64,
+            );
+        }
+
+        out.family(
+            "wei_indexer_items_ingested_total",
+            "Fetched records stored or quarantined by the ingest pipeline",
+            "counter",
+        );
+        for (((source, protocol), outcome), count) in &registry.ingested {
+            out.sample(
+                "wei_indexer_items_ingested_total",
+                &[
+                    ("source", source),
+                    ("protocol", protocol),
+                    ("outcome", outcome),
+                ],
+                *count as f64,
+            );
+        }
+
+        out.family(
+            "wei_indexer_sync_lag_seconds",
+            "Seconds since the newest indexed proposal was created",
+            "gauge",
+        );
+        for ((source, protocol), lag) in &gauges.sync_lag {
+            out.sample(
+                "wei_indexer_sync_lag_seconds",
+                &[("source", source), ("protocol", protocol)],
+                *lag,
+            );
+        }
+
+        out.family(
+            "wei_indexer_webhook_deliveries_total",
+            "Webhook deliveries by outcome, after retries",
+            "counter",
+        );
+        for (outcome, count) in &registry.deliveries {
+            out.sample(
+                "wei_indexer_webhook_deliveries_total",
+                &[("outcome", outcome)],
+                *count as f64,
+            );
+        }
+        out.family(
+            "wei_indexer_webhooks_disabled_total",
+            "Webhooks disabled after failing repeatedly",
+            "counter",
+        );
+        out.sample(
+            "wei_indexer_webhooks_disabled_total",
+            &[],
+            registry.webhooks_disabled as f64,
+        );
+
+        out.family(
+            "wei_indexer_db_pool_connections",
+            "Database connections by state",
+            "gauge",
+        );
+        let idle = gauges.pool_idle as f64;
+        out.sample(
+            "wei_indexer_db_pool_connections",
+            &[("state", "active")],
+            (gauges.pool_size as f64 - idle).max(0.0),
+        );
+        out.sample(
+            "wei_indexer_db_pool_connections",
+            &[("state", "idle")],
+            idle,
+        );
+        out.family(
+            "wei_indexer_db_pool_max_connections",
+            "Largest number of database connections",
+            "gauge",
+        );
+        out.sample(
+            "wei_indexer_db_pool_max_connections",
+            &[],
+            gauges.pool_max as f64,
+        );
+
+        let host_counters: [HostCounter; 6] = [
+            ("requests", "HTTP requests sent, including retries", |h| {
+                h.requests
+            }),
+            (
+                "throttled",
+                "HTTP requests delayed by the local rate limit",
+                |h| h.throttled,
+            ),
+            ("rate_limited", "429 responses received", |h| h.rate_limited),
+            ("retries", "HTTP requests retried", |h| h.retries),
+            ("failures", "HTTP requests failed after all retries", |h| {
+                h.failures
+            }),
+            (
+                "rejected",
+                "HTTP requests rejected by an open circuit breaker",
+                |h| h.rejected,
+            ),
+        ];
+        for (name, help, value) in host_counters {
+            let name = format!("wei_indexer_http_{name}_total");
+            out.family(&name, help, "counter");
+            for host in &gauges.hosts {
+                out.sample(&name, &[("host", &host.host)], value(host) as f64);
+            }
+        }
+        out.family(
+            "wei_indexer_http_circuit_open",
+            "Whether the circuit breaker of a host rejects requests",
+            "gauge",
+        );
+        for host in &gauges.hosts {
+            out.sample(
+                "wei_indexer_http_circuit_open",
+                &[("host", &host.host)],
+                f64::from(u8::from(host.circuit == CircuitState::Open)),
+            );
+        }
+
+        out.0
+    }
+}
+
+/// Writer of the Prometheus text format
+#[derive(Default)]
+struct Exposition(String);
+
+impl Exposition {
+    fn family(&mut self, name: &str, help: &str, kind: &str) {
+        let _ = writeln!(self.0, "# HELP {name} {help}");
+        let _ = writeln!(self.0, "# TYPE {name} {kind}");
+    }
+
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) {
+        self.0.push_str(name);
+        if !labels.is_empty() {
+            let labels: Vec<String> = labels
+                .iter()
+                .map(|(label, value)| {
+                    let value = value
+                        .replace('\\', "\\\\")
+                        .replace('"', "\\\"")
+                        .replace('\n', "\\n");
+                    format!("{label}=\"{value}\"")
+                })
+                .collect();
+            let _ = write!(self.0, "{{{}}}", labels.join(","));
+        }
+        let _ = writeln!(self.0, " {value}");
+    }
+}
@@ -3,13 +3,67 @@
 //! This module contains the core business logic services for the indexer,
 //! including the main indexer service, data source abstractions, and webhook handling.
 
+/// API key authentication and request signing
+pub mod auth;
+/// Resumable backfills and reindexing from data sources
+pub mod backfill;
+/// Background crawling of the data sources
+pub mod crawler;
 /// Data source abstractions and implementations
 pub mod data_sources;
+/// Training dataset export
+pub mod dataset;
+/// Proposal embeddings and similarity search
+pub mod embeddings;
+/// In-process event bus and persistent event log
+pub mod events;
+/// Rate-limited HTTP client shared by the data sources
+pub mod http;
 /// Main indexer service implementation
 pub mod indexer;
+/// Human labeling and inter-annotator agreement
+pub mod labeling;
+/// Lease-based leader election between replicas
+pub mod leader;
+/// Timed proposal status transitions
+pub mod lifecycle;
+/// Prometheus metrics of the indexer
+pub mod metrics;
+/// Record normalization, validation and quarantine
+pub mod pipeline;
+/// Daily token prices and USD values
+pub mod prices;
+/// Near-duplicate and spam detection
+pub mod quality;
+/// Protocol registry loading and synchronization
+pub mod registry;
+/// JSON-RPC client of EVM nodes
+pub mod rpc;
+/// Periodic treasury balance snapshots
+pub mod treasury;
+/// Consistency checks of stored proposals
+pub mod verify;
 /// Webhook service for external notifications
 pub mod webhook;
+pub use auth::Authenticator;
+pub use backfill::Backfiller;
+pub use crawler::Crawler;
 #[allow(unused_imports)]
 pub use data_sources::DataSource;
+pub use dataset::DatasetExporter;
+pub use embeddings::{EmbeddingIndexer, SimilaritySearch};
+pub use events::{EventBus, EventLog};
+pub use http::HttpClient;
 #[allow(unused_imports)]
 pub use indexer::IndexerService;
+pub use leader::LeaderElection;
+pub use lifecycle::{LifecycleEngine, LifecycleScheduler};
+pub use metrics::Metrics;
+pub use pipeline::{IngestOutcome, IngestPipeline};
+pub use prices::{PriceService, PriceSync};
+pub use quality::QualityAssessor;
+pub use registry::RegistryService;
+pub use rpc::RpcClient;
+pub use treasury::TreasuryTracker;
+pub use verify::Verifier;
+pub use webhook::WebhookService;
@@ -0,0 +1,664 @@
+//! Ingestion pipeline
+//!
+//! Every raw record returned by a data source is normalized into a
+//! [`Proposal`](crate::models::Proposal) by a source-specific normalizer and
+//! then validated against the protocol registry. Records passing both steps are stored; any failure
+//! sends the raw payload to quarantine together with the rules that failed,
+//! where it can be inspected, fixed and re-ingested.
+
+use chrono::{DateTime, TimeZone, Utc};
+use serde_json::Value;
+use tracing::{debug, error, warn};
+use url::Url;
+
+use crate::{
+    config::ProtocolRegistry,
+    db::{
+        repositories::{
+            proposal::SaveOutcome, ProposalRepository, QuarantineRepository, TransferRepository,
+        },
+        Database,
+    },
+    models::{
+        proposal::{ProposalStatus, VotingType},
+        Proposal, ProposalTransfer, QuarantineStatus, RawRecord,
+    },
+    services::{
+        rpc::decode_transfer_calldata, EventBus, LifecycleScheduler, Metrics, QualityAssessor,
+    },
+    utils::{
+        id::ProposalId,
+        validation::{
+            validate_proposal, validate_protocol_id, validate_snapshot_space, ValidationError,
+            ValidationErrors, ValidationRule,
+        },
+    },
+};
+
+/// Result of ingesting a record
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IngestOutcome {
+    /// The record was stored as a proposal
+    Stored(ProposalId),
+    /// The record failed the pipeline and was quarantined
+    Quarantined {
+        /// Quarantine entry ID
+        id: i64,
+        /// Failures that caused the quarantine
+        errors: ValidationErrors,
+    },
+    /// The source hides or no longer has the proposal, which was removed
+    Removed(ProposalId),
+}
+
+/// Pipeline storing valid records and quarantining the others
+#[derive(Clone)]
+pub struct IngestPipeline {
+    db: Database,
+    events: EventBus,
+    lifecycle: LifecycleScheduler,
+    metrics: Metrics,
+}
+
+impl IngestPipeline {
+    /// Create a new ingestion pipeline
+    ///
+    /// Proposal events are published to `events` and the voting window of
+    /// stored proposals is handed to `lifecycle`.
+    pub fn new(db: Database, events: EventBus, lifecycle: LifecycleScheduler) -> Self {
+        Self {
+            db,
+            events,
+            lifecycle,
+            metrics: Metrics::default(),
+        }
+    }
+
+    /// Record fetches and ingested records in the given metrics
+    pub fn with_metrics(mut self, metrics: Metrics) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Metrics fetches and ingested records are recorded in
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    /// Store a valid proposal and publish the resulting events
+    ///
+    /// The ERC-20 transfers among its executable calls are stored with it.
+    /// New and edited proposals are then assessed for near-duplicates and
+    /// spam. A failed assessment is only logged, as the proposal is stored;
+    /// the assessment backfill picks it up on the next start.
+    async fn store(&self, proposal: &Proposal, record: &RawRecord) -> Result<(), sqlx::Error> {
+        let mut proposal = proposal.clone();
+        proposal.status = proposal.status_at(Utc::now());
+
+        let saved = ProposalRepository::new(self.db.clone())
+            .save(&proposal)
+            .await?;
+
+        match &saved.content {
+            SaveOutcome::Created { .. } => debug!("Stored new proposal {}", proposal.id),
+            SaveOutcome::Updated {
+                revision,
+                changed_fields,
+            } => debug!(
+                "Proposal {} changed ({:?}), revision {}",
+                proposal.id, changed_fields, revision
+            ),
+            SaveOutcome::Unchanged => {}
+        }
+        if saved.status != proposal.status {
+            warn!(
+                "Ignoring invalid status transition of proposal {} from {:?} to {:?}",
+                proposal.id, saved.status, proposal.status
+            );
+            proposal.status = saved.status;
+        }
+        for event in saved.events {
+            self.events.publish(event);
+        }
+        self.lifecycle.schedule(&proposal);
+
+        if let Some(transfers) = decoded_transfers(record) {
+            TransferRepository::new(self.db.clone())
+                .replace(&proposal.id, &transfers)
+                .await?;
+        }
+
+        if saved.content != SaveOutcome::Unchanged {
+            if let Err(e) = QualityAssessor::new(self.db.clone())
+                .assess(&proposal, space_verified(record))
+                .await
+            {
+                error!("Failed to assess proposal {}: {}", proposal.id, e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Remove a proposal its source deleted or hid, returning whether it was stored
+    ///
+    /// The removal is recorded as a tombstone in the change log.
+    pub async fn remove(&self, id: &ProposalId) -> Result<bool, sqlx::Error> {
+        let removed = ProposalRepository::new(self.db.clone()).delete(id).await?;
+        if removed {
+            debug!("Removed proposal {} no longer published by its source", id);
+        }
+        Ok(removed)
+    }
+
+    /// Normalize, validate and store a record, quarantining it on failure
+    ///
+    /// Records the source hides remove their stored proposal instead.
+    pub async fn ingest(
+        &self,
+        record: &RawRecord,
+        registry: &ProtocolRegistry,
+    ) -> Result<IngestOutcome, sqlx::Error> {
+        if is_hidden(record) {
+            let native_id = record.native_id.as_deref().unwrap_or_default();
+            if let Ok(id) = ProposalId::new(record.protocol_id.clone(), &record.source, native_id) {
+                self.remove(&id).await?;
+                return Ok(IngestOutcome::Removed(id));
+            }
+        }
+
+        let quarantine = QuarantineRepository::new(self.db.clone());
+
+        match process(record, registry) {
+            Ok(proposal) => {
+                self.store(&proposal, record).await?;
+                // A previously quarantined version is superseded by this one
+                quarantine.resolve_matching(record, &proposal.id).await?;
+                Ok(IngestOutcome::Stored(proposal.id))
+            }
+            Err(errors) => {
+                let id = quarantine.quarantine(record, &errors).await?;
+                warn!(
+                    "Quarantined {} record {:?} of {} (entry {}): {}",
+                    record.source, record.native_id, record.protocol_id, id, errors
+                );
+                Ok(IngestOutcome::Quarantined { id, errors })
+            }
+        }
+    }
+
+    /// Run a quarantined record through the pipeline again
+    ///
+    /// Returns `None` if the entry does not exist or was already resolved.
+    /// A record failing again stays in quarantine with the new errors.
+    pub async fn reingest(
+        &self,
+        id: i64,
+        registry: &ProtocolRegistry,
+    ) -> Result<Option<Result<ProposalId, ValidationErrors>>, sqlx::Error> {
+        let quarantine = QuarantineRepository::new(self.db.clone());
+        let Some(entry) = quarantine.find_by_id(id).await? else {
+            return Ok(None);
+        };
+        if entry.status != QuarantineStatus::Pending {
+            return Ok(None);
+        }
+
+        let protocol_id = match entry.protocol_id.parse() {
+            Ok(protocol_id) => protocol_id,
+            Err(e) => {
+                let errors = single_error("protocol_id", ValidationRule::UnknownProtocol, e);
+                quarantine.record_failure(id, &errors).await?;
+                return Ok(Some(Err(errors)));
+            }
+        };
+        let record = RawRecord::new(&entry.source, protocol_id, entry.payload);
+
+        match process(&record, registry) {
+            Ok(proposal) => {
+                self.store(&proposal, &record).await?;
+                quarantine.resolve(id, &proposal.id).await?;
+                Ok(Some(Ok(proposal.id)))
+            }
+            Err(errors) => {
+                quarantine.record_failure(id, &errors).await?;
+                Ok(Some(Err(errors)))
+            }
+        }
+    }
+}
+
+/// ERC-20 transfers among the executable calls of a record
+///
+/// Returns `None` for records without executable calls, such as Snapshot
+/// proposals, and skips calls of other functions.
+pub fn decoded_transfers(record: &RawRecord) -> Option<Vec<ProposalTransfer>> {
+    let calls = record.payload.get("executableCalls")?.as_array()?;
+    let transfers = calls
+        .iter()
+        .enumerate()
+        .filter_map(|(index, call)| {
+            let token = call.get("target")?.as_str()?;
+            let (recipient, raw_amount) =
+                decode_transfer_calldata(call.get("calldata")?.as_str()?)?;
+            Some(ProposalTransfer {
+                call_index: index as u32,
+                token: token.to_ascii_lowercase(),
+                recipient,
+                raw_amount,
+                value: None,
+            })
+        })
+        .collect();
+    Some(transfers)
+}
+
+/// Whether the source hides the record, such as a flagged Snapshot proposal
+fn is_hidden(record: &RawRecord) -> bool {
+    record.payload.get("flagged").and_then(Value::as_bool) == Some(true)
+}
+
+/// Whether the Snapshot space of a record is verified, if the record says
+fn space_verified(record: &RawRecord) -> Option<bool> {
+    record
+        .payload
+        .pointer("/space/verified")
+        .and_then(Value::as_bool)
+}
+
+/// Normalize and validate a record without storing it
+pub fn process(
+    record: &RawRecord,
+    registry: &ProtocolRegistry,
+) -> Result<Proposal, ValidationErrors> {
+    let mut proposal = normalize(record, registry)?;
+    validate_proposal(&mut proposal, registry)?;
+    Ok(proposal)
+}
+
+/// Normalize a raw record into a proposal using its source's normalizer
+pub fn normalize(
+    record: &RawRecord,
+    registry: &ProtocolRegistry,
+) -> Result<Proposal, ValidationErrors> {
+    match record.source.as_str() {
+        "snapshot" => normalize_snapshot(record, registry),
+        "tally" => normalize_tally(record),
+        other => Err(single_error(
+            "source",
+            ValidationRule::UnknownSource,
+            format!("no normalizer for source '{other}'"),
+        )),
+    }
+}
+
+fn single_error(field: &str, rule: ValidationRule, message: impl ToString) -> ValidationErrors {
+    ValidationErrors {
+        errors: vec![ValidationError::new(field, rule, message.to_string())],
+    }
+}
+
+/// Accessor for payload fields recording failures under their JSON path
+struct Fields<'a> {
+    payload: &'a Value,
+    errors: ValidationErrors,
+}
+
+impl<'a> Fields<'a> {
+    fn new(payload: &'a Value) -> Self {
+        Self {
+            payload,
+            errors: ValidationErrors::default(),
+        }
+    }
+
+    fn get(&self, path: &str) -> Option<&'a Value> {
+        path.split('.')
+            .try_fold(self.payload, |value, key| value.get(key))
+            .filter(|value| !value.is_null())
+    }
+
+    fn malformed(&mut self, path: &str, expected: &str) {
+        self.errors.push(ValidationError::new(
+            path,
+            ValidationRule::Malformed,
+            format!("expected {expected}"),
+        ));
+    }
+
+    fn string(&mut self, path: &str) -> String {
+        match self.get(path).and_then(Value::as_str) {
+            Some(value) => value.to_string(),
+            None => {
+                self.malformed(path, "a string");
+                String::new()
+            }
+        }
+    }
+
+    fn optional_string(&mut self, path: &str) -> Option<String> {
+        self.get(path)?;
+        Some(self.string(path))
+    }
+
+    fn strings(&mut self, path: &str) -> Vec<String> {
+        let values = self.get(path).and_then(Value::as_array).and_then(|values| {
+            values
+                .iter()
+                .map(|value| value.as_str().map(str::to_string))
+                .collect::<Option<Vec<_>>>()
+        });
+
+        values.unwrap_or_else(|| {
+            self.malformed(path, "an array of strings");
+            Vec::new()
+        })
+    }
+
+    fn unix_timestamp(&mut self, path: &str) -> Option<DateTime<Utc>> {
+        let timestamp = self
+            .get(path)
+            .and_then(Value::as_i64)
+            .and_then(|secs| Utc.timestamp_opt(secs, 0).single());
+        if timestamp.is_none() {
+            self.malformed(path, "a unix timestamp");
+        }
+        timestamp
+    }
+
+    fn rfc3339_timestamp(&mut self, path: &str) -> Option<DateTime<Utc>> {
+        let timestamp = self
+            .get(path)
+            .and_then(Value::as_str)
+            .and_then(|value| DateTime::parse_from_rfc3339(value).ok())
+            .map(|value| value.with_timezone(&Utc));
+        if timestamp.is_none() {
+            self.malformed(path, "an RFC 3339 timestamp");
+        }
+        timestamp
+    }
+
+    fn optional_unix_timestamp(&mut self, path: &str) -> Option<DateTime<Utc>> {
+        self.get(path)?;
+        self.unix_timestamp(path)
+    }
+
+    fn optional_rfc3339_timestamp(&mut self, path: &str) -> Option<DateTime<Utc>> {
+        self.get(path)?;
+        self.rfc3339_timestamp(path)
+    }
+
+    /// Number given either as a JSON number or a decimal string
+    fn optional_number(&mut self, path: &str) -> Option<f64> {
+        let number = as_number(self.get(path)?);
+        if number.is_none() {
+            self.malformed(path, "a number");
+        }
+        number
+    }
+
+    fn optional_block(&mut self, path: &str) -> Option<u64> {
+        let value = self.get(path)?;
+        let block = value
+            .as_u64()
+            .or_else(|| value.as_str().and_then(|s| s.parse().ok()));
+        if block.is_none() {
+            self.malformed(path, "a block number");
+        }
+        block
+    }
+
+    fn numbers(&mut self, path: &str) -> Vec<f64> {
+        let Some(value) = self.get(path) else {
+            return Vec::new();
+        };
+        let numbers = value
+            .as_array()
+            .and_then(|values| values.iter().map(as_number).collect::<Option<Vec<_>>>());
+
+        numbers.unwrap_or_else(|| {
+            self.malformed(path, "an array of numbers");
+            Vec::new()
+        })
+    }
+
+    /// Non-empty string, or `None` if missing or empty
+    fn non_empty_string(&mut self, path: &str) -> Option<String> {
+        self.optional_string(path).filter(|value| !value.is_empty())
+    }
+
+    /// HTTP(S) link entered by proposal authors
+    ///
+    /// Links are informational, so invalid ones are dropped rather than
+    /// quarantining the whole record.
+    fn optional_url(&mut self, path: &str) -> Option<String> {
+        let value = self.non_empty_string(path)?;
+        match Url::parse(&value) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => Some(value),
+            _ => {
+                debug!("Ignoring invalid {path} link {value:?}");
+                None
+            }
+        }
+    }
+
+    fn proposal_id(&mut self, record: &RawRecord) -> Option<ProposalId> {
+        let native_id = record.native_id.as_deref().unwrap_or_default();
+        match ProposalId::new(record.protocol_id.clone(), &record.source, native_id) {
+            Ok(id) => Some(id),
+            Err(e) => {
+                self.errors.push(ValidationError::new(
+                    "id",
+                    ValidationRule::Malformed,
+                    e.to_string(),
+                ));
+                None
+            }
+        }
+    }
+}
+
+fn as_number(value: &Value) -> Option<f64> {
+    value
+        .as_f64()
+        .or_else(|| value.as_str().and_then(|s| s.parse().ok()))
+}
+
+/// Normalize a Snapshot GraphQL `proposal` object
+fn normalize_snapshot(
+    record: &RawRecord,
+    registry: &ProtocolRegistry,
+) -> Result<Proposal, ValidationErrors> {
+    let mut fields = Fields::new(&record.payload);
+
+    let id = fields.proposal_id(record);
+    let title = fields.string("title");
+    let description = fields.string("body");
+    let choices = fields.strings("choices");
+    let author = fields.string("author");
+    let state = fields.string("state");
+    let created_at = fields.unix_timestamp("created");
+    let updated_at = fields
+        .get("updated")
+        .and_then(|_| fields.unix_timestamp("updated"));
+    let space = fields.string("space.id");
+
+    // The space must belong to the protocol the record was crawled for
+    if let Ok(entry) = validate_protocol_id("protocol_id", &record.protocol_id, registry) {
+        if let Err(e) = validate_snapshot_space("space.id", &space, entry) {
+            fields.errors.push(e);
+        }
+    }
+
+    let voting_start = fields.optional_unix_timestamp("start");
+    let voting_end = fields.optional_unix_timestamp("end");
+    let snapshot_block = fields.optional_block("snapshot");
+    // Spaces without a quorum report 0
+    let quorum = fields
+        .optional_number("quorum")
+        .filter(|quorum| *quorum > 0.0);
+    let voting_type = fields.optional_string("type").and_then(|value| {
+        value.parse().ok().or_else(|| {
+            fields.malformed("type", "a Snapshot voting type");
+            None
+        })
+    });
+    let scores = fields.numbers("scores");
+    let scores_total = fields.optional_number("scores_total");
+    let discussion_url = fields.optional_url("discussion");
+    let ipfs_hash = fields.non_empty_string("ipfs");
+    let source_url = fields.optional_url("link").or_else(|| {
+        let native_id = record.native_id.as_deref()?;
+        Some(format!(
+            "https://snapshot.org/#/{space}/proposal/{native_id}"
+        ))
+    });
+
+    let status = match state.as_str() {
+        "pending" => Some(ProposalStatus::Pending),
+        "active" => Some(ProposalStatus::Active),
+        "closed" => {
+            // Snapshot has no outcome; it is derived from the scores
+            if scores.is_empty() {
+                fields.malformed("scores", "an array of numbers for a closed proposal");
+                None
+            } else {
+                Some(ProposalStatus::Rejected)
+            }
+        }
+        _ => {
+            fields.malformed("state", "one of pending, active or closed");
+            None
+        }
+    };
+
+    match (id, status, created_at, fields.errors.is_empty()) {
+        (Some(id), Some(status), Some(created_at), true) => {
+            let mut proposal = Proposal {
+                protocol_id: id.protocol_id().clone(),
+                id,
+                title,
+                description,
+                status,
+                choices,
+                author,
+                comments: Vec::new(),
+                created_at,
+                updated_at: updated_at.unwrap_or(created_at),
+                voting_start,
+                voting_end,
+                snapshot_block,
+                quorum,
+                voting_type: voting_type.unwrap_or_default(),
+                scores,
+                scores_total,
+                discussion_url,
+                ipfs_hash,
+                source_url,
+                is_duplicate_of: None,
+                spam_score: None,
+            };
+            if state == "closed" {
+                proposal.status = proposal.outcome().unwrap_or(ProposalStatus::Rejected);
+            }
+            Ok(proposal)
+        }
+        _ => Err(fields.errors),
+    }
+}
+
+/// Governor proposals always offer the same choices
+const GOVERNOR_CHOICES: [&str; 3] = ["For", "Against", "Abstain"];
+
+/// Normalize a Tally GraphQL `proposal` object
+fn normalize_tally(record: &RawRecord) -> Result<Proposal, ValidationErrors> {
+    let mut fields = Fields::new(&record.payload);
+
+    let id = fields.proposal_id(record);
+    let title = fields.string("metadata.title");
+    let description = fields.string("metadata.description");
+    let author = fields.string("proposer.address");
+    let created_at = fields.rfc3339_timestamp("createdAt");
+    let status = fields
+        .optional_string("status")
+        .map(|status| status.to_ascii_lowercase());
+    let status = match status.as_deref() {
+        Some("pending") => Some(ProposalStatus::Pending),
+        Some("active") => Some(ProposalStatus::Active),
+        Some("succeeded" | "queued") => Some(ProposalStatus::Accepted),
+        Some("defeated" | "expired") => Some(ProposalStatus::Rejected),
+        Some("executed" | "crosschainexecuted") => Some(ProposalStatus::Executed),
+        Some("canceled" | "cancelled") => Some(ProposalStatus::Cancelled),
+        _ => {
+            fields.malformed("status", "a Governor proposal status");
+            None
+        }
+    };
+
+    let voting_start = fields.optional_rfc3339_timestamp("start.timestamp");
+    let voting_end = fields.optional_rfc3339_timestamp("end.timestamp");
+    // Voting power is measured at the block voting starts
+    let snapshot_block = fields.optional_block("start.number");
+    let quorum = fields.optional_number("quorum");
+    let scores = match fields.get("voteStats") {
+        None => Vec::new(),
+        Some(stats) => governor_scores(stats).unwrap_or_else(|| {
+            fields.malformed("voteStats", "a list of vote types and counts");
+            Vec::new()
+        }),
+    };
+    let scores_total = (!scores.is_empty()).then(|| scores.iter().sum());
+    let discussion_url = fields.optional_url("metadata.discourseURL");
+    let ipfs_hash = fields.non_empty_string("metadata.ipfsHash");
+    let source_url = fields
+        .get("organization.slug")
+        .and_then(Value::as_str)
+        .zip(record.native_id.as_deref())
+        .map(|(slug, native_id)| format!("https://www.tally.xyz/gov/{slug}/proposal/{native_id}"));
+
+    match (id, status, created_at, fields.errors.is_empty()) {
+        (Some(id), Some(status), Some(created_at), true) => Ok(Proposal {
+            protocol_id: id.protocol_id().clone(),
+            id,
+            title,
+            description,
+            status,
+            choices: GOVERNOR_CHOICES.iter().map(|c| c.to_string()).collect(),
+            author,
+            comments: Vec::new(),
+            created_at,
+            updated_at: created_at,
+            voting_start,
+            voting_end,
+            snapshot_block,
+            quorum,
+            voting_type: VotingType::Basic,
+            scores,
+            scores_total,
+            discussion_url,
+            ipfs_hash,
+            source_url,
+            is_duplicate_of: None,
+            spam_score: None,
+        }),
+        _ => Err(fields.errors),
+    }
+}
+
+/// For, Against and Abstain totals from Tally `voteStats`
+///
+/// Pending vote types, cast but not yet counted on-chain, are ignored.
+fn governor_scores(stats: &Value) -> Option<Vec<f64>> {
+    let mut scores = vec![0.0; GOVERNOR_CHOICES.len()];
+    for stat in stats.as_array()? {
+        let count = as_number(stat.get("votesCount")?)?;
+        let choice = stat.get("type")?.as_str()?.to_ascii_lowercase();
+        if let Some(i) = GOVERNOR_CHOICES
+            .iter()
+            .position(|c| c.eq_ignore_ascii_case(&choice))
+        {
+            scores[i] = count;
+        }
+    }
+    Some(scores)
+}
@@ -0,0 +1,591 @@
+//! Daily token prices and USD values of token amounts
+//!
+//! The registry lists the tracked tokens of each chain with the ID of their
+//! asset at the price provider. A pluggable
+//! [`PriceProvider`](crate::services::prices::PriceProvider) supplies daily
+//! USD prices, which the [`PriceSync`](crate::services::prices::PriceSync)
+//! task stores in the background and which can also be imported from CSV
+//! files. The [`PriceService`](crate::services::prices::PriceService) values
+//! token amounts at the price of the day they refer to.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{anyhow, ensure, Context};
+use async_trait::async_trait;
+use chrono::{DateTime, Days, NaiveDate, NaiveTime, Utc};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+use crate::{
+    config::{PriceConfig, PriceProviderKind, ProtocolRegistry, TokenEntry},
+    db::{repositories::PriceRepository, Database},
+    models::{DailyPrice, ProposalTransfer, TokenValue, Treasury},
+    services::{
+        http::HttpClient,
+        leader::{HeldLeases, LeaderElection},
+        registry::RegistryService,
+        rpc::scale_balance,
+    },
+};
+
+/// Most days a price is used after its day
+///
+/// Amounts dated later than that are not valued, rather than valued at a
+/// stale price.
+pub const MAX_PRICE_AGE_DAYS: u64 = 7;
+
+/// Lease key of the price sync, held by one replica at a time
+pub const PRICES_LEASE_KEY: &str = "prices";
+
+/// Header of the API key of the CoinGecko demo API
+const COINGECKO_DEMO_KEY_HEADER: &str = "x-cg-demo-api-key";
+
+/// Header of the API key of the CoinGecko Pro API
+const COINGECKO_PRO_KEY_HEADER: &str = "x-cg-pro-api-key";
+
+/// Errors raised when valuing token amounts
+#[derive(Error, Debug)]
+pub enum PriceError {
+    /// The token is not listed in the registry
+    #[error("Unknown token {token} on chain {chain_id}")]
+    UnknownToken {
+        /// EVM chain ID
+        chain_id: u64,
+        /// Symbol or address the token was looked up by
+        token: String,
+    },
+    /// No price of the token is recent enough
+    #[error("No price of {asset} within {MAX_PRICE_AGE_DAYS} days up to {day}")]
+    NoPrice {
+        /// ID of the asset at the price provider
+        asset: String,
+        /// Day the amount was valued at
+        day: NaiveDate,
+    },
+    /// The prices could not be loaded
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+}
+
+/// Source of daily USD prices
+#[async_trait]
+pub trait PriceProvider: Send + Sync {
+    /// Name of the provider, stored as the source of its prices
+    fn name(&self) -> &str;
+
+    /// Daily prices of an asset from `from` to `to`, both included
+    ///
+    /// Days the provider has no price for are left out.
+    async fn daily_prices(
+        &self,
+        asset: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> anyhow::Result<Vec<DailyPrice>>;
+}
+
+/// Create the provider selected by the configuration, if any
+pub fn price_provider_from_config(
+    config: &PriceConfig,
+    http: HttpClient,
+) -> Option<Arc<dyn PriceProvider>> {
+    match config.provider? {
+        PriceProviderKind::Coingecko => Some(Arc::new(CoinGeckoPriceProvider::new(
+            http,
+            &config.base_url,
+            config.api_key.clone(),
+        ))),
+        PriceProviderKind::Csv => config
+            .csv_path
+            .clone()
+            .map(|path| Arc::new(CsvPriceProvider::new(path)) as Arc<dyn PriceProvider>),
+        PriceProviderKind::Mock => Some(Arc::new(MockPriceProvider)),
+    }
+}
+
+/// Provider for the CoinGecko `market_chart/range` endpoint
+///
+/// Assets are CoinGecko coin IDs. The last price CoinGecko reports for a day
+/// is used as the day's price.
+pub struct CoinGeckoPriceProvider {
+    http: HttpClient,
+    base_url: String,
+    api_key: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct MarketChart {
+    /// Pairs of a timestamp in milliseconds and a USD price
+    prices: Vec<(f64, f64)>,
+}
+
+impl CoinGeckoPriceProvider {
+    /// Name of the provider
+    pub const NAME: &'static str = "coingecko";
+
+    /// Create a provider for the API at `base_url`, e.g. `https://api.coingecko.com/api/v3`
+    pub fn new(http: HttpClient, base_url: &str, api_key: Option<String>) -> Self {
+        Self {
+            http,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+        }
+    }
+}
+
+#[async_trait]
+impl PriceProvider for CoinGeckoPriceProvider {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    async fn daily_prices(
+        &self,
+        asset: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> anyhow::Result<Vec<DailyPrice>> {
+        let midnight = |day: NaiveDate| day.and_time(NaiveTime::MIN).and_utc().timestamp();
+        let (start, end) = (midnight(from), midnight(to + Days::new(1)) - 1);
+        let url = format!("{}/coins/{}/market_chart/range", self.base_url, asset);
+        let mut request = self.http.get(&url).query(&[
+            ("vs_currency", "usd".to_string()),
+            ("from", start.to_string()),
+            ("to", end.to_string()),
+        ]);
+        if let Some(api_key) = &self.api_key {
+            let header = if self.base_url.contains("pro-api.") {
+                COINGECKO_PRO_KEY_HEADER
+            } else {
+                COINGECKO_DEMO_KEY_HEADER
+            };
+            request = request.header(header, api_key);
+        }
+
+        let chart: MarketChart = self
+            .http
+            .send(request)
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        // Points are in time order, so later ones replace earlier ones of the same day
+        let mut days = BTreeMap::new();
+        for (timestamp, price) in chart.prices {
+            let Some(time) = DateTime::from_timestamp_millis(timestamp as i64) else {
+                continue;
+            };
+            let day = time.date_naive();
+            if (from..=to).contains(&day) && price.is_finite() {
+                days.insert(day, price);
+            }
+        }
+        Ok(days
+            .into_iter()
+            .map(|(day, price_usd)| DailyPrice {
+                asset: asset.to_string(),
+                day,
+                price_usd,
+                source: Self::NAME.to_string(),
+            })
+            .collect())
+    }
+}
+
+/// Provider reading a `date,asset,price_usd` CSV file
+///
+/// The file is read again on every sync, so prices added to it are picked up
+/// without a restart.
+pub struct CsvPriceProvider {
+    path: PathBuf,
+}
+
+impl CsvPriceProvider {
+    /// Name of the provider
+    pub const NAME: &'static str = "csv";
+
+    /// Create a provider for the file at `path`
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Parse the rows of a CSV file of daily prices
+    ///
+    /// Rows are `date,asset,price_usd`, with dates as `YYYY-MM-DD`. A header
+    /// row, blank lines and lines starting with `#` are skipped.
+    pub fn parse(contents: &str) -> anyhow::Result<Vec<DailyPrice>> {
+        let mut prices = Vec::new();
+        for (i, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line
+                .split(',')
+                .map(|field| field.trim().trim_matches('"'))
+                .collect();
+            if prices.is_empty() && fields == ["date", "asset", "price_usd"] {
+                continue;
+            }
+
+            let line = i + 1;
+            let [date, asset, price] = fields[..] else {
+                return Err(anyhow!(
+                    "Line {line}: expected 3 fields date,asset,price_usd, found {}",
+                    fields.len()
+                ));
+            };
+            let day = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map_err(|_| anyhow!("Line {line}: invalid date {date}, expected YYYY-MM-DD"))?;
+            ensure!(!asset.is_empty(), "Line {line}: asset cannot be empty");
+            let price_usd: f64 = price
+                .parse()
+                .ok()
+                .filter(|price: &f64| price.is_finite() && *price >= 0.0)
+                .ok_or_else(|| anyhow!("Line {line}: invalid price {price}"))?;
+
+            prices.push(DailyPrice {
+                asset: asset.to_string(),
+                day,
+                price_usd,
+                source: Self::NAME.to_string(),
+            });
+        }
+        Ok(prices)
+    }
+
+    /// Read and parse a CSV file of daily prices
+    pub async fn read(path: &Path) -> anyhow::Result<Vec<DailyPrice>> {
+        let contents = tokio::fs::read_to_string(path)
+            .await
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&contents).with_context(|| format!("Invalid price file {}", path.display()))
+    }
+}
+
+#[async_trait]
+impl PriceProvider for CsvPriceProvider {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    async fn daily_prices(
+        &self,
+        asset: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> anyhow::Result<Vec<DailyPrice>> {
+        Ok(Self::read(&self.path)
+            .await?
+            .into_iter()
+            .filter(|price| price.asset == asset && (from..=to).contains(&price.day))
+            .collect())
+    }
+}
+
+/// Deterministic provider for tests and local development
+///
+/// Every asset gets a base price between 1 and 1,000 USD from a hash of its
+/// ID, which moves by up to 5% from day to day.
+pub struct MockPriceProvider;
+
+impl MockPriceProvider {
+    /// Name of the provider
+    pub const NAME: &'static str = "mock";
+
+    /// Price of an asset on a day, in USD rounded to cents
+    pub fn price(asset: &str, day: NaiveDate) -> f64 {
+        let hash = |text: &str| {
+            let digest = Sha256::digest(text.as_bytes());
+            u64::from_be_bytes(digest[..8].try_into().expect("8 bytes"))
+        };
+        let base = 1.0 + (hash(asset) % 99_900) as f64 / 100.0;
+        let change = (hash(&format!("{asset}:{day}")) % 1001) as f64 / 10_000.0 - 0.05;
+        (base * (1.0 + change) * 100.0).round() / 100.0
+    }
+}
+
+#[async_trait]
+impl PriceProvider for MockPriceProvider {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    async fn daily_prices(
+        &self,
+        asset: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> anyhow::Result<Vec<DailyPrice>> {
+        Ok(from
+            .iter_days()
+            .take_while(|day| *day <= to)
+            .map(|day| DailyPrice {
+                asset: asset.to_string(),
+                day,
+                price_usd: Self::price(asset, day),
+                source: Self::NAME.to_string(),
+            })
+            .collect())
+    }
+}
+
+/// Value an amount of a token at its latest price on or before `day`
+///
+/// Fails if the price is more than [`MAX_PRICE_AGE_DAYS`] older than `day`.
+pub fn token_value(
+    token: &TokenEntry,
+    amount: f64,
+    price: Option<&DailyPrice>,
+    day: NaiveDate,
+) -> Result<TokenValue, PriceError> {
+    let price = price
+        .filter(|price| price.day <= day && price.day + Days::new(MAX_PRICE_AGE_DAYS) >= day)
+        .ok_or_else(|| PriceError::NoPrice {
+            asset: token.price_id.clone(),
+            day,
+        })?;
+    Ok(TokenValue {
+        chain_id: token.chain_id,
+        token: token.address.clone(),
+        symbol: token.symbol.clone(),
+        amount,
+        price_usd: price.price_usd,
+        price_date: price.day,
+        value_usd: amount * price.price_usd,
+    })
+}
+
+/// Registry token of a treasury balance, by address or as the native currency
+fn balance_token<'a>(
+    registry: &'a ProtocolRegistry,
+    chain_id: u64,
+    token: Option<&str>,
+) -> Option<&'a TokenEntry> {
+    match token {
+        Some(address) => registry.resolve_token(chain_id, address),
+        None => registry.native_token(chain_id),
+    }
+}
+
+/// Handle valuing token amounts in USD
+#[derive(Clone)]
+pub struct PriceService {
+    db: Database,
+    registry: RegistryService,
+}
+
+impl PriceService {
+    /// Create a price service over the stored prices
+    pub fn new(db: Database, registry: RegistryService) -> Self {
+        Self { db, registry }
+    }
+
+    /// Value an amount of a token at the price of the day of `at`
+    ///
+    /// The token is a registry token of the chain, given by its symbol or
+    /// contract address.
+    pub async fn value_usd(
+        &self,
+        chain_id: u64,
+        token: &str,
+        amount: f64,
+        at: DateTime<Utc>,
+    ) -> Result<TokenValue, PriceError> {
+        let registry = self.registry.current();
+        let entry =
+            registry
+                .resolve_token(chain_id, token)
+                .ok_or_else(|| PriceError::UnknownToken {
+                    chain_id,
+                    token: token.to_string(),
+                })?;
+        let day = at.date_naive();
+        let price = PriceRepository::new(self.db.clone())
+            .at(&entry.price_id, day)
+            .await?;
+        token_value(entry, amount, price.as_ref(), day)
+    }
+
+    /// Fill in the USD values of a treasury's balances and history
+    ///
+    /// Every snapshot is valued at the prices of its own day. Balances of
+    /// tokens without a recent price keep no value.
+    pub async fn value_treasury(
+        &self,
+        chain_id: u64,
+        treasury: &mut Treasury,
+    ) -> Result<(), sqlx::Error> {
+        let registry = self.registry.current();
+        let repository = PriceRepository::new(self.db.clone());
+        let mut prices = HashMap::new();
+
+        let amounts = treasury
+            .balances
+            .iter_mut()
+            .map(|balance| {
+                (
+                    balance.token.as_deref(),
+                    balance.balance,
+                    balance.recorded_at,
+                    &mut balance.value_usd,
+                )
+            })
+            .chain(treasury.history.iter_mut().map(|total| {
+                (
+                    total.token.as_deref(),
+                    total.balance,
+                    total.recorded_at,
+                    &mut total.value_usd,
+                )
+            }));
+        for (token, amount, at, value_usd) in amounts {
+            let Some(entry) = balance_token(&registry, chain_id, token) else {
+                continue;
+            };
+            let day = at.date_naive();
+            let key = (entry.price_id.clone(), day);
+            if !prices.contains_key(&key) {
+                let price = repository.at(&entry.price_id, day).await?;
+                prices.insert(key.clone(), price);
+            }
+            *value_usd = token_value(entry, amount, prices[&key].as_ref(), day)
+                .ok()
+                .map(|value| value.value_usd);
+        }
+        Ok(())
+    }
+
+    /// Fill in the USD values of transfers at the price of the day of `at`
+    ///
+    /// Amounts are scaled by the decimals of the registry token. Transfers
+    /// of tokens missing from the registry or without a recent price keep
+    /// no value.
+    pub async fn value_transfers(
+        &self,
+        chain_id: u64,
+        transfers: &mut [ProposalTransfer],
+        at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        let registry = self.registry.current();
+        for transfer in transfers {
+            let Some(entry) = registry.resolve_token(chain_id, &transfer.token) else {
+                continue;
+            };
+            let amount = scale_balance(&transfer.raw_amount, entry.decimals);
+            transfer.value = match self.value_usd(chain_id, &transfer.token, amount, at).await {
+                Ok(value) => Some(value),
+                Err(PriceError::Database(e)) => return Err(e),
+                Err(_) => None,
+            };
+        }
+        Ok(())
+    }
+
+    /// Fetch the prices of the registry's assets missing up to `today`
+    ///
+    /// Each asset is fetched from its latest stored day, which is fetched
+    /// again since its price may have been taken before the day ended.
+    /// Assets without prices get `history_days` of history. Returns the
+    /// number of prices stored.
+    pub async fn sync(
+        &self,
+        provider: &dyn PriceProvider,
+        today: NaiveDate,
+        history_days: u32,
+    ) -> Result<u64, sqlx::Error> {
+        let registry = self.registry.current();
+        let assets: BTreeSet<&str> = registry
+            .tokens
+            .iter()
+            .map(|token| token.price_id.as_str())
+            .collect();
+
+        let repository = PriceRepository::new(self.db.clone());
+        let mut stored = 0;
+        for asset in assets {
+            let from = match repository.latest_day(asset).await? {
+                Some(latest) => latest.min(today),
+                None => today - Days::new(history_days.into()),
+            };
+            match provider.daily_prices(asset, from, today).await {
+                Ok(prices) => stored += repository.upsert(&prices).await?,
+                Err(e) => warn!(
+                    "Failed to fetch prices of {} from {}: {:#}",
+                    asset,
+                    provider.name(),
+                    e
+                ),
+            }
+        }
+        Ok(stored)
+    }
+}
+
+/// Background task syncing prices on the replica holding the prices lease
+pub struct PriceSync {
+    service: PriceService,
+    provider: Arc<dyn PriceProvider>,
+    interval: Duration,
+    history_days: u32,
+    leases: HeldLeases,
+}
+
+impl PriceSync {
+    /// Start syncing prices every `sync_interval_secs` of the configuration in the background
+    pub fn spawn(
+        service: PriceService,
+        provider: Arc<dyn PriceProvider>,
+        leader: LeaderElection,
+        config: &PriceConfig,
+    ) -> JoinHandle<()> {
+        let sync = Self {
+            service,
+            provider,
+            interval: Duration::from_secs(config.sync_interval_secs),
+            history_days: config.history_days,
+            leases: HeldLeases::new(leader, "syncs"),
+        };
+        tokio::spawn(sync.run())
+    }
+
+    /// Run sync rounds forever
+    async fn run(mut self) {
+        let mut interval = tokio::time::interval(self.leases.leader().renew_interval());
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            self.round().await;
+        }
+    }
+
+    /// Renew the lease, then sync if this replica holds it and a sync is due
+    async fn round(&mut self) {
+        self.leases.renew(&[PRICES_LEASE_KEY.to_string()]).await;
+        if !self.leases.is_due(PRICES_LEASE_KEY) {
+            return;
+        }
+
+        let sync = self
+            .service
+            .sync(&*self.provider, Utc::now().date_naive(), self.history_days);
+        match self.leases.run(PRICES_LEASE_KEY, self.interval, sync).await {
+            Some(Ok(stored)) => info!(
+                "Stored {} token prices from {}",
+                stored,
+                self.provider.name()
+            ),
+            Some(Err(e)) => error!("Failed to sync token prices: {}", e),
+            None => {}
+        }
+    }
+}
@@ -0,0 +1,248 @@
+//! Near-duplicate and spam detection
+//!
+//! Stored proposals are fingerprinted and compared with proposals sharing a
+//! band key (see [`crate::utils::fingerprint`]). Near-duplicates form
+//! clusters across spaces, each pointing at its earliest proposal through
+//! `is_duplicate_of`. The spam score adds up the weights of heuristic
+//! signals, so it is easy to explain which signals fired.
+
+use serde::Serialize;
+use tracing::{error, info};
+
+use crate::{
+    db::{
+        repositories::{fingerprint::StoredFingerprint, FingerprintRepository, ProposalRepository},
+        Database,
+    },
+    models::Proposal,
+    utils::{
+        fingerprint::{is_link_only, Fingerprint},
+        id::ProposalId,
+    },
+};
+
+/// Number of proposals assessed per backfill query
+const BACKFILL_BATCH_SIZE: i64 = 100;
+
+/// Spam score weight of a proposal in an unverified Snapshot space
+const UNVERIFIED_SPACE_WEIGHT: f64 = 0.25;
+
+/// Spam score weight of a proposal without a description
+const EMPTY_BODY_WEIGHT: f64 = 0.3;
+
+/// Spam score weight of a description made of links only
+const LINK_ONLY_WEIGHT: f64 = 0.35;
+
+/// Spam score weight of a proposal by an author without earlier proposals
+const NEW_AUTHOR_WEIGHT: f64 = 0.15;
+
+/// Spam score weight of a near-duplicate of another protocol's proposal
+const CROSS_SPACE_DUPLICATE_WEIGHT: f64 = 0.45;
+
+/// Heuristic spam signals of a proposal
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct SpamSignals {
+    /// The Snapshot space is not verified
+    pub unverified_space: bool,
+    /// The description is empty
+    pub empty_body: bool,
+    /// The description consists of links only
+    pub link_only: bool,
+    /// The author has no earlier proposal
+    pub new_author: bool,
+    /// The proposal is a near-duplicate of another protocol's proposal
+    pub duplicate_of_other_space: bool,
+}
+
+impl SpamSignals {
+    /// Spam likelihood from 0 to 1, the capped sum of the signal weights
+    pub fn score(&self) -> f64 {
+        [
+            (self.unverified_space, UNVERIFIED_SPACE_WEIGHT),
+            (self.empty_body, EMPTY_BODY_WEIGHT),
+            (self.link_only, LINK_ONLY_WEIGHT),
+            (self.new_author, NEW_AUTHOR_WEIGHT),
+            (self.duplicate_of_other_space, CROSS_SPACE_DUPLICATE_WEIGHT),
+        ]
+        .into_iter()
+        .filter(|(fired, _)| *fired)
+        .fold(0.0, |score: f64, (_, weight)| score + weight)
+        .min(1.0)
+    }
+}
+
+/// Duplicate and spam assessment of a proposal
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assessment {
+    /// Earliest proposal of the near-duplicate cluster, if the proposal is a copy
+    pub is_duplicate_of: Option<ProposalId>,
+    /// Signals that fired
+    pub signals: SpamSignals,
+    /// Spam likelihood from 0 to 1
+    pub spam_score: f64,
+}
+
+/// Text of a proposal that is fingerprinted
+pub fn fingerprint_text(proposal: &Proposal) -> String {
+    format!("{}\n\n{}", proposal.title, proposal.description)
+}
+
+/// Assesses proposals for near-duplicates and spam
+#[derive(Clone)]
+pub struct QualityAssessor {
+    db: Database,
+}
+
+impl QualityAssessor {
+    /// Create a new assessor
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// Fingerprint a stored proposal and assess it
+    ///
+    /// `space_verified` is the verification status of the proposal's Snapshot
+    /// space, if known. Later proposals found to be near-duplicates are
+    /// assessed again, as the proposal may be the new original of their
+    /// cluster.
+    pub async fn assess(
+        &self,
+        proposal: &Proposal,
+        space_verified: Option<bool>,
+    ) -> Result<Assessment, sqlx::Error> {
+        let stored = StoredFingerprint {
+            fingerprint: Fingerprint::of(&fingerprint_text(proposal)),
+            space_verified,
+        };
+        FingerprintRepository::new(self.db.clone())
+            .save(&proposal.id, &stored)
+            .await?;
+
+        let (assessment, stale) = self.evaluate(proposal, &stored).await?;
+
+        let proposals = ProposalRepository::new(self.db.clone());
+        let fingerprints = FingerprintRepository::new(self.db.clone());
+        for proposal_id in stale {
+            let (Some(later), Some(stored)) = (
+                proposals.find_by_id(&proposal_id).await?,
+                fingerprints.find(&proposal_id).await?,
+            ) else {
+                continue;
+            };
+            self.evaluate(&later, &stored).await?;
+        }
+
+        Ok(assessment)
+    }
+
+    /// Assess a proposal against the stored fingerprints and store the result
+    ///
+    /// Also returns the later near-duplicates not pointing at the
+    /// proposal's cluster.
+    async fn evaluate(
+        &self,
+        proposal: &Proposal,
+        stored: &StoredFingerprint,
+    ) -> Result<(Assessment, Vec<ProposalId>), sqlx::Error> {
+        let proposals = ProposalRepository::new(self.db.clone());
+        let duplicates = match &stored.fingerprint {
+            Some(fingerprint) => FingerprintRepository::new(self.db.clone())
+                .candidates(&proposal.id, fingerprint)
+                .await?
+                .into_iter()
+                .filter(|candidate| candidate.fingerprint.is_near_duplicate(fingerprint))
+                .collect(),
+            None => Vec::new(),
+        };
+
+        // Candidates are sorted oldest first
+        let key = (proposal.created_at, proposal.id.to_string());
+        let is_duplicate_of = duplicates
+            .iter()
+            .find(|candidate| (candidate.created_at, candidate.proposal_id.to_string()) < key)
+            .map(|original| {
+                original
+                    .is_duplicate_of
+                    .clone()
+                    .filter(|root| *root != proposal.id)
+                    .unwrap_or_else(|| original.proposal_id.clone())
+            });
+        let root = is_duplicate_of.as_ref().unwrap_or(&proposal.id);
+        let stale = duplicates
+            .iter()
+            .filter(|candidate| (candidate.created_at, candidate.proposal_id.to_string()) > key)
+            .filter(|candidate| candidate.is_duplicate_of.as_ref() != Some(root))
+            .map(|candidate| candidate.proposal_id.clone())
+            .collect();
+
+        let signals = SpamSignals {
+            unverified_space: stored.space_verified == Some(false),
+            empty_body: proposal.description.trim().is_empty(),
+            link_only: is_link_only(&proposal.description),
+            new_author: !proposals
+                .has_earlier_by_author(&proposal.author, proposal.created_at)
+                .await?,
+            duplicate_of_other_space: is_duplicate_of
+                .as_ref()
+                .is_some_and(|original| original.protocol_id() != &proposal.protocol_id),
+        };
+        let spam_score = signals.score();
+        proposals
+            .set_quality(&proposal.id, is_duplicate_of.as_ref(), spam_score)
+            .await?;
+
+        Ok((
+            Assessment {
+                is_duplicate_of,
+                signals,
+                spam_score,
+            },
+            stale,
+        ))
+    }
+
+    /// Assess every proposal without a fingerprint of its latest revision
+    ///
+    /// This covers proposals stored before fingerprinting as well as new and
+    /// edited proposals whose assessment failed when they were ingested.
+    ///
+    /// Returns the number of proposals assessed.
+    pub async fn backfill(&self) -> Result<usize, sqlx::Error> {
+        let proposals = ProposalRepository::new(self.db.clone());
+        let fingerprints = FingerprintRepository::new(self.db.clone());
+        let mut cursor = None;
+        let mut assessed = 0;
+
+        loop {
+            let unassessed = fingerprints
+                .find_unassessed(cursor.as_ref(), BACKFILL_BATCH_SIZE)
+                .await?;
+            let Some(last) = unassessed.last().cloned() else {
+                break;
+            };
+
+            for proposal_id in &unassessed {
+                let Some(proposal) = proposals.find_by_id(proposal_id).await? else {
+                    continue;
+                };
+                match self.assess(&proposal, None).await {
+                    Ok(_) => assessed += 1,
+                    Err(e) => error!("Failed to assess proposal {}: {}", proposal_id, e),
+                }
+            }
+            cursor = Some(last);
+        }
+
+        Ok(assessed)
+    }
+
+    /// Assess the proposals left unassessed in the background
+    pub fn spawn_backfill(db: Database) {
+        tokio::spawn(async move {
+            match Self::new(db).backfill().await {
+                Ok(count) => info!("Assessed {} proposals for duplicates and spam", count),
+                Err(e) => error!("Failed to backfill proposal assessments: {}", e),
+            }
+        });
+    }
+}
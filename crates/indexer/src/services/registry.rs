@@ -0,0 +1,162 @@
+//! Protocol registry service
+//!
+//! Keeps the in-memory registry and the `protocols` table in sync with the
+//! registry file, reloading it when the file changes or on `SIGHUP`.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use tokio::sync::watch;
+use tracing::{error, info, warn};
+
+use crate::{
+    config::{ProtocolRegistry, RegistryConfig},
+    db::{repositories::ProtocolRepository, Database},
+};
+
+/// Service owning the currently active protocol registry
+#[derive(Clone)]
+pub struct RegistryService {
+    path: PathBuf,
+    db: Database,
+    sender: Arc<watch::Sender<Arc<ProtocolRegistry>>>,
+}
+
+impl RegistryService {
+    /// Load the registry file, synchronize it into the database and create the service
+    ///
+    /// Fails if the registry is invalid, so that a broken configuration is
+    /// caught at startup.
+    pub async fn init(config: &RegistryConfig, db: Database) -> anyhow::Result<Self> {
+        let registry = ProtocolRegistry::load(&config.path)?;
+        let service = Self {
+            path: config.path.clone(),
+            db,
+            sender: Arc::new(watch::channel(Arc::new(registry.clone())).0),
+        };
+
+        service.sync(&registry).await?;
+        Ok(service)
+    }
+
+    /// Currently active registry
+    pub fn current(&self) -> Arc<ProtocolRegistry> {
+        self.sender.borrow().clone()
+    }
+
+    /// Subscribe to registry changes
+    pub fn subscribe(&self) -> watch::Receiver<Arc<ProtocolRegistry>> {
+        self.sender.subscribe()
+    }
+
+    /// Reload the registry file
+    ///
+    /// An invalid file is rejected and the previous registry stays active.
+    /// Returns whether the registry changed.
+    pub async fn reload(&self) -> anyhow::Result<bool> {
+        let registry = ProtocolRegistry::load(&self.path)?;
+        if *self.current() == registry {
+            return Ok(false);
+        }
+
+        self.sync(&registry).await?;
+        self.sender.send_replace(Arc::new(registry));
+        Ok(true)
+    }
+
+    /// Spawn a background task reloading the registry when its file changes
+    pub fn spawn_watcher(&self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let service = self.clone();
+
+        tokio::spawn(async move {
+            let mut last_modified = modified_at(&service.path);
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            let mut hangup = Hangup::new();
+
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {
+                        let modified = modified_at(&service.path);
+                        if modified == last_modified {
+                            continue;
+                        }
+                        last_modified = modified;
+                    }
+                    _ = hangup.recv() => {
+                        info!("Received SIGHUP, reloading protocol registry");
+                    }
+                }
+
+                match service.reload().await {
+                    Ok(true) => info!("Protocol registry reloaded from {}", service.path.display()),
+                    Ok(false) => {}
+                    Err(e) => error!(
+                        "Failed to reload protocol registry, keeping previous version: {}",
+                        e
+                    ),
+                }
+            }
+        })
+    }
+
+    async fn sync(&self, registry: &ProtocolRegistry) -> anyhow::Result<()> {
+        let report = ProtocolRepository::new(self.db.clone())
+            .sync_registry(registry)
+            .await?;
+
+        info!(
+            "Synced {} protocols into the database ({} disabled)",
+            report.upserted, report.disabled
+        );
+        Ok(())
+    }
+}
+
+fn modified_at(path: &Path) -> Option<SystemTime> {
+    match std::fs::metadata(path).and_then(|metadata| metadata.modified()) {
+        Ok(modified) => Some(modified),
+        Err(e) => {
+            warn!("Failed to stat registry file {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// `SIGHUP` listener, pending forever on platforms without signals
+struct Hangup {
+    #[cfg(unix)]
+    signal: Option<tokio::signal::unix::Signal>,
+}
+
+impl Hangup {
+    fn new() -> Self {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{signal, SignalKind};
+
+            let signal = signal(SignalKind::hangup())
+                .map_err(|e| warn!("Failed to install SIGHUP handler: {}", e))
+                .ok();
+            Self { signal }
+        }
+
+        #[cfg(not(unix))]
+        Self {}
+    }
+
+    async fn recv(&mut self) {
+        #[cfg(unix)]
+        if let Some(signal) = self.signal.as_mut() {
+            if signal.recv().await.is_some() {
+                return;
+            }
+            self.signal = None;
+        }
+
+        std::future::pending::<()>().await
+    }
+}
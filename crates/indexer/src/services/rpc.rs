@@ -0,0 +1,270 @@
+//! JSON-RPC client of EVM nodes
+//!
+//! Reads native and ERC-20 balances from the node configured for a chain.
+//! Requests go through the shared [`HttpClient`], so nodes get the same
+//! retries and circuit breaking as the data sources.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use thiserror::Error;
+
+use crate::{
+    config::RpcEndpoint,
+    services::http::{HttpClient, HttpError},
+};
+
+/// Selector of the ERC-20 `balanceOf(address)` function
+pub const BALANCE_OF_SELECTOR: &str = "70a08231";
+
+/// Selector of the ERC-20 `transfer(address,uint256)` function
+pub const TRANSFER_SELECTOR: &str = "a9059cbb";
+
+/// Errors raised by JSON-RPC calls
+#[derive(Error, Debug)]
+pub enum RpcError {
+    /// No node is configured for the chain
+    #[error("No JSON-RPC node configured for chain {0}")]
+    UnknownChain(u64),
+    /// The request could not be sent
+    #[error(transparent)]
+    Http(#[from] HttpError),
+    /// The node answered with an error status
+    #[error("JSON-RPC node answered {0}")]
+    Status(u16),
+    /// The node answered with a JSON-RPC error
+    #[error("JSON-RPC error {code}: {message}")]
+    Rpc {
+        /// Error code
+        code: i64,
+        /// Error message
+        message: String,
+    },
+    /// An address argument is not a 20-byte hex address
+    #[error("Invalid address: {0}")]
+    InvalidAddress(String),
+    /// The response could not be decoded
+    #[error("Invalid JSON-RPC response: {0}")]
+    InvalidResponse(String),
+}
+
+impl From<reqwest::Error> for RpcError {
+    fn from(e: reqwest::Error) -> Self {
+        Self::InvalidResponse(e.to_string())
+    }
+}
+
+/// Body of a JSON-RPC response
+#[derive(Deserialize)]
+struct RpcResponse {
+    result: Option<Value>,
+    error: Option<RpcErrorObject>,
+}
+
+/// Error object of a JSON-RPC response
+#[derive(Deserialize)]
+struct RpcErrorObject {
+    code: i64,
+    message: String,
+}
+
+/// Client of the JSON-RPC nodes of the configured chains
+#[derive(Clone)]
+pub struct RpcClient {
+    http: HttpClient,
+    nodes: Arc<HashMap<u64, String>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl RpcClient {
+    /// Create a client for the given nodes
+    pub fn new(http: HttpClient, endpoints: &[RpcEndpoint]) -> Self {
+        Self {
+            http,
+            nodes: Arc::new(
+                endpoints
+                    .iter()
+                    .map(|endpoint| (endpoint.chain_id, endpoint.url.clone()))
+                    .collect(),
+            ),
+            next_id: Arc::new(AtomicU64::new(1)),
+        }
+    }
+
+    /// Whether a node is configured for the chain
+    pub fn supports(&self, chain_id: u64) -> bool {
+        self.nodes.contains_key(&chain_id)
+    }
+
+    /// Call a JSON-RPC method on the node of a chain, returning its result
+    pub async fn call(
+        &self,
+        chain_id: u64,
+        method: &str,
+        params: Value,
+    ) -> Result<Value, RpcError> {
+        let url = self
+            .nodes
+            .get(&chain_id)
+            .ok_or(RpcError::UnknownChain(chain_id))?;
+        let request = self.http.post(url).json(&json!({
+            "jsonrpc": "2.0",
+            "id": self.next_id.fetch_add(1, Ordering::Relaxed),
+            "method": method,
+            "params": params,
+        }));
+
+        let response = self.http.send(request).await?;
+        if !response.status().is_success() {
+            return Err(RpcError::Status(response.status().as_u16()));
+        }
+        let body: RpcResponse = response.json().await?;
+        if let Some(error) = body.error {
+            return Err(RpcError::Rpc {
+                code: error.code,
+                message: error.message,
+            });
+        }
+        body.result
+            .ok_or_else(|| RpcError::InvalidResponse(format!("{method} returned no result")))
+    }
+
+    /// Number of the latest block of a chain
+    pub async fn block_number(&self, chain_id: u64) -> Result<u64, RpcError> {
+        let result = self.call(chain_id, "eth_blockNumber", json!([])).await?;
+        parse_quantity(as_str(&result)?)
+    }
+
+    /// Native balance of an address at a block, in base units
+    pub async fn balance(
+        &self,
+        chain_id: u64,
+        address: &str,
+        block: u64,
+    ) -> Result<String, RpcError> {
+        let result = self
+            .call(
+                chain_id,
+                "eth_getBalance",
+                json!([address, format!("{block:#x}")]),
+            )
+            .await?;
+        hex_to_decimal(as_str(&result)?)
+    }
+
+    /// ERC-20 balance of an address at a block, in base units
+    pub async fn token_balance(
+        &self,
+        chain_id: u64,
+        token: &str,
+        holder: &str,
+        block: u64,
+    ) -> Result<String, RpcError> {
+        let result = self
+            .call(
+                chain_id,
+                "eth_call",
+                json!([
+                    { "to": token, "data": balance_of_calldata(holder)? },
+                    format!("{block:#x}")
+                ]),
+            )
+            .await?;
+        hex_to_decimal(as_str(&result)?)
+    }
+}
+
+fn as_str(value: &Value) -> Result<&str, RpcError> {
+    value
+        .as_str()
+        .ok_or_else(|| RpcError::InvalidResponse(format!("expected a hex string, got {value}")))
+}
+
+/// Call data of `balanceOf(holder)`
+pub fn balance_of_calldata(holder: &str) -> Result<String, RpcError> {
+    let address = holder.strip_prefix("0x").unwrap_or(holder);
+    if address.len() != 40 || !address.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(RpcError::InvalidAddress(holder.to_string()));
+    }
+    Ok(format!(
+        "0x{BALANCE_OF_SELECTOR}{:0>64}",
+        address.to_ascii_lowercase()
+    ))
+}
+
+/// Recipient and base unit amount of `transfer(recipient, amount)` call data
+///
+/// Returns `None` for calls of other functions and malformed arguments.
+/// The recipient is returned in lowercase and the amount as a decimal string.
+pub fn decode_transfer_calldata(calldata: &str) -> Option<(String, String)> {
+    let data = calldata.strip_prefix("0x").unwrap_or(calldata);
+    let arguments = data.strip_prefix(TRANSFER_SELECTOR)?;
+    if arguments.len() != 128 || !arguments.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let (recipient, amount) = arguments.split_at(64);
+    // Addresses are left-padded with zeros to a 32-byte word
+    let (padding, address) = recipient.split_at(24);
+    if padding.chars().any(|c| c != '0') {
+        return None;
+    }
+    Some((
+        format!("0x{}", address.to_ascii_lowercase()),
+        hex_to_decimal(amount).ok()?,
+    ))
+}
+
+/// Parse a hex quantity such as a block number
+pub fn parse_quantity(value: &str) -> Result<u64, RpcError> {
+    let digits = value.strip_prefix("0x").unwrap_or(value);
+    u64::from_str_radix(digits, 16)
+        .map_err(|_| RpcError::InvalidResponse(format!("invalid quantity {value}")))
+}
+
+/// Convert a hex number of up to 256 bits to a decimal string
+///
+/// Accepts both quantities (`0x1a`) and 32-byte words returned by
+/// `eth_call`. An empty word (`0x`) is zero.
+pub fn hex_to_decimal(value: &str) -> Result<String, RpcError> {
+    let digits = value.strip_prefix("0x").unwrap_or(value);
+    let digits = digits.trim_start_matches('0');
+    if digits.len() > 64 {
+        return Err(RpcError::InvalidResponse(format!(
+            "{value} exceeds 256 bits"
+        )));
+    }
+
+    // Decimal digits, least significant first
+    let mut decimal: Vec<u8> = vec![0];
+    for c in digits.chars() {
+        let mut carry = c
+            .to_digit(16)
+            .ok_or_else(|| RpcError::InvalidResponse(format!("invalid hex number {value}")))?;
+        for digit in decimal.iter_mut() {
+            let value = *digit as u32 * 16 + carry;
+            *digit = (value % 10) as u8;
+            carry = value / 10;
+        }
+        while carry > 0 {
+            decimal.push((carry % 10) as u8);
+            carry /= 10;
+        }
+    }
+    Ok(decimal
+        .iter()
+        .rev()
+        .map(|digit| char::from(b'0' + digit))
+        .collect())
+}
+
+/// Balance in whole tokens of a decimal base unit amount
+pub fn scale_balance(raw: &str, decimals: u8) -> f64 {
+    raw.parse::<f64>().unwrap_or_default() / 10f64.powi(decimals as i32)
+}
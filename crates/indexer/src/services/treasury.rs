@@ -0,0 +1,181 @@
+//! Periodic snapshots of protocol treasuries
+//!
+//! The registry lists the treasury addresses of each protocol and the ERC-20
+//! tokens to track in them. Every snapshot reads the native and token
+//! balances of all addresses at the same block from the chain's JSON-RPC
+//! node and stores them in one go. Like data sources, each protocol's
+//! treasury has a lease, so only one replica takes its snapshots, and a
+//! snapshot is canceled if its lease is lost.
+
+use std::{collections::HashSet, time::Duration};
+
+use chrono::Utc;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, warn};
+
+use crate::{
+    config::ProtocolEntry,
+    db::{repositories::TreasuryRepository, Database},
+    models::TreasuryBalance,
+    services::{
+        leader::{HeldLeases, LeaderElection},
+        registry::RegistryService,
+        rpc::{scale_balance, RpcClient, RpcError},
+    },
+};
+
+/// Decimals of the native currency of EVM chains
+pub const NATIVE_DECIMALS: u8 = 18;
+
+/// Symbol of the native currency of a chain
+pub fn native_symbol(chain_id: u64) -> &'static str {
+    match chain_id {
+        56 => "BNB",
+        100 => "XDAI",
+        137 => "POL",
+        43114 => "AVAX",
+        _ => "ETH",
+    }
+}
+
+/// Lease key of a protocol's treasury
+pub fn lease_key(entry: &ProtocolEntry) -> String {
+    format!("treasury:{}", entry.id)
+}
+
+/// Background task snapshotting the treasuries leased by this replica
+pub struct TreasuryTracker {
+    registry: RegistryService,
+    rpc: RpcClient,
+    db: Database,
+    interval: Duration,
+    leases: HeldLeases,
+    unsupported: HashSet<u64>,
+}
+
+impl TreasuryTracker {
+    /// Start taking snapshots every `interval` in the background
+    pub fn spawn(
+        registry: RegistryService,
+        rpc: RpcClient,
+        leader: LeaderElection,
+        db: Database,
+        interval: Duration,
+    ) -> JoinHandle<()> {
+        let tracker = Self {
+            registry,
+            rpc,
+            db,
+            interval,
+            leases: HeldLeases::new(leader, "tracks"),
+            unsupported: HashSet::new(),
+        };
+        tokio::spawn(tracker.run())
+    }
+
+    /// Run snapshot rounds forever
+    async fn run(mut self) {
+        let mut interval = tokio::time::interval(self.leases.leader().renew_interval());
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            self.round().await;
+        }
+    }
+
+    /// Renew leases, then snapshot the due treasuries this replica holds
+    async fn round(&mut self) {
+        let registry = self.registry.current();
+        let mut entries = Vec::new();
+        for entry in registry
+            .enabled()
+            .filter(|entry| !entry.treasuries.is_empty())
+        {
+            if self.rpc.supports(entry.chain_id) {
+                entries.push(entry);
+            } else if self.unsupported.insert(entry.chain_id) {
+                warn!(
+                    "Not tracking treasuries on chain {}, no JSON-RPC node is configured",
+                    entry.chain_id
+                );
+            }
+        }
+
+        // Treasuries removed from the registry are handed back
+        let keys: Vec<String> = entries.iter().map(|entry| lease_key(entry)).collect();
+        self.leases.renew(&keys).await;
+
+        for (entry, key) in entries.into_iter().zip(keys) {
+            if !self.leases.is_due(&key) {
+                continue;
+            }
+            let snapshot = snapshot(&self.rpc, &self.db, entry);
+            match self.leases.run(&key, self.interval, snapshot).await {
+                Some(Ok(balances)) => debug!(
+                    "Recorded {} treasury balances of {}",
+                    balances.len(),
+                    entry.id
+                ),
+                Some(Err(e)) => {
+                    error!("Failed to snapshot the treasury of {}: {:#}", entry.id, e)
+                }
+                None => {}
+            }
+        }
+    }
+}
+
+/// Read and store the current balances of a protocol's treasury
+async fn snapshot(
+    rpc: &RpcClient,
+    db: &Database,
+    entry: &ProtocolEntry,
+) -> anyhow::Result<Vec<TreasuryBalance>> {
+    let balances = read_balances(rpc, entry).await?;
+    TreasuryRepository::new(db.clone())
+        .record(&entry.id, &balances)
+        .await?;
+    Ok(balances)
+}
+/// Read the native and token balances of a protocol's treasury at the latest block
+pub async fn read_balances(
+    rpc: &RpcClient,
+    entry: &ProtocolEntry,
+) -> Result<Vec<TreasuryBalance>, RpcError> {
+    let block = rpc.block_number(entry.chain_id).await?;
+    let recorded_at = Utc::now();
+
+    let mut balances = Vec::new();
+    for address in &entry.treasuries {
+        let raw = rpc.balance(entry.chain_id, address, block).await?;
+        balances.push(TreasuryBalance {
+            address: address.clone(),
+            token: None,
+            symbol: native_symbol(entry.chain_id).to_string(),
+            decimals: NATIVE_DECIMALS,
+            balance: scale_balance(&raw, NATIVE_DECIMALS),
+            raw_balance: raw,
+            value_usd: None,
+            block_number: block,
+            recorded_at,
+        });
+
+        for token in &entry.treasury_tokens {
+            let raw = rpc
+                .token_balance(entry.chain_id, &token.address, address, block)
+                .await?;
+            balances.push(TreasuryBalance {
+                address: address.clone(),
+                token: Some(token.address.clone()),
+                symbol: token.symbol.clone(),
+                decimals: token.decimals,
+                balance: scale_balance(&raw, token.decimals),
+                raw_balance: raw,
+                value_usd: None,
+                block_number: block,
+                recorded_at,
+            });
+        }
+    }
+    Ok(balances)
+}
@@ -0,0 +1,163 @@
+//! Consistency checks of the stored proposals
+//!
+//! Every proposal is checked against its revision and status histories and
+//! validated against the current protocol registry, which catches rows
+//! written by older versions or edited by hand. Pending quarantine entries
+//! and unfinished backfills are reported alongside.
+
+use serde::Serialize;
+use tracing::info;
+
+use crate::{
+    config::ProtocolRegistry,
+    db::{
+        repositories::{
+            proposal::StoredProposal, BackfillRepository, ProposalRepository, QuarantineRepository,
+        },
+        Database,
+    },
+    models::{revision::content_hash, BackfillJob},
+    utils::{id::ProposalId, validation::validate_proposal},
+};
+
+/// Number of proposals checked per query
+const VERIFY_BATCH_SIZE: i64 = 500;
+
+/// Kind of inconsistency found in a stored proposal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// The proposal has no revision
+    MissingRevision,
+    /// The latest revision does not match the stored content
+    StaleRevision,
+    /// The status history does not end at the stored status
+    StatusMismatch,
+    /// The protocol is not in the registry
+    UnknownProtocol,
+    /// The proposal fails validation against the registry
+    Invalid,
+}
+
+/// Inconsistency found in a stored proposal
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Issue {
+    /// Canonical ID of the proposal
+    pub proposal_id: ProposalId,
+    /// Kind of inconsistency
+    pub kind: IssueKind,
+    /// Details of the inconsistency
+    pub message: String,
+}
+
+/// Result of a verification run
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifyReport {
+    /// Number of proposals checked
+    pub proposals: usize,
+    /// Inconsistencies found, by proposal
+    pub issues: Vec<Issue>,
+    /// Number of quarantined records waiting for a fix
+    pub pending_quarantine: i64,
+    /// Backfills that are running or failed
+    pub unfinished_backfills: Vec<BackfillJob>,
+}
+
+/// Inconsistencies of a stored proposal
+pub fn check_proposal(stored: &StoredProposal, registry: &ProtocolRegistry) -> Vec<Issue> {
+    let proposal = &stored.proposal;
+    let issue = |kind, message: String| Issue {
+        proposal_id: proposal.id.clone(),
+        kind,
+        message,
+    };
+    let mut issues = Vec::new();
+
+    match &stored.revision_hash {
+        None => issues.push(issue(
+            IssueKind::MissingRevision,
+            "no revision is stored".to_string(),
+        )),
+        Some(hash) if *hash != content_hash(proposal) => issues.push(issue(
+            IssueKind::StaleRevision,
+            "the latest revision differs from the stored content".to_string(),
+        )),
+        Some(_) => {}
+    }
+
+    if stored.history_status != Some(proposal.status) {
+        issues.push(issue(
+            IssueKind::StatusMismatch,
+            match stored.history_status {
+                Some(status) => format!(
+                    "status history ends at {}, stored status is {}",
+                    status.as_str(),
+                    proposal.status.as_str()
+                ),
+                None => "no status history is stored".to_string(),
+            },
+        ));
+    }
+
+    if registry.get(&proposal.protocol_id.protocol).is_none() {
+        issues.push(issue(
+            IssueKind::UnknownProtocol,
+            format!("protocol {} is not in the registry", proposal.protocol_id),
+        ));
+    } else if let Err(errors) = validate_proposal(&mut proposal.clone(), registry) {
+        issues.push(issue(IssueKind::Invalid, errors.to_string()));
+    }
+
+    issues
+}
+
+/// Checker of the stored proposals
+#[derive(Clone)]
+pub struct Verifier {
+    db: Database,
+}
+
+impl Verifier {
+    /// Create a new verifier
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// Check every stored proposal, in batches by ID
+    pub async fn verify(&self, registry: &ProtocolRegistry) -> Result<VerifyReport, sqlx::Error> {
+        let proposals = ProposalRepository::new(self.db.clone());
+        let mut report = VerifyReport {
+            proposals: 0,
+            issues: Vec::new(),
+            pending_quarantine: QuarantineRepository::new(self.db.clone())
+                .count_pending()
+                .await?,
+            unfinished_backfills: BackfillRepository::new(self.db.clone())
+                .unfinished()
+                .await?,
+        };
+        let mut cursor = None;
+
+        loop {
+            let batch = proposals
+                .find_stored(cursor.as_ref(), VERIFY_BATCH_SIZE)
+                .await?;
+            let Some(last) = batch.last() else {
+                break;
+            };
+            cursor = Some(last.proposal.id.clone());
+
+            for stored in &batch {
+                report.issues.extend(check_proposal(stored, registry));
+            }
+            report.proposals += batch.len();
+            info!(
+                "Verified {} proposals, {} issues found",
+                report.proposals,
+                report.issues.len()
+            );
+        }
+
+        Ok(report)
+    }
+}
@@ -1,67 +1,609 @@
-//! Webhook service for notifying the agent
-
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-
-/// Webhook registration
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct WebhookRegistration {
-    /// Unique identifier for the webhook registration
-    pub id: String,
-    /// URL to send webhook notifications to
-    pub url: String,
-    /// List of event types to subscribe to
-    pub events: Vec<String>,
-    /// Secret for webhook authentication
+//! Webhook service for external notifications
+//!
+//! Every event appended to the
+//! [`EventLog`](crate::services::events::EventLog) is posted to the enabled
+//! webhooks whose event type, protocol and proposal status filters match it.
+//! Deliveries are signed with the webhook's secret, retried with backoff and
+//! logged; a webhook failing too many deliveries in a row is disabled until
+//! it is enabled again. At most
+//! [`MAX_CONCURRENT_DELIVERIES`](crate::services::webhook::MAX_CONCURRENT_DELIVERIES)
+//! deliveries run at once.
+//!
+//! Events are dispatched by one replica at a time, the one holding the
+//! [`WEBHOOKS_LEASE_KEY`](crate::services::webhook::WEBHOOKS_LEASE_KEY) lease.
+//! The dispatcher stores the ID of the last event whose deliveries all
+//! finished and resumes after it when it restarts or another replica takes
+//! over, so no event is skipped. Events whose deliveries were still running
+//! are dispatched again, so receivers should ignore event IDs they already
+//! handled.
+//!
+//! The body is the logged event as JSON, as sent by the event streams. The
+//! body and headers follow the contract in [`governance::webhook`], which
+//! receivers can use to parse and verify deliveries. Each
+//! delivery runs in a `webhook` span whose trace context is sent in the
+//! `traceparent` header.
+
+use std::{
+    collections::BTreeMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use chrono::Utc;
+use serde::Serialize;
+use thiserror::Error;
+use tokio::sync::{mpsc, Semaphore};
+use tracing::{debug, error, warn};
+use url::Url;
+
+use governance::webhook::{PingEvent, WebhookPayload};
+
+use crate::{
+    config::WebhookConfig,
+    db::{
+        repositories::{
+            webhook::{NewDelivery, StoredWebhook},
+            EventRepository, ProposalRepository, WebhookRepository,
+        },
+        Database,
+    },
+    models::{
+        event::{IndexerEvent, EVENT_TYPES},
+        proposal::ProposalStatus,
+        webhook::PING_EVENT,
+        EventEnvelope, EventFilter, NewWebhook, Webhook, WebhookDelivery, WebhookUpdate,
+    },
+    services::{
+        auth::random_hex,
+        events::EventLog,
+        http::{is_retryable, parse_retry_after, RetryPolicy},
+        leader::{HeldLeases, LeaderElection},
+        metrics::Metrics,
+    },
+};
+
+pub use governance::webhook::{
+    signature_header, EVENT_HEADER, SIGNATURE_HEADER, WEBHOOK_ID_HEADER,
+};
+
+/// Time a webhook has to answer a delivery
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Events buffered for the dispatcher before the event log waits for it
+const DISPATCH_BUFFER: usize = 256;
+
+/// Deliveries running at once, further events wait for one to finish
+pub const MAX_CONCURRENT_DELIVERIES: usize = 32;
+
+/// Lease key of the webhook dispatcher, held by one replica at a time
+pub const WEBHOOKS_LEASE_KEY: &str = "webhooks";
+
+/// Errors of webhook management
+#[derive(Debug, Error)]
+pub enum WebhookError {
+    /// The webhook URL is not an HTTP(S) URL
+    #[error("Invalid webhook URL: {0}")]
+    InvalidUrl(String),
+    /// An event type filter names an unknown event
+    #[error("Unknown event type: {0}")]
+    UnknownEvent(String),
+    /// No webhook has the given ID
+    #[error("Webhook {0} not found")]
+    NotFound(String),
+    /// The webhook could not be stored
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+    /// A delivery body could not be serialized
+    #[error(transparent)]
+    Serialization(#[from] serde_json::Error),
+}
+
+/// Webhook created for a client, with its secret
+#[derive(Debug, Clone, Serialize)]
+pub struct CreatedWebhook {
+    /// The stored webhook
+    #[serde(flatten)]
+    pub webhook: Webhook,
+    /// Secret signing deliveries
     pub secret: String,
 }
 
-/// Webhook service
-#[allow(dead_code)] // TODO: Remove after development phase
+/// Check the URL and event types of a webhook
+pub fn validate(url: Option<&str>, events: Option<&[String]>) -> Result<(), WebhookError> {
+    if let Some(url) = url {
+        let parsed = Url::parse(url).map_err(|_| WebhookError::InvalidUrl(url.to_string()))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(WebhookError::InvalidUrl(url.to_string()));
+        }
+    }
+    if let Some(unknown) = events
+        .unwrap_or_default()
+        .iter()
+        .find(|event| !EVENT_TYPES.contains(&event.as_str()))
+    {
+        return Err(WebhookError::UnknownEvent(unknown.clone()));
+    }
+    Ok(())
+}
+
+/// Status of the proposal an event announces, if the event carries it
+fn announced_status(event: &IndexerEvent) -> Option<ProposalStatus> {
+    match event {
+        IndexerEvent::VotingStarted { .. } => Some(ProposalStatus::Active),
+        IndexerEvent::VotingEnded { outcome, .. } => Some(*outcome),
+        IndexerEvent::StatusChanged { to, .. } => Some(*to),
+        _ => None,
+    }
+}
+
+/// Position of the dispatcher in the event log
+///
+/// Events are dispatched in order, but their deliveries finish in any order.
+/// The cursor is the last event up to which all deliveries finished.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DispatchCursor {
+    dispatched: i64,
+    pending: BTreeMap<i64, usize>,
+}
+
+impl DispatchCursor {
+    /// Start after event `cursor`
+    pub fn new(cursor: i64) -> Self {
+        Self {
+            dispatched: cursor,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    /// Last event dispatched, whose deliveries may still be running
+    pub fn dispatched(&self) -> i64 {
+        self.dispatched
+    }
+
+    /// Last event up to which all deliveries finished
+    pub fn cursor(&self) -> i64 {
+        self.pending
+            .keys()
+            .next()
+            .map_or(self.dispatched, |first| first - 1)
+    }
+
+    /// Record the dispatch of an event to `deliveries` webhooks
+    pub fn dispatch(&mut self, event_id: i64, deliveries: usize) {
+        self.dispatched = self.dispatched.max(event_id);
+        if deliveries > 0 {
+            self.pending.insert(event_id, deliveries);
+        }
+    }
+
+    /// Record a finished delivery of an event
+    pub fn finish(&mut self, event_id: i64) {
+        if let Some(remaining) = self.pending.get_mut(&event_id) {
+            *remaining -= 1;
+            if *remaining == 0 {
+                self.pending.remove(&event_id);
+            }
+        }
+    }
+}
+
+/// Outcome of posting a body to a webhook
+struct Attempts {
+    attempts: i32,
+    status_code: Option<i32>,
+    error: Option<String>,
+    success: bool,
+}
+
+/// Webhook management and delivery
+#[derive(Clone)]
 pub struct WebhookService {
-    registrations: HashMap<String, WebhookRegistration>,
+    db: Database,
+    client: reqwest::Client,
+    config: WebhookConfig,
+    metrics: Metrics,
+    deliveries: Arc<Semaphore>,
 }
 
 impl WebhookService {
-    /// Create a new webhook service
-    #[allow(dead_code, unused_variables)] // TODO: Remove after development phase
-    pub fn new() -> Self {
+    /// Create a webhook service that does not deliver events by itself
+    pub fn new(db: Database, config: WebhookConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(DELIVERY_TIMEOUT)
+            .build()
+            .expect("Failed to build webhook HTTP client");
         Self {
-            registrations: HashMap::new(),
+            db,
+            client,
+            config,
+            metrics: Metrics::default(),
+            deliveries: Arc::new(Semaphore::new(MAX_CONCURRENT_DELIVERIES)),
+        }
+    }
+
+    /// Create a webhook service delivering the events appended to `log`
+    ///
+    /// Events are dispatched while this replica holds the webhooks lease of
+    /// `leader`. Delivery outcomes are recorded in `metrics`.
+    pub fn spawn(
+        db: Database,
+        log: &EventLog,
+        leader: LeaderElection,
+        config: WebhookConfig,
+        metrics: Metrics,
+    ) -> Self {
+        let service = Self {
+            metrics,
+            ..Self::new(db, config)
+        };
+        tokio::spawn(service.clone().run(log.clone(), leader));
+        service
+    }
+
+    /// Dispatch events whenever this replica holds the webhooks lease
+    async fn run(self, log: EventLog, leader: LeaderElection) {
+        let key = WEBHOOKS_LEASE_KEY.to_string();
+        let mut leases = HeldLeases::new(leader, "delivers");
+        let mut interval = tokio::time::interval(leases.leader().renew_interval());
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            leases.renew(std::slice::from_ref(&key)).await;
+            if leases.is_due(&key) {
+                // Only returns once the lease is lost
+                leases
+                    .run(&key, Duration::ZERO, self.dispatch_log(&log))
+                    .await;
+            }
+        }
+    }
+
+    /// Dispatch the events after the stored cursor, retrying failures with backoff
+    async fn dispatch_log(&self, log: &EventLog) {
+        let repository = WebhookRepository::new(self.db.clone());
+        let backoff = RetryPolicy::default();
+        let mut failures = 0;
+
+        let mut cursor = loop {
+            match self.load_cursor().await {
+                Ok(cursor) => break DispatchCursor::new(cursor),
+                Err(e) => {
+                    let delay = backoff.delay(failures, None, rand::random());
+                    error!(
+                        "Failed to load the webhook cursor, retrying in {:?}: {}",
+                        delay, e
+                    );
+                    tokio::time::sleep(delay).await;
+                    failures += 1;
+                }
+            }
+        };
+        let mut saved = cursor.cursor();
+        let (finished, mut finished_events) = mpsc::unbounded_channel();
+
+        loop {
+            let (sender, mut receiver) = mpsc::channel(DISPATCH_BUFFER);
+            let feed = log.feed(EventFilter::default(), Some(cursor.dispatched()), sender);
+            tokio::pin!(feed);
+
+            let error = loop {
+                tokio::select! {
+                    Some(stored) = receiver.recv() => {
+                        // A restarted feed replays events dispatched before
+                        if stored.id <= cursor.dispatched() {
+                            continue;
+                        }
+                        let event_id = stored.id;
+                        match self.dispatch(stored, &finished).await {
+                            Ok(deliveries) => cursor.dispatch(event_id, deliveries),
+                            Err(e) => break Some(e),
+                        }
+                        failures = 0;
+                    }
+                    Some(event_id) = finished_events.recv() => cursor.finish(event_id),
+                    result = &mut feed => break result.err(),
+                }
+
+                if cursor.cursor() > saved {
+                    match repository.save_dispatch_cursor(cursor.cursor()).await {
+                        Ok(()) => saved = cursor.cursor(),
+                        Err(e) => error!("Failed to store the webhook cursor: {}", e),
+                    }
+                }
+            };
+
+            let delay = backoff.delay(failures, None, rand::random());
+            match error {
+                Some(e) => error!("Webhook dispatch failed, retrying in {:?}: {}", delay, e),
+                None => warn!("Webhook event feed ended, restarting in {:?}", delay),
+            }
+            tokio::time::sleep(delay).await;
+            failures += 1;
+        }
+    }
+
+    /// Stored dispatcher cursor, or the latest event when dispatching for the first time
+    async fn load_cursor(&self) -> Result<i64, sqlx::Error> {
+        let repository = WebhookRepository::new(self.db.clone());
+        if let Some(cursor) = repository.dispatch_cursor().await? {
+            return Ok(cursor);
         }
+        let latest = EventRepository::new(self.db.clone()).latest_id().await?;
+        repository.save_dispatch_cursor(latest).await?;
+        Ok(latest)
+    }
+
+    /// Register a new webhook, generating its secret unless one is given
+    pub async fn create(&self, webhook: &NewWebhook) -> Result<CreatedWebhook, WebhookError> {
+        validate(Some(&webhook.url), Some(&webhook.events))?;
+        let id = uuid::Uuid::new_v4().to_string();
+        let secret = webhook.secret.clone().unwrap_or_else(generate_secret);
+        let webhook = WebhookRepository::new(self.db.clone())
+            .create(&id, webhook, &secret)
+            .await?;
+
+        Ok(CreatedWebhook { webhook, secret })
     }
 
-    /// Register a new webhook
-    #[allow(dead_code, unused_variables)] // TODO: Remove after development phase
-    pub async fn register_webhook(
-        &mut self,
-        registration: WebhookRegistration,
-    ) -> anyhow::Result<()> {
-        // TODO: Implement webhook registration
-        todo!("Implement register_webhook")
+    /// Change the URL, filters or state of a webhook
+    pub async fn update(&self, id: &str, update: &WebhookUpdate) -> Result<Webhook, WebhookError> {
+        validate(update.url.as_deref(), update.events.as_deref())?;
+        WebhookRepository::new(self.db.clone())
+            .update(id, update)
+            .await?
+            .ok_or_else(|| WebhookError::NotFound(id.to_string()))
     }
 
-    /// Send webhook notification
-    #[allow(dead_code, unused_variables)] // TODO: Remove after development phase
-    pub async fn send_notification(
+    /// Replace the secret of a webhook
+    ///
+    /// The previous secret keeps signing deliveries for `grace_period`, or
+    /// the configured grace period if unset, so receivers can switch over.
+    pub async fn rotate_secret(
         &self,
-        event_type: &str,
-        payload: &serde_json::Value,
-    ) -> anyhow::Result<()> {
-        // TODO: Implement webhook notification sending
-        todo!("Implement send_notification")
+        id: &str,
+        grace_period: Option<Duration>,
+    ) -> Result<CreatedWebhook, WebhookError> {
+        let secret = generate_secret();
+        let grace_period =
+            grace_period.unwrap_or(Duration::from_secs(self.config.secret_grace_period_secs));
+        let webhook = WebhookRepository::new(self.db.clone())
+            .rotate_secret(id, &secret, grace_period.as_secs() as i64)
+            .await?
+            .ok_or_else(|| WebhookError::NotFound(id.to_string()))?;
+
+        Ok(CreatedWebhook { webhook, secret })
     }
 
-    /// Remove webhook registration
-    #[allow(dead_code, unused_variables)] // TODO: Remove after development phase
-    pub async fn remove_webhook(&mut self, id: &str) -> anyhow::Result<()> {
-        // TODO: Implement webhook removal
-        todo!("Implement remove_webhook")
+    /// Send a test event to a webhook, even a disabled one, without retries
+    ///
+    /// The delivery is logged but does not count towards disabling the webhook.
+    pub async fn ping(&self, id: &str) -> Result<WebhookDelivery, WebhookError> {
+        let repository = WebhookRepository::new(self.db.clone());
+        let webhook = repository
+            .find_by_id(id)
+            .await?
+            .ok_or_else(|| WebhookError::NotFound(id.to_string()))?;
+        let body = serde_json::to_string(&WebhookPayload::Ping(PingEvent {
+            webhook_id: id.to_string(),
+            timestamp: Utc::now(),
+        }))?;
+
+        let started = Instant::now();
+        let outcome = self.post(&webhook, PING_EVENT, &body, 0).await;
+        Ok(repository
+            .record_delivery(&NewDelivery {
+                webhook_id: id,
+                event_id: None,
+                event_type: PING_EVENT,
+                attempts: outcome.attempts,
+                status_code: outcome.status_code,
+                error: outcome.error,
+                success: outcome.success,
+                duration_ms: started.elapsed().as_millis() as i64,
+            })
+            .await?)
     }
-}
 
-impl Default for WebhookService {
-    fn default() -> Self {
-        Self::new()
+    /// Deliver an event to every matching webhook in the background
+    ///
+    /// Returns the number of deliveries started, each sending the event ID to
+    /// `finished` when done. Fails if the webhooks cannot be loaded.
+    async fn dispatch(
+        &self,
+        stored: EventEnvelope,
+        finished: &mpsc::UnboundedSender<i64>,
+    ) -> Result<usize, sqlx::Error> {
+        let webhooks = WebhookRepository::new(self.db.clone()).enabled().await?;
+        if webhooks.is_empty() {
+            return Ok(0);
+        }
+
+        let mut status = announced_status(&stored.event);
+        if status.is_none() && webhooks.iter().any(|w| !w.webhook.statuses.is_empty()) {
+            status = match ProposalRepository::new(self.db.clone())
+                .find_by_id(stored.event.proposal_id())
+                .await
+            {
+                Ok(proposal) => proposal.map(|p| p.status),
+                Err(e) => {
+                    error!("Failed to load proposal of event {}: {}", stored.id, e);
+                    None
+                }
+            };
+        }
+
+        let body = match serde_json::to_string(&stored) {
+            Ok(body) => body,
+            Err(e) => {
+                error!("Failed to serialize event {}: {}", stored.id, e);
+                return Ok(0);
+            }
+        };
+        let mut deliveries = 0;
+        for webhook in webhooks {
+            if !webhook.webhook.matches(&stored.event, status) {
+                continue;
+            }
+            // Webhooks are delivered concurrently, so a slow one delays no other
+            let permit = self
+                .deliveries
+                .clone()
+                .acquire_owned()
+                .await
+                .expect("Delivery semaphore is never closed");
+            let service = self.clone();
+            let finished = finished.clone();
+            let body = body.clone();
+            let event_id = stored.id;
+            let event_type = stored.event.event_type();
+            tokio::spawn(async move {
+                service.deliver(&webhook, event_id, event_type, &body).await;
+                drop(permit);
+                let _ = finished.send(event_id);
+            });
+            deliveries += 1;
+        }
+        Ok(deliveries)
+    }
+
+    /// Deliver a logged event to a webhook, recording the outcome
+    #[tracing::instrument(
+        name = "webhook",
+        skip_all,
+        fields(
+            webhook_id = %webhook.webhook.id,
+            event_id,
+            event_type,
+            otel.kind = "client",
+            attempts = tracing::field::Empty,
+            status = tracing::field::Empty,
+        )
+    )]
+    async fn deliver(&self, webhook: &StoredWebhook, event_id: i64, event_type: &str, body: &str) {
+        let id = &webhook.webhook.id;
+        let started = Instant::now();
+        let outcome = self
+            .post(webhook, event_type, body, self.config.max_retries)
+            .await;
+        self.metrics.record_delivery(outcome.success);
+        let span = tracing::Span::current();
+        span.record("attempts", outcome.attempts);
+        if let Some(status) = outcome.status_code {
+            span.record("status", status);
+        }
+
+        let repository = WebhookRepository::new(self.db.clone());
+        if let Err(e) = repository
+            .record_delivery(&NewDelivery {
+                webhook_id: id,
+                event_id: Some(event_id),
+                event_type,
+                attempts: outcome.attempts,
+                status_code: outcome.status_code,
+                error: outcome.error.clone(),
+                success: outcome.success,
+                duration_ms: started.elapsed().as_millis() as i64,
+            })
+            .await
+        {
+            error!(
+                "Failed to log delivery of event {} to {}: {}",
+                event_id, id, e
+            );
+        }
+
+        let limit = self.config.failure_limit.max(1) as i32;
+        match repository.record_outcome(id, outcome.success, limit).await {
+            Ok(Some(updated)) if !updated.enabled && updated.consecutive_failures == limit => {
+                self.metrics.record_webhook_disabled();
+                warn!(
+                    "Disabled webhook {} after {} failed deliveries in a row",
+                    id, limit
+                )
+            }
+            Ok(_) => {}
+            Err(e) => error!("Failed to record delivery outcome of {}: {}", id, e),
+        }
+        if outcome.success {
+            debug!("Delivered event {} to webhook {}", event_id, id);
+        } else {
+            warn!(
+                "Failed to deliver event {} to webhook {}: {}",
+                event_id,
+                id,
+                outcome.error.as_deref().unwrap_or("unknown error")
+            );
+        }
     }
+
+    /// Post a signed body, retrying up to `max_retries` times
+    async fn post(
+        &self,
+        webhook: &StoredWebhook,
+        event_type: &str,
+        body: &str,
+        max_retries: u32,
+    ) -> Attempts {
+        let policy = RetryPolicy {
+            max_retries,
+            ..RetryPolicy::default()
+        };
+        let signature = signature_header(&webhook.signing_secrets(Utc::now()), body);
+
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let request = self
+                .client
+                .post(&webhook.webhook.url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(EVENT_HEADER, event_type)
+                .header(WEBHOOK_ID_HEADER, &webhook.webhook.id)
+                .header(SIGNATURE_HEADER, &signature)
+                .body(body.to_string());
+            let result = telemetry::propagate(request).send().await;
+
+            let (retryable, retry_after, outcome) = match result {
+                Ok(response) => {
+                    let status = response.status();
+                    let retry_after = response
+                        .headers()
+                        .get(reqwest::header::RETRY_AFTER)
+                        .and_then(|value| value.to_str().ok())
+                        .and_then(|value| parse_retry_after(value, Utc::now()));
+                    (
+                        is_retryable(status),
+                        retry_after,
+                        Attempts {
+                            attempts: attempt,
+                            status_code: Some(status.as_u16() as i32),
+                            error: (!status.is_success()).then(|| format!("HTTP {status}")),
+                            success: status.is_success(),
+                        },
+                    )
+                }
+                Err(e) => (
+                    true,
+                    None,
+                    Attempts {
+                        attempts: attempt,
+                        status_code: None,
+                        error: Some(e.to_string()),
+                        success: false,
+                    },
+                ),
+            };
+
+            let retry = attempt as u32 - 1;
+            if outcome.success || !retryable || retry >= policy.max_retries {
+                return outcome;
+            }
+            tokio::time::sleep(policy.delay(retry, retry_after, rand::random())).await;
+        }
+    }
+}
+
+/// Random secret signing deliveries
+fn generate_secret() -> String {
+    random_hex(24)
 }
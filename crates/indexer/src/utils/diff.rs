@@ -0,0 +1,70 @@
+//! Line-level diffs between proposal revisions
+
+use similar::{ChangeTag, TextDiff};
+
+use crate::models::revision::{FieldDiff, ProposalField, ProposalRevision, RevisionDiff};
+
+/// Lines of context around each change in unified diffs
+const CONTEXT_LINES: usize = 3;
+
+/// Diff two revisions of the same proposal
+pub fn diff_revisions(from: &ProposalRevision, to: &ProposalRevision) -> RevisionDiff {
+    let fields = from
+        .changed_fields(to)
+        .into_iter()
+        .map(|field| match field {
+            ProposalField::Title => diff_field(field, &from.title, &to.title, from, to),
+            ProposalField::Description => {
+                diff_field(field, &from.description, &to.description, from, to)
+            }
+            ProposalField::Choices => diff_field(
+                field,
+                &from.choices.join("\n"),
+                &to.choices.join("\n"),
+                from,
+                to,
+            ),
+        })
+        .collect();
+
+    RevisionDiff {
+        proposal_id: to.proposal_id.clone(),
+        from: from.revision,
+        to: to.revision,
+        fields,
+    }
+}
+
+fn diff_field(
+    field: ProposalField,
+    old: &str,
+    new: &str,
+    from: &ProposalRevision,
+    to: &ProposalRevision,
+) -> FieldDiff {
+    let diff = TextDiff::from_lines(old, new);
+    let (mut insertions, mut deletions) = (0, 0);
+    for change in diff.iter_all_changes() {
+        match change.tag() {
+            ChangeTag::Insert => insertions += 1,
+            ChangeTag::Delete => deletions += 1,
+            ChangeTag::Equal => {}
+        }
+    }
+
+    let unified = diff
+        .unified_diff()
+        .context_radius(CONTEXT_LINES)
+        .header(
+            &format!("revision {}", from.revision),
+            &format!("revision {}", to.revision),
+        )
+        .to_string();
+
+    FieldDiff {
+        field,
+        unified,
+        insertions,
+        deletions,
+    }
+}
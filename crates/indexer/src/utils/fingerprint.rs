@@ -0,0 +1,174 @@
+//! Text fingerprints for near-duplicate detection
+//!
+//! Proposal text is normalized into lowercase word tokens and fingerprinted
+//! twice: a 64-bit SimHash over the words, which catches small edits, and a
+//! MinHash signature over word shingles, which estimates the Jaccard
+//! similarity of longer texts. Both are cut into band keys for lookups, so
+//! candidates are found by equal keys instead of comparing every pair.
+
+use sha2::{Digest, Sha256};
+
+/// Number of hash functions in a MinHash signature
+pub const MINHASH_PERMUTATIONS: usize = 64;
+
+/// Number of MinHash values per band key
+const MINHASH_ROWS: usize = 4;
+
+/// Number of bits per SimHash band key
+const SIMHASH_BLOCK_BITS: usize = 16;
+
+/// Number of words per shingle
+const SHINGLE_SIZE: usize = 3;
+
+/// Minimum number of words for a text to be fingerprinted
+///
+/// Shorter texts share too few words for similarity to mean anything.
+pub const MIN_WORDS: usize = 5;
+
+/// Minimum estimated Jaccard similarity of near-duplicates
+pub const DUPLICATE_SIMILARITY: f64 = 0.8;
+
+/// Maximum SimHash Hamming distance of near-duplicates
+///
+/// Must stay below the number of SimHash band keys, so near-duplicates
+/// always share at least one of them.
+pub const MAX_SIMHASH_DISTANCE: u32 = 3;
+
+/// Words below which a text containing links counts as link-only
+const LINK_ONLY_MAX_WORDS: usize = 5;
+
+/// Fingerprints of a text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fingerprint {
+    /// SimHash of the words
+    pub simhash: u64,
+    /// MinHash signature of the word shingles
+    pub minhash: Vec<u64>,
+}
+
+impl Fingerprint {
+    /// Fingerprint a text, or `None` if it has fewer than [`MIN_WORDS`] words
+    pub fn of(text: &str) -> Option<Self> {
+        let words = normalize_words(text);
+        if words.len() < MIN_WORDS {
+            return None;
+        }
+
+        Some(Self {
+            simhash: simhash(&words),
+            minhash: minhash(&words),
+        })
+    }
+
+    /// Estimated Jaccard similarity of the shingles of two texts
+    pub fn similarity(&self, other: &Self) -> f64 {
+        let equal = self
+            .minhash
+            .iter()
+            .zip(&other.minhash)
+            .filter(|(a, b)| a == b)
+            .count();
+        equal as f64 / self.minhash.len().max(1) as f64
+    }
+
+    /// Number of differing SimHash bits
+    pub fn simhash_distance(&self, other: &Self) -> u32 {
+        (self.simhash ^ other.simhash).count_ones()
+    }
+
+    /// Whether two texts are near-duplicates by either fingerprint
+    pub fn is_near_duplicate(&self, other: &Self) -> bool {
+        self.similarity(other) >= DUPLICATE_SIMILARITY
+            || self.simhash_distance(other) <= MAX_SIMHASH_DISTANCE
+    }
+
+    /// Band keys of the fingerprints, as `(band, key)` pairs
+    ///
+    /// MinHash bands come first, followed by one band per SimHash block.
+    /// Texts sharing any key are candidate near-duplicates.
+    pub fn band_keys(&self) -> Vec<(i16, i64)> {
+        let minhash_bands = self
+            .minhash
+            .chunks(MINHASH_ROWS)
+            .map(|rows| rows.iter().fold(0u64, |key, value| mix(key ^ value)) as i64);
+        let simhash_blocks = (0..64 / SIMHASH_BLOCK_BITS).map(|block| {
+            ((self.simhash >> (block * SIMHASH_BLOCK_BITS)) & ((1 << SIMHASH_BLOCK_BITS) - 1))
+                as i64
+        });
+
+        minhash_bands
+            .chain(simhash_blocks)
+            .enumerate()
+            .map(|(band, key)| (band as i16, key))
+            .collect()
+    }
+}
+
+/// Split a text into lowercase alphanumeric words
+pub fn normalize_words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Whether a text consists of links with hardly any words around them
+pub fn is_link_only(text: &str) -> bool {
+    let (links, words): (Vec<&str>, Vec<&str>) = text
+        .split_whitespace()
+        .partition(|piece| piece.contains("http://") || piece.contains("https://"));
+    let words = words
+        .iter()
+        .flat_map(|piece| normalize_words(piece))
+        .count();
+
+    !links.is_empty() && words < LINK_ONLY_MAX_WORDS
+}
+
+/// Stable 64-bit hash of a string
+fn hash(value: &str) -> u64 {
+    let digest = Sha256::digest(value.as_bytes());
+    u64::from_le_bytes(digest[..8].try_into().expect("digest has 32 bytes"))
+}
+
+/// SplitMix64 finalizer, used to derive independent hash functions
+fn mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+fn simhash(words: &[String]) -> u64 {
+    let mut weights = [0i64; 64];
+    for word in words {
+        let hash = hash(word);
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            *weight += if hash >> bit & 1 == 1 { 1 } else { -1 };
+        }
+    }
+
+    weights
+        .iter()
+        .enumerate()
+        .filter(|(_, weight)| **weight > 0)
+        .fold(0, |simhash, (bit, _)| simhash | 1 << bit)
+}
+
+fn minhash(words: &[String]) -> Vec<u64> {
+    let shingles: Vec<u64> = words
+        .windows(SHINGLE_SIZE.min(words.len()))
+        .map(|shingle| hash(&shingle.join(" ")))
+        .collect();
+
+    (0..MINHASH_PERMUTATIONS as u64)
+        .map(|permutation| {
+            let seed = mix(permutation);
+            shingles
+                .iter()
+                .map(|shingle| mix(shingle ^ seed))
+                .min()
+                .unwrap_or(u64::MAX)
+        })
+        .collect()
+}
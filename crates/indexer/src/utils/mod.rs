@@ -3,8 +3,12 @@
 //! This module contains utility functions and types for the indexer service,
 //! including error handling, ID generation, and validation.
 
+/// Line diffs between proposal revisions
+pub mod diff;
 /// Error types and handling
 pub mod error;
+/// Text fingerprints for near-duplicate detection
+pub mod fingerprint;
 /// ID generation utilities
 pub mod id;
 /// Data validation utilities
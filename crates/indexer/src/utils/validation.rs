@@ -1,46 +1,435 @@
 //! Validation utilities
+//!
+//! Validators return a structured
+//! [`ValidationError`](crate::utils::validation::ValidationError) carrying
+//! the path of the offending field and the rule that failed, so that rejected
+//! records can be quarantined with an actionable reason. Values with a
+//! canonical form (addresses, ENS names) are normalized in place.
 
-use crate::models::{Actor, Proposal, ProtocolId};
+use std::{fmt, sync::LazyLock};
 
-/// Validate a proposal
-#[allow(dead_code, unused_variables)] // TODO: Remove after development phase
-pub fn validate_proposal(proposal: &Proposal) -> Result<(), String> {
-    if proposal.title.is_empty() {
-        return Err("Proposal title cannot be empty".to_string());
+use ens_normalize_rs::EnsNameNormalizer;
+
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+use thiserror::Error;
+
+use crate::{
+    config::{ProtocolEntry, ProtocolRegistry},
+    models::{Actor, Proposal, ProtocolId},
+    utils::id::is_valid_protocol_slug,
+};
+
+/// Validation rule that a value failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidationRule {
+    /// Value is required but empty
+    Required,
+    /// Payload field is missing or has the wrong type
+    Malformed,
+    /// Record comes from a source without a normalizer
+    UnknownSource,
+    /// Value is not a `0x` prefixed 20-byte hex address
+    HexAddress,
+    /// Mixed-case address does not match its EIP-55 checksum
+    Checksum,
+    /// Value is not a valid ENSIP-15 name
+    EnsName,
+    /// Value is not a valid Snapshot proposal ID
+    SnapshotId,
+    /// Value is not a valid on-chain (Governor) proposal ID
+    OnchainProposalId,
+    /// Snapshot space is not registered for the protocol
+    UnknownSnapshotSpace,
+    /// Protocol is not part of the registry
+    UnknownProtocol,
+    /// Chain does not match the protocol's chain in the registry
+    ChainMismatch,
+    /// Identifiers of the record disagree with each other
+    InconsistentId,
+    /// Voting ends before it starts
+    VotingWindow,
+    /// Number of scores does not match the number of choices
+    ScoreCount,
+}
+
+impl ValidationRule {
+    /// Stable name of the rule
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Required => "required",
+            Self::Malformed => "malformed",
+            Self::UnknownSource => "unknown_source",
+            Self::HexAddress => "hex_address",
+            Self::Checksum => "checksum",
+            Self::EnsName => "ens_name",
+            Self::SnapshotId => "snapshot_id",
+            Self::OnchainProposalId => "onchain_proposal_id",
+            Self::UnknownSnapshotSpace => "unknown_snapshot_space",
+            Self::UnknownProtocol => "unknown_protocol",
+            Self::ChainMismatch => "chain_mismatch",
+            Self::InconsistentId => "inconsistent_id",
+            Self::VotingWindow => "voting_window",
+            Self::ScoreCount => "score_count",
+        }
+    }
+}
+
+impl fmt::Display for ValidationRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A single validation failure
+#[derive(Error, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[error("{field}: {message} ({rule})")]
+pub struct ValidationError {
+    /// Path of the offending field (e.g. `author`, `choices[2]`)
+    pub field: String,
+    /// Rule that failed
+    pub rule: ValidationRule,
+    /// Human readable description
+    pub message: String,
+}
+
+impl ValidationError {
+    /// Create a validation error
+    pub fn new(field: impl Into<String>, rule: ValidationRule, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            rule,
+            message: message.into(),
+        }
+    }
+}
+
+/// All validation failures of a record
+#[derive(Error, Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[error("{}", .errors.iter().map(ToString::to_string).collect::<Vec<_>>().join("; "))]
+pub struct ValidationErrors {
+    /// Individual failures, in field order
+    pub errors: Vec<ValidationError>,
+}
+
+impl ValidationErrors {
+    /// Record a failure
+    pub fn push(&mut self, error: ValidationError) {
+        self.errors.push(error);
     }
 
-    if proposal.description.is_empty() {
-        return Err("Proposal description cannot be empty".to_string());
+    /// Record the failure of a result, if any
+    pub fn check<T>(&mut self, result: Result<T, ValidationError>) -> Option<T> {
+        result.map_err(|e| self.push(e)).ok()
     }
 
-    if proposal.author.is_empty() {
-        return Err("Proposal author cannot be empty".to_string());
+    /// Whether no failure was recorded
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
     }
 
+    /// `Ok(())` if no failure was recorded
+    pub fn into_result(self) -> Result<(), Self> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(self)
+        }
+    }
+}
+
+/// ENSIP-15 normalizer, built once as loading its tables is expensive
+static ENS_NORMALIZER: LazyLock<EnsNameNormalizer> = LazyLock::new(EnsNameNormalizer::default);
+
+fn require(field: &str, value: &str) -> Result<(), ValidationError> {
+    if value.trim().is_empty() {
+        return Err(ValidationError::new(
+            field,
+            ValidationRule::Required,
+            "value cannot be empty",
+        ));
+    }
     Ok(())
 }
 
-/// Validate an actor
-#[allow(dead_code, unused_variables)] // TODO: Remove after development phase
-pub fn validate_actor(actor: &Actor) -> Result<(), String> {
-    if actor.address.is_empty() {
-        return Err("Actor address cannot be empty".to_string());
+/// EIP-55 checksummed form of a 20-byte hex address (without `0x`)
+fn to_checksum(hex: &str) -> String {
+    let lower = hex.to_ascii_lowercase();
+    let hash = Keccak256::digest(lower.as_bytes());
+
+    lower
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let nibble = (hash[i / 2] >> (if i % 2 == 0 { 4 } else { 0 })) & 0x0f;
+            if c.is_ascii_alphabetic() && nibble >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+/// Validate an Ethereum address and return its EIP-55 checksummed form
+///
+/// All-lowercase and all-uppercase addresses carry no checksum and are
+/// accepted as is; mixed-case addresses must match their checksum.
+pub fn normalize_address(field: &str, value: &str) -> Result<String, ValidationError> {
+    require(field, value)?;
+
+    let hex = value
+        .strip_prefix("0x")
+        .filter(|hex| hex.len() == 40 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+        .ok_or_else(|| {
+            ValidationError::new(
+                field,
+                ValidationRule::HexAddress,
+                format!("'{value}' is not a 0x prefixed 20-byte hex address"),
+            )
+        })?;
+
+    let checksummed = to_checksum(hex);
+    let has_lower = hex.chars().any(|c| c.is_ascii_lowercase());
+    let has_upper = hex.chars().any(|c| c.is_ascii_uppercase());
+    if has_lower && has_upper && hex != checksummed {
+        return Err(ValidationError::new(
+            field,
+            ValidationRule::Checksum,
+            format!("'{value}' does not match its EIP-55 checksum"),
+        ));
+    }
+
+    Ok(format!("0x{checksummed}"))
+}
+
+/// Validate an ENS name per ENSIP-15 and return its normalized form
+pub fn normalize_ens_name(field: &str, value: &str) -> Result<String, ValidationError> {
+    require(field, value)?;
+
+    let normalized = ENS_NORMALIZER.normalize(value).map_err(|e| {
+        ValidationError::new(
+            field,
+            ValidationRule::EnsName,
+            format!("'{value}' is not a valid ENS name: {e}"),
+        )
+    })?;
+
+    if normalized.split('.').any(str::is_empty) {
+        return Err(ValidationError::new(
+            field,
+            ValidationRule::EnsName,
+            format!("'{value}' contains an empty label"),
+        ));
+    }
+
+    Ok(normalized)
+}
+
+/// Validate a Snapshot proposal ID
+///
+/// Current proposals use a `0x` prefixed 32-byte hash; early proposals were
+/// identified by their IPFS CIDv0 (`Qm…`).
+pub fn validate_snapshot_id(field: &str, value: &str) -> Result<(), ValidationError> {
+    let is_hash = value
+        .strip_prefix("0x")
+        .is_some_and(|hex| hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()));
+    let is_cid = value.len() == 46
+        && value.starts_with("Qm")
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() && !matches!(c, '0' | 'O' | 'I' | 'l'));
+
+    if !is_hash && !is_cid {
+        return Err(ValidationError::new(
+            field,
+            ValidationRule::SnapshotId,
+            format!("'{value}' is not a Snapshot proposal ID"),
+        ));
     }
+    Ok(())
+}
 
-    // TODO: Add more validation rules
+/// Validate an on-chain Governor proposal ID (a decimal uint256)
+pub fn validate_onchain_proposal_id(field: &str, value: &str) -> Result<(), ValidationError> {
+    // 2^256 - 1 has 78 decimal digits
+    if value.is_empty() || value.len() > 78 || !value.chars().all(|c| c.is_ascii_digit()) {
+        return Err(ValidationError::new(
+            field,
+            ValidationRule::OnchainProposalId,
+            format!("'{value}' is not a decimal proposal ID"),
+        ));
+    }
     Ok(())
 }
 
-/// Validate a protocol ID
-#[allow(dead_code, unused_variables)] // TODO: Remove after development phase
-pub fn validate_protocol_id(protocol_id: &ProtocolId) -> Result<(), String> {
-    if protocol_id.name.is_empty() {
-        return Err("Protocol name cannot be empty".to_string());
+/// Resolve a protocol ID against the registry, checking its chain
+pub fn validate_protocol_id<'a>(
+    field: &str,
+    protocol_id: &ProtocolId,
+    registry: &'a ProtocolRegistry,
+) -> Result<&'a ProtocolEntry, ValidationError> {
+    if !is_valid_protocol_slug(&protocol_id.protocol) {
+        return Err(ValidationError::new(
+            field,
+            ValidationRule::UnknownProtocol,
+            format!("invalid protocol identifier '{}'", protocol_id.protocol),
+        ));
     }
 
-    if protocol_id.protocol.is_empty() {
-        return Err("Protocol identifier cannot be empty".to_string());
+    let entry = registry.get(&protocol_id.protocol).ok_or_else(|| {
+        ValidationError::new(
+            field,
+            ValidationRule::UnknownProtocol,
+            format!("protocol '{}' is not in the registry", protocol_id.protocol),
+        )
+    })?;
+
+    if protocol_id.chain_id.as_eip155() != Some(entry.chain_id) {
+        return Err(ValidationError::new(
+            field,
+            ValidationRule::ChainMismatch,
+            format!(
+                "protocol '{}' is registered on eip155:{}, not {}",
+                entry.id, entry.chain_id, protocol_id.chain_id
+            ),
+        ));
     }
 
+    Ok(entry)
+}
+
+/// Check that a Snapshot space is registered for a protocol
+pub fn validate_snapshot_space(
+    field: &str,
+    space: &str,
+    entry: &ProtocolEntry,
+) -> Result<(), ValidationError> {
+    if !entry.snapshot_spaces.iter().any(|s| s == space) {
+        return Err(ValidationError::new(
+            field,
+            ValidationRule::UnknownSnapshotSpace,
+            format!(
+                "Snapshot space '{space}' is not registered for '{}'",
+                entry.id
+            ),
+        ));
+    }
     Ok(())
 }
+
+/// Validate a proposal against the registry, normalizing its author address
+///
+/// The author is validated as an actor with [`validate_actor`].
+pub fn validate_proposal(
+    proposal: &mut Proposal,
+    registry: &ProtocolRegistry,
+) -> Result<(), ValidationErrors> {
+    let mut errors = ValidationErrors::default();
+
+    errors.check(require("title", &proposal.title));
+    errors.check(require("description", &proposal.description));
+    for (i, choice) in proposal.choices.iter().enumerate() {
+        errors.check(require(&format!("choices[{i}]"), choice));
+    }
+
+    let mut author = Actor {
+        address: proposal.author.clone(),
+        ens: None,
+        name: None,
+        description: None,
+        voting_power: None,
+        // The protocol is validated with the proposal below
+        protocol_id: None,
+        created_at: proposal.created_at,
+        updated_at: proposal.updated_at,
+    };
+    match validate_actor(&mut author, registry) {
+        Ok(()) => proposal.author = author.address,
+        Err(author_errors) => {
+            for mut error in author_errors.errors {
+                error.field = match error.field.as_str() {
+                    "address" => "author".to_string(),
+                    field => format!("author.{field}"),
+                };
+                errors.push(error);
+            }
+        }
+    }
+
+    if let (Some(start), Some(end)) = (proposal.voting_start, proposal.voting_end) {
+        if end < start {
+            errors.push(ValidationError::new(
+                "voting_end",
+                ValidationRule::VotingWindow,
+                format!("voting ends at {end}, before it starts at {start}"),
+            ));
+        }
+    }
+
+    // Scores are only known once voting started
+    if !proposal.scores.is_empty() && proposal.scores.len() != proposal.choices.len() {
+        errors.push(ValidationError::new(
+            "scores",
+            ValidationRule::ScoreCount,
+            format!(
+                "{} scores for {} choices",
+                proposal.scores.len(),
+                proposal.choices.len()
+            ),
+        ));
+    }
+
+    errors.check(validate_protocol_id(
+        "protocol_id",
+        &proposal.protocol_id,
+        registry,
+    ));
+
+    if proposal.id.protocol_id() != &proposal.protocol_id {
+        errors.push(ValidationError::new(
+            "id",
+            ValidationRule::InconsistentId,
+            format!(
+                "proposal ID belongs to '{}', not '{}'",
+                proposal.id.protocol_id(),
+                proposal.protocol_id
+            ),
+        ));
+    }
+
+    match proposal.id.source() {
+        "snapshot" => errors.check(validate_snapshot_id("id", proposal.id.native_id())),
+        "tally" | "onchain" => {
+            errors.check(validate_onchain_proposal_id("id", proposal.id.native_id()))
+        }
+        _ => None,
+    };
+
+    errors.into_result()
+}
+
+/// Validate an actor against the registry, normalizing its address and ENS name
+pub fn validate_actor(
+    actor: &mut Actor,
+    registry: &ProtocolRegistry,
+) -> Result<(), ValidationErrors> {
+    let mut errors = ValidationErrors::default();
+
+    if let Some(address) = errors.check(normalize_address("address", &actor.address)) {
+        actor.address = address;
+    }
+
+    if let Some(ens) = &actor.ens {
+        if let Some(ens) = errors.check(normalize_ens_name("ens", ens)) {
+            actor.ens = Some(ens);
+        }
+    }
+
+    if let Some(protocol_id) = &actor.protocol_id {
+        errors.check(validate_protocol_id("protocol_id", protocol_id, registry));
+    }
+
+    errors.into_result()
+}
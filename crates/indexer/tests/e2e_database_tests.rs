@@ -0,0 +1,273 @@
+//! End-to-end tests against a PostgreSQL database
+//!
+//! Run with `WEI_INDEXER_TEST_DATABASE_URL` set, e.g.
+//! `cargo test -p indexer --test e2e_database_tests`.
+
+use chrono::{NaiveDate, TimeZone, Utc};
+use indexer::{
+    config::{ProtocolRegistry, RegistryConfig},
+    db::repositories::{
+        ChangeRepository, EventRepository, PriceRepository, ProposalRepository,
+        QuarantineRepository, TransferRepository,
+    },
+    models::{
+        ChangeOperation, DailyPrice, EntityType, EventEnvelope, EventFilter, ProposalTransfer,
+        ProtocolId, RawRecord,
+    },
+    services::{
+        events::{EventLog, LIVE_POLL_INTERVAL},
+        EventBus, IngestOutcome, IngestPipeline, LifecycleScheduler, PriceService, RegistryService,
+    },
+    utils::{
+        id::ProposalId,
+        validation::{ValidationError, ValidationErrors, ValidationRule},
+    },
+};
+use tokio::sync::mpsc;
+
+mod fixtures;
+use fixtures::{database, random_native_id, ProposalBuilder};
+
+#[tokio::test]
+async fn test_e2e_feed_receives_events_of_other_replicas() {
+    let db = database().await;
+    let protocol: ProtocolId = "eip155:1:uniswap".parse().unwrap();
+    let latest = EventRepository::new(db.clone()).latest_id().await.unwrap();
+
+    // Each replica feeds its subscriber from its own bus
+    let filter = EventFilter {
+        protocols: vec![protocol.clone()],
+        ..Default::default()
+    };
+    let feed = |bus: &EventBus| {
+        let log = EventLog::new(db.clone(), bus.clone());
+        let (sender, receiver) = mpsc::channel(16);
+        let filter = filter.clone();
+        tokio::spawn(async move { log.feed(filter, Some(latest), sender).await });
+        receiver
+    };
+    let (this, other) = (EventBus::default(), EventBus::default());
+    let mut receiver = feed(&this);
+    let mut other_receiver = feed(&other);
+    // Let the feeds catch up and wait for live events
+    tokio::time::sleep(LIVE_POLL_INTERVAL / 4).await;
+
+    // The other replica logs an event and only publishes it on its own bus
+    let proposal = ProposalBuilder::new(protocol, "snapshot", &random_native_id()).build();
+    let saved = ProposalRepository::new(db.clone())
+        .save(&proposal)
+        .await
+        .unwrap();
+    let logged = saved.events[0].clone();
+    for event in saved.events {
+        other.publish(event);
+    }
+
+    assert_eq!(next_event(&mut other_receiver, &proposal.id).await, logged);
+    assert_eq!(next_event(&mut receiver, &proposal.id).await, logged);
+}
+
+/// Waits for the next fed event of a proposal
+async fn next_event(
+    receiver: &mut mpsc::Receiver<EventEnvelope>,
+    id: &ProposalId,
+) -> EventEnvelope {
+    tokio::time::timeout(LIVE_POLL_INTERVAL * 3, async {
+        loop {
+            let stored = receiver.recv().await.unwrap();
+            if stored.event.proposal_id() == id {
+                return stored;
+            }
+        }
+    })
+    .await
+    .expect("the logged event was not fed")
+}
+
+#[tokio::test]
+async fn test_e2e_search_matches_title_and_description() {
+    let repository = ProposalRepository::new(database().await);
+    let protocol: ProtocolId = "eip155:1:uniswap".parse().unwrap();
+    // A marker unique to this run keeps other rows out of the results
+    let marker = random_native_id();
+    let matching = ProposalBuilder::new(protocol.clone(), "snapshot", &random_native_id())
+        .title(&format!("Fund {marker}"))
+        .description("Allocate 1M UNI to GRANTS")
+        .build();
+    let other = ProposalBuilder::new(protocol, "snapshot", &random_native_id())
+        .title(&format!("Fund {marker}"))
+        .description("Lower the fee switch")
+        .build();
+    for proposal in [&matching, &other] {
+        repository.save(proposal).await.unwrap();
+    }
+
+    let found = repository
+        .search(Some(&marker.to_uppercase()), Some("grants"), 10)
+        .await
+        .unwrap();
+    let ids: Vec<_> = found.iter().map(|proposal| &proposal.id).collect();
+    assert_eq!(ids, [&matching.id]);
+
+    let found = repository.search(Some(&marker), None, 10).await.unwrap();
+    assert_eq!(found.len(), 2);
+}
+
+#[tokio::test]
+async fn test_e2e_quarantine_keeps_records_without_native_id_apart() {
+    let repository = QuarantineRepository::new(database().await);
+    let protocol: ProtocolId = "eip155:1:uniswap".parse().unwrap();
+    let mut errors = ValidationErrors::default();
+    errors.push(ValidationError::new(
+        "id",
+        ValidationRule::Required,
+        "id is required",
+    ));
+    let record = |title: &str| {
+        RawRecord::new(
+            "snapshot",
+            protocol.clone(),
+            serde_json::json!({ "title": title }),
+        )
+    };
+    let (first, second) = (record(&random_native_id()), record(&random_native_id()));
+    assert_eq!(first.native_id, None);
+
+    let first_id = repository.quarantine(&first, &errors).await.unwrap();
+    let second_id = repository.quarantine(&second, &errors).await.unwrap();
+    assert_ne!(first_id, second_id);
+    let stored = repository.find_by_id(first_id).await.unwrap().unwrap();
+    assert_eq!(stored.payload, first.payload);
+
+    // The same record crawled again updates its entry
+    assert_eq!(
+        repository.quarantine(&first, &errors).await.unwrap(),
+        first_id
+    );
+    let stored = repository.find_by_id(first_id).await.unwrap().unwrap();
+    assert_eq!(stored.attempts, 2);
+}
+
+#[tokio::test]
+async fn test_e2e_transfers_are_valued_when_the_proposal_was_created() {
+    let db = database().await;
+    let registry = RegistryService::init(
+        &RegistryConfig {
+            path: concat!(env!("CARGO_MANIFEST_DIR"), "/config/protocols.toml").into(),
+            reload_interval_secs: 60,
+        },
+        db.clone(),
+    )
+    .await
+    .unwrap();
+    PriceRepository::new(db.clone())
+        .upsert(&[DailyPrice {
+            asset: "usd-coin".to_string(),
+            day: NaiveDate::from_ymd_opt(2001, 1, 1).unwrap(),
+            price_usd: 0.99,
+            source: "test".to_string(),
+        }])
+        .await
+        .unwrap();
+    let created_at = Utc.with_ymd_and_hms(2001, 1, 2, 12, 0, 0).unwrap();
+    let proposal = ProposalBuilder::new(
+        "eip155:1:uniswap".parse().unwrap(),
+        "snapshot",
+        &random_native_id(),
+    )
+    .created_at(created_at)
+    .build();
+    ProposalRepository::new(db.clone())
+        .save(&proposal)
+        .await
+        .unwrap();
+
+    let transfer = |call_index, token: &str| ProposalTransfer {
+        call_index,
+        token: token.to_string(),
+        recipient: "0xfb6916095ca1df60bb79ce92ce3ea74c37c5d359".to_string(),
+        raw_amount: "2500000".to_string(),
+        value: None,
+    };
+    let repository = TransferRepository::new(db.clone());
+    repository
+        .replace(
+            &proposal.id,
+            &[
+                transfer(0, "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"),
+                transfer(2, "0x0000000000000000000000000000000000000001"),
+            ],
+        )
+        .await
+        .unwrap();
+    let mut transfers = repository.for_proposal(&proposal.id).await.unwrap();
+    assert_eq!(transfers.len(), 2);
+
+    PriceService::new(db, registry)
+        .value_transfers(1, &mut transfers, created_at)
+        .await
+        .unwrap();
+    let value = transfers[0].value.as_ref().unwrap();
+    assert_eq!(value.symbol, "USDC");
+    assert_eq!(value.amount, 2.5);
+    assert_eq!(value.value_usd, 2.5 * 0.99);
+    // Tokens missing from the registry are not valued
+    assert_eq!(transfers[1].value, None);
+}
+
+#[tokio::test]
+async fn test_e2e_hidden_proposals_are_removed() {
+    let db = database().await;
+    let registry = ProtocolRegistry::load(std::path::Path::new(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/config/protocols.toml"
+    )))
+    .unwrap();
+    let pipeline = IngestPipeline::new(
+        db.clone(),
+        EventBus::default(),
+        LifecycleScheduler::default(),
+    );
+    let native_id = random_native_id();
+    let mut payload = serde_json::json!({
+        "id": native_id,
+        "title": "Fund the grants program",
+        "body": "Allocate 1M ARB to grants",
+        "choices": ["For", "Against"],
+        "author": "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed",
+        "state": "closed",
+        "scores": [10, 2],
+        "created": 1_700_000_000,
+        "type": "single-choice",
+        "space": { "id": "arbitrumfoundation.eth" }
+    });
+    let protocol: ProtocolId = "eip155:42161:arbitrum".parse().unwrap();
+    let record = RawRecord::new("snapshot", protocol.clone(), payload.clone());
+    let outcome = pipeline.ingest(&record, &registry).await.unwrap();
+    let IngestOutcome::Stored(id) = outcome else {
+        panic!("the record was not stored: {outcome:?}");
+    };
+    let latest: i64 = sqlx::query_scalar("SELECT COALESCE(MAX(seq), 0) FROM change_log")
+        .fetch_one(&db)
+        .await
+        .unwrap();
+
+    // Snapshot flags the proposal
+    payload["flagged"] = serde_json::json!(true);
+    let record = RawRecord::new("snapshot", protocol, payload);
+    assert_eq!(
+        pipeline.ingest(&record, &registry).await.unwrap(),
+        IngestOutcome::Removed(id.clone())
+    );
+    let repository = ProposalRepository::new(db.clone());
+    assert!(repository.find_by_id(&id).await.unwrap().is_none());
+
+    let changes = ChangeRepository::new(db)
+        .since(latest, Some(EntityType::Proposal), 1000)
+        .await
+        .unwrap();
+    assert!(changes
+        .iter()
+        .any(|change| change.entity_id == id.to_string()
+            && change.operation == ChangeOperation::Delete));
+}
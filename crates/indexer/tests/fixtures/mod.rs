@@ -0,0 +1,138 @@
+// Shared test fixtures
+
+use chrono::{DateTime, Utc};
+use indexer::{
+    db::{init_database, Database},
+    models::{
+        proposal::{ProposalStatus, VotingType},
+        Proposal, ProtocolId,
+    },
+    utils::id::ProposalId,
+};
+
+/// Builder for test proposals with sensible defaults
+///
+/// Defaults to an active single-choice proposal with For/Against choices,
+/// created now and without a voting window or scores.
+#[allow(dead_code)]
+pub struct ProposalBuilder {
+    proposal: Proposal,
+}
+
+#[allow(dead_code)]
+impl ProposalBuilder {
+    pub fn new(protocol_id: ProtocolId, source: &str, native_id: &str) -> Self {
+        let now = Utc::now();
+        Self {
+            proposal: Proposal {
+                id: ProposalId::new(protocol_id.clone(), source, native_id).unwrap(),
+                title: "Fund the grants program".to_string(),
+                description: "Allocate 1M ARB to grants".to_string(),
+                status: ProposalStatus::Active,
+                protocol_id,
+                choices: vec!["For".to_string(), "Against".to_string()],
+                author: "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string(),
+                comments: vec![],
+                created_at: now,
+                updated_at: now,
+                voting_start: None,
+                voting_end: None,
+                snapshot_block: None,
+                quorum: None,
+                voting_type: VotingType::SingleChoice,
+                scores: vec![],
+                scores_total: None,
+                discussion_url: None,
+                ipfs_hash: None,
+                source_url: None,
+                is_duplicate_of: None,
+                spam_score: None,
+            },
+        }
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.proposal.title = title.to_string();
+        self
+    }
+
+    pub fn description(mut self, description: &str) -> Self {
+        self.proposal.description = description.to_string();
+        self
+    }
+
+    pub fn status(mut self, status: ProposalStatus) -> Self {
+        self.proposal.status = status;
+        self
+    }
+
+    pub fn choices(mut self, choices: &[&str]) -> Self {
+        self.proposal.choices = choices.iter().map(|choice| choice.to_string()).collect();
+        self
+    }
+
+    pub fn author(mut self, author: &str) -> Self {
+        self.proposal.author = author.to_string();
+        self
+    }
+
+    /// Set both the creation and the last update time
+    pub fn created_at(mut self, created_at: DateTime<Utc>) -> Self {
+        self.proposal.created_at = created_at;
+        self.proposal.updated_at = created_at;
+        self
+    }
+
+    pub fn voting(mut self, start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
+        self.proposal.voting_start = Some(start);
+        self.proposal.voting_end = Some(end);
+        self
+    }
+
+    pub fn snapshot_block(mut self, block: u64) -> Self {
+        self.proposal.snapshot_block = Some(block);
+        self
+    }
+
+    pub fn voting_type(mut self, voting_type: VotingType) -> Self {
+        self.proposal.voting_type = voting_type;
+        self
+    }
+
+    pub fn scores(mut self, scores: &[f64]) -> Self {
+        self.proposal.scores = scores.to_vec();
+        self
+    }
+
+    pub fn scores_total(mut self, total: f64) -> Self {
+        self.proposal.scores_total = Some(total);
+        self
+    }
+
+    pub fn build(self) -> Proposal {
+        self.proposal
+    }
+}
+
+/// Migrated database of the e2e tests
+///
+/// Requires `WEI_INDEXER_TEST_DATABASE_URL` to point to a PostgreSQL
+/// database the tests may write to. Tests only add rows with random IDs.
+#[allow(dead_code)]
+pub async fn database() -> Database {
+    let url = std::env::var("WEI_INDEXER_TEST_DATABASE_URL")
+        .ok()
+        .filter(|url| !url.trim().is_empty())
+        .expect("WEI_INDEXER_TEST_DATABASE_URL must be set for e2e tests");
+    init_database(&url).await.unwrap()
+}
+
+/// Random Snapshot proposal ID, so tests sharing a database do not collide
+#[allow(dead_code)]
+pub fn random_native_id() -> String {
+    format!(
+        "0x{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    )
+}
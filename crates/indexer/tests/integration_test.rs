@@ -43,10 +43,19 @@ fn test_data_source_config() {
         snapshot: indexer::config::SnapshotConfig {
             base_url: "https://test.snapshot.org".to_string(),
             api_key: Some("test-key".to_string()),
+            requests_per_minute: 100,
         },
         tally: indexer::config::TallyConfig {
             base_url: "https://test.tally.xyz".to_string(),
             api_key: None,
+            requests_per_minute: 60,
+        },
+        http: indexer::config::HttpConfig {
+            max_retries: 3,
+            failure_threshold: 5,
+            cooldown_secs: 60,
+            timeout_secs: 30,
+            connect_timeout_secs: 10,
         },
     };
 
@@ -61,6 +70,8 @@ fn test_webhook_config() {
     let webhook_config = WebhookConfig {
         secret: "test-secret".to_string(),
         max_retries: 5,
+        failure_limit: 10,
+        secret_grace_period_secs: 3600,
     };
 
     assert_eq!(webhook_config.secret, "test-secret");
@@ -0,0 +1,194 @@
+//! Unit tests for API key authentication
+
+use axum::http::Method;
+use chrono::Utc;
+use clap::Parser;
+use indexer::{
+    config::{ApiKeyCommand, AuthConfig, Command, Config},
+    models::{ApiKey, ApiScope},
+    services::{
+        auth::{
+            generate_key, hash_secret, required_scope, sign, split_key, string_to_sign,
+            verify_signature, AuthError, Credentials, MAX_SIGNATURE_AGE_SECS,
+        },
+        Authenticator,
+    },
+};
+use sqlx::postgres::PgPoolOptions;
+
+fn key(scopes: Vec<ApiScope>) -> ApiKey {
+    ApiKey {
+        id: 1,
+        name: "agent".to_string(),
+        prefix: "0123abcd".to_string(),
+        scopes,
+        rate_limit: None,
+        signing: false,
+        created_at: Utc::now(),
+        last_used_at: None,
+        revoked_at: None,
+    }
+}
+
+#[test]
+fn test_required_scope() {
+    assert_eq!(required_scope(&Method::GET, "/health"), None);
+    assert_eq!(required_scope(&Method::OPTIONS, "/graphql"), None);
+    assert_eq!(
+        required_scope(&Method::GET, "/proposals/search"),
+        Some(ApiScope::Read)
+    );
+    assert_eq!(
+        required_scope(&Method::POST, "/graphql"),
+        Some(ApiScope::Read)
+    );
+    assert_eq!(
+        required_scope(&Method::POST, "/hooks"),
+        Some(ApiScope::WebhooksWrite)
+    );
+    assert_eq!(
+        required_scope(&Method::GET, "/api-keys"),
+        Some(ApiScope::Admin)
+    );
+    assert_eq!(
+        required_scope(&Method::DELETE, "/api-keys/3"),
+        Some(ApiScope::Admin)
+    );
+    assert_eq!(
+        required_scope(&Method::POST, "/datasets"),
+        Some(ApiScope::Admin)
+    );
+    // Only whole path segments match
+    assert_eq!(
+        required_scope(&Method::GET, "/hooksmith"),
+        Some(ApiScope::Read)
+    );
+}
+
+#[test]
+fn test_scopes() {
+    let reader = key(vec![ApiScope::Read]);
+    assert!(reader.allows(ApiScope::Read));
+    assert!(!reader.allows(ApiScope::WebhooksWrite));
+    assert!(!reader.allows(ApiScope::Admin));
+
+    let admin = key(vec![ApiScope::Admin]);
+    assert!(admin.allows(ApiScope::Read));
+    assert!(admin.allows(ApiScope::WebhooksWrite));
+
+    assert_eq!("webhooks:write".parse(), Ok(ApiScope::WebhooksWrite));
+    assert!("write".parse::<ApiScope>().is_err());
+}
+
+#[test]
+fn test_generated_keys() {
+    let (prefix, secret, full) = generate_key();
+    assert_eq!(prefix.len(), 8);
+    assert_eq!(split_key(&full), Some((prefix.as_str(), secret.as_str())));
+    assert_ne!(generate_key().2, full);
+
+    // Only the hash of the secret is stored
+    let hash = hash_secret(&secret);
+    assert_eq!(hash.len(), 64);
+    assert!(!hash.contains(&secret));
+
+    assert_eq!(split_key("wei_abc"), None);
+    assert_eq!(split_key("key_abc_def"), None);
+    assert_eq!(split_key("wei__def"), None);
+}
+
+#[test]
+fn test_request_signatures() {
+    let signed = string_to_sign(1_700_000_000, "POST", "/hooks?x=1", b"{}");
+    assert_eq!(
+        signed,
+        "1700000000\nPOST\n/hooks?x=1\n\
+         44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a"
+    );
+
+    let signature = sign("secret", &signed);
+    assert!(verify_signature("secret", &signed, &signature));
+    assert!(!verify_signature("other", &signed, &signature));
+    assert!(!verify_signature("secret", &signed, "not hex"));
+
+    let tampered = string_to_sign(1_700_000_000, "POST", "/hooks?x=1", b"{\"a\":1}");
+    assert!(!verify_signature("secret", &tampered, &signature));
+}
+
+#[tokio::test]
+async fn test_signatures_with_extreme_timestamps_expire() {
+    // Expired signatures are rejected before any key is looked up
+    let db = PgPoolOptions::new()
+        .connect_lazy("postgres://localhost/unreachable")
+        .unwrap();
+    let authenticator = Authenticator::new(
+        db,
+        AuthConfig {
+            enabled: true,
+            anonymous_read: false,
+        },
+    );
+    let now = Utc::now().timestamp();
+    let stale = now - MAX_SIGNATURE_AGE_SECS as i64 - 1;
+
+    for timestamp in [i64::MIN, i64::MAX, 0, stale] {
+        let result = authenticator
+            .authenticate(
+                Credentials::Signed {
+                    key_id: "0123abcd",
+                    timestamp,
+                    signature: "00",
+                    signed: "",
+                },
+                ApiScope::Read,
+            )
+            .await;
+        assert!(
+            matches!(result, Err(AuthError::ExpiredSignature)),
+            "{timestamp}: {result:?}"
+        );
+    }
+}
+
+#[test]
+fn test_create_key_command() {
+    let config = Config::try_parse_from([
+        "indexer",
+        "--webhook-secret",
+        "secret",
+        "api-key",
+        "create",
+        "--name",
+        "agent",
+        "--scope",
+        "read",
+        "--scope",
+        "webhooks:write",
+        "--rate-limit",
+        "120",
+    ])
+    .unwrap();
+
+    let Some(Command::ApiKey(ApiKeyCommand::Create(args))) = config.command else {
+        panic!("Expected api-key create");
+    };
+    assert_eq!(args.name, "agent");
+    assert_eq!(args.scopes, vec![ApiScope::Read, ApiScope::WebhooksWrite]);
+    assert_eq!(args.rate_limit, Some(120));
+    assert!(!args.signing);
+
+    assert!(Config::try_parse_from([
+        "indexer",
+        "--webhook-secret",
+        "secret",
+        "api-key",
+        "create",
+        "--name",
+        "agent",
+        "--scope",
+        "read",
+        "--rate-limit",
+        "0",
+    ])
+    .is_err());
+}
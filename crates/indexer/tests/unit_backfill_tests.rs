@@ -0,0 +1,193 @@
+//! Unit tests for backfill cursors, maintenance commands and verification
+
+mod fixtures;
+
+use chrono::{TimeZone, Utc};
+use clap::Parser;
+use fixtures::ProposalBuilder;
+use indexer::{
+    config::{parse_since, registry::RegistryFormat, Command, Config, ProtocolRegistry},
+    db::repositories::proposal::StoredProposal,
+    models::{
+        proposal::ProposalStatus, revision::content_hash, BackfillSource, Proposal, ProtocolId,
+    },
+    services::{
+        data_sources::SnapshotCursor,
+        verify::{check_proposal, IssueKind},
+    },
+    utils::id::ChainId,
+};
+
+const REGISTRY: &str = r#"
+[[protocols]]
+id = "uniswap"
+name = "Uniswap"
+chain_id = 1
+snapshot_spaces = ["uniswapgovernance.eth"]
+"#;
+
+fn proposal(protocol: &str) -> Proposal {
+    let protocol_id = ProtocolId::new(ChainId::eip155(1), protocol).unwrap();
+    ProposalBuilder::new(
+        protocol_id,
+        "snapshot",
+        "0x3a1f6e4f3b1f3c7a9d2e5b8c0f4a6d2e9b7c1a3f5e8d0b2c4a6f8e1d3b5c7a9f",
+    )
+    .description("Allocate 1M UNI to grants")
+    .author("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed")
+    .build()
+}
+
+fn stored(proposal: Proposal) -> StoredProposal {
+    StoredProposal {
+        revision_hash: Some(content_hash(&proposal)),
+        history_status: Some(proposal.status),
+        proposal,
+    }
+}
+
+fn issue_kinds(stored: &StoredProposal) -> Vec<IssueKind> {
+    let registry = ProtocolRegistry::parse(REGISTRY, RegistryFormat::Toml).unwrap();
+    check_proposal(stored, &registry)
+        .into_iter()
+        .map(|issue| issue.kind)
+        .collect()
+}
+
+#[test]
+fn test_snapshot_cursor_pages() {
+    let start = SnapshotCursor {
+        created: 100,
+        skip: 0,
+    };
+
+    let next = start.next(&[101, 102, 102], 3).unwrap();
+    assert_eq!(
+        next,
+        SnapshotCursor {
+            created: 102,
+            skip: 2
+        }
+    );
+
+    // Proposals of the same second on consecutive pages add up
+    let after = next.next(&[102, 102, 102], 3).unwrap();
+    assert_eq!(
+        after,
+        SnapshotCursor {
+            created: 102,
+            skip: 5
+        }
+    );
+
+    assert_eq!(after.next(&[103], 3), None);
+    assert_eq!(start.next(&[], 3), None);
+}
+
+#[test]
+fn test_snapshot_cursor_round_trip() {
+    let cursor = SnapshotCursor {
+        created: 1_672_531_200,
+        skip: 4,
+    };
+
+    assert_eq!(cursor.to_string(), "1672531200:4");
+    assert_eq!("1672531200:4".parse(), Ok(cursor));
+    assert!("1672531200".parse::<SnapshotCursor>().is_err());
+    assert!("abc:1".parse::<SnapshotCursor>().is_err());
+}
+
+#[test]
+fn test_parse_since() {
+    assert_eq!(
+        parse_since("2023-01-01"),
+        Ok(Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 0).unwrap())
+    );
+    assert_eq!(
+        parse_since("2023-01-01T12:30:00+02:00"),
+        Ok(Utc.with_ymd_and_hms(2023, 1, 1, 10, 30, 0).unwrap())
+    );
+    assert!(parse_since("01/01/2023").is_err());
+}
+
+#[test]
+fn test_parse_maintenance_commands() {
+    let config = Config::try_parse_from([
+        "indexer",
+        "--webhook-secret",
+        "secret",
+        "backfill",
+        "--source",
+        "snapshot",
+        "--space",
+        "arbitrumfoundation.eth",
+        "--since",
+        "2023-01-01",
+    ])
+    .unwrap();
+    match config.command {
+        Some(Command::Backfill(args)) => {
+            assert_eq!(args.source, BackfillSource::Snapshot);
+            assert_eq!(args.space.as_deref(), Some("arbitrumfoundation.eth"));
+            assert_eq!(
+                args.since,
+                Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 0).unwrap()
+            );
+            assert!(!args.restart);
+        }
+        other => panic!("expected backfill command, got {other:?}"),
+    }
+
+    let config = Config::try_parse_from([
+        "indexer",
+        "--webhook-secret",
+        "secret",
+        "reindex",
+        "--proposal",
+        "eip155:1:uniswap:snapshot:0xabc",
+        "--proposal",
+        "eip155:1:uniswap:snapshot:0xdef",
+    ])
+    .unwrap();
+    match config.command {
+        Some(Command::Reindex(args)) => assert_eq!(args.proposals.len(), 2),
+        other => panic!("expected reindex command, got {other:?}"),
+    }
+
+    // A Snapshot backfill needs a space
+    assert!(Config::try_parse_from([
+        "indexer",
+        "--webhook-secret",
+        "secret",
+        "backfill",
+        "--source",
+        "snapshot",
+    ])
+    .is_err());
+}
+
+#[test]
+fn test_check_proposal() {
+    assert!(issue_kinds(&stored(proposal("uniswap"))).is_empty());
+
+    let mut edited = stored(proposal("uniswap"));
+    edited.proposal.title = "Fund the grants program twice".to_string();
+    edited.history_status = Some(ProposalStatus::Pending);
+    assert_eq!(
+        issue_kinds(&edited),
+        vec![IssueKind::StaleRevision, IssueKind::StatusMismatch]
+    );
+
+    let mut bare = stored(proposal("uniswap"));
+    bare.revision_hash = None;
+    bare.history_status = None;
+    assert_eq!(
+        issue_kinds(&bare),
+        vec![IssueKind::MissingRevision, IssueKind::StatusMismatch]
+    );
+
+    assert_eq!(
+        issue_kinds(&stored(proposal("compound"))),
+        vec![IssueKind::UnknownProtocol]
+    );
+}
@@ -0,0 +1,58 @@
+//! Unit tests for change log entries
+
+use chrono::Utc;
+use indexer::models::{Change, ChangeOperation, EntityType};
+use serde_json::json;
+
+#[test]
+fn test_entity_type_round_trip() {
+    for entity_type in [EntityType::Proposal, EntityType::Protocol] {
+        assert_eq!(entity_type.as_str().parse::<EntityType>(), Ok(entity_type));
+    }
+    assert!("actor".parse::<EntityType>().is_err());
+}
+
+#[test]
+fn test_change_operation_round_trip() {
+    for operation in [ChangeOperation::Upsert, ChangeOperation::Delete] {
+        assert_eq!(operation.as_str().parse::<ChangeOperation>(), Ok(operation));
+    }
+    assert!("insert".parse::<ChangeOperation>().is_err());
+}
+
+#[test]
+fn test_upsert_serialization() {
+    let change = Change {
+        seq: 42,
+        entity_type: EntityType::Protocol,
+        entity_id: "eip155:42161:arbitrum".to_string(),
+        operation: ChangeOperation::Upsert,
+        data: Some(json!({"id": "arbitrum", "enabled": true})),
+        changed_at: Utc::now(),
+    };
+
+    let value = serde_json::to_value(&change).unwrap();
+    assert_eq!(value["seq"], 42);
+    assert_eq!(value["entity_type"], "protocol");
+    assert_eq!(value["operation"], "upsert");
+    assert_eq!(value["data"]["id"], "arbitrum");
+}
+
+#[test]
+fn test_tombstone_serialization() {
+    let change = Change {
+        seq: 43,
+        entity_type: EntityType::Proposal,
+        entity_id: "eip155:1:uniswap:tally:42".to_string(),
+        operation: ChangeOperation::Delete,
+        data: None,
+        changed_at: Utc::now(),
+    };
+
+    let value = serde_json::to_value(&change).unwrap();
+    assert_eq!(value["operation"], "delete");
+    assert!(value["data"].is_null());
+
+    let parsed: Change = serde_json::from_value(value).unwrap();
+    assert_eq!(parsed, change);
+}
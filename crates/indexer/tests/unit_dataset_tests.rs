@@ -0,0 +1,301 @@
+//! Unit tests for training dataset records and splits
+
+mod fixtures;
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use fixtures::ProposalBuilder;
+use indexer::{
+    models::{
+        dataset::{DatasetSplit, Outcome, RecordLabel, SplitRatios},
+        label::{CriterionStatus, LabelCriteria, LabelResolution},
+        proposal::{ProposalStatus, VotingType},
+        LabelValue, Proposal, ProposalLabel, ProtocolId,
+    },
+    services::dataset::{
+        assign_splits, author_metrics, build_records, is_valid_version, majority_label,
+        parquet_schema, stratum,
+    },
+    utils::id::ChainId,
+};
+
+fn start() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()
+}
+
+fn proposal(protocol: &str, native_id: &str, author: &str, status: ProposalStatus) -> Proposal {
+    let protocol_id = ProtocolId::new(ChainId::eip155(1), protocol).unwrap();
+    let created_at = start() + Duration::days(native_id.parse().unwrap());
+    ProposalBuilder::new(protocol_id, "tally", native_id)
+        .title(&format!("Proposal {native_id}"))
+        .description("Fund the grants program")
+        .status(status)
+        .author(author)
+        .created_at(created_at)
+        .voting(created_at, created_at + Duration::days(7))
+        .voting_type(VotingType::Basic)
+        .scores(&[10.0, 5.0])
+        .scores_total(15.0)
+        .build()
+}
+
+fn label(proposal: &Proposal, labeler: &str, label: LabelValue) -> ProposalLabel {
+    ProposalLabel {
+        proposal_id: proposal.id.clone(),
+        labeler: labeler.to_string(),
+        label,
+        criteria: LabelCriteria::default(),
+        rationale: None,
+        created_at: start(),
+    }
+}
+
+fn record_label(label: LabelValue) -> RecordLabel {
+    RecordLabel {
+        labeler: "alice".to_string(),
+        label,
+        criteria: LabelCriteria::default(),
+        rationale: None,
+    }
+}
+
+/// Proposals of two protocols, alternating between passed and failed
+fn proposals(count: usize) -> Vec<Proposal> {
+    (0..count)
+        .map(|i| {
+            let protocol = if i % 2 == 0 { "uniswap" } else { "aave" };
+            let status = if i % 4 < 2 {
+                ProposalStatus::Accepted
+            } else {
+                ProposalStatus::Rejected
+            };
+            proposal(protocol, &i.to_string(), "0xauthor", status)
+        })
+        .collect()
+}
+
+#[test]
+fn test_split_ratios() {
+    assert_eq!(SplitRatios::default().to_string(), "5:10:5");
+    assert_eq!(
+        "8:1:1".parse::<SplitRatios>().unwrap(),
+        SplitRatios {
+            train: 8,
+            validation: 1,
+            test: 1,
+        }
+    );
+    assert!("8:1".parse::<SplitRatios>().is_err());
+    assert!("0:0:0".parse::<SplitRatios>().is_err());
+    assert!("a:b:c".parse::<SplitRatios>().is_err());
+    assert!("4294967295:1:0".parse::<SplitRatios>().is_err());
+
+    // Ratios from JSON are validated separately
+    let overflowing = SplitRatios {
+        train: u32::MAX,
+        validation: u32::MAX,
+        test: 0,
+    };
+    assert_eq!(overflowing.total(), None);
+    assert!(overflowing.validate().is_err());
+    assert!(SplitRatios {
+        train: 0,
+        validation: 0,
+        test: 0,
+    }
+    .validate()
+    .is_err());
+    assert!(SplitRatios::default().validate().is_ok());
+}
+
+#[test]
+fn test_outcomes() {
+    assert_eq!(Outcome::of(ProposalStatus::Executed), Some(Outcome::Passed));
+    assert_eq!(Outcome::of(ProposalStatus::Rejected), Some(Outcome::Failed));
+    assert_eq!(Outcome::of(ProposalStatus::Active), None);
+    assert_eq!(Outcome::of(ProposalStatus::Cancelled), None);
+}
+
+#[test]
+fn test_versions() {
+    assert!(is_valid_version("2024-06-01"));
+    assert!(is_valid_version("v1.2_rc"));
+    assert!(!is_valid_version(""));
+    assert!(!is_valid_version(".hidden"));
+    assert!(!is_valid_version("../escape"));
+    assert!(!is_valid_version("a/b"));
+}
+
+#[test]
+fn test_author_metrics_only_count_earlier_proposals() {
+    let first = proposal("uniswap", "1", "0xAuthor", ProposalStatus::Accepted);
+    let second = proposal("aave", "2", "0xauthor", ProposalStatus::Rejected);
+    let third = proposal("uniswap", "3", "0xAUTHOR", ProposalStatus::Active);
+    let other = proposal("uniswap", "4", "0xother", ProposalStatus::Accepted);
+    let metrics = author_metrics(&[third.clone(), other.clone(), second.clone(), first.clone()]);
+
+    assert_eq!(metrics[&first.id].prior_proposals, 0);
+    assert_eq!(metrics[&second.id].prior_proposals, 1);
+    assert_eq!(metrics[&second.id].prior_passed, 1);
+    assert_eq!(metrics[&third.id].prior_proposals, 2);
+    assert_eq!(metrics[&third.id].prior_failed, 1);
+    assert_eq!(metrics[&other.id].prior_proposals, 0);
+}
+
+#[test]
+fn test_majority_label() {
+    assert_eq!(majority_label(&[]), None);
+    assert_eq!(
+        majority_label(&[
+            record_label(LabelValue::Good),
+            record_label(LabelValue::Bad),
+            record_label(LabelValue::Good),
+        ]),
+        Some(LabelValue::Good)
+    );
+    assert_eq!(
+        majority_label(&[
+            record_label(LabelValue::Good),
+            record_label(LabelValue::Bad)
+        ]),
+        None
+    );
+}
+
+#[test]
+fn test_records_only_include_closed_votes() {
+    let accepted = proposal("uniswap", "1", "0xauthor", ProposalStatus::Accepted);
+    let active = proposal("uniswap", "2", "0xauthor", ProposalStatus::Active);
+    let labels = vec![
+        label(&accepted, "alice", LabelValue::Good),
+        label(&accepted, "bob", LabelValue::Good),
+        label(&active, "alice", LabelValue::Bad),
+    ];
+
+    let records = build_records(
+        &[active, accepted.clone()],
+        &labels,
+        &[],
+        &SplitRatios::default(),
+        "wei",
+    );
+
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].id, accepted.id.to_string());
+    assert_eq!(records[0].outcome, Outcome::Passed);
+    assert_eq!(records[0].labels.len(), 2);
+    assert_eq!(records[0].label, Some(LabelValue::Good));
+    assert_eq!(records[0].criteria, Some(LabelCriteria::default()));
+}
+
+#[test]
+fn test_resolutions_override_majority() {
+    let accepted = proposal("uniswap", "1", "0xauthor", ProposalStatus::Accepted);
+    let criteria = LabelCriteria {
+        budget: CriterionStatus::Fail,
+        ..Default::default()
+    };
+    let mut disagreeing = label(&accepted, "bob", LabelValue::Bad);
+    disagreeing.criteria = criteria;
+    let labels = vec![label(&accepted, "alice", LabelValue::Good), disagreeing];
+
+    let records = build_records(
+        std::slice::from_ref(&accepted),
+        &labels,
+        &[],
+        &SplitRatios::default(),
+        "wei",
+    );
+    assert_eq!(records[0].label, None);
+    assert_eq!(records[0].criteria, None);
+
+    let resolution = LabelResolution {
+        proposal_id: accepted.id.clone(),
+        label: LabelValue::Bad,
+        criteria,
+        resolved_by: "carol".to_string(),
+        rationale: None,
+        resolved_at: start(),
+    };
+    let records = build_records(
+        &[accepted],
+        &labels,
+        &[resolution],
+        &SplitRatios::default(),
+        "wei",
+    );
+    assert_eq!(records[0].label, Some(LabelValue::Bad));
+    assert_eq!(records[0].criteria, Some(criteria));
+}
+
+#[test]
+fn test_splits_are_stratified() {
+    let ratios = SplitRatios::default();
+    let records = build_records(&proposals(80), &[], &[], &ratios, "wei");
+
+    let mut strata: HashMap<String, HashMap<DatasetSplit, usize>> = HashMap::new();
+    for record in &records {
+        *strata
+            .entry(stratum(record))
+            .or_default()
+            .entry(record.split)
+            .or_default() += 1;
+    }
+
+    assert_eq!(strata.len(), 4);
+    for splits in strata.values() {
+        assert_eq!(splits[&DatasetSplit::Train], 5);
+        assert_eq!(splits[&DatasetSplit::Validation], 10);
+        assert_eq!(splits[&DatasetSplit::Test], 5);
+    }
+
+    // Weights whose sum overflows a u32 still split without panicking
+    let mut records = records;
+    let halves = SplitRatios {
+        train: u32::MAX,
+        validation: u32::MAX,
+        test: 0,
+    };
+    assign_splits(&mut records, &halves, "wei");
+    let train = records
+        .iter()
+        .filter(|record| record.split == DatasetSplit::Train)
+        .count();
+    assert_eq!(train, 40);
+}
+
+#[test]
+fn test_splits_are_deterministic() {
+    let ratios = SplitRatios::default();
+    let splits = |seed: &str| -> Vec<DatasetSplit> {
+        let records = build_records(&proposals(40), &[], &[], &ratios, seed);
+        records.iter().map(|record| record.split).collect()
+    };
+
+    assert_eq!(splits("wei"), splits("wei"));
+    let mut records = build_records(&proposals(40), &[], &[], &ratios, "wei");
+    assign_splits(&mut records, &ratios, "wei");
+    assert_eq!(
+        records.iter().map(|r| r.split).collect::<Vec<_>>(),
+        splits("wei")
+    );
+    assert_ne!(splits("wei"), splits("other"));
+
+    let mut reversed = proposals(40);
+    reversed.reverse();
+    let records = build_records(&reversed, &[], &[], &ratios, "wei");
+    assert_eq!(
+        records.iter().map(|r| r.split).collect::<Vec<_>>(),
+        splits("wei")
+    );
+}
+
+#[test]
+fn test_parquet_schema_matches_records() {
+    let schema = parquet_schema();
+    assert!(schema.field_with_name("labels").is_ok());
+    assert!(schema.field_with_name("criteria").is_ok());
+    assert!(schema.field_with_name("author_prior_proposals").is_ok());
+    assert!(schema.field_with_name("split").is_ok());
+}
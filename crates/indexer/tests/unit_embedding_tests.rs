@@ -0,0 +1,90 @@
+//! Unit tests for proposal chunking and embedding providers
+
+use indexer::{
+    config::{EmbeddingConfig, EmbeddingProviderKind},
+    services::embeddings::{
+        chunk_text, provider_from_config, EmbeddingProvider, MockEmbeddingProvider,
+        EMBEDDING_DIMENSIONS,
+    },
+};
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn config(provider: Option<EmbeddingProviderKind>) -> EmbeddingConfig {
+    EmbeddingConfig {
+        provider,
+        base_url: "https://api.openai.com/v1".to_string(),
+        api_key: None,
+        model: "text-embedding-3-small".to_string(),
+    }
+}
+
+#[test]
+fn test_short_text_is_one_chunk() {
+    let text = "Fund the grants program\n\nAllocate 1M ARB to grants";
+    assert_eq!(chunk_text(text, 100), vec![text.to_string()]);
+    assert!(chunk_text("  \n\n  ", 100).is_empty());
+}
+
+#[test]
+fn test_paragraphs_are_packed_into_chunks() {
+    let text = "aaaa aaaa\n\nbbbb bbbb\n\ncccc cccc";
+    assert_eq!(
+        chunk_text(text, 20),
+        vec!["aaaa aaaa\n\nbbbb bbbb", "cccc cccc"]
+    );
+}
+
+#[test]
+fn test_long_paragraphs_are_split_between_words() {
+    let paragraph = "word ".repeat(30);
+    let chunks = chunk_text(&paragraph, 24);
+
+    assert!(chunks.iter().all(|chunk| chunk.chars().count() <= 24));
+    assert!(chunks.iter().all(|chunk| !chunk.starts_with(' ')));
+    assert_eq!(chunks.join(" "), paragraph.trim());
+}
+
+#[test]
+fn test_long_words_are_split() {
+    let chunks = chunk_text("short 0123456789abcdef end", 6);
+    assert_eq!(chunks, vec!["short", "012345", "6789ab", "cdef", "end"]);
+}
+
+#[test]
+fn test_mock_embeddings_are_normalized_and_deterministic() {
+    let embedding = MockEmbeddingProvider::embed_text("Fund the grants program");
+
+    assert_eq!(embedding.len(), EMBEDDING_DIMENSIONS);
+    assert!((cosine(&embedding, &embedding) - 1.0).abs() < 1e-5);
+    assert_eq!(
+        embedding,
+        MockEmbeddingProvider::embed_text("fund THE grants, program")
+    );
+}
+
+#[tokio::test]
+async fn test_mock_embeddings_rank_related_text_higher() {
+    let texts = vec![
+        "Allocate ARB to the grants program for builders".to_string(),
+        "Grants program for builders funded with ARB".to_string(),
+        "Upgrade the bridge contract to the new implementation".to_string(),
+    ];
+    let vectors = MockEmbeddingProvider.embed(&texts).await.unwrap();
+
+    assert_eq!(vectors.len(), 3);
+    assert!(cosine(&vectors[0], &vectors[1]) > cosine(&vectors[0], &vectors[2]));
+}
+
+#[test]
+fn test_provider_from_config() {
+    assert!(provider_from_config(&config(None)).is_none());
+
+    let mock = provider_from_config(&config(Some(EmbeddingProviderKind::Mock))).unwrap();
+    assert_eq!(mock.model(), MockEmbeddingProvider::MODEL);
+
+    let openai = provider_from_config(&config(Some(EmbeddingProviderKind::Openai))).unwrap();
+    assert_eq!(openai.model(), "text-embedding-3-small");
+}
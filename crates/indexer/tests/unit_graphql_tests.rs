@@ -0,0 +1,142 @@
+//! Unit tests for the Snapshot-compatible GraphQL API
+//!
+//! The queries below are answered from the registry or rejected before the
+//! database is reached, so the pool is never connected.
+
+use std::sync::Arc;
+
+use indexer::{
+    api::graphql::{build_schema, snapshot_state, GraphqlSchema},
+    config::{registry::RegistryFormat, ProtocolRegistry},
+    models::proposal::ProposalStatus,
+};
+use serde_json::{json, Value};
+use sqlx::postgres::PgPoolOptions;
+use tokio::sync::watch;
+
+const REGISTRY: &str = r#"
+[[protocols]]
+id = "arbitrum"
+name = "Arbitrum DAO"
+chain_id = 42161
+snapshot_spaces = ["arbitrumfoundation.eth", "arbitrum-odyssey.eth"]
+tally_organization = "arbitrum"
+forum_urls = ["https://forum.arbitrum.foundation"]
+
+[[protocols]]
+id = "compound"
+name = "Compound"
+chain_id = 1
+tally_organization = "compound"
+
+[[protocols]]
+id = "uniswap"
+name = "Uniswap"
+chain_id = 1
+snapshot_spaces = ["uniswapgovernance.eth"]
+enabled = false
+"#;
+
+fn schema() -> GraphqlSchema {
+    let registry = ProtocolRegistry::parse(REGISTRY, RegistryFormat::Toml).unwrap();
+    let (_, receiver) = watch::channel(Arc::new(registry));
+    let db = PgPoolOptions::new()
+        .connect_lazy("postgres://localhost/unreachable")
+        .unwrap();
+
+    build_schema(db, receiver)
+}
+
+async fn execute(query: &str) -> (Value, Vec<String>) {
+    let response = schema().execute(query).await;
+    let errors = response.errors.iter().map(|e| e.message.clone()).collect();
+
+    (response.data.into_json().unwrap(), errors)
+}
+
+#[tokio::test]
+async fn test_spaces_list_enabled_protocols() {
+    let (data, errors) = execute(
+        r#"{ spaces(first: 10, skip: 0, orderBy: "created", orderDirection: desc,
+                    where: { verified: true }) {
+            id name network verified members protocol tally_organization
+        } }"#,
+    )
+    .await;
+
+    assert!(errors.is_empty(), "{errors:?}");
+    assert_eq!(
+        data["spaces"],
+        json!([
+            {
+                "id": "arbitrumfoundation.eth",
+                "name": "Arbitrum DAO",
+                "network": "42161",
+                "verified": true,
+                "members": 0,
+                "protocol": "eip155:42161:arbitrum",
+                "tally_organization": "arbitrum"
+            },
+            {
+                "id": "compound",
+                "name": "Compound",
+                "network": "1",
+                "verified": true,
+                "members": 0,
+                "protocol": "eip155:1:compound",
+                "tally_organization": "compound"
+            }
+        ])
+    );
+}
+
+#[tokio::test]
+async fn test_space_by_any_snapshot_space() {
+    let (data, errors) =
+        execute(r#"{ space(id: "arbitrum-odyssey.eth") { id snapshot_spaces forums } }"#).await;
+
+    assert!(errors.is_empty(), "{errors:?}");
+    assert_eq!(data["space"]["id"], "arbitrumfoundation.eth");
+    assert_eq!(
+        data["space"]["forums"],
+        json!(["https://forum.arbitrum.foundation"])
+    );
+
+    let (data, _) = execute(r#"{ space(id: "uniswapgovernance.eth") { id } }"#).await;
+    assert!(data["space"].is_null());
+}
+
+#[tokio::test]
+async fn test_proposals_of_untracked_space_are_empty() {
+    let (data, errors) =
+        execute(r#"{ proposals(where: { space: "unknown.eth" }) { id title } }"#).await;
+
+    assert!(errors.is_empty(), "{errors:?}");
+    assert_eq!(data["proposals"], json!([]));
+}
+
+#[tokio::test]
+async fn test_invalid_proposal_arguments() {
+    let (_, errors) = execute(r#"{ proposals(first: 5000) { id } }"#).await;
+    assert_eq!(errors, vec!["`first` must be between 0 and 1000"]);
+
+    let (_, errors) = execute(r#"{ proposals(orderBy: "votes") { id } }"#).await;
+    assert_eq!(errors, vec!["Invalid orderBy: votes"]);
+
+    let (_, errors) = execute(r#"{ proposals(where: { state: "open" }) { id } }"#).await;
+    assert_eq!(errors, vec!["Invalid state: open"]);
+}
+
+#[test]
+fn test_snapshot_state() {
+    assert_eq!(snapshot_state(ProposalStatus::Pending), "pending");
+    assert_eq!(snapshot_state(ProposalStatus::Active), "active");
+    for status in [
+        ProposalStatus::Accepted,
+        ProposalStatus::Rejected,
+        ProposalStatus::Cancelled,
+        ProposalStatus::Executed,
+    ] {
+        assert_eq!(snapshot_state(status), "closed");
+    }
+}
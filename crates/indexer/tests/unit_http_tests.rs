@@ -0,0 +1,227 @@
+//! Unit tests for the rate-limited data source HTTP client
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use axum::{http::StatusCode, response::IntoResponse, routing::any, Router};
+use chrono::{TimeZone, Utc};
+use indexer::{
+    config::HttpConfig,
+    services::http::{
+        parse_retry_after, CircuitBreaker, CircuitState, HttpClient, HttpError, RetryPolicy,
+        TokenBucket,
+    },
+};
+
+/// Serve `/` answering with `statuses` in turn, then `200`, returning the base URL
+async fn serve(statuses: Vec<StatusCode>) -> String {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let app = Router::new().route(
+        "/",
+        any(move || {
+            let calls = calls.clone();
+            let statuses = statuses.clone();
+            async move {
+                let call = calls.fetch_add(1, Ordering::SeqCst);
+                match statuses.get(call) {
+                    Some(&status) => (status, [("retry-after", "0")]).into_response(),
+                    None => StatusCode::OK.into_response(),
+                }
+            }
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{address}/")
+}
+
+fn client(max_retries: u32, failure_threshold: u32) -> HttpClient {
+    HttpClient::with_limits(
+        HashMap::from([("127.0.0.1".to_string(), 600)]),
+        &HttpConfig {
+            max_retries,
+            failure_threshold,
+            cooldown_secs: 60,
+            timeout_secs: 30,
+            connect_timeout_secs: 10,
+        },
+    )
+}
+
+#[test]
+fn test_token_bucket() {
+    let start = Instant::now();
+    let mut bucket = TokenBucket::new(2, start);
+
+    assert_eq!(bucket.try_acquire(start), Ok(()));
+    assert_eq!(bucket.try_acquire(start), Ok(()));
+    assert_eq!(bucket.try_acquire(start), Err(Duration::from_secs(30)));
+
+    // Refilled at two tokens a minute
+    assert!(bucket.try_acquire(start + Duration::from_secs(20)).is_err());
+    assert_eq!(bucket.try_acquire(start + Duration::from_secs(30)), Ok(()));
+    assert_eq!(bucket.try_acquire(start + Duration::from_secs(600)), Ok(()));
+    assert_eq!(bucket.try_acquire(start + Duration::from_secs(600)), Ok(()));
+    assert!(bucket
+        .try_acquire(start + Duration::from_secs(600))
+        .is_err());
+}
+
+#[test]
+fn test_circuit_breaker() {
+    let start = Instant::now();
+    let mut breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+
+    assert!(!breaker.record_failure(start));
+    assert_eq!(breaker.state(start), CircuitState::Closed);
+    assert!(breaker.record_failure(start));
+    assert_eq!(breaker.state(start), CircuitState::Open);
+    assert_eq!(
+        breaker.retry_in(start + Duration::from_secs(15)),
+        Some(Duration::from_secs(45))
+    );
+
+    // After the cooldown one failure opens the breaker again
+    let later = start + Duration::from_secs(60);
+    assert_eq!(breaker.state(later), CircuitState::HalfOpen);
+    assert_eq!(breaker.retry_in(later), None);
+    assert!(breaker.record_failure(later));
+    assert_eq!(breaker.state(later), CircuitState::Open);
+
+    breaker.record_success();
+    assert_eq!(breaker.state(later), CircuitState::Closed);
+    assert!(!breaker.record_failure(later));
+}
+
+#[test]
+fn test_retry_delay() {
+    let policy = RetryPolicy {
+        max_retries: 5,
+        base_delay: Duration::from_secs(1),
+        max_delay: Duration::from_secs(8),
+    };
+
+    assert_eq!(policy.delay(0, None, 0.0), Duration::from_millis(500));
+    assert_eq!(policy.delay(0, None, 1.0), Duration::from_secs(1));
+    assert_eq!(policy.delay(2, None, 0.5), Duration::from_secs(3));
+    assert_eq!(policy.delay(10, None, 1.0), Duration::from_secs(8));
+
+    // Retry-After is a lower bound, up to the longest delay
+    assert_eq!(
+        policy.delay(0, Some(Duration::from_secs(5)), 1.0),
+        Duration::from_secs(5)
+    );
+    assert_eq!(
+        policy.delay(0, Some(Duration::from_secs(3600)), 1.0),
+        Duration::from_secs(8)
+    );
+    assert_eq!(
+        policy.delay(3, Some(Duration::ZERO), 0.0),
+        Duration::from_secs(4)
+    );
+}
+
+#[test]
+fn test_parse_retry_after() {
+    let now = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+
+    assert_eq!(
+        parse_retry_after("120", now),
+        Some(Duration::from_secs(120))
+    );
+    assert_eq!(
+        parse_retry_after("Mon, 01 Jan 2024 00:00:30 GMT", now),
+        Some(Duration::from_secs(30))
+    );
+    assert_eq!(
+        parse_retry_after("Sun, 31 Dec 2023 23:59:00 GMT", now),
+        Some(Duration::ZERO)
+    );
+    assert_eq!(parse_retry_after("soon", now), None);
+}
+
+#[tokio::test]
+async fn test_retries_rate_limited_requests() {
+    let url = serve(vec![
+        StatusCode::TOO_MANY_REQUESTS,
+        StatusCode::SERVICE_UNAVAILABLE,
+    ])
+    .await;
+    let http = client(2, 5);
+
+    let response = http.send(http.post(&url)).await.unwrap();
+    assert_eq!(response.status().as_u16(), StatusCode::OK.as_u16());
+
+    let metrics = http.metrics();
+    assert_eq!(metrics.len(), 1);
+    assert_eq!(metrics[0].host, "127.0.0.1");
+    assert_eq!(metrics[0].requests_per_minute, Some(600));
+    assert_eq!(metrics[0].requests, 3);
+    assert_eq!(metrics[0].rate_limited, 1);
+    assert_eq!(metrics[0].retries, 2);
+    assert_eq!(metrics[0].failures, 0);
+    assert_eq!(metrics[0].circuit, CircuitState::Closed);
+}
+
+#[tokio::test]
+async fn test_circuit_breaker_marks_host_unavailable() {
+    let url = serve(vec![StatusCode::BAD_GATEWAY, StatusCode::BAD_GATEWAY]).await;
+    let http = client(0, 1);
+    assert!(http.is_available(&url));
+
+    // Retries are exhausted and the last response is returned
+    let response = http.send(http.post(&url)).await.unwrap();
+    assert_eq!(response.status().as_u16(), StatusCode::BAD_GATEWAY.as_u16());
+    assert!(!http.is_available(&url));
+
+    let rejected = http.send(http.post(&url)).await;
+    assert!(matches!(rejected, Err(HttpError::CircuitOpen { .. })));
+
+    let metrics = &http.metrics()[0];
+    assert_eq!(metrics.requests, 1);
+    assert_eq!(metrics.failures, 1);
+    assert_eq!(metrics.rejected, 1);
+    assert_eq!(metrics.circuit_opened, 1);
+    assert_eq!(metrics.circuit, CircuitState::Open);
+}
+
+#[tokio::test]
+async fn test_timed_out_requests_are_retried_and_open_the_breaker() {
+    let app = Router::new().route(
+        "/",
+        any(|| async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            StatusCode::OK
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    let http = HttpClient::with_limits(
+        HashMap::new(),
+        &HttpConfig {
+            max_retries: 1,
+            failure_threshold: 1,
+            cooldown_secs: 60,
+            timeout_secs: 1,
+            connect_timeout_secs: 1,
+        },
+    );
+
+    let result = http.send(http.get(&url)).await;
+    assert!(matches!(result, Err(HttpError::Request(e)) if e.is_timeout()));
+    assert!(!http.is_available(&url));
+
+    let metrics = &http.metrics()[0];
+    assert_eq!(metrics.requests, 2);
+    assert_eq!(metrics.retries, 1);
+    assert_eq!(metrics.failures, 1);
+    assert_eq!(metrics.circuit, CircuitState::Open);
+}
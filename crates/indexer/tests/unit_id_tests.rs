@@ -0,0 +1,126 @@
+//! Unit tests for canonical identifiers
+
+use indexer::{
+    models::ProtocolId,
+    utils::id::{AccountId, ChainId, IdError, ProposalId},
+};
+
+fn arbitrum() -> ProtocolId {
+    ProtocolId::new(ChainId::eip155(42161), "arbitrum").unwrap()
+}
+
+#[test]
+fn test_chain_id_roundtrip() {
+    let chain_id: ChainId = "eip155:42161".parse().unwrap();
+    assert_eq!(chain_id, ChainId::eip155(42161));
+    assert_eq!(chain_id.as_eip155(), Some(42161));
+    assert_eq!(chain_id.to_string(), "eip155:42161");
+
+    let cosmos: ChainId = "cosmos:cosmoshub-3".parse().unwrap();
+    assert_eq!(cosmos.namespace(), "cosmos");
+    assert_eq!(cosmos.as_eip155(), None);
+}
+
+#[test]
+fn test_chain_id_rejects_malformed() {
+    for value in [
+        "",
+        "eip155",
+        "eip155:",
+        "EIP155:1",
+        "e:1",
+        "eip155:1:2",
+        "eip155:1 ",
+    ] {
+        assert!(value.parse::<ChainId>().is_err(), "accepted {value:?}");
+    }
+}
+
+#[test]
+fn test_account_id_parse() {
+    let account: AccountId = "eip155:1:0xab16a96D359eC26a11e2C2b3d8f8B8942d5Bfcdb"
+        .parse()
+        .unwrap();
+    assert_eq!(account.chain_id(), &ChainId::eip155(1));
+    assert_eq!(
+        account.address(),
+        "0xab16a96D359eC26a11e2C2b3d8f8B8942d5Bfcdb"
+    );
+    assert_eq!(
+        account.to_string(),
+        "eip155:1:0xab16a96D359eC26a11e2C2b3d8f8B8942d5Bfcdb"
+    );
+
+    assert!("eip155:1".parse::<AccountId>().is_err());
+    assert!("eip155:1:0xab:cd".parse::<AccountId>().is_err());
+}
+
+#[test]
+fn test_protocol_id_roundtrip() {
+    let protocol_id: ProtocolId = "eip155:42161:arbitrum".parse().unwrap();
+    assert_eq!(protocol_id, arbitrum());
+    assert_eq!(protocol_id.to_string(), "eip155:42161:arbitrum");
+
+    assert!("eip155:42161".parse::<ProtocolId>().is_err());
+    assert!("eip155:42161:Arbitrum".parse::<ProtocolId>().is_err());
+    assert!("eip155:42161:arbitrum:extra".parse::<ProtocolId>().is_err());
+}
+
+#[test]
+fn test_proposal_id_roundtrip() {
+    let snapshot_id = "0x1f3a8b1c7e5a6d04b2c9f0e3d8a7b6c5d4e3f2a1b0c9d8e7f6a5b4c3d2e1f0a9";
+    let id = ProposalId::new(arbitrum(), "snapshot", snapshot_id).unwrap();
+    let formatted = id.to_string();
+    assert_eq!(
+        formatted,
+        format!("eip155:42161:arbitrum:snapshot:{snapshot_id}")
+    );
+
+    let parsed: ProposalId = formatted.parse().unwrap();
+    assert_eq!(parsed, id);
+    assert_eq!(parsed.protocol_id(), &arbitrum());
+    assert_eq!(parsed.source(), "snapshot");
+    assert_eq!(parsed.native_id(), snapshot_id);
+}
+
+#[test]
+fn test_proposal_id_native_id_with_colons() {
+    let id = ProposalId::new(arbitrum(), "forum", "t:aip-1:post:42").unwrap();
+    let parsed: ProposalId = id.to_string().parse().unwrap();
+    assert_eq!(parsed.native_id(), "t:aip-1:post:42");
+    assert_eq!(parsed, id);
+}
+
+#[test]
+fn test_proposal_id_rejects_malformed() {
+    for value in [
+        "",
+        "eip155:42161:arbitrum:snapshot",
+        "eip155:42161:arbitrum:snapshot:",
+        "eip155:42161:arbitrum:Snapshot:1",
+        "eip155:42161::snapshot:1",
+        "1:arbitrum:arbitrum:snapshot:1",
+        "eip155:42161:arbitrum:tally:has space",
+    ] {
+        assert!(value.parse::<ProposalId>().is_err(), "accepted {value:?}");
+    }
+
+    let too_long = "1".repeat(257);
+    assert!(matches!(
+        ProposalId::new(arbitrum(), "tally", &too_long),
+        Err(IdError::Segment { .. })
+    ));
+}
+
+#[test]
+fn test_ids_serialize_as_strings() {
+    let id = ProposalId::new(arbitrum(), "tally", "1234").unwrap();
+    let json = serde_json::to_string(&id).unwrap();
+    assert_eq!(json, "\"eip155:42161:arbitrum:tally:1234\"");
+    assert_eq!(serde_json::from_str::<ProposalId>(&json).unwrap(), id);
+
+    let json = serde_json::to_string(&arbitrum()).unwrap();
+    assert_eq!(json, "\"eip155:42161:arbitrum\"");
+
+    assert!(serde_json::from_str::<ProposalId>("\"42161:arbitrum:arbitrum:1\"").is_err());
+}
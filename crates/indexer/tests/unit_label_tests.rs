@@ -0,0 +1,167 @@
+//! Unit tests for human labels, agreement and conflict resolution
+
+use chrono::{TimeZone, Utc};
+use indexer::{
+    models::{
+        label::{CriterionStatus, LabelCriteria, LabelCriterion, LabelResolution},
+        LabelValue, ProposalLabel, ProtocolId,
+    },
+    services::labeling::{agreement_report, cohens_kappa, conflicts, ground_truth},
+    utils::id::{ChainId, ProposalId},
+};
+
+fn proposal_id(native_id: &str) -> ProposalId {
+    let protocol_id = ProtocolId::new(ChainId::eip155(1), "uniswap").unwrap();
+    ProposalId::new(protocol_id, "tally", native_id).unwrap()
+}
+
+fn label(native_id: &str, labeler: &str, label: LabelValue) -> ProposalLabel {
+    ProposalLabel {
+        proposal_id: proposal_id(native_id),
+        labeler: labeler.to_string(),
+        label,
+        criteria: LabelCriteria::default(),
+        rationale: None,
+        created_at: Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+    }
+}
+
+fn resolution(native_id: &str, label: LabelValue) -> LabelResolution {
+    LabelResolution {
+        proposal_id: proposal_id(native_id),
+        label,
+        criteria: LabelCriteria::default(),
+        resolved_by: "carol".to_string(),
+        rationale: Some("Budget is not broken down".to_string()),
+        resolved_at: Utc.with_ymd_and_hms(2024, 1, 2, 0, 0, 0).unwrap(),
+    }
+}
+
+#[test]
+fn test_criteria_match_agent_categories() {
+    let json = serde_json::to_value(LabelCriteria {
+        budget: CriterionStatus::Fail,
+        language_quality: CriterionStatus::Pass,
+        ..Default::default()
+    })
+    .unwrap();
+
+    assert_eq!(json["budget"], "fail");
+    assert_eq!(json["language_quality"], "pass");
+    assert_eq!(json["goals_and_motivation"], "n/a");
+    for criterion in LabelCriterion::ALL {
+        assert!(json.get(criterion.as_str()).is_some());
+    }
+    assert!(serde_json::from_str::<LabelCriteria>(r#"{"budget":"pass"}"#).is_err());
+    assert_eq!("n/a".parse(), Ok(CriterionStatus::NotApplicable));
+}
+
+#[test]
+fn test_cohens_kappa() {
+    use LabelValue::{Bad, Good};
+
+    assert_eq!(cohens_kappa::<LabelValue>(&[]), None);
+    assert_eq!(cohens_kappa(&[(Good, Good), (Bad, Bad)]), Some(1.0));
+    assert_eq!(cohens_kappa(&[(Good, Good), (Good, Good)]), None);
+    assert_eq!(cohens_kappa(&[(Good, Bad), (Bad, Good)]), Some(-1.0));
+
+    // 20 of 25 agree, chance agreement is (10 * 15 + 15 * 10) / 625 = 0.48
+    let mut pairs = vec![(Good, Good); 10];
+    pairs.extend(vec![(Bad, Bad); 10]);
+    pairs.extend(vec![(Bad, Good); 5]);
+    let kappa = cohens_kappa(&pairs).unwrap();
+    assert!((kappa - (0.8 - 0.48) / (1.0 - 0.48)).abs() < 1e-9);
+}
+
+#[test]
+fn test_agreement_report() {
+    let mut budget_fail = label("2", "bob", LabelValue::Bad);
+    budget_fail.criteria.budget = CriterionStatus::Fail;
+    let labels = vec![
+        label("1", "bob", LabelValue::Good),
+        label("1", "alice", LabelValue::Good),
+        label("2", "alice", LabelValue::Bad),
+        budget_fail,
+        label("3", "alice", LabelValue::Good),
+        label("3", "carol", LabelValue::Bad),
+        label("4", "carol", LabelValue::Good),
+    ];
+
+    let report = agreement_report(&labels);
+    assert_eq!(report.labelers, 3);
+    assert_eq!(report.proposals, 4);
+    assert_eq!(report.labels, 7);
+
+    let overall = report.overall.unwrap();
+    assert_eq!(overall.label.items, 3);
+    assert!((overall.label.observed - 2.0 / 3.0).abs() < 1e-9);
+    assert_eq!(overall.criteria[&LabelCriterion::Budget].items, 3);
+    assert_eq!(
+        overall.criteria[&LabelCriterion::LanguageQuality].kappa,
+        None
+    );
+
+    assert_eq!(report.pairs.len(), 2);
+    assert_eq!(report.pairs[0].labelers, ["alice", "bob"]);
+    assert_eq!(report.pairs[0].agreement.label.kappa, Some(1.0));
+    assert_eq!(report.pairs[1].labelers, ["alice", "carol"]);
+    assert_eq!(report.pairs[1].agreement.label.observed, 0.0);
+}
+
+#[test]
+fn test_conflicts_exclude_resolved_proposals() {
+    let mut criterion_only = label("2", "bob", LabelValue::Good);
+    criterion_only.criteria.budget = CriterionStatus::Pass;
+    let labels = vec![
+        label("1", "alice", LabelValue::Good),
+        label("1", "bob", LabelValue::Bad),
+        label("2", "alice", LabelValue::Good),
+        criterion_only,
+        label("3", "alice", LabelValue::Good),
+        label("3", "bob", LabelValue::Good),
+        label("4", "alice", LabelValue::Good),
+        label("4", "bob", LabelValue::Bad),
+    ];
+
+    let found = conflicts(&labels, &[resolution("4", LabelValue::Bad)]);
+    assert_eq!(found.len(), 2);
+    assert_eq!(found[0].proposal_id, proposal_id("1"));
+    assert!(found[0].label);
+    assert!(found[0].criteria.is_empty());
+    assert_eq!(found[1].proposal_id, proposal_id("2"));
+    assert!(!found[1].label);
+    assert_eq!(found[1].criteria, vec![LabelCriterion::Budget]);
+    assert_eq!(found[1].labels.len(), 2);
+}
+
+#[test]
+fn test_ground_truth_prefers_resolutions() {
+    let labels = vec![
+        label("1", "alice", LabelValue::Good),
+        label("1", "bob", LabelValue::Bad),
+        label("2", "alice", LabelValue::Good),
+        label("2", "bob", LabelValue::Bad),
+        label("3", "alice", LabelValue::Good),
+    ];
+
+    let truths = ground_truth(
+        &labels,
+        &[
+            resolution("2", LabelValue::Bad),
+            resolution("5", LabelValue::Good),
+        ],
+    );
+    let ids: Vec<_> = truths.iter().map(|t| t.proposal_id.clone()).collect();
+    assert_eq!(
+        ids,
+        vec![proposal_id("2"), proposal_id("3"), proposal_id("5")]
+    );
+
+    assert_eq!(truths[0].label, LabelValue::Bad);
+    assert_eq!(truths[0].labels, 2);
+    assert_eq!(truths[0].resolved_by.as_deref(), Some("carol"));
+    assert_eq!(truths[1].label, LabelValue::Good);
+    assert_eq!(truths[1].criteria, Some(LabelCriteria::default()));
+    assert_eq!(truths[1].resolved_by, None);
+    assert_eq!(truths[2].labels, 0);
+}
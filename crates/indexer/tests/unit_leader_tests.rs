@@ -0,0 +1,107 @@
+//! Unit tests for leader election and crawl scheduling
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use indexer::{
+    config::HttpConfig,
+    models::ProtocolId,
+    services::{
+        crawler::CrawlSchedule,
+        data_sources::{DataSource, SnapshotDataSource, TallyDataSource},
+        leader::HeldLeases,
+        HttpClient, LeaderElection,
+    },
+    utils::id::ChainId,
+};
+use sqlx::postgres::PgPoolOptions;
+
+/// Election whose database cannot be reached, so no lease is ever renewed
+fn unreachable_leader(ttl: Duration) -> LeaderElection {
+    let db = PgPoolOptions::new()
+        .acquire_timeout(Duration::from_millis(50))
+        .connect_lazy("postgres://localhost:1/unreachable")
+        .unwrap();
+    LeaderElection::new(db, "replica-a".to_string(), ttl)
+}
+
+fn http() -> HttpClient {
+    HttpClient::with_limits(
+        HashMap::new(),
+        &HttpConfig {
+            max_retries: 0,
+            failure_threshold: 5,
+            cooldown_secs: 60,
+            timeout_secs: 30,
+            connect_timeout_secs: 10,
+        },
+    )
+}
+
+#[test]
+fn test_lease_keys() {
+    let protocol_id = ProtocolId::new(ChainId::eip155(42161), "arbitrum").unwrap();
+    let snapshot = SnapshotDataSource::new(
+        http(),
+        "https://hub.snapshot.org".to_string(),
+        None,
+        protocol_id.clone(),
+        "arbitrumfoundation.eth".to_string(),
+    );
+    let tally = TallyDataSource::new(
+        http(),
+        "https://api.tally.xyz".to_string(),
+        None,
+        protocol_id,
+        "arbitrum".to_string(),
+    );
+
+    assert_eq!(snapshot.lease_key(), "snapshot:arbitrumfoundation.eth");
+    assert_eq!(tally.lease_key(), "tally:arbitrum");
+}
+
+#[test]
+fn test_crawl_schedule() {
+    let start = Instant::now();
+    let mut schedule = CrawlSchedule::default();
+
+    assert!(schedule.is_due("snapshot:ens.eth", start));
+    schedule.crawled("snapshot:ens.eth", start, Duration::from_secs(300));
+    assert!(!schedule.is_due("snapshot:ens.eth", start + Duration::from_secs(299)));
+    assert!(schedule.is_due("snapshot:ens.eth", start + Duration::from_secs(300)));
+    assert!(schedule.is_due("tally:ens", start));
+
+    // A lease taken over from another replica is crawled right away
+    schedule.forget("snapshot:ens.eth");
+    assert!(schedule.is_due("snapshot:ens.eth", start));
+}
+
+#[test]
+fn test_default_replica_ids_differ() {
+    let first = LeaderElection::default_replica_id();
+    let second = LeaderElection::default_replica_id();
+
+    assert_ne!(first, second);
+    assert_eq!(first.rsplit_once('-').unwrap().1.len(), 8);
+}
+
+#[tokio::test]
+async fn test_lease_lost_while_held() {
+    // Renewals keep failing, so the lease may expire after the second one
+    let leader = unreachable_leader(Duration::from_millis(600));
+    let start = Instant::now();
+    leader.hold("snapshot:ens.eth").await;
+    assert!(start.elapsed() >= Duration::from_millis(400));
+    assert!(start.elapsed() < Duration::from_millis(600));
+
+    // Work is not started without the lease
+    let mut leases = HeldLeases::new(leader, "crawls");
+    leases.renew(&["snapshot:ens.eth".to_string()]).await;
+    assert!(!leases.is_due("snapshot:ens.eth"));
+    let work = leases.run("snapshot:ens.eth", Duration::from_secs(300), async {
+        panic!("crawled without the lease")
+    });
+    assert_eq!(work.await, None::<()>);
+}
@@ -0,0 +1,137 @@
+//! Unit tests for proposal status transitions and lifecycle events
+
+mod fixtures;
+
+use chrono::{Duration, Utc};
+use fixtures::ProposalBuilder;
+use indexer::{
+    models::{
+        proposal::{ProposalStatus, VotingType, OUTCOME_REVISION_WINDOW},
+        IndexerEvent, Proposal, ProtocolId, StatusChange, StatusTrigger,
+    },
+    utils::id::{ChainId, ProposalId},
+};
+
+fn proposal_id() -> ProposalId {
+    let protocol_id = ProtocolId::new(ChainId::eip155(1), "uniswap").unwrap();
+    ProposalId::new(protocol_id, "tally", "42").unwrap()
+}
+
+fn proposal(status: ProposalStatus) -> Proposal {
+    let now = Utc::now();
+    ProposalBuilder::new(proposal_id().protocol_id().clone(), "tally", "42")
+        .title("Upgrade the fee switch")
+        .description("Turn on protocol fees")
+        .status(status)
+        .choices(&["For", "Against", "Abstain"])
+        .created_at(now)
+        .voting(now + Duration::days(1), now + Duration::days(8))
+        .voting_type(VotingType::Basic)
+        .build()
+}
+
+fn change(from: Option<ProposalStatus>, to: ProposalStatus) -> StatusChange {
+    StatusChange {
+        proposal_id: proposal_id(),
+        from,
+        to,
+        triggered_by: StatusTrigger::Timer,
+        changed_at: Utc::now(),
+    }
+}
+
+#[test]
+fn test_statuses_only_move_forward() {
+    use ProposalStatus::*;
+
+    assert!(Pending.can_transition_to(Active));
+    assert!(Active.can_transition_to(Accepted));
+    assert!(Active.can_transition_to(Cancelled));
+    assert!(Accepted.can_transition_to(Executed));
+    assert!(Executed.can_transition_to(Executed));
+
+    assert!(!Executed.can_transition_to(Active));
+    assert!(!Executed.can_transition_to(Rejected));
+    assert!(!Active.can_transition_to(Pending));
+    assert!(!Accepted.can_transition_to(Active));
+    assert!(!Rejected.can_transition_to(Executed));
+    assert!(!Cancelled.can_transition_to(Pending));
+    assert!(!Accepted.can_transition_to(Rejected));
+    assert!(!Rejected.can_transition_to(Accepted));
+}
+
+#[test]
+fn test_outcome_settles_after_revision_window() {
+    use ProposalStatus::*;
+
+    let accepted = proposal(Accepted);
+    let end = accepted.voting_end.unwrap();
+
+    // Final scores may revise an outcome shortly after voting closed
+    assert!(accepted.can_transition_to(Rejected, end + Duration::hours(1)));
+    assert!(proposal(Rejected).can_transition_to(Accepted, end));
+    assert!(!accepted.can_transition_to(Rejected, end + OUTCOME_REVISION_WINDOW));
+    assert!(accepted.can_transition_to(Executed, end + OUTCOME_REVISION_WINDOW));
+
+    let mut unknown_end = proposal(Accepted);
+    unknown_end.voting_end = None;
+    assert!(!unknown_end.can_transition_to(Rejected, end));
+    assert!(!proposal(Executed).can_transition_to(Rejected, end));
+}
+
+#[test]
+fn test_next_transition_follows_voting_window() {
+    let pending = proposal(ProposalStatus::Pending);
+    let start = pending.voting_start.unwrap();
+    let end = pending.voting_end.unwrap();
+
+    assert_eq!(pending.next_transition_at(Utc::now()), Some(start));
+    assert_eq!(pending.next_transition_at(start), Some(end));
+    assert_eq!(pending.next_transition_at(end), None);
+
+    let executed = proposal(ProposalStatus::Executed);
+    assert_eq!(executed.next_transition_at(Utc::now()), None);
+}
+
+#[test]
+fn test_status_changes_map_to_lifecycle_events() {
+    use ProposalStatus::*;
+
+    assert_eq!(
+        IndexerEvent::for_status_change(&change(None, Pending)),
+        None
+    );
+    assert!(matches!(
+        IndexerEvent::for_status_change(&change(Some(Pending), Active)),
+        Some(IndexerEvent::VotingStarted { .. })
+    ));
+    assert!(matches!(
+        IndexerEvent::for_status_change(&change(Some(Active), Rejected)),
+        Some(IndexerEvent::VotingEnded {
+            outcome: Rejected,
+            ..
+        })
+    ));
+    assert!(matches!(
+        IndexerEvent::for_status_change(&change(Some(Accepted), Executed)),
+        Some(IndexerEvent::StatusChanged {
+            from: Accepted,
+            to: Executed,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_voting_ended_event_serialization() {
+    let event = IndexerEvent::for_status_change(&change(
+        Some(ProposalStatus::Active),
+        ProposalStatus::Accepted,
+    ))
+    .unwrap();
+
+    let json = serde_json::to_value(&event).unwrap();
+    assert_eq!(json["type"], "voting_ended");
+    assert_eq!(json["outcome"], "Accepted");
+    assert_eq!(serde_json::from_value::<IndexerEvent>(json).unwrap(), event);
+}
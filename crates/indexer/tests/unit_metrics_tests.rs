@@ -0,0 +1,119 @@
+//! Unit tests for Prometheus metrics
+
+use std::time::Duration;
+
+use axum::http::Method;
+use indexer::services::{
+    auth::required_scope,
+    http::{CircuitState, HostMetrics},
+    metrics::{Gauges, Metrics},
+};
+
+fn host(name: &str, circuit: CircuitState) -> HostMetrics {
+    HostMetrics {
+        host: name.to_string(),
+        requests_per_minute: Some(60),
+        circuit,
+        requests: 12,
+        throttled: 1,
+        rate_limited: 2,
+        retries: 3,
+        failures: 1,
+        rejected: 4,
+        circuit_opened: 1,
+    }
+}
+
+#[test]
+fn test_histogram_buckets() {
+    let metrics = Metrics::default();
+    metrics.record_fetch(
+        "snapshot",
+        "eip155:1:uniswap",
+        Duration::from_millis(200),
+        true,
+    );
+    metrics.record_fetch(
+        "snapshot",
+        "eip155:1:uniswap",
+        Duration::from_millis(200),
+        true,
+    );
+    metrics.record_fetch(
+        "snapshot",
+        "eip155:1:uniswap",
+        Duration::from_secs(120),
+        false,
+    );
+    let rendered = metrics.render(&Gauges::default());
+
+    let labels = r#"source="snapshot",protocol="eip155:1:uniswap""#;
+    // Buckets are cumulative and slow fetches only land in +Inf
+    for line in [
+        format!(r#"wei_indexer_source_fetch_duration_seconds_bucket{{{labels},le="0.1"}} 0"#),
+        format!(r#"wei_indexer_source_fetch_duration_seconds_bucket{{{labels},le="0.25"}} 2"#),
+        format!(r#"wei_indexer_source_fetch_duration_seconds_bucket{{{labels},le="60"}} 2"#),
+        format!(r#"wei_indexer_source_fetch_duration_seconds_bucket{{{labels},le="+Inf"}} 3"#),
+        format!("wei_indexer_source_fetch_duration_seconds_count{{{labels}}} 3"),
+        format!("wei_indexer_source_fetch_duration_seconds_sum{{{labels}}} 120.4"),
+        format!(r#"wei_indexer_source_fetches_total{{{labels},outcome="success"}} 2"#),
+        format!(r#"wei_indexer_source_fetches_total{{{labels},outcome="error"}} 1"#),
+    ] {
+        assert!(rendered.contains(&line), "missing {line} in\n{rendered}");
+    }
+}
+
+#[test]
+fn test_render() {
+    let metrics = Metrics::default();
+    metrics.record_ingested("tally", "eip155:42161:arbitrum", 3, 1);
+    metrics.record_ingested("tally", "eip155:42161:arbitrum", 2, 0);
+    metrics.record_delivery(true);
+    metrics.record_delivery(false);
+    metrics.record_delivery(false);
+    metrics.record_webhook_disabled();
+
+    let gauges = Gauges {
+        sync_lag: vec![(
+            ("snapshot".to_string(), "eip155:1:\"quoted\"".to_string()),
+            90.5,
+        )],
+        pool_size: 5,
+        pool_idle: 2,
+        pool_max: 10,
+        hosts: vec![host("hub.snapshot.org", CircuitState::Open)],
+    };
+    let rendered = metrics.render(&gauges);
+
+    for line in [
+        "# TYPE wei_indexer_items_ingested_total counter",
+        r#"wei_indexer_items_ingested_total{source="tally",protocol="eip155:42161:arbitrum",outcome="stored"} 5"#,
+        r#"wei_indexer_items_ingested_total{source="tally",protocol="eip155:42161:arbitrum",outcome="quarantined"} 1"#,
+        "# TYPE wei_indexer_sync_lag_seconds gauge",
+        r#"wei_indexer_sync_lag_seconds{source="snapshot",protocol="eip155:1:\"quoted\""} 90.5"#,
+        r#"wei_indexer_webhook_deliveries_total{outcome="success"} 1"#,
+        r#"wei_indexer_webhook_deliveries_total{outcome="error"} 2"#,
+        "wei_indexer_webhooks_disabled_total 1",
+        r#"wei_indexer_db_pool_connections{state="active"} 3"#,
+        r#"wei_indexer_db_pool_connections{state="idle"} 2"#,
+        "wei_indexer_db_pool_max_connections 10",
+        r#"wei_indexer_http_requests_total{host="hub.snapshot.org"} 12"#,
+        r#"wei_indexer_http_rejected_total{host="hub.snapshot.org"} 4"#,
+        r#"wei_indexer_http_circuit_open{host="hub.snapshot.org"} 1"#,
+    ] {
+        assert!(rendered.contains(line), "missing {line} in\n{rendered}");
+    }
+
+    // Every family is declared once, before its samples
+    for family in rendered.lines().filter_map(|l| l.strip_prefix("# TYPE ")) {
+        let name = family.split(' ').next().unwrap();
+        assert_eq!(rendered.matches(&format!("# TYPE {name} ")).count(), 1);
+    }
+}
+
+#[test]
+fn test_probes_are_public() {
+    assert_eq!(required_scope(&Method::GET, "/ready"), None);
+    assert_eq!(required_scope(&Method::GET, "/metrics"), None);
+    assert!(required_scope(&Method::GET, "/metrics/extra").is_some());
+}
@@ -0,0 +1,263 @@
+//! Unit tests for the ingestion pipeline's normalization and validation
+
+use indexer::{
+    config::{registry::RegistryFormat, ProtocolRegistry},
+    models::{
+        proposal::{ProposalStatus, VotingType},
+        ProtocolId, RawRecord,
+    },
+    services::pipeline::{decoded_transfers, process},
+    utils::{id::ChainId, validation::ValidationRule},
+};
+use serde_json::{json, Value};
+
+const REGISTRY: &str = r#"
+[[protocols]]
+id = "arbitrum"
+name = "Arbitrum DAO"
+chain_id = 42161
+snapshot_spaces = ["arbitrumfoundation.eth"]
+tally_organization = "arbitrum"
+"#;
+
+const SNAPSHOT_ID: &str = "0x1f3a8b1c7e5a6d04b2c9f0e3d8a7b6c5d4e3f2a1b0c9d8e7f6a5b4c3d2e1f0a9";
+
+fn registry() -> ProtocolRegistry {
+    ProtocolRegistry::parse(REGISTRY, RegistryFormat::Toml).unwrap()
+}
+
+fn arbitrum() -> ProtocolId {
+    ProtocolId::new(ChainId::eip155(42161), "arbitrum").unwrap()
+}
+
+fn snapshot_payload() -> Value {
+    json!({
+        "id": SNAPSHOT_ID,
+        "title": "Fund the grants program",
+        "body": "Allocate 1M ARB to grants",
+        "choices": ["For", "Against", "Abstain"],
+        "author": "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed",
+        "state": "active",
+        "created": 1_700_000_000,
+        "start": 1_700_086_400,
+        "end": 1_700_691_200,
+        "snapshot": "18500000",
+        "type": "single-choice",
+        "scores": [0, 0, 0],
+        "scores_total": 0,
+        "quorum": 0,
+        "discussion": "https://forum.arbitrum.foundation/t/grants/123",
+        "ipfs": "bafkreib4ytnsq7ec3zvxoyiwtcx7k4ewtwzpxu7yrqd5exz7c6g7pyb2nm",
+        "space": { "id": "arbitrumfoundation.eth" }
+    })
+}
+
+fn failed_rules(record: &RawRecord) -> Vec<(String, ValidationRule)> {
+    process(record, &registry())
+        .unwrap_err()
+        .errors
+        .into_iter()
+        .map(|e| (e.field, e.rule))
+        .collect()
+}
+
+#[test]
+fn test_snapshot_record_is_normalized() {
+    let record = RawRecord::new("snapshot", arbitrum(), snapshot_payload());
+    let proposal = process(&record, &registry()).unwrap();
+
+    assert_eq!(
+        proposal.id.to_string(),
+        format!("eip155:42161:arbitrum:snapshot:{SNAPSHOT_ID}")
+    );
+    assert_eq!(proposal.protocol_id, arbitrum());
+    assert_eq!(proposal.status, ProposalStatus::Active);
+    assert_eq!(proposal.choices.len(), 3);
+    assert_eq!(
+        proposal.author,
+        "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+    );
+    assert_eq!(proposal.created_at.timestamp(), 1_700_000_000);
+    assert_eq!(proposal.voting_start.unwrap().timestamp(), 1_700_086_400);
+    assert_eq!(proposal.voting_end.unwrap().timestamp(), 1_700_691_200);
+    assert_eq!(proposal.snapshot_block, Some(18_500_000));
+    assert_eq!(proposal.voting_type, VotingType::SingleChoice);
+    assert_eq!(proposal.scores, vec![0.0, 0.0, 0.0]);
+    assert_eq!(proposal.quorum, None);
+    assert_eq!(
+        proposal.discussion_url.as_deref(),
+        Some("https://forum.arbitrum.foundation/t/grants/123")
+    );
+    assert!(proposal.ipfs_hash.is_some());
+    assert_eq!(
+        proposal.source_url,
+        Some(format!(
+            "https://snapshot.org/#/arbitrumfoundation.eth/proposal/{SNAPSHOT_ID}"
+        ))
+    );
+}
+
+#[test]
+fn test_invalid_snapshot_links_are_dropped() {
+    let mut payload = snapshot_payload();
+    payload["discussion"] = json!("see forum");
+    payload["type"] = json!("shielded-vote");
+    let record = RawRecord::new("snapshot", arbitrum(), payload);
+
+    // An unknown voting type matters for counting, a bad link does not
+    assert_eq!(
+        failed_rules(&record),
+        vec![("type".to_string(), ValidationRule::Malformed)]
+    );
+}
+
+#[test]
+fn test_closed_snapshot_outcome_from_scores() {
+    let mut payload = snapshot_payload();
+    payload["state"] = json!("closed");
+    payload["scores"] = json!([120.5, 30.0, 2.0]);
+    let record = RawRecord::new("snapshot", arbitrum(), payload.clone());
+    assert_eq!(
+        process(&record, &registry()).unwrap().status,
+        ProposalStatus::Accepted
+    );
+
+    payload["scores"] = json!([10.0, 30.0, 2.0]);
+    let record = RawRecord::new("snapshot", arbitrum(), payload.clone());
+    assert_eq!(
+        process(&record, &registry()).unwrap().status,
+        ProposalStatus::Rejected
+    );
+
+    payload["scores"] = json!([0, 0, 0]);
+    let record = RawRecord::new("snapshot", arbitrum(), payload.clone());
+    assert_eq!(
+        process(&record, &registry()).unwrap().status,
+        ProposalStatus::Rejected
+    );
+
+    payload.as_object_mut().unwrap().remove("scores");
+    let record = RawRecord::new("snapshot", arbitrum(), payload);
+    assert_eq!(
+        failed_rules(&record),
+        vec![("scores".to_string(), ValidationRule::Malformed)]
+    );
+}
+
+#[test]
+fn test_malformed_snapshot_record_reports_payload_paths() {
+    let mut payload = snapshot_payload();
+    payload.as_object_mut().unwrap().remove("title");
+    payload["choices"] = json!("For");
+    payload["space"]["id"] = json!("uniswapgovernance.eth");
+    let record = RawRecord::new("snapshot", arbitrum(), payload);
+
+    assert_eq!(
+        failed_rules(&record),
+        vec![
+            ("title".to_string(), ValidationRule::Malformed),
+            ("choices".to_string(), ValidationRule::Malformed),
+            ("space.id".to_string(), ValidationRule::UnknownSnapshotSpace),
+        ]
+    );
+}
+
+#[test]
+fn test_garbage_content_is_rejected_after_normalization() {
+    let mut payload = snapshot_payload();
+    payload["body"] = json!("");
+    payload["author"] = json!("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD");
+    let record = RawRecord::new("snapshot", arbitrum(), payload);
+
+    assert_eq!(
+        failed_rules(&record),
+        vec![
+            ("description".to_string(), ValidationRule::Required),
+            ("author".to_string(), ValidationRule::Checksum),
+        ]
+    );
+}
+
+#[test]
+fn test_tally_record_is_normalized() {
+    let payload = json!({
+        "id": "2262526998447146",
+        "onchainId": "53472400873981607449547539050199074000442490831067826984987297151333310022877",
+        "status": "Executed",
+        "metadata": { "title": "AIP-1.2", "description": "Foundation and DAO governance" },
+        "proposer": { "address": "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359" },
+        "createdAt": "2023-04-11T16:02:31Z",
+        "quorum": "300000000000000000000000000",
+        "start": { "number": 79_000_000, "timestamp": "2023-04-14T16:02:31Z" },
+        "end": { "timestamp": "2023-04-28T16:02:31Z" },
+        "voteStats": [
+            { "type": "for", "votesCount": "180000000000000000000000000" },
+            { "type": "against", "votesCount": "1000000000000000000000000" },
+            { "type": "abstain", "votesCount": "150000000000000000000000000" },
+            { "type": "pendingfor", "votesCount": "1" }
+        ],
+        "organization": { "slug": "arbitrum" }
+    });
+    let record = RawRecord::new("tally", arbitrum(), payload);
+    let proposal = process(&record, &registry()).unwrap();
+
+    assert_eq!(proposal.id.source(), "tally");
+    assert!(proposal.id.native_id().starts_with("5347240087"));
+    assert_eq!(proposal.status, ProposalStatus::Executed);
+    assert_eq!(proposal.choices, vec!["For", "Against", "Abstain"]);
+    assert_eq!(proposal.voting_type, VotingType::Basic);
+    assert_eq!(proposal.snapshot_block, Some(79_000_000));
+    assert_eq!(proposal.scores, vec![1.8e26, 1e24, 1.5e26]);
+    assert_eq!(proposal.scores_total, Some(1.8e26 + 1e24 + 1.5e26));
+    assert_eq!(proposal.outcome(), Some(ProposalStatus::Accepted));
+    assert!(proposal
+        .source_url
+        .unwrap()
+        .starts_with("https://www.tally.xyz/gov/arbitrum/proposal/5347240087"));
+}
+
+#[test]
+fn test_tally_transfers_are_decoded() {
+    let payload = json!({
+        "onchainId": "1",
+        "executableCalls": [
+            {
+                "target": "0xbFc1FECa8B09A5c5D3EFfE7429eBE24b9c09EF58",
+                "calldata": "0x"
+            },
+            {
+                "target": "0x912CE59144191C1204E64559FE8253a0e49E6548",
+                "calldata": "0xa9059cbb\
+                             000000000000000000000000fb6916095ca1df60bb79ce92ce3ea74c37c5d359\
+                             00000000000000000000000000000000000000000000003635c9adc5dea00000"
+            }
+        ]
+    });
+    let transfers = decoded_transfers(&RawRecord::new("tally", arbitrum(), payload)).unwrap();
+
+    assert_eq!(transfers.len(), 1);
+    assert_eq!(transfers[0].call_index, 1);
+    assert_eq!(
+        transfers[0].token,
+        "0x912ce59144191c1204e64559fe8253a0e49e6548"
+    );
+    assert_eq!(
+        transfers[0].recipient,
+        "0xfb6916095ca1df60bb79ce92ce3ea74c37c5d359"
+    );
+    assert_eq!(transfers[0].raw_amount, "1000000000000000000000");
+    assert_eq!(transfers[0].value, None);
+
+    // Snapshot proposals execute nothing
+    let record = RawRecord::new("snapshot", arbitrum(), snapshot_payload());
+    assert_eq!(decoded_transfers(&record), None);
+}
+
+#[test]
+fn test_unknown_source_is_quarantined() {
+    let record = RawRecord::new("forum", arbitrum(), snapshot_payload());
+    assert_eq!(
+        failed_rules(&record),
+        vec![("source".to_string(), ValidationRule::UnknownSource)]
+    );
+}
@@ -0,0 +1,284 @@
+//! Unit tests for token prices
+
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, Query},
+    http::{HeaderMap, StatusCode},
+    routing::get,
+    Json, Router,
+};
+use chrono::{Days, NaiveDate};
+use indexer::{
+    config::{registry::RegistryFormat, HttpConfig, ProtocolRegistry, RegistryError},
+    models::DailyPrice,
+    services::{
+        http::HttpClient,
+        prices::{
+            token_value, CoinGeckoPriceProvider, CsvPriceProvider, MockPriceProvider, PriceError,
+            PriceProvider,
+        },
+    },
+};
+use serde_json::json;
+
+const ARB: &str = "0x912CE59144191C1204E64559FE8253a0e49E6548";
+
+const REGISTRY: &str = r#"
+[[tokens]]
+chain_id = 42161
+symbol = "ETH"
+decimals = 18
+price_id = "ethereum"
+
+[[tokens]]
+chain_id = 42161
+address = "0x912CE59144191C1204E64559FE8253a0e49E6548"
+symbol = "ARB"
+decimals = 18
+price_id = "arbitrum"
+"#;
+
+fn day(value: &str) -> NaiveDate {
+    value.parse().unwrap()
+}
+
+fn price(asset: &str, on: &str, price_usd: f64) -> DailyPrice {
+    DailyPrice {
+        asset: asset.to_string(),
+        day: day(on),
+        price_usd,
+        source: "csv".to_string(),
+    }
+}
+
+#[test]
+fn test_parse_price_csv() {
+    let prices = CsvPriceProvider::parse(
+        "date,asset,price_usd\n\
+         # ARB before the unlock\n\
+         2026-03-01, arbitrum, 0.42\n\
+         \n\
+         \"2026-03-02\",\"ethereum\",\"2500.5\"\n",
+    )
+    .unwrap();
+    assert_eq!(
+        prices,
+        vec![
+            price("arbitrum", "2026-03-01", 0.42),
+            price("ethereum", "2026-03-02", 2500.5),
+        ]
+    );
+
+    let error = |contents: &str| CsvPriceProvider::parse(contents).unwrap_err().to_string();
+    assert!(error("2026-03-01,arbitrum").starts_with("Line 1: expected 3 fields"));
+    assert!(
+        error("date,asset,price_usd\n03/01/2026,arbitrum,0.42").contains("Line 2: invalid date")
+    );
+    assert!(error("2026-03-01,,0.42").contains("asset cannot be empty"));
+    assert!(error("2026-03-01,arbitrum,-1").contains("invalid price -1"));
+    assert!(error("2026-03-01,arbitrum,NaN").contains("invalid price NaN"));
+    // Only the first row can be a header
+    assert!(error("2026-03-01,arbitrum,0.42\ndate,asset,price_usd").contains("Line 2"));
+}
+
+#[test]
+fn test_registry_tokens() {
+    let registry = ProtocolRegistry::parse(REGISTRY, RegistryFormat::Toml).unwrap();
+    assert_eq!(registry.resolve_token(42161, "arb").unwrap().symbol, "ARB");
+    assert_eq!(
+        registry
+            .resolve_token(42161, &ARB.to_lowercase())
+            .unwrap()
+            .price_id,
+        "arbitrum"
+    );
+    assert_eq!(
+        registry.resolve_token(42161, "ETH"),
+        registry.native_token(42161)
+    );
+    assert_eq!(registry.resolve_token(1, "ARB"), None);
+    assert_eq!(registry.native_token(1), None);
+
+    let invalid = |contents: &str| match ProtocolRegistry::parse(contents, RegistryFormat::Toml) {
+        Err(RegistryError::Invalid { protocol, message }) => format!("{protocol}: {message}"),
+        other => panic!("expected validation error, got {other:?}"),
+    };
+    let wrapped = "symbol = \"WETH\"\naddress = \"0x82aF49447D8a07e3bd95BD0d56f35241523fBab1\"";
+    let duplicate = format!(
+        "{REGISTRY}{}",
+        REGISTRY.replace("symbol = \"ETH\"", wrapped)
+    );
+    assert!(invalid(&duplicate).contains("token ARB on chain 42161: symbol is already listed"));
+    let second_native = format!(
+        "{REGISTRY}[[tokens]]\nchain_id = 42161\nsymbol = \"XDAI\"\ndecimals = 18\nprice_id = \"xdai\"\n"
+    );
+    assert!(invalid(&second_native).contains("native currency is already listed"));
+    assert!(invalid(&REGISTRY.replace(ARB, "0x1234")).contains("invalid token address"));
+    assert!(invalid(&REGISTRY.replace("\"arbitrum\"", "\"\"")).contains("price ID"));
+    assert!(invalid(&REGISTRY.replace("chain_id = 42161", "chain_id = 0")).contains("chain ID"));
+    assert!(invalid(&REGISTRY.replace("\"ARB\"", "\"A RB\"")).contains("symbol"));
+}
+
+#[test]
+fn test_shipped_registry_tokens() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/config/protocols.toml");
+    let registry = ProtocolRegistry::load(std::path::Path::new(path)).unwrap();
+    // Every treasury holding can be valued
+    for entry in &registry.protocols {
+        if !entry.treasuries.is_empty() {
+            assert!(
+                registry.native_token(entry.chain_id).is_some(),
+                "{}",
+                entry.id
+            );
+        }
+        for token in &entry.treasury_tokens {
+            assert!(
+                registry
+                    .resolve_token(entry.chain_id, &token.address)
+                    .is_some(),
+                "{} of {}",
+                token.symbol,
+                entry.id
+            );
+        }
+    }
+}
+
+#[test]
+fn test_token_value() {
+    let registry = ProtocolRegistry::parse(REGISTRY, RegistryFormat::Toml).unwrap();
+    let arb = registry.resolve_token(42161, "ARB").unwrap();
+    let on = day("2026-03-10");
+
+    let value = token_value(
+        arb,
+        1_000.0,
+        Some(&price("arbitrum", "2026-03-04", 0.5)),
+        on,
+    )
+    .unwrap();
+    assert_eq!(value.chain_id, 42161);
+    assert_eq!(value.token.as_deref(), Some(ARB));
+    assert_eq!(value.symbol, "ARB");
+    assert_eq!(value.price_usd, 0.5);
+    assert_eq!(value.price_date, day("2026-03-04"));
+    assert_eq!(value.value_usd, 500.0);
+
+    let no_price = |price: Option<&DailyPrice>| {
+        matches!(
+            token_value(arb, 1.0, price, on),
+            Err(PriceError::NoPrice { asset, .. }) if asset == "arbitrum"
+        )
+    };
+    assert!(no_price(None));
+    assert!(no_price(Some(&price("arbitrum", "2026-03-02", 0.5))));
+    assert!(no_price(Some(&price("arbitrum", "2026-03-11", 0.5))));
+}
+
+#[tokio::test]
+async fn test_mock_and_csv_providers() {
+    let (from, to) = (day("2026-02-27"), day("2026-03-02"));
+    let prices = MockPriceProvider
+        .daily_prices("arbitrum", from, to)
+        .await
+        .unwrap();
+    assert_eq!(prices.len(), 4);
+    assert_eq!(prices[0].day, from);
+    assert_eq!(prices[3].day, to);
+    assert_eq!(
+        prices[1].price_usd,
+        MockPriceProvider::price("arbitrum", from + Days::new(1))
+    );
+    assert!(prices.iter().all(|price| price.price_usd >= 0.95));
+    assert_ne!(
+        MockPriceProvider::price("arbitrum", from),
+        MockPriceProvider::price("ethereum", from)
+    );
+
+    let path = std::env::temp_dir().join(format!("wei-prices-{}.csv", std::process::id()));
+    std::fs::write(
+        &path,
+        "date,asset,price_usd\n\
+         2026-02-26,arbitrum,0.40\n\
+         2026-02-28,arbitrum,0.41\n\
+         2026-02-28,ethereum,2400\n\
+         2026-03-03,arbitrum,0.43\n",
+    )
+    .unwrap();
+    let prices = CsvPriceProvider::new(path.clone())
+        .daily_prices("arbitrum", from, to)
+        .await;
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(prices.unwrap(), vec![price("arbitrum", "2026-02-28", 0.41)]);
+}
+
+#[tokio::test]
+async fn test_coingecko_daily_prices() {
+    let app = Router::new().route(
+        "/api/v3/coins/:id/market_chart/range",
+        get(
+            |Path(id): Path<String>,
+             Query(query): Query<HashMap<String, String>>,
+             headers: HeaderMap| async move {
+                if headers
+                    .get("x-cg-demo-api-key")
+                    .is_none_or(|key| key != "secret")
+                {
+                    return Err(StatusCode::UNAUTHORIZED);
+                }
+                if id != "arbitrum" {
+                    return Err(StatusCode::NOT_FOUND);
+                }
+                // 2026-03-01 and 2026-03-02 are requested, from midnight to the last second
+                assert_eq!(query["vs_currency"], "usd");
+                assert_eq!(query["from"], "1772323200");
+                assert_eq!(query["to"], "1772495999");
+                let hour = 3_600_000i64;
+                let midnight = 1_772_323_200_000i64;
+                Ok(Json(json!({
+                    "prices": [
+                        [midnight - hour, 0.39],
+                        [midnight + hour, 0.40],
+                        [midnight + 23 * hour, 0.41],
+                        [midnight + 25 * hour, 0.42],
+                        [midnight + 49 * hour, 0.43],
+                    ],
+                    "market_caps": [],
+                    "total_volumes": [],
+                })))
+            },
+        ),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    let http = HttpClient::with_limits(
+        HashMap::new(),
+        &HttpConfig {
+            max_retries: 0,
+            failure_threshold: 5,
+            cooldown_secs: 60,
+            timeout_secs: 30,
+            connect_timeout_secs: 10,
+        },
+    );
+    let base_url = format!("http://{address}/api/v3/");
+    let provider = CoinGeckoPriceProvider::new(http.clone(), &base_url, Some("secret".to_string()));
+    let (from, to) = (day("2026-03-01"), day("2026-03-02"));
+
+    let prices = provider.daily_prices("arbitrum", from, to).await.unwrap();
+    assert_eq!(
+        prices
+            .iter()
+            .map(|price| (price.day, price.price_usd, price.source.as_str()))
+            .collect::<Vec<_>>(),
+        vec![(from, 0.41, "coingecko"), (to, 0.42, "coingecko")]
+    );
+
+    assert!(provider.daily_prices("unknown", from, to).await.is_err());
+    let anonymous = CoinGeckoPriceProvider::new(http, &base_url, None);
+    assert!(anonymous.daily_prices("arbitrum", from, to).await.is_err());
+}
@@ -0,0 +1,108 @@
+//! Unit tests for proposal outcomes and status derivation
+
+mod fixtures;
+
+use chrono::{Duration, Utc};
+use fixtures::ProposalBuilder;
+use indexer::{
+    models::{
+        proposal::{ProposalStatus, VotingType},
+        Proposal, ProtocolId,
+    },
+    utils::id::ChainId,
+};
+
+fn proposal(status: ProposalStatus, voting_type: VotingType, scores: &[f64]) -> Proposal {
+    let protocol_id = ProtocolId::new(ChainId::eip155(42161), "arbitrum").unwrap();
+    let now = Utc::now();
+    ProposalBuilder::new(protocol_id, "tally", "42")
+        .status(status)
+        .choices(&["For", "Against", "Abstain"])
+        .created_at(now - Duration::days(5))
+        .voting(now - Duration::days(3), now + Duration::days(4))
+        .snapshot_block(18_000_000)
+        .voting_type(voting_type)
+        .scores(scores)
+        .build()
+}
+
+#[test]
+fn test_governor_outcome_counts_for_and_abstain_towards_quorum() {
+    let mut vote = proposal(
+        ProposalStatus::Active,
+        VotingType::Basic,
+        &[60.0, 40.0, 50.0],
+    );
+    assert_eq!(vote.outcome(), Some(ProposalStatus::Accepted));
+
+    vote.quorum = Some(100.0);
+    assert_eq!(vote.outcome(), Some(ProposalStatus::Accepted));
+
+    vote.quorum = Some(120.0);
+    assert_eq!(vote.outcome(), Some(ProposalStatus::Rejected));
+
+    vote.quorum = None;
+    vote.scores = vec![40.0, 40.0, 0.0];
+    assert_eq!(vote.outcome(), Some(ProposalStatus::Rejected));
+}
+
+#[test]
+fn test_single_choice_outcome() {
+    let mut vote = proposal(
+        ProposalStatus::Active,
+        VotingType::SingleChoice,
+        &[10.0, 30.0, 2.0],
+    );
+    assert_eq!(vote.outcome(), Some(ProposalStatus::Rejected));
+
+    vote.scores = vec![120.5, 30.0, 2.0];
+    vote.scores_total = Some(152.5);
+    vote.quorum = Some(200.0);
+    assert_eq!(vote.outcome(), Some(ProposalStatus::Rejected));
+
+    vote.quorum = Some(150.0);
+    assert_eq!(vote.outcome(), Some(ProposalStatus::Accepted));
+
+    vote.scores = vec![0.0, 0.0, 0.0];
+    assert_eq!(vote.outcome(), None);
+}
+
+#[test]
+fn test_status_follows_voting_window() {
+    let vote = proposal(
+        ProposalStatus::Pending,
+        VotingType::Basic,
+        &[60.0, 40.0, 0.0],
+    );
+    let start = vote.voting_start.unwrap();
+    let end = vote.voting_end.unwrap();
+
+    assert_eq!(
+        vote.status_at(start - Duration::hours(1)),
+        ProposalStatus::Pending
+    );
+    assert_eq!(vote.status_at(start), ProposalStatus::Active);
+    assert_eq!(vote.status_at(end), ProposalStatus::Accepted);
+
+    let empty = proposal(ProposalStatus::Active, VotingType::Basic, &[]);
+    assert_eq!(
+        empty.status_at(empty.voting_end.unwrap()),
+        ProposalStatus::Rejected
+    );
+}
+
+#[test]
+fn test_final_status_is_kept() {
+    let vote = proposal(
+        ProposalStatus::Executed,
+        VotingType::Basic,
+        &[0.0, 40.0, 0.0],
+    );
+    let end = vote.voting_end.unwrap();
+    assert_eq!(vote.status_at(end), ProposalStatus::Executed);
+
+    let mut unscheduled = proposal(ProposalStatus::Active, VotingType::Basic, &[]);
+    unscheduled.voting_start = None;
+    unscheduled.voting_end = None;
+    assert_eq!(unscheduled.status_at(Utc::now()), ProposalStatus::Active);
+}
@@ -0,0 +1,102 @@
+//! Unit tests for text fingerprints and spam scoring
+
+use indexer::{
+    services::quality::SpamSignals,
+    utils::fingerprint::{
+        is_link_only, normalize_words, Fingerprint, DUPLICATE_SIMILARITY, MINHASH_PERMUTATIONS,
+    },
+};
+
+const PROPOSAL: &str = "Fund the Arbitrum grants program with 1M ARB. The program pays \
+                        builders of public goods on Arbitrum, reviewed monthly by a \
+                        committee elected by the DAO. Unused funds return to the treasury.";
+
+#[test]
+fn test_words_are_normalized() {
+    assert_eq!(
+        normalize_words("Fund the **Grants** program!\n\n- 1M ARB"),
+        vec!["fund", "the", "grants", "program", "1m", "arb"]
+    );
+}
+
+#[test]
+fn test_short_texts_are_not_fingerprinted() {
+    assert!(Fingerprint::of("Test proposal").is_none());
+    assert!(Fingerprint::of("").is_none());
+
+    let fingerprint = Fingerprint::of(PROPOSAL).unwrap();
+    assert_eq!(fingerprint.minhash.len(), MINHASH_PERMUTATIONS);
+}
+
+#[test]
+fn test_copies_are_near_duplicates() {
+    let original = Fingerprint::of(PROPOSAL).unwrap();
+    let reformatted = Fingerprint::of(&format!("# {}", PROPOSAL.to_uppercase())).unwrap();
+    let edited = Fingerprint::of(&PROPOSAL.replace("monthly", "quarterly")).unwrap();
+
+    assert_eq!(original, reformatted);
+    assert!(original.is_near_duplicate(&edited));
+    assert!(original.similarity(&edited) < 1.0);
+}
+
+#[test]
+fn test_different_texts_are_not_near_duplicates() {
+    let original = Fingerprint::of(PROPOSAL).unwrap();
+    let other = Fingerprint::of(
+        "Upgrade the bridge contracts to the audited implementation and pause \
+         deposits during the migration window announced on the forum.",
+    )
+    .unwrap();
+
+    assert!(original.similarity(&other) < DUPLICATE_SIMILARITY);
+    assert!(!original.is_near_duplicate(&other));
+}
+
+#[test]
+fn test_near_duplicates_share_band_keys() {
+    let original = Fingerprint::of(PROPOSAL).unwrap().band_keys();
+    let edited = Fingerprint::of(&PROPOSAL.replace("monthly", "quarterly"))
+        .unwrap()
+        .band_keys();
+
+    assert_eq!(original.len(), edited.len());
+    assert!(original.iter().any(|key| edited.contains(key)));
+}
+
+#[test]
+fn test_link_only_texts() {
+    assert!(is_link_only("https://forum.arbitrum.foundation/t/123"));
+    assert!(is_link_only(
+        "Details: [here](https://example.com) https://x.com"
+    ));
+    assert!(!is_link_only(PROPOSAL));
+    assert!(!is_link_only(&format!("{PROPOSAL} https://example.com")));
+    assert!(!is_link_only(""));
+}
+
+#[test]
+fn test_spam_score_adds_signal_weights() {
+    assert!(SpamSignals::default().score().is_sign_positive());
+    assert_eq!(SpamSignals::default().score(), 0.0);
+
+    let new_author = SpamSignals {
+        new_author: true,
+        ..Default::default()
+    };
+    let copied = SpamSignals {
+        new_author: true,
+        duplicate_of_other_space: true,
+        ..Default::default()
+    };
+    assert!(new_author.score() > 0.0);
+    assert!(copied.score() > new_author.score());
+
+    let everything = SpamSignals {
+        unverified_space: true,
+        empty_body: true,
+        link_only: true,
+        new_author: true,
+        duplicate_of_other_space: true,
+    };
+    assert_eq!(everything.score(), 1.0);
+}
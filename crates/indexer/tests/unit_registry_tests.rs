@@ -0,0 +1,191 @@
+//! Unit tests for the protocol registry
+
+use std::path::Path;
+
+use indexer::config::{
+    registry::{RegistryFormat, DEFAULT_POLLING_INTERVAL_SECS},
+    ProtocolRegistry, RegistryError,
+};
+
+const VALID_TOML: &str = r#"
+[defaults]
+polling_interval_secs = 120
+
+[[protocols]]
+id = "arbitrum"
+name = "Arbitrum DAO"
+chain_id = 42161
+snapshot_spaces = ["arbitrumfoundation.eth"]
+tally_organization = "arbitrum"
+governors = ["0xf07DeD9dC292157749B6Fd268E37DF6EA38395B9"]
+forum_urls = ["https://forum.arbitrum.foundation"]
+
+[[protocols]]
+id = "uniswap"
+name = "Uniswap"
+chain_id = 1
+snapshot_spaces = ["uniswapgovernance.eth"]
+polling_interval_secs = 60
+"#;
+
+fn invalid_reason(contents: &str) -> String {
+    match ProtocolRegistry::parse(contents, RegistryFormat::Toml) {
+        Err(RegistryError::Invalid { message, .. }) => message,
+        other => panic!("expected validation error, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_parse_toml_registry() {
+    let registry = ProtocolRegistry::parse(VALID_TOML, RegistryFormat::Toml).unwrap();
+
+    assert_eq!(registry.protocols.len(), 2);
+    let arbitrum = registry.get("arbitrum").unwrap();
+    assert_eq!(arbitrum.chain_id, 42161);
+    assert_eq!(arbitrum.tally_organization.as_deref(), Some("arbitrum"));
+    assert!(arbitrum.enabled);
+    assert_eq!(registry.polling_interval_secs(arbitrum), 120);
+
+    let uniswap = registry
+        .find_by_snapshot_space("uniswapgovernance.eth")
+        .unwrap();
+    assert_eq!(uniswap.id, "uniswap");
+    assert_eq!(registry.polling_interval_secs(uniswap), 60);
+
+    let protocol_id = arbitrum.protocol_id();
+    assert_eq!(protocol_id.chain_id.as_eip155(), Some(42161));
+    assert_eq!(protocol_id.to_string(), "eip155:42161:arbitrum");
+    assert_eq!(protocol_id.protocol, "arbitrum");
+}
+
+#[test]
+fn test_parse_yaml_registry() {
+    let yaml = r#"
+protocols:
+  - id: ens
+    name: ENS DAO
+    chain_id: 1
+    snapshot_spaces: [ens.eth]
+    enabled: false
+"#;
+    let registry = ProtocolRegistry::parse(yaml, RegistryFormat::Yaml).unwrap();
+
+    assert_eq!(
+        registry.defaults.polling_interval_secs,
+        DEFAULT_POLLING_INTERVAL_SECS
+    );
+    assert_eq!(registry.enabled().count(), 0);
+}
+
+#[test]
+fn test_format_detection() {
+    assert_eq!(
+        RegistryFormat::from_path(Path::new("protocols.toml")),
+        Some(RegistryFormat::Toml)
+    );
+    assert_eq!(
+        RegistryFormat::from_path(Path::new("protocols.yml")),
+        Some(RegistryFormat::Yaml)
+    );
+    assert_eq!(RegistryFormat::from_path(Path::new("protocols.json")), None);
+}
+
+#[test]
+fn test_rejects_duplicate_ids() {
+    let contents = r#"
+[[protocols]]
+id = "uniswap"
+name = "Uniswap"
+chain_id = 1
+snapshot_spaces = ["uniswapgovernance.eth"]
+
+[[protocols]]
+id = "uniswap"
+name = "Uniswap again"
+chain_id = 1
+tally_organization = "uniswap"
+"#;
+    assert_eq!(invalid_reason(contents), "duplicate protocol ID");
+}
+
+#[test]
+fn test_rejects_shared_snapshot_space() {
+    let contents = r#"
+[[protocols]]
+id = "a"
+name = "A"
+chain_id = 1
+snapshot_spaces = ["shared.eth"]
+
+[[protocols]]
+id = "b"
+name = "B"
+chain_id = 1
+snapshot_spaces = ["shared.eth"]
+"#;
+    assert!(invalid_reason(contents).contains("shared.eth"));
+}
+
+#[test]
+fn test_rejects_invalid_entries() {
+    let bad_slug = r#"
+[[protocols]]
+id = "Uniswap DAO"
+name = "Uniswap"
+chain_id = 1
+snapshot_spaces = ["uniswapgovernance.eth"]
+"#;
+    assert!(invalid_reason(bad_slug).contains("slug"));
+
+    let no_sources = r#"
+[[protocols]]
+id = "uniswap"
+name = "Uniswap"
+chain_id = 1
+"#;
+    assert!(invalid_reason(no_sources).contains("at least one"));
+
+    let bad_address = r#"
+[[protocols]]
+id = "uniswap"
+name = "Uniswap"
+chain_id = 1
+governors = ["0x1234"]
+"#;
+    assert!(invalid_reason(bad_address).contains("0x1234"));
+
+    let bad_forum = r#"
+[[protocols]]
+id = "uniswap"
+name = "Uniswap"
+chain_id = 1
+snapshot_spaces = ["uniswapgovernance.eth"]
+forum_urls = ["not a url"]
+"#;
+    assert!(invalid_reason(bad_forum).contains("forum URL"));
+
+    let fast_polling = r#"
+[[protocols]]
+id = "uniswap"
+name = "Uniswap"
+chain_id = 1
+snapshot_spaces = ["uniswapgovernance.eth"]
+polling_interval_secs = 1
+"#;
+    assert!(invalid_reason(fast_polling).contains("polling interval"));
+}
+
+#[test]
+fn test_rejects_unparseable_registry() {
+    let result = ProtocolRegistry::parse("[[protocols]\nid =", RegistryFormat::Toml);
+    assert!(matches!(result, Err(RegistryError::Parse(_))));
+}
+
+#[test]
+fn test_shipped_registry_is_valid() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("config/protocols.toml");
+    let registry = ProtocolRegistry::load(&path).unwrap();
+
+    assert!(registry.get("arbitrum").is_some());
+    assert!(registry.enabled().count() > 0);
+}
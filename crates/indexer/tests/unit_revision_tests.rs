@@ -0,0 +1,121 @@
+//! Unit tests for proposal revisions, diffs and events
+
+mod fixtures;
+
+use chrono::Utc;
+use fixtures::ProposalBuilder;
+use indexer::{
+    models::{
+        proposal::ProposalStatus,
+        revision::{content_hash, ProposalField},
+        IndexerEvent, Proposal, ProposalRevision, ProtocolId,
+    },
+    utils::{
+        diff::diff_revisions,
+        id::{ChainId, ProposalId},
+    },
+};
+
+fn proposal_id() -> ProposalId {
+    let protocol_id = ProtocolId::new(ChainId::eip155(1), "uniswap").unwrap();
+    ProposalId::new(protocol_id, "snapshot", "0xabc").unwrap()
+}
+
+fn proposal(title: &str, description: &str, choices: &[&str]) -> Proposal {
+    ProposalBuilder::new(proposal_id().protocol_id().clone(), "snapshot", "0xabc")
+        .title(title)
+        .description(description)
+        .choices(choices)
+        .build()
+}
+
+fn revision(revision: i32, proposal: &Proposal) -> ProposalRevision {
+    ProposalRevision {
+        proposal_id: proposal.id.clone(),
+        revision,
+        title: proposal.title.clone(),
+        description: proposal.description.clone(),
+        choices: proposal.choices.clone(),
+        content_hash: content_hash(proposal),
+        edited_at: None,
+        created_at: Utc::now(),
+    }
+}
+
+#[test]
+fn test_content_hash_tracks_content_only() {
+    let a = proposal("Title", "Body", &["For", "Against"]);
+    let mut b = a.clone();
+    b.status = ProposalStatus::Executed;
+    b.updated_at = Utc::now();
+
+    assert_eq!(content_hash(&a).len(), 64);
+    assert_eq!(content_hash(&a), content_hash(&b));
+
+    // Moving text between fields must change the hash
+    let c = proposal("TitleBody", "", &["For", "Against"]);
+    let d = proposal("Title", "Body", &["ForAgainst"]);
+    assert_ne!(content_hash(&a), content_hash(&c));
+    assert_ne!(content_hash(&a), content_hash(&d));
+}
+
+#[test]
+fn test_diff_between_revisions() {
+    let v1 = proposal(
+        "Fund grants",
+        "## Summary\nAllocate 1M\n\n## Budget\nQ1\n",
+        &["For", "Against"],
+    );
+    let v2 = proposal(
+        "Fund grants",
+        "## Summary\nAllocate 2M\n\n## Budget\nQ1\n",
+        &["For", "Against", "Abstain"],
+    );
+    let (r1, r2) = (revision(1, &v1), revision(2, &v2));
+
+    assert_eq!(
+        r1.changed_fields(&r2),
+        vec![ProposalField::Description, ProposalField::Choices]
+    );
+
+    let diff = diff_revisions(&r1, &r2);
+    assert_eq!((diff.from, diff.to), (1, 2));
+    assert_eq!(diff.fields.len(), 2);
+
+    let description = &diff.fields[0];
+    assert_eq!(description.field, ProposalField::Description);
+    assert_eq!((description.insertions, description.deletions), (1, 1));
+    assert!(description.unified.contains("-Allocate 1M\n"));
+    assert!(description.unified.contains("+Allocate 2M\n"));
+    assert!(description.unified.contains("--- revision 1"));
+
+    let choices = &diff.fields[1];
+    assert_eq!(choices.field, ProposalField::Choices);
+    assert!(choices.unified.contains("+Abstain"));
+}
+
+#[test]
+fn test_identical_revisions_have_no_diff() {
+    let v1 = proposal("Title", "Body", &["For"]);
+    let diff = diff_revisions(&revision(1, &v1), &revision(2, &v1));
+    assert!(diff.fields.is_empty());
+}
+
+#[test]
+fn test_updated_event_serialization() {
+    let event = IndexerEvent::ProposalUpdated {
+        proposal_id: proposal_id(),
+        revision: 3,
+        changed_fields: vec![ProposalField::Title, ProposalField::Description],
+        timestamp: Utc::now(),
+    };
+
+    let json = serde_json::to_value(&event).unwrap();
+    assert_eq!(json["type"], "proposal_updated");
+    assert_eq!(json["proposal_id"], "eip155:1:uniswap:snapshot:0xabc");
+    assert_eq!(
+        json["changed_fields"],
+        serde_json::json!(["title", "description"])
+    );
+    assert_eq!(serde_json::from_value::<IndexerEvent>(json).unwrap(), event);
+}
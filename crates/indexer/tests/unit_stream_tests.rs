@@ -0,0 +1,114 @@
+//! Unit tests for event stream filters
+
+use chrono::Utc;
+use indexer::{
+    api::stream::StreamParams,
+    models::{
+        event::EVENT_TYPES, proposal::ProposalStatus, EventEnvelope, IndexerEvent, ProtocolId,
+    },
+    utils::id::{ChainId, ProposalId},
+};
+
+fn protocol(chain_id: u64, slug: &str) -> ProtocolId {
+    ProtocolId::new(ChainId::eip155(chain_id), slug).unwrap()
+}
+
+fn events() -> Vec<IndexerEvent> {
+    let uniswap = ProposalId::new(protocol(1, "uniswap"), "tally", "42").unwrap();
+    let arbitrum = ProposalId::new(protocol(42161, "arbitrum"), "tally", "7").unwrap();
+    let timestamp = Utc::now();
+
+    vec![
+        IndexerEvent::ProposalCreated {
+            proposal_id: uniswap.clone(),
+            revision: 1,
+            timestamp,
+        },
+        IndexerEvent::ProposalUpdated {
+            proposal_id: uniswap.clone(),
+            revision: 2,
+            changed_fields: vec![],
+            timestamp,
+        },
+        IndexerEvent::VotesUpdated {
+            proposal_id: arbitrum.clone(),
+            scores: vec![1.0, 2.0, 0.0],
+            scores_total: Some(3.0),
+            timestamp,
+        },
+        IndexerEvent::VotingStarted {
+            proposal_id: arbitrum.clone(),
+            timestamp,
+        },
+        IndexerEvent::VotingEnded {
+            proposal_id: arbitrum.clone(),
+            outcome: ProposalStatus::Rejected,
+            timestamp,
+        },
+        IndexerEvent::StatusChanged {
+            proposal_id: uniswap,
+            from: ProposalStatus::Accepted,
+            to: ProposalStatus::Executed,
+            timestamp,
+        },
+    ]
+}
+
+#[test]
+fn test_event_types_match_serialized_type() {
+    let events = events();
+    assert_eq!(events.len(), EVENT_TYPES.len());
+    for (event, event_type) in events.iter().zip(EVENT_TYPES) {
+        assert_eq!(event.event_type(), event_type);
+        assert_eq!(serde_json::to_value(event).unwrap()["type"], event_type);
+    }
+}
+
+#[test]
+fn test_filter_by_protocol_and_type() {
+    let params = StreamParams {
+        protocols: Some("eip155:42161:arbitrum".to_string()),
+        types: Some("voting_started, voting_ended".to_string()),
+        last_event_id: None,
+    };
+    let filter = params.filter().unwrap();
+
+    let selected: Vec<_> = events()
+        .into_iter()
+        .filter(|event| filter.matches(event))
+        .map(|event| event.event_type())
+        .collect();
+    assert_eq!(selected, vec!["voting_started", "voting_ended"]);
+
+    let everything = StreamParams::default().filter().unwrap();
+    assert!(events().iter().all(|event| everything.matches(event)));
+}
+
+#[test]
+fn test_invalid_filters_are_rejected() {
+    let params = StreamParams {
+        types: Some("proposal_deleted".to_string()),
+        ..Default::default()
+    };
+    assert!(params.filter().unwrap_err().contains("proposal_deleted"));
+
+    let params = StreamParams {
+        protocols: Some("uniswap".to_string()),
+        ..Default::default()
+    };
+    assert!(params.filter().is_err());
+}
+
+#[test]
+fn test_stored_event_serialization() {
+    let stored = EventEnvelope::new(17, events().remove(3));
+
+    let json = serde_json::to_value(&stored).unwrap();
+    assert_eq!(json["version"], 1);
+    assert_eq!(json["id"], 17);
+    assert_eq!(json["type"], "voting_started");
+    assert_eq!(
+        serde_json::from_value::<EventEnvelope>(json).unwrap(),
+        stored
+    );
+}
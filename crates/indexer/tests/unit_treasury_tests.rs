@@ -0,0 +1,220 @@
+//! Unit tests for treasury tracking
+
+use std::collections::HashMap;
+
+use axum::{routing::post, Json, Router};
+use indexer::{
+    config::{
+        parse_rpc_endpoint, registry::RegistryFormat, HttpConfig, ProtocolRegistry, RegistryError,
+        RpcEndpoint,
+    },
+    services::{
+        http::HttpClient,
+        rpc::{
+            balance_of_calldata, decode_transfer_calldata, hex_to_decimal, parse_quantity,
+            scale_balance, RpcError,
+        },
+        treasury::read_balances,
+        RpcClient,
+    },
+};
+use serde_json::{json, Value};
+
+const TREASURY: &str = "0x1a9C8182C09F50C8318d769245beA52c32BE35BC";
+const UNI: &str = "0x1f9840a85d5aF5bf1D1762F925BDADdC4201F984";
+
+const REGISTRY: &str = r#"
+[[protocols]]
+id = "uniswap"
+name = "Uniswap"
+chain_id = 1
+snapshot_spaces = ["uniswapgovernance.eth"]
+treasuries = ["0x1a9C8182C09F50C8318d769245beA52c32BE35BC"]
+treasury_tokens = [
+    { address = "0x1f9840a85d5aF5bf1D1762F925BDADdC4201F984", symbol = "UNI", decimals = 18 },
+]
+"#;
+
+/// Serve a JSON-RPC node answering `eth_blockNumber`, `eth_getBalance` and
+/// `balanceOf` calls, returning the node's URL
+async fn serve_node() -> String {
+    let app = Router::new().route(
+        "/",
+        post(|Json(request): Json<Value>| async move {
+            let result = match request["method"].as_str() {
+                Some("eth_blockNumber") => json!("0x1312d00"),
+                // 200 ETH
+                Some("eth_getBalance") if request["params"][1] == "0x1312d00" => {
+                    json!("0xad78ebc5ac6200000")
+                }
+                Some("eth_call") if request["params"][0]["to"] == UNI => {
+                    // 250,000 UNI as a 32-byte word
+                    json!(format!("0x{:064x}", 250_000u128 * 10u128.pow(18)))
+                }
+                _ => {
+                    return Json(json!({
+                        "jsonrpc": "2.0",
+                        "id": request["id"],
+                        "error": { "code": -32601, "message": "method not found" },
+                    }))
+                }
+            };
+            Json(json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{address}/")
+}
+
+fn rpc_client(chain_id: u64, url: String) -> RpcClient {
+    let http = HttpClient::with_limits(
+        HashMap::new(),
+        &HttpConfig {
+            max_retries: 0,
+            failure_threshold: 5,
+            cooldown_secs: 60,
+            timeout_secs: 30,
+            connect_timeout_secs: 10,
+        },
+    );
+    RpcClient::new(http, &[RpcEndpoint { chain_id, url }])
+}
+
+#[test]
+fn test_hex_to_decimal() {
+    assert_eq!(hex_to_decimal("0x").unwrap(), "0");
+    assert_eq!(hex_to_decimal("0x0").unwrap(), "0");
+    assert_eq!(hex_to_decimal("0x1a").unwrap(), "26");
+    assert_eq!(
+        hex_to_decimal(&format!("0x{:064x}", u128::MAX)).unwrap(),
+        u128::MAX.to_string()
+    );
+    assert_eq!(
+        hex_to_decimal(&format!("0x{}", "f".repeat(64))).unwrap(),
+        "115792089237316195423570985008687907853269984665640564039457584007913129639935"
+    );
+    assert!(hex_to_decimal(&format!("0x1{}", "0".repeat(64))).is_err());
+    assert!(hex_to_decimal("0xzz").is_err());
+
+    assert_eq!(parse_quantity("0x1312d00").unwrap(), 20_000_000);
+    assert!(parse_quantity("latest").is_err());
+    assert_eq!(scale_balance("12500000000000000000", 18), 12.5);
+    assert_eq!(scale_balance("1500000", 6), 1.5);
+}
+
+#[test]
+fn test_balance_of_calldata() {
+    assert_eq!(
+        balance_of_calldata(TREASURY).unwrap(),
+        "0x70a08231\
+         0000000000000000000000001a9c8182c09f50c8318d769245bea52c32be35bc"
+    );
+    assert!(matches!(
+        balance_of_calldata("0x1234"),
+        Err(RpcError::InvalidAddress(_))
+    ));
+}
+
+#[test]
+fn test_decode_transfer_calldata() {
+    let calldata = "0xa9059cbb\
+                    0000000000000000000000001A9C8182C09F50C8318D769245BEA52C32BE35BC\
+                    00000000000000000000000000000000000000000000000ad78ebc5ac6200000";
+    assert_eq!(
+        decode_transfer_calldata(calldata),
+        Some((
+            TREASURY.to_ascii_lowercase(),
+            "200000000000000000000".to_string()
+        ))
+    );
+
+    // Other functions, truncated arguments and dirty address padding
+    assert_eq!(
+        decode_transfer_calldata(&calldata.replace("a9059cbb", "095ea7b3")),
+        None
+    );
+    assert_eq!(
+        decode_transfer_calldata(&calldata[..calldata.len() - 2]),
+        None
+    );
+    assert_eq!(
+        decode_transfer_calldata(&calldata.replacen("00", "ff", 3)),
+        None
+    );
+}
+
+#[test]
+fn test_parse_rpc_endpoint() {
+    assert_eq!(
+        parse_rpc_endpoint("42161=http://localhost:8547").unwrap(),
+        RpcEndpoint {
+            chain_id: 42161,
+            url: "http://localhost:8547".to_string(),
+        }
+    );
+    assert!(parse_rpc_endpoint("http://localhost:8545").is_err());
+    assert!(parse_rpc_endpoint("mainnet=http://localhost:8545").is_err());
+    assert!(parse_rpc_endpoint("1=localhost:8545").is_err());
+}
+
+#[test]
+fn test_registry_treasuries() {
+    let registry = ProtocolRegistry::parse(REGISTRY, RegistryFormat::Toml).unwrap();
+    let uniswap = registry.get("uniswap").unwrap();
+    assert_eq!(uniswap.treasuries, vec![TREASURY]);
+    assert_eq!(uniswap.treasury_tokens[0].symbol, "UNI");
+    assert_eq!(uniswap.treasury_tokens[0].decimals, 18);
+
+    let invalid = |contents: &str| match ProtocolRegistry::parse(contents, RegistryFormat::Toml) {
+        Err(RegistryError::Invalid { message, .. }) => message,
+        other => panic!("expected validation error, got {other:?}"),
+    };
+    let bad_treasury = REGISTRY.replace(TREASURY, "0x1234");
+    assert!(invalid(&bad_treasury).contains("0x1234"));
+    let no_treasury = REGISTRY.replace(&format!("treasuries = [\"{TREASURY}\"]"), "");
+    assert!(invalid(&no_treasury).contains("treasury address"));
+    let no_symbol = REGISTRY.replace("symbol = \"UNI\"", "symbol = \"\"");
+    assert!(invalid(&no_symbol).contains("symbol"));
+}
+
+#[tokio::test]
+async fn test_read_balances() {
+    let registry = ProtocolRegistry::parse(REGISTRY, RegistryFormat::Toml).unwrap();
+    let uniswap = registry.get("uniswap").unwrap();
+    let rpc = rpc_client(1, serve_node().await);
+
+    let balances = read_balances(&rpc, uniswap).await.unwrap();
+    assert_eq!(balances.len(), 2);
+
+    let native = &balances[0];
+    assert_eq!(native.address, TREASURY);
+    assert_eq!(native.token, None);
+    assert_eq!(native.symbol, "ETH");
+    assert_eq!(native.raw_balance, "200000000000000000000");
+    assert_eq!(native.balance, 200.0);
+    assert_eq!(native.block_number, 20_000_000);
+
+    let uni = &balances[1];
+    assert_eq!(uni.token.as_deref(), Some(UNI));
+    assert_eq!(uni.symbol, "UNI");
+    assert_eq!(uni.raw_balance, "250000000000000000000000");
+    assert_eq!(uni.balance, 250_000.0);
+    assert_eq!(uni.recorded_at, native.recorded_at);
+}
+
+#[tokio::test]
+async fn test_rpc_errors() {
+    let rpc = rpc_client(1, serve_node().await);
+
+    assert!(matches!(
+        rpc.call(1, "eth_chainId", json!([])).await,
+        Err(RpcError::Rpc { code: -32601, .. })
+    ));
+    assert!(matches!(
+        rpc.block_number(10).await,
+        Err(RpcError::UnknownChain(10))
+    ));
+    assert!(rpc.supports(1) && !rpc.supports(10));
+}
@@ -0,0 +1,193 @@
+//! Unit tests for record validation
+
+mod fixtures;
+
+use chrono::{Duration, Utc};
+use fixtures::ProposalBuilder;
+use indexer::{
+    config::{registry::RegistryFormat, ProtocolRegistry},
+    models::{Actor, Proposal, ProtocolId},
+    utils::{
+        id::ChainId,
+        validation::{
+            normalize_address, normalize_ens_name, validate_actor, validate_proposal,
+            validate_snapshot_id, validate_snapshot_space, ValidationRule,
+        },
+    },
+};
+
+const REGISTRY: &str = r#"
+[[protocols]]
+id = "arbitrum"
+name = "Arbitrum DAO"
+chain_id = 42161
+snapshot_spaces = ["arbitrumfoundation.eth"]
+"#;
+
+const SNAPSHOT_ID: &str = "0x1f3a8b1c7e5a6d04b2c9f0e3d8a7b6c5d4e3f2a1b0c9d8e7f6a5b4c3d2e1f0a9";
+
+fn registry() -> ProtocolRegistry {
+    ProtocolRegistry::parse(REGISTRY, RegistryFormat::Toml).unwrap()
+}
+
+fn proposal(protocol_id: ProtocolId, source: &str, native_id: &str) -> Proposal {
+    ProposalBuilder::new(protocol_id, source, native_id)
+        .author("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed")
+        .build()
+}
+
+fn arbitrum() -> ProtocolId {
+    ProtocolId::new(ChainId::eip155(42161), "arbitrum").unwrap()
+}
+
+#[test]
+fn test_address_checksum() {
+    // Test vectors from EIP-55
+    for address in [
+        "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+        "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+        "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+        "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+    ] {
+        assert_eq!(normalize_address("author", address).unwrap(), address);
+        assert_eq!(
+            normalize_address("author", &address.to_ascii_lowercase()).unwrap(),
+            address
+        );
+    }
+
+    let error =
+        normalize_address("author", "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD").unwrap_err();
+    assert_eq!(error.rule, ValidationRule::Checksum);
+    assert_eq!(error.field, "author");
+
+    for address in [
+        "",
+        "5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+        "0x1234",
+        "0xZZ",
+    ] {
+        assert!(
+            normalize_address("author", address).is_err(),
+            "accepted {address:?}"
+        );
+    }
+}
+
+#[test]
+fn test_ens_names() {
+    assert_eq!(
+        normalize_ens_name("ens", "vitalik.eth").unwrap(),
+        "vitalik.eth"
+    );
+    assert_eq!(normalize_ens_name("ens", "Nick.ETH").unwrap(), "nick.eth");
+
+    for name in ["", "nick..eth", "a_b.eth", "ni ck.eth"] {
+        let error = normalize_ens_name("ens", name).unwrap_err();
+        assert!(
+            matches!(
+                error.rule,
+                ValidationRule::EnsName | ValidationRule::Required
+            ),
+            "unexpected rule for {name:?}: {error}"
+        );
+    }
+}
+
+#[test]
+fn test_snapshot_ids() {
+    assert!(validate_snapshot_id("id", SNAPSHOT_ID).is_ok());
+    assert!(validate_snapshot_id("id", "QmWbpCtwdLzxuLKnMW4Vv4MPFd2pdPX71YBKPasfZxqLUS").is_ok());
+    assert!(validate_snapshot_id("id", "0x1234").is_err());
+    assert!(validate_snapshot_id("id", "42").is_err());
+
+    let registry = registry();
+    let entry = registry.get("arbitrum").unwrap();
+    assert!(validate_snapshot_space("space", "arbitrumfoundation.eth", entry).is_ok());
+    assert_eq!(
+        validate_snapshot_space("space", "uniswapgovernance.eth", entry)
+            .unwrap_err()
+            .rule,
+        ValidationRule::UnknownSnapshotSpace
+    );
+}
+
+#[test]
+fn test_valid_proposal_is_normalized() {
+    let mut proposal = proposal(arbitrum(), "snapshot", SNAPSHOT_ID);
+    validate_proposal(&mut proposal, &registry()).unwrap();
+    assert_eq!(
+        proposal.author,
+        "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+    );
+}
+
+#[test]
+fn test_invalid_proposal_reports_field_paths() {
+    let mut proposal = proposal(arbitrum(), "tally", "not-a-number");
+    proposal.title = " ".to_string();
+    proposal.choices.push(String::new());
+    proposal.author = "vitalik.eth".to_string();
+
+    let errors = validate_proposal(&mut proposal, &registry()).unwrap_err();
+    let fields: Vec<_> = errors
+        .errors
+        .iter()
+        .map(|e| (e.field.as_str(), e.rule))
+        .collect();
+    assert_eq!(
+        fields,
+        vec![
+            ("title", ValidationRule::Required),
+            ("choices[2]", ValidationRule::Required),
+            ("author", ValidationRule::HexAddress),
+            ("id", ValidationRule::OnchainProposalId),
+        ]
+    );
+}
+
+#[test]
+fn test_proposal_checked_against_registry() {
+    let mainnet = ProtocolId::new(ChainId::eip155(1), "arbitrum").unwrap();
+    let mut record = proposal(mainnet, "snapshot", SNAPSHOT_ID);
+    let errors = validate_proposal(&mut record, &registry()).unwrap_err();
+    assert_eq!(errors.errors[0].rule, ValidationRule::ChainMismatch);
+
+    let unknown = ProtocolId::new(ChainId::eip155(1), "unknown").unwrap();
+    let mut record = proposal(unknown, "snapshot", SNAPSHOT_ID);
+    let errors = validate_proposal(&mut record, &registry()).unwrap_err();
+    assert_eq!(errors.errors[0].rule, ValidationRule::UnknownProtocol);
+}
+
+#[test]
+fn test_voting_window_and_scores_are_consistent() {
+    let mut record = proposal(arbitrum(), "snapshot", SNAPSHOT_ID);
+    record.voting_start = Some(Utc::now());
+    record.voting_end = Some(Utc::now() - Duration::days(1));
+    record.scores = vec![10.0];
+
+    let errors = validate_proposal(&mut record, &registry()).unwrap_err();
+    let rules: Vec<_> = errors.errors.iter().map(|e| e.rule).collect();
+    assert_eq!(
+        rules,
+        vec![ValidationRule::VotingWindow, ValidationRule::ScoreCount]
+    );
+}
+
+#[test]
+fn test_actor_is_normalized() {
+    let mut actor = Actor {
+        address: "0xd1220a0cf47c7b9be7a2e6ba89f429762e7b9adb".to_string(),
+        ens: Some("Nick.ETH".to_string()),
+        name: None,
+        description: None,
+        voting_power: None,
+        protocol_id: Some(arbitrum()),
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    };
+
+    validate_actor(&mut actor, &registry()).unwrap();
+    assert_eq!(actor.address, "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb");
+    assert_eq!(actor.ens.as_deref(), Some("nick.eth"));
+}
@@ -0,0 +1,132 @@
+//! Unit tests for webhook subscriptions
+
+use chrono::{Duration, Utc};
+use indexer::{
+    db::repositories::webhook::StoredWebhook,
+    models::{event::IndexerEvent, proposal::ProposalStatus, Webhook},
+    services::{
+        auth::sign,
+        webhook::{signature_header, validate, DispatchCursor, WebhookError},
+    },
+};
+
+fn webhook() -> Webhook {
+    Webhook {
+        id: "hook".to_string(),
+        url: "https://agent.example/hooks".to_string(),
+        events: vec![],
+        protocols: vec![],
+        statuses: vec![],
+        enabled: true,
+        disabled_reason: None,
+        consecutive_failures: 0,
+        previous_secret_expires_at: None,
+        last_delivery_at: None,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+fn created(protocol: &str) -> IndexerEvent {
+    IndexerEvent::ProposalCreated {
+        proposal_id: format!("{protocol}:snapshot:0x01").parse().unwrap(),
+        revision: 1,
+        timestamp: Utc::now(),
+    }
+}
+
+#[test]
+fn test_webhook_filters() {
+    let event = created("eip155:42161:arbitrum");
+    assert!(webhook().matches(&event, None));
+
+    let by_type = Webhook {
+        events: vec!["voting_ended".to_string()],
+        ..webhook()
+    };
+    assert!(!by_type.matches(&event, None));
+
+    let by_protocol = Webhook {
+        protocols: vec!["eip155:42161:arbitrum".parse().unwrap()],
+        ..webhook()
+    };
+    assert!(by_protocol.matches(&event, None));
+    assert!(!by_protocol.matches(&created("eip155:10:optimism"), None));
+
+    // Status filters need the proposal's status
+    let by_status = Webhook {
+        statuses: vec![ProposalStatus::Active],
+        ..webhook()
+    };
+    assert!(by_status.matches(&event, Some(ProposalStatus::Active)));
+    assert!(!by_status.matches(&event, Some(ProposalStatus::Pending)));
+    assert!(!by_status.matches(&event, None));
+}
+
+#[test]
+fn test_validate() {
+    assert!(validate(Some("https://agent.example/hooks"), Some(&[])).is_ok());
+    assert!(validate(None, Some(&["status_changed".to_string()])).is_ok());
+    assert!(matches!(
+        validate(Some("ftp://agent.example"), None),
+        Err(WebhookError::InvalidUrl(_))
+    ));
+    assert!(matches!(
+        validate(Some("not a url"), None),
+        Err(WebhookError::InvalidUrl(_))
+    ));
+    assert!(matches!(
+        validate(None, Some(&["proposal_deleted".to_string()])),
+        Err(WebhookError::UnknownEvent(event)) if event == "proposal_deleted"
+    ));
+}
+
+#[test]
+fn test_rotated_secrets_sign_during_grace_period() {
+    let now = Utc::now();
+    let stored = StoredWebhook {
+        webhook: Webhook {
+            previous_secret_expires_at: Some(now + Duration::hours(1)),
+            ..webhook()
+        },
+        secret: "new".to_string(),
+        previous_secret: Some("old".to_string()),
+    };
+
+    assert_eq!(stored.signing_secrets(now), vec!["new", "old"]);
+    assert_eq!(
+        stored.signing_secrets(now + Duration::hours(2)),
+        vec!["new"]
+    );
+
+    let body = r#"{"id":1,"type":"ping"}"#;
+    assert_eq!(
+        signature_header(&stored.signing_secrets(now), body),
+        format!("sha256={},sha256={}", sign("new", body), sign("old", body))
+    );
+}
+
+#[test]
+fn test_dispatch_cursor() {
+    let mut cursor = DispatchCursor::new(10);
+    assert_eq!((cursor.dispatched(), cursor.cursor()), (10, 10));
+
+    // Events without deliveries are done right away
+    cursor.dispatch(11, 0);
+    assert_eq!(cursor.cursor(), 11);
+
+    cursor.dispatch(12, 2);
+    cursor.dispatch(13, 1);
+    assert_eq!((cursor.dispatched(), cursor.cursor()), (13, 11));
+
+    // A later event finishing first does not move the cursor past an earlier one
+    cursor.finish(13);
+    cursor.finish(12);
+    assert_eq!(cursor.cursor(), 11);
+    cursor.finish(12);
+    assert_eq!(cursor.cursor(), 13);
+
+    // Unknown events are ignored
+    cursor.finish(42);
+    assert_eq!((cursor.dispatched(), cursor.cursor()), (13, 13));
+}
@@ -0,0 +1,142 @@
+//! In-process stand-in for an OTLP collector
+//!
+//! [`Collector`] listens on a local port, decodes the spans posted to it
+//! over OTLP/HTTP and keeps them in memory, so tests can check what a
+//! service exports without running a real collector.
+
+use std::{
+    collections::BTreeMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use axum::{body::Bytes, extract::State, http::StatusCode, routing::post, Router};
+use opentelemetry_proto::tonic::{
+    collector::trace::v1::ExportTraceServiceRequest,
+    common::v1::{any_value::Value, AnyValue, KeyValue},
+};
+use prost::Message;
+
+use crate::otel::TRACES_PATH;
+
+/// Span received by the [`Collector`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollectedSpan {
+    /// Span name
+    pub name: String,
+    /// Hex trace ID
+    pub trace_id: String,
+    /// Hex span ID
+    pub span_id: String,
+    /// Hex ID of the parent span, empty for root spans
+    pub parent_span_id: String,
+    /// `service.name` of the exporting service
+    pub service_name: String,
+    /// Span attributes, rendered as strings
+    pub attributes: BTreeMap<String, String>,
+}
+
+/// OTLP/HTTP collector keeping received spans in memory
+#[derive(Clone)]
+pub struct Collector {
+    address: SocketAddr,
+    spans: Arc<Mutex<Vec<CollectedSpan>>>,
+}
+
+impl Collector {
+    /// Start a collector on a free local port
+    pub async fn start() -> std::io::Result<Self> {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let collector = Self {
+            address: listener.local_addr()?,
+            spans: Arc::default(),
+        };
+        let app = Router::new()
+            .route(TRACES_PATH, post(receive))
+            .with_state(collector.spans.clone());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        Ok(collector)
+    }
+
+    /// Base URL to configure as the OTLP endpoint
+    pub fn endpoint(&self) -> String {
+        format!("http://{}", self.address)
+    }
+
+    /// Spans received so far
+    pub fn spans(&self) -> Vec<CollectedSpan> {
+        self.spans.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Wait until a span with the given name is received
+    pub async fn wait_for(&self, name: &str, timeout: Duration) -> Option<CollectedSpan> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            if let Some(span) = self.spans().into_iter().find(|span| span.name == name) {
+                return Some(span);
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return None;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }
+}
+
+async fn receive(State(spans): State<Arc<Mutex<Vec<CollectedSpan>>>>, body: Bytes) -> StatusCode {
+    let Ok(request) = ExportTraceServiceRequest::decode(body) else {
+        return StatusCode::BAD_REQUEST;
+    };
+
+    let mut received = Vec::new();
+    for resource_spans in request.resource_spans {
+        let service_name = resource_spans
+            .resource
+            .map(|resource| attributes(&resource.attributes))
+            .and_then(|mut attributes| attributes.remove("service.name"))
+            .unwrap_or_default();
+        for span in resource_spans
+            .scope_spans
+            .into_iter()
+            .flat_map(|scope| scope.spans)
+        {
+            received.push(CollectedSpan {
+                name: span.name,
+                trace_id: hex(&span.trace_id),
+                span_id: hex(&span.span_id),
+                parent_span_id: hex(&span.parent_span_id),
+                service_name: service_name.clone(),
+                attributes: attributes(&span.attributes),
+            });
+        }
+    }
+    spans
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .extend(received);
+    StatusCode::OK
+}
+
+fn attributes(attributes: &[KeyValue]) -> BTreeMap<String, String> {
+    attributes
+        .iter()
+        .map(|attribute| (attribute.key.clone(), render(attribute.value.as_ref())))
+        .collect()
+}
+
+fn render(value: Option<&AnyValue>) -> String {
+    match value.and_then(|value| value.value.as_ref()) {
+        Some(Value::StringValue(value)) => value.clone(),
+        Some(Value::BoolValue(value)) => value.to_string(),
+        Some(Value::IntValue(value)) => value.to_string(),
+        Some(Value::DoubleValue(value)) => value.to_string(),
+        Some(Value::BytesValue(value)) => hex(value),
+        Some(other) => format!("{other:?}"),
+        None => String::new(),
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
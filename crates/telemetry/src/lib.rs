@@ -0,0 +1,19 @@
+//! Wei Telemetry - request IDs, structured logging and trace export
+//!
+//! This crate provides the request logging middleware, log setup and
+//! OpenTelemetry export shared by the agent and indexer services, so both
+//! tag their logs and error responses with the same request IDs and join
+//! the same distributed traces.
+#![deny(missing_docs)]
+
+pub mod collector;
+pub mod logging;
+pub mod otel;
+pub mod request_id;
+
+// Re-export commonly used types
+pub use logging::{init_logging, LogFormat};
+pub use otel::{OtlpConfig, TracingGuard};
+pub use request_id::{
+    current_request_id, logging_middleware, propagate, record_api_key, RequestId, REQUEST_ID_HEADER,
+};
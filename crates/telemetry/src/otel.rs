@@ -0,0 +1,146 @@
+//! OpenTelemetry span export and W3C trace context propagation
+//!
+//! When an OTLP endpoint is configured, tracing spans are exported to it
+//! over OTLP/HTTP. Spans keep their tracing fields as attributes. Inbound
+//! requests continue the trace of a `traceparent` header, and outbound
+//! requests sent through [`propagate`](crate::request_id::propagate) carry
+//! the current trace context.
+
+use std::collections::HashMap;
+
+use axum::http::HeaderMap;
+use opentelemetry::{
+    global,
+    propagation::Extractor,
+    trace::{TraceContextExt, TraceError, TracerProvider as _},
+    Context, KeyValue,
+};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{
+    propagation::TraceContextPropagator,
+    runtime,
+    trace::{Sampler, TracerProvider},
+    Resource,
+};
+use tracing::{warn, Span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// Path OTLP/HTTP collectors receive spans on
+pub const TRACES_PATH: &str = "/v1/traces";
+
+/// Where and how to export spans
+#[derive(Debug, Clone, PartialEq)]
+pub struct OtlpConfig {
+    /// Base URL of the OTLP/HTTP collector, e.g. `http://localhost:4318`
+    pub endpoint: String,
+    /// Value of the `service.name` resource attribute
+    pub service_name: String,
+    /// Share of new traces sampled, from 0 to 1
+    ///
+    /// Traces continued from an inbound `traceparent` follow the caller's
+    /// sampling decision.
+    pub sample_ratio: f64,
+}
+
+impl OtlpConfig {
+    /// URL spans are posted to
+    pub fn traces_url(&self) -> String {
+        format!("{}{TRACES_PATH}", self.endpoint.trim_end_matches('/'))
+    }
+}
+
+/// Create a provider exporting spans in batches to the configured collector
+///
+/// Must be called within a Tokio runtime.
+pub fn tracer_provider(config: &OtlpConfig) -> Result<TracerProvider, TraceError> {
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(config.traces_url())
+        .build()?;
+    let sampler = Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+        config.sample_ratio.clamp(0.0, 1.0),
+    )));
+
+    Ok(TracerProvider::builder()
+        .with_batch_exporter(exporter, runtime::Tokio)
+        .with_sampler(sampler)
+        .with_resource(Resource::new([KeyValue::new(
+            "service.name",
+            config.service_name.clone(),
+        )]))
+        .build())
+}
+
+/// Layer turning tracing spans into OpenTelemetry spans
+pub fn layer<S>(
+    provider: &TracerProvider,
+) -> tracing_opentelemetry::OpenTelemetryLayer<S, opentelemetry_sdk::trace::Tracer>
+where
+    S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+{
+    tracing_opentelemetry::layer().with_tracer(provider.tracer("wei"))
+}
+
+/// Use W3C trace context (`traceparent` and `tracestate`) for propagation
+pub fn install_propagator() {
+    global::set_text_map_propagator(TraceContextPropagator::new());
+}
+
+/// Flushes and stops span export when dropped
+///
+/// Keep it alive for as long as the process runs.
+#[derive(Default)]
+pub struct TracingGuard {
+    provider: Option<TracerProvider>,
+}
+
+impl TracingGuard {
+    /// Guard of a provider, or of nothing when export is disabled
+    pub fn new(provider: Option<TracerProvider>) -> Self {
+        Self { provider }
+    }
+}
+
+impl Drop for TracingGuard {
+    fn drop(&mut self) {
+        if let Some(provider) = self.provider.take() {
+            if let Err(e) = provider.shutdown() {
+                warn!("Failed to flush exported spans: {}", e);
+            }
+        }
+    }
+}
+
+/// Reads trace context from request headers
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
+
+/// Continue the trace of an inbound request in `span`, if it sent one
+pub fn continue_trace(span: &Span, headers: &HeaderMap) {
+    let context =
+        global::get_text_map_propagator(|propagator| propagator.extract(&HeaderExtractor(headers)));
+    if has_remote_parent(&context) {
+        span.set_parent(context);
+    }
+}
+
+fn has_remote_parent(context: &Context) -> bool {
+    context.span().span_context().is_valid()
+}
+
+/// Trace context headers of the current span, for outbound requests
+pub fn trace_headers() -> HashMap<String, String> {
+    let context = Span::current().context();
+    let mut headers = HashMap::new();
+    global::get_text_map_propagator(|propagator| propagator.inject_context(&context, &mut headers));
+    headers
+}
@@ -0,0 +1,194 @@
+//! Request IDs and request logging
+//!
+//! [`logging_middleware`] gives every request an ID, taken from the
+//! `x-request-id` header when the client sent a usable one. The ID is
+//! returned in the response header and in JSON error bodies, and every log
+//! line of the request carries it through the `request` span. Outbound
+//! calls made while handling the request pass it on with [`propagate`],
+//! along with the W3C trace context of the current span.
+
+use std::{fmt, time::Instant};
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::Request,
+    http::{header, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use serde_json::{Map, Value};
+use tracing::{field, info, info_span, warn, Instrument, Span};
+
+use crate::otel;
+
+/// Header carrying the request ID, in requests and responses
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest request ID accepted from clients
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+/// Largest error body the request ID is added to
+const MAX_ERROR_BODY_BYTES: usize = 64 * 1024;
+
+tokio::task_local! {
+    static REQUEST_ID: RequestId;
+}
+
+/// ID of a request, stored in the request extensions
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RequestId(String);
+
+impl RequestId {
+    /// Generate a new random request ID
+    pub fn new() -> Self {
+        Self(uuid::Uuid::new_v4().to_string())
+    }
+
+    /// Use an ID sent by a client, if it is printable ASCII of reasonable length
+    pub fn from_header(value: &str) -> Option<Self> {
+        let valid = !value.is_empty()
+            && value.len() <= MAX_REQUEST_ID_LENGTH
+            && value.bytes().all(|b| b.is_ascii_graphic());
+        valid.then(|| Self(value.to_string()))
+    }
+
+    /// The ID as a string
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for RequestId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// ID of the request being handled by the current task, if any
+pub fn current_request_id() -> Option<RequestId> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+/// Record who authenticated the current request in its log lines
+///
+/// `identity` must not be a secret; use a key name or fingerprint.
+pub fn record_api_key(identity: &str) {
+    Span::current().record("api_key", identity);
+}
+
+/// Pass the current request ID and trace context on to an outbound HTTP request
+pub fn propagate(request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    let request = otel::trace_headers()
+        .into_iter()
+        .fold(request, |request, (name, value)| {
+            request.header(name, value)
+        });
+    match current_request_id() {
+        Some(id) => request.header(REQUEST_ID_HEADER, id.as_str()),
+        None => request,
+    }
+}
+
+/// Request logging middleware
+///
+/// Assigns or propagates the request ID, logs the method, path, status,
+/// latency and API key of each request, and adds the request ID to the
+/// response headers and error bodies. The `request` span continues the
+/// trace of an inbound `traceparent` header.
+pub async fn logging_middleware(mut request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(RequestId::from_header)
+        .unwrap_or_default();
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let span = info_span!(
+        "request",
+        request_id = %id,
+        method = %method,
+        path = %path,
+        api_key = field::Empty,
+        status = field::Empty,
+        otel.kind = "server",
+    );
+    otel::continue_trace(&span, request.headers());
+    request.extensions_mut().insert(id.clone());
+
+    let started = Instant::now();
+    let response = REQUEST_ID
+        .scope(id.clone(), next.run(request).instrument(span.clone()))
+        .await;
+    let response = with_request_id(response, &id).await;
+
+    let status = response.status().as_u16();
+    let latency_ms = started.elapsed().as_millis() as u64;
+    span.record("status", status);
+    span.in_scope(|| {
+        if response.status().is_server_error() {
+            warn!(status, latency_ms, "Request failed");
+        } else {
+            info!(status, latency_ms, "Request completed");
+        }
+    });
+    response
+}
+
+/// Add the request ID to the headers and, for errors, to the body
+///
+/// JSON object bodies get a `request_id` field. Empty and plain text error
+/// bodies are replaced by a JSON body with the `message`, `status` and
+/// `request_id` of the error.
+async fn with_request_id(response: Response, id: &RequestId) -> Response {
+    let (mut parts, body) = response.into_parts();
+    if let Ok(value) = HeaderValue::from_str(id.as_str()) {
+        parts.headers.insert(REQUEST_ID_HEADER, value);
+    }
+    if !(parts.status.is_client_error() || parts.status.is_server_error()) {
+        return Response::from_parts(parts, body);
+    }
+
+    let Ok(bytes) = to_bytes(body, MAX_ERROR_BODY_BYTES).await else {
+        return Response::from_parts(parts, Body::empty());
+    };
+    let body = match serde_json::from_slice::<Value>(&bytes) {
+        Ok(Value::Object(mut object)) => {
+            object
+                .entry("request_id")
+                .or_insert_with(|| id.as_str().into());
+            object
+        }
+        Ok(_) => return Response::from_parts(parts, Body::from(bytes)),
+        Err(_) => {
+            let text = String::from_utf8_lossy(&bytes).trim().to_string();
+            let message = if text.is_empty() {
+                parts
+                    .status
+                    .canonical_reason()
+                    .unwrap_or_default()
+                    .to_string()
+            } else {
+                text
+            };
+            let mut object = Map::new();
+            object.insert("message".to_string(), message.into());
+            object.insert("status".to_string(), parts.status.as_u16().into());
+            object.insert("request_id".to_string(), id.as_str().into());
+            object
+        }
+    };
+
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts.headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    Response::from_parts(parts, Body::from(Value::Object(body).to_string()))
+}
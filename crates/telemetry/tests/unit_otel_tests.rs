@@ -0,0 +1,158 @@
+//! Unit tests for span export and trace context propagation
+
+use std::time::Duration;
+
+use axum::{body::Body, http::Request, middleware, routing::get, Router};
+use opentelemetry::trace::TraceContextExt;
+use opentelemetry_sdk::trace::TracerProvider;
+use telemetry::{
+    collector::Collector,
+    logging_middleware,
+    otel::{self, OtlpConfig},
+    propagate,
+};
+use tower::ServiceExt;
+use tracing::{field, info_span, subscriber::DefaultGuard};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::layer::SubscriberExt;
+
+const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+const PARENT_SPAN_ID: &str = "00f067aa0ba902b7";
+
+/// Export spans of the current thread to a new collector
+async fn export(sample_ratio: f64) -> (Collector, TracerProvider, DefaultGuard) {
+    let collector = Collector::start().await.unwrap();
+    let provider = otel::tracer_provider(&OtlpConfig {
+        endpoint: format!("{}/", collector.endpoint()),
+        service_name: "wei-test".to_string(),
+        sample_ratio,
+    })
+    .unwrap();
+    otel::install_propagator();
+    let subscriber = tracing_subscriber::registry().with(otel::layer(&provider));
+    let guard = tracing::subscriber::set_default(subscriber);
+    (collector, provider, guard)
+}
+
+async fn request_with_parent(sampled: bool) -> u16 {
+    let app = Router::new()
+        .route("/proposals", get(|| async { "[]" }))
+        .layer(middleware::from_fn(logging_middleware));
+    let flags = if sampled { "01" } else { "00" };
+    let request = Request::get("/proposals")
+        .header(
+            "traceparent",
+            format!("00-{TRACE_ID}-{PARENT_SPAN_ID}-{flags}"),
+        )
+        .body(Body::empty())
+        .unwrap();
+    app.oneshot(request).await.unwrap().status().as_u16()
+}
+
+#[test]
+fn test_traces_url() {
+    let config = OtlpConfig {
+        endpoint: "http://collector:4318/".to_string(),
+        service_name: "wei-indexer".to_string(),
+        sample_ratio: 1.0,
+    };
+    assert_eq!(config.traces_url(), "http://collector:4318/v1/traces");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_exports_span_attributes() {
+    let (collector, provider, _guard) = export(1.0).await;
+
+    {
+        let span = info_span!(
+            "openrouter",
+            model = "gpt-4o-mini",
+            total_tokens = field::Empty
+        );
+        span.record("total_tokens", 42u32);
+        let _entered = span.enter();
+        info_span!("cache", cache_hit = true).in_scope(|| {});
+    }
+    provider.force_flush();
+
+    let span = collector
+        .wait_for("openrouter", Duration::from_secs(5))
+        .await
+        .expect("span not exported");
+    assert_eq!(span.service_name, "wei-test");
+    assert_eq!(span.attributes["model"], "gpt-4o-mini");
+    assert_eq!(span.attributes["total_tokens"], "42");
+    assert_eq!(span.parent_span_id, "");
+
+    let cache = collector
+        .wait_for("cache", Duration::from_secs(5))
+        .await
+        .unwrap();
+    assert_eq!(cache.attributes["cache_hit"], "true");
+    assert_eq!(cache.trace_id, span.trace_id);
+    assert_eq!(cache.parent_span_id, span.span_id);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_continues_inbound_trace() {
+    let (collector, provider, _guard) = export(1.0).await;
+
+    assert_eq!(request_with_parent(true).await, 200);
+    provider.force_flush();
+
+    let span = collector
+        .wait_for("request", Duration::from_secs(5))
+        .await
+        .expect("span not exported");
+    assert_eq!(span.trace_id, TRACE_ID);
+    assert_eq!(span.parent_span_id, PARENT_SPAN_ID);
+    assert_eq!(span.attributes["path"], "/proposals");
+    assert_eq!(span.attributes["status"], "200");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_propagates_trace_context() {
+    let (_collector, _provider, _guard) = export(1.0).await;
+    let client = reqwest::Client::new();
+
+    // Without a span there is no trace to continue
+    let request = propagate(client.get("http://localhost/")).build().unwrap();
+    assert!(request.headers().get("traceparent").is_none());
+
+    let span = info_span!("snapshot");
+    let trace_id = span.context().span().span_context().trace_id();
+    let request = span
+        .in_scope(|| propagate(client.get("http://localhost/")))
+        .build()
+        .unwrap();
+    let traceparent = request.headers()["traceparent"].to_str().unwrap();
+    assert!(traceparent.starts_with(&format!("00-{trace_id}-")));
+    assert!(traceparent.ends_with("-01"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_sample_ratio() {
+    let (collector, provider, _guard) = export(0.0).await;
+
+    info_span!("unsampled").in_scope(|| {});
+    // Traces sampled by the caller are still exported
+    assert_eq!(request_with_parent(true).await, 200);
+    provider.force_flush();
+
+    let span = collector
+        .wait_for("request", Duration::from_secs(5))
+        .await
+        .expect("span not exported");
+    assert_eq!(span.trace_id, TRACE_ID);
+    assert!(collector
+        .spans()
+        .iter()
+        .all(|span| span.name != "unsampled"));
+
+    // As are traces the caller did not sample
+    let before = collector.spans().len();
+    assert_eq!(request_with_parent(false).await, 200);
+    provider.force_flush();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(collector.spans().len(), before);
+}
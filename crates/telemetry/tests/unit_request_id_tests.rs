@@ -0,0 +1,171 @@
+//! Unit tests for request IDs and request logging
+
+use std::{
+    io,
+    sync::{Arc, Mutex},
+};
+
+use axum::{http::StatusCode, middleware, routing::get, Extension, Json, Router};
+use serde_json::{json, Value};
+use telemetry::{
+    current_request_id,
+    logging::{JsonFields, JsonFormat},
+    logging_middleware, record_api_key, RequestId, REQUEST_ID_HEADER,
+};
+use tracing::info;
+
+async fn serve() -> String {
+    let app = Router::new()
+        .route(
+            "/echo",
+            get(|Extension(id): Extension<RequestId>| async move {
+                assert_eq!(current_request_id(), Some(id.clone()));
+                id.to_string()
+            }),
+        )
+        .route("/empty", get(|| async { StatusCode::NOT_FOUND }))
+        .route(
+            "/text",
+            get(|| async { (StatusCode::UNAUTHORIZED, "API key is required") }),
+        )
+        .route(
+            "/json",
+            get(|| async {
+                (
+                    StatusCode::BAD_REQUEST,
+                    Json(json!({"message": "Bad input", "status": 400})),
+                )
+            }),
+        )
+        .layer(middleware::from_fn(logging_middleware));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{address}")
+}
+
+fn request_id(response: &reqwest::Response) -> String {
+    response.headers()[REQUEST_ID_HEADER]
+        .to_str()
+        .unwrap()
+        .to_string()
+}
+
+#[test]
+fn test_request_id_from_header() {
+    assert!(RequestId::from_header("req-42").is_some());
+    assert!(RequestId::from_header("").is_none());
+    assert!(RequestId::from_header("two words").is_none());
+    assert!(RequestId::from_header(&"x".repeat(129)).is_none());
+    assert_ne!(RequestId::new(), RequestId::new());
+    assert_eq!(current_request_id(), None);
+}
+
+#[tokio::test]
+async fn test_assigns_and_propagates_request_ids() {
+    let url = serve().await;
+    let client = reqwest::Client::new();
+
+    let response = client.get(format!("{url}/echo")).send().await.unwrap();
+    let generated = request_id(&response);
+    assert_eq!(generated.len(), 36);
+    assert_eq!(response.text().await.unwrap(), generated);
+
+    let response = client
+        .get(format!("{url}/echo"))
+        .header(REQUEST_ID_HEADER, "client-id-1")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(request_id(&response), "client-id-1");
+    assert_eq!(response.text().await.unwrap(), "client-id-1");
+
+    // Unusable IDs are replaced
+    let response = client
+        .get(format!("{url}/echo"))
+        .header(REQUEST_ID_HEADER, "not an id")
+        .send()
+        .await
+        .unwrap();
+    assert_ne!(request_id(&response), "not an id");
+}
+
+#[tokio::test]
+async fn test_error_bodies_carry_request_id() {
+    let url = serve().await;
+    let client = reqwest::Client::new();
+    let get = |path: &str| {
+        client
+            .get(format!("{url}{path}"))
+            .header(REQUEST_ID_HEADER, "req-7")
+            .send()
+    };
+
+    let body: Value = get("/empty").await.unwrap().json().await.unwrap();
+    assert_eq!(
+        body,
+        json!({"message": "Not Found", "status": 404, "request_id": "req-7"})
+    );
+
+    let response = get("/text").await.unwrap();
+    assert_eq!(response.status().as_u16(), 401);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(
+        body,
+        json!({"message": "API key is required", "status": 401, "request_id": "req-7"})
+    );
+
+    let body: Value = get("/json").await.unwrap().json().await.unwrap();
+    assert_eq!(
+        body,
+        json!({"message": "Bad input", "status": 400, "request_id": "req-7"})
+    );
+}
+
+/// Log output captured in memory
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_json_lines_carry_span_fields() {
+    let captured = Captured::default();
+    let writer = captured.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .fmt_fields(JsonFields)
+        .event_format(JsonFormat)
+        .with_writer(move || writer.clone())
+        .finish();
+
+    tracing::subscriber::with_default(subscriber, || {
+        let span = tracing::info_span!(
+            "request",
+            request_id = "req-9",
+            api_key = tracing::field::Empty
+        );
+        let _entered = span.enter();
+        record_api_key("0123abcd");
+        info!(status = 200u16, latency_ms = 12u64, "Request completed");
+    });
+
+    let output = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+    let line: Value = serde_json::from_str(output.trim()).unwrap();
+    assert_eq!(line["level"], "INFO");
+    assert_eq!(line["span"], "request");
+    assert_eq!(line["request_id"], "req-9");
+    assert_eq!(line["api_key"], "0123abcd");
+    assert_eq!(line["status"], 200);
+    assert_eq!(line["latency_ms"], 12);
+    assert_eq!(line["message"], "Request completed");
+}